@@ -48,6 +48,27 @@
 //! let event = receiver.recv().expect("Error: failed to receive event");
 //! println!("Received a debugger event: {:?}", event);
 //! ```
+//!
+//! A parse can also be recorded, and its transitions then replayed forwards and backwards, e.g.
+//! to step back to where a branch was abandoned:
+//!
+//! ```
+//! use pest_debugger::{DebuggerContext, TransitionKind};
+//! let mut context = DebuggerContext::default();
+//!
+//! context
+//! .load_grammar_direct("testgrammar", r#"pair = { "(" ~ ("a" | "b") ~ ")" }"#)
+//! .expect("Error: failed to load grammar");
+//! context.load_input_direct("(c)".to_owned());
+//!
+//! context.record("pair").expect("Error: failed to record rule");
+//!
+//! let transition = context.step_forward().expect("Error: failed to step forward");
+//! assert_eq!(transition.kind, TransitionKind::Fail);
+//!
+//! let transition = context.step_back().expect("Error: failed to step back");
+//! assert_eq!(transition.kind, TransitionKind::Enter);
+//! ```
 //! ## Current Limitations
 //! - relies on OS threads instead of stack-full generators
 //! - only shows position from the `ParserState` when it reaches a breakpoint
@@ -78,6 +99,10 @@ use pest_meta::{
 };
 use pest_vm::Vm;
 
+mod recording;
+
+pub use crate::recording::{Recording, Transition, TransitionKind};
+
 /// Possible errors that can occur in the debugger context.
 #[derive(Debug, thiserror::Error)]
 pub enum DebuggerError {
@@ -99,6 +124,12 @@ pub enum DebuggerError {
     /// Parsing finished (i.e. cannot continue the session).
     #[error("End-of-input reached")]
     EofReached,
+    /// Replaying a parse requires recording it first.
+    #[error("Record rule first")]
+    RecordFirst,
+    /// Replaying backwards reached the start of the recorded parse.
+    #[error("Start of recording reached")]
+    StartReached,
     /// Can't create a `Position` in a given input.
     #[error("Invalid position: {0}")]
     InvalidPosition(usize),
@@ -111,6 +142,9 @@ pub enum DebuggerError {
     /// seem to have panicked.
     #[error("Previous parsing execution panic: {0}")]
     PreviousRunPanic(String),
+    /// When recording a parse, the parse panicked.
+    #[error("Recorded parsing execution panic: {0}")]
+    RecordingPanic(String),
 }
 
 /// Events that are sent from the debugger.
@@ -133,6 +167,7 @@ pub struct DebuggerContext {
     grammar: Option<Vec<OptimizedRule>>,
     input: Option<String>,
     breakpoints: Arc<Mutex<HashSet<String>>>,
+    recording: Option<Recording>,
 }
 
 const POISONED_LOCK_PANIC: &str = "poisoned lock";
@@ -342,6 +377,58 @@ impl DebuggerContext {
         }
     }
 
+    /// Records all the transitions of a parse of the input from a rule, so that they can be
+    /// replayed with `step_forward`, `step_back`, `replay_forward` and `replay_back`, starting
+    /// from the first one.
+    /// Unlike `run`, this parses the whole input at once, without stopping at breakpoints.
+    pub fn record(&mut self, rule: &str) -> Result<(), DebuggerError> {
+        let ast = self
+            .grammar
+            .as_ref()
+            .ok_or(DebuggerError::GrammarNotOpened)?;
+        let input = self.input.as_ref().ok_or(DebuggerError::InputNotOpened)?;
+
+        self.recording = Some(Recording::record(ast.clone(), rule, input)?);
+        Ok(())
+    }
+
+    /// Returns the recorded parse, if there is any.
+    pub fn recording(&self) -> Option<&Recording> {
+        self.recording.as_ref()
+    }
+
+    fn recording_mut(&mut self) -> Result<&mut Recording, DebuggerError> {
+        self.recording.as_mut().ok_or(DebuggerError::RecordFirst)
+    }
+
+    /// Replays the next transition of the recorded parse.
+    /// It returns an error if the last transition was reached or if nothing was recorded.
+    pub fn step_forward(&mut self) -> Result<Transition, DebuggerError> {
+        self.recording_mut()?.step_forward()
+    }
+
+    /// Replays the recorded parse back to the previous transition.
+    /// It returns an error if the first transition was reached or if nothing was recorded.
+    pub fn step_back(&mut self) -> Result<Transition, DebuggerError> {
+        self.recording_mut()?.step_back()
+    }
+
+    /// Replays the recorded parse forwards to the next breakpoint.
+    /// It returns an error if there is none or if nothing was recorded.
+    pub fn replay_forward(&mut self) -> Result<Transition, DebuggerError> {
+        let breakpoints = self.breakpoints.lock().expect(POISONED_LOCK_PANIC).clone();
+
+        self.recording_mut()?.replay_forward(&breakpoints)
+    }
+
+    /// Replays the recorded parse backwards to the previous breakpoint.
+    /// It returns an error if there is none or if nothing was recorded.
+    pub fn replay_back(&mut self) -> Result<Transition, DebuggerError> {
+        let breakpoints = self.breakpoints.lock().expect(POISONED_LOCK_PANIC).clone();
+
+        self.recording_mut()?.replay_back(&breakpoints)
+    }
+
     /// Returns a `Position` from the loaded input.
     pub fn get_position(&self, pos: usize) -> Result<Position<'_>, DebuggerError> {
         match self.input {
@@ -359,6 +446,7 @@ impl Default for DebuggerContext {
             grammar: None,
             input: None,
             breakpoints: Arc::new(Mutex::new(HashSet::new())),
+            recording: None,
         }
     }
 }
@@ -470,5 +558,87 @@ mod test {
         assert!(context.run("ANY", sender).is_ok());
         while context.cont().is_ok() {}
         assert!(context.cont().is_err());
+        assert!(matches!(
+            context.step_forward(),
+            Err(DebuggerError::RecordFirst)
+        ));
+    }
+
+    #[test]
+    fn test_record() {
+        let mut context = get_test_context();
+
+        context
+            .record("ident_list")
+            .expect("Error: failed to record rule");
+        let recording = context.recording().expect("parse is recorded");
+        assert_eq!(*recording.outcome(), DebuggerEvent::Eof);
+
+        let transitions: Vec<_> = recording
+            .transitions()
+            .iter()
+            .take(6)
+            .map(|transition| {
+                (
+                    recording.rule_name(transition),
+                    transition.kind,
+                    transition.pos,
+                )
+            })
+            .collect();
+        assert_eq!(
+            transitions,
+            [
+                ("ident_list", TransitionKind::Enter, 0),
+                ("ident", TransitionKind::Enter, 0),
+                ("digit", TransitionKind::Enter, 0),
+                ("digit", TransitionKind::Fail, 0),
+                ("alpha", TransitionKind::Enter, 0),
+                ("alpha", TransitionKind::Match, 1),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_replay() {
+        let mut context = get_test_context();
+
+        assert!(matches!(
+            context.step_back(),
+            Err(DebuggerError::RecordFirst)
+        ));
+        context
+            .record("ident_list")
+            .expect("Error: failed to record rule");
+        assert!(matches!(
+            context.step_back(),
+            Err(DebuggerError::StartReached)
+        ));
+
+        context.add_breakpoint("ident".to_owned());
+        let first = context.replay_forward().expect("Error: failed to replay");
+        assert_eq!(first.pos, 0);
+        let second = context.replay_forward().expect("Error: failed to replay");
+        assert_eq!(second.pos, 5);
+        assert!(matches!(
+            context.replay_forward(),
+            Err(DebuggerError::EofReached)
+        ));
+
+        let transition = context.step_back().expect("Error: failed to step back");
+        let recording = context.recording().expect("parse is recorded");
+        assert_eq!(recording.rule_name(&transition), "ident");
+        assert_eq!(
+            (transition.kind, transition.pos),
+            (TransitionKind::Match, 4)
+        );
+        assert_eq!(recording.stack(), ["ident_list"]);
+
+        assert_eq!(
+            context.replay_back().expect("Error: failed to replay"),
+            first
+        );
+        let recording = context.recording().expect("parse is recorded");
+        assert_eq!(recording.stack(), ["ident_list", "ident"]);
     }
 }
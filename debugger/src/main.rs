@@ -21,7 +21,7 @@ use std::time::Duration;
 
 use pest::error::{Error, ErrorVariant};
 
-use pest_debugger::{DebuggerContext, DebuggerError, DebuggerEvent};
+use pest_debugger::{DebuggerContext, DebuggerError, DebuggerEvent, Transition, TransitionKind};
 use rustyline::completion::{Completer, FilenameCompleter, Pair};
 use rustyline::error::ReadlineError;
 use rustyline::highlight::Highlighter;
@@ -95,6 +95,40 @@ impl Cli {
         Ok(())
     }
 
+    fn record(&mut self, rule: &str) -> Result<(), DebuggerError> {
+        self.context.record(rule)?;
+
+        let recording = self.context.recording().expect("parse is recorded");
+        println!("recorded {} transitions", recording.transitions().len());
+        match recording.outcome() {
+            DebuggerEvent::Error(error) => println!("{}", error),
+            _ => println!("end-of-input reached"),
+        }
+        self.transition(recording.current())
+    }
+
+    fn transition(&self, transition: Transition) -> Result<(), DebuggerError> {
+        let recording = self.context.recording().expect("parse is recorded");
+        let rule = recording.rule_name(&transition);
+        let message = match transition.kind {
+            TransitionKind::Enter => format!("parsing {}", rule),
+            TransitionKind::Match => format!("{} matched", rule),
+            TransitionKind::Fail => format!("{} failed", rule),
+        };
+
+        let error: Error<()> = Error::new_from_pos(
+            ErrorVariant::CustomError { message },
+            self.context.get_position(transition.pos)?,
+        );
+        println!("{}", error);
+        println!(
+            "transition {} in {}",
+            recording.cursor() + 1,
+            recording.stack().join(" > ")
+        );
+        Ok(())
+    }
+
     fn list(&mut self) {
         let breakpoints = self.context.list_breakpoints();
 
@@ -114,6 +148,11 @@ impl Cli {
              da                            - delete all breakpoints\n\
              r <rule>                      - run a rule\n\
              c                             - continue\n\
+             rec <rule>                    - record a whole parse of a rule\n\
+             sf                            - step forward in the recorded parse\n\
+             sb                            - step back in the recorded parse\n\
+             rf                            - replay the recorded parse forwards to a breakpoint\n\
+             rb                            - replay the recorded parse backwards to a breakpoint\n\
              l                             - list breakpoints\n\
              h                             - help\n\
          "
@@ -130,6 +169,22 @@ impl Cli {
             "h" => Cli::help(),
             "l" => self.list(),
             "c" => self.cont()?,
+            "sf" => {
+                let transition = self.context.step_forward()?;
+                self.transition(transition)?;
+            }
+            "sb" => {
+                let transition = self.context.step_back()?;
+                self.transition(transition)?;
+            }
+            "rf" => {
+                let transition = self.context.replay_forward()?;
+                self.transition(transition)?;
+            }
+            "rb" => {
+                let transition = self.context.replay_back()?;
+                self.transition(transition)?;
+            }
             "da" => self.context.add_all_rules_breakpoints()?,
             "ba" => self.context.delete_all_breakpoints(),
             x if x.starts_with("g ") => self.grammar(PathBuf::from(&x[2..]))?,
@@ -143,6 +198,7 @@ impl Cli {
                 self.context.delete_breakpoint(&x[2..]);
             }
             x if x.starts_with("r ") => self.run(&x[2..])?,
+            x if x.starts_with("rec ") => self.record(&x[4..])?,
             x => Cli::unrecognized(x),
         };
         Ok(())
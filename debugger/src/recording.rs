@@ -0,0 +1,228 @@
+// pest. The Elegant Parser
+// Copyright (c) 2018-2022 Dragoș Tiselice, Tomas Tauber
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+use std::collections::{HashMap, HashSet};
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::{Arc, Mutex};
+
+use pest_meta::optimizer::OptimizedRule;
+use pest_vm::Vm;
+
+use crate::{DebuggerError, DebuggerEvent, POISONED_LOCK_PANIC};
+
+/// The kind of a [`Transition`] of a recorded parse.
+///
+/// [`Transition`]: struct.Transition.html
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TransitionKind {
+    /// A rule is entered.
+    Enter,
+    /// A rule matched.
+    Match,
+    /// A rule failed, e.g. because a branch of a choice was abandoned.
+    Fail,
+}
+
+/// A transition of a recorded parse.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Transition {
+    /// The kind of the transition.
+    pub kind: TransitionKind,
+    /// The index of the rule in the rule names of the [`Recording`], see [`Recording::rule_name`].
+    ///
+    /// [`Recording`]: struct.Recording.html
+    /// [`Recording::rule_name`]: struct.Recording.html#method.rule_name
+    pub rule: usize,
+    /// The position of the parser after the transition, which is where the rule started if it
+    /// is entered or failed, and where it ended if it matched.
+    pub pos: usize,
+}
+
+/// A log of all the transitions of a parse, along with a cursor which replays them forwards and
+/// backwards.
+#[derive(Debug)]
+pub struct Recording {
+    rules: Vec<String>,
+    transitions: Vec<Transition>,
+    outcome: DebuggerEvent,
+    cursor: usize,
+}
+
+#[derive(Default)]
+struct Recorder {
+    indices: HashMap<String, usize>,
+    rules: Vec<String>,
+    transitions: Vec<Transition>,
+}
+
+impl Recorder {
+    fn push(&mut self, kind: TransitionKind, rule: String, pos: usize) {
+        let rule = match self.indices.get(&rule) {
+            Some(&index) => index,
+            None => {
+                let index = self.rules.len();
+                self.indices.insert(rule.clone(), index);
+                self.rules.push(rule);
+                index
+            }
+        };
+
+        self.transitions.push(Transition { kind, rule, pos });
+    }
+}
+
+impl Recording {
+    /// Records a parse of `input` from `rule`, with the cursor on its first transition.
+    pub(crate) fn record(
+        ast: Vec<OptimizedRule>,
+        rule: &str,
+        input: &str,
+    ) -> Result<Recording, DebuggerError> {
+        let recorder = Arc::new(Mutex::new(Recorder::default()));
+        let enter = Arc::clone(&recorder);
+        let exit = Arc::clone(&recorder);
+
+        let mut vm = Vm::new_with_listener(
+            ast,
+            Box::new(move |rule, pos| {
+                let mut recorder = enter.lock().expect(POISONED_LOCK_PANIC);
+                recorder.push(TransitionKind::Enter, rule, pos.pos());
+                false
+            }),
+        );
+        vm.set_exit_listener(move |rule, pos, matched| {
+            let kind = if matched {
+                TransitionKind::Match
+            } else {
+                TransitionKind::Fail
+            };
+            let mut recorder = exit.lock().expect(POISONED_LOCK_PANIC);
+            recorder.push(kind, rule, pos.pos());
+        });
+
+        let outcome = panic::catch_unwind(AssertUnwindSafe(|| match vm.parse(rule, input) {
+            Ok(_) => DebuggerEvent::Eof,
+            Err(error) => DebuggerEvent::Error(error.to_string()),
+        }))
+        .map_err(|e| DebuggerError::RecordingPanic(format!("{:?}", e)))?;
+        drop(vm);
+
+        let recorder = Arc::try_unwrap(recorder)
+            .ok()
+            .expect("listeners are dropped with the vm")
+            .into_inner()
+            .expect(POISONED_LOCK_PANIC);
+
+        Ok(Recording {
+            rules: recorder.rules,
+            transitions: recorder.transitions,
+            outcome,
+            cursor: 0,
+        })
+    }
+
+    /// Returns all the transitions of the parse.
+    pub fn transitions(&self) -> &[Transition] {
+        &self.transitions
+    }
+
+    /// Returns the name of the rule of `transition`.
+    pub fn rule_name(&self, transition: &Transition) -> &str {
+        &self.rules[transition.rule]
+    }
+
+    /// Returns how the parse ended, i.e. `DebuggerEvent::Eof` or `DebuggerEvent::Error`.
+    pub fn outcome(&self) -> &DebuggerEvent {
+        &self.outcome
+    }
+
+    /// Returns the index of the current transition.
+    pub fn cursor(&self) -> usize {
+        self.cursor
+    }
+
+    /// Returns the current transition.
+    pub fn current(&self) -> Transition {
+        self.transitions[self.cursor]
+    }
+
+    /// Returns the names of the rules that are being parsed at the current transition, from the
+    /// outermost to the innermost one.
+    pub fn stack(&self) -> Vec<&str> {
+        let mut stack = vec![];
+
+        for transition in &self.transitions[..=self.cursor] {
+            match transition.kind {
+                TransitionKind::Enter => stack.push(self.rule_name(transition)),
+                TransitionKind::Match | TransitionKind::Fail => {
+                    stack.pop();
+                }
+            }
+        }
+
+        stack
+    }
+
+    /// Moves the cursor to the next transition.
+    /// It returns an error if the cursor is on the last one.
+    pub fn step_forward(&mut self) -> Result<Transition, DebuggerError> {
+        if self.cursor + 1 >= self.transitions.len() {
+            return Err(DebuggerError::EofReached);
+        }
+
+        self.cursor += 1;
+        Ok(self.current())
+    }
+
+    /// Moves the cursor to the previous transition.
+    /// It returns an error if the cursor is on the first one.
+    pub fn step_back(&mut self) -> Result<Transition, DebuggerError> {
+        if self.cursor == 0 {
+            return Err(DebuggerError::StartReached);
+        }
+
+        self.cursor -= 1;
+        Ok(self.current())
+    }
+
+    /// Moves the cursor forwards to the next transition entering a rule of `breakpoints`.
+    /// It returns an error, leaving the cursor as it was, if there is none.
+    pub fn replay_forward(
+        &mut self,
+        breakpoints: &HashSet<String>,
+    ) -> Result<Transition, DebuggerError> {
+        let next = (self.cursor + 1..self.transitions.len())
+            .find(|&i| self.is_breakpoint(i, breakpoints))
+            .ok_or(DebuggerError::EofReached)?;
+
+        self.cursor = next;
+        Ok(self.current())
+    }
+
+    /// Moves the cursor backwards to the previous transition entering a rule of `breakpoints`.
+    /// It returns an error, leaving the cursor as it was, if there is none.
+    pub fn replay_back(
+        &mut self,
+        breakpoints: &HashSet<String>,
+    ) -> Result<Transition, DebuggerError> {
+        let previous = (0..self.cursor)
+            .rev()
+            .find(|&i| self.is_breakpoint(i, breakpoints))
+            .ok_or(DebuggerError::StartReached)?;
+
+        self.cursor = previous;
+        Ok(self.current())
+    }
+
+    fn is_breakpoint(&self, index: usize, breakpoints: &HashSet<String>) -> bool {
+        let transition = &self.transitions[index];
+
+        transition.kind == TransitionKind::Enter && breakpoints.contains(self.rule_name(transition))
+    }
+}
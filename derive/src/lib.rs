@@ -56,16 +56,110 @@
 //!
 //! Grammars can also be inlined by using the `#[grammar_inline = "..."]` attribute.
 //!
+//! ## Grammar provenance
+//!
+//! Derived parsers identify the grammar they were generated from with two constants:
+//! `GRAMMAR_HASH`, the hash of its content computed by `pest::grammar_hash`, and `GRAMMAR_SOURCE`,
+//! the path given to `#[grammar = "..."]`, or `None` for an inline grammar. Caches of parsed trees
+//! can be keyed by the hash, and bug reports can name the exact grammar:
+//!
+//! ```ignore
+//! let key = (MyParser::GRAMMAR_HASH, input_hash);
+//! ```
+//!
+//! ## Grammar documentation
+//!
+//! The `#[doc_module = "..."]` attribute generates an empty module of the given name whose
+//! documentation lists every rule of the grammar, with its doc comments (`/// ...`), its
+//! definition and links to the rules referencing it, so that `cargo doc` shows the grammar next
+//! to the parser:
+//!
+//! ```ignore
+//! #[derive(Parser)]
+//! #[grammar = "path/to/my_grammar.pest"]
+//! #[doc_module = "grammar"]
+//! pub struct MyParser;
+//! ```
+//!
+//! ## Entry rules
+//!
+//! When several parsers share one large grammar while parsing from different rules, the
+//! `#[entry_rules = "..."]` attribute lists the rules a parser starts from, separated by commas.
+//! The code of the rules which none of them can reach is left out, while `Rule` keeps their
+//! variants, and parsing from one of them fails right away. With `#[prune = "enum"]`, their
+//! variants are left out as well, which changes the numeric codes of the variants following them:
+//!
+//! ```ignore
+//! #[derive(Parser)]
+//! #[grammar = "path/to/shared.pest"]
+//! #[entry_rules = "expression, statement"]
+//! #[prune = "enum"]
+//! pub struct ExpressionParser;
+//! ```
+//!
+//! ## Public `Rule` enums
+//!
+//! Libraries exposing the generated `Rule` in their API can control how it evolves:
+//! `#[non_exhaustive_rules = true]` makes it `#[non_exhaustive]`, so that adding rules is not a
+//! breaking change, `#[hide_silent_rules = true]` hides the variants of silent rules with
+//! `#[doc(hidden)]`, and `#[rule_attributes = "..."]` gives it additional attributes:
+//!
+//! ```ignore
+//! #[derive(Parser)]
+//! #[grammar = "path/to/my_grammar.pest"]
+//! #[non_exhaustive_rules = true]
+//! #[hide_silent_rules = true]
+//! #[rule_attributes = "#[cfg_attr(feature = \"serde\", derive(serde::Serialize))]"]
+//! pub struct MyParser;
+//! ```
+//!
+//! ## Grammar families
+//!
+//! Related grammars, e.g. a strict and a lenient variant of the same language, can share one
+//! `Rule` enum, so that the code handling their pairs is written once. One parser lists the other
+//! grammars of the family with `#[grammar_family = "..."]`, which can be repeated: its `Rule`
+//! gets the variants of their rules after its own, while parsing from them fails right away. The
+//! other parsers use its `Rule` with `#[rule_enum = "..."]`, the path of the enum, which they
+//! bring into scope instead of generating one:
+//!
+//! ```ignore
+//! mod strict {
+//!     #[derive(Parser)]
+//!     #[grammar = "strict.pest"]
+//!     #[grammar_family = "lenient.pest"]
+//!     pub struct StrictParser;
+//! }
+//!
+//! mod lenient {
+//!     #[derive(Parser)]
+//!     #[grammar = "lenient.pest"]
+//!     #[rule_enum = "super::strict::Rule"]
+//!     pub struct LenientParser;
+//! }
+//! ```
+//!
 //! ## Grammar
 //!
 //! A grammar is a series of rules separated by whitespace, possibly containing comments.
 //!
 //! ### Comments
 //!
-//! Comments start with `//` and end at the end of the line.
+//! Line comments start with `//` and end at the end of the line, while block comments start with
+//! `/*`, end with `*/`, and can be nested.
 //!
 //! ```text
 //! // a comment
+//! /* a /* nested */ block comment */
+//! ```
+//!
+//! Doc comments are kept by `pest_meta` for tooling: `//!` comments at the top of the grammar
+//! document the grammar, while `///` comments document the rule that follows them.
+//!
+//! ```text
+//! //! A grammar for greetings.
+//!
+//! /// Matches a greeting.
+//! greeting = { "hello" }
 //! ```
 //!
 //! ### Rules
@@ -152,17 +246,19 @@
 //!
 //! 1. Terminals
 //!
-//! | Terminal   | Usage                                                          |
-//! |------------|----------------------------------------------------------------|
-//! | `"a"`      | matches the exact string `"a"`                                 |
-//! | `^"a"`     | matches the exact string `"a"` case insensitively (ASCII only) |
-//! | `'a'..'z'` | matches one character between `'a'` and `'z'`                  |
-//! | `a`        | matches rule `a`                                               |
+//! | Terminal     | Usage                                                          |
+//! |--------------|----------------------------------------------------------------|
+//! | `"a"`        | matches the exact string `"a"`                                 |
+//! | `^"a"`       | matches the exact string `"a"` case insensitively (ASCII only) |
+//! | `r#"a"b"#`   | matches the exact string `a"b`, without escapes                |
+//! | `'a'..'z'`   | matches one character between `'a'` and `'z'`                  |
+//! | `a`          | matches rule `a`                                               |
 //!
 //! Strings and characters follow
-//! [Rust's escape mechanisms](https://doc.rust-lang.org/reference/tokens.html#byte-escapes), while
-//! identifiers can contain alphanumeric characters and underscores (`_`), as long as they do not
-//! start with a digit.
+//! [Rust's escape mechanisms](https://doc.rust-lang.org/reference/tokens.html#byte-escapes)
+//! (including `\x7F` and `\u{XXXX}`) and strings can also be written as raw strings (`r"..."`,
+//! `r#"..."#`, etc.), while identifiers can contain alphanumeric characters and underscores
+//! (`_`), as long as they do not start with a digit.
 //!
 //! 2. Non-terminals
 //!
@@ -180,7 +276,13 @@
 //! | `e?`                  | optionally matches `e`                                     |
 //! | `&e`                  | matches `e` without making progress                        |
 //! | `!e`                  | matches if `e` doesn't match without making progress       |
+//! | `<&e`                 | matches if the input right before matches `e`, without     |
+//! |                       | making progress; `e` must have a fixed width (see below)   |
 //! | `PUSH(e)`             | matches `e` and pushes it's captured string down the stack |
+//! | `LIST(e1, e2)`        | matches zero or more `e1` separated by `e2`, with an       |
+//! |                       | optional trailing `e2`, i.e. `(e1 ~ (e2 ~ e1)* ~ e2?)?`    |
+//! | `#"label" e`          | matches `e`, labeling errors within it (see below)         |
+//! | `#{name} e`           | matches `e` if the function `name` accepts it (see below)  |
 //!
 //! where `e`, `e1`, and `e2` are expressions.
 //!
@@ -201,13 +303,189 @@
 //! if `e1` in the compound expression `e1 | e2` does not match the input, then
 //! it does not modify the stack, so `e2` sees the stack in the same state as
 //! `e1` did. Repetitions and optionals (`e*`, `e+`, `e{, n}`, `e{n,}`,
-//! `e{m,n}`, `e?`) can modify the stack each time `e` matches. The `!e` and `&e`
-//! expressions are a special case; they never modify the stack.
+//! `e{m,n}`, `e?`) can modify the stack each time `e` matches. The `!e`, `&e`, and
+//! `<&e` expressions are a special case; they never modify the stack.
+//!
+//! The lookbehind `<&e` matches `e` atomically, right before the current position, e.g.
+//! `<&"/"` only matches right after a `/`. It is bounded: `e` must always match the same
+//! number of bytes, so it can only contain strings, ranges of characters of the same UTF-8
+//! width, and sequences, same-width choices, and exact repetitions (`e{n}`) of those.
+//!
+//! A labeled group `#"label" e` produces no pair of its own, but gives context to error messages:
+//! when a parse fails furthest within `e`, the error points back at where the innermost such group
+//! started with `while parsing label`:
+//!
+//! ```ignore
+//! call = { identifier ~ "(" ~ #"argument list" (expr ~ ("," ~ expr)*)? ~ ")" }
+//! ```
+//!
+//! A semantic predicate `#{name} e` matches `e`, then calls the function `name` of the module of
+//! the parser with the input it matched and the context given to `Parser::parse_with_context`,
+//! failing unless it returns `true`. It lets context-sensitive parts of a language be parsed, e.g.
+//! telling C declarations from multiplications by the type names declared so far:
+//!
+//! ```ignore
+//! declaration = { #{is_type_name} identifier ~ "*" ~ identifier }
+//! // fn is_type_name(text: &str, type_names: &TypeNames) -> bool
+//! ```
+//!
+//! The context is downcast to the type the function takes, panicking if it is of another type.
+//! `Parser::parse` gives `&()` as the context.
+//!
 //! Many languages have "keyword" tokens (e.g. if, for, while) as well as general
 //! tokens (e.g. identifier) that matches any word. In order to match a keyword,
 //! generally, you may need to restrict that is not immediately followed by another
 //! letter or digit (otherwise it would be matched as an identifier).
 //!
+//! A `@keywords` declaration takes care of this. It generates an atomic `kw_<keyword>` rule for
+//! every keyword, which is not followed by `ASCII_ALPHANUMERIC | "_"`, and a silent `keyword`
+//! rule matching any of them. When given a rule, e.g. an atomic `identifier` rule, keywords are
+//! also excluded from it, as if its expression was prefixed with `!keyword ~`:
+//!
+//! ```ignore
+//! @keywords(identifier) { "if" "for" "while" }
+//! identifier = @{ ASCII_ALPHA ~ ASCII_ALPHANUMERIC* }
+//! statement = { kw_if ~ identifier }
+//! ```
+//!
+//! ## Aliases
+//!
+//! An `@alias` declaration makes several rules produce pairs of the same kind. The aliased rules
+//! don't get `Rule` variants of their own; their pairs carry the alias' variant instead, so they
+//! can be matched in a single arm. The alias can also be called like a silent rule matching any of
+//! the aliased rules in order:
+//!
+//! ```ignore
+//! @alias(string) { single_quoted double_quoted }
+//! single_quoted = @{ "'" ~ (!"'" ~ ANY)* ~ "'" }
+//! double_quoted = @{ "\"" ~ (!"\"" ~ ANY)* ~ "\"" }
+//! value = { string | number }
+//! ```
+//!
+//! ## Islands
+//!
+//! An `@island` declaration matches documents of opaque text with embedded code regions, like
+//! templates. It lists the code regions as their opening markers, their rules and their closing
+//! markers:
+//!
+//! ```ignore
+//! @island(template) { "{{" expr "}}" "<%" stmt "%>" }
+//! document = { SOI ~ template ~ EOI }
+//! ```
+//!
+//! `template` is a compound-atomic rule producing alternating `template_text` pairs and the pairs
+//! of the code regions' rules. `template_text` skips straight to the next opening marker, which is
+//! much faster than `(!("{{" | "<%") ~ ANY)*` in a non-atomic rule. Since `template` is
+//! compound-atomic, no whitespace is skipped implicitly around the regions' rules.
+//!
+//! ## Extern rules
+//!
+//! An `@extern` declaration defines rules which are matched by other parsers, e.g. for a language
+//! embedded in another one. Every extern rule gets a `Rule` variant and produces a pair like a
+//! normal rule, but it has to be bound to a parser with `Parser::bind` on the current thread
+//! before it is parsed:
+//!
+//! ```ignore
+//! @extern { expr }
+//! template = { "{{" ~ expr ~ "}}" }
+//! number = { ASCII_DIGIT+ }
+//! ```
+//!
+//! ```ignore
+//! TemplateParser::bind(Rule::expr, ExprParser, ExprRule::main, |rule| match rule {
+//!     ExprRule::number => Some(Rule::number),
+//!     _ => None,
+//! });
+//! ```
+//!
+//! The pairs of the other parser are grafted into the pair of the extern rule, with rules converted
+//! by the closure. Their spans point into the outer input.
+//!
+//! ## Dialects
+//!
+//! A `@dialect` annotation defines a rule again for some dialects of the grammar, so that one
+//! parser can parse several variants of a language, picked at runtime. Without a dialect, the
+//! unannotated definition of a rule is used; a rule without one never matches outside of its
+//! dialects. All the definitions of a rule must have the same type:
+//!
+//! ```ignore
+//! number = @{ ASCII_DIGIT+ }
+//! @dialect(lenient, legacy)
+//! number = @{ ASCII_DIGIT ~ ("_" | ASCII_DIGIT)* }
+//! @dialect(legacy)
+//! octal = @{ "0" ~ ASCII_OCT_DIGIT+ }
+//! ```
+//!
+//! The dialects get a `Dialect` enum, which is passed to the parser's `parse_dialect` function:
+//!
+//! ```ignore
+//! let pairs = MyParser::parse_dialect(Dialect::lenient, Rule::number, "1_000")?;
+//! ```
+//!
+//! ## Left recursion
+//!
+//! Rules calling themselves before matching any input, directly or through other rules, are
+//! reported as errors, since they would never stop. With `#[left_recursion = true]`, they are
+//! allowed instead, and are matched by growing a seed: the rule is matched with its recursive
+//! calls failing, then again with them matching its previous match, until it matches no further.
+//! This makes them associate to the left:
+//!
+//! ```ignore
+//! #[derive(Parser)]
+//! #[grammar_inline = r#"
+//! expr = { expr ~ "-" ~ term | term }
+//! term = { ASCII_DIGIT+ }
+//! "#]
+//! #[left_recursion = true]
+//! pub struct ExprParser;
+//! ```
+//!
+//! `1 - 2 - 3` is matched as `(1 - 2) - 3`. The stack changes of left-recursive rules are not
+//! replayed by their recursive calls, so they should not use `PUSH` or `POP`.
+//!
+//! ## Byte input
+//!
+//! With `#[bytes = true]`, the parser also implements `pest::ByteParser`, whose `parse_bytes`
+//! parses raw `&[u8]` input without validating it as UTF-8, every byte being parsed as the `char`
+//! of the same value. The grammar is then validated to only match bytes: its strings can only
+//! contain chars up to `'\u{ff}'`, its ranges must be within `'\u{00}'..'\u{ff}'` and it cannot
+//! match Unicode properties like `XID_START`.
+//!
+//! ```ignore
+//! #[derive(Parser)]
+//! #[grammar_inline = r#"
+//! frame = { "\x01" ~ payload }
+//! payload = { ('\u{80}'..'\u{ff}')* }
+//! "#]
+//! #[bytes = true]
+//! pub struct FrameParser;
+//! ```
+//!
+//! ```ignore
+//! let input = ByteInput::new(b"\x01\xca\xfe");
+//! let pairs = FrameParser::parse_bytes(Rule::frame, &input)?;
+//! ```
+//!
+//! ## Memoization
+//!
+//! With `#[grammar_memoize]`, the parses store the match of every rule at every position where
+//! it is matched, so that backtracking to match it there again replays it. This makes grammars
+//! which backtrack heavily parse in linear time, at the cost of the memory of the stored matches.
+//! `#[grammar_memoize = "..."]` only memoizes the rules it lists, separated by commas, and
+//! `#[memo_limit = ...]` caps the memory used, in bytes:
+//!
+//! ```ignore
+//! #[derive(Parser)]
+//! #[grammar = "json.pest"]
+//! #[grammar_memoize = "value, object"]
+//! #[memo_limit = 1048576]
+//! pub struct JsonParser;
+//! ```
+//!
+//! Other parsers can memoize a single parse with `Parser::parse_with`. The matches of the
+//! memoized rules should only depend on their position, so rules using the stack, e.g. with
+//! `PEEK`, should be left out.
+//!
 //! ## Special rules
 //!
 //! Special rules can be called within the grammar. They are:
@@ -223,6 +501,9 @@
 //! * `PEEK[a..b]` - peeks part of the stack and matches it
 //! * `PEEK_ALL` - peeks the entire state of the stack and matches it
 //! * `DROP` - drops the top of the stack (fails to match if the stack is empty)
+//! * `PUSH_INDENT` - matches and pushes an indentation deeper than the top of the stack
+//! * `MATCH_INDENT` - matches the indentation on top of the stack exactly
+//! * `DEDENT` - drops the top of the stack when the indentation is shallower, matching nothing
 //!
 //! `WHITESPACE` and `COMMENT` should be defined manually if needed. All other rules cannot be
 //! overridden.
@@ -294,11 +575,114 @@
 //! For historical reasons, `PEEK_ALL` matches from top to bottom, while `PEEK[start..end]` matches
 //! from bottom to top. There is currently no syntax to match a slice of the stack top to bottom.
 //!
+//! ### Indentation
+//!
+//! `PUSH_INDENT`, `MATCH_INDENT` and `DEDENT` use the stack to match blocks of lines indented
+//! like in Python or YAML. An indentation is made of spaces and tabs, and an empty stack stands
+//! for no indentation:
+//!
+//! * `PUSH_INDENT` matches the indentation at the start of a line if it is deeper than the one on
+//!   top of the stack, i.e. starts with it and is longer, and pushes it
+//! * `MATCH_INDENT` matches the indentation on top of the stack, when it is not followed by more
+//!   spaces or tabs
+//! * `DEDENT` drops the top of the stack if the indentation at the start of a line is shallower,
+//!   i.e. the top of the stack starts with it and is longer, matching nothing; it also matches at
+//!   the end of the input
+//!
+//! With statements ending in a `NEWLINE`, a block could then be:
+//!
+//! ```ignore
+//! statement = { MATCH_INDENT ~ line ~ NEWLINE ~ block? }
+//! block = { PUSH_INDENT ~ line ~ NEWLINE ~ block? ~ statement* ~ DEDENT }
+//! ```
+//!
 //! ## `Rule`
 //!
 //! All rules defined or used in the grammar populate a generated `enum` called `Rule`. This
 //! implements `pest`'s `RuleType` and can be used throughout the API.
 //!
+//! Rules named after Rust keywords become raw identifiers, e.g. `Rule::r#type`. Since `crate`,
+//! `self`, `Self` and `super` cannot be raw identifiers, rules with these names are renamed by
+//! appending `_`, e.g. `Rule::self_`. The documentation of a renamed variant states its name in
+//! the grammar, and `DynParser` keeps using the names of the grammar.
+//!
+//! Rules named after pest keywords, such as `ANY` or `EOI`, are rejected by default. With the
+//! `#[reserved_names = "rename"]` attribute, they are renamed the same way and take precedence
+//! over the keywords in the grammar, which helps when porting grammars from other tools:
+//!
+//! ```ignore
+//! #[derive(Parser)]
+//! #[grammar_inline = "ANY = { \"a\" } any = { ANY+ }"]
+//! #[reserved_names = "rename"]
+//! struct MyParser;
+//!
+//! // `Rule::ANY_` is the rule `ANY` of the grammar.
+//! ```
+//!
+//! Next to `Rule`, the generated `rule_children` module holds an `enum` for every rule producing
+//! pairs, listing the rules which can appear as direct children of its pairs. The `match_rule!`
+//! macro uses it to turn forgetting to handle one of them into a compile error:
+//!
+//! ```ignore
+//! use pest::match_rule;
+//!
+//! // expr = { number | ident }
+//! for pair in expr.into_inner() {
+//!     match_rule!(pair, Rule::expr {
+//!         number => println!("number {}", pair.as_str()),
+//!         ident => println!("ident {}", pair.as_str()),
+//!     });
+//! }
+//! ```
+//!
+//! The same analysis of the grammar is available at runtime, and in `const` contexts, through
+//! `Rule::possible_children`, `Rule::can_contain`, `Rule::is_atomic` and `Rule::is_silent`, e.g.
+//! for validating trees built by other means. It is also exposed by `pest_meta::children`.
+//!
+//! ## `FromPest`
+//!
+//! The `FromPest` derive implements `pest::FromPairs` for the types of a syntax tree, so that
+//! `File::from_pairs(&mut pairs)` builds a typed tree instead of walking the `Pairs` by hand.
+//!
+//! A struct is built from a pair of the rule given by `#[pest_ast(rule(Rule::name))]`. Its fields
+//! are built in order from the inner pairs, which they must all consume, unless marked with:
+//!
+//! * `#[pest_ast(span)]` - the `Span` of the pair
+//! * `#[pest_ast(str)]` - the text of the pair, converted with `From<&str>`, e.g. `&str` or `String`
+//! * `#[pest_ast(parse)]` - the text of the pair, parsed with `FromStr`
+//!
+//! Inner pairs are ignored if no field is built from them. Fields of type `Option<T>`, `Vec<T>`,
+//! `Box<T>` and `Spanned<T>` are built from `T`.
+//!
+//! The variants of an enum are tried in order. A variant with a `rule` attribute is built like a
+//! struct, while a variant without one holds a single field, built from the pairs themselves. An
+//! enum with a `rule` attribute is built from the inner pairs of a pair of this rule.
+//!
+//! ```ignore
+//! // call = { ident ~ "(" ~ (expr ~ ("," ~ expr)*)? ~ ")" }
+//! #[derive(FromPest)]
+//! #[pest_ast(rule(Rule::call))]
+//! struct Call<'i> {
+//!     #[pest_ast(span)]
+//!     span: Span<'i>,
+//!     name: Ident<'i>,
+//!     args: Vec<Expr<'i>>,
+//! }
+//!
+//! #[derive(FromPest)]
+//! #[pest_ast(rule(Rule::ident))]
+//! struct Ident<'i>(#[pest_ast(str)] &'i str);
+//!
+//! // expr = { number | call }
+//! #[derive(FromPest)]
+//! #[pest_ast(rule(Rule::expr))]
+//! enum Expr<'i> {
+//!     #[pest_ast(rule(Rule::number))]
+//!     Number(#[pest_ast(parse)] f64),
+//!     Call(Box<Call<'i>>),
+//! }
+//! ```
+//!
 //! ## `Built-in rules`
 //!
 //! Pest also comes with a number of built-in rules for convenience. They are:
@@ -319,7 +703,33 @@ use proc_macro::TokenStream;
 
 /// The main method that's called by the proc macro
 /// (a wrapper around `pest_generator::derive_parser`)
-#[proc_macro_derive(Parser, attributes(grammar, grammar_inline))]
+#[proc_macro_derive(
+    Parser,
+    attributes(
+        grammar,
+        grammar_inline,
+        reserved_names,
+        doc_module,
+        entry_rules,
+        prune,
+        non_exhaustive_rules,
+        hide_silent_rules,
+        rule_attributes,
+        grammar_family,
+        rule_enum,
+        left_recursion,
+        grammar_memoize,
+        memo_limit,
+        bytes
+    )
+)]
 pub fn derive_parser(input: TokenStream) -> TokenStream {
     pest_generator::derive_parser(input.into(), true).into()
 }
+
+/// The derive building syntax trees from pairs
+/// (a wrapper around `pest_generator::derive_from_pest`)
+#[proc_macro_derive(FromPest, attributes(pest_ast))]
+pub fn derive_from_pest(input: TokenStream) -> TokenStream {
+    pest_generator::derive_from_pest(input.into()).into()
+}
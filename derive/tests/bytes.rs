@@ -0,0 +1,39 @@
+// pest. The Elegant Parser
+// Copyright (c) 2018 Dragoș Tiselice
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+extern crate pest;
+extern crate pest_derive;
+
+use pest::{ByteInput, ByteParser};
+use pest_derive::Parser;
+
+#[derive(Parser)]
+#[grammar_inline = r#"
+frame = { SOI ~ "\x01" ~ length ~ payload ~ EOI }
+length = { ASCII_DIGIT }
+payload = { ('\u{80}'..'\u{ff}' | "\x00")* }
+"#]
+#[bytes = true]
+struct FrameParser;
+
+#[test]
+fn parse_bytes() {
+    let input = ByteInput::new(b"\x013\xca\x00\xfe");
+    let frame = FrameParser::parse_bytes(Rule::frame, &input)
+        .unwrap()
+        .next()
+        .unwrap();
+    let payload = frame.into_inner().nth(1).unwrap();
+
+    assert_eq!(payload.as_rule(), Rule::payload);
+    assert_eq!(input.span(&payload.as_span()).as_bytes(), b"\xca\x00\xfe");
+
+    let input = ByteInput::new(b"\x01a\xca");
+    assert!(FrameParser::parse_bytes(Rule::frame, &input).is_err());
+}
@@ -0,0 +1,55 @@
+// pest. The Elegant Parser
+// Copyright (c) 2018 Dragoș Tiselice
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+extern crate pest;
+extern crate pest_derive;
+
+use pest::Parser;
+use pest_derive::Parser;
+
+#[derive(Parser)]
+#[grammar_inline = r#"
+list = { SOI ~ number ~ ("," ~ number)* ~ trailing ~ EOI }
+number = @{ ASCII_DIGIT+ }
+@dialect(lenient, legacy)
+number = @{ ASCII_DIGIT ~ ("_" | ASCII_DIGIT)* }
+@dialect(lenient)
+trailing = { ","? }
+trailing = { "" }
+@dialect(legacy)
+octal = @{ "0" ~ ASCII_OCT_DIGIT+ }
+"#]
+struct ListParser;
+
+#[test]
+fn base() {
+    assert!(ListParser::parse(Rule::list, "1,2").is_ok());
+    assert!(ListParser::parse(Rule::list, "1_000,2").is_err());
+    assert!(ListParser::parse(Rule::list, "1,2,").is_err());
+}
+
+#[test]
+fn dialect() {
+    assert!(ListParser::parse_dialect(Dialect::lenient, Rule::list, "1_000,2,").is_ok());
+    assert!(ListParser::parse_dialect(Dialect::legacy, Rule::list, "1_000,2").is_ok());
+    assert!(ListParser::parse_dialect(Dialect::legacy, Rule::list, "1,2,").is_err());
+}
+
+#[test]
+fn only_in_dialect() {
+    assert!(ListParser::parse(Rule::octal, "017").is_err());
+    assert!(ListParser::parse_dialect(Dialect::legacy, Rule::octal, "017").is_ok());
+}
+
+#[test]
+fn dialect_names() {
+    assert_eq!(Dialect::lenient.as_str(), "lenient");
+    assert_eq!(Dialect::legacy.as_str(), "legacy");
+    assert!(ListParser::parse_with_dialect(Rule::list, "1,2,", "unknown").is_err());
+}
@@ -0,0 +1,82 @@
+// pest. The Elegant Parser
+// Copyright (c) 2018 Dragoș Tiselice
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+extern crate alloc;
+use alloc::boxed::Box;
+use alloc::vec;
+use alloc::vec::Vec;
+
+extern crate pest;
+#[macro_use]
+extern crate pest_derive;
+
+use pest::error::ErrorVariant;
+use pest::DynParser;
+
+mod numbers {
+    #[derive(Parser)]
+    #[grammar_inline = "number = { ASCII_DIGIT+ } numbers = { SOI ~ number ~ (\",\" ~ number)* ~ EOI }"]
+    pub struct NumbersParser;
+}
+
+mod words {
+    #[derive(Parser)]
+    #[grammar_inline = "word = { ASCII_ALPHA+ }"]
+    pub struct WordsParser;
+}
+
+fn parsers() -> Vec<Box<dyn DynParser>> {
+    vec![
+        Box::new(numbers::NumbersParser),
+        Box::new(words::WordsParser),
+    ]
+}
+
+#[test]
+fn rule_names() {
+    let parsers = parsers();
+
+    assert_eq!(parsers[0].rule_names(), ["EOI", "number", "numbers"]);
+    assert_eq!(parsers[1].rule_names(), ["word"]);
+}
+
+#[test]
+fn parse_dyn() {
+    let parsers = parsers();
+
+    let pairs = parsers[0].parse_dyn("numbers", "1,23").unwrap();
+    let rules: Vec<_> = pairs.flatten().map(|pair| pair.as_rule()).collect();
+    assert_eq!(rules, ["numbers", "number", "number", "EOI"]);
+
+    let pairs = parsers[1].parse_dyn("word", "abc").unwrap();
+    assert_eq!(pairs.as_str(), "abc");
+}
+
+#[test]
+fn parse_dyn_error() {
+    let parsers = parsers();
+
+    let error = parsers[0].parse_dyn("numbers", "1,").unwrap_err();
+    assert_eq!(
+        error.variant,
+        ErrorVariant::ParsingError {
+            positives: vec!["number"],
+            negatives: vec![],
+        }
+    );
+
+    let error = parsers[1].parse_dyn("number", "1").unwrap_err();
+    assert_eq!(
+        error.variant,
+        ErrorVariant::CustomError {
+            message: "unknown rule `number`".into(),
+        }
+    );
+}
@@ -0,0 +1,54 @@
+// pest. The Elegant Parser
+// Copyright (c) 2018 Dragoș Tiselice
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+extern crate alloc;
+use alloc::format;
+
+extern crate pest;
+#[macro_use]
+extern crate pest_derive;
+
+use pest::explain::Outcome;
+use pest::{ParseOptions, Parser};
+
+#[derive(Parser)]
+#[grammar_inline = "
+    assignments = { SOI ~ assignment ~ (\",\" ~ assignment)* ~ EOI }
+    assignment = { key ~ \"=\" ~ value }
+    key = @{ !keyword ~ ASCII_ALPHA+ }
+    keyword = { \"let\" }
+    value = @{ ASCII_DIGIT+ }
+    WHITESPACE = _{ \" \" }
+"]
+struct AssignmentParser;
+
+#[test]
+fn explanation() {
+    let options = ParseOptions::new().with_explain();
+    let error =
+        AssignmentParser::parse_with(Rule::assignments, "a = 1, let = 2", &options).unwrap_err();
+
+    let explanation = error.explanation().unwrap();
+    assert_eq!(explanation.pos, 7);
+    assert_eq!(
+        format!("{}", explanation),
+        "assignments at 0, reached 7: failed, expected \" \"\n  \
+           assignment at 7, reached 7: failed\n    \
+             key at 7, reached 7: failed\n      \
+               keyword at 7, reached 10: matched, but a negative predicate forbids it\n"
+    );
+
+    let keyword = &explanation.attempts[0].children[0].children[0].children[0];
+    assert_eq!(keyword.rule, Rule::keyword);
+    assert_eq!(keyword.outcome, Outcome::Forbidden);
+
+    let error = AssignmentParser::parse(Rule::assignments, "a = 1, let = 2").unwrap_err();
+    assert!(error.explanation().is_none());
+}
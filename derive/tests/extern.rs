@@ -0,0 +1,143 @@
+// pest. The Elegant Parser
+// Copyright (c) 2018 Dragoș Tiselice
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+#![cfg(feature = "std")]
+
+#[macro_use]
+extern crate pest;
+#[macro_use]
+extern crate pest_derive;
+
+use pest::Parser;
+
+mod template {
+    #[derive(Parser)]
+    #[grammar_inline = "
+        @extern { expr }
+        template = { SOI ~ (\"{{\" ~ expr ~ \"}}\" | text)* ~ EOI }
+        text = { (!\"{{\" ~ ANY)+ }
+        number = { ASCII_DIGIT+ }
+    "]
+    pub struct TemplateParser;
+}
+
+mod expr {
+    #[derive(Parser)]
+    #[grammar_inline = "
+        sum = { number ~ (\"+\" ~ number)* }
+        number = @{ ASCII_DIGIT+ }
+    "]
+    pub struct ExprParser;
+}
+
+mod c {
+    #[derive(Parser)]
+    #[grammar_inline = "
+        @extern { statement }
+        program = { SOI ~ (statement ~ \";\")* ~ EOI }
+        declaration = { ident ~ \"*\" ~ ident }
+        expression = { ident ~ (\"*\" ~ ident)* }
+        ident = @{ ASCII_ALPHA+ }
+        WHITESPACE = _{ \" \" }
+    "]
+    pub struct CParser;
+}
+
+// `T` is the only typedef name.
+fn bind_statement() {
+    use c::{CParser, Rule};
+
+    CParser::bind_fn(Rule::statement, |state| {
+        match state.lookahead(true, |s| {
+            s.match_string("T").and_then(|s| s.match_string(" "))
+        }) {
+            Ok(state) => CParser::parse_state(Rule::declaration, state),
+            Err(state) => CParser::parse_state(Rule::expression, state),
+        }
+    });
+}
+
+fn bind() {
+    template::TemplateParser::bind(
+        template::Rule::expr,
+        expr::ExprParser,
+        expr::Rule::sum,
+        |rule| match rule {
+            expr::Rule::number => Some(template::Rule::number),
+            expr::Rule::sum => None,
+        },
+    );
+}
+
+#[test]
+fn nested() {
+    use template::{Rule, TemplateParser};
+
+    bind();
+
+    parses_to! {
+        parser: TemplateParser,
+        input: "a{{1+23}}b",
+        rule: Rule::template,
+        tokens: [
+            template(0, 10, [
+                text(0, 1),
+                expr(3, 7, [number(3, 4), number(5, 7)]),
+                text(9, 10),
+                EOI(10, 10)
+            ])
+        ]
+    };
+}
+
+#[test]
+fn nested_failure() {
+    bind();
+
+    let error = template::TemplateParser::parse(template::Rule::template, "a{{+}}").unwrap_err();
+
+    // The error points at the start of the extern rule.
+    assert_eq!(error.line_col, pest::error::LineColLocation::Pos((1, 4)));
+}
+
+#[test]
+#[should_panic(expected = "no parser is bound to the extern rule expr")]
+fn unbound() {
+    let _ = template::TemplateParser::parse(template::Rule::template, "{{1}}");
+}
+
+#[test]
+fn reentrant() {
+    use c::{CParser, Rule};
+
+    bind_statement();
+
+    parses_to! {
+        parser: CParser,
+        input: "T * x; a * b;",
+        rule: Rule::program,
+        tokens: [
+            program(0, 13, [
+                statement(0, 5, [declaration(0, 5, [ident(0, 1), ident(4, 5)])]),
+                statement(7, 12, [expression(7, 12, [ident(7, 8), ident(11, 12)])]),
+                EOI(13, 13)
+            ])
+        ]
+    };
+}
+
+#[test]
+fn reentrant_failure() {
+    bind_statement();
+
+    let error = c::CParser::parse(c::Rule::program, "T * ;").unwrap_err();
+
+    // The inner rules are matched within the outer parse, which reports their attempts.
+    assert_eq!(error.line_col, pest::error::LineColLocation::Pos((1, 5)));
+}
@@ -0,0 +1,69 @@
+// pest. The Elegant Parser
+// Copyright (c) 2018 Dragoș Tiselice
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+#[macro_use]
+extern crate pest_derive;
+extern crate pest;
+
+use pest::iterators::Pairs;
+use pest::{DynParser, Parser, RuleCode};
+
+mod strict {
+    #[derive(Parser)]
+    #[grammar_inline = "
+        list   = { \"[\" ~ (number ~ (\",\" ~ number)*)? ~ \"]\" ~ EOI }
+        number = @{ ASCII_DIGIT+ }
+    "]
+    #[grammar_family = "tests/family_lenient.pest"]
+    pub struct StrictParser;
+}
+
+mod lenient {
+    #[derive(Parser)]
+    #[grammar = "tests/family_lenient.pest"]
+    #[rule_enum = "super::strict::Rule"]
+    pub struct LenientParser;
+}
+
+use strict::Rule;
+
+// Handles the pairs of both parsers.
+fn numbers(pairs: Pairs<'_, Rule>) -> Vec<&str> {
+    pairs
+        .flatten()
+        .filter(|pair| pair.as_rule() == Rule::number)
+        .map(|pair| pair.as_str())
+        .collect()
+}
+
+#[test]
+fn shared_rule() {
+    let strict = strict::StrictParser::parse(Rule::list, "[1,2]").unwrap();
+    let lenient = lenient::LenientParser::parse(Rule::list, "[1 x 2,]").unwrap();
+
+    assert_eq!(numbers(strict), ["1", "2"]);
+    assert_eq!(numbers(lenient), ["1", "2"]);
+    assert!(strict::StrictParser::parse(Rule::list, "[1 2]").is_err());
+}
+
+#[test]
+fn family_variants() {
+    // The variants of the rest of the family follow the rules of the grammar.
+    assert_eq!(Rule::word.code(), 4);
+    assert!(strict::StrictParser::parse(Rule::word, "x").is_err());
+    assert!(lenient::LenientParser::parse(Rule::word, "x").is_ok());
+}
+
+#[test]
+fn family_dyn_parser() {
+    let pairs = lenient::LenientParser.parse_dyn("list", "[a]").unwrap();
+    let rules: Vec<_> = pairs.flatten().map(|pair| pair.as_rule()).collect();
+
+    assert_eq!(rules, ["list", "word", "EOI"]);
+}
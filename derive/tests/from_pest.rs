@@ -0,0 +1,128 @@
+// pest. The Elegant Parser
+// Copyright (c) 2018 Dragoș Tiselice
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+#![cfg(feature = "std")]
+
+extern crate pest;
+#[macro_use]
+extern crate pest_derive;
+
+use pest::error::{ErrorVariant, InputLocation};
+use pest::{FromPairs, Parser, Span, Spanned};
+
+#[derive(Parser)]
+#[grammar_inline = "
+    file = { SOI ~ call* ~ EOI }
+    call = { ident ~ \"(\" ~ (expr ~ (\",\" ~ expr)*)? ~ \")\" }
+    expr = { number | call | nil }
+    nil = { \"nil\" }
+    ident = @{ ASCII_ALPHA+ }
+    number = @{ ASCII_DIGIT+ }
+    WHITESPACE = _{ \" \" }
+"]
+struct CallParser;
+
+#[derive(Debug, FromPest, PartialEq)]
+#[pest_ast(rule(Rule::file))]
+struct File<'i> {
+    calls: Vec<Spanned<'i, Call<'i>>>,
+    eoi: Eoi,
+}
+
+#[derive(Debug, FromPest, PartialEq)]
+#[pest_ast(rule(Rule::EOI))]
+struct Eoi;
+
+#[derive(Debug, FromPest, PartialEq)]
+#[pest_ast(rule(Rule::call))]
+struct Call<'i> {
+    #[pest_ast(span)]
+    span: Span<'i>,
+    name: Ident,
+    args: Vec<Expr<'i>>,
+}
+
+#[derive(Debug, FromPest, PartialEq)]
+#[pest_ast(rule(Rule::ident))]
+struct Ident(#[pest_ast(str)] String);
+
+#[derive(Debug, FromPest, PartialEq)]
+#[pest_ast(rule(Rule::expr))]
+enum Expr<'i> {
+    #[pest_ast(rule(Rule::number))]
+    Number(#[pest_ast(parse)] u8),
+    #[pest_ast(rule(Rule::nil))]
+    Nil,
+    Call(Box<Call<'i>>),
+}
+
+#[allow(clippy::perf)]
+fn file(input: &str) -> Result<File<'_>, pest::error::Error<Rule>> {
+    let mut pairs = CallParser::parse(Rule::file, input).unwrap();
+    File::from_pairs(&mut pairs)
+}
+
+#[test]
+fn builds_tree() {
+    let input = "f(1, g(nil)) h()";
+    let file = file(input).unwrap();
+
+    assert_eq!(file.calls.len(), 2);
+    assert_eq!(file.calls[0].span.as_str(), "f(1, g(nil))");
+    assert_eq!(file.calls[0].name, Ident("f".to_owned()));
+    assert_eq!(file.calls[0].args[0], Expr::Number(1));
+    match file.calls[0].args[1] {
+        Expr::Call(ref call) => {
+            assert_eq!(call.span.as_str(), "g(nil)");
+            assert_eq!(call.args, vec![Expr::Nil]);
+        }
+        ref expr => panic!("expected a call, found {:?}", expr),
+    }
+    assert_eq!(file.calls[1].span, file.calls[1].value.span);
+    assert!(file.calls[1].args.is_empty());
+    assert_eq!(file.eoi, Eoi);
+}
+
+#[test]
+fn parse_error() {
+    let error = file("f(1, 256)").unwrap_err();
+
+    assert_eq!(error.location, InputLocation::Span((5, 8)));
+    assert_eq!(
+        error.variant,
+        ErrorVariant::CustomError {
+            message: "number too large to fit in target type".to_owned()
+        }
+    );
+}
+
+// An enum built from the pairs themselves, whatever the rule type.
+#[derive(Debug, FromPest, PartialEq)]
+enum Either<L, R> {
+    Left(L),
+    Right(R),
+}
+
+#[test]
+fn unexpected_pair() {
+    let mut pairs = CallParser::parse(Rule::call, "f(1)").unwrap();
+    let mut inner = pairs.next().unwrap().into_inner();
+    let error = Either::<Eoi, Call<'_>>::from_pairs(&mut inner).unwrap_err();
+
+    // The errors of both variants are merged, and the pairs are left as they were.
+    assert_eq!(error.location, InputLocation::Pos(0));
+    assert_eq!(
+        error.variant,
+        ErrorVariant::ParsingError {
+            positives: vec![Rule::EOI, Rule::call],
+            negatives: vec![]
+        }
+    );
+    assert_eq!(inner.next().unwrap().as_rule(), Rule::ident);
+}
@@ -17,6 +17,7 @@ extern crate pest_derive;
 
 #[derive(Parser)]
 #[grammar = "tests/grammar.pest"]
+#[doc_module = "grammar"]
 struct GrammarParser;
 
 #[test]
@@ -31,6 +32,30 @@ fn string() {
     };
 }
 
+#[test]
+fn escaped_string() {
+    parses_to! {
+        parser: GrammarParser,
+        input: "ab",
+        rule: Rule::escaped_string,
+        tokens: [
+            escaped_string(0, 2)
+        ]
+    };
+}
+
+#[test]
+fn raw_string() {
+    parses_to! {
+        parser: GrammarParser,
+        input: "a\"\\",
+        rule: Rule::raw_string,
+        tokens: [
+            raw_string(0, 3)
+        ]
+    };
+}
+
 #[test]
 fn insensitive() {
     parses_to! {
@@ -105,6 +130,121 @@ fn double_neg_pred() {
     };
 }
 
+#[test]
+fn lookbehind() {
+    parses_to! {
+        parser: GrammarParser,
+        input: "abc1",
+        rule: Rule::lookbehind,
+        tokens: [
+            lookbehind(0, 4, [
+                string(0, 3),
+                range(3, 4)
+            ])
+        ]
+    };
+}
+
+#[test]
+#[should_panic]
+fn lookbehind_space() {
+    parses_to! {
+        parser: GrammarParser,
+        input: "abc 1",
+        rule: Rule::lookbehind,
+        tokens: []
+    };
+}
+
+#[test]
+fn keyword_ident() {
+    parses_to! {
+        parser: GrammarParser,
+        input: "iffy",
+        rule: Rule::keyword_ident,
+        tokens: [
+            keyword_ident(0, 4)
+        ]
+    };
+}
+
+#[test]
+#[should_panic]
+fn keyword_ident_keyword() {
+    parses_to! {
+        parser: GrammarParser,
+        input: "else",
+        rule: Rule::keyword_ident,
+        tokens: []
+    };
+}
+
+#[test]
+fn keyword() {
+    parses_to! {
+        parser: GrammarParser,
+        input: "if",
+        rule: Rule::keyword,
+        tokens: [
+            kw_if(0, 2)
+        ]
+    };
+}
+
+#[test]
+fn quoted() {
+    parses_to! {
+        parser: GrammarParser,
+        input: "\"ab\"",
+        rule: Rule::quoted,
+        tokens: [
+            quoted(0, 4)
+        ]
+    };
+}
+
+#[test]
+fn quoted_list() {
+    parses_to! {
+        parser: GrammarParser,
+        input: "'a', \"b\"",
+        rule: Rule::quoted_list,
+        tokens: [
+            quoted_list(0, 8, [
+                quoted(0, 3),
+                quoted(5, 8)
+            ])
+        ]
+    };
+}
+
+#[test]
+fn labeled() {
+    parses_to! {
+        parser: GrammarParser,
+        input: "(abc, abc)",
+        rule: Rule::labeled,
+        tokens: [
+            labeled(0, 10, [
+                string(1, 4),
+                string(6, 9)
+            ])
+        ]
+    };
+}
+
+#[test]
+fn labeled_error() {
+    use pest::Parser;
+
+    let error = GrammarParser::parse(Rule::labeled, "(abc, ab)").unwrap_err();
+    let labels = error.labels();
+
+    assert_eq!(labels.len(), 1);
+    assert_eq!(labels[0].location(), (1, 6));
+    assert_eq!(labels[0].message(), "while parsing argument list");
+}
+
 #[test]
 fn sequence() {
     parses_to! {
@@ -825,6 +965,35 @@ fn checkpoint_restore() {
     };
 }
 
+#[test]
+fn indented() {
+    parses_to! {
+        parser: GrammarParser,
+        input: "a\n b\n  c\n d\ne\n",
+        rule: Rule::indented,
+        tokens: [
+            indented(0, 14, [
+                indented_line(0, 12, [
+                    indented_block(2, 12, [
+                        indented_block(5, 9),
+                        indented_line(9, 12)
+                    ])
+                ]),
+                indented_line(12, 14),
+                EOI(14, 14)
+            ])
+        ]
+    };
+}
+
+#[test]
+fn indented_mismatch() {
+    use pest::Parser;
+
+    assert!(GrammarParser::parse(Rule::indented, "a\n  b\n c\n").is_err());
+    assert!(GrammarParser::parse(Rule::indented, "a\n\tb\n    c\n").is_err());
+}
+
 #[test]
 fn ascii_digits() {
     parses_to! {
@@ -969,6 +1138,23 @@ fn unicode() {
     }
 }
 
+#[test]
+fn provenance() {
+    assert_eq!(
+        GrammarParser::GRAMMAR_HASH,
+        pest::grammar_hash(include_str!("grammar.pest"))
+    );
+    assert_eq!(GrammarParser::GRAMMAR_SOURCE, Some("tests/grammar.pest"));
+}
+
+#[test]
+fn warm_up() {
+    use pest::Parser;
+
+    GrammarParser::warm_up();
+    assert!(GrammarParser::parse(Rule::unicode, "نامهای").is_ok());
+}
+
 #[test]
 fn shadowing() {
     parses_to! {
@@ -980,3 +1166,96 @@ fn shadowing() {
         ]
     }
 }
+
+#[test]
+fn rule_codes() {
+    use pest::RuleCode;
+
+    assert_eq!(Rule::EOI.code(), 0);
+    assert_eq!(Rule::from_code(Rule::quoted.code()), Some(Rule::quoted));
+    assert_eq!(Rule::from_code(u16::MAX), None);
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn write_to() {
+    use pest::iterators::OwnedPairs;
+    use pest::Parser;
+
+    let pairs = GrammarParser::parse(Rule::quoted_list, "'a', \"b\"").unwrap();
+    let mut bytes = Vec::new();
+    pairs.write_to(&mut bytes).unwrap();
+    let owned = OwnedPairs::<Rule>::read_from(&bytes[..]).unwrap();
+
+    assert_eq!(format!("{}", owned.pairs()), format!("{}", pairs));
+}
+
+#[test]
+fn match_rule() {
+    use pest::Parser;
+
+    let list = GrammarParser::parse(Rule::quoted_list, "'a', \"b\"")
+        .unwrap()
+        .next()
+        .unwrap();
+    let quoted: Vec<_> = list
+        .into_inner()
+        .map(|pair| match_rule!(pair, Rule::quoted_list { quoted => pair.as_str() }))
+        .collect();
+
+    assert_eq!(quoted, ["'a'", "\"b\""]);
+}
+
+#[test]
+fn possible_children() {
+    assert_eq!(Rule::quoted_list.possible_children(), [Rule::quoted]);
+    assert_eq!(
+        Rule::choice.possible_children(),
+        [Rule::range, Rule::string]
+    );
+    assert!(Rule::sequence_compound_nested.can_contain(Rule::sequence_nested));
+    assert!(!Rule::sequence_compound_nested.can_contain(Rule::string));
+    assert!(Rule::sequence_atomic.possible_children().is_empty());
+    assert!(Rule::EOI.possible_children().is_empty());
+}
+
+#[test]
+fn rule_types() {
+    const ATOMIC: bool = Rule::sequence_compound.is_atomic();
+
+    assert!(ATOMIC);
+    assert!(Rule::quoted.is_atomic());
+    assert!(!Rule::sequence.is_atomic());
+    assert!(!Rule::quoted.is_silent());
+    assert!(Rule::WHITESPACE.is_silent());
+}
+
+#[test]
+fn literals() {
+    assert_eq!(Rule::string.literal(), Some("abc"));
+    assert_eq!(Rule::escaped_string.literal(), Some("ab"));
+    assert_eq!(Rule::insensitive.literal(), None);
+    assert_eq!(Rule::EOI.literal(), None);
+}
+
+#[test]
+fn suggestions() {
+    use pest::Parser;
+
+    let input = "abd";
+    let error = GrammarParser::parse(Rule::string, input)
+        .unwrap_err()
+        .with_suggestions(input, Rule::literal);
+
+    assert_eq!(error.suggestions(), ["abc"]);
+}
+
+#[test]
+fn parse_with_stats() {
+    use pest::Parser;
+
+    let (result, stats) = GrammarParser::parse_with_stats(Rule::string, "abc");
+
+    assert_eq!(result.unwrap().as_str(), "abc");
+    assert!(stats.queue_bytes > 0);
+}
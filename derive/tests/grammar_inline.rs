@@ -28,3 +28,12 @@ fn inline_string() {
         ]
     };
 }
+
+#[test]
+fn inline_provenance() {
+    assert_eq!(
+        GrammarParser::GRAMMAR_HASH,
+        pest::grammar_hash("string = { \"abc\" }")
+    );
+    assert_eq!(GrammarParser::GRAMMAR_SOURCE, None);
+}
@@ -0,0 +1,69 @@
+// pest. The Elegant Parser
+// Copyright (c) 2018 Dragoș Tiselice
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+#![cfg(feature = "std")]
+
+extern crate pest;
+#[macro_use]
+extern crate pest_derive;
+
+use pest::{IncrementalParser, Parser, TextEdit};
+
+#[derive(Parser)]
+#[grammar_inline = "
+    list = { SOI ~ item* ~ EOI }
+    item = { \"(\" ~ (number | item)* ~ \")\" }
+    number = @{ ASCII_DIGIT+ }
+    WHITESPACE = _{ \" \" }
+"]
+struct ListParser;
+
+fn incremental(input: &str) -> IncrementalParser<ListParser, Rule> {
+    IncrementalParser::new(ListParser, Rule::list, input.to_owned()).with_reparse_rule(Rule::item)
+}
+
+fn assert_reparsed(parser: &IncrementalParser<ListParser, Rule>) {
+    let full = ListParser::parse(Rule::list, parser.input()).unwrap();
+    assert_eq!(format!("{}", parser.pairs().unwrap()), format!("{}", full));
+}
+
+#[test]
+fn reparses_innermost_item() {
+    let mut parser = incremental("(1 (2 3)) (4)");
+
+    assert_eq!(parser.edit(TextEdit::new(6..7, "30 (5)".to_owned())), 3..13);
+    assert_eq!(parser.input(), "(1 (2 30 (5))) (4)");
+    assert_reparsed(&parser);
+
+    assert_eq!(parser.edit(TextEdit::new(17..17, " 6".to_owned())), 15..20);
+    assert_eq!(parser.input(), "(1 (2 30 (5))) (4 6)");
+    assert_reparsed(&parser);
+}
+
+#[test]
+fn reparses_enclosing_item() {
+    let mut parser = incremental("((1 2)) (3)");
+
+    // Splitting the inner item makes it end elsewhere, so its parent is parsed again.
+    assert_eq!(parser.edit(TextEdit::new(3..4, ") (".to_owned())), 0..9);
+    assert_eq!(parser.input(), "((1) (2)) (3)");
+    assert_reparsed(&parser);
+}
+
+#[test]
+fn reparses_everything() {
+    let mut parser = incremental("(1 (2 3)) (4)");
+
+    assert_eq!(parser.edit(TextEdit::new(5..5, ")".to_owned())), 0..14);
+    assert_eq!(parser.input(), "(1 (2) 3)) (4)");
+    assert!(parser.pairs().is_err());
+
+    assert_eq!(parser.edit(TextEdit::new(9..10, String::new())), 0..13);
+    assert_reparsed(&parser);
+}
@@ -0,0 +1,58 @@
+// pest. The Elegant Parser
+// Copyright (c) 2018 Dragoș Tiselice
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+#[macro_use]
+extern crate pest;
+#[macro_use]
+extern crate pest_derive;
+
+#[derive(Parser)]
+#[grammar_inline = "
+    @island(template) { \"{{\" expr \"}}\" \"<%\" stmt \"%>\" }
+    document = { SOI ~ template ~ EOI }
+    expr = { ASCII_ALPHA+ }
+    stmt = { \"end\" }
+"]
+struct TemplateParser;
+
+#[test]
+fn island() {
+    parses_to! {
+        parser: TemplateParser,
+        input: "a {{x}} <%end%>{{y}}\n",
+        rule: Rule::document,
+        tokens: [
+            document(0, 21, [
+                template(0, 21, [
+                    template_text(0, 2),
+                    expr(4, 5),
+                    template_text(7, 8),
+                    stmt(10, 13),
+                    expr(17, 18),
+                    template_text(20, 21)
+                ]),
+                EOI(21, 21)
+            ])
+        ]
+    };
+}
+
+#[test]
+fn unclosed() {
+    fails_with! {
+        parser: TemplateParser,
+        input: "a {{x",
+        rule: Rule::document,
+        positives: vec![Rule::EOI, Rule::template_text],
+        negatives: vec![],
+        pos: 2
+    };
+}
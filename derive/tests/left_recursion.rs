@@ -0,0 +1,94 @@
+// pest. The Elegant Parser
+// Copyright (c) 2018 Dragoș Tiselice
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+#[macro_use]
+extern crate pest;
+extern crate pest_derive;
+
+use pest_derive::Parser;
+
+#[derive(Parser)]
+#[grammar_inline = r#"
+expr = { expr ~ "+" ~ term | expr ~ "-" ~ term | term }
+term = { call | number }
+call = { term ~ "()" }
+number = @{ ASCII_DIGIT+ }
+letters = { SOI ~ silent ~ EOI }
+silent = _{ silent ~ "a" | "" }
+WHITESPACE = _{ " " }
+"#]
+#[left_recursion = true]
+struct ExprParser;
+
+#[test]
+fn left_associative() {
+    parses_to! {
+        parser: ExprParser,
+        input: "1 + 2 - 3",
+        rule: Rule::expr,
+        tokens: [
+            expr(0, 9, [
+                expr(0, 5, [
+                    expr(0, 1, [term(0, 1, [number(0, 1)])]),
+                    term(4, 5, [number(4, 5)])
+                ]),
+                term(8, 9, [number(8, 9)])
+            ])
+        ]
+    };
+}
+
+#[test]
+fn indirect() {
+    parses_to! {
+        parser: ExprParser,
+        input: "1()()",
+        rule: Rule::term,
+        tokens: [
+            term(0, 5, [
+                call(0, 5, [
+                    term(0, 3, [call(0, 3, [term(0, 1, [number(0, 1)])])])
+                ])
+            ])
+        ]
+    };
+}
+
+#[test]
+fn partial() {
+    parses_to! {
+        parser: ExprParser,
+        input: "1 + ",
+        rule: Rule::expr,
+        tokens: [
+            expr(0, 1, [term(0, 1, [number(0, 1)])])
+        ]
+    };
+
+    fails_with! {
+        parser: ExprParser,
+        input: "+",
+        rule: Rule::expr,
+        positives: vec![Rule::term],
+        negatives: vec![],
+        pos: 0
+    };
+}
+
+#[test]
+fn silent() {
+    parses_to! {
+        parser: ExprParser,
+        input: "aaa",
+        rule: Rule::letters,
+        tokens: [
+            letters(0, 3, [EOI(3, 3)])
+        ]
+    };
+}
@@ -0,0 +1,105 @@
+// pest. The Elegant Parser
+// Copyright (c) 2018 Dragoș Tiselice
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+extern crate pest;
+extern crate pest_derive;
+
+use pest::{ParseOptions, Parser};
+
+// Without memoization, every nesting level of `value` matches the next one twice, which takes
+// exponential time.
+mod memo {
+    use pest_derive::Parser;
+
+    #[derive(Parser)]
+    #[grammar_inline = r#"
+value = { "[" ~ value ~ "]" ~ "!" | "[" ~ value ~ "]" | "x" }
+@alias(word) { lower upper }
+lower = @{ ASCII_ALPHA_LOWER+ }
+upper = @{ ASCII_ALPHA_UPPER+ }
+words = { word ~ ("," ~ word)* }
+"#]
+    #[grammar_memoize]
+    pub struct MemoParser;
+}
+
+mod limited {
+    use pest_derive::Parser;
+
+    #[derive(Parser)]
+    #[grammar_inline = r#"
+value = { "[" ~ value ~ "]" ~ "!" | "[" ~ value ~ "]" | "x" }
+"#]
+    #[grammar_memoize = "value"]
+    #[memo_limit = 1048576]
+    pub struct LimitedParser;
+}
+
+mod plain {
+    use pest_derive::Parser;
+
+    #[derive(Parser)]
+    #[grammar_inline = r#"
+value = { "[" ~ value ~ "]" ~ "!" | "[" ~ value ~ "]" | "x" }
+"#]
+    pub struct PlainParser;
+}
+
+use limited::LimitedParser;
+use memo::{MemoParser, Rule};
+use plain::PlainParser;
+
+fn nested(depth: usize) -> String {
+    format!("{}x{}", "[".repeat(depth), "]".repeat(depth))
+}
+
+#[test]
+fn backtracking() {
+    let input = nested(40);
+
+    let pairs = MemoParser::parse(Rule::value, &input).unwrap();
+    assert_eq!(pairs.flatten().count(), 41);
+
+    let pairs = LimitedParser::parse(limited::Rule::value, &input).unwrap();
+    assert_eq!(pairs.flatten().count(), 41);
+}
+
+#[test]
+fn memoized_pairs() {
+    let input = nested(5) + "!";
+
+    assert_eq!(
+        format!("{}", MemoParser::parse(Rule::value, &input).unwrap()),
+        format!(
+            "{}",
+            PlainParser::parse(plain::Rule::value, &input).unwrap()
+        )
+    );
+}
+
+#[test]
+fn aliases() {
+    let pairs = MemoParser::parse(Rule::words, "ab,CD").unwrap();
+    let words: Vec<_> = pairs
+        .flatten()
+        .filter(|pair| pair.as_rule() == Rule::word)
+        .map(|pair| pair.as_str())
+        .collect();
+
+    assert_eq!(words, ["ab", "CD"]);
+}
+
+#[test]
+fn options() {
+    let options = ParseOptions::new().memoize();
+    let input = nested(40);
+
+    let pairs = PlainParser::parse_with(plain::Rule::value, &input, &options).unwrap();
+    assert_eq!(pairs.flatten().count(), 41);
+}
@@ -0,0 +1,61 @@
+// pest. The Elegant Parser
+// Copyright (c) 2018 Dragoș Tiselice
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+extern crate pest;
+extern crate pest_derive;
+
+use pest::Parser;
+use pest_derive::Parser;
+
+// The typedef names declared so far, which tell declarations from multiplications.
+struct TypeNames(Vec<&'static str>);
+
+fn is_type_name(text: &str, type_names: &TypeNames) -> bool {
+    type_names.0.contains(&text)
+}
+
+fn is_lowercase(text: &str, _: &()) -> bool {
+    text.chars().all(|c| c.is_ascii_lowercase())
+}
+
+#[derive(Parser)]
+#[grammar_inline = r#"
+statement = { declaration | multiplication }
+declaration = { #{is_type_name} name ~ "*" ~ name }
+multiplication = { name ~ "*" ~ name }
+name = @{ ASCII_ALPHA+ }
+lowercase = { #{is_lowercase} name }
+WHITESPACE = _{ " " }
+"#]
+struct CParser;
+
+fn first_rule(input: &str, type_names: &TypeNames) -> Rule {
+    let mut pairs = CParser::parse_with_context(Rule::statement, input, type_names).unwrap();
+    pairs.next().unwrap().into_inner().next().unwrap().as_rule()
+}
+
+#[test]
+fn context() {
+    let type_names = TypeNames(vec!["T"]);
+
+    assert_eq!(first_rule("T * x", &type_names), Rule::declaration);
+    assert_eq!(first_rule("a * b", &type_names), Rule::multiplication);
+}
+
+#[test]
+fn default_context() {
+    assert!(CParser::parse(Rule::lowercase, "abc").is_ok());
+    assert!(CParser::parse(Rule::lowercase, "aBc").is_err());
+}
+
+#[test]
+#[should_panic(expected = "not of the type taken by the predicate `is_type_name`")]
+fn wrong_context() {
+    let _ = CParser::parse(Rule::statement, "T * x");
+}
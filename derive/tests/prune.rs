@@ -0,0 +1,61 @@
+// pest. The Elegant Parser
+// Copyright (c) 2018 Dragoș Tiselice
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+#[macro_use]
+extern crate pest;
+#[macro_use]
+extern crate pest_derive;
+
+use pest::{DynParser, Parser};
+
+mod code {
+    #[derive(Parser)]
+    #[grammar_inline = "
+        list = { item ~ (\",\" ~ item)* }
+        item = { ASCII_DIGIT+ }
+        table = { list ~ (\";\" ~ list)* }
+    "]
+    #[entry_rules = "list"]
+    pub struct ListParser;
+}
+
+mod variants {
+    #[derive(Parser)]
+    #[grammar_inline = "
+        list = { item ~ (\",\" ~ item)* }
+        item = { ASCII_DIGIT+ }
+        table = { list ~ (\";\" ~ list)* }
+    "]
+    #[entry_rules = "list"]
+    #[prune = "enum"]
+    pub struct ListParser;
+}
+
+#[test]
+fn pruned_code() {
+    use code::{ListParser, Rule};
+
+    parses_to! {
+        parser: ListParser,
+        input: "1,2",
+        rule: Rule::list,
+        tokens: [list(0, 3, [item(0, 1), item(2, 3)])]
+    };
+
+    // The variant is kept, but parsing from it fails.
+    assert!(ListParser::parse(Rule::table, "1;2").is_err());
+}
+
+#[test]
+fn pruned_enum() {
+    use variants::{ListParser, Rule};
+
+    assert_eq!(ListParser.rule_names(), vec!["list", "item"]);
+    assert!(ListParser::parse(Rule::list, "1,2").is_ok());
+}
@@ -0,0 +1,71 @@
+// pest. The Elegant Parser
+// Copyright (c) 2018 Dragoș Tiselice
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+extern crate pest;
+#[macro_use]
+extern crate pest_derive;
+
+use pest::error::{ErrorVariant, InputLocation};
+use pest::{Parser, Recovery};
+
+#[derive(Parser)]
+#[grammar_inline = "
+    program = { SOI ~ stmt* ~ EOI }
+    stmt = { ident ~ \"=\" ~ number ~ \";\" }
+    ident = @{ ASCII_ALPHA+ }
+    number = @{ ASCII_DIGIT+ }
+    error = { ANY }
+    WHITESPACE = _{ \" \" }
+"]
+struct StmtParser;
+
+fn recovery() -> Recovery<Rule> {
+    Recovery::new(Rule::error)
+        .with_sync_rule(Rule::stmt)
+        .with_boundary(";")
+}
+
+#[test]
+fn recovers() {
+    let (pairs, errors) =
+        StmtParser::parse_recoverable(Rule::program, "a = 1; b = ; c = 3;", &recovery());
+
+    let rules: Vec<_> = pairs
+        .flatten()
+        .filter(|pair| pair.as_rule() == Rule::stmt || pair.as_rule() == Rule::error)
+        .map(|pair| (pair.as_rule(), pair.as_str()))
+        .collect();
+    assert_eq!(
+        rules,
+        vec![
+            (Rule::stmt, "a = 1;"),
+            (Rule::error, "b = ;"),
+            (Rule::stmt, "c = 3;")
+        ]
+    );
+
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].location, InputLocation::Pos(11));
+    assert_eq!(
+        errors[0].variant,
+        ErrorVariant::ParsingError {
+            positives: vec![Rule::number],
+            negatives: vec![]
+        }
+    );
+}
+
+#[test]
+fn unrecoverable() {
+    let (pairs, errors) = StmtParser::parse_recoverable(Rule::program, "a = 1; 2", &recovery());
+
+    assert_eq!(pairs.count(), 0);
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].location, InputLocation::Pos(7));
+}
@@ -127,3 +127,24 @@ fn mixed_progress() {
         pos: 1
     };
 }
+
+#[test]
+fn error_head() {
+    use pest::error::{ErrorVariant, InputLocation};
+    use pest::Parser;
+
+    let head = ReportingParser::parse_head(Rule::choices_a_progress, "ax").unwrap_err();
+
+    assert_eq!(head.location, InputLocation::Pos(1));
+    assert_eq!(
+        head.variant,
+        ErrorVariant::ParsingError {
+            positives: vec![Rule::a],
+            negatives: vec![],
+        }
+    );
+    assert_eq!(
+        head.into_error("ax"),
+        ReportingParser::parse(Rule::choices_a_progress, "ax").unwrap_err()
+    );
+}
@@ -0,0 +1,57 @@
+// pest. The Elegant Parser
+// Copyright (c) 2018 Dragoș Tiselice
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+extern crate alloc;
+use alloc::{format, vec::Vec};
+
+#[macro_use]
+extern crate pest;
+#[macro_use]
+extern crate pest_derive;
+
+use pest::DynParser;
+
+#[derive(Parser)]
+#[grammar_inline = "
+    match = { SOI ~ (type | self)* ~ EOI }
+    type = { \"t\" }
+    self = { ANY }
+    ANY = { \"a\" }
+"]
+#[reserved_names = "rename"]
+struct ReservedParser;
+
+#[test]
+fn keywords_and_builtins() {
+    parses_to! {
+        parser: ReservedParser,
+        input: "ta",
+        rule: Rule::r#match,
+        tokens: [
+            r#match(0, 2, [
+                r#type(0, 1),
+                self_(1, 2, [ANY_(1, 2)]),
+                EOI(2, 2)
+            ])
+        ]
+    };
+}
+
+#[test]
+fn dyn_names() {
+    assert_eq!(
+        ReservedParser.rule_names(),
+        ["EOI", "match", "type", "self", "ANY"]
+    );
+
+    let pairs = ReservedParser.parse_dyn("self", "a").unwrap();
+    let rules: Vec<_> = pairs.flatten().map(|pair| pair.as_rule()).collect();
+    assert_eq!(rules, ["self", "ANY"]);
+}
@@ -0,0 +1,42 @@
+// pest. The Elegant Parser
+// Copyright (c) 2018 Dragoș Tiselice
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+#[macro_use]
+extern crate pest;
+#[macro_use]
+extern crate pest_derive;
+
+#[derive(Parser)]
+#[grammar_inline = "
+    list = { SOI ~ item ~ (separator ~ item)* ~ EOI }
+    item = { ASCII_DIGIT+ }
+    separator = _{ \",\" }
+"]
+#[non_exhaustive_rules = true]
+#[hide_silent_rules = true]
+#[rule_attributes = "#[repr(u8)]"]
+pub struct ListParser;
+
+#[test]
+fn parses() {
+    parses_to! {
+        parser: ListParser,
+        input: "1,23",
+        rule: Rule::list,
+        tokens: [
+            list(0, 4, [item(0, 1), item(2, 4), EOI(4, 4)])
+        ]
+    };
+}
+
+#[test]
+fn attributes() {
+    assert_eq!(core::mem::size_of::<Rule>(), 1);
+    assert_eq!(Rule::separator as u8, 3);
+}
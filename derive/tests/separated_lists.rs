@@ -0,0 +1,85 @@
+// pest. The Elegant Parser
+// Copyright (c) 2018 Dragoș Tiselice
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+#[macro_use]
+extern crate pest;
+extern crate pest_derive;
+
+use pest::Parser;
+use pest_derive::Parser;
+
+#[derive(Parser)]
+#[grammar_inline = r#"
+array  = { SOI ~ "[" ~ LIST(number, comma) ~ "]" ~ EOI }
+number = @{ ASCII_DIGIT+ }
+comma  = { "," }
+WHITESPACE = _{ " " }
+"#]
+struct ArrayParser;
+
+#[test]
+fn items() {
+    parses_to! {
+        parser: ArrayParser,
+        input: "[1, 23]",
+        rule: Rule::array,
+        tokens: [
+            array(0, 7, [
+                number(1, 2),
+                comma(2, 3),
+                number(4, 6),
+                EOI(7, 7)
+            ])
+        ]
+    };
+}
+
+#[test]
+fn trailing_separator() {
+    parses_to! {
+        parser: ArrayParser,
+        input: "[1,]",
+        rule: Rule::array,
+        tokens: [
+            array(0, 4, [
+                number(1, 2),
+                comma(2, 3),
+                EOI(4, 4)
+            ])
+        ]
+    };
+}
+
+#[test]
+fn empty() {
+    parses_to! {
+        parser: ArrayParser,
+        input: "[ ]",
+        rule: Rule::array,
+        tokens: [
+            array(0, 3, [
+                EOI(3, 3)
+            ])
+        ]
+    };
+}
+
+#[test]
+fn missing_item() {
+    assert!(ArrayParser::parse(Rule::array, "[,]").is_err());
+
+    fails_with! {
+        parser: ArrayParser,
+        input: "[1,,2]",
+        rule: Rule::array,
+        positives: vec![Rule::number],
+        negatives: vec![],
+        pos: 3
+    };
+}
@@ -0,0 +1,69 @@
+// pest. The Elegant Parser
+// Copyright (c) 2018 Dragoș Tiselice
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+extern crate pest;
+#[macro_use]
+extern crate pest_derive;
+
+use pest::{Parser, Repair, RepairKind, Tolerance};
+
+#[derive(Parser)]
+#[grammar_inline = "
+    log = { SOI ~ (line ~ NEWLINE)* ~ EOI }
+    line = { level ~ \":\" ~ message }
+    level = { \"ERROR\" | \"WARNING\" | \"INFO\" }
+    message = { (!NEWLINE ~ ANY)* }
+"]
+struct LogParser;
+
+#[test]
+fn repairs_tolerant_rules() {
+    let tolerance = Tolerance::new().with_rule(Rule::level);
+    let (pairs, repairs) =
+        LogParser::parse_tolerant(Rule::log, "INFO:up\nWARNNING:slow\nEROR:down\n", &tolerance)
+            .unwrap();
+
+    let levels: Vec<_> = pairs
+        .flatten()
+        .filter(|pair| pair.as_rule() == Rule::level)
+        .map(|pair| pair.as_str())
+        .collect();
+    assert_eq!(levels, ["INFO", "WARNNING", "EROR"]);
+
+    assert_eq!(
+        repairs,
+        vec![
+            Repair {
+                literal: "WARNING".to_owned(),
+                start: 8,
+                end: 16,
+                pos: 11,
+                kind: RepairKind::Extra('N')
+            },
+            Repair {
+                literal: "ERROR".to_owned(),
+                start: 22,
+                end: 26,
+                pos: 23,
+                kind: RepairKind::Missing('R')
+            }
+        ]
+    );
+}
+
+#[test]
+fn strict_first() {
+    let tolerance = Tolerance::new().with_rule(Rule::level);
+    let (_, repairs) = LogParser::parse_tolerant(Rule::log, "INFO:up\n", &tolerance).unwrap();
+    assert!(repairs.is_empty());
+
+    // Only literals of the tolerant rules are repaired.
+    let error = LogParser::parse_tolerant(Rule::log, "INFO;up\n", &tolerance).unwrap_err();
+    assert_eq!(error, LogParser::parse(Rule::log, "INFO;up\n").unwrap_err());
+}
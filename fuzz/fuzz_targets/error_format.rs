@@ -0,0 +1,6 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use pest_fuzz::ErrorInput;
+
+fuzz_target!(|data: ErrorInput<'_>| pest_fuzz::error_format(data));
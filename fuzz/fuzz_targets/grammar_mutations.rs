@@ -0,0 +1,6 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use pest_fuzz::MutationInput;
+
+fuzz_target!(|data: MutationInput<'_>| pest_fuzz::grammar_mutations(data));
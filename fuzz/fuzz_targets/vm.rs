@@ -0,0 +1,6 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use pest_fuzz::VmInput;
+
+fuzz_target!(|data: VmInput<'_>| pest_fuzz::vm(data));
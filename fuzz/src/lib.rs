@@ -0,0 +1,543 @@
+// pest. The Elegant Parser
+// Copyright (c) 2018 Dragoș Tiselice
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! # pest fuzzing harnesses
+//!
+//! The harnesses are plain, deterministic functions, which keeps the targets in `fuzz_targets/`
+//! down to a single line and makes any crashing input replayable as a regular test. Parses run
+//! under a call limit, so that grammars with exponential backtracking fail instead of hanging.
+//!
+//! Run a target with `cargo fuzz run <target>` from this directory, e.g. `cargo fuzz run vm`.
+
+#![warn(missing_docs, rust_2018_idioms, unused_qualifications)]
+
+use std::mem;
+use std::num::NonZeroUsize;
+use std::str;
+
+use arbitrary::Arbitrary;
+use pest::error::{Error, ErrorVariant};
+use pest::iterators::Pairs;
+use pest::{ColumnMode, Parser, Position, RuleType, Span};
+use pest_meta::ast::{Expr, Rule, RuleType as AstRuleType};
+use pest_meta::optimizer::OptimizedRule;
+use pest_meta::parser::{self, Rule as GrammarRule};
+use pest_vm::Vm;
+
+const CALL_LIMIT: usize = 10_000;
+
+/// The valid grammars mutated by [`grammar_mutations`]. Since `POP` and `PEEK` panic on an empty
+/// stack by design, none of them use the stack.
+pub const SEED_GRAMMARS: &[&str] = &[
+    include_str!("../../grammars/src/grammars/http.pest"),
+    include_str!("../../grammars/src/grammars/json.pest"),
+    include_str!("../../grammars/src/grammars/toml.pest"),
+];
+
+/// Parses and validates `data` as a grammar.
+pub fn meta(data: &[u8]) {
+    if let Ok(grammar) = str::from_utf8(data) {
+        let _ = pest_meta::parse_and_optimize(grammar);
+    }
+}
+
+/// Parses `data` with each of the parsers generated for `pest_grammars`.
+pub fn generated(data: &[u8]) {
+    use pest_grammars::{http, json, toml};
+
+    let input = match str::from_utf8(data) {
+        Ok(input) => input,
+        Err(_) => return,
+    };
+
+    with_call_limit(|| {
+        check(http::HttpParser::parse(http::Rule::http, input));
+        check(json::JsonParser::parse(json::Rule::json, input));
+        check(toml::TomlParser::parse(toml::Rule::toml, input));
+    });
+}
+
+/// The input of [`vm`].
+#[derive(Arbitrary, Debug)]
+pub struct VmInput<'a> {
+    /// The grammar to parse `input` with
+    pub grammar: &'a str,
+    /// The input to parse with every rule of `grammar`
+    pub input: &'a str,
+}
+
+/// Parses `data.input` with every rule of `data.grammar`, if it is valid.
+pub fn vm(data: VmInput<'_>) {
+    if let Ok((_, rules)) = pest_meta::parse_and_optimize(data.grammar) {
+        run_vm(rules, data.input);
+    }
+}
+
+/// The input of [`error_format`].
+#[derive(Arbitrary, Debug)]
+pub struct ErrorInput<'a> {
+    /// The input the error is located in
+    pub input: &'a str,
+    /// The error's position, or the start of its span
+    pub start: usize,
+    /// The end of the error's span, if any
+    pub end: Option<usize>,
+    /// The number of rules expected by the error, or a custom error if `0`
+    pub positives: u8,
+    /// The error's path
+    pub path: Option<&'a str>,
+    /// The error's column mode, as an index into `[Chars, Graphemes, DisplayWidth]`
+    pub column_mode: u8,
+    /// The error's snippet width
+    pub snippet_width: Option<u8>,
+    /// The spans of the error's labels
+    pub labels: Vec<(usize, usize)>,
+}
+
+/// Builds an error as described by `data` and formats it.
+pub fn error_format(data: ErrorInput<'_>) {
+    let input = data.input;
+    let start = boundary(input, data.start);
+    let variant = match data.positives {
+        0 => ErrorVariant::CustomError {
+            message: "custom".to_owned(),
+        },
+        n => ErrorVariant::ParsingError {
+            positives: (0..n).collect(),
+            negatives: vec![n],
+        },
+    };
+
+    let mut error = match data.end {
+        Some(end) => {
+            let (start, end) = ordered(start, boundary(input, end));
+            Error::new_from_span(variant, Span::new(input, start, end).unwrap())
+        }
+        None => Error::new_from_pos(variant, Position::new(input, start).unwrap()),
+    };
+
+    if let Some(path) = data.path {
+        error = error.with_path(path);
+    }
+    if let Some(width) = data.snippet_width {
+        error = error.with_snippet_width(width as usize);
+    }
+    for (start, end) in data.labels {
+        let (start, end) = ordered(boundary(input, start), boundary(input, end));
+        error = error.with_label(Span::new(input, start, end).unwrap(), "label");
+    }
+
+    let column_mode = match data.column_mode % 3 {
+        0 => ColumnMode::Chars,
+        1 => ColumnMode::Graphemes,
+        _ => ColumnMode::DisplayWidth,
+    };
+    let error = error
+        .with_column_mode(column_mode)
+        .with_unclosed_delimiter(input, &[("(", ")"), ("\"", "\"")]);
+
+    let _ = error.to_string();
+    let _ = error.stable_display();
+    let _ = error.snippet_window();
+}
+
+/// The input of [`grammar_mutations`].
+#[derive(Arbitrary, Debug)]
+pub struct MutationInput<'a> {
+    /// The index of the seed grammar to mutate in [`SEED_GRAMMARS`]
+    pub seed: u8,
+    /// The mutations to apply, in order
+    pub mutations: Vec<Mutation<'a>>,
+    /// The input to parse with every rule of the mutated grammar
+    pub input: &'a str,
+}
+
+/// A mutation of the expression node at index `node` (in pre-order) of the rule at index `rule`.
+/// Both indices wrap around.
+#[derive(Arbitrary, Debug)]
+pub struct Mutation<'a> {
+    /// The index of the mutated rule
+    pub rule: usize,
+    /// The index of the mutated node
+    pub node: usize,
+    /// The mutation to apply
+    pub kind: MutationKind<'a>,
+}
+
+/// A kind of [`Mutation`].
+#[derive(Arbitrary, Debug)]
+pub enum MutationKind<'a> {
+    /// Replaces the node with `&node`
+    PosPred,
+    /// Replaces the node with `!node`
+    NegPred,
+    /// Replaces the node with `node?`
+    Opt,
+    /// Replaces the node with `node*`
+    Rep,
+    /// Replaces the node with `node+`
+    RepOnce,
+    /// Replaces the node with `node{n}`
+    RepExact(u8),
+    /// Replaces the node with `node{min, max}`
+    RepMinMax(u8, u8),
+    /// Replaces the node with `PUSH(node)`
+    Push,
+    /// Replaces the node with `node ~ node`
+    Duplicate,
+    /// Replaces the node with `node | string`
+    Choice(&'a str),
+    /// Replaces a sequence or choice with one of its sides
+    Unwrap(bool),
+    /// Swaps the sides of a sequence or choice
+    Swap,
+    /// Replaces the node with a string
+    Str(&'a str),
+    /// Replaces the node with a reference to the rule at the given index
+    Ident(usize),
+    /// Changes the type of the rule, as an index into all rule types
+    RuleType(u8),
+}
+
+/// Applies `data.mutations` to a seed grammar, checks that the printed result parses back to the
+/// same rules, and, if the result is valid, parses `data.input` with every one of its rules.
+pub fn grammar_mutations(data: MutationInput<'_>) {
+    let seed = SEED_GRAMMARS[data.seed as usize % SEED_GRAMMARS.len()];
+    let mut rules = parse_rules(seed).expect("invalid seed grammar");
+
+    for mutation in &data.mutations {
+        mutate(&mut rules, mutation);
+    }
+
+    let grammar = print(&rules);
+    if let Some(reparsed) = parse_rules(&grammar) {
+        assert_eq!(reparsed, rules, "printed grammar:\n{}", grammar);
+    }
+
+    if let Ok((_, rules)) = pest_meta::parse_and_optimize(&grammar) {
+        run_vm(rules, data.input);
+    }
+}
+
+/// Prints `rules` as a grammar, parenthesizing every compound expression.
+pub fn print(rules: &[Rule]) -> String {
+    rules
+        .iter()
+        .map(|rule| {
+            let modifier = match rule.ty {
+                AstRuleType::Normal => "",
+                AstRuleType::Silent => "_",
+                AstRuleType::Atomic => "@",
+                AstRuleType::CompoundAtomic => "$",
+                AstRuleType::NonAtomic => "!",
+            };
+
+            format!(
+                "{} = {}{{ {} }}\n",
+                rule.name,
+                modifier,
+                print_expr(&rule.expr)
+            )
+        })
+        .collect()
+}
+
+fn print_expr(expr: &Expr) -> String {
+    match expr {
+        Expr::Str(string) => quote(string, '"'),
+        Expr::Insens(string) => format!("^{}", quote(string, '"')),
+        Expr::Range(start, end) => format!("{}..{}", quote(start, '\''), quote(end, '\'')),
+        Expr::Ident(name) => name.clone(),
+        Expr::PeekSlice(start, end) => match end {
+            Some(end) => format!("PEEK[{}..{}]", start, end),
+            None => format!("PEEK[{}..]", start),
+        },
+        Expr::PosPred(expr) => format!("&({})", print_expr(expr)),
+        Expr::NegPred(expr) => format!("!({})", print_expr(expr)),
+        Expr::PosLookbehind(expr) => format!("<&({})", print_expr(expr)),
+        Expr::Seq(lhs, rhs) => format!("({} ~ {})", print_expr(lhs), print_expr(rhs)),
+        Expr::Choice(lhs, rhs) => format!("({} | {})", print_expr(lhs), print_expr(rhs)),
+        Expr::Opt(expr) => format!("({})?", print_expr(expr)),
+        Expr::Rep(expr) => format!("({})*", print_expr(expr)),
+        Expr::RepOnce(expr) => format!("({})+", print_expr(expr)),
+        Expr::RepExact(expr, n) => format!("({}){{{}}}", print_expr(expr), n),
+        Expr::RepMin(expr, min) => format!("({}){{{},}}", print_expr(expr), min),
+        Expr::RepMax(expr, max) => format!("({}){{,{}}}", print_expr(expr), max),
+        Expr::RepMinMax(expr, min, max) => {
+            format!("({}){{{}, {}}}", print_expr(expr), min, max)
+        }
+        Expr::Skip(strings) => {
+            let strings: Vec<_> = strings.iter().map(|string| quote(string, '"')).collect();
+            format!("(!({}) ~ ANY)*", strings.join(" | "))
+        }
+        Expr::Push(expr) => format!("PUSH({})", print_expr(expr)),
+        Expr::Labeled(label, expr) => format!("#{} ({})", quote(label, '"'), print_expr(expr)),
+    }
+}
+
+fn quote(string: &str, delimiter: char) -> String {
+    let mut quoted = String::new();
+    quoted.push(delimiter);
+
+    for c in string.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\'' => quoted.push_str("\\'"),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            ' '..='~' => quoted.push(c),
+            c => quoted.push_str(&format!("\\u{{{:04x}}}", c as u32)),
+        }
+    }
+
+    quoted.push(delimiter);
+    quoted
+}
+
+fn mutate(rules: &mut [Rule], mutation: &Mutation<'_>) {
+    if rules.is_empty() {
+        return;
+    }
+
+    let names: Vec<String> = rules.iter().map(|rule| rule.name.clone()).collect();
+    let rule = &mut rules[mutation.rule % names.len()];
+
+    if let MutationKind::RuleType(ty) = mutation.kind {
+        rule.ty = match ty % 5 {
+            0 => AstRuleType::Normal,
+            1 => AstRuleType::Silent,
+            2 => AstRuleType::Atomic,
+            3 => AstRuleType::CompoundAtomic,
+            _ => AstRuleType::NonAtomic,
+        };
+        return;
+    }
+
+    let count = rule.expr.iter_top_down().count();
+    let node = match nth_mut(&mut rule.expr, &mut (mutation.node % count)) {
+        Some(node) => node,
+        None => return,
+    };
+    let old = mem::replace(node, Expr::Str(String::new()));
+    let boxed = || Box::new(old.clone());
+
+    *node = match mutation.kind {
+        MutationKind::PosPred => Expr::PosPred(boxed()),
+        MutationKind::NegPred => Expr::NegPred(boxed()),
+        MutationKind::Opt => Expr::Opt(boxed()),
+        MutationKind::Rep => Expr::Rep(boxed()),
+        MutationKind::RepOnce => Expr::RepOnce(boxed()),
+        MutationKind::RepExact(n) => Expr::RepExact(boxed(), n as u32),
+        MutationKind::RepMinMax(min, max) => Expr::RepMinMax(boxed(), min as u32, max as u32),
+        MutationKind::Push => Expr::Push(boxed()),
+        MutationKind::Duplicate => Expr::Seq(boxed(), boxed()),
+        MutationKind::Choice(string) => {
+            Expr::Choice(boxed(), Box::new(Expr::Str(string.to_owned())))
+        }
+        MutationKind::Unwrap(left) => match old {
+            Expr::Seq(lhs, rhs) | Expr::Choice(lhs, rhs) => *if left { lhs } else { rhs },
+            old => old,
+        },
+        MutationKind::Swap => match old {
+            Expr::Seq(lhs, rhs) => Expr::Seq(rhs, lhs),
+            Expr::Choice(lhs, rhs) => Expr::Choice(rhs, lhs),
+            old => old,
+        },
+        MutationKind::Str(string) => Expr::Str(string.to_owned()),
+        MutationKind::Ident(index) => Expr::Ident(names[index % names.len()].clone()),
+        MutationKind::RuleType(_) => unreachable!(),
+    };
+}
+
+// Returns the node at index `n` of `expr`, in the order of `Expr::iter_top_down`.
+fn nth_mut<'a>(expr: &'a mut Expr, n: &mut usize) -> Option<&'a mut Expr> {
+    if *n == 0 {
+        return Some(expr);
+    }
+    *n -= 1;
+
+    match expr {
+        Expr::Seq(lhs, rhs) | Expr::Choice(lhs, rhs) => {
+            if let Some(node) = nth_mut(lhs, n) {
+                return Some(node);
+            }
+            nth_mut(rhs, n)
+        }
+        Expr::PosPred(expr)
+        | Expr::NegPred(expr)
+        | Expr::PosLookbehind(expr)
+        | Expr::Opt(expr)
+        | Expr::Rep(expr)
+        | Expr::RepOnce(expr)
+        | Expr::RepExact(expr, _)
+        | Expr::RepMin(expr, _)
+        | Expr::RepMax(expr, _)
+        | Expr::RepMinMax(expr, ..)
+        | Expr::Push(expr)
+        | Expr::Labeled(_, expr) => nth_mut(expr, n),
+        _ => None,
+    }
+}
+
+fn parse_rules(grammar: &str) -> Option<Vec<Rule>> {
+    let pairs = parser::parse(GrammarRule::grammar_rules, grammar).ok()?;
+    parser::consume_rules(pairs).ok()
+}
+
+fn run_vm(rules: Vec<OptimizedRule>, input: &str) {
+    let names: Vec<String> = rules.iter().map(|rule| rule.name.clone()).collect();
+    let vm = Vm::new(rules);
+
+    with_call_limit(|| {
+        for name in &names {
+            check(vm.parse(name, input));
+        }
+    });
+}
+
+fn check<R: RuleType>(result: Result<Pairs<'_, R>, Error<R>>) {
+    match result {
+        Ok(pairs) => {
+            for pair in pairs.flatten() {
+                let _ = pair.as_str();
+                let _ = pair.as_span().start_pos().line_col();
+            }
+        }
+        Err(error) => {
+            let _ = error.to_string();
+        }
+    }
+}
+
+// The call limit is global, so it is lifted again right after `f`, before e.g. parsing grammars.
+fn with_call_limit<F: FnOnce()>(f: F) {
+    struct Lift;
+
+    impl Drop for Lift {
+        fn drop(&mut self) {
+            pest::set_call_limit(None);
+        }
+    }
+
+    pest::set_call_limit(NonZeroUsize::new(CALL_LIMIT));
+    let _lift = Lift;
+    f();
+}
+
+fn boundary(input: &str, pos: usize) -> usize {
+    let mut pos = pos % (input.len() + 1);
+    while !input.is_char_boundary(pos) {
+        pos -= 1;
+    }
+    pos
+}
+
+fn ordered(a: usize, b: usize) -> (usize, usize) {
+    if a <= b {
+        (a, b)
+    } else {
+        (b, a)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    // Tests run in parallel, while the call limit is global.
+    static CALL_LIMIT_LOCK: Mutex<()> = Mutex::new(());
+
+    #[test]
+    fn seeds_round_trip() {
+        let _lock = CALL_LIMIT_LOCK
+            .lock()
+            .unwrap_or_else(|error| error.into_inner());
+        for seed in SEED_GRAMMARS {
+            let rules = parse_rules(seed).unwrap();
+            assert_eq!(parse_rules(&print(&rules)).unwrap(), rules);
+        }
+    }
+
+    #[test]
+    fn quotes() {
+        assert_eq!(
+            quote("a\"'\\\n\u{1}é", '"'),
+            r#""a\"\'\\\n\u{0001}\u{00e9}""#
+        );
+    }
+
+    #[test]
+    fn corpus() {
+        let _lock = CALL_LIMIT_LOCK
+            .lock()
+            .unwrap_or_else(|error| error.into_inner());
+        meta(b"a = { b ~ (\"c\" | 'a'..'z')* }\nb = { PUSH(\"(\") ~ POP }");
+        meta(b"a = { a }");
+        generated(b"{\"a\": [1, 2.5e3, true, null, \"\\u00e9\"]}");
+        generated(&[b'['; 4096]);
+        vm(VmInput {
+            grammar: "a = { (\"a\"* ~ \"a\"*)* ~ \"b\" }",
+            input: &"a".repeat(64),
+        });
+        error_format(ErrorInput {
+            input: "(a\u{301}\t\"\r\né😀",
+            start: 7,
+            end: Some(2),
+            positives: 2,
+            path: Some("file"),
+            column_mode: 1,
+            snippet_width: Some(1),
+            labels: vec![(0, 1), (usize::MAX, 3)],
+        });
+        error_format(ErrorInput {
+            input: "",
+            start: 3,
+            end: None,
+            positives: 0,
+            path: None,
+            column_mode: 2,
+            snippet_width: Some(0),
+            labels: vec![(0, 0)],
+        });
+    }
+
+    #[test]
+    fn mutations() {
+        let _lock = CALL_LIMIT_LOCK
+            .lock()
+            .unwrap_or_else(|error| error.into_inner());
+        for seed in 0..SEED_GRAMMARS.len() as u8 {
+            let mutations = (0..16)
+                .map(|i| Mutation {
+                    rule: i * 7,
+                    node: i * 13,
+                    kind: match i % 8 {
+                        0 => MutationKind::Duplicate,
+                        1 => MutationKind::Swap,
+                        2 => MutationKind::Str("\u{e9}\"x"),
+                        3 => MutationKind::RepMinMax(2, 1),
+                        4 => MutationKind::Ident(i),
+                        5 => MutationKind::Unwrap(true),
+                        6 => MutationKind::Choice("a"),
+                        _ => MutationKind::RuleType(i as u8),
+                    },
+                })
+                .collect();
+
+            grammar_mutations(MutationInput {
+                seed,
+                mutations,
+                input: "GET / HTTP/1.1\r\n{\"a\" = [1]}",
+            });
+        }
+    }
+}
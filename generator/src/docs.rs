@@ -0,0 +1,119 @@
+// pest. The Elegant Parser
+// Copyright (c) 2018 Dragoș Tiselice
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+use proc_macro2::TokenStream;
+use syn::Ident;
+
+use pest_meta::metrics;
+use pest_meta::parser::{self, Rule};
+
+/// Generates the module requested by `#[doc_module = "..."]`, whose documentation lists every
+/// rule of the grammar.
+pub fn generate_doc_module(module: &Ident, parser: &Ident, grammar: &str) -> TokenStream {
+    let doc = render(&parser.to_string(), grammar);
+
+    quote! {
+        #[doc = #doc]
+        pub mod #module {}
+    }
+}
+
+// Renders the grammar as Markdown: its doc comments, then a section per rule with the rule's doc
+// comments, its definition and links to the rules referencing it.
+fn render(parser: &str, grammar: &str) -> String {
+    // The grammar has already been validated by the time it is documented.
+    let pairs = parser::parse(Rule::grammar_rules, grammar).expect("invalid grammar");
+    let rules = parser::consume_rules_with_spans(pairs.clone()).expect("invalid grammar");
+    let ast = parser::consume_rules(pairs.clone()).expect("invalid grammar");
+    let metrics = metrics::metrics(&ast);
+
+    let mut doc = format!("The grammar of [`{}`].\n", parser);
+    let grammar_docs = parser::consume_grammar_docs(pairs.clone());
+    if !grammar_docs.is_empty() {
+        doc.push('\n');
+        doc.push_str(&grammar_docs.join("\n"));
+        doc.push('\n');
+    }
+    doc.push_str("\n# Rules\n");
+
+    let definitions = pairs
+        .filter(|pair| pair.as_rule() == Rule::grammar_rule)
+        .map(|pair| {
+            let definition = pair.as_str().trim();
+            let name = pair.into_inner().next().unwrap().as_str();
+            (name, definition)
+        });
+
+    for (name, definition) in definitions {
+        doc.push_str(&format!("\n## `{}`\n\n", name));
+
+        let docs = rules
+            .iter()
+            .find(|rule| rule.name == name)
+            .map_or(&[][..], |rule| rule.docs());
+        if !docs.is_empty() {
+            doc.push_str(&docs.join("\n"));
+            doc.push_str("\n\n");
+        }
+
+        doc.push_str(&format!("```text\n{}\n```\n", definition));
+
+        let references: Vec<_> = metrics
+            .iter()
+            .find(|metrics| metrics.name == name)
+            .map_or(&[][..], |metrics| &metrics.references_in[..])
+            .iter()
+            .map(|rule| format!("[`{}`](#{})", rule, rule.to_lowercase()))
+            .collect();
+        if !references.is_empty() {
+            doc.push_str(&format!("\nReferenced by {}.\n", references.join(", ")));
+        }
+    }
+
+    doc
+}
+
+#[cfg(test)]
+mod tests {
+    use super::render;
+
+    #[test]
+    fn rules() {
+        let grammar = "//! Numbers.\n\
+                       \n\
+                       /// A list of numbers.\n\
+                       list = { number ~ (\",\" ~ number)* }\n\
+                       number = @{ ASCII_DIGIT+ }\n";
+
+        assert_eq!(
+            render("NumberParser", grammar),
+            "The grammar of [`NumberParser`].\n\
+             \n\
+             Numbers.\n\
+             \n\
+             # Rules\n\
+             \n\
+             ## `list`\n\
+             \n\
+             A list of numbers.\n\
+             \n\
+             ```text\n\
+             list = { number ~ (\",\" ~ number)* }\n\
+             ```\n\
+             \n\
+             ## `number`\n\
+             \n\
+             ```text\n\
+             number = @{ ASCII_DIGIT+ }\n\
+             ```\n\
+             \n\
+             Referenced by [`list`](#list).\n"
+        );
+    }
+}
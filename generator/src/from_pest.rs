@@ -0,0 +1,319 @@
+// pest. The Elegant Parser
+// Copyright (c) 2018 Dragoș Tiselice
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+use proc_macro2::{Span, TokenStream};
+use syn::punctuated::Punctuated;
+use syn::{
+    Attribute, Data, DeriveInput, Fields, GenericParam, Ident, Lifetime, LifetimeDef, Meta,
+    NestedMeta, Path, Type, WherePredicate,
+};
+
+enum FieldKind {
+    // The span of the pair.
+    Span,
+    // The text of the pair, converted with `From<&str>`.
+    Str,
+    // The text of the pair, parsed with `FromStr`.
+    Parse,
+    // Built from the inner pairs of the pair.
+    Inner,
+}
+
+pub fn generate(ast: DeriveInput) -> TokenStream {
+    let name = &ast.ident;
+    let rule = get_rule(&ast.attrs);
+
+    let mut generics = ast.generics.clone();
+    let lifetime = match ast.generics.lifetimes().next() {
+        Some(def) => def.lifetime.clone(),
+        None => {
+            let lifetime = Lifetime::new("'pest", Span::call_site());
+            generics.params.insert(
+                0,
+                GenericParam::Lifetime(LifetimeDef::new(lifetime.clone())),
+            );
+            lifetime
+        }
+    };
+
+    let (rule_type, body) = match ast.data {
+        Data::Struct(ref data) => {
+            let rule = match rule {
+                Some(rule) => rule,
+                None => panic!("FromPest structs need a #[pest_ast(rule(Rule::name))] attribute"),
+            };
+            let body = construct(quote! { Self }, &data.fields, &rule);
+
+            (
+                rule_type(&rule),
+                quote! { ::pest::from_pairs::attempt(pairs, |pairs| #body) },
+            )
+        }
+        Data::Enum(ref data) => {
+            if data.variants.is_empty() {
+                panic!("FromPest enums need at least one variant");
+            }
+
+            let variants: Vec<_> = data
+                .variants
+                .iter()
+                .map(|variant| (variant, get_rule(&variant.attrs)))
+                .collect();
+            let first_rule = rule
+                .as_ref()
+                .or_else(|| variants.iter().filter_map(|(_, rule)| rule.as_ref()).next());
+            let rule_type = match first_rule {
+                Some(rule) => rule_type(rule),
+                None => {
+                    // Without rules, the enum can be built from the pairs of any rule type which its
+                    // variants can be built from.
+                    let rule_type = Ident::new("__R", Span::call_site());
+                    generics
+                        .params
+                        .push(syn::parse_quote! { #rule_type: ::pest::RuleType });
+                    for (variant, _) in &variants {
+                        let ty = delegated_type(&variant.fields);
+                        let predicate: WherePredicate =
+                            syn::parse_quote! { #ty: ::pest::FromPairs<#lifetime, #rule_type> };
+                        generics.make_where_clause().predicates.push(predicate);
+                    }
+                    quote! { #rule_type }
+                }
+            };
+
+            let mut bodies = variants.iter().map(|(variant, rule)| {
+                let ident = &variant.ident;
+                let body = match *rule {
+                    Some(ref rule) => construct(quote! { Self::#ident }, &variant.fields, rule),
+                    None => {
+                        let value = quote! { ::pest::FromPairs::from_pairs(pairs)? };
+                        match variant.fields {
+                            Fields::Named(ref fields) => {
+                                let field = &fields.named[0].ident;
+                                quote! { Ok(Self::#ident { #field: #value }) }
+                            }
+                            _ => quote! { Ok(Self::#ident(#value)) },
+                        }
+                    }
+                };
+                quote! { ::pest::from_pairs::attempt(pairs, |pairs| #body) }
+            });
+
+            let first = bodies.next().unwrap();
+            let rest = bodies.map(|body| {
+                quote! {
+                    let result = match result {
+                        Ok(value) => Ok(value),
+                        Err(error) => {
+                            #body.map_err(|other| ::pest::from_pairs::merge(error, other))
+                        }
+                    };
+                }
+            });
+            let choice = quote! {
+                let result = #first;
+                #(#rest)*
+            };
+
+            let body = match rule {
+                Some(ref rule) => quote! {
+                    ::pest::from_pairs::attempt(pairs, |pairs| {
+                        let mut inner = ::pest::from_pairs::next_pair(pairs, #rule)?.into_inner();
+                        let pairs = &mut inner;
+                        #choice
+                        let value = result?;
+                        ::pest::from_pairs::finish(pairs)?;
+                        Ok(value)
+                    })
+                },
+                None => quote! {
+                    #choice
+                    result
+                },
+            };
+
+            (rule_type, body)
+        }
+        Data::Union(_) => panic!("FromPest cannot be derived for unions"),
+    };
+
+    let (impl_generics, _, where_clause) = generics.split_for_impl();
+    let (_, ty_generics, _) = ast.generics.split_for_impl();
+
+    quote! {
+        impl #impl_generics ::pest::FromPairs<#lifetime, #rule_type> for #name #ty_generics
+            #where_clause
+        {
+            #[allow(clippy::perf)]
+            fn from_pairs(
+                pairs: &mut ::pest::iterators::Pairs<#lifetime, #rule_type>,
+            ) -> ::core::result::Result<Self, ::pest::error::Error<#rule_type>> {
+                #body
+            }
+        }
+    }
+}
+
+// Builds `constructor` with `fields` from the next pair, which must be a pair of `rule`.
+fn construct(constructor: TokenStream, fields: &Fields, rule: &Path) -> TokenStream {
+    let kinds: Vec<_> = fields
+        .iter()
+        .map(|field| get_field_kind(&field.attrs))
+        .collect();
+    let uses_pair = kinds.iter().any(|kind| !matches!(kind, FieldKind::Inner));
+    let uses_inner = kinds.iter().any(|kind| matches!(kind, FieldKind::Inner));
+
+    let next_pair = quote! { ::pest::from_pairs::next_pair(pairs, #rule)? };
+    let start = match (uses_pair, uses_inner) {
+        (true, true) => quote! {
+            let pair = #next_pair;
+            let mut inner = pair.clone().into_inner();
+        },
+        (false, true) => quote! { let mut inner = #next_pair.into_inner(); },
+        (true, false) => quote! { let pair = #next_pair; },
+        (false, false) => quote! { #next_pair; },
+    };
+
+    let values = kinds.iter().map(|kind| match kind {
+        FieldKind::Span => quote! { pair.as_span() },
+        FieldKind::Str => quote! { ::core::convert::From::from(pair.as_str()) },
+        FieldKind::Parse => quote! { ::pest::from_pairs::parse(&pair)? },
+        FieldKind::Inner => quote! { ::pest::FromPairs::from_pairs(&mut inner)? },
+    });
+    let value = match fields {
+        Fields::Named(fields) => {
+            let names = fields.named.iter().map(|field| &field.ident);
+            quote! { #constructor { #(#names: #values),* } }
+        }
+        Fields::Unnamed(_) => quote! { #constructor(#(#values),*) },
+        Fields::Unit => constructor,
+    };
+
+    let finish = if uses_inner {
+        quote! { ::pest::from_pairs::finish(&inner)?; }
+    } else {
+        quote! {}
+    };
+
+    quote! {
+        {
+            #start
+            let value = #value;
+            #finish
+            Ok(value)
+        }
+    }
+}
+
+// Returns the type of the single field of a variant built from the pairs themselves.
+fn delegated_type(fields: &Fields) -> &Type {
+    match fields.iter().collect::<Vec<_>>()[..] {
+        [field] if matches!(get_field_kind(&field.attrs), FieldKind::Inner) => &field.ty,
+        _ => panic!(
+            "FromPest variants without a #[pest_ast(rule(Rule::name))] attribute need a single \
+             field built from pairs"
+        ),
+    }
+}
+
+// Returns the type of the rules, e.g. `Rule` for `Rule::name`.
+fn rule_type(rule: &Path) -> TokenStream {
+    let len = rule.segments.len();
+    if len < 2 {
+        panic!("rule attribute must be a path to a rule, e.g. `rule(Rule::name)`");
+    }
+
+    let path = Path {
+        leading_colon: rule.leading_colon,
+        segments: rule
+            .segments
+            .iter()
+            .take(len - 1)
+            .cloned()
+            .collect::<Punctuated<_, _>>(),
+    };
+    quote! { #path }
+}
+
+fn pest_ast_items(attrs: &[Attribute]) -> Vec<NestedMeta> {
+    attrs
+        .iter()
+        .filter(|attr| attr.path.is_ident("pest_ast"))
+        .flat_map(|attr| match attr.parse_meta() {
+            Ok(Meta::List(list)) => list.nested,
+            _ => panic!("pest_ast attribute must be of the form `pest_ast(...)`"),
+        })
+        .collect()
+}
+
+fn get_rule(attrs: &[Attribute]) -> Option<Path> {
+    pest_ast_items(attrs)
+        .into_iter()
+        .filter_map(|item| match item {
+            NestedMeta::Meta(Meta::List(list)) if list.path.is_ident("rule") => {
+                match list.nested.into_iter().collect::<Vec<_>>().pop() {
+                    Some(NestedMeta::Meta(Meta::Path(path))) => Some(path),
+                    _ => panic!("rule attribute must be a path to a rule, e.g. `rule(Rule::name)`"),
+                }
+            }
+            _ => None,
+        })
+        .last()
+}
+
+fn get_field_kind(attrs: &[Attribute]) -> FieldKind {
+    let mut kind = FieldKind::Inner;
+
+    for item in pest_ast_items(attrs) {
+        kind = match item {
+            NestedMeta::Meta(Meta::Path(ref path)) if path.is_ident("span") => FieldKind::Span,
+            NestedMeta::Meta(Meta::Path(ref path)) if path.is_ident("str") => FieldKind::Str,
+            NestedMeta::Meta(Meta::Path(ref path)) if path.is_ident("parse") => FieldKind::Parse,
+            _ => panic!("pest_ast attributes of fields must be `span`, `str` or `parse`"),
+        };
+    }
+
+    kind
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[should_panic(expected = "FromPest structs need a #[pest_ast(rule(Rule::name))] attribute")]
+    fn struct_without_rule() {
+        generate(syn::parse_quote! { struct Number(#[pest_ast(parse)] u32); });
+    }
+
+    #[test]
+    #[should_panic(expected = "rule attribute must be a path to a rule, e.g. `rule(Rule::name)`")]
+    fn rule_without_type() {
+        generate(syn::parse_quote! {
+            #[pest_ast(rule(number))]
+            struct Number(#[pest_ast(parse)] u32);
+        });
+    }
+
+    #[test]
+    fn generic_enum() {
+        let tokens = generate(syn::parse_quote! {
+            enum Value<'i> {
+                Number(Number),
+                Text(Text<'i>),
+            }
+        });
+
+        assert!(tokens.to_string().starts_with(
+            "impl < 'i , __R : :: pest :: RuleType > :: pest :: FromPairs < 'i , __R > for Value \
+             < 'i > where Number : :: pest :: FromPairs < 'i , __R > , Text < 'i > : :: pest :: \
+             FromPairs < 'i , __R >"
+        ));
+    }
+}
@@ -7,25 +7,91 @@
 // option. All files in the project carrying such notice may not be copied,
 // modified, or distributed except according to those terms.
 
+use std::collections::BTreeSet;
 use std::path::PathBuf;
 
-use proc_macro2::TokenStream;
+use proc_macro2::{Literal, TokenStream};
 use quote::{ToTokens, TokenStreamExt};
 use syn::{self, Generics, Ident};
 
 use pest_meta::ast::*;
+use pest_meta::children;
 use pest_meta::optimizer::*;
 use pest_meta::UNICODE_PROPERTY_NAMES;
 
+/// How the `Rule` enum is generated, as set by the attributes of the derive.
+#[derive(Default)]
+pub struct RuleEnumOptions {
+    /// Whether `Rule` is `#[non_exhaustive]`
+    pub non_exhaustive: bool,
+    /// The additional attributes of `Rule`
+    pub attributes: Vec<syn::Attribute>,
+    /// Whether the variants of silent rules are `#[doc(hidden)]`
+    pub hide_silent: bool,
+    /// The `Rule` enum of another parser of the same grammar family, which is used instead of
+    /// generating one
+    pub shared: Option<syn::Path>,
+}
+
+/// How the parses of a parser memoize the matches of rules, as set by `#[grammar_memoize]`.
+pub struct MemoOptions {
+    /// The memoized rules, or `None` for all of them
+    pub rules: Option<Vec<String>>,
+    /// The cap on the memory used by memoization, in bytes
+    pub limit: Option<usize>,
+}
+
+/// The options of a generated parser besides its rules, as collected from its grammar and the
+/// attributes of the derive.
+#[derive(Default)]
+pub struct ParserOptions {
+    /// The rules renamed after pest keywords, as their original names along with the new ones
+    pub renamed: Vec<(String, String)>,
+    /// The aliased rules along with their aliases
+    pub aliases: Vec<(String, String)>,
+    /// The `@extern` rules
+    pub externs: Vec<String>,
+    /// The rules which only get a `Rule` variant, without being matched
+    pub pruned: Vec<String>,
+    /// The left-recursive rules, which are matched by growing seeds
+    pub left_recursive: Vec<String>,
+    /// How the `Rule` enum is generated
+    pub rule_enum: RuleEnumOptions,
+    /// How the parses memoize the matches of rules, if they do
+    pub memoize: Option<MemoOptions>,
+    /// Whether the grammar was validated to only have byte terminals, so that the parser also
+    /// parses raw bytes
+    pub bytes: bool,
+}
+
 pub fn generate(
     name: Ident,
     generics: &Generics,
     path: Option<PathBuf>,
-    rules: Vec<OptimizedRule>,
+    mut rules: Vec<OptimizedRule>,
     defaults: Vec<&str>,
     include_grammar: bool,
+    options: &ParserOptions,
 ) -> TokenStream {
-    let uses_eoi = defaults.iter().any(|name| *name == "EOI");
+    let ParserOptions {
+        ref renamed,
+        ref aliases,
+        ref externs,
+        ref pruned,
+        ref left_recursive,
+        ref rule_enum,
+        ref memoize,
+        bytes,
+    } = *options;
+    let uses_eoi = defaults.contains(&"EOI");
+
+    // `@extern` rules are generated like normal rules, but are matched by the parser bound to them
+    // instead of their expression.
+    rules.extend(externs.iter().map(|name| OptimizedRule {
+        name: name.clone(),
+        ty: RuleType::Normal,
+        expr: OptimizedExpr::Str(String::new()),
+    }));
 
     let builtins = generate_builtin_rules();
     let include_fix = if include_grammar {
@@ -36,11 +102,48 @@ pub fn generate(
     } else {
         quote!()
     };
-    let rule_enum = generate_enum(&rules, uses_eoi);
-    let patterns = generate_patterns(&rules, uses_eoi);
-    let skip = generate_skip(&rules);
-
-    let mut rules: Vec<_> = rules.into_iter().map(generate_rule).collect();
+    let shared = rule_enum.shared.is_some();
+    // A shared `Rule` comes with its codes and queries, and has the variants of the other grammars
+    // of the family, which this parser does not match.
+    let (rule_enum, fallback) = match rule_enum.shared {
+        Some(ref path) => {
+            let fallback = quote! {
+                ,
+                #[allow(unreachable_patterns)]
+                _ => Err(state)
+            };
+            (quote! { pub use #path as Rule; }, fallback)
+        }
+        None => {
+            let children = pair_children(&rules, uses_eoi, aliases);
+            let rule_enum = generate_enum(&rules, uses_eoi, renamed, aliases, rule_enum);
+            let rule_codes = generate_rule_codes(&rules, uses_eoi, aliases);
+            let rule_children = generate_rule_children(&children);
+            let rule_queries = generate_rule_queries(&rules, &children, uses_eoi, aliases);
+            let rule_enum = quote! {
+                #rule_enum
+                #rule_codes
+                #rule_children
+                #rule_queries
+            };
+            (rule_enum, quote!())
+        }
+    };
+    let patterns = generate_patterns(&rules, uses_eoi, aliases, pruned);
+    let skip = generate_skip(&rules, pruned);
+    let memoize = memoize.as_ref().map_or_else(
+        || quote!(),
+        |memoize| generate_memoize(memoize, &rules, aliases),
+    );
+    let warm_up = generate_warm_up(&defaults);
+    let dyn_parser_impl =
+        generate_dyn_parser(&name, generics, &rules, uses_eoi, renamed, aliases, shared);
+
+    let mut rules: Vec<_> = rules
+        .into_iter()
+        .filter(|rule| !pruned.contains(&rule.name))
+        .map(|rule| generate_rule(rule, aliases, externs, left_recursive))
+        .collect();
     rules.extend(builtins.into_iter().filter_map(|(builtin, tokens)| {
         if defaults.contains(&builtin) {
             Some(tokens)
@@ -53,16 +156,17 @@ pub fn generate(
 
     let result = result_type();
 
+    let box_ty = box_type();
+    let vec_ty = vec_type();
+
     let parser_impl = quote! {
-        #[allow(clippy::all)]
-        impl #impl_generics ::pest::Parser<Rule> for #name #ty_generics #where_clause {
-            fn parse<'i>(
+        impl #impl_generics #name #ty_generics #where_clause {
+            #[doc(hidden)]
+            #[allow(clippy::all)]
+            fn __parse_state<'i>(
                 rule: Rule,
-                input: &'i str
-            ) -> #result<
-                ::pest::iterators::Pairs<'i, Rule>,
-                ::pest::error::Error<Rule>
-            > {
+                state: #box_ty<::pest::ParserState<'i, Rule>>
+            ) -> ::pest::ParseResult<#box_ty<::pest::ParserState<'i, Rule>>> {
                 mod rules {
                     #![allow(clippy::upper_case_acronyms)]
                     pub mod hidden {
@@ -78,19 +182,85 @@ pub fn generate(
                     pub use self::visible::*;
                 }
 
-                ::pest::state(input, |state| {
-                    match rule {
-                        #patterns
-                    }
-                })
+                #memoize
+
+                match rule {
+                    #patterns
+                    #fallback
+                }
+            }
+        }
+
+        #[allow(clippy::all)]
+        impl #impl_generics ::pest::Parser<Rule> for #name #ty_generics #where_clause {
+            fn parse<'i>(
+                rule: Rule,
+                input: &'i str
+            ) -> #result<
+                ::pest::iterators::Pairs<'i, Rule>,
+                ::pest::error::Error<Rule>
+            > {
+                <Self as ::pest::Parser<Rule>>::parse_head(rule, input)
+                    .map_err(|head| head.into_error(input))
+            }
+
+            fn parse_head<'i>(
+                rule: Rule,
+                input: &'i str
+            ) -> #result<
+                ::pest::iterators::Pairs<'i, Rule>,
+                ::pest::error::ErrorHead<Rule>
+            > {
+                ::pest::state_head(input, |state| Self::__parse_state(rule, state))
+            }
+
+            fn parse_state<'i>(
+                rule: Rule,
+                state: #box_ty<::pest::ParserState<'i, Rule>>
+            ) -> ::pest::ParseResult<#box_ty<::pest::ParserState<'i, Rule>>> {
+                Self::__parse_state(rule, state)
+            }
+
+            fn parse_recoverable<'i>(
+                rule: Rule,
+                input: &'i str,
+                recovery: &::pest::Recovery<Rule>
+            ) -> (
+                ::pest::iterators::Pairs<'i, Rule>,
+                #vec_ty<::pest::error::Error<Rule>>
+            ) {
+                ::pest::state_recoverable(input, recovery, |state| Self::__parse_state(rule, state))
+            }
+
+            fn parse_tolerant<'i>(
+                rule: Rule,
+                input: &'i str,
+                tolerance: &::pest::Tolerance<Rule>
+            ) -> #result<
+                (::pest::iterators::Pairs<'i, Rule>, #vec_ty<::pest::Repair>),
+                ::pest::error::Error<Rule>
+            > {
+                ::pest::state_tolerant(input, tolerance, |state| Self::__parse_state(rule, state))
             }
+
+            #warm_up
+        }
+    };
+
+    let byte_parser_impl = if bytes {
+        quote! {
+            impl #impl_generics ::pest::ByteParser<Rule> for #name #ty_generics #where_clause {}
         }
+    } else {
+        quote!()
     };
 
     quote! {
         #include_fix
         #rule_enum
         #parser_impl
+        #byte_parser_impl
+        #dyn_parser_impl
     }
 }
 
@@ -111,6 +281,9 @@ fn generate_builtin_rules() -> Vec<(&'static str, TokenStream)> {
     insert_builtin!(builtins, POP, state.stack_pop());
     insert_builtin!(builtins, POP_ALL, state.stack_match_pop());
     insert_builtin!(builtins, DROP, state.stack_drop());
+    insert_builtin!(builtins, PUSH_INDENT, state.stack_push_indent());
+    insert_builtin!(builtins, MATCH_INDENT, state.stack_match_indent());
+    insert_builtin!(builtins, DEDENT, state.stack_dedent());
 
     insert_builtin!(builtins, ASCII_DIGIT, state.match_range('0'..'9'));
     insert_builtin!(builtins, ASCII_NONZERO_DIGIT, state.match_range('1'..'9'));
@@ -167,8 +340,101 @@ fn generate_builtin_rules() -> Vec<(&'static str, TokenStream)> {
     builtins
 }
 
-// Needed because Cargo doesn't watch for changes in grammars.
-fn generate_include(name: &Ident, path: &str) -> TokenStream {
+// Starts memoizing, unless the parse already does, e.g. with the options given to
+// `Parser::parse_with`. Aliased rules share the variant of their alias, so that their
+// matches cannot be told apart, and are left out.
+fn generate_memoize(
+    memoize: &MemoOptions,
+    rules: &[OptimizedRule],
+    aliases: &[(String, String)],
+) -> TokenStream {
+    let memoized: Option<Vec<Ident>> = match memoize.rules {
+        Some(ref memoized) => Some(
+            memoized
+                .iter()
+                .map(|rule| format_ident!("r#{}", rule))
+                .collect(),
+        ),
+        None if aliases.is_empty() => None,
+        None => Some(
+            rules
+                .iter()
+                .filter(|rule| {
+                    rule.ty != RuleType::Silent && alias_of(&rule.name, aliases).is_none()
+                })
+                .map(|rule| format_ident!("r#{}", rule.name))
+                .collect(),
+        ),
+    };
+    let rules = match memoized {
+        Some(rules) => quote! { #( .with_memoized_rule(Rule::#rules) )* },
+        None => quote! { .memoize() },
+    };
+    let limit = memoize
+        .limit
+        .map(|limit| quote! { .with_memo_limit(#limit) });
+
+    quote! {
+        let state = if state.memoizing() {
+            state
+        } else {
+            state.with_options(&::pest::ParseOptions::new() #rules #limit)
+        };
+    }
+}
+
+// Overrides `Parser::warm_up` to also look up the tables of the Unicode properties used by the
+// grammar, if any.
+fn generate_warm_up(defaults: &[&str]) -> TokenStream {
+    let properties: Vec<Ident> = UNICODE_PROPERTY_NAMES
+        .iter()
+        .filter(|property| defaults.contains(property))
+        .map(|property| syn::parse_str(property).unwrap())
+        .collect();
+
+    if properties.is_empty() {
+        return quote!();
+    }
+
+    quote! {
+        fn warm_up() {
+            ::pest::warm_up();
+            #( ::pest::unicode::warm_up(::pest::unicode::#properties); )*
+        }
+    }
+}
+
+/// Generates the `GRAMMAR_HASH` and `GRAMMAR_SOURCE` constants of the parser `name`, which
+/// identify the grammar `data`, read from the file `source` unless it is inline.
+pub fn generate_provenance(
+    name: &Ident,
+    generics: &Generics,
+    data: &str,
+    source: Option<&str>,
+) -> TokenStream {
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    let option = option_type();
+    let hash = Literal::u64_suffixed(pest::grammar_hash(data));
+    let source = match source {
+        Some(source) => quote! { #option::Some(#source) },
+        None => quote! { #option::None },
+    };
+
+    quote! {
+        impl #impl_generics #name #ty_generics #where_clause {
+            #[doc = "The hash of the grammar of this parser, computed by `pest::grammar_hash`."]
+            #[allow(dead_code)]
+            pub const GRAMMAR_HASH: u64 = #hash;
+            #[doc = "The path of the grammar file of this parser, or `None` for an inline grammar."]
+            #[allow(dead_code)]
+            pub const GRAMMAR_SOURCE: #option<&'static str> = #source;
+        }
+    }
+}
+
+/// Includes the grammar file `path` in the constant `_PEST_GRAMMAR_<name>`, which is needed
+/// because Cargo doesn't watch for changes in grammars.
+pub fn generate_include(name: &Ident, path: &str) -> TokenStream {
     let const_name = format_ident!("_PEST_GRAMMAR_{}", name);
     // Need to make this relative to the current directory since the path to the file
     // is derived from the CARGO_MANIFEST_DIR environment variable
@@ -181,12 +447,86 @@ fn generate_include(name: &Ident, path: &str) -> TokenStream {
     }
 }
 
-fn generate_enum(rules: &[OptimizedRule], uses_eoi: bool) -> TokenStream {
-    let rules = rules.iter().map(|rule| format_ident!("r#{}", rule.name));
+/// Generates the `Dialect` enum of the `dialects` of a grammar, along with the `parse_dialect`
+/// function of the parser `name`, or nothing if the grammar has no dialects.
+pub fn generate_dialects(name: &Ident, generics: &Generics, dialects: &[String]) -> TokenStream {
+    if dialects.is_empty() {
+        return quote!();
+    }
+
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    let idents: Vec<_> = dialects
+        .iter()
+        .map(|dialect| format_ident!("{}", dialect))
+        .collect();
+    let result = result_type();
+
+    quote! {
+        #[doc = "The dialects of the grammar, named by its `@dialect` annotations."]
+        #[allow(dead_code, non_camel_case_types, clippy::upper_case_acronyms)]
+        #[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+        pub enum Dialect {
+            #( #idents ),*
+        }
+
+        impl Dialect {
+            #[doc = "Returns the name of the dialect in the grammar."]
+            #[allow(dead_code)]
+            pub fn as_str(self) -> &'static str {
+                match self {
+                    #( Dialect::#idents => #dialects ),*
+                }
+            }
+        }
+
+        impl #impl_generics #name #ty_generics #where_clause {
+            #[doc = "Parses `input` starting from `rule` in the dialect `dialect` of the grammar."]
+            #[allow(dead_code)]
+            pub fn parse_dialect<'i>(
+                dialect: Dialect,
+                rule: Rule,
+                input: &'i str
+            ) -> #result<
+                ::pest::iterators::Pairs<'i, Rule>,
+                ::pest::error::Error<Rule>
+            > {
+                <Self as ::pest::Parser<Rule>>::parse_with_dialect(rule, input, dialect.as_str())
+            }
+        }
+    }
+}
+
+fn generate_enum(
+    rules: &[OptimizedRule],
+    uses_eoi: bool,
+    renamed: &[(String, String)],
+    aliases: &[(String, String)],
+    options: &RuleEnumOptions,
+) -> TokenStream {
+    // Aliased rules produce the pairs of their alias, so they don't get variants of their own.
+    let rules = rules
+        .iter()
+        .filter(|rule| alias_of(&rule.name, aliases).is_none())
+        .map(|rule| {
+            let variant = generate_variant(rule, renamed, aliases);
+            if options.hide_silent && rule.ty == RuleType::Silent {
+                quote! { #[doc(hidden)] #variant }
+            } else {
+                variant
+            }
+        });
+    let attributes = &options.attributes;
+    let non_exhaustive = if options.non_exhaustive {
+        quote! { #[non_exhaustive] }
+    } else {
+        quote! {}
+    };
     if uses_eoi {
         quote! {
             #[allow(dead_code, non_camel_case_types, clippy::upper_case_acronyms)]
             #[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+            #( #attributes )*
+            #non_exhaustive
             pub enum Rule {
                 EOI,
                 #( #rules ),*
@@ -196,6 +536,8 @@ fn generate_enum(rules: &[OptimizedRule], uses_eoi: bool) -> TokenStream {
         quote! {
             #[allow(dead_code, non_camel_case_types, clippy::upper_case_acronyms)]
             #[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+            #( #attributes )*
+            #non_exhaustive
             pub enum Rule {
                 #( #rules ),*
             }
@@ -203,13 +545,420 @@ fn generate_enum(rules: &[OptimizedRule], uses_eoi: bool) -> TokenStream {
     }
 }
 
-fn generate_patterns(rules: &[OptimizedRule], uses_eoi: bool) -> TokenStream {
+fn generate_variant(
+    rule: &OptimizedRule,
+    renamed: &[(String, String)],
+    aliases: &[(String, String)],
+) -> TokenStream {
+    let ident = format_ident!("r#{}", rule.name);
+    let members: Vec<_> = aliases
+        .iter()
+        .filter(|(_, alias)| *alias == rule.name)
+        .map(|(member, _)| format!("`{}`", member))
+        .collect();
+    if !members.is_empty() {
+        let doc = format!(
+            "The alias of the {} rules of the grammar.",
+            members.join(", ")
+        );
+        return quote! { #[doc = #doc] #ident };
+    }
+    match original_name(&rule.name, renamed) {
+        Some(name) => {
+            let doc = format!("The `{}` rule of the grammar, renamed in Rust.", name);
+            quote! { #[doc = #doc] #ident }
+        }
+        None => quote! { #ident },
+    }
+}
+
+// Codes follow the order of the variants, so they only change when rules are inserted, removed
+// or reordered in the grammar.
+fn generate_rule_codes(
+    rules: &[OptimizedRule],
+    uses_eoi: bool,
+    aliases: &[(String, String)],
+) -> TokenStream {
+    let mut idents: Vec<_> = rules
+        .iter()
+        .filter(|rule| alias_of(&rule.name, aliases).is_none())
+        .map(|rule| format_ident!("r#{}", rule.name))
+        .collect();
+    if uses_eoi {
+        idents.insert(0, format_ident!("EOI"));
+    }
+    assert!(
+        idents.len() <= usize::from(u16::MAX) + 1,
+        "grammars cannot have more than 65536 rules"
+    );
+    let codes: Vec<_> = (0..idents.len()).map(|code| code as u16).collect();
+    let codes = &codes;
+    let idents = &idents;
+
+    let option = option_type();
+
+    quote! {
+        #[allow(clippy::all)]
+        impl ::pest::RuleCode for Rule {
+            fn code(self) -> u16 {
+                match self {
+                    #( Rule::#idents => #codes ),*
+                }
+            }
+
+            fn from_code(code: u16) -> #option<Rule> {
+                match code {
+                    #( #codes => #option::Some(Rule::#idents), )*
+                    _ => #option::None
+                }
+            }
+        }
+    }
+}
+
+// The rules producing pairs along with their children, with aliased rules replaced by their
+// aliases.
+fn pair_children(
+    rules: &[OptimizedRule],
+    uses_eoi: bool,
+    aliases: &[(String, String)],
+) -> Vec<(String, Vec<String>)> {
+    let is_alias = |name: &str| aliases.iter().any(|(_, alias)| alias == name);
+
+    children::children(rules)
+        .into_iter()
+        .zip(rules)
+        .filter(|(_, rule)| alias_of(&rule.name, aliases).is_none())
+        .filter(|(_, rule)| rule.ty != RuleType::Silent || is_alias(&rule.name))
+        .map(|((name, children), _)| {
+            let children: BTreeSet<_> = children
+                .into_iter()
+                .filter(|child| uses_eoi || child != "EOI")
+                .map(|child| alias_of(&child, aliases).map_or(child.clone(), str::to_owned))
+                .collect();
+            (name, children.into_iter().collect())
+        })
+        .collect()
+}
+
+fn variant_ident(name: &str) -> Ident {
+    if name == "EOI" {
+        format_ident!("EOI")
+    } else {
+        format_ident!("r#{}", name)
+    }
+}
+
+// Every rule producing pairs gets an enum of the rules its pairs can directly contain, which
+// `pest::match_rule!` matches exhaustively.
+fn generate_rule_children(children: &[(String, Vec<String>)]) -> TokenStream {
+    let enums = children.iter().map(|(name, children)| {
+        let ident = variant_ident(name);
+        let doc = format!(
+            "The rules which can appear as direct children of the pairs of `{}`.",
+            name
+        );
+        let children = children.iter().map(|child| variant_ident(child));
+
+        quote! {
+            #[doc = #doc]
+            #[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+            pub enum #ident {
+                #( #children ),*
+            }
+        }
+    });
+
+    quote! {
+        /// The rules which can appear as direct children of the pairs of every rule, used by
+        /// `pest::match_rule!`.
+        #[allow(dead_code, non_camel_case_types, clippy::upper_case_acronyms)]
+        pub mod rule_children {
+            #( #enums )*
+        }
+    }
+}
+
+fn generate_rule_queries(
+    rules: &[OptimizedRule],
+    children: &[(String, Vec<String>)],
+    uses_eoi: bool,
+    aliases: &[(String, String)],
+) -> TokenStream {
+    let is_atomic = |name: &str| {
+        let ty = |name: &str| {
+            rules
+                .iter()
+                .find(|rule| rule.name == name)
+                .map(|rule| rule.ty)
+        };
+        let atomic = |ty| ty == Some(RuleType::Atomic) || ty == Some(RuleType::CompoundAtomic);
+        let members: Vec<_> = aliases
+            .iter()
+            .filter(|(_, alias)| alias == name)
+            .map(|(member, _)| member.as_str())
+            .collect();
+
+        if members.is_empty() {
+            atomic(ty(name))
+        } else {
+            members.into_iter().all(|member| atomic(ty(member)))
+        }
+    };
+
+    let is_silent = |name: &str| {
+        let alias = aliases.iter().any(|(_, alias)| alias == name);
+        !alias
+            && rules
+                .iter()
+                .any(|rule| rule.name == name && rule.ty == RuleType::Silent)
+    };
+
+    let mut names: Vec<_> = rules
+        .iter()
+        .filter(|rule| alias_of(&rule.name, aliases).is_none())
+        .map(|rule| rule.name.as_str())
+        .collect();
+    if uses_eoi {
+        names.insert(0, "EOI");
+    }
+
+    // Silent rules and `EOI` produce no pairs with children.
+    let no_children = vec![];
+    let children_of = |name: &str| {
+        children
+            .iter()
+            .find(|(parent, _)| parent == name)
+            .map_or(&no_children, |(_, children)| children)
+    };
+
+    let idents: Vec<_> = names.iter().map(|name| variant_ident(name)).collect();
+    let child_lists = names.iter().map(|name| {
+        let children = children_of(name).iter().map(|child| variant_ident(child));
+        quote! { &[#( Rule::#children ),*] }
+    });
+    let contains = names.iter().map(|name| {
+        let children = children_of(name);
+        if children.is_empty() {
+            return quote! { false };
+        }
+        let children = children.iter().map(|child| variant_ident(child));
+        quote! { matches!(other, #( Rule::#children )|*) }
+    });
+    let atomics = names.iter().map(|name| is_atomic(name));
+    let silents = names.iter().map(|name| is_silent(name));
+    let option = option_type();
+    let literals = names
+        .iter()
+        .map(|name| match rules.iter().find(|rule| rule.name == *name) {
+            Some(OptimizedRule {
+                expr: OptimizedExpr::Str(string),
+                ..
+            }) => quote! { #option::Some(#string) },
+            _ => quote! { #option::None },
+        });
+    let idents = &idents;
+
+    quote! {
+        #[allow(clippy::all)]
+        impl Rule {
+            /// Returns the rules which can appear as direct children of the pairs of the rule.
+            pub const fn possible_children(self) -> &'static [Rule] {
+                match self {
+                    #( Rule::#idents => #child_lists ),*
+                }
+            }
+
+            /// Returns whether the pairs of the rule can directly contain pairs of `other`.
+            pub const fn can_contain(self, other: Rule) -> bool {
+                match self {
+                    #( Rule::#idents => #contains ),*
+                }
+            }
+
+            /// Returns whether the rule is atomic or compound-atomic, i.e. whether it matches its
+            /// input without implicit whitespace.
+            pub const fn is_atomic(self) -> bool {
+                match self {
+                    #( Rule::#idents => #atomics ),*
+                }
+            }
+
+            /// Returns whether the rule is silent, i.e. whether it never produces pairs.
+            pub const fn is_silent(self) -> bool {
+                match self {
+                    #( Rule::#idents => #silents ),*
+                }
+            }
+
+            /// Returns the string the rule matches if it matches a single string, e.g. a keyword,
+            /// as used by `pest::error::Error::with_suggestions`.
+            pub const fn literal(self) -> #option<&'static str> {
+                match self {
+                    #( Rule::#idents => #literals ),*
+                }
+            }
+        }
+    }
+}
+
+fn generate_dyn_parser(
+    name: &Ident,
+    generics: &Generics,
+    rules: &[OptimizedRule],
+    uses_eoi: bool,
+    renamed: &[(String, String)],
+    aliases: &[(String, String)],
+    shared: bool,
+) -> TokenStream {
+    let rules: Vec<_> = rules
+        .iter()
+        .filter(|rule| alias_of(&rule.name, aliases).is_none())
+        .collect();
+    let mut idents: Vec<_> = rules
+        .iter()
+        .map(|rule| format_ident!("r#{}", rule.name))
+        .collect();
+    // The parser is used through the names of the grammar, not of the `Rule` variants.
+    let mut names: Vec<_> = rules
+        .iter()
+        .map(|rule| original_name(&rule.name, renamed).unwrap_or(&rule.name))
+        .collect();
+    if uses_eoi {
+        idents.insert(0, format_ident!("EOI"));
+        names.insert(0, "EOI");
+    }
+    let names = &names;
+    let idents = &idents;
+    // The pairs of this parser only have the variants of its own rules.
+    let fallback = if shared {
+        quote! {
+            ,
+            #[allow(unreachable_patterns)]
+            _ => unreachable!()
+        }
+    } else {
+        quote!()
+    };
+
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    let result = result_type();
+    let vec_ty = vec_type();
+
+    quote! {
+        #[allow(clippy::all)]
+        impl #impl_generics ::pest::DynParser for #name #ty_generics #where_clause {
+            fn rule_names(&self) -> #vec_ty<&str> {
+                [#( #names ),*].to_vec()
+            }
+
+            fn parse_dyn<'a, 'i>(
+                &'a self,
+                rule_name: &str,
+                input: &'i str
+            ) -> #result<
+                ::pest::dyn_parser::DynPairs<'i, 'a>,
+                ::pest::dyn_parser::DynError<'a>
+            > {
+                let rule = match rule_name {
+                    #( #names => Rule::#idents, )*
+                    _ => return #result::Err(::pest::dyn_parser::unknown_rule(rule_name, input))
+                };
+
+                ::pest::dyn_parser::into_dyn(
+                    <Self as ::pest::Parser<Rule>>::parse(rule, input),
+                    |rule| match rule {
+                        #( Rule::#idents => #names ),*
+                        #fallback
+                    }
+                )
+            }
+        }
+    }
+}
+
+/// Returns the names of the rules which cannot be reached from any of the `entry_rules`, in the
+/// order of `rules`, followed by the unreachable `externs`. `WHITESPACE` and `COMMENT` are always
+/// reachable, as well as the alias of every reachable aliased rule.
+pub fn unreachable_rules(
+    rules: &[OptimizedRule],
+    externs: &[String],
+    aliases: &[(String, String)],
+    entry_rules: &[String],
+) -> Vec<String> {
+    let mut reachable: BTreeSet<&str> = BTreeSet::new();
+    let mut pending: Vec<&str> = entry_rules.iter().map(String::as_str).collect();
+    pending.push("WHITESPACE");
+    pending.push("COMMENT");
+
+    while let Some(name) = pending.pop() {
+        if !reachable.insert(name) {
+            continue;
+        }
+        if let Some(alias) = alias_of(name, aliases) {
+            pending.push(alias);
+        }
+        if let Some(rule) = rules.iter().find(|rule| rule.name == name) {
+            pending.extend(rule.expr.iter_top_down().filter_map(|expr| {
+                match expr {
+                    OptimizedExpr::Ident(ident) => Some(
+                        rules
+                            .iter()
+                            .map(|rule| rule.name.as_str())
+                            .chain(externs.iter().map(String::as_str))
+                            .find(|name| *name == ident)?,
+                    ),
+                    _ => None,
+                }
+            }));
+        }
+    }
+
+    rules
+        .iter()
+        .map(|rule| &rule.name)
+        .chain(externs)
+        .filter(|name| !reachable.contains(name.as_str()))
+        .cloned()
+        .collect()
+}
+
+fn original_name<'a>(name: &str, renamed: &'a [(String, String)]) -> Option<&'a str> {
+    renamed
+        .iter()
+        .find(|(_, new)| new == name)
+        .map(|(original, _)| original.as_str())
+}
+
+fn alias_of<'a>(name: &str, aliases: &'a [(String, String)]) -> Option<&'a str> {
+    aliases
+        .iter()
+        .find(|(rule, _)| rule == name)
+        .map(|(_, alias)| alias.as_str())
+}
+
+// Parsing from a pruned rule fails right away, since its code was left out.
+fn generate_patterns(
+    rules: &[OptimizedRule],
+    uses_eoi: bool,
+    aliases: &[(String, String)],
+    pruned: &[String],
+) -> TokenStream {
     let mut rules: Vec<TokenStream> = rules
         .iter()
+        .filter(|rule| alias_of(&rule.name, aliases).is_none())
         .map(|rule| {
+            let pruned = pruned.contains(&rule.name);
             let rule = format_ident!("r#{}", rule.name);
-            quote! {
-                Rule::#rule => rules::#rule(state)
+            if pruned {
+                quote! {
+                    Rule::#rule => Err(state)
+                }
+            } else {
+                quote! {
+                    Rule::#rule => rules::#rule(state)
+                }
             }
         })
         .collect();
@@ -225,9 +974,23 @@ fn generate_patterns(rules: &[OptimizedRule], uses_eoi: bool) -> TokenStream {
     }
 }
 
-fn generate_rule(rule: OptimizedRule) -> TokenStream {
+fn generate_rule(
+    rule: OptimizedRule,
+    aliases: &[(String, String)],
+    externs: &[String],
+    left_recursive: &[String],
+) -> TokenStream {
     let name = format_ident!("r#{}", rule.name);
-    let expr = if rule.ty == RuleType::Atomic || rule.ty == RuleType::CompoundAtomic {
+    // The pairs of aliased rules are produced with the variant of their alias.
+    let variant = match alias_of(&rule.name, aliases) {
+        Some(alias) => format_ident!("r#{}", alias),
+        None => name.clone(),
+    };
+    let expr = if externs.contains(&rule.name) {
+        quote! {
+            state.external(Rule::#variant)
+        }
+    } else if rule.ty == RuleType::Atomic || rule.ty == RuleType::CompoundAtomic {
         generate_expr_atomic(rule.expr)
     } else if rule.name == "WHITESPACE" || rule.name == "COMMENT" {
         let atomic = generate_expr_atomic(rule.expr);
@@ -238,67 +1001,72 @@ fn generate_rule(rule: OptimizedRule) -> TokenStream {
             })
         }
     } else {
-        generate_expr(rule.expr)
-    };
-
-    let box_ty = box_type();
-
-    match rule.ty {
-        RuleType::Normal => quote! {
-            #[inline]
-            #[allow(non_snake_case, unused_variables)]
-            pub fn #name(state: #box_ty<::pest::ParserState<'_, Rule>>) -> ::pest::ParseResult<#box_ty<::pest::ParserState<'_, Rule>>> {
-                state.rule(Rule::#name, |state| {
-                    #expr
-                })
-            }
-        },
-        RuleType::Silent => quote! {
-            #[inline]
-            #[allow(non_snake_case, unused_variables)]
-            pub fn #name(state: #box_ty<::pest::ParserState<'_, Rule>>) -> ::pest::ParseResult<#box_ty<::pest::ParserState<'_, Rule>>> {
+        generate_expr(rule.expr)
+    };
+
+    let body = match rule.ty {
+        RuleType::Normal => quote! {
+            state.rule(Rule::#variant, |state| {
                 #expr
-            }
+            })
         },
+        RuleType::Silent => expr,
         RuleType::Atomic => quote! {
-            #[inline]
-            #[allow(non_snake_case, unused_variables)]
-            pub fn #name(state: #box_ty<::pest::ParserState<'_, Rule>>) -> ::pest::ParseResult<#box_ty<::pest::ParserState<'_, Rule>>> {
-                state.rule(Rule::#name, |state| {
-                    state.atomic(::pest::Atomicity::Atomic, |state| {
-                        #expr
-                    })
+            state.rule(Rule::#variant, |state| {
+                state.atomic(::pest::Atomicity::Atomic, |state| {
+                    #expr
                 })
-            }
+            })
         },
         RuleType::CompoundAtomic => quote! {
-            #[inline]
-            #[allow(non_snake_case, unused_variables)]
-            pub fn #name(state: #box_ty<::pest::ParserState<'_, Rule>>) -> ::pest::ParseResult<#box_ty<::pest::ParserState<'_, Rule>>> {
-                state.atomic(::pest::Atomicity::CompoundAtomic, |state| {
-                    state.rule(Rule::#name, |state| {
-                        #expr
-                    })
+            state.atomic(::pest::Atomicity::CompoundAtomic, |state| {
+                state.rule(Rule::#variant, |state| {
+                    #expr
                 })
-            }
+            })
         },
         RuleType::NonAtomic => quote! {
-            #[inline]
-            #[allow(non_snake_case, unused_variables)]
-            pub fn #name(state: #box_ty<::pest::ParserState<'_, Rule>>) -> ::pest::ParseResult<#box_ty<::pest::ParserState<'_, Rule>>> {
-                state.atomic(::pest::Atomicity::NonAtomic, |state| {
-                    state.rule(Rule::#name, |state| {
-                        #expr
-                    })
+            state.atomic(::pest::Atomicity::NonAtomic, |state| {
+                state.rule(Rule::#variant, |state| {
+                    #expr
                 })
-            }
+            })
         },
+    };
+    // Left-recursive rules are matched by growing a seed, which calls them again and again.
+    let body = if left_recursive.contains(&rule.name) {
+        let name = &rule.name;
+
+        quote! {
+            state.left_recursive(#name, |state| {
+                #body
+            })
+        }
+    } else {
+        body
+    };
+
+    let box_ty = box_type();
+
+    quote! {
+        #[inline]
+        #[allow(non_snake_case, unused_variables)]
+        pub fn #name(state: #box_ty<::pest::ParserState<'_, Rule>>) -> ::pest::ParseResult<#box_ty<::pest::ParserState<'_, Rule>>> {
+            #body
+        }
     }
 }
 
-fn generate_skip(rules: &[OptimizedRule]) -> TokenStream {
-    let whitespace = rules.iter().any(|rule| rule.name == "WHITESPACE");
-    let comment = rules.iter().any(|rule| rule.name == "COMMENT");
+// Pruned rules are not generated, e.g. the `WHITESPACE` of another grammar of the family, so they
+// are not skipped.
+fn generate_skip(rules: &[OptimizedRule], pruned: &[String]) -> TokenStream {
+    let defines = |name: &str| {
+        rules
+            .iter()
+            .any(|rule| rule.name == name && !pruned.contains(&rule.name))
+    };
+    let whitespace = defines("WHITESPACE");
+    let comment = defines("COMMENT");
 
     match (whitespace, comment) {
         (false, false) => generate_rule!(skip, Ok(state)),
@@ -380,6 +1148,17 @@ fn generate_expr(expr: OptimizedExpr) -> TokenStream {
                 })
             }
         }
+        OptimizedExpr::PosLookbehind(expr, width) => {
+            let expr = generate_expr_atomic(*expr);
+
+            quote! {
+                state.lookbehind(#width, |state| {
+                    state.atomic(::pest::Atomicity::Atomic, |state| {
+                        #expr
+                    })
+                })
+            }
+        }
         OptimizedExpr::NegPred(expr) => {
             let expr = generate_expr(*expr);
 
@@ -470,6 +1249,14 @@ fn generate_expr(expr: OptimizedExpr) -> TokenStream {
                 state.skip_until(&strings)
             }
         }
+        OptimizedExpr::Complement(ranges) => {
+            let starts = ranges.iter().map(|&(start, _)| start);
+            let ends = ranges.iter().map(|&(_, end)| end);
+
+            quote! {
+                state.match_char_by(|c| !matches!(c, #(#starts..=#ends)|*))
+            }
+        }
         OptimizedExpr::Push(expr) => {
             let expr = generate_expr(*expr);
 
@@ -484,6 +1271,25 @@ fn generate_expr(expr: OptimizedExpr) -> TokenStream {
                 state.restore_on_err(|state| #expr)
             }
         }
+        OptimizedExpr::Labeled(label, expr) => {
+            let expr = generate_expr(*expr);
+
+            quote! {
+                state.labeled(#label, |state| #expr)
+            }
+        }
+        OptimizedExpr::Predicate(name, expr) => {
+            let expr = generate_expr(*expr);
+            generate_predicate(&name, expr)
+        }
+        OptimizedExpr::Dialect(dialects, then, otherwise) => {
+            let then = generate_expr(*then);
+            let otherwise = generate_expr(*otherwise);
+
+            quote! {
+                state.in_dialect(&[#( #dialects ),*], |state| { #then }, |state| { #otherwise })
+            }
+        }
     }
 }
 
@@ -526,6 +1332,17 @@ fn generate_expr_atomic(expr: OptimizedExpr) -> TokenStream {
                 })
             }
         }
+        OptimizedExpr::PosLookbehind(expr, width) => {
+            let expr = generate_expr_atomic(*expr);
+
+            quote! {
+                state.lookbehind(#width, |state| {
+                    state.atomic(::pest::Atomicity::Atomic, |state| {
+                        #expr
+                    })
+                })
+            }
+        }
         OptimizedExpr::NegPred(expr) => {
             let expr = generate_expr_atomic(*expr);
 
@@ -602,6 +1419,14 @@ fn generate_expr_atomic(expr: OptimizedExpr) -> TokenStream {
                 state.skip_until(&strings)
             }
         }
+        OptimizedExpr::Complement(ranges) => {
+            let starts = ranges.iter().map(|&(start, _)| start);
+            let ends = ranges.iter().map(|&(_, end)| end);
+
+            quote! {
+                state.match_char_by(|c| !matches!(c, #(#starts..=#ends)|*))
+            }
+        }
         OptimizedExpr::Push(expr) => {
             let expr = generate_expr_atomic(*expr);
 
@@ -616,6 +1441,41 @@ fn generate_expr_atomic(expr: OptimizedExpr) -> TokenStream {
                 state.restore_on_err(|state| #expr)
             }
         }
+        OptimizedExpr::Labeled(label, expr) => {
+            let expr = generate_expr_atomic(*expr);
+
+            quote! {
+                state.labeled(#label, |state| #expr)
+            }
+        }
+        OptimizedExpr::Predicate(name, expr) => {
+            let expr = generate_expr_atomic(*expr);
+            generate_predicate(&name, expr)
+        }
+        OptimizedExpr::Dialect(dialects, then, otherwise) => {
+            let then = generate_expr_atomic(*then);
+            let otherwise = generate_expr_atomic(*otherwise);
+
+            quote! {
+                state.in_dialect(&[#( #dialects ),*], |state| { #then }, |state| { #otherwise })
+            }
+        }
+    }
+}
+
+// Calls the function `name` of the module of the parser with the input matched by `expr` and the
+// context of the parse, downcast to the type it takes.
+fn generate_predicate(name: &str, expr: TokenStream) -> TokenStream {
+    let ident = format_ident!("{}", name);
+    let message = format!(
+        "the context of the parse is not of the type taken by the predicate `{}`",
+        name
+    );
+
+    quote! {
+        state.predicate(|state| #expr, |text, context| {
+            super::super::#ident(text, context.downcast_ref().expect(#message))
+        })
     }
 }
 
@@ -639,6 +1499,14 @@ fn box_type() -> TokenStream {
     quote! { ::alloc::boxed::Box }
 }
 
+fn vec_type() -> TokenStream {
+    #[cfg(feature = "std")]
+    quote! { ::std::vec::Vec }
+
+    #[cfg(not(feature = "std"))]
+    quote! { ::alloc::vec::Vec }
+}
+
 fn result_type() -> TokenStream {
     #[cfg(feature = "std")]
     quote! { ::std::result::Result }
@@ -670,7 +1538,7 @@ mod tests {
         }];
 
         assert_eq!(
-            generate_enum(&rules, false).to_string(),
+            generate_enum(&rules, false, &[], &[], &RuleEnumOptions::default()).to_string(),
             quote! {
                 #[allow(dead_code, non_camel_case_types, clippy::upper_case_acronyms)]
                 #[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
@@ -682,6 +1550,44 @@ mod tests {
         );
     }
 
+    #[test]
+    fn rule_enum_options() {
+        let rules = vec![
+            OptimizedRule {
+                name: "f".to_owned(),
+                ty: RuleType::Normal,
+                expr: OptimizedExpr::Ident("g".to_owned()),
+            },
+            OptimizedRule {
+                name: "g".to_owned(),
+                ty: RuleType::Silent,
+                expr: OptimizedExpr::Str("g".to_owned()),
+            },
+        ];
+        let options = RuleEnumOptions {
+            non_exhaustive: true,
+            attributes: vec![syn::parse_quote! { #[repr(u8)] }],
+            hide_silent: true,
+            shared: None,
+        };
+
+        assert_eq!(
+            generate_enum(&rules, false, &[], &[], &options).to_string(),
+            quote! {
+                #[allow(dead_code, non_camel_case_types, clippy::upper_case_acronyms)]
+                #[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+                #[repr(u8)]
+                #[non_exhaustive]
+                pub enum Rule {
+                    r#f,
+                    #[doc(hidden)]
+                    r#g
+                }
+            }
+            .to_string()
+        );
+    }
+
     #[test]
     fn sequence() {
         let expr = OptimizedExpr::Seq(
@@ -804,6 +1710,19 @@ mod tests {
         );
     }
 
+    #[test]
+    fn complement() {
+        let expr = OptimizedExpr::Complement(vec![('\n', '\n'), ('a', 'z')]);
+
+        assert_eq!(
+            generate_expr_atomic(expr).to_string(),
+            quote! {
+                state.match_char_by(|c| !matches!(c, '\n'..='\n' | 'a'..='z'))
+            }
+            .to_string()
+        );
+    }
+
     #[test]
     fn skip() {
         let expr = OptimizedExpr::Skip(vec!["a".to_owned(), "b".to_owned()]);
@@ -956,6 +1875,141 @@ mod tests {
         );
     }
 
+    #[test]
+    fn unreachable() {
+        let rule = |name: &str, expr| OptimizedRule {
+            name: name.to_owned(),
+            ty: RuleType::Normal,
+            expr,
+        };
+        let ident = |name: &str| OptimizedExpr::Ident(name.to_owned());
+        let rules = vec![
+            rule(
+                "a",
+                OptimizedExpr::Seq(Box::new(ident("b")), Box::new(ident("ext"))),
+            ),
+            rule("b", ident("ANY")),
+            rule("c", ident("a")),
+            rule("d", ident("other")),
+            rule("WHITESPACE", OptimizedExpr::Str(" ".to_owned())),
+            rule("other", OptimizedExpr::Str("o".to_owned())),
+        ];
+        let externs = vec!["ext".to_owned(), "unused".to_owned()];
+        let aliases = vec![("b".to_owned(), "other".to_owned())];
+
+        assert_eq!(
+            unreachable_rules(&rules, &externs, &aliases, &["a".to_owned()]),
+            vec!["c".to_owned(), "d".to_owned(), "unused".to_owned()]
+        );
+        assert_eq!(
+            unreachable_rules(&rules, &externs, &[], &["a".to_owned()]),
+            vec![
+                "c".to_owned(),
+                "d".to_owned(),
+                "other".to_owned(),
+                "unused".to_owned()
+            ]
+        );
+    }
+
+    #[test]
+    fn provenance() {
+        let name = Ident::new("MyParser", Span::call_site());
+        let generics = Generics::default();
+
+        assert_eq!(
+            generate_provenance(&name, &generics, "", Some("grammar.pest")).to_string(),
+            quote! {
+                impl MyParser {
+                    #[doc = "The hash of the grammar of this parser, computed by `pest::grammar_hash`."]
+                    #[allow(dead_code)]
+                    pub const GRAMMAR_HASH: u64 = 14695981039346656037u64;
+                    #[doc = "The path of the grammar file of this parser, or `None` for an inline grammar."]
+                    #[allow(dead_code)]
+                    pub const GRAMMAR_SOURCE: ::std::option::Option<&'static str> =
+                        ::std::option::Option::Some("grammar.pest");
+                }
+            }
+            .to_string()
+        );
+    }
+
+    #[test]
+    fn warm_up() {
+        assert!(generate_warm_up(&["ANY", "EOI"]).is_empty());
+        assert_eq!(
+            generate_warm_up(&["ANY", "XID_START", "LETTER"]).to_string(),
+            quote! {
+                fn warm_up() {
+                    ::pest::warm_up();
+                    ::pest::unicode::warm_up(::pest::unicode::XID_START);
+                    ::pest::unicode::warm_up(::pest::unicode::LETTER);
+                }
+            }
+            .to_string()
+        );
+    }
+
+    #[test]
+    fn unreferenced_builtins() {
+        let generate_with = |expr, defaults| {
+            let rules = vec![OptimizedRule {
+                name: "a".to_owned(),
+                ty: RuleType::Normal,
+                expr,
+            }];
+            generate(
+                Ident::new("MyParser", Span::call_site()),
+                &Generics::default(),
+                None,
+                rules,
+                defaults,
+                false,
+                &ParserOptions::default(),
+            )
+            .to_string()
+        };
+
+        let plain = generate_with(OptimizedExpr::Str("a".to_owned()), vec![]);
+        assert!(!plain.contains("indent"));
+
+        let indented = generate_with(
+            OptimizedExpr::Ident("PUSH_INDENT".to_owned()),
+            vec!["PUSH_INDENT"],
+        );
+        assert!(indented.contains("stack_push_indent"));
+        assert!(!indented.contains("stack_match_indent"));
+        assert!(!indented.contains("stack_dedent"));
+    }
+
+    #[test]
+    fn byte_parser() {
+        let generate_with = |options| {
+            let rules = vec![OptimizedRule {
+                name: "a".to_owned(),
+                ty: RuleType::Normal,
+                expr: OptimizedExpr::Str("a".to_owned()),
+            }];
+            generate(
+                Ident::new("MyParser", Span::call_site()),
+                &Generics::default(),
+                None,
+                rules,
+                vec![],
+                false,
+                &options,
+            )
+            .to_string()
+        };
+
+        let bytes = ParserOptions {
+            bytes: true,
+            ..ParserOptions::default()
+        };
+        assert!(generate_with(bytes).contains("ByteParser"));
+        assert!(!generate_with(ParserOptions::default()).contains("ByteParser"));
+    }
+
     #[test]
     fn generate_complete() {
         let name = Ident::new("MyParser", Span::call_site());
@@ -977,11 +2031,13 @@ mod tests {
         let defaults = vec!["ANY"];
         let result = result_type();
         let box_ty = box_type();
+        let vec_ty = vec_type();
+        let option = option_type();
         let mut current_dir = std::env::current_dir().expect("Unable to get current directory");
         current_dir.push("test.pest");
         let test_path = current_dir.to_str().expect("path contains invalid unicode");
         assert_eq!(
-            generate(name, &generics, Some(PathBuf::from("test.pest")), rules, defaults, true).to_string(),
+            generate(name, &generics, Some(PathBuf::from("test.pest")), rules, defaults, true, &ParserOptions::default()).to_string(),
             quote! {
                 #[allow(non_upper_case_globals)]
                 const _PEST_GRAMMAR_MyParser: &'static str = include_str!(#test_path);
@@ -994,14 +2050,80 @@ mod tests {
                 }
 
                 #[allow(clippy::all)]
-                impl ::pest::Parser<Rule> for MyParser {
-                    fn parse<'i>(
+                impl ::pest::RuleCode for Rule {
+                    fn code(self) -> u16 {
+                        match self {
+                            Rule::r#a => 0u16,
+                            Rule::r#if => 1u16
+                        }
+                    }
+
+                    fn from_code(code: u16) -> #option<Rule> {
+                        match code {
+                            0u16 => #option::Some(Rule::r#a),
+                            1u16 => #option::Some(Rule::r#if),
+                            _ => #option::None
+                        }
+                    }
+                }
+
+                /// The rules which can appear as direct children of the pairs of every rule, used by
+                /// `pest::match_rule!`.
+                #[allow(dead_code, non_camel_case_types, clippy::upper_case_acronyms)]
+                pub mod rule_children {}
+
+                #[allow(clippy::all)]
+                impl Rule {
+                    /// Returns the rules which can appear as direct children of the pairs of the rule.
+                    pub const fn possible_children(self) -> &'static [Rule] {
+                        match self {
+                            Rule::r#a => &[],
+                            Rule::r#if => &[]
+                        }
+                    }
+
+                    /// Returns whether the pairs of the rule can directly contain pairs of `other`.
+                    pub const fn can_contain(self, other: Rule) -> bool {
+                        match self {
+                            Rule::r#a => false,
+                            Rule::r#if => false
+                        }
+                    }
+
+                    /// Returns whether the rule is atomic or compound-atomic, i.e. whether it matches its
+                    /// input without implicit whitespace.
+                    pub const fn is_atomic(self) -> bool {
+                        match self {
+                            Rule::r#a => false,
+                            Rule::r#if => false
+                        }
+                    }
+
+                    /// Returns whether the rule is silent, i.e. whether it never produces pairs.
+                    pub const fn is_silent(self) -> bool {
+                        match self {
+                            Rule::r#a => true,
+                            Rule::r#if => true
+                        }
+                    }
+
+                    /// Returns the string the rule matches if it matches a single string, e.g. a keyword,
+                    /// as used by `pest::error::Error::with_suggestions`.
+                    pub const fn literal(self) -> #option<&'static str> {
+                        match self {
+                            Rule::r#a => #option::Some("b"),
+                            Rule::r#if => #option::None
+                        }
+                    }
+                }
+
+                impl MyParser {
+                    #[doc(hidden)]
+                    #[allow(clippy::all)]
+                    fn __parse_state<'i>(
                         rule: Rule,
-                        input: &'i str
-                    ) -> #result<
-                        ::pest::iterators::Pairs<'i, Rule>,
-                        ::pest::error::Error<Rule>
-                    > {
+                        state: #box_ty<::pest::ParserState<'i, Rule>>
+                    ) -> ::pest::ParseResult<#box_ty<::pest::ParserState<'i, Rule>>> {
                         mod rules {
                             #![allow(clippy::upper_case_acronyms)]
                             pub mod hidden {
@@ -1039,12 +2161,93 @@ mod tests {
                             pub use self::visible::*;
                         }
 
-                        ::pest::state(input, |state| {
-                            match rule {
-                                Rule::r#a => rules::r#a(state),
-                                Rule::r#if => rules::r#if(state)
+                        match rule {
+                            Rule::r#a => rules::r#a(state),
+                            Rule::r#if => rules::r#if(state)
+                        }
+                    }
+                }
+
+                #[allow(clippy::all)]
+                impl ::pest::Parser<Rule> for MyParser {
+                    fn parse<'i>(
+                        rule: Rule,
+                        input: &'i str
+                    ) -> #result<
+                        ::pest::iterators::Pairs<'i, Rule>,
+                        ::pest::error::Error<Rule>
+                    > {
+                        <Self as ::pest::Parser<Rule>>::parse_head(rule, input)
+                            .map_err(|head| head.into_error(input))
+                    }
+
+                    fn parse_head<'i>(
+                        rule: Rule,
+                        input: &'i str
+                    ) -> #result<
+                        ::pest::iterators::Pairs<'i, Rule>,
+                        ::pest::error::ErrorHead<Rule>
+                    > {
+                        ::pest::state_head(input, |state| Self::__parse_state(rule, state))
+                    }
+
+                    fn parse_state<'i>(
+                        rule: Rule,
+                        state: #box_ty<::pest::ParserState<'i, Rule>>
+                    ) -> ::pest::ParseResult<#box_ty<::pest::ParserState<'i, Rule>>> {
+                        Self::__parse_state(rule, state)
+                    }
+
+                    fn parse_recoverable<'i>(
+                        rule: Rule,
+                        input: &'i str,
+                        recovery: &::pest::Recovery<Rule>
+                    ) -> (
+                        ::pest::iterators::Pairs<'i, Rule>,
+                        #vec_ty<::pest::error::Error<Rule>>
+                    ) {
+                        ::pest::state_recoverable(input, recovery, |state| Self::__parse_state(rule, state))
+                    }
+
+                    fn parse_tolerant<'i>(
+                        rule: Rule,
+                        input: &'i str,
+                        tolerance: &::pest::Tolerance<Rule>
+                    ) -> #result<
+                        (::pest::iterators::Pairs<'i, Rule>, #vec_ty<::pest::Repair>),
+                        ::pest::error::Error<Rule>
+                    > {
+                        ::pest::state_tolerant(input, tolerance, |state| Self::__parse_state(rule, state))
+                    }
+                }
+
+                #[allow(clippy::all)]
+                impl ::pest::DynParser for MyParser {
+                    fn rule_names(&self) -> #vec_ty<&str> {
+                        ["a", "if"].to_vec()
+                    }
+
+                    fn parse_dyn<'a, 'i>(
+                        &'a self,
+                        rule_name: &str,
+                        input: &'i str
+                    ) -> #result<
+                        ::pest::dyn_parser::DynPairs<'i, 'a>,
+                        ::pest::dyn_parser::DynError<'a>
+                    > {
+                        let rule = match rule_name {
+                            "a" => Rule::r#a,
+                            "if" => Rule::r#if,
+                            _ => return #result::Err(::pest::dyn_parser::unknown_rule(rule_name, input))
+                        };
+
+                        ::pest::dyn_parser::into_dyn(
+                            <Self as ::pest::Parser<Rule>>::parse(rule, input),
+                            |rule| match rule {
+                                Rule::r#a => "a",
+                                Rule::r#if => "if"
                             }
-                        })
+                        )
                     }
                 }
             }.to_string()
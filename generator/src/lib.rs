@@ -24,53 +24,62 @@ extern crate quote;
 use std::env;
 use std::fs::File;
 use std::io::{self, Read};
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 use proc_macro2::TokenStream;
+use syn::parse::Parser;
 use syn::{Attribute, DeriveInput, Generics, Ident, Lit, Meta};
 
 #[macro_use]
 mod macros;
+mod docs;
+mod from_pest;
 mod generator;
 
 use pest_meta::parser::{self, rename_meta_rule, Rule};
+use pest_meta::validator::{LeftRecursion, ReservedNames};
 use pest_meta::{optimizer, unwrap_or_report, validator};
 
 /// Processes the derive/proc macro input and generates the corresponding parser based
 /// on the parsed grammar. If `include_grammar` is set to true, it'll generate an explicit
 /// "include_str" statement (done in pest_derive, but turned off in the local bootstrap).
 pub fn derive_parser(input: TokenStream, include_grammar: bool) -> TokenStream {
+    derive_parser_with_passes(input, include_grammar, &[])
+}
+
+/// Same as [`derive_parser`], but runs the custom optimizer `passes` on the grammar after pest's
+/// built-in ones. This is meant for custom derive macros that wrap pest's.
+///
+/// [`derive_parser`]: fn.derive_parser.html
+pub fn derive_parser_with_passes(
+    input: TokenStream,
+    include_grammar: bool,
+    passes: &[optimizer::OptimizerPass],
+) -> TokenStream {
     let ast: DeriveInput = syn::parse2(input).unwrap();
+    let reserved = get_reserved_names(&ast.attrs);
+    let doc_module = get_doc_module(&ast.attrs);
+    let entry_rules = get_entry_rules(&ast.attrs);
+    let prune = get_prune(&ast.attrs);
+    let rule_enum = get_rule_enum_options(&ast.attrs);
+    let family = get_family(&ast.attrs);
+    let memoize = get_memoize(&ast.attrs);
+    let left_recursion = if get_bool(&ast.attrs, "left_recursion") {
+        LeftRecursion::Grow
+    } else {
+        LeftRecursion::Error
+    };
+    let bytes = get_bool(&ast.attrs, "bytes");
     let (name, generics, content) = parse_derive(ast);
+    let source = match content {
+        GrammarSource::File(ref path) => Some(path.clone()),
+        GrammarSource::Inline(_) => None,
+    };
 
     let (data, path) = match content {
         GrammarSource::File(ref path) => {
-            let root = env::var("CARGO_MANIFEST_DIR").unwrap_or_else(|_| ".".into());
-
-            // Check whether we can find a file at the path relative to the CARGO_MANIFEST_DIR
-            // first.
-            //
-            // If we cannot find the expected file over there, fallback to the
-            // `CARGO_MANIFEST_DIR/src`, which is the old default and kept for convenience
-            // reasons.
-            // TODO: This could be refactored once `std::path::absolute()` get's stabilized.
-            // https://doc.rust-lang.org/std/path/fn.absolute.html
-            let path = if Path::new(&root).join(path).exists() {
-                Path::new(&root).join(path)
-            } else {
-                Path::new(&root).join("src/").join(path)
-            };
-
-            let file_name = match path.file_name() {
-                Some(file_name) => file_name,
-                None => panic!("grammar attribute should point to a file"),
-            };
-
-            let data = match read_file(&path) {
-                Ok(data) => data,
-                Err(error) => panic!("error opening {:?}: {}", file_name, error),
-            };
-            (data, Some(path.clone()))
+            let (data, path) = read_grammar(path, "grammar");
+            (data, Some(path))
         }
         GrammarSource::Inline(content) => (content, None),
     };
@@ -80,11 +89,220 @@ pub fn derive_parser(input: TokenStream, include_grammar: bool) -> TokenStream {
         Err(error) => panic!("error parsing \n{}", error.renamed_rules(rename_meta_rule)),
     };
 
-    let defaults = unwrap_or_report(validator::validate_pairs(pairs.clone()));
+    let mut defaults = unwrap_or_report(validator::validate_pairs_with(pairs.clone(), reserved));
+    let aliases = parser::alias_names(&parser::consume_aliases(pairs.clone()));
+    let dialects = parser::consume_dialects(pairs.clone());
+    let mut externs: Vec<_> = parser::consume_externs(pairs.clone())
+        .iter()
+        .map(|span| span.as_str().to_owned())
+        .collect();
+    let left_recursive = match left_recursion {
+        LeftRecursion::Grow => validator::left_recursive_rules(&unwrap_or_report(
+            parser::consume_rules_with_spans(pairs.clone()),
+        )),
+        LeftRecursion::Error => vec![],
+    };
+    if bytes {
+        let rules = unwrap_or_report(parser::consume_rules_with_spans(pairs.clone()));
+        let errors = validator::validate_bytes(&rules);
+        unwrap_or_report(if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        });
+    }
+    let ast = unwrap_or_report(parser::consume_rules_with(pairs, left_recursion));
+    let (ast, mut renamed) = validator::rename_reserved(ast, reserved);
+
+    let rename = |name: String| match renamed.iter().find(|(original, _)| *original == name) {
+        Some((_, new)) => new.clone(),
+        None => name,
+    };
+    let mut aliases: Vec<_> = aliases
+        .into_iter()
+        .map(|(rule, alias)| (rename(rule), rename(alias)))
+        .collect();
+    let left_recursive: Vec<_> = left_recursive.into_iter().map(rename).collect();
+    let mut optimized = optimizer::optimize_with_passes(ast, passes);
+
+    let mut pruned = match entry_rules {
+        Some(entry_rules) => {
+            let entry_rules: Vec<_> = entry_rules.into_iter().map(rename).collect();
+            for rule in &entry_rules {
+                if !optimized.iter().any(|optimized| optimized.name == *rule)
+                    && !externs.contains(rule)
+                {
+                    panic!("entry rule {} is not defined", rule);
+                }
+            }
+            generator::unreachable_rules(&optimized, &externs, &aliases, &entry_rules)
+        }
+        None => vec![],
+    };
+    let memoize = memoize.map(|memoize| {
+        let rules = memoize.rules.map(|rules| {
+            let rules: Vec<_> = rules.into_iter().map(rename).collect();
+            for rule in &rules {
+                if !optimized.iter().any(|optimized| optimized.name == *rule)
+                    && !externs.contains(rule)
+                {
+                    panic!("memoized rule {} is not defined", rule);
+                }
+            }
+            rules
+        });
+        generator::MemoOptions { rules, ..memoize }
+    });
+    if prune == Prune::Enum {
+        optimized.retain(|rule| !pruned.contains(&rule.name));
+        externs.retain(|rule| !pruned.contains(rule));
+        aliases.retain(|(rule, _)| !pruned.contains(rule));
+        pruned.clear();
+    }
+
+    // `Rule` also gets the variants of the rules of the other grammars of the family, after its
+    // own, so that their parsers can share it. This parser does not match them, like pruned rules.
+    let mut family_includes = TokenStream::new();
+    for (index, path) in family.iter().enumerate() {
+        let (data, path) = read_grammar(path, "grammar_family");
+        let (rules, family_renamed, uses_eoi) = family_rules(&data, reserved);
+
+        for rule in rules {
+            if optimized
+                .iter()
+                .all(|optimized| optimized.name != rule.name)
+                && !externs.contains(&rule.name)
+                && aliases.iter().all(|(aliased, _)| *aliased != rule.name)
+            {
+                if let Some(entry) = family_renamed.iter().find(|(_, new)| *new == rule.name) {
+                    renamed.push(entry.clone());
+                }
+                pruned.push(rule.name.clone());
+                optimized.push(rule);
+            }
+        }
+        if uses_eoi && !defaults.contains(&"EOI") {
+            defaults.push("EOI");
+        }
+
+        if include_grammar {
+            let name = format_ident!("{}_FAMILY_{}", name, index);
+            family_includes.extend(generator::generate_include(
+                &name,
+                path.to_str().expect("non-Unicode path"),
+            ));
+        }
+    }
+
+    let doc_module = doc_module.map(|module| docs::generate_doc_module(&module, &name, &data));
+    let provenance = generator::generate_provenance(&name, &generics, &data, source.as_deref());
+    let dialects = generator::generate_dialects(&name, &generics, &dialects);
+
+    let options = generator::ParserOptions {
+        renamed,
+        aliases,
+        externs,
+        pruned,
+        left_recursive,
+        rule_enum,
+        memoize,
+        bytes,
+    };
+    let mut parser = generator::generate(
+        name,
+        &generics,
+        path,
+        optimized,
+        defaults,
+        include_grammar,
+        &options,
+    );
+    parser.extend(family_includes);
+    parser.extend(provenance);
+    parser.extend(dialects);
+    parser.extend(doc_module);
+    parser
+}
+
+/// Processes the input of the `FromPest` derive, generating the `pest::FromPairs` implementation
+/// of the struct or enum.
+pub fn derive_from_pest(input: TokenStream) -> TokenStream {
+    let ast: DeriveInput = syn::parse2(input).unwrap();
+    from_pest::generate(ast)
+}
+
+// Reads the grammar file at `path`, given to the `attribute` attribute, returning its content
+// along with its full path.
+fn read_grammar(path: &str, attribute: &str) -> (String, PathBuf) {
+    let root = env::var("CARGO_MANIFEST_DIR").unwrap_or_else(|_| ".".into());
+
+    // Check whether we can find a file at the path relative to the CARGO_MANIFEST_DIR
+    // first.
+    //
+    // If we cannot find the expected file over there, fallback to the
+    // `CARGO_MANIFEST_DIR/src`, which is the old default and kept for convenience
+    // reasons.
+    // TODO: This could be refactored once `std::path::absolute()` get's stabilized.
+    // https://doc.rust-lang.org/std/path/fn.absolute.html
+    let path = if Path::new(&root).join(path).exists() {
+        Path::new(&root).join(path)
+    } else {
+        Path::new(&root).join("src/").join(path)
+    };
+
+    let file_name = match path.file_name() {
+        Some(file_name) => file_name,
+        None => panic!("{} attribute should point to a file", attribute),
+    };
+
+    let data = match read_file(&path) {
+        Ok(data) => data,
+        Err(error) => panic!("error opening {:?}: {}", file_name, error),
+    };
+    (data, path)
+}
+
+// The rules of the grammar `data` of another parser of the family, which get variants in `Rule`,
+// along with its renamed rules and whether it uses `EOI`.
+fn family_rules(
+    data: &str,
+    reserved: ReservedNames,
+) -> (Vec<optimizer::OptimizedRule>, Vec<(String, String)>, bool) {
+    let pairs = match parser::parse(Rule::grammar_rules, data) {
+        Ok(pairs) => pairs,
+        Err(error) => panic!("error parsing \n{}", error.renamed_rules(rename_meta_rule)),
+    };
+
+    let defaults = unwrap_or_report(validator::validate_pairs_with(pairs.clone(), reserved));
+    let aliases = parser::alias_names(&parser::consume_aliases(pairs.clone()));
+    let externs: Vec<_> = parser::consume_externs(pairs.clone())
+        .iter()
+        .map(|span| span.as_str().to_owned())
+        .collect();
     let ast = unwrap_or_report(parser::consume_rules(pairs));
-    let optimized = optimizer::optimize(ast);
+    let (ast, renamed) = validator::rename_reserved(ast, reserved);
+
+    // Aliased rules produce the pairs of their alias, so they don't get variants of their own.
+    let aliased: Vec<_> = aliases
+        .into_iter()
+        .map(
+            |(rule, _)| match renamed.iter().find(|(original, _)| *original == rule) {
+                Some((_, new)) => new.clone(),
+                None => rule,
+            },
+        )
+        .collect();
+    let mut rules: Vec<_> = optimizer::optimize(ast)
+        .into_iter()
+        .filter(|rule| !aliased.contains(&rule.name))
+        .collect();
+    rules.extend(externs.into_iter().map(|name| optimizer::OptimizedRule {
+        name,
+        ty: pest_meta::ast::RuleType::Normal,
+        expr: optimizer::OptimizedExpr::Str(String::new()),
+    }));
 
-    generator::generate(name, &generics, path, optimized, defaults, include_grammar)
+    (rules, renamed, defaults.contains(&"EOI"))
 }
 
 fn read_file<P: AsRef<Path>>(path: P) -> io::Result<String> {
@@ -140,10 +358,222 @@ fn get_attribute(attr: &Attribute) -> GrammarSource {
     }
 }
 
+fn get_reserved_names(attrs: &[Attribute]) -> ReservedNames {
+    let mut reserved = attrs.iter().filter_map(|attr| match attr.parse_meta() {
+        Ok(Meta::NameValue(name_value)) if name_value.path.is_ident("reserved_names") => {
+            Some(name_value.lit)
+        }
+        _ => None,
+    });
+
+    match reserved.next() {
+        None => ReservedNames::default(),
+        Some(Lit::Str(string)) if string.value() == "error" => ReservedNames::Error,
+        Some(Lit::Str(string)) if string.value() == "rename" => ReservedNames::Rename,
+        Some(_) => panic!("reserved_names attribute must be either \"error\" or \"rename\""),
+    }
+}
+
+fn get_doc_module(attrs: &[Attribute]) -> Option<Ident> {
+    let mut modules = attrs.iter().filter_map(|attr| match attr.parse_meta() {
+        Ok(Meta::NameValue(name_value)) if name_value.path.is_ident("doc_module") => {
+            Some(name_value.lit)
+        }
+        _ => None,
+    });
+
+    match modules.next() {
+        None => None,
+        Some(Lit::Str(string)) => match syn::parse_str(&string.value()) {
+            Ok(module) => Some(module),
+            Err(_) => panic!("doc_module attribute must be a module name"),
+        },
+        Some(_) => panic!("doc_module attribute must be a string"),
+    }
+}
+
+fn get_entry_rules(attrs: &[Attribute]) -> Option<Vec<String>> {
+    let mut entry_rules = attrs.iter().filter_map(|attr| match attr.parse_meta() {
+        Ok(Meta::NameValue(name_value)) if name_value.path.is_ident("entry_rules") => {
+            Some(name_value.lit)
+        }
+        _ => None,
+    });
+
+    match entry_rules.next() {
+        None => None,
+        Some(Lit::Str(string)) => {
+            let rules: Vec<_> = string
+                .value()
+                .split(',')
+                .map(|rule| rule.trim().to_owned())
+                .filter(|rule| !rule.is_empty())
+                .collect();
+            if rules.is_empty() {
+                panic!("entry_rules attribute must list at least one rule");
+            }
+            Some(rules)
+        }
+        Some(_) => panic!("entry_rules attribute must be a string"),
+    }
+}
+
+/// What is left out of the generated parser for the rules which are unreachable from the entry
+/// rules.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Prune {
+    /// The code matching them, while `Rule` keeps their variants
+    Code,
+    /// Their code along with their `Rule` variants
+    Enum,
+}
+
+fn get_prune(attrs: &[Attribute]) -> Prune {
+    let mut prune = attrs.iter().filter_map(|attr| match attr.parse_meta() {
+        Ok(Meta::NameValue(name_value)) if name_value.path.is_ident("prune") => {
+            Some(name_value.lit)
+        }
+        _ => None,
+    });
+
+    match prune.next() {
+        None => Prune::Code,
+        Some(Lit::Str(string)) if string.value() == "code" => Prune::Code,
+        Some(Lit::Str(string)) if string.value() == "enum" => Prune::Enum,
+        Some(_) => panic!("prune attribute must be either \"code\" or \"enum\""),
+    }
+}
+
+fn get_family(attrs: &[Attribute]) -> Vec<String> {
+    attrs
+        .iter()
+        .filter_map(|attr| match attr.parse_meta() {
+            Ok(Meta::NameValue(name_value)) if name_value.path.is_ident("grammar_family") => {
+                match name_value.lit {
+                    Lit::Str(string) => Some(string.value()),
+                    _ => panic!("grammar_family attribute must be a string"),
+                }
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+fn get_memoize(attrs: &[Attribute]) -> Option<generator::MemoOptions> {
+    let mut memoize = attrs.iter().filter_map(|attr| match attr.parse_meta() {
+        Ok(Meta::Path(path)) if path.is_ident("grammar_memoize") => Some(None),
+        Ok(Meta::NameValue(name_value)) if name_value.path.is_ident("grammar_memoize") => {
+            Some(Some(name_value.lit))
+        }
+        _ => None,
+    });
+    let mut limits = attrs.iter().filter_map(|attr| match attr.parse_meta() {
+        Ok(Meta::NameValue(name_value)) if name_value.path.is_ident("memo_limit") => {
+            Some(name_value.lit)
+        }
+        _ => None,
+    });
+
+    let limit = match limits.next() {
+        None => None,
+        Some(Lit::Int(limit)) => match limit.base10_parse() {
+            Ok(limit) => Some(limit),
+            Err(_) => panic!("memo_limit attribute must be a number of bytes"),
+        },
+        Some(_) => panic!("memo_limit attribute must be a number of bytes"),
+    };
+
+    let rules = match memoize.next() {
+        None if limit.is_some() => panic!("memo_limit attribute requires grammar_memoize"),
+        None => return None,
+        Some(None) => None,
+        Some(Some(Lit::Str(string))) => {
+            let rules: Vec<_> = string
+                .value()
+                .split(',')
+                .map(|rule| rule.trim().to_owned())
+                .filter(|rule| !rule.is_empty())
+                .collect();
+            if rules.is_empty() {
+                panic!("grammar_memoize attribute must list at least one rule");
+            }
+            Some(rules)
+        }
+        Some(Some(_)) => panic!("grammar_memoize attribute must be a string"),
+    };
+
+    Some(generator::MemoOptions { rules, limit })
+}
+
+fn get_bool(attrs: &[Attribute], name: &str) -> bool {
+    let mut values = attrs.iter().filter_map(|attr| match attr.parse_meta() {
+        Ok(Meta::NameValue(name_value)) if name_value.path.is_ident(name) => Some(name_value.lit),
+        _ => None,
+    });
+
+    match values.next() {
+        None => false,
+        Some(Lit::Bool(value)) => value.value,
+        Some(_) => panic!("{} attribute must be either true or false", name),
+    }
+}
+
+fn get_rule_enum_options(attrs: &[Attribute]) -> generator::RuleEnumOptions {
+    let mut attributes = attrs.iter().filter_map(|attr| match attr.parse_meta() {
+        Ok(Meta::NameValue(name_value)) if name_value.path.is_ident("rule_attributes") => {
+            Some(name_value.lit)
+        }
+        _ => None,
+    });
+
+    let attributes = match attributes.next() {
+        None => vec![],
+        Some(Lit::Str(string)) => match Attribute::parse_outer.parse_str(&string.value()) {
+            Ok(attributes) => attributes,
+            Err(_) => panic!("rule_attributes attribute must be a list of attributes"),
+        },
+        Some(_) => panic!("rule_attributes attribute must be a string"),
+    };
+
+    let mut shared = attrs.iter().filter_map(|attr| match attr.parse_meta() {
+        Ok(Meta::NameValue(name_value)) if name_value.path.is_ident("rule_enum") => {
+            Some(name_value.lit)
+        }
+        _ => None,
+    });
+
+    let shared = match shared.next() {
+        None => None,
+        Some(Lit::Str(string)) => match syn::parse_str(&string.value()) {
+            Ok(path) => Some(path),
+            Err(_) => panic!("rule_enum attribute must be the path of a Rule enum"),
+        },
+        Some(_) => panic!("rule_enum attribute must be a string"),
+    };
+
+    let options = generator::RuleEnumOptions {
+        non_exhaustive: get_bool(attrs, "non_exhaustive_rules"),
+        attributes,
+        hide_silent: get_bool(attrs, "hide_silent_rules"),
+        shared,
+    };
+    if options.shared.is_some()
+        && (options.non_exhaustive || !options.attributes.is_empty() || options.hide_silent)
+    {
+        panic!("rule_enum attribute cannot be used along with the attributes of a generated Rule");
+    }
+    options
+}
+
 #[cfg(test)]
 mod tests {
-    use super::parse_derive;
     use super::GrammarSource;
+    use super::Prune;
+    use super::{
+        get_doc_module, get_entry_rules, get_family, get_memoize, get_prune, get_reserved_names,
+        get_rule_enum_options, parse_derive,
+    };
+    use pest_meta::validator::ReservedNames;
 
     #[test]
     fn derive_inline_file() {
@@ -193,4 +623,184 @@ mod tests {
         let ast = syn::parse_str(definition).unwrap();
         parse_derive(ast);
     }
+
+    #[test]
+    fn derive_reserved_names() {
+        let definition = "
+            #[grammar = \"myfile.pest\"]
+            #[reserved_names = \"rename\"]
+            pub struct MyParser;
+        ";
+        let ast: syn::DeriveInput = syn::parse_str(definition).unwrap();
+        assert_eq!(get_reserved_names(&ast.attrs), ReservedNames::Rename);
+
+        let ast: syn::DeriveInput = syn::parse_str("pub struct MyParser;").unwrap();
+        assert_eq!(get_reserved_names(&ast.attrs), ReservedNames::Error);
+    }
+
+    #[test]
+    #[should_panic(expected = "reserved_names attribute must be either \"error\" or \"rename\"")]
+    fn derive_wrong_reserved_names() {
+        let definition = "
+            #[reserved_names = \"ignore\"]
+            pub struct MyParser;
+        ";
+        let ast: syn::DeriveInput = syn::parse_str(definition).unwrap();
+        get_reserved_names(&ast.attrs);
+    }
+
+    #[test]
+    fn derive_memoize() {
+        let ast: syn::DeriveInput =
+            syn::parse_str("#[grammar_memoize] pub struct MyParser;").unwrap();
+        let memoize = get_memoize(&ast.attrs).unwrap();
+        assert_eq!((memoize.rules, memoize.limit), (None, None));
+
+        let definition = "
+            #[grammar_memoize = \"expr, term\"]
+            #[memo_limit = 4096]
+            pub struct MyParser;
+        ";
+        let ast: syn::DeriveInput = syn::parse_str(definition).unwrap();
+        let memoize = get_memoize(&ast.attrs).unwrap();
+        assert_eq!(
+            memoize.rules,
+            Some(vec!["expr".to_owned(), "term".to_owned()])
+        );
+        assert_eq!(memoize.limit, Some(4096));
+
+        let ast: syn::DeriveInput = syn::parse_str("pub struct MyParser;").unwrap();
+        assert!(get_memoize(&ast.attrs).is_none());
+    }
+
+    #[test]
+    #[should_panic(expected = "memo_limit attribute requires grammar_memoize")]
+    fn derive_memo_limit_without_memoize() {
+        let ast: syn::DeriveInput =
+            syn::parse_str("#[memo_limit = 4096] pub struct MyParser;").unwrap();
+        get_memoize(&ast.attrs);
+    }
+
+    #[test]
+    fn derive_doc_module() {
+        let definition = "
+            #[grammar = \"myfile.pest\"]
+            #[doc_module = \"grammar\"]
+            pub struct MyParser;
+        ";
+        let ast: syn::DeriveInput = syn::parse_str(definition).unwrap();
+        assert_eq!(get_doc_module(&ast.attrs).unwrap(), "grammar");
+
+        let ast: syn::DeriveInput = syn::parse_str("pub struct MyParser;").unwrap();
+        assert_eq!(get_doc_module(&ast.attrs), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "doc_module attribute must be a module name")]
+    fn derive_wrong_doc_module() {
+        let definition = "
+            #[doc_module = \"the grammar\"]
+            pub struct MyParser;
+        ";
+        let ast: syn::DeriveInput = syn::parse_str(definition).unwrap();
+        get_doc_module(&ast.attrs);
+    }
+
+    #[test]
+    fn derive_entry_rules() {
+        let definition = "
+            #[grammar = \"myfile.pest\"]
+            #[entry_rules = \"json, value\"]
+            #[prune = \"enum\"]
+            pub struct MyParser;
+        ";
+        let ast: syn::DeriveInput = syn::parse_str(definition).unwrap();
+        assert_eq!(
+            get_entry_rules(&ast.attrs).unwrap(),
+            vec!["json".to_owned(), "value".to_owned()]
+        );
+        assert_eq!(get_prune(&ast.attrs), Prune::Enum);
+
+        let ast: syn::DeriveInput = syn::parse_str("pub struct MyParser;").unwrap();
+        assert_eq!(get_entry_rules(&ast.attrs), None);
+        assert_eq!(get_prune(&ast.attrs), Prune::Code);
+    }
+
+    #[test]
+    #[should_panic(expected = "entry_rules attribute must list at least one rule")]
+    fn derive_empty_entry_rules() {
+        let definition = "
+            #[entry_rules = \" , \"]
+            pub struct MyParser;
+        ";
+        let ast: syn::DeriveInput = syn::parse_str(definition).unwrap();
+        get_entry_rules(&ast.attrs);
+    }
+
+    #[test]
+    fn derive_rule_enum_options() {
+        let definition = "
+            #[grammar = \"myfile.pest\"]
+            #[non_exhaustive_rules = true]
+            #[rule_attributes = \"#[derive(Default)] #[repr(u8)]\"]
+            #[hide_silent_rules = false]
+            pub struct MyParser;
+        ";
+        let ast: syn::DeriveInput = syn::parse_str(definition).unwrap();
+        let options = get_rule_enum_options(&ast.attrs);
+        assert!(options.non_exhaustive);
+        assert_eq!(options.attributes.len(), 2);
+        assert!(!options.hide_silent);
+
+        let ast: syn::DeriveInput = syn::parse_str("pub struct MyParser;").unwrap();
+        let options = get_rule_enum_options(&ast.attrs);
+        assert!(!options.non_exhaustive && options.attributes.is_empty() && !options.hide_silent);
+    }
+
+    #[test]
+    fn derive_rule_enum_family() {
+        let definition = "
+            #[grammar = \"strict.pest\"]
+            #[grammar_family = \"lenient.pest\"]
+            #[grammar_family = \"legacy.pest\"]
+            pub struct StrictParser;
+        ";
+        let ast: syn::DeriveInput = syn::parse_str(definition).unwrap();
+        assert_eq!(get_family(&ast.attrs), ["lenient.pest", "legacy.pest"]);
+        assert!(get_rule_enum_options(&ast.attrs).shared.is_none());
+
+        let definition = "
+            #[grammar = \"lenient.pest\"]
+            #[rule_enum = \"super::strict::Rule\"]
+            pub struct LenientParser;
+        ";
+        let ast: syn::DeriveInput = syn::parse_str(definition).unwrap();
+        assert!(get_family(&ast.attrs).is_empty());
+        assert!(get_rule_enum_options(&ast.attrs).shared.is_some());
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "rule_enum attribute cannot be used along with the attributes of a generated Rule"
+    )]
+    fn derive_shared_rule_enum_attributes() {
+        let definition = "
+            #[rule_enum = \"super::strict::Rule\"]
+            #[non_exhaustive_rules = true]
+            pub struct LenientParser;
+        ";
+        let ast: syn::DeriveInput = syn::parse_str(definition).unwrap();
+        get_rule_enum_options(&ast.attrs);
+    }
+
+    #[test]
+    #[should_panic(expected = "rule_attributes attribute must be a list of attributes")]
+    fn derive_wrong_rule_attributes() {
+        let definition = "
+            #[rule_attributes = \"derive(Default)\"]
+            pub struct MyParser;
+        ";
+        let ast: syn::DeriveInput = syn::parse_str(definition).unwrap();
+        get_rule_enum_options(&ast.attrs);
+    }
 }
@@ -65,6 +65,9 @@ pub enum Expr {
     PosPred(Box<Expr>),
     /// Negative lookahead; matches if expression doesn't match, without making progress, e.g. `!e`
     NegPred(Box<Expr>),
+    /// Positive lookbehind; matches if the input right before the current position matches the
+    /// fixed-width expression, without making progress, e.g. `<&e`
+    PosLookbehind(Box<Expr>),
     /// Matches a sequence of two expressions, e.g. `e1 ~ e2`
     Seq(Box<Expr>, Box<Expr>),
     /// Matches either of two expressions, e.g. `e1 | e2`
@@ -87,9 +90,51 @@ pub enum Expr {
     Skip(Vec<String>),
     /// Matches an expression and pushes it to the stack, e.g. `push(e)`
     Push(Box<Expr>),
+    /// Matches an expression, labeling the errors within it for context without producing a
+    /// pair, e.g. `#"argument list" e`
+    Labeled(String, Box<Expr>),
+    /// Matches an expression, then fails unless the semantic predicate with the given name
+    /// accepts the input it matched, e.g. `#{is_type_name} e`
+    Predicate(String, Box<Expr>),
+    /// Matches the first expression when parsing one of the given dialects, and the second one
+    /// otherwise, e.g. for the definitions of a rule annotated with `@dialect(lenient)`
+    Dialect(Vec<String>, Box<Expr>, Box<Expr>),
 }
 
 impl Expr {
+    /// Returns the number of bytes matched by the expression if it is always the same, e.g. for
+    /// `"ab" ~ ('a'..'z' | "c")`. Rule references are not followed, so they have no fixed width.
+    pub fn fixed_width(&self) -> Option<usize> {
+        match self {
+            Expr::Str(string) | Expr::Insens(string) => Some(string.len()),
+            Expr::Range(start, end) => {
+                // UTF-8 widths grow with code points, so all the characters in between match too.
+                let start = start.chars().next()?.len_utf8();
+                let end = end.chars().next()?.len_utf8();
+
+                if start == end {
+                    Some(start)
+                } else {
+                    None
+                }
+            }
+            Expr::PosPred(_) | Expr::NegPred(_) | Expr::PosLookbehind(_) => Some(0),
+            Expr::Seq(lhs, rhs) => Some(lhs.fixed_width()? + rhs.fixed_width()?),
+            Expr::Choice(lhs, rhs) | Expr::Dialect(_, lhs, rhs) => {
+                let width = lhs.fixed_width()?;
+
+                if rhs.fixed_width()? == width {
+                    Some(width)
+                } else {
+                    None
+                }
+            }
+            Expr::RepExact(expr, num) => Some(expr.fixed_width()? * *num as usize),
+            Expr::Labeled(_, expr) | Expr::Predicate(_, expr) => expr.fixed_width(),
+            _ => None,
+        }
+    }
+
     /// Returns the iterator that steps the expression from top to bottom.
     pub fn iter_top_down(&self) -> ExprTopDownIterator {
         ExprTopDownIterator::new(self)
@@ -116,6 +161,10 @@ impl Expr {
                     let mapped = Box::new(map_internal(*expr, f));
                     Expr::NegPred(mapped)
                 }
+                Expr::PosLookbehind(expr) => {
+                    let mapped = Box::new(map_internal(*expr, f));
+                    Expr::PosLookbehind(mapped)
+                }
                 Expr::Seq(lhs, rhs) => {
                     let mapped_lhs = Box::new(map_internal(*lhs, f));
                     let mapped_rhs = Box::new(map_internal(*rhs, f));
@@ -158,6 +207,19 @@ impl Expr {
                     let mapped = Box::new(map_internal(*expr, f));
                     Expr::Push(mapped)
                 }
+                Expr::Labeled(label, expr) => {
+                    let mapped = Box::new(map_internal(*expr, f));
+                    Expr::Labeled(label, mapped)
+                }
+                Expr::Predicate(name, expr) => {
+                    let mapped = Box::new(map_internal(*expr, f));
+                    Expr::Predicate(name, mapped)
+                }
+                Expr::Dialect(dialects, then, otherwise) => {
+                    let mapped_then = Box::new(map_internal(*then, f));
+                    let mapped_otherwise = Box::new(map_internal(*otherwise, f));
+                    Expr::Dialect(dialects, mapped_then, mapped_otherwise)
+                }
                 expr => expr,
             }
         }
@@ -184,6 +246,10 @@ impl Expr {
                     let mapped = Box::new(map_internal(*expr, f));
                     Expr::NegPred(mapped)
                 }
+                Expr::PosLookbehind(expr) => {
+                    let mapped = Box::new(map_internal(*expr, f));
+                    Expr::PosLookbehind(mapped)
+                }
                 Expr::Seq(lhs, rhs) => {
                     let mapped_lhs = Box::new(map_internal(*lhs, f));
                     let mapped_rhs = Box::new(map_internal(*rhs, f));
@@ -226,6 +292,19 @@ impl Expr {
                     let mapped = Box::new(map_internal(*expr, f));
                     Expr::Push(mapped)
                 }
+                Expr::Labeled(label, expr) => {
+                    let mapped = Box::new(map_internal(*expr, f));
+                    Expr::Labeled(label, mapped)
+                }
+                Expr::Predicate(name, expr) => {
+                    let mapped = Box::new(map_internal(*expr, f));
+                    Expr::Predicate(name, mapped)
+                }
+                Expr::Dialect(dialects, then, otherwise) => {
+                    let mapped_then = Box::new(map_internal(*then, f));
+                    let mapped_otherwise = Box::new(map_internal(*otherwise, f));
+                    Expr::Dialect(dialects, mapped_then, mapped_otherwise)
+                }
                 expr => expr,
             };
 
@@ -262,12 +341,13 @@ impl ExprTopDownIterator {
                 self.right_branches.push(*rhs);
                 self.next = Some(*lhs);
             }
-            Expr::Choice(lhs, rhs) => {
+            Expr::Choice(lhs, rhs) | Expr::Dialect(_, lhs, rhs) => {
                 self.right_branches.push(*rhs);
                 self.next = Some(*lhs);
             }
             Expr::PosPred(expr)
             | Expr::NegPred(expr)
+            | Expr::PosLookbehind(expr)
             | Expr::Rep(expr)
             | Expr::RepOnce(expr)
             | Expr::RepExact(expr, _)
@@ -275,7 +355,9 @@ impl ExprTopDownIterator {
             | Expr::RepMax(expr, _)
             | Expr::RepMinMax(expr, ..)
             | Expr::Opt(expr)
-            | Expr::Push(expr) => {
+            | Expr::Push(expr)
+            | Expr::Labeled(_, expr)
+            | Expr::Predicate(_, expr) => {
                 self.next = Some(*expr);
             }
             _ => {
@@ -343,4 +425,38 @@ mod tests {
             expr
         );
     }
+
+    #[test]
+    fn fixed_width() {
+        let expr = Expr::Seq(
+            Box::new(Expr::RepExact(Box::new(Expr::Insens("ab".to_owned())), 3)),
+            Box::new(Expr::Choice(
+                Box::new(Expr::Range("a".to_owned(), "z".to_owned())),
+                Box::new(Expr::NegPred(Box::new(Expr::Ident("a".to_owned())))),
+            )),
+        );
+        assert_eq!(expr.fixed_width(), None);
+
+        let expr = Expr::Seq(
+            Box::new(Expr::RepExact(Box::new(Expr::Insens("ab".to_owned())), 3)),
+            Box::new(Expr::Choice(
+                Box::new(Expr::Range("a".to_owned(), "z".to_owned())),
+                Box::new(Expr::Str("é".to_owned())),
+            )),
+        );
+        assert_eq!(expr.fixed_width(), None);
+
+        let expr = Expr::Seq(
+            Box::new(Expr::RepExact(Box::new(Expr::Insens("ab".to_owned())), 3)),
+            Box::new(Expr::Choice(
+                Box::new(Expr::Range("à".to_owned(), "é".to_owned())),
+                Box::new(Expr::Str("é".to_owned())),
+            )),
+        );
+        assert_eq!(expr.fixed_width(), Some(8));
+        assert_eq!(
+            Expr::Range("a".to_owned(), "é".to_owned()).fixed_width(),
+            None
+        );
+    }
 }
@@ -0,0 +1,142 @@
+// pest. The Elegant Parser
+// Copyright (c) 2018 Dragoș Tiselice
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Analysis of the rules which can appear as direct children of the pairs of every rule.
+//!
+//! This is the data behind the `rule_children` module and the `Rule::possible_children` method
+//! generated by `pest_derive`, meant for validating parse trees or for checking that every child
+//! of a rule is handled.
+
+use std::collections::BTreeSet;
+
+use crate::ast::RuleType;
+use crate::optimizer::{OptimizedExpr, OptimizedRule};
+
+/// Returns the names of the rules which can appear as direct children of the pairs of every rule
+/// in `rules`, in the order of `rules`, with the children sorted by name.
+///
+/// The children of a rule are the non-silent rules it references directly or through silent
+/// rules, along with `EOI`. Atomic rules have no children, while silent rules get the children
+/// they add to the rules referencing them.
+///
+/// # Examples
+///
+/// ```
+/// # use pest_meta::ast::RuleType;
+/// # use pest_meta::children::children;
+/// # use pest_meta::optimizer::{OptimizedExpr, OptimizedRule};
+/// let rule = |name: &str, ty, expr| OptimizedRule {
+///     name: name.to_owned(),
+///     ty,
+///     expr,
+/// };
+/// let ident = |name: &str| Box::new(OptimizedExpr::Ident(name.to_owned()));
+///
+/// let rules = vec![
+///     rule("list", RuleType::Normal, OptimizedExpr::Seq(ident("item"), ident("EOI"))),
+///     rule("item", RuleType::Silent, OptimizedExpr::Choice(ident("a"), ident("b"))),
+///     rule("a", RuleType::Atomic, *ident("b")),
+///     rule("b", RuleType::Normal, OptimizedExpr::Str("b".to_owned())),
+/// ];
+///
+/// assert_eq!(
+///     children(&rules),
+///     vec![
+///         ("list".to_owned(), vec!["EOI".to_owned(), "a".to_owned(), "b".to_owned()]),
+///         ("item".to_owned(), vec!["a".to_owned(), "b".to_owned()]),
+///         ("a".to_owned(), vec![]),
+///         ("b".to_owned(), vec![]),
+///     ]
+/// );
+/// ```
+pub fn children(rules: &[OptimizedRule]) -> Vec<(String, Vec<String>)> {
+    rules
+        .iter()
+        .map(|rule| {
+            let mut children = BTreeSet::new();
+            if rule.ty != RuleType::Atomic {
+                collect_children(rule, rules, &mut vec![], &mut children);
+            }
+
+            (rule.name.clone(), children.into_iter().collect())
+        })
+        .collect()
+}
+
+// Collects the children of `rule`, inlining the silent rules it references.
+fn collect_children<'a>(
+    rule: &'a OptimizedRule,
+    rules: &'a [OptimizedRule],
+    visited: &mut Vec<&'a str>,
+    children: &mut BTreeSet<String>,
+) {
+    if visited.contains(&rule.name.as_str()) {
+        return;
+    }
+    visited.push(&rule.name);
+
+    for expr in rule.expr.iter_top_down() {
+        if let OptimizedExpr::Ident(name) = expr {
+            match rules.iter().find(|rule| rule.name == name) {
+                Some(rule) if rule.ty == RuleType::Silent => {
+                    collect_children(rule, rules, visited, children)
+                }
+                Some(_) => {
+                    children.insert(name);
+                }
+                None if name == "EOI" => {
+                    children.insert(name);
+                }
+                None => (),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::optimizer::optimize;
+    use crate::parser::{self, Rule};
+
+    fn children_of(grammar: &str) -> Vec<(String, Vec<String>)> {
+        let pairs = parser::parse(Rule::grammar_rules, grammar).unwrap();
+        let rules = optimize(parser::consume_rules(pairs).unwrap());
+
+        children(&rules)
+    }
+
+    #[test]
+    fn silent_and_atomic() {
+        assert_eq!(
+            children_of(r#"a = { b ~ c* ~ EOI } b = _{ c | d } c = @{ d } d = { "d" }"#),
+            vec![
+                (
+                    "a".to_owned(),
+                    vec!["EOI".to_owned(), "c".to_owned(), "d".to_owned()]
+                ),
+                ("b".to_owned(), vec!["c".to_owned(), "d".to_owned()]),
+                ("c".to_owned(), vec![]),
+                ("d".to_owned(), vec![]),
+            ]
+        );
+    }
+
+    #[test]
+    fn recursive() {
+        assert_eq!(
+            children_of(r#"a = { "(" ~ b ~ ")" } b = _{ a | "x" ~ b } c = ${ a }"#),
+            vec![
+                ("a".to_owned(), vec!["a".to_owned()]),
+                ("b".to_owned(), vec!["a".to_owned()]),
+                ("c".to_owned(), vec!["a".to_owned()]),
+            ]
+        );
+    }
+}
@@ -0,0 +1,367 @@
+// pest. The Elegant Parser
+// Copyright (c) 2018 Dragoș Tiselice
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Compatibility checks between two versions of a grammar.
+//!
+//! A change is breaking when code consuming the `Pairs` produced by the old grammar might not
+//! work with the new one, e.g. when a rule is removed or when it can produce different children.
+//! Changes to the language accepted by the grammar are not checked.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+
+use pest::error::Error;
+
+use crate::ast::{Expr, Rule, RuleType};
+use crate::parser;
+
+/// A change between two versions of a grammar, affecting the rule named `rule`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Change {
+    /// The name of the rule in the old grammar, or in the new one if it was added
+    pub rule: String,
+    /// The kind of change
+    pub kind: ChangeKind,
+}
+
+/// A kind of [`Change`].
+///
+/// [`Change`]: struct.Change.html
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum ChangeKind {
+    /// The rule was added
+    Added,
+    /// The rule was removed
+    Removed,
+    /// The rule was renamed, i.e. removed while a new rule with the same type and expression was
+    /// added
+    Renamed {
+        /// The new name of the rule
+        to: String,
+    },
+    /// The type of the rule changed, e.g. it was made silent or atomic
+    TypeChanged {
+        /// The old type of the rule
+        from: RuleType,
+        /// The new type of the rule
+        to: RuleType,
+    },
+    /// The rules which can appear as direct children of the rule's pairs changed
+    ChildrenChanged {
+        /// The rules which cannot appear as children anymore
+        removed: Vec<String>,
+        /// The rules which can now appear as children
+        added: Vec<String>,
+    },
+    /// The expression of the rule changed, while the children it can produce did not
+    ExpressionChanged,
+}
+
+impl Change {
+    /// Returns `true` if the change can break code consuming the `Pairs` of the old grammar.
+    pub fn is_breaking(&self) -> bool {
+        !matches!(self.kind, ChangeKind::Added | ChangeKind::ExpressionChanged)
+    }
+}
+
+impl fmt::Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.kind {
+            ChangeKind::Added => write!(f, "rule {} was added", self.rule),
+            ChangeKind::Removed => write!(f, "rule {} was removed", self.rule),
+            ChangeKind::Renamed { to } => write!(f, "rule {} was renamed to {}", self.rule, to),
+            ChangeKind::TypeChanged { from, to } => write!(
+                f,
+                "rule {} changed from {} to {}",
+                self.rule,
+                type_name(*from),
+                type_name(*to)
+            ),
+            ChangeKind::ChildrenChanged { removed, added } => {
+                write!(f, "children of rule {} changed", self.rule)?;
+                if !removed.is_empty() {
+                    write!(f, ", without {}", removed.join(", "))?;
+                }
+                if !added.is_empty() {
+                    write!(f, ", with {}", added.join(", "))?;
+                }
+                Ok(())
+            }
+            ChangeKind::ExpressionChanged => write!(f, "expression of rule {} changed", self.rule),
+        }
+    }
+}
+
+/// Compares the `old` and `new` rules of a grammar, returning the changes sorted by rule name.
+///
+/// Renames are detected when a removed rule has the same type and expression as an added one. The
+/// children of a rule are the non-silent rules it references directly or through silent rules,
+/// along with `EOI`. Atomic rules have no children.
+///
+/// # Examples
+///
+/// ```
+/// # use pest_meta::ast::{Expr, Rule, RuleType};
+/// # use pest_meta::compat::{compare, ChangeKind};
+/// let rule = |name: &str, ty| Rule {
+///     name: name.to_owned(),
+///     ty,
+///     expr: Expr::Str("a".to_owned()),
+/// };
+///
+/// let changes = compare(
+///     &[rule("a", RuleType::Normal)],
+///     &[rule("a", RuleType::Silent)],
+/// );
+///
+/// assert_eq!(changes[0].rule, "a");
+/// assert!(matches!(changes[0].kind, ChangeKind::TypeChanged { .. }));
+/// assert!(changes[0].is_breaking());
+/// ```
+pub fn compare(old: &[Rule], new: &[Rule]) -> Vec<Change> {
+    let old_rules = by_name(old);
+    let new_rules = by_name(new);
+    let mut changes = vec![];
+
+    let added: Vec<&Rule> = new
+        .iter()
+        .filter(|rule| !old_rules.contains_key(rule.name.as_str()))
+        .collect();
+    let mut renamed_to = BTreeSet::new();
+
+    for rule in old {
+        let new_rule = match new_rules.get(rule.name.as_str()) {
+            Some(new_rule) => new_rule,
+            None => {
+                let renamed = added.iter().find(|added| {
+                    added.ty == rule.ty
+                        && added.expr == rule.expr
+                        && !renamed_to.contains(added.name.as_str())
+                });
+                let kind = match renamed {
+                    Some(renamed) => {
+                        renamed_to.insert(renamed.name.as_str());
+                        ChangeKind::Renamed {
+                            to: renamed.name.clone(),
+                        }
+                    }
+                    None => ChangeKind::Removed,
+                };
+
+                changes.push(Change {
+                    rule: rule.name.clone(),
+                    kind,
+                });
+                continue;
+            }
+        };
+
+        if rule.ty != new_rule.ty {
+            changes.push(Change {
+                rule: rule.name.clone(),
+                kind: ChangeKind::TypeChanged {
+                    from: rule.ty,
+                    to: new_rule.ty,
+                },
+            });
+        }
+
+        // Silent rules have no pairs, so only the children of their callers matter.
+        let silent = rule.ty == RuleType::Silent || new_rule.ty == RuleType::Silent;
+        let old_children = children(rule, &old_rules);
+        let new_children = children(new_rule, &new_rules);
+        if !silent && old_children != new_children {
+            changes.push(Change {
+                rule: rule.name.clone(),
+                kind: ChangeKind::ChildrenChanged {
+                    removed: old_children.difference(&new_children).cloned().collect(),
+                    added: new_children.difference(&old_children).cloned().collect(),
+                },
+            });
+        } else if rule.expr != new_rule.expr {
+            changes.push(Change {
+                rule: rule.name.clone(),
+                kind: ChangeKind::ExpressionChanged,
+            });
+        }
+    }
+
+    for rule in added {
+        if !renamed_to.contains(rule.name.as_str()) {
+            changes.push(Change {
+                rule: rule.name.clone(),
+                kind: ChangeKind::Added,
+            });
+        }
+    }
+
+    // Sorting is stable, so the changes of each rule stay in order.
+    changes.sort_by(|a, b| a.rule.cmp(&b.rule));
+    changes
+}
+
+/// Parses the `old` and `new` versions of a grammar and compares their rules like [`compare`].
+///
+/// [`compare`]: fn.compare.html
+#[allow(clippy::perf)]
+pub fn compare_grammars(old: &str, new: &str) -> Result<Vec<Change>, Vec<Error<parser::Rule>>> {
+    let old = parse(old)?;
+    let new = parse(new)?;
+
+    Ok(compare(&old, &new))
+}
+
+#[allow(clippy::perf)]
+fn parse(grammar: &str) -> Result<Vec<Rule>, Vec<Error<parser::Rule>>> {
+    let pairs = parser::parse(parser::Rule::grammar_rules, grammar).map_err(|error| vec![error])?;
+    parser::consume_rules(pairs)
+}
+
+fn by_name(rules: &[Rule]) -> BTreeMap<&str, &Rule> {
+    rules
+        .iter()
+        .map(|rule| (rule.name.as_str(), rule))
+        .collect()
+}
+
+fn children(rule: &Rule, rules: &BTreeMap<&str, &Rule>) -> BTreeSet<String> {
+    let mut children = BTreeSet::new();
+
+    if rule.ty != RuleType::Atomic {
+        collect_children(rule, rules, &mut BTreeSet::new(), &mut children);
+    }
+
+    children
+}
+
+// Collects the children of `rule`, inlining the silent rules it references.
+fn collect_children<'a>(
+    rule: &'a Rule,
+    rules: &BTreeMap<&str, &'a Rule>,
+    visited: &mut BTreeSet<&'a str>,
+    children: &mut BTreeSet<String>,
+) {
+    if !visited.insert(rule.name.as_str()) {
+        return;
+    }
+
+    for expr in rule.expr.iter_top_down() {
+        if let Expr::Ident(name) = expr {
+            match rules.get(name.as_str()) {
+                Some(rule) if rule.ty == RuleType::Silent => {
+                    collect_children(rule, rules, visited, children)
+                }
+                Some(_) => {
+                    children.insert(name.clone());
+                }
+                None if name == "EOI" => {
+                    children.insert(name.clone());
+                }
+                None => (),
+            }
+        }
+    }
+}
+
+fn type_name(ty: RuleType) -> &'static str {
+    match ty {
+        RuleType::Normal => "normal",
+        RuleType::Silent => "silent",
+        RuleType::Atomic => "atomic",
+        RuleType::CompoundAtomic => "compound-atomic",
+        RuleType::NonAtomic => "non-atomic",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn changes(old: &str, new: &str) -> Vec<String> {
+        compare_grammars(old, new)
+            .unwrap()
+            .iter()
+            .map(|change| format!("{}{}", if change.is_breaking() { "! " } else { "" }, change))
+            .collect()
+    }
+
+    #[test]
+    fn compatible() {
+        assert_eq!(
+            changes(
+                r#"a = { b ~ c? } b = { "b" } c = { "c" }"#,
+                r#"a = { b+ ~ c* } b = { "b" | "B" } c = { "c" } d = { "d" }"#
+            ),
+            vec![
+                "expression of rule a changed",
+                "expression of rule b changed",
+                "rule d was added"
+            ]
+        );
+    }
+
+    #[test]
+    fn breaking() {
+        assert_eq!(
+            changes(
+                r#"a = { b ~ c ~ EOI } b = { "b" } c = { "c" } d = { "d" }"#,
+                r#"a = { b ~ c } b = _{ "b" } c = @{ "c" }"#
+            ),
+            vec![
+                "! children of rule a changed, without EOI, b",
+                "! rule b changed from normal to silent",
+                "! rule c changed from normal to atomic",
+                "! rule d was removed"
+            ]
+        );
+    }
+
+    #[test]
+    fn silent_children() {
+        assert_eq!(
+            changes(
+                r#"a = { s } s = _{ b | s2 } s2 = _{ "(" ~ s ~ c } b = { "b" } c = { "c" }"#,
+                r#"a = { s } s = _{ b | s2 } s2 = _{ "(" ~ s ~ "c" } b = { "b" } c = { "c" }"#
+            ),
+            vec![
+                "! children of rule a changed, without c",
+                "expression of rule s2 changed"
+            ]
+        );
+    }
+
+    #[test]
+    fn atomic_children() {
+        assert_eq!(
+            changes(
+                r#"a = { b } b = @{ c } c = { "c" }"#,
+                r#"a = { b } b = ${ c } c = { "c" }"#
+            ),
+            vec![
+                "! rule b changed from atomic to compound-atomic",
+                "! children of rule b changed, with c"
+            ]
+        );
+    }
+
+    #[test]
+    fn renamed() {
+        assert_eq!(
+            changes(
+                r#"a = { b } b = { "b" } c = { "c" }"#,
+                r#"a = { d } d = { "b" } c = { "c" } e = { "c" }"#
+            ),
+            vec![
+                "! children of rule a changed, without b, with d",
+                "! rule b was renamed to d",
+                "rule e was added"
+            ]
+        );
+    }
+}
@@ -0,0 +1,172 @@
+// pest. The Elegant Parser
+// Copyright (c) 2018 Dragoș Tiselice
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Detection of the paired delimiters used by a grammar.
+//!
+//! The detected pairs are meant to be passed to `pest::error::Error::with_unclosed_delimiter`,
+//! which points parsing errors at the innermost delimiter left open.
+
+use crate::ast::{Expr, Rule};
+
+/// Returns the pairs of opening and closing delimiters used by `rules`, in the order they first
+/// appear.
+///
+/// A pair is detected from every sequence which starts and ends with string literals, where the
+/// closing literal mirrors the opening one, e.g. `"(" ~ expr ~ ")"`, `"[" ~ "]"`, `"/*" ~ (!"*/"
+/// ~ ANY)* ~ "*/"`, or `"\"" ~ inner ~ "\""`. Literals made of anything but ASCII punctuation are
+/// never treated as delimiters.
+///
+/// # Examples
+///
+/// ```
+/// # use pest_meta::ast::{Expr, Rule, RuleType};
+/// # use pest_meta::delimiters::delimiters;
+/// let rules = vec![Rule {
+///     name: "paren".to_owned(),
+///     ty: RuleType::Normal,
+///     expr: Expr::Seq(
+///         Box::new(Expr::Str("(".to_owned())),
+///         Box::new(Expr::Seq(
+///             Box::new(Expr::Ident("paren".to_owned())),
+///             Box::new(Expr::Str(")".to_owned())),
+///         )),
+///     ),
+/// }];
+///
+/// assert_eq!(delimiters(&rules), vec![("(".to_owned(), ")".to_owned())]);
+/// ```
+pub fn delimiters(rules: &[Rule]) -> Vec<(String, String)> {
+    let mut delimiters = vec![];
+
+    for rule in rules {
+        collect(&rule.expr, &mut delimiters);
+    }
+
+    delimiters
+}
+
+fn collect(expr: &Expr, delimiters: &mut Vec<(String, String)>) {
+    match expr {
+        Expr::Seq(..) => {
+            let mut chain = vec![];
+            flatten(expr, &mut chain);
+
+            if let (Some(Expr::Str(open)), Some(Expr::Str(close))) = (chain.first(), chain.last()) {
+                let pair = (open.clone(), close.clone());
+                if chain.len() > 1 && is_mirrored(open, close) && !delimiters.contains(&pair) {
+                    delimiters.push(pair);
+                }
+            }
+
+            for expr in chain {
+                collect(expr, delimiters);
+            }
+        }
+        Expr::Choice(lhs, rhs) | Expr::Dialect(_, lhs, rhs) => {
+            collect(lhs, delimiters);
+            collect(rhs, delimiters);
+        }
+        Expr::PosPred(expr)
+        | Expr::NegPred(expr)
+        | Expr::PosLookbehind(expr)
+        | Expr::Opt(expr)
+        | Expr::Rep(expr)
+        | Expr::RepOnce(expr)
+        | Expr::RepExact(expr, _)
+        | Expr::RepMin(expr, _)
+        | Expr::RepMax(expr, _)
+        | Expr::RepMinMax(expr, ..)
+        | Expr::Push(expr)
+        | Expr::Labeled(_, expr)
+        | Expr::Predicate(_, expr) => collect(expr, delimiters),
+        _ => (),
+    }
+}
+
+fn flatten<'a>(expr: &'a Expr, chain: &mut Vec<&'a Expr>) {
+    match expr {
+        Expr::Seq(lhs, rhs) => {
+            flatten(lhs, chain);
+            flatten(rhs, chain);
+        }
+        expr => chain.push(expr),
+    }
+}
+
+fn is_mirrored(open: &str, close: &str) -> bool {
+    let mirror = |c| match c {
+        '(' => ')',
+        ')' => '(',
+        '[' => ']',
+        ']' => '[',
+        '{' => '}',
+        '}' => '{',
+        '<' => '>',
+        '>' => '<',
+        c => c,
+    };
+
+    !open.is_empty()
+        && open.chars().all(|c| c.is_ascii_punctuation())
+        && open.chars().rev().map(mirror).eq(close.chars())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser;
+
+    fn delimiters_of(grammar: &str) -> Vec<(String, String)> {
+        let pairs = parser::parse(parser::Rule::grammar_rules, grammar).unwrap();
+        delimiters(&parser::consume_rules(pairs).unwrap())
+    }
+
+    #[test]
+    fn brackets() {
+        assert_eq!(
+            delimiters_of(
+                r#"
+                a = { "(" ~ b ~ ")" | "[" ~ (b ~ ",")* ~ "]" }
+                b = { "{" ~ "}" ~ "(" ~ a ~ ")" }
+                c = { "/*" ~ (!"*/" ~ ANY)* ~ "*/" }
+                "#
+            ),
+            vec![
+                ("(".to_owned(), ")".to_owned()),
+                ("[".to_owned(), "]".to_owned()),
+                ("/*".to_owned(), "*/".to_owned()),
+            ]
+        );
+    }
+
+    #[test]
+    fn not_delimiters() {
+        assert!(delimiters_of(
+            r#"
+            a = { "let" ~ b ~ ";" }
+            b = { "(" ~ "]" }
+            c = { "(" }
+            d = { "" ~ "" }
+            "#
+        )
+        .is_empty());
+    }
+
+    #[test]
+    fn nested_and_quotes() {
+        assert_eq!(
+            delimiters_of(r#"a = { "<" ~ ("'" ~ (!"'" ~ ANY)* ~ "'")? ~ ("{" ~ "}")* ~ ">" }"#),
+            vec![
+                ("<".to_owned(), ">".to_owned()),
+                ("'".to_owned(), "'".to_owned()),
+                ("{".to_owned(), "}".to_owned()),
+            ]
+        );
+    }
+}
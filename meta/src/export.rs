@@ -0,0 +1,641 @@
+// pest. The Elegant Parser
+// Copyright (c) 2018 Dragoș Tiselice
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Exporting pest grammars to other notations, e.g. for the appendices of specifications.
+//!
+//! The rules are exported in order, along with the comments before them. Constructs specific to
+//! PEGs, such as predicates and the stack, have no equivalent and are annotated with comments
+//! instead.
+
+use std::collections::HashSet;
+
+use pest::error::Error;
+
+use crate::ast::{Expr, Rule as AstRule, RuleType};
+use crate::parser::{self, Rule};
+
+/// A grammar notation which pest grammars can be exported to.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum Notation {
+    /// ISO/IEC 14977 EBNF, e.g. `number = [ "-" ], digit, { digit } ;`
+    Ebnf,
+    /// The EBNF notation of the W3C, used e.g. by the XML specification, e.g.
+    /// `number ::= "-"? [0-9]+`
+    W3c,
+}
+
+/// Exports `grammar` to `notation`, keeping the order of its rules and the comments before them.
+///
+/// Since ordered choices and repetitions are exported as regular ones, the exported grammar can
+/// accept more inputs than the original one. Predicates, stack operations, `SOI` and `EOI` are
+/// replaced with comments. Built-in rules are expanded when the notation has an equivalent.
+///
+/// # Examples
+///
+/// ```
+/// # use pest_meta::export::{export, Notation};
+/// let grammar = "// A number.\nnumber = @{ \"-\"? ~ ASCII_DIGIT+ }";
+///
+/// assert_eq!(
+///     export(grammar, Notation::W3c).unwrap(),
+///     "/* A number. */\nnumber ::= \"-\"? [0-9]+\n"
+/// );
+/// ```
+#[allow(clippy::perf)]
+pub fn export(grammar: &str, notation: Notation) -> Result<String, Vec<Error<Rule>>> {
+    let pairs = parser::parse(Rule::grammar_rules, grammar).map_err(|error| vec![error])?;
+    let spans: Vec<_> = pairs
+        .clone()
+        .filter(|pair| pair.as_rule() == Rule::grammar_rule)
+        .map(|pair| {
+            let span = pair.as_span();
+            let name = pair.into_inner().next().unwrap().as_str();
+            (name, span.start(), span.end())
+        })
+        .collect();
+    let rules = parser::consume_rules(pairs)?;
+
+    let exporter = Exporter {
+        notation,
+        defined: rules.iter().map(|rule| rule.name.as_str()).collect(),
+    };
+    let skips_whitespace =
+        exporter.defined.contains("WHITESPACE") || exporter.defined.contains("COMMENT");
+
+    let mut output = String::new();
+    if skips_whitespace {
+        output.push_str(&exporter.comment(
+            "WHITESPACE and COMMENT may appear between the symbols of the rules which are not \
+             marked as atomic.",
+        ));
+        output.push_str("\n\n");
+    }
+
+    let mut last = 0;
+    for rule in &rules {
+        // Rules generated by `@keywords` declarations have no text of their own.
+        if let Some(&(_, start, end)) = spans.iter().find(|(name, ..)| *name == rule.name) {
+            exporter.write_comments(&grammar[last..start], &mut output);
+            last = end;
+        }
+
+        exporter.write_rule(rule, skips_whitespace, &mut output);
+    }
+    exporter.write_comments(&grammar[last..], &mut output);
+
+    Ok(output)
+}
+
+const CHOICE: u8 = 0;
+const SEQUENCE: u8 = 1;
+const TERM: u8 = 2;
+
+// An exported expression, along with its precedence, or a comment annotating a construct which
+// matches no text in the notation.
+enum Part {
+    Expr(String, u8),
+    Comment(String),
+}
+
+struct Exporter<'a> {
+    notation: Notation,
+    defined: HashSet<&'a str>,
+}
+
+impl<'a> Exporter<'a> {
+    fn comment(&self, text: &str) -> String {
+        match self.notation {
+            Notation::Ebnf => format!("(* {} *)", text.replace("*)", "* )")),
+            Notation::W3c => format!("/* {} */", text.replace("*/", "* /")),
+        }
+    }
+
+    // Writes the comments of `text`, which lies between two rules, keeping blank lines.
+    fn write_comments(&self, text: &str, output: &mut String) {
+        let mut chars = text.char_indices().peekable();
+        let mut newlines = 0;
+
+        while let Some((i, c)) = chars.next() {
+            let comment = if text[i..].starts_with("//") {
+                let end = text[i..].find('\n').map_or(text.len(), |end| i + end);
+                let comment = text[i..end].trim_start_matches('/').trim_start_matches('!');
+                Some((comment.trim().to_owned(), end))
+            } else if text[i..].starts_with("/*") {
+                let end = block_comment_end(&text[i..]).map_or(text.len(), |end| i + end);
+                let comment = text[i + 2..end].trim_end_matches("*/");
+                let lines: Vec<_> = comment.lines().map(str::trim).collect();
+                Some((lines.join(" ").trim().to_owned(), end))
+            } else if c == '"' {
+                // Skips the strings of `@keywords` declarations.
+                while let Some((_, c)) = chars.next() {
+                    match c {
+                        '\\' => {
+                            chars.next();
+                        }
+                        '"' => break,
+                        _ => (),
+                    }
+                }
+                None
+            } else {
+                if c == '\n' {
+                    newlines += 1;
+                } else if !c.is_whitespace() {
+                    newlines = 0;
+                }
+                None
+            };
+
+            if let Some((comment, end)) = comment {
+                if newlines >= 2 && !output.is_empty() && !output.ends_with("\n\n") {
+                    output.push('\n');
+                }
+                newlines = 0;
+
+                output.push_str(&self.comment(&comment));
+                output.push('\n');
+                while chars.peek().map_or(false, |&(i, _)| i < end) {
+                    chars.next();
+                }
+            }
+        }
+
+        if newlines >= 2 && !output.is_empty() && !output.ends_with("\n\n") {
+            output.push('\n');
+        }
+    }
+
+    fn write_rule(&self, rule: &AstRule, skips_whitespace: bool, output: &mut String) {
+        let atomic = matches!(rule.ty, RuleType::Atomic | RuleType::CompoundAtomic);
+        let annotation = if skips_whitespace && atomic {
+            format!(" {}", self.comment("atomic"))
+        } else {
+            String::new()
+        };
+        let expr = match self.part(&rule.expr) {
+            Part::Expr(expr, _) => expr,
+            Part::Comment(comment) => comment,
+        };
+
+        match self.notation {
+            Notation::Ebnf => {
+                output.push_str(&format!("{} = {} ;{}\n", rule.name, expr, annotation))
+            }
+            Notation::W3c => {
+                output.push_str(&format!("{} ::= {}{}\n", rule.name, expr, annotation))
+            }
+        }
+    }
+
+    // Returns `expr` as an expression of precedence at least `prec`.
+    fn expr(&self, expr: &Expr, prec: u8) -> String {
+        self.wrap(self.part(expr), prec)
+    }
+
+    fn wrap(&self, part: Part, prec: u8) -> String {
+        match part {
+            Part::Expr(expr, own) if own < prec => format!("( {} )", expr),
+            Part::Expr(expr, _) => expr,
+            Part::Comment(comment) => comment,
+        }
+    }
+
+    fn sequence(&self, parts: Vec<Part>) -> Part {
+        let separator = match self.notation {
+            Notation::Ebnf => ", ",
+            Notation::W3c => " ",
+        };
+        let mut sequence = String::new();
+        let mut exprs = 0;
+        let mut separated = true;
+        let last = parts.len().saturating_sub(1);
+
+        // Comments annotate the next expression, except at the end of the sequence.
+        for (i, part) in parts.into_iter().enumerate() {
+            match part {
+                Part::Expr(expr, own) => {
+                    if !separated {
+                        sequence.push_str(separator);
+                    } else if !sequence.is_empty() {
+                        sequence.push(' ');
+                    }
+                    if own < SEQUENCE {
+                        sequence.push_str(&format!("( {} )", expr));
+                    } else {
+                        sequence.push_str(&expr);
+                    }
+                    exprs += 1;
+                    separated = false;
+                }
+                Part::Comment(comment) => {
+                    if !separated && i != last {
+                        sequence.push_str(separator);
+                        separated = true;
+                    } else if !sequence.is_empty() {
+                        sequence.push(' ');
+                    }
+                    sequence.push_str(&comment);
+                }
+            }
+        }
+
+        match exprs {
+            0 => Part::Comment(sequence),
+            1 => Part::Expr(sequence, TERM),
+            _ => Part::Expr(sequence, SEQUENCE),
+        }
+    }
+
+    fn sequence_parts(&self, expr: &Expr, parts: &mut Vec<Part>) {
+        match expr {
+            Expr::Seq(left, right) => {
+                self.sequence_parts(left, parts);
+                self.sequence_parts(right, parts);
+            }
+            expr => parts.push(self.part(expr)),
+        }
+    }
+
+    fn repeated(&self, expr: &Expr, min: u32, max: Option<u32>) -> Part {
+        let ebnf = self.notation == Notation::Ebnf;
+        let mut parts = vec![];
+
+        if min > 0 {
+            let exactly = match (ebnf, min) {
+                (_, 1) => self.expr(expr, TERM),
+                (true, _) => format!("{} * {}", min, self.expr(expr, TERM)),
+                (false, _) => vec![self.expr(expr, TERM); min as usize].join(" "),
+            };
+            parts.push(Part::Expr(
+                exactly,
+                if ebnf || min == 1 { TERM } else { SEQUENCE },
+            ));
+        }
+
+        match max {
+            None => {
+                let any = if ebnf {
+                    format!("{{ {} }}", self.expr(expr, CHOICE))
+                } else {
+                    format!("{}*", self.expr(expr, TERM))
+                };
+                parts.push(Part::Expr(any, TERM));
+            }
+            Some(max) if max > min => {
+                let optional = if ebnf {
+                    format!("[ {} ]", self.expr(expr, CHOICE))
+                } else {
+                    format!("{}?", self.expr(expr, TERM))
+                };
+                let count = max - min;
+                let optional = match (ebnf, count) {
+                    (_, 1) => optional,
+                    (true, _) => format!("{} * {}", count, optional),
+                    (false, _) => vec![optional; count as usize].join(" "),
+                };
+                parts.push(Part::Expr(optional, TERM));
+            }
+            Some(_) => (),
+        }
+
+        self.sequence(parts)
+    }
+
+    fn part(&self, expr: &Expr) -> Part {
+        let ebnf = self.notation == Notation::Ebnf;
+
+        match expr {
+            Expr::Str(string) => self.string(string),
+            Expr::Insens(string) => {
+                if ebnf {
+                    return Part::Expr(format!("? case-insensitive {:?} ?", string), TERM);
+                }
+
+                let parts = string
+                    .chars()
+                    .map(|c| {
+                        if c.is_ascii_alphabetic() {
+                            Part::Expr(
+                                format!("[{}{}]", c.to_ascii_lowercase(), c.to_ascii_uppercase()),
+                                TERM,
+                            )
+                        } else {
+                            self.string(&c.to_string())
+                        }
+                    })
+                    .collect();
+                self.sequence(parts)
+            }
+            Expr::Range(start, end) => self.range(start, end),
+            Expr::Ident(name) if !self.defined.contains(name.as_str()) => self.builtin(name),
+            Expr::Ident(name) => Part::Expr(name.clone(), TERM),
+            Expr::PeekSlice(start, end) => {
+                let end = end.map_or(String::new(), |end| end.to_string());
+                Part::Comment(self.comment(&format!("PEEK[{}..{}]", start, end)))
+            }
+            Expr::PosPred(expr) => {
+                Part::Comment(self.comment(&format!("followed by {}", self.expr(expr, CHOICE))))
+            }
+            Expr::NegPred(expr) => {
+                Part::Comment(self.comment(&format!("not followed by {}", self.expr(expr, CHOICE))))
+            }
+            Expr::PosLookbehind(expr) => {
+                Part::Comment(self.comment(&format!("preceded by {}", self.expr(expr, CHOICE))))
+            }
+            Expr::Seq(..) => {
+                let mut parts = vec![];
+                self.sequence_parts(expr, &mut parts);
+                self.sequence(parts)
+            }
+            Expr::Choice(left, right) => Part::Expr(
+                format!("{} | {}", self.expr(left, CHOICE), self.expr(right, CHOICE)),
+                CHOICE,
+            ),
+            Expr::Opt(expr) => self.repeated(expr, 0, Some(1)),
+            Expr::Rep(expr) => self.repeated(expr, 0, None),
+            Expr::RepOnce(expr) if ebnf => self.repeated(expr, 1, None),
+            Expr::RepOnce(expr) => Part::Expr(format!("{}+", self.expr(expr, TERM)), TERM),
+            Expr::RepExact(expr, num) => self.repeated(expr, *num, Some(*num)),
+            Expr::RepMin(expr, min) => self.repeated(expr, *min, None),
+            Expr::RepMax(expr, max) => self.repeated(expr, 0, Some(*max)),
+            Expr::RepMinMax(expr, min, max) => self.repeated(expr, *min, Some(*max)),
+            Expr::Skip(strings) => Part::Comment(self.comment(&format!(
+                "anything up to {}",
+                strings
+                    .iter()
+                    .map(|string| format!("{:?}", string))
+                    .collect::<Vec<_>>()
+                    .join(" or ")
+            ))),
+            Expr::Push(expr) => self.sequence(vec![
+                self.part(expr),
+                Part::Comment(self.comment("pushed to the stack")),
+            ]),
+            // Labels only affect error messages.
+            Expr::Labeled(_, expr) => self.part(expr),
+            Expr::Predicate(name, expr) => self.sequence(vec![
+                self.part(expr),
+                Part::Comment(self.comment(&format!("accepted by {}", name))),
+            ]),
+            // Exported grammars accept the inputs of all the dialects.
+            Expr::Dialect(dialects, then, otherwise) => {
+                let then = self.sequence(vec![
+                    Part::Comment(self.comment(&format!("in {}", dialects.join(", ")))),
+                    self.part(then),
+                ]);
+                Part::Expr(
+                    format!(
+                        "{} | {}",
+                        self.wrap(then, CHOICE),
+                        self.expr(otherwise, CHOICE)
+                    ),
+                    CHOICE,
+                )
+            }
+        }
+    }
+
+    fn string(&self, string: &str) -> Part {
+        if string.is_empty() {
+            return Part::Comment(self.comment("empty"));
+        }
+
+        // Splits the string so that every part can be quoted.
+        let mut parts = vec![];
+        let mut current = String::new();
+        for c in string.chars() {
+            if c.is_control() {
+                if !current.is_empty() {
+                    parts.push(Part::Expr(quote(&current), TERM));
+                    current.clear();
+                }
+                parts.push(Part::Expr(self.code_point(c), TERM));
+                continue;
+            }
+
+            if (c == '"' && current.contains('\'')) || (c == '\'' && current.contains('"')) {
+                parts.push(Part::Expr(quote(&current), TERM));
+                current.clear();
+            }
+            current.push(c);
+        }
+        if !current.is_empty() {
+            parts.push(Part::Expr(quote(&current), TERM));
+        }
+
+        self.sequence(parts)
+    }
+
+    fn code_point(&self, c: char) -> String {
+        match self.notation {
+            Notation::Ebnf => format!("? U+{:04X} ?", c as u32),
+            Notation::W3c => format!("#x{:X}", c as u32),
+        }
+    }
+
+    fn range(&self, start: &str, end: &str) -> Part {
+        let (start, end) = match (start.chars().next(), end.chars().next()) {
+            (Some(start), Some(end)) => (start, end),
+            _ => return Part::Comment(self.comment("empty range")),
+        };
+
+        match self.notation {
+            // Ranges of up to ten characters, e.g. digits, are spelled out.
+            Notation::Ebnf if (end as u32).saturating_sub(start as u32) < 10 => {
+                let chars: Vec<_> = (start..=end)
+                    .map(|c| match self.string(&c.to_string()) {
+                        Part::Expr(expr, _) => expr,
+                        Part::Comment(comment) => comment,
+                    })
+                    .collect();
+                let prec = if chars.len() > 1 { CHOICE } else { TERM };
+                Part::Expr(chars.join(" | "), prec)
+            }
+            Notation::Ebnf => Part::Expr(
+                format!(
+                    "? characters from {} to {} ?",
+                    self.char_name(start),
+                    self.char_name(end)
+                ),
+                TERM,
+            ),
+            Notation::W3c => Part::Expr(
+                format!("[{}-{}]", self.class_char(start), self.class_char(end)),
+                TERM,
+            ),
+        }
+    }
+
+    fn char_name(&self, c: char) -> String {
+        if c.is_control() {
+            format!("U+{:04X}", c as u32)
+        } else {
+            quote(&c.to_string())
+        }
+    }
+
+    fn class_char(&self, c: char) -> String {
+        if c.is_control() || c.is_whitespace() || "[]-^\\#".contains(c) {
+            format!("#x{:X}", c as u32)
+        } else {
+            c.to_string()
+        }
+    }
+
+    fn builtin(&self, name: &str) -> Part {
+        let range = |start: char, end: char| Expr::Range(start.to_string(), end.to_string());
+        let choice = |left, right| Expr::Choice(Box::new(left), Box::new(right));
+
+        let expr = match name {
+            "ASCII_DIGIT" => range('0', '9'),
+            "ASCII_NONZERO_DIGIT" => range('1', '9'),
+            "ASCII_BIN_DIGIT" => range('0', '1'),
+            "ASCII_OCT_DIGIT" => range('0', '7'),
+            "ASCII_HEX_DIGIT" => choice(range('0', '9'), choice(range('a', 'f'), range('A', 'F'))),
+            "ASCII_ALPHA_LOWER" => range('a', 'z'),
+            "ASCII_ALPHA_UPPER" => range('A', 'Z'),
+            "ASCII_ALPHA" => choice(range('a', 'z'), range('A', 'Z')),
+            "ASCII_ALPHANUMERIC" => {
+                choice(range('a', 'z'), choice(range('A', 'Z'), range('0', '9')))
+            }
+            "ASCII" => range('\0', '\x7f'),
+            "NEWLINE" => choice(
+                Expr::Str("\n".to_owned()),
+                choice(Expr::Str("\r\n".to_owned()), Expr::Str("\r".to_owned())),
+            ),
+            _ => {
+                let text = match name {
+                    "ANY" if self.notation == Notation::W3c => {
+                        return Part::Expr("[#x0-#x10FFFF]".to_owned(), TERM)
+                    }
+                    "ANY" => "any character".to_owned(),
+                    "SOI" => "start of input".to_owned(),
+                    "EOI" => "end of input".to_owned(),
+                    "PEEK" | "PEEK_ALL" | "POP" | "POP_ALL" | "DROP" | "PUSH_INDENT"
+                    | "MATCH_INDENT" | "DEDENT" => name.to_owned(),
+                    _ => format!("Unicode property {}", name),
+                };
+
+                return match (self.notation, name) {
+                    (_, "SOI") | (_, "EOI") | (_, "DROP") | (_, "DEDENT") => {
+                        Part::Comment(self.comment(&text))
+                    }
+                    (Notation::Ebnf, _) => Part::Expr(format!("? {} ?", text), TERM),
+                    (Notation::W3c, _) => {
+                        Part::Expr(format!("{} {}", name, self.comment(&text)), TERM)
+                    }
+                };
+            }
+        };
+
+        self.part(&expr)
+    }
+}
+
+fn quote(string: &str) -> String {
+    if string.contains('"') {
+        format!("'{}'", string)
+    } else {
+        format!("\"{}\"", string)
+    }
+}
+
+// Returns the length of the block comment at the start of `text`, which may be nested.
+fn block_comment_end(text: &str) -> Option<usize> {
+    let mut depth = 0;
+    let mut i = 0;
+    while i < text.len() {
+        if text[i..].starts_with("/*") {
+            depth += 1;
+            i += 2;
+        } else if text[i..].starts_with("*/") {
+            depth -= 1;
+            i += 2;
+            if depth == 0 {
+                return Some(i);
+            }
+        } else {
+            i += text[i..].chars().next().map_or(1, char::len_utf8);
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GRAMMAR: &str = r#"//! A grammar.
+
+/// A list of numbers.
+list = { SOI ~ number ~ ("," ~ number){0, 3} ~ EOI }
+
+// Numbers.
+/* They cannot
+   start with 0. */
+number = @{ "-"? ~ !"0" ~ ASCII_DIGIT+ | "0" }
+
+quote = { "\"'" | ^"nil" | "\n" | 'a'..'z' }
+WHITESPACE = _{ " " }
+"#;
+
+    #[test]
+    fn ebnf() {
+        assert_eq!(
+            export(GRAMMAR, Notation::Ebnf).unwrap(),
+            r#"(* WHITESPACE and COMMENT may appear between the symbols of the rules which are not marked as atomic. *)
+
+(* A grammar. *)
+
+(* A list of numbers. *)
+list = (* start of input *) number, 3 * [ ",", number ] (* end of input *) ;
+
+(* Numbers. *)
+(* They cannot start with 0. *)
+number = [ "-" ], (* not followed by "0" *) ( "0" | "1" | "2" | "3" | "4" | "5" | "6" | "7" | "8" | "9" ), { "0" | "1" | "2" | "3" | "4" | "5" | "6" | "7" | "8" | "9" } | "0" ; (* atomic *)
+
+quote = '"', "'" | ? case-insensitive "nil" ? | ? U+000A ? | ? characters from "a" to "z" ? ;
+WHITESPACE = " " ;
+"#
+        );
+    }
+
+    #[test]
+    fn w3c() {
+        assert_eq!(
+            export(GRAMMAR, Notation::W3c).unwrap(),
+            r#"/* WHITESPACE and COMMENT may appear between the symbols of the rules which are not marked as atomic. */
+
+/* A grammar. */
+
+/* A list of numbers. */
+list ::= /* start of input */ number ( "," number )? ( "," number )? ( "," number )? /* end of input */
+
+/* Numbers. */
+/* They cannot start with 0. */
+number ::= "-"? /* not followed by "0" */ [0-9]+ | "0" /* atomic */
+
+quote ::= '"' "'" | [nN] [iI] [lL] | #xA | [a-z]
+WHITESPACE ::= " "
+"#
+        );
+    }
+
+    #[test]
+    fn stack() {
+        assert_eq!(
+            export(
+                r##"raw = { PUSH("#"*) ~ "x" ~ POP ~ PEEK[..1] }"##,
+                Notation::Ebnf
+            )
+            .unwrap(),
+            "raw = { \"#\" } (* pushed to the stack *), \"x\", ? POP ? (* PEEK[0..1] *) ;\n"
+        );
+    }
+}
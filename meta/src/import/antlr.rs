@@ -0,0 +1,962 @@
+// pest. The Elegant Parser
+// Copyright (c) 2018 Dragoș Tiselice
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+use super::{
+    any_except, choice, end_of, finish, postfix, rule_name, seq, Conversion, ImportError,
+    ImportedRule, Note, Scanner,
+};
+use crate::ast::{Expr, RuleType};
+use crate::UNICODE_PROPERTY_NAMES;
+
+/// Converts an ANTLR 4 grammar to a pest grammar.
+///
+/// Parser rules become normal rules and lexer rules become atomic rules, so that the whitespace
+/// and comment tokens sent to the `skip` or `HIDDEN` channels, which are gathered in a generated
+/// `WHITESPACE` rule, are only skipped between tokens. Fragments become silent rules, labels are
+/// dropped and `EOF` becomes `EOI`.
+///
+/// Actions, semantic predicates, rule arguments, lexer modes and commands, non-greedy operators
+/// and left recursion are reported as [`Note`]s.
+///
+/// # Examples
+///
+/// ```
+/// # use pest_meta::import::from_antlr;
+/// let conversion = from_antlr("grammar Sum; sum : INT ('+' INT)* ; INT : [0-9]+ ;").unwrap();
+///
+/// assert!(conversion.grammar.contains(r#"sum = { INT ~ ("+" ~ INT)* }"#));
+/// assert!(conversion.grammar.contains("INT = @{ '0'..'9'+ }"));
+/// assert!(conversion.notes.is_empty());
+/// ```
+///
+/// [`Note`]: struct.Note.html
+pub fn from_antlr(source: &str) -> Result<Conversion, ImportError> {
+    let mut parser = AntlrParser {
+        tokens: lex(source)?,
+        pos: 0,
+        end: end_of(source),
+    };
+    parser.grammar()
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Ident(String),
+    Str(String),
+    // The raw contents of `[...]`, which are either a character set or arguments.
+    Set(String),
+    // The raw contents of `{...}`.
+    Action(String),
+    // The raw contents of `<...>`.
+    Options(String),
+    Punct(&'static str),
+}
+
+struct Lexed {
+    token: Token,
+    line: usize,
+    col: usize,
+}
+
+fn lex(source: &str) -> Result<Vec<Lexed>, ImportError> {
+    let mut s = Scanner::new(source);
+    let mut tokens = vec![];
+
+    loop {
+        while s.peek().map_or(false, char::is_whitespace) {
+            s.bump();
+        }
+
+        if s.eat_str("//") {
+            while s.peek().map_or(false, |c| c != '\n') {
+                s.bump();
+            }
+            continue;
+        }
+        if s.eat_str("/*") {
+            while !s.eat_str("*/") {
+                if s.bump().is_none() {
+                    return Err(s.error("unterminated comment"));
+                }
+            }
+            continue;
+        }
+
+        let (line, col) = (s.line, s.col);
+        let c = match s.peek() {
+            Some(c) => c,
+            None => return Ok(tokens),
+        };
+
+        let token = if c.is_alphanumeric() || c == '_' {
+            let mut ident = String::new();
+            while let Some(c) = s.peek().filter(|&c| c.is_alphanumeric() || c == '_') {
+                ident.push(c);
+                s.bump();
+            }
+            Token::Ident(ident)
+        } else if s.eat('\'') {
+            let mut string = String::new();
+            loop {
+                match s.bump() {
+                    Some('\'') => break,
+                    Some('\\') => string.push(escape(&mut s)?),
+                    Some('\n') | None => return Err(s.error("unterminated string literal")),
+                    Some(c) => string.push(c),
+                }
+            }
+            Token::Str(string)
+        } else if s.eat('[') {
+            Token::Set(delimited(&mut s, ']')?)
+        } else if s.eat('<') {
+            Token::Options(delimited(&mut s, '>')?)
+        } else if s.eat('{') {
+            Token::Action(action(&mut s)?)
+        } else {
+            let punct = ["->", "+=", "..", "::"]
+                .iter()
+                .chain(&[
+                    ":", ";", "|", "(", ")", "*", "+", "?", "~", ".", ",", "=", "#", "@",
+                ])
+                .find(|punct| s.eat_str(punct));
+            match punct {
+                Some(punct) => Token::Punct(punct),
+                None => return Err(s.error(&format!("unexpected character `{}`", c))),
+            }
+        };
+
+        tokens.push(Lexed { token, line, col });
+    }
+}
+
+// Decodes the escape sequence of a string literal after the `\`.
+fn escape(s: &mut Scanner) -> Result<char, ImportError> {
+    let c = match s.bump() {
+        Some('n') => '\n',
+        Some('r') => '\r',
+        Some('t') => '\t',
+        Some('b') => '\u{8}',
+        Some('f') => '\u{c}',
+        Some('u') => {
+            let braces = s.eat('{');
+            let mut hex = String::new();
+            while s.peek().map_or(false, |c| c.is_ascii_hexdigit()) && (braces || hex.len() < 4) {
+                hex.push(s.bump().unwrap());
+            }
+            if braces && !s.eat('}') {
+                return Err(s.error("expected `}` in unicode escape"));
+            }
+            u32::from_str_radix(&hex, 16)
+                .ok()
+                .and_then(std::char::from_u32)
+                .ok_or_else(|| s.error("invalid unicode escape"))?
+        }
+        Some(c) => c,
+        None => return Err(s.error("unterminated escape sequence")),
+    };
+    Ok(c)
+}
+
+// Returns the raw text up to the unescaped `end`.
+fn delimited(s: &mut Scanner, end: char) -> Result<String, ImportError> {
+    let mut text = String::new();
+    loop {
+        match s.bump() {
+            Some(c) if c == end => return Ok(text),
+            Some('\\') => {
+                text.push('\\');
+                if let Some(c) = s.bump() {
+                    text.push(c);
+                }
+            }
+            Some(c) => text.push(c),
+            None => return Err(s.error(&format!("expected `{}`", end))),
+        }
+    }
+}
+
+// Returns the raw text of an action up to its closing brace, skipping nested braces and strings.
+fn action(s: &mut Scanner) -> Result<String, ImportError> {
+    let mut text = String::new();
+    let mut depth = 0;
+    loop {
+        match s.bump() {
+            Some('}') if depth == 0 => return Ok(text),
+            Some(c @ '"') | Some(c @ '\'') => {
+                text.push(c);
+                text.push_str(&delimited(s, c)?);
+                text.push(c);
+            }
+            Some(c) => {
+                match c {
+                    '{' => depth += 1,
+                    '}' => depth -= 1,
+                    _ => (),
+                }
+                text.push(c);
+            }
+            None => return Err(s.error("unterminated action")),
+        }
+    }
+}
+
+struct AntlrParser {
+    tokens: Vec<Lexed>,
+    pos: usize,
+    end: (usize, usize),
+}
+
+// The state of the rule being converted.
+struct RuleState {
+    lexer: bool,
+    skipped: bool,
+    notes: Vec<(usize, String)>,
+}
+
+impl AntlrParser {
+    fn peek(&self) -> Option<&Token> {
+        self.peek_at(0)
+    }
+
+    fn peek_at(&self, offset: usize) -> Option<&Token> {
+        self.tokens.get(self.pos + offset).map(|lexed| &lexed.token)
+    }
+
+    fn line(&self) -> usize {
+        self.tokens
+            .get(self.pos)
+            .map_or(self.end.0, |lexed| lexed.line)
+    }
+
+    fn bump(&mut self) -> Option<Token> {
+        let token = self.peek().cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn eat(&mut self, punct: &str) -> bool {
+        match self.peek() {
+            Some(Token::Punct(p)) if *p == punct => {
+                self.pos += 1;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn is_ident_at(&self, offset: usize, ident: &str) -> bool {
+        matches!(self.peek_at(offset), Some(Token::Ident(i)) if i == ident)
+    }
+
+    fn error(&self, message: &str) -> ImportError {
+        let (line, col) = self
+            .tokens
+            .get(self.pos)
+            .map_or(self.end, |lexed| (lexed.line, lexed.col));
+        ImportError {
+            line,
+            col,
+            message: message.to_owned(),
+        }
+    }
+
+    fn expect(&mut self, punct: &str) -> Result<(), ImportError> {
+        if self.eat(punct) {
+            Ok(())
+        } else {
+            Err(self.error(&format!("expected `{}`", punct)))
+        }
+    }
+
+    fn expect_ident(&mut self) -> Result<String, ImportError> {
+        match self.peek().cloned() {
+            Some(Token::Ident(ident)) => {
+                self.pos += 1;
+                Ok(ident)
+            }
+            _ => Err(self.error("expected an identifier")),
+        }
+    }
+
+    fn grammar(&mut self) -> Result<Conversion, ImportError> {
+        let mut name = None;
+        let mut notes = vec![];
+        let mut rules = vec![];
+        let mut skipped = vec![];
+
+        while let Some(token) = self.peek().cloned() {
+            let line = self.line();
+            let note = |message: String| Note {
+                line,
+                rule: None,
+                message,
+            };
+            let followed_by_ident = matches!(self.peek_at(1), Some(Token::Ident(_)));
+            let followed_by_action = matches!(self.peek_at(1), Some(Token::Action(_)));
+
+            match token {
+                Token::Ident(ref kind)
+                    if (kind == "lexer" || kind == "parser") && self.is_ident_at(1, "grammar") =>
+                {
+                    self.pos += 2;
+                    name = Some(self.expect_ident()?);
+                    self.expect(";")?;
+                }
+                Token::Ident(ref kind) if kind == "grammar" && followed_by_ident => {
+                    self.pos += 1;
+                    name = Some(self.expect_ident()?);
+                    self.expect(";")?;
+                }
+                Token::Ident(ref kind) if kind == "options" && followed_by_action => {
+                    self.pos += 1;
+                    if let Some(Token::Action(options)) = self.bump() {
+                        notes.extend(options_notes(&options).into_iter().map(note));
+                    }
+                }
+                Token::Ident(ref kind) if kind == "tokens" && followed_by_action => {
+                    self.pos += 1;
+                    if let Some(Token::Action(tokens)) = self.bump() {
+                        notes.push(note(format!(
+                            "tokens {} are declared without rules and must be defined",
+                            tokens
+                                .split(',')
+                                .map(str::trim)
+                                .collect::<Vec<_>>()
+                                .join(", ")
+                        )));
+                    }
+                }
+                Token::Ident(ref kind) if kind == "channels" && followed_by_action => {
+                    self.pos += 2;
+                }
+                Token::Ident(ref kind) if kind == "import" && followed_by_ident => {
+                    self.pos += 1;
+                    let mut imported = vec![];
+                    while !self.eat(";") {
+                        match self.bump() {
+                            Some(Token::Ident(ident)) => imported.push(ident),
+                            Some(Token::Punct(",")) | Some(Token::Punct("=")) => (),
+                            _ => return Err(self.error("expected `;`")),
+                        }
+                    }
+                    notes.push(note(format!(
+                        "imported grammars {} must be converted separately",
+                        imported.join(", ")
+                    )));
+                }
+                Token::Ident(ref kind) if kind == "mode" && followed_by_ident => {
+                    self.pos += 1;
+                    let mode = self.expect_ident()?;
+                    self.expect(";")?;
+                    notes.push(note(format!(
+                        "lexer mode {} is not supported; its rules were converted as regular rules",
+                        mode
+                    )));
+                }
+                Token::Punct("@") => {
+                    self.pos += 1;
+                    let mut action = self.expect_ident()?;
+                    if self.eat("::") {
+                        action = self.expect_ident()?;
+                    }
+                    match self.bump() {
+                        Some(Token::Action(_)) => {
+                            notes.push(note(format!("named action @{} was dropped", action)))
+                        }
+                        _ => return Err(self.error("expected an action")),
+                    }
+                }
+                Token::Ident(ref kind) if kind == "fragment" && followed_by_ident => {
+                    self.pos += 1;
+                    rules.push(self.rule(true, &mut skipped)?);
+                }
+                Token::Ident(_) => rules.push(self.rule(false, &mut skipped)?),
+                _ => return Err(self.error("expected a rule")),
+            }
+        }
+
+        let special = ["WHITESPACE", "COMMENT"];
+        let whitespace: Vec<_> = skipped
+            .into_iter()
+            .filter(|name: &String| !special.contains(&name.as_str()))
+            .map(|name| Some(Expr::Ident(name)))
+            .collect();
+        if !whitespace.is_empty() {
+            rules.push(ImportedRule {
+                name: "WHITESPACE".to_owned(),
+                ty: RuleType::Silent,
+                expr: choice(whitespace),
+                line: self.end.0,
+                notes: vec![],
+            });
+        }
+
+        let header = match name {
+            Some(name) => format!("Converted from the ANTLR grammar {}.", name),
+            None => "Converted from an ANTLR grammar.".to_owned(),
+        };
+        Ok(finish(&header, notes, rules))
+    }
+
+    fn rule(
+        &mut self,
+        fragment: bool,
+        skipped: &mut Vec<String>,
+    ) -> Result<ImportedRule, ImportError> {
+        let line = self.line();
+        let original = self.expect_ident()?;
+        let name = rule_name(&original);
+        let mut state = RuleState {
+            lexer: original.starts_with(char::is_uppercase),
+            skipped: false,
+            notes: vec![],
+        };
+
+        if name != original {
+            state.notes.push((
+                line,
+                format!("renamed from {} since it is a pest keyword", original),
+            ));
+        }
+
+        loop {
+            let line = self.line();
+            match self.peek().cloned() {
+                Some(Token::Set(_)) => {
+                    self.pos += 1;
+                    state
+                        .notes
+                        .push((line, "rule arguments were dropped".to_owned()));
+                }
+                Some(Token::Ident(ref kind)) if kind == "returns" || kind == "locals" => {
+                    self.pos += 2;
+                    state
+                        .notes
+                        .push((line, format!("rule {} were dropped", kind)));
+                }
+                Some(Token::Ident(ref kind)) if kind == "throws" => {
+                    self.pos += 1;
+                    self.expect_ident()?;
+                    while self.eat(",") {
+                        self.expect_ident()?;
+                    }
+                }
+                Some(Token::Ident(ref kind)) if kind == "options" => self.pos += 2,
+                Some(Token::Punct("@")) => {
+                    self.pos += 1;
+                    let action = self.expect_ident()?;
+                    self.pos += 1;
+                    state
+                        .notes
+                        .push((line, format!("rule action @{} was dropped", action)));
+                }
+                _ => break,
+            }
+        }
+
+        self.expect(":")?;
+        let expr = self.alternatives(&mut state)?;
+        self.expect(";")?;
+
+        while self.is_ident_at(0, "catch") || self.is_ident_at(0, "finally") {
+            let line = self.line();
+            let kind = if self.is_ident_at(0, "catch") { 3 } else { 2 };
+            self.pos += kind;
+            state
+                .notes
+                .push((line, "exception handlers were dropped".to_owned()));
+        }
+
+        let ty = if state.skipped {
+            skipped.push(name.clone());
+            RuleType::Silent
+        } else if fragment {
+            RuleType::Silent
+        } else if state.lexer {
+            RuleType::Atomic
+        } else {
+            RuleType::Normal
+        };
+
+        Ok(ImportedRule {
+            name,
+            ty,
+            expr,
+            line,
+            notes: state.notes,
+        })
+    }
+
+    fn alternatives(&mut self, state: &mut RuleState) -> Result<Expr, ImportError> {
+        let mut alternatives = vec![self.alternative(state)?];
+        while self.eat("|") {
+            alternatives.push(self.alternative(state)?);
+        }
+
+        Ok(choice(alternatives))
+    }
+
+    fn alternative(&mut self, state: &mut RuleState) -> Result<Option<Expr>, ImportError> {
+        let mut elements = vec![];
+
+        loop {
+            let line = self.line();
+            match self.peek().cloned() {
+                None
+                | Some(Token::Punct("|"))
+                | Some(Token::Punct(";"))
+                | Some(Token::Punct(")")) => break,
+                Some(Token::Punct("#")) => {
+                    // Alternative labels only name the generated contexts.
+                    self.pos += 1;
+                    self.expect_ident()?;
+                }
+                Some(Token::Punct("->")) => {
+                    self.pos += 1;
+                    self.commands(state)?;
+                }
+                Some(Token::Options(options)) => {
+                    self.pos += 1;
+                    state.notes.push((
+                        line,
+                        format!("element option <{}> was dropped", options.trim()),
+                    ));
+                }
+                Some(_) => {
+                    if let Some(element) = self.element(state)? {
+                        elements.push(element);
+                    }
+                }
+            }
+        }
+
+        Ok(seq(elements))
+    }
+
+    fn commands(&mut self, state: &mut RuleState) -> Result<(), ImportError> {
+        loop {
+            let line = self.line();
+            let command = self.expect_ident()?;
+            let mut argument = None;
+            if self.eat("(") {
+                argument = Some(self.expect_ident()?);
+                self.expect(")")?;
+            }
+
+            match command.as_str() {
+                "skip" | "channel" => state.skipped = true,
+                _ => {
+                    let command = match argument {
+                        Some(argument) => format!("{}({})", command, argument),
+                        None => command,
+                    };
+                    state.notes.push((
+                        line,
+                        format!("lexer command `{}` is not supported", command),
+                    ));
+                }
+            }
+
+            if !self.eat(",") {
+                return Ok(());
+            }
+        }
+    }
+
+    fn element(&mut self, state: &mut RuleState) -> Result<Option<Expr>, ImportError> {
+        // Element labels only name the fields of the generated contexts.
+        if let (Some(Token::Ident(_)), Some(Token::Punct("=")))
+        | (Some(Token::Ident(_)), Some(Token::Punct("+="))) = (self.peek(), self.peek_at(1))
+        {
+            self.pos += 2;
+        }
+
+        let line = self.line();
+        let atom = match self.atom(state)? {
+            Some(atom) => atom,
+            None => return Ok(None),
+        };
+
+        let op = match self.peek() {
+            Some(Token::Punct("?")) => '?',
+            Some(Token::Punct("*")) => '*',
+            Some(Token::Punct("+")) => '+',
+            _ => return Ok(Some(atom)),
+        };
+        self.pos += 1;
+
+        if self.eat("?") {
+            state.notes.push((
+                line,
+                format!(
+                    "non-greedy `{}?` was converted to a greedy `{}`; use e.g. `(!end ~ ANY){}` \
+                     instead",
+                    op, op, op
+                ),
+            ));
+        }
+
+        Ok(Some(postfix(op, atom)))
+    }
+
+    fn atom(&mut self, state: &mut RuleState) -> Result<Option<Expr>, ImportError> {
+        let line = self.line();
+        let expr = match self.bump() {
+            Some(Token::Ident(ident)) if ident == "EOF" => Expr::Ident("EOI".to_owned()),
+            Some(Token::Ident(ident)) => Expr::Ident(rule_name(&ident)),
+            Some(Token::Str(start)) => {
+                if !self.eat("..") {
+                    return Ok(Some(Expr::Str(start)));
+                }
+
+                match self.bump() {
+                    Some(Token::Str(ref end))
+                        if start.chars().count() == 1 && end.chars().count() == 1 =>
+                    {
+                        Expr::Range(start, end.clone())
+                    }
+                    _ => {
+                        self.pos -= 1;
+                        return Err(self.error("expected a range between two characters"));
+                    }
+                }
+            }
+            Some(Token::Set(set)) => set_expr(&set, line, state),
+            Some(Token::Punct(".")) => {
+                if !state.lexer {
+                    state.notes.push((
+                        line,
+                        "`.` matches any token in parser rules, but any character here".to_owned(),
+                    ));
+                }
+                Expr::Ident("ANY".to_owned())
+            }
+            Some(Token::Punct("~")) => {
+                if !state.lexer {
+                    state.notes.push((
+                        line,
+                        "`~` negates tokens in parser rules, but characters here".to_owned(),
+                    ));
+                }
+                match self.atom(state)? {
+                    Some(expr) => any_except(expr),
+                    None => return Ok(None),
+                }
+            }
+            Some(Token::Punct("(")) => {
+                let expr = self.alternatives(state)?;
+                self.expect(")")?;
+                expr
+            }
+            Some(Token::Action(action)) => {
+                let kind = if self.eat("?") {
+                    "semantic predicate"
+                } else {
+                    "action"
+                };
+                state.notes.push((
+                    line,
+                    format!("{} `{{{}}}` was dropped", kind, shorten(&action)),
+                ));
+                return Ok(None);
+            }
+            _ => {
+                self.pos -= 1;
+                return Err(self.error("expected an element"));
+            }
+        };
+
+        Ok(Some(expr))
+    }
+}
+
+fn options_notes(options: &str) -> Vec<String> {
+    options
+        .split(';')
+        .filter_map(|option| {
+            let mut parts = option.splitn(2, '=').map(str::trim);
+            match (parts.next(), parts.next()) {
+                (Some("tokenVocab"), Some(vocab)) => Some(format!(
+                    "the tokens are defined by the lexer grammar {}, which must be converted \
+                     separately",
+                    vocab
+                )),
+                (Some("caseInsensitive"), Some("true")) => Some(
+                    "the lexer is case-insensitive; use `^\"...\"` strings where needed".to_owned(),
+                ),
+                _ => None,
+            }
+        })
+        .collect()
+}
+
+fn shorten(action: &str) -> String {
+    let action = action.split_whitespace().collect::<Vec<_>>().join(" ");
+    if action.chars().count() > 40 {
+        format!("{}...", action.chars().take(40).collect::<String>())
+    } else {
+        action
+    }
+}
+
+enum SetItem {
+    Char(char),
+    Property(String),
+}
+
+// Converts the raw contents of a lexer character set.
+fn set_expr(set: &str, line: usize, state: &mut RuleState) -> Expr {
+    let mut s = Scanner::new(set);
+    let mut items = vec![];
+
+    while let Some(c) = s.bump() {
+        if c != '\\' {
+            items.push(SetItem::Char(c));
+            continue;
+        }
+
+        match s.peek() {
+            Some('p') | Some('P') => {
+                let negated = s.bump() == Some('P');
+                let property = if s.eat('{') {
+                    delimited(&mut s, '}').unwrap_or_default()
+                } else {
+                    String::new()
+                };
+                if negated {
+                    state.notes.push((
+                        line,
+                        format!("negated property \\P{{{}}} was dropped", property),
+                    ));
+                } else {
+                    items.push(SetItem::Property(property));
+                }
+            }
+            Some(_) => match escape(&mut s) {
+                Ok(c) => items.push(SetItem::Char(c)),
+                Err(_) => state
+                    .notes
+                    .push((line, format!("invalid escape in [{}] was dropped", set))),
+            },
+            None => items.push(SetItem::Char('\\')),
+        }
+    }
+
+    let mut alternatives = vec![];
+    let mut i = 0;
+    while i < items.len() {
+        match (&items[i], items.get(i + 1), items.get(i + 2)) {
+            (SetItem::Char(start), Some(SetItem::Char('-')), Some(SetItem::Char(end))) => {
+                alternatives.push(Some(Expr::Range(start.to_string(), end.to_string())));
+                i += 3;
+            }
+            (SetItem::Char(c), _, _) => {
+                alternatives.push(Some(Expr::Str(c.to_string())));
+                i += 1;
+            }
+            (SetItem::Property(property), _, _) => {
+                match unicode_property(property) {
+                    Some(property) => alternatives.push(Some(Expr::Ident(property))),
+                    None => state.notes.push((
+                        line,
+                        format!("unknown property \\p{{{}}} was dropped", property),
+                    )),
+                }
+                i += 1;
+            }
+        }
+    }
+
+    choice(alternatives)
+}
+
+// Maps a Unicode property of ANTLR to the corresponding built-in rule.
+fn unicode_property(property: &str) -> Option<String> {
+    let categories = [
+        ("L", "LETTER"),
+        ("Lu", "UPPERCASE_LETTER"),
+        ("Ll", "LOWERCASE_LETTER"),
+        ("Lt", "TITLECASE_LETTER"),
+        ("Lm", "MODIFIER_LETTER"),
+        ("Lo", "OTHER_LETTER"),
+        ("M", "MARK"),
+        ("N", "NUMBER"),
+        ("Nd", "DECIMAL_NUMBER"),
+        ("Nl", "LETTER_NUMBER"),
+        ("No", "OTHER_NUMBER"),
+        ("P", "PUNCTUATION"),
+        ("S", "SYMBOL"),
+        ("Z", "SEPARATOR"),
+        ("Zs", "SPACE_SEPARATOR"),
+    ];
+    let name = match categories.iter().find(|(short, _)| *short == property) {
+        Some((_, name)) => (*name).to_owned(),
+        None => property.to_uppercase().replace('-', "_"),
+    };
+
+    if UNICODE_PROPERTY_NAMES.contains(&name.as_str()) {
+        Some(name)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_and_optimize;
+
+    #[test]
+    fn expr_grammar() {
+        let conversion = from_antlr(
+            r#"
+grammar Expr;
+
+prog : stat+ EOF ;
+stat : expr NEWLINE         # printExpr
+     | id=ID '=' expr NEWLINE # assign
+     | NEWLINE              # blank
+     ;
+expr : expr ('*'|'/') expr
+     | INT
+     | '(' expr ')'
+     ;
+ID : [a-zA-Z_\-]+ ;
+INT : DIGIT+ { count++; } ;
+NEWLINE : '\r'? '\n' ;
+STRING : '"' (~["\\] | '\\' .)*? '"' ;
+fragment DIGIT : '0'..'9' ;
+WS : [ \t]+ -> skip ;
+"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            conversion.grammar,
+            r#"// Converted from the ANTLR grammar Expr.
+
+prog = { stat+ ~ EOI }
+
+stat = { expr ~ NEWLINE | ID ~ "=" ~ expr ~ NEWLINE | NEWLINE }
+
+// TODO: the rule is left-recursive and must be rewritten, e.g. with repetitions or pest::pratt_parser
+expr = { expr ~ ("*" | "/") ~ expr | INT | "(" ~ expr ~ ")" }
+
+ID = @{ ('a'..'z' | 'A'..'Z' | "_" | "-")+ }
+
+// TODO: action `{count++;}` was dropped
+INT = @{ DIGIT+ }
+
+NEWLINE = @{ "\r"? ~ "\n" }
+
+// TODO: non-greedy `*?` was converted to a greedy `*`; use e.g. `(!end ~ ANY)*` instead
+STRING = @{ "\"" ~ (!("\"" | "\\") ~ ANY | "\\" ~ ANY)* ~ "\"" }
+
+DIGIT = _{ '0'..'9' }
+
+WS = _{ (" " | "\t")+ }
+
+WHITESPACE = _{ WS }
+"#
+        );
+        assert_eq!(
+            conversion.notes[0],
+            Note {
+                line: 9,
+                rule: Some("expr".to_owned()),
+                message: "the rule is left-recursive and must be rewritten, e.g. with repetitions \
+                          or pest::pratt_parser"
+                    .to_owned(),
+            }
+        );
+        assert_eq!(conversion.notes.len(), 3);
+    }
+
+    #[test]
+    fn converted_grammar_is_valid() {
+        let conversion = from_antlr(
+            r#"
+lexer grammar Json;
+options { language = Java; }
+@header { package json; }
+
+value : obj | arr | STRING | NUMBER | 'true' | 'false' | 'null' ;
+obj : '{' (pair (',' pair)*)? '}' ;
+pair : STRING ':' value ;
+arr : '[' elements ']' ;
+elements : value (',' value)* | ;
+STRING : '"' (ESC | ~["\\\u0000-\u001F])* '"' ;
+fragment ESC : '\\' (["\\/bfnrt] | 'u' HEX HEX HEX HEX) ;
+fragment HEX : [\p{Nd}a-fA-F] ;
+NUMBER : '-'? [0-9]+ ('.' [0-9]+)? ;
+ANY : [ \t\r\n]+ -> channel(HIDDEN) ;
+"#,
+        )
+        .unwrap();
+
+        assert!(conversion
+            .grammar
+            .contains("elements = { (value ~ (\",\" ~ value)*)? }"));
+        assert!(conversion.grammar.contains("'\\0'..'\\u{001F}'"));
+        assert!(conversion
+            .grammar
+            .contains("HEX = _{ DECIMAL_NUMBER | 'a'..'f' | 'A'..'F' }"));
+        assert!(conversion.grammar.contains("WHITESPACE = _{ ANY_ }"));
+        assert_eq!(
+            conversion
+                .notes
+                .iter()
+                .map(|note| note.to_string())
+                .collect::<Vec<_>>(),
+            vec![
+                "line 4: named action @header was dropped",
+                "line 15 (rule ANY_): renamed from ANY since it is a pest keyword"
+            ]
+        );
+        parse_and_optimize(&conversion.grammar).unwrap();
+    }
+
+    #[test]
+    fn unsupported() {
+        let conversion = from_antlr(
+            "grammar G;\n\
+             import Base;\n\
+             a[int x] returns [int y] : {isType()}? ID <assoc=right> ;\n\
+             mode STRING_MODE;\n\
+             ID : [a-z]+ -> pushMode(STRING_MODE) ;",
+        )
+        .unwrap();
+
+        assert_eq!(
+            conversion
+                .notes
+                .iter()
+                .map(|note| note.to_string())
+                .collect::<Vec<_>>(),
+            vec![
+                "line 2: imported grammars Base must be converted separately",
+                "line 4: lexer mode STRING_MODE is not supported; its rules were converted as \
+                 regular rules",
+                "line 3 (rule a): rule arguments were dropped",
+                "line 3 (rule a): rule returns were dropped",
+                "line 3 (rule a): semantic predicate `{isType()}` was dropped",
+                "line 3 (rule a): element option <assoc=right> was dropped",
+                "line 5 (rule ID): lexer command `pushMode(STRING_MODE)` is not supported"
+            ]
+        );
+        assert!(conversion.grammar.contains("a = { ID }"));
+    }
+
+    #[test]
+    fn syntax_error() {
+        let error = from_antlr("grammar G;\na : 'a ;").unwrap_err();
+
+        assert_eq!(error.to_string(), "2:9: unterminated string literal");
+    }
+}
@@ -0,0 +1,446 @@
+// pest. The Elegant Parser
+// Copyright (c) 2018 Dragoș Tiselice
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+use super::{
+    choice, end_of, finish, postfix, rule_name, seq, Conversion, ImportError, ImportedRule, Scanner,
+};
+use crate::ast::{Expr, RuleType};
+
+/// Converts an ISO/IEC 14977 EBNF grammar to a pest grammar.
+///
+/// Every rule becomes a normal rule. Since no `WHITESPACE` rule is generated, the converted
+/// grammar matches the input character by character, like the original one. The words of a meta
+/// identifier are joined with `_`, e.g. `digit excluding zero` becomes `digit_excluding_zero`, and
+/// an exception `a - b` becomes `!b ~ a`.
+///
+/// Special sequences, e.g. `? any character ?`, and left recursion are reported as [`Note`]s.
+///
+/// # Examples
+///
+/// ```
+/// # use pest_meta::import::from_ebnf;
+/// let conversion = from_ebnf("number = [ '-' ], digit, { digit } ; digit = '0' | '1' ;").unwrap();
+///
+/// assert!(conversion.grammar.contains(r#"number = { "-"? ~ digit ~ digit* }"#));
+/// assert!(conversion.grammar.contains(r#"digit = { "0" | "1" }"#));
+/// assert!(conversion.notes.is_empty());
+/// ```
+///
+/// [`Note`]: struct.Note.html
+pub fn from_ebnf(source: &str) -> Result<Conversion, ImportError> {
+    let mut parser = EbnfParser {
+        tokens: lex(source)?,
+        pos: 0,
+        end: end_of(source),
+        notes: vec![],
+    };
+
+    let mut rules = vec![];
+    while parser.pos < parser.tokens.len() {
+        rules.push(parser.rule()?);
+    }
+
+    Ok(finish("Converted from an ISO EBNF grammar.", vec![], rules))
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Word(String),
+    Integer(u32),
+    Terminal(String),
+    Special(String),
+    Punct(&'static str),
+}
+
+struct Lexed {
+    token: Token,
+    line: usize,
+    col: usize,
+}
+
+fn lex(source: &str) -> Result<Vec<Lexed>, ImportError> {
+    let mut s = Scanner::new(source);
+    let mut tokens = vec![];
+
+    loop {
+        while s.peek().map_or(false, char::is_whitespace) {
+            s.bump();
+        }
+
+        if s.eat_str("(*") {
+            let mut depth = 1;
+            while depth > 0 {
+                if s.eat_str("(*") {
+                    depth += 1;
+                } else if s.eat_str("*)") {
+                    depth -= 1;
+                } else if s.bump().is_none() {
+                    return Err(s.error("unterminated comment"));
+                }
+            }
+            continue;
+        }
+
+        let (line, col) = (s.line, s.col);
+        let c = match s.peek() {
+            Some(c) => c,
+            None => return Ok(tokens),
+        };
+
+        let token = if c.is_alphabetic() || c == '_' {
+            let mut word = String::new();
+            while let Some(c) = s.peek().filter(|&c| c.is_alphanumeric() || c == '_') {
+                word.push(c);
+                s.bump();
+            }
+            Token::Word(word)
+        } else if c.is_ascii_digit() {
+            let mut digits = String::new();
+            while let Some(c) = s.peek().filter(char::is_ascii_digit) {
+                digits.push(c);
+                s.bump();
+            }
+            match digits.parse() {
+                Ok(integer) => Token::Integer(integer),
+                Err(_) => return Err(s.error("integer is too large")),
+            }
+        } else if c == '\'' || c == '"' || c == '?' {
+            s.bump();
+            let mut text = String::new();
+            loop {
+                match s.bump() {
+                    Some(end) if end == c => break,
+                    Some(c) => text.push(c),
+                    None if c == '?' => return Err(s.error("unterminated special sequence")),
+                    None => return Err(s.error("unterminated terminal string")),
+                }
+            }
+            if c == '?' {
+                Token::Special(text.trim().to_owned())
+            } else {
+                Token::Terminal(text)
+            }
+        } else {
+            // The alternative representations of the standard are mapped to the usual ones.
+            let punct = [
+                ("(/", "["),
+                ("/)", "]"),
+                ("(:", "{"),
+                (":)", "}"),
+                ("=", "="),
+                (",", ","),
+                ("|", "|"),
+                ("/", "|"),
+                ("!", "|"),
+                (";", ";"),
+                (".", ";"),
+                ("-", "-"),
+                ("*", "*"),
+                ("[", "["),
+                ("]", "]"),
+                ("{", "{"),
+                ("}", "}"),
+                ("(", "("),
+                (")", ")"),
+            ]
+            .iter()
+            .find(|(punct, _)| s.eat_str(punct));
+            match punct {
+                Some((_, punct)) => Token::Punct(punct),
+                None => return Err(s.error(&format!("unexpected character `{}`", c))),
+            }
+        };
+
+        tokens.push(Lexed { token, line, col });
+    }
+}
+
+struct EbnfParser {
+    tokens: Vec<Lexed>,
+    pos: usize,
+    end: (usize, usize),
+    notes: Vec<(usize, String)>,
+}
+
+impl EbnfParser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos).map(|lexed| &lexed.token)
+    }
+
+    fn line(&self) -> usize {
+        self.tokens
+            .get(self.pos)
+            .map_or(self.end.0, |lexed| lexed.line)
+    }
+
+    fn eat(&mut self, punct: &str) -> bool {
+        match self.peek() {
+            Some(Token::Punct(p)) if *p == punct => {
+                self.pos += 1;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn error(&self, message: &str) -> ImportError {
+        let (line, col) = self
+            .tokens
+            .get(self.pos)
+            .map_or(self.end, |lexed| (lexed.line, lexed.col));
+        ImportError {
+            line,
+            col,
+            message: message.to_owned(),
+        }
+    }
+
+    fn expect(&mut self, punct: &str) -> Result<(), ImportError> {
+        if self.eat(punct) {
+            Ok(())
+        } else {
+            Err(self.error(&format!("expected `{}`", punct)))
+        }
+    }
+
+    // Reads a meta identifier, whose words may be separated by whitespace.
+    fn identifier(&mut self) -> Option<String> {
+        let mut words = vec![];
+        while let Some(Token::Word(word)) = self.peek() {
+            words.push(word.clone());
+            self.pos += 1;
+        }
+
+        if words.is_empty() {
+            None
+        } else {
+            Some(words.join("_"))
+        }
+    }
+
+    fn rule(&mut self) -> Result<ImportedRule, ImportError> {
+        let line = self.line();
+        let original = self
+            .identifier()
+            .ok_or_else(|| self.error("expected a meta identifier"))?;
+        let name = rule_name(&original);
+        if name != original {
+            self.notes.push((
+                line,
+                format!("renamed from {} since it is a pest keyword", original),
+            ));
+        }
+
+        self.expect("=")?;
+        let expr = self.definitions()?;
+        self.expect(";")?;
+
+        Ok(ImportedRule {
+            name,
+            ty: RuleType::Normal,
+            expr,
+            line,
+            notes: self.notes.drain(..).collect(),
+        })
+    }
+
+    fn definitions(&mut self) -> Result<Expr, ImportError> {
+        let mut alternatives = vec![self.definition()?];
+        while self.eat("|") {
+            alternatives.push(self.definition()?);
+        }
+
+        Ok(choice(alternatives))
+    }
+
+    fn definition(&mut self) -> Result<Option<Expr>, ImportError> {
+        let mut terms = vec![];
+        loop {
+            if let Some(term) = self.term()? {
+                terms.push(term);
+            }
+            if !self.eat(",") {
+                return Ok(seq(terms));
+            }
+        }
+    }
+
+    fn term(&mut self) -> Result<Option<Expr>, ImportError> {
+        let factor = self.factor()?;
+        if !self.eat("-") {
+            return Ok(factor);
+        }
+
+        let exception = self
+            .factor()?
+            .ok_or_else(|| self.error("expected an exception"))?;
+        let exception = Box::new(Expr::NegPred(Box::new(exception)));
+        Ok(Some(match factor {
+            Some(factor) => Expr::Seq(exception, Box::new(factor)),
+            None => *exception,
+        }))
+    }
+
+    fn factor(&mut self) -> Result<Option<Expr>, ImportError> {
+        let count = match self.peek() {
+            Some(&Token::Integer(count)) => {
+                self.pos += 1;
+                self.expect("*")?;
+                Some(count)
+            }
+            _ => None,
+        };
+
+        let primary = self.primary()?;
+        Ok(match (primary, count) {
+            (Some(primary), Some(count)) => Some(Expr::RepExact(Box::new(primary), count)),
+            (primary, _) => primary,
+        })
+    }
+
+    fn primary(&mut self) -> Result<Option<Expr>, ImportError> {
+        let line = self.line();
+        let expr = match self.peek().cloned() {
+            Some(Token::Word(_)) => {
+                let name = self.identifier().unwrap();
+                return Ok(Some(Expr::Ident(rule_name(&name))));
+            }
+            Some(Token::Terminal(string)) => Expr::Str(string),
+            Some(Token::Special(special)) => {
+                self.pos += 1;
+                self.notes.push((
+                    line,
+                    format!("special sequence `? {} ?` was dropped", special),
+                ));
+                return Ok(None);
+            }
+            Some(Token::Punct(open @ "["))
+            | Some(Token::Punct(open @ "{"))
+            | Some(Token::Punct(open @ "(")) => {
+                self.pos += 1;
+                let expr = self.definitions()?;
+                match open {
+                    "[" => {
+                        self.expect("]")?;
+                        return Ok(Some(postfix('?', expr)));
+                    }
+                    "{" => {
+                        self.expect("}")?;
+                        return Ok(Some(postfix('*', expr)));
+                    }
+                    _ => {
+                        self.expect(")")?;
+                        return Ok(Some(expr));
+                    }
+                }
+            }
+            // An empty sequence.
+            _ => return Ok(None),
+        };
+
+        self.pos += 1;
+        Ok(Some(expr))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_and_optimize;
+
+    #[test]
+    fn program() {
+        let conversion = from_ebnf(
+            r#"(* a simple program syntax (* from Wikipedia *) *)
+program = 'PROGRAM', white space, identifier, white space,
+          'BEGIN', white space,
+          { assignment, ";", white space },
+          'END.' ;
+identifier = alphabetic character, { alphabetic character | digit } ;
+string = '"' , { all characters - '"' }, '"' ;
+assignment = identifier , ":=" , ( identifier | string ) ;
+alphabetic character = "A" | "B" | "C" ;
+digit = "0" | "1" | "2" ;
+white space = ? white space characters ? ;
+all characters = ? all visible characters ? ;
+"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            conversion.grammar,
+            r#"// Converted from an ISO EBNF grammar.
+
+program = { "PROGRAM" ~ white_space ~ identifier ~ white_space ~ "BEGIN" ~ white_space ~ (assignment ~ ";" ~ white_space)* ~ "END." }
+
+identifier = { alphabetic_character ~ (alphabetic_character | digit)* }
+
+string = { "\"" ~ (!"\"" ~ all_characters)* ~ "\"" }
+
+assignment = { identifier ~ ":=" ~ (identifier | string) }
+
+alphabetic_character = { "A" | "B" | "C" }
+
+digit = { "0" | "1" | "2" }
+
+// TODO: special sequence `? white space characters ?` was dropped
+white_space = { "" }
+
+// TODO: special sequence `? all visible characters ?` was dropped
+all_characters = { "" }
+"#
+        );
+        assert_eq!(
+            conversion.notes[0].to_string(),
+            "line 11 (rule white_space): special sequence `? white space characters ?` was dropped"
+        );
+    }
+
+    #[test]
+    fn converted_grammar_is_valid() {
+        let conversion = from_ebnf(
+            "date = 4 * digit, '-', 2 * digit, '-', 2 * digit ;\n\
+             digit = '0' | nonzero digit ;\n\
+             nonzero digit = (/ '1' | '2' /), (: '0' :) ;\n\
+             list = '(', [ date, { ',', date } | ], ')' .\n\
+             EOI = 'eoi' ;",
+        )
+        .unwrap();
+
+        assert!(conversion
+            .grammar
+            .contains(r#"date = { digit{4} ~ "-" ~ digit{2} ~ "-" ~ digit{2} }"#));
+        assert!(conversion
+            .grammar
+            .contains(r#"nonzero_digit = { ("1" | "2")? ~ "0"* }"#));
+        assert!(conversion
+            .grammar
+            .contains(r#"list = { "(" ~ (date ~ ("," ~ date)*)? ~ ")" }"#));
+        assert_eq!(
+            conversion.notes[0].to_string(),
+            "line 5 (rule EOI_): renamed from EOI since it is a pest keyword"
+        );
+        parse_and_optimize(&conversion.grammar).unwrap();
+    }
+
+    #[test]
+    fn left_recursion() {
+        let conversion = from_ebnf("list = [ list, ',' ], 'a' ;").unwrap();
+
+        assert_eq!(conversion.notes.len(), 1);
+        assert!(conversion.notes[0].message.contains("left-recursive"));
+    }
+
+    #[test]
+    fn syntax_error() {
+        let error = from_ebnf("a = 'a' ;\nb = 'b' ").unwrap_err();
+
+        assert_eq!(error.to_string(), "2:9: expected `;`");
+    }
+}
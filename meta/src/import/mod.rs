@@ -0,0 +1,378 @@
+// pest. The Elegant Parser
+// Copyright (c) 2018 Dragoș Tiselice
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Converters from other grammar notations to pest grammars.
+//!
+//! The converters translate the constructs which have a direct pest equivalent and report the
+//! others as [`Note`]s, which are also inserted as `// TODO` comments in the converted grammar.
+//! The result is meant as a starting point to be reviewed by hand: e.g. ANTLR lexers pick the
+//! longest matching token, while pest tries alternatives in order.
+//!
+//! [`Note`]: struct.Note.html
+
+use std::error;
+use std::fmt;
+
+use crate::ast::{Expr, RuleType};
+use crate::validator::PEST_KEYWORDS;
+
+mod antlr;
+mod ebnf;
+
+pub use self::antlr::from_antlr;
+pub use self::ebnf::from_ebnf;
+
+/// The result of converting a grammar to pest.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Conversion {
+    /// The converted pest grammar
+    pub grammar: String,
+    /// The constructs which could not be converted faithfully and need manual attention
+    pub notes: Vec<Note>,
+}
+
+/// A construct of the original grammar which needs manual attention.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Note {
+    /// The line of the construct in the original grammar
+    pub line: usize,
+    /// The name of the converted rule containing the construct, if any
+    pub rule: Option<String>,
+    /// What needs attention
+    pub message: String,
+}
+
+impl fmt::Display for Note {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.rule {
+            Some(ref rule) => write!(f, "line {} (rule {}): {}", self.line, rule, self.message),
+            None => write!(f, "line {}: {}", self.line, self.message),
+        }
+    }
+}
+
+/// An error returned when the original grammar cannot be read.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ImportError {
+    /// The line of the error
+    pub line: usize,
+    /// The column of the error
+    pub col: usize,
+    /// The error message
+    pub message: String,
+}
+
+impl fmt::Display for ImportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}: {}", self.line, self.col, self.message)
+    }
+}
+
+impl error::Error for ImportError {}
+
+// A converted rule, along with the notes concerning it.
+struct ImportedRule {
+    name: String,
+    ty: RuleType,
+    expr: Expr,
+    line: usize,
+    notes: Vec<(usize, String)>,
+}
+
+// A character scanner tracking lines and columns.
+struct Scanner {
+    chars: Vec<char>,
+    pos: usize,
+    line: usize,
+    col: usize,
+}
+
+impl Scanner {
+    fn new(source: &str) -> Scanner {
+        Scanner {
+            chars: source.chars().collect(),
+            pos: 0,
+            line: 1,
+            col: 1,
+        }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).cloned()
+    }
+
+    fn peek_at(&self, offset: usize) -> Option<char> {
+        self.chars.get(self.pos + offset).cloned()
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.pos += 1;
+        if c == '\n' {
+            self.line += 1;
+            self.col = 1;
+        } else {
+            self.col += 1;
+        }
+        Some(c)
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        if self.peek() == Some(c) {
+            self.bump();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn eat_str(&mut self, s: &str) -> bool {
+        let matches = s
+            .chars()
+            .enumerate()
+            .all(|(i, c)| self.peek_at(i) == Some(c));
+        if matches {
+            s.chars().for_each(|_| {
+                self.bump();
+            });
+        }
+        matches
+    }
+
+    fn error(&self, message: &str) -> ImportError {
+        ImportError {
+            line: self.line,
+            col: self.col,
+            message: message.to_owned(),
+        }
+    }
+}
+
+// Returns the line and column of the end of `source`.
+fn end_of(source: &str) -> (usize, usize) {
+    let last_line = source.rsplit('\n').next().unwrap_or("");
+    (
+        source.matches('\n').count() + 1,
+        last_line.chars().count() + 1,
+    )
+}
+
+// Renames rules named after pest keywords, which cannot be defined in pest grammars.
+fn rule_name(name: &str) -> String {
+    if PEST_KEYWORDS.contains(name) {
+        format!("{}_", name)
+    } else {
+        name.to_owned()
+    }
+}
+
+fn seq(exprs: Vec<Expr>) -> Option<Expr> {
+    exprs.into_iter().rev().fold(None, |rest, expr| match rest {
+        Some(rest) => Some(Expr::Seq(Box::new(expr), Box::new(rest))),
+        None => Some(expr),
+    })
+}
+
+// Combines alternatives, where `None` is an empty alternative.
+fn choice(alternatives: Vec<Option<Expr>>) -> Expr {
+    let optional = alternatives.iter().any(Option::is_none);
+    let choice = alternatives
+        .into_iter()
+        .flatten()
+        .rev()
+        .fold(None, |rest, expr| match rest {
+            Some(rest) => Some(Expr::Choice(Box::new(expr), Box::new(rest))),
+            None => Some(expr),
+        });
+
+    match choice {
+        Some(expr) if optional => Expr::Opt(Box::new(expr)),
+        Some(expr) => expr,
+        None => Expr::Str(String::new()),
+    }
+}
+
+// Applies the postfix operator `op`, folding the optional expressions produced by empty
+// alternatives, since e.g. `(a?)*` would never progress.
+fn postfix(op: char, expr: Expr) -> Expr {
+    match (op, expr) {
+        ('?', Expr::Opt(expr)) => Expr::Opt(expr),
+        ('?', expr) => Expr::Opt(Box::new(expr)),
+        ('*', Expr::Opt(expr)) | ('+', Expr::Opt(expr)) => Expr::Rep(expr),
+        ('*', expr) => Expr::Rep(Box::new(expr)),
+        (_, expr) => Expr::RepOnce(Box::new(expr)),
+    }
+}
+
+// Matches any single character not matched by `expr`.
+fn any_except(expr: Expr) -> Expr {
+    Expr::Seq(
+        Box::new(Expr::NegPred(Box::new(expr))),
+        Box::new(Expr::Ident("ANY".to_owned())),
+    )
+}
+
+fn is_left_recursive(expr: &Expr, name: &str) -> bool {
+    match expr {
+        Expr::Ident(ident) => ident == name,
+        Expr::Seq(first, rest) => {
+            let nullable = match first.as_ref() {
+                Expr::Opt(_) | Expr::Rep(_) => true,
+                Expr::Str(string) => string.is_empty(),
+                _ => false,
+            };
+            is_left_recursive(first, name) || (nullable && is_left_recursive(rest, name))
+        }
+        Expr::Choice(left, right) => {
+            is_left_recursive(left, name) || is_left_recursive(right, name)
+        }
+        Expr::Opt(expr) | Expr::Rep(expr) | Expr::RepOnce(expr) | Expr::RepExact(expr, _) => {
+            is_left_recursive(expr, name)
+        }
+        _ => false,
+    }
+}
+
+fn finish(header: &str, mut notes: Vec<Note>, rules: Vec<ImportedRule>) -> Conversion {
+    let mut grammar = format!("// {}\n", header);
+    for note in &notes {
+        grammar.push_str(&format!("// TODO: {}\n", note.message));
+    }
+
+    for mut rule in rules {
+        if is_left_recursive(&rule.expr, &rule.name) {
+            rule.notes.push((
+                rule.line,
+                "the rule is left-recursive and must be rewritten, e.g. with repetitions or \
+                 pest::pratt_parser"
+                    .to_owned(),
+            ));
+        }
+
+        grammar.push('\n');
+        for (line, message) in rule.notes {
+            grammar.push_str(&format!("// TODO: {}\n", message));
+            notes.push(Note {
+                line,
+                rule: Some(rule.name.clone()),
+                message,
+            });
+        }
+
+        let modifier = match rule.ty {
+            RuleType::Normal => "",
+            RuleType::Silent => "_",
+            RuleType::Atomic => "@",
+            RuleType::CompoundAtomic => "$",
+            RuleType::NonAtomic => "!",
+        };
+        grammar.push_str(&format!("{} = {}{{ ", rule.name, modifier));
+        write_expr(&rule.expr, 0, &mut grammar);
+        grammar.push_str(" }\n");
+    }
+
+    Conversion { grammar, notes }
+}
+
+// Writes `expr` where an expression of precedence `prec` is expected: 0 for choices, 1 for
+// sequences, 2 for prefix operators and 3 for postfix operators.
+fn write_expr(expr: &Expr, prec: u8, out: &mut String) {
+    let (own, inner) = match expr {
+        Expr::Choice(..) => (0, 0),
+        Expr::Seq(..) => (1, 1),
+        Expr::PosPred(_) | Expr::NegPred(_) => (2, 2),
+        Expr::Opt(_) | Expr::Rep(_) | Expr::RepOnce(_) | Expr::RepExact(..) => (3, 3),
+        _ => (3, 0),
+    };
+    // Prefix operators bind looser than postfix ones, e.g. `!a?` is `!(a?)`.
+    let parens = own < prec || (own == 2 && prec == 3);
+    if parens {
+        out.push('(');
+    }
+
+    match expr {
+        Expr::Str(string) => {
+            out.push('"');
+            out.push_str(&escape(string, '"'));
+            out.push('"');
+        }
+        Expr::Insens(string) => {
+            out.push_str("^\"");
+            out.push_str(&escape(string, '"'));
+            out.push('"');
+        }
+        Expr::Range(start, end) => {
+            out.push_str(&format!(
+                "'{}'..'{}'",
+                escape(start, '\''),
+                escape(end, '\'')
+            ));
+        }
+        Expr::Ident(ident) => out.push_str(ident),
+        Expr::PosPred(expr) => {
+            out.push('&');
+            write_expr(expr, inner, out);
+        }
+        Expr::NegPred(expr) => {
+            out.push('!');
+            write_expr(expr, inner, out);
+        }
+        Expr::Seq(left, right) => {
+            write_expr(left, inner, out);
+            out.push_str(" ~ ");
+            write_expr(right, inner, out);
+        }
+        Expr::Choice(left, right) => {
+            write_expr(left, inner, out);
+            out.push_str(" | ");
+            write_expr(right, inner, out);
+        }
+        Expr::Opt(expr) => {
+            write_expr(expr, inner, out);
+            out.push('?');
+        }
+        Expr::Rep(expr) => {
+            write_expr(expr, inner, out);
+            out.push('*');
+        }
+        Expr::RepOnce(expr) => {
+            write_expr(expr, inner, out);
+            out.push('+');
+        }
+        Expr::RepExact(expr, num) => {
+            write_expr(expr, inner, out);
+            out.push_str(&format!("{{{}}}", num));
+        }
+        _ => unreachable!("importers do not produce {:?}", expr),
+    }
+
+    if parens {
+        out.push(')');
+    }
+}
+
+fn escape(string: &str, quote: char) -> String {
+    let mut escaped = String::new();
+    for c in string.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            '\0' => escaped.push_str("\\0"),
+            c if c == quote => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            c if c.is_control() => escaped.push_str(&format!("\\u{{{:04X}}}", c as u32)),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
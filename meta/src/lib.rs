@@ -25,8 +25,16 @@ use std::fmt::Display;
 use pest::error::Error;
 
 pub mod ast;
+pub mod children;
+pub mod compat;
+pub mod delimiters;
+pub mod export;
+pub mod import;
+pub mod metrics;
+pub mod mutation;
 pub mod optimizer;
 pub mod parser;
+pub mod refactor;
 pub mod validator;
 
 /// A helper that will unwrap the result or panic
@@ -57,6 +65,15 @@ type UsedBuiltinAndOptimized<'i> = (Vec<&'i str>, Vec<optimizer::OptimizedRule>)
 pub fn parse_and_optimize(
     grammar: &str,
 ) -> Result<UsedBuiltinAndOptimized<'_>, Vec<Error<parser::Rule>>> {
+    parse_and_optimize_with_passes(grammar, &[])
+}
+
+/// Parses, validates, processes and optimizes the provided grammar, running the custom optimizer
+/// `passes` after the built-in ones.
+pub fn parse_and_optimize_with_passes<'i>(
+    grammar: &'i str,
+    passes: &[optimizer::OptimizerPass],
+) -> Result<UsedBuiltinAndOptimized<'i>, Vec<Error<parser::Rule>>> {
     let pairs = match parser::parse(parser::Rule::grammar_rules, grammar) {
         Ok(pairs) => Ok(pairs),
         Err(error) => Err(vec![error]),
@@ -65,6 +82,26 @@ pub fn parse_and_optimize(
     let defaults = validator::validate_pairs(pairs.clone())?;
     let ast = parser::consume_rules(pairs)?;
 
+    Ok((defaults, optimizer::optimize_with_passes(ast, passes)))
+}
+
+/// Same as [`parse_and_optimize`], but calls to the rules named in `builtins` are not reported as
+/// undefined. See [`validator::validate_pairs_with_builtins`].
+///
+/// [`parse_and_optimize`]: fn.parse_and_optimize.html
+/// [`validator::validate_pairs_with_builtins`]: validator/fn.validate_pairs_with_builtins.html
+pub fn parse_and_optimize_with_builtins<'i>(
+    grammar: &'i str,
+    builtins: &[&str],
+) -> Result<UsedBuiltinAndOptimized<'i>, Vec<Error<parser::Rule>>> {
+    let pairs = match parser::parse(parser::Rule::grammar_rules, grammar) {
+        Ok(pairs) => Ok(pairs),
+        Err(error) => Err(vec![error]),
+    }?;
+
+    let defaults = validator::validate_pairs_with_builtins(pairs.clone(), builtins)?;
+    let ast = parser::consume_rules(pairs)?;
+
     Ok((defaults, optimizer::optimize(ast)))
 }
 
@@ -0,0 +1,255 @@
+// pest. The Elegant Parser
+// Copyright (c) 2018 Dragoș Tiselice
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Complexity metrics for pest's ASTs.
+//!
+//! These are meant to be used by tooling, e.g. to enforce a complexity budget in CI or to find
+//! the hot spots of a large grammar.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::ast::{Expr, Rule};
+
+/// Complexity metrics of a single grammar rule.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RuleMetrics {
+    /// The name of the rule
+    pub name: String,
+    /// The number of expression nodes in the rule's expression
+    pub node_count: usize,
+    /// The maximum nesting depth of the rule's expression
+    ///
+    /// Chains of sequences (`a ~ b ~ c`) and choices (`a | b | c`) count as a single level.
+    pub max_depth: usize,
+    /// The number of alternation branches in the rule's expression
+    ///
+    /// `a | b | c` has 3 branches, while an expression without choices has none.
+    pub choice_branches: usize,
+    /// The names of the rules referenced by this rule, sorted and deduplicated
+    pub references_out: Vec<String>,
+    /// The names of the rules that reference this rule, sorted and deduplicated
+    pub references_in: Vec<String>,
+}
+
+impl RuleMetrics {
+    /// Returns the complexity score of the rule, which is its expression node count.
+    pub fn complexity(&self) -> usize {
+        self.node_count
+    }
+}
+
+/// Computes the complexity metrics of every rule, in the order the rules were given.
+///
+/// # Examples
+///
+/// ```
+/// # use pest_meta::ast::{Expr, Rule, RuleType};
+/// # use pest_meta::metrics::metrics;
+/// let rules = vec![
+///     Rule {
+///         name: "a".to_owned(),
+///         ty: RuleType::Normal,
+///         expr: Expr::Choice(
+///             Box::new(Expr::Str("a".to_owned())),
+///             Box::new(Expr::Ident("b".to_owned())),
+///         ),
+///     },
+///     Rule {
+///         name: "b".to_owned(),
+///         ty: RuleType::Normal,
+///         expr: Expr::Str("b".to_owned()),
+///     },
+/// ];
+///
+/// let metrics = metrics(&rules);
+///
+/// assert_eq!(metrics[0].node_count, 3);
+/// assert_eq!(metrics[0].choice_branches, 2);
+/// assert_eq!(metrics[1].references_in, vec!["a".to_owned()]);
+/// ```
+pub fn metrics(rules: &[Rule]) -> Vec<RuleMetrics> {
+    let references_out: Vec<BTreeSet<String>> =
+        rules.iter().map(|rule| references(&rule.expr)).collect();
+
+    let mut references_in: BTreeMap<&str, BTreeSet<String>> = BTreeMap::new();
+    for (rule, out) in rules.iter().zip(references_out.iter()) {
+        for name in out {
+            references_in
+                .entry(name.as_str())
+                .or_default()
+                .insert(rule.name.clone());
+        }
+    }
+
+    rules
+        .iter()
+        .zip(references_out.iter())
+        .map(|(rule, out)| RuleMetrics {
+            name: rule.name.clone(),
+            node_count: rule.expr.iter_top_down().count(),
+            max_depth: depth(&rule.expr, None),
+            choice_branches: choice_branches(&rule.expr),
+            references_out: out.iter().cloned().collect(),
+            references_in: references_in
+                .get(rule.name.as_str())
+                .map(|names| names.iter().cloned().collect())
+                .unwrap_or_default(),
+        })
+        .collect()
+}
+
+fn references(expr: &Expr) -> BTreeSet<String> {
+    expr.iter_top_down()
+        .filter_map(|expr| match expr {
+            Expr::Ident(name) => Some(name),
+            _ => None,
+        })
+        .collect()
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Chain {
+    Seq,
+    Choice,
+}
+
+fn depth(expr: &Expr, parent: Option<Chain>) -> usize {
+    match expr {
+        Expr::Seq(lhs, rhs) => {
+            let level = if parent == Some(Chain::Seq) { 0 } else { 1 };
+            level + depth(lhs, Some(Chain::Seq)).max(depth(rhs, Some(Chain::Seq)))
+        }
+        Expr::Choice(lhs, rhs) => {
+            let level = if parent == Some(Chain::Choice) { 0 } else { 1 };
+            level + depth(lhs, Some(Chain::Choice)).max(depth(rhs, Some(Chain::Choice)))
+        }
+        Expr::PosPred(expr)
+        | Expr::NegPred(expr)
+        | Expr::PosLookbehind(expr)
+        | Expr::Opt(expr)
+        | Expr::Rep(expr)
+        | Expr::RepOnce(expr)
+        | Expr::RepExact(expr, _)
+        | Expr::RepMin(expr, _)
+        | Expr::RepMax(expr, _)
+        | Expr::RepMinMax(expr, ..)
+        | Expr::Push(expr)
+        | Expr::Labeled(_, expr)
+        | Expr::Predicate(_, expr) => 1 + depth(expr, None),
+        Expr::Dialect(_, then, otherwise) => 1 + depth(then, None).max(depth(otherwise, None)),
+        _ => 1,
+    }
+}
+
+fn choice_branches(expr: &Expr) -> usize {
+    match expr {
+        Expr::Choice(..) => chain_branches(expr),
+        Expr::Seq(lhs, rhs) | Expr::Dialect(_, lhs, rhs) => {
+            choice_branches(lhs) + choice_branches(rhs)
+        }
+        Expr::PosPred(expr)
+        | Expr::NegPred(expr)
+        | Expr::PosLookbehind(expr)
+        | Expr::Opt(expr)
+        | Expr::Rep(expr)
+        | Expr::RepOnce(expr)
+        | Expr::RepExact(expr, _)
+        | Expr::RepMin(expr, _)
+        | Expr::RepMax(expr, _)
+        | Expr::RepMinMax(expr, ..)
+        | Expr::Push(expr)
+        | Expr::Labeled(_, expr)
+        | Expr::Predicate(_, expr) => choice_branches(expr),
+        _ => 0,
+    }
+}
+
+// Counts the branches of a maximal choice chain, plus the branches nested inside of them.
+fn chain_branches(expr: &Expr) -> usize {
+    match expr {
+        Expr::Choice(lhs, rhs) => chain_branches(lhs) + chain_branches(rhs),
+        expr => 1 + choice_branches(expr),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ast::RuleType;
+
+    fn rule(name: &str, expr: Expr) -> Rule {
+        Rule {
+            name: name.to_owned(),
+            ty: RuleType::Normal,
+            expr,
+        }
+    }
+
+    #[test]
+    fn flat_chains() {
+        let expr = Expr::Choice(
+            Box::new(Expr::Choice(
+                Box::new(Expr::Str("a".to_owned())),
+                Box::new(Expr::Str("b".to_owned())),
+            )),
+            Box::new(Expr::Seq(
+                Box::new(Expr::Ident("c".to_owned())),
+                Box::new(Expr::Seq(
+                    Box::new(Expr::Ident("d".to_owned())),
+                    Box::new(Expr::Ident("c".to_owned())),
+                )),
+            )),
+        );
+
+        let metrics = metrics(&[rule("r", expr)]);
+
+        assert_eq!(metrics[0].node_count, 9);
+        assert_eq!(metrics[0].max_depth, 3);
+        assert_eq!(metrics[0].choice_branches, 3);
+        assert_eq!(
+            metrics[0].references_out,
+            vec!["c".to_owned(), "d".to_owned()]
+        );
+    }
+
+    #[test]
+    fn nested_choices() {
+        let expr = Expr::Rep(Box::new(Expr::Choice(
+            Box::new(Expr::Str("a".to_owned())),
+            Box::new(Expr::Opt(Box::new(Expr::Choice(
+                Box::new(Expr::Str("b".to_owned())),
+                Box::new(Expr::Str("c".to_owned())),
+            )))),
+        )));
+
+        let metrics = metrics(&[rule("r", expr)]);
+
+        assert_eq!(metrics[0].max_depth, 5);
+        assert_eq!(metrics[0].choice_branches, 4);
+    }
+
+    #[test]
+    fn references_in() {
+        let rules = vec![
+            rule("a", Expr::Ident("c".to_owned())),
+            rule("b", Expr::Push(Box::new(Expr::Ident("c".to_owned())))),
+            rule("c", Expr::Ident("a".to_owned())),
+        ];
+
+        let metrics = metrics(&rules);
+
+        assert_eq!(metrics[0].references_in, vec!["c".to_owned()]);
+        assert!(metrics[1].references_in.is_empty());
+        assert_eq!(
+            metrics[2].references_in,
+            vec!["a".to_owned(), "b".to_owned()]
+        );
+        assert_eq!(metrics[2].complexity(), 1);
+    }
+}
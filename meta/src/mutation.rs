@@ -0,0 +1,255 @@
+// pest. The Elegant Parser
+// Copyright (c) 2018 Dragoș Tiselice
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Systematic mutations of grammars, for mutation testing.
+//!
+//! A test suite of a grammar is only as good as the changes it catches: every [`Mutant`] is the
+//! grammar with a single small change, like a swapped choice, and a mutant which every test still
+//! accepts points at a part of the grammar which is not really tested. Mutants can be run against
+//! test inputs with `pest_vm`'s `mutation` module.
+//!
+//! [`Mutant`]: struct.Mutant.html
+
+use std::fmt;
+
+use pest::error::Error;
+
+use crate::ast::{Expr, Rule};
+use crate::parser;
+
+/// A kind of mutation of an expression.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum MutationKind {
+    /// The alternatives of a choice were swapped, e.g. `a | b` became `b | a`
+    SwapChoice,
+    /// An optional expression was made mandatory, e.g. `a?` became `a`
+    DropOptional,
+    /// A range was widened by one character on each side, e.g. `'b'..'y'` became `'a'..'z'`
+    WidenRange {
+        /// The new start of the range
+        start: char,
+        /// The new end of the range
+        end: char,
+    },
+}
+
+/// A grammar with a single mutation applied to one of its rules.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Mutant {
+    /// The name of the mutated rule
+    pub rule: String,
+    /// The index of the mutation among the mutations of the rule, which are numbered from the
+    /// top of its expression down, e.g. `1` for the second one
+    pub site: usize,
+    /// The kind of mutation
+    pub kind: MutationKind,
+    /// All the rules of the mutated grammar
+    pub rules: Vec<Rule>,
+}
+
+impl fmt::Display for Mutant {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "rule {}, mutation #{}: ", self.rule, self.site + 1)?;
+
+        match &self.kind {
+            MutationKind::SwapChoice => write!(f, "swapped the alternatives of a choice"),
+            MutationKind::DropOptional => write!(f, "made an optional expression mandatory"),
+            MutationKind::WidenRange { start, end } => {
+                write!(f, "widened a range to {:?}..{:?}", start, end)
+            }
+        }
+    }
+}
+
+/// Returns every mutant of `rules` with a single mutation, in the order of the rules.
+///
+/// # Examples
+///
+/// ```
+/// # use pest_meta::ast::{Expr, Rule, RuleType};
+/// # use pest_meta::mutation::{mutants, MutationKind};
+/// let rules = vec![Rule {
+///     name: "digit".to_owned(),
+///     ty: RuleType::Normal,
+///     expr: Expr::Range("1".to_owned(), "8".to_owned()),
+/// }];
+///
+/// let mutants = mutants(&rules);
+///
+/// assert_eq!(mutants.len(), 1);
+/// assert_eq!(mutants[0].kind, MutationKind::WidenRange { start: '0', end: '9' });
+/// assert_eq!(mutants[0].rules[0].expr, Expr::Range("0".to_owned(), "9".to_owned()));
+/// ```
+pub fn mutants(rules: &[Rule]) -> Vec<Mutant> {
+    let mut mutants = vec![];
+
+    for (i, rule) in rules.iter().enumerate() {
+        for (site, (kind, expr)) in mutate(&rule.expr).into_iter().enumerate() {
+            let mut rules = rules.to_vec();
+            rules[i].expr = expr;
+
+            mutants.push(Mutant {
+                rule: rule.name.clone(),
+                site,
+                kind,
+                rules,
+            });
+        }
+    }
+
+    mutants
+}
+
+/// Parses `grammar` and returns the mutants of its rules like [`mutants`].
+///
+/// [`mutants`]: fn.mutants.html
+#[allow(clippy::perf)]
+pub fn mutate_grammar(grammar: &str) -> Result<Vec<Mutant>, Vec<Error<parser::Rule>>> {
+    let pairs = parser::parse(parser::Rule::grammar_rules, grammar).map_err(|error| vec![error])?;
+
+    Ok(mutants(&parser::consume_rules(pairs)?))
+}
+
+// Returns the mutations of `expr` itself, followed by those of its sub-expressions.
+fn mutate(expr: &Expr) -> Vec<(MutationKind, Expr)> {
+    let mut mutations = vec![];
+
+    match expr {
+        Expr::Choice(lhs, rhs) => mutations.push((
+            MutationKind::SwapChoice,
+            Expr::Choice(rhs.clone(), lhs.clone()),
+        )),
+        Expr::Opt(inner) => mutations.push((MutationKind::DropOptional, (**inner).clone())),
+        Expr::Range(start, end) => {
+            if let Some((start, end)) = widen(start, end) {
+                mutations.push((
+                    MutationKind::WidenRange { start, end },
+                    Expr::Range(start.to_string(), end.to_string()),
+                ));
+            }
+        }
+        _ => (),
+    }
+
+    let unary = |inner: &Expr, f: &dyn Fn(Box<Expr>) -> Expr| {
+        mutate(inner)
+            .into_iter()
+            .map(|(kind, inner)| (kind, f(Box::new(inner))))
+            .collect::<Vec<_>>()
+    };
+
+    mutations.extend(match expr {
+        Expr::PosPred(inner) => unary(inner, &Expr::PosPred),
+        Expr::NegPred(inner) => unary(inner, &Expr::NegPred),
+        Expr::PosLookbehind(inner) => unary(inner, &Expr::PosLookbehind),
+        Expr::Opt(inner) => unary(inner, &Expr::Opt),
+        Expr::Rep(inner) => unary(inner, &Expr::Rep),
+        Expr::RepOnce(inner) => unary(inner, &Expr::RepOnce),
+        Expr::Push(inner) => unary(inner, &Expr::Push),
+        Expr::RepExact(inner, n) => unary(inner, &|inner| Expr::RepExact(inner, *n)),
+        Expr::RepMin(inner, n) => unary(inner, &|inner| Expr::RepMin(inner, *n)),
+        Expr::RepMax(inner, n) => unary(inner, &|inner| Expr::RepMax(inner, *n)),
+        Expr::RepMinMax(inner, min, max) => {
+            unary(inner, &|inner| Expr::RepMinMax(inner, *min, *max))
+        }
+        Expr::Labeled(label, inner) => unary(inner, &|inner| Expr::Labeled(label.clone(), inner)),
+        Expr::Predicate(name, inner) => unary(inner, &|inner| Expr::Predicate(name.clone(), inner)),
+        Expr::Seq(lhs, rhs) => binary(lhs, rhs, &Expr::Seq),
+        Expr::Choice(lhs, rhs) => binary(lhs, rhs, &Expr::Choice),
+        Expr::Dialect(dialects, then, otherwise) => binary(then, otherwise, &|then, otherwise| {
+            Expr::Dialect(dialects.clone(), then, otherwise)
+        }),
+        _ => vec![],
+    });
+
+    mutations
+}
+
+fn binary(
+    lhs: &Expr,
+    rhs: &Expr,
+    f: &dyn Fn(Box<Expr>, Box<Expr>) -> Expr,
+) -> Vec<(MutationKind, Expr)> {
+    let lhs_mutations = mutate(lhs)
+        .into_iter()
+        .map(|(kind, lhs)| (kind, f(Box::new(lhs), Box::new(rhs.clone()))));
+    let rhs_mutations = mutate(rhs)
+        .into_iter()
+        .map(|(kind, rhs)| (kind, f(Box::new(lhs.clone()), Box::new(rhs))));
+
+    lhs_mutations.chain(rhs_mutations).collect()
+}
+
+// Widens the range by one character on each side, as far as there are characters to widen to.
+fn widen(start: &str, end: &str) -> Option<(char, char)> {
+    let start = start.chars().next()?;
+    let end = end.chars().next()?;
+
+    let widened_start = (start as u32)
+        .checked_sub(1)
+        .and_then(char::from_u32)
+        .unwrap_or(start);
+    let widened_end = char::from_u32(end as u32 + 1).unwrap_or(end);
+
+    if (widened_start, widened_end) == (start, end) {
+        None
+    } else {
+        Some((widened_start, widened_end))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn descriptions(grammar: &str) -> Vec<String> {
+        mutate_grammar(grammar)
+            .unwrap()
+            .iter()
+            .map(|mutant| mutant.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn mutations() {
+        assert_eq!(
+            descriptions(r#"a = { ("a" | 'b'..'y')? ~ b } b = { "b"* }"#),
+            [
+                "rule a, mutation #1: made an optional expression mandatory",
+                "rule a, mutation #2: swapped the alternatives of a choice",
+                "rule a, mutation #3: widened a range to 'a'..'z'",
+            ]
+        );
+    }
+
+    #[test]
+    fn mutated_rules() {
+        let mutants = mutate_grammar(r#"a = { "a" ~ ("b" | "c") } b = { "b" }"#).unwrap();
+        let pairs = parser::parse(
+            parser::Rule::grammar_rules,
+            r#"a = { "a" ~ ("c" | "b") } b = { "b" }"#,
+        )
+        .unwrap();
+
+        assert_eq!(mutants.len(), 1);
+        assert_eq!(mutants[0].kind, MutationKind::SwapChoice);
+        assert_eq!(mutants[0].rules, parser::consume_rules(pairs).unwrap());
+    }
+
+    #[test]
+    fn range_bounds() {
+        assert_eq!(widen("\u{0}", "\u{10ffff}"), None);
+        assert_eq!(widen("\u{0}", "a"), Some(('\u{0}', 'b')));
+        // Surrogates are not characters.
+        assert_eq!(
+            widen("\u{e000}", "\u{e001}"),
+            Some(('\u{e000}', '\u{e002}'))
+        );
+    }
+}
@@ -0,0 +1,129 @@
+// pest. The Elegant Parser
+// Copyright (c) 2018 Dragoș Tiselice
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+use crate::ast::RuleType;
+use crate::optimizer::*;
+
+pub fn complement(rule: OptimizedRule) -> OptimizedRule {
+    let OptimizedRule { name, ty, expr } = rule;
+    OptimizedRule {
+        name,
+        ty,
+        // Whitespace is skipped between `!e` and `ANY` in non-atomic rules.
+        expr: if ty == RuleType::Atomic || ty == RuleType::CompoundAtomic {
+            expr.map_bottom_up(|expr| {
+                if let OptimizedExpr::Seq(ref lhs, ref rhs) = expr {
+                    if let OptimizedExpr::NegPred(ref negated) = **lhs {
+                        // Converts `!e ~ ANY` to a check of the character against the ranges of
+                        // `e`, which then only needs to be matched once.
+                        if let Some(ranges) = char_ranges(negated) {
+                            match **rhs {
+                                ref any if is_any(any) => {
+                                    return OptimizedExpr::Complement(ranges);
+                                }
+                                OptimizedExpr::Seq(ref any, ref rest) if is_any(any) => {
+                                    return OptimizedExpr::Seq(
+                                        Box::new(OptimizedExpr::Complement(ranges)),
+                                        rest.clone(),
+                                    );
+                                }
+                                _ => (),
+                            }
+                        }
+                    }
+                }
+
+                expr
+            })
+        } else {
+            expr
+        },
+    }
+}
+
+fn is_any(expr: &OptimizedExpr) -> bool {
+    matches!(*expr, OptimizedExpr::Ident(ref ident) if ident == "ANY")
+}
+
+// Returns the sorted and merged ranges of the characters matched by `expr`, if it only ever matches
+// a single character.
+fn char_ranges(expr: &OptimizedExpr) -> Option<Vec<(char, char)>> {
+    fn collect(expr: &OptimizedExpr, ranges: &mut Vec<(char, char)>) -> Option<()> {
+        match *expr {
+            OptimizedExpr::Str(ref string) => {
+                let c = single_char(string)?;
+                ranges.push((c, c));
+            }
+            OptimizedExpr::Insens(ref string) => {
+                let c = single_char(string)?;
+                let (lower, upper) = (c.to_ascii_lowercase(), c.to_ascii_uppercase());
+                ranges.push((lower, lower));
+                ranges.push((upper, upper));
+            }
+            OptimizedExpr::Range(ref start, ref end) => {
+                let (start, end) = (single_char(start)?, single_char(end)?);
+                if start > end {
+                    return None;
+                }
+                ranges.push((start, end));
+            }
+            OptimizedExpr::Choice(ref lhs, ref rhs) => {
+                collect(lhs, ranges)?;
+                collect(rhs, ranges)?;
+            }
+            OptimizedExpr::Ident(ref ident) => ranges.extend_from_slice(builtin_ranges(ident)?),
+            _ => return None,
+        }
+
+        Some(())
+    }
+
+    let mut ranges = vec![];
+    collect(expr, &mut ranges)?;
+    ranges.sort_unstable();
+
+    let mut merged: Vec<(char, char)> = Vec::with_capacity(ranges.len());
+    for (start, end) in ranges {
+        match merged.last_mut() {
+            Some(last) if start as u32 <= last.1 as u32 + 1 => last.1 = last.1.max(end),
+            _ => merged.push((start, end)),
+        }
+    }
+
+    Some(merged)
+}
+
+fn single_char(string: &str) -> Option<char> {
+    let mut chars = string.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) => Some(c),
+        _ => None,
+    }
+}
+
+// The ranges of the built-in rules which match a single character. `NEWLINE` also matches "\r\n",
+// but not matching it is the same as matching neither '\r' nor '\n'.
+fn builtin_ranges(ident: &str) -> Option<&'static [(char, char)]> {
+    let ranges: &'static [(char, char)] = match ident {
+        "ASCII_DIGIT" => &[('0', '9')],
+        "ASCII_NONZERO_DIGIT" => &[('1', '9')],
+        "ASCII_BIN_DIGIT" => &[('0', '1')],
+        "ASCII_OCT_DIGIT" => &[('0', '7')],
+        "ASCII_HEX_DIGIT" => &[('0', '9'), ('A', 'F'), ('a', 'f')],
+        "ASCII_ALPHA_LOWER" => &[('a', 'z')],
+        "ASCII_ALPHA_UPPER" => &[('A', 'Z')],
+        "ASCII_ALPHA" => &[('A', 'Z'), ('a', 'z')],
+        "ASCII_ALPHANUMERIC" => &[('0', '9'), ('A', 'Z'), ('a', 'z')],
+        "ASCII" => &[('\x00', '\x7f')],
+        "NEWLINE" => &[('\n', '\n'), ('\r', '\r')],
+        _ => return None,
+    };
+
+    Some(ranges)
+}
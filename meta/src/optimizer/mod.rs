@@ -11,6 +11,7 @@
 
 use crate::ast::*;
 use std::collections::HashMap;
+use std::{error, fmt};
 
 #[cfg(test)]
 macro_rules! box_tree {
@@ -20,6 +21,7 @@ macro_rules! box_tree {
     ($expr:expr) => ($expr);
 }
 
+mod complementer;
 mod concatenator;
 mod factorizer;
 mod lister;
@@ -28,9 +30,96 @@ mod rotater;
 mod skipper;
 mod unroller;
 
+/// A custom optimizer pass, which rewrites the rules of a whole grammar.
+///
+/// See [`optimize_with_passes`](fn.optimize_with_passes.html).
+pub type OptimizerPass = fn(Vec<OptimizedRule>) -> Vec<OptimizedRule>;
+
+/// An error returned when an AST cannot be optimized, which only happens for ASTs which were not
+/// produced by [`consume_rules`](../parser/fn.consume_rules.html).
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct OptimizerError {
+    /// The name of the rule containing the error
+    pub rule: String,
+    /// The error message
+    pub message: String,
+}
+
+impl fmt::Display for OptimizerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "rule {}: {}", self.rule, self.message)
+    }
+}
+
+impl error::Error for OptimizerError {}
+
 /// Takes pest's ASTs and optimizes them
+///
+/// # Panics
+///
+/// Panics if a lookbehind expression of `rules` does not have a fixed width. See
+/// [`try_optimize`](fn.try_optimize.html).
 pub fn optimize(rules: Vec<Rule>) -> Vec<OptimizedRule> {
-    let optimized: Vec<OptimizedRule> = rules
+    optimize_with_passes(rules, &[])
+}
+
+/// Same as [`optimize`](fn.optimize.html), but returns an error instead of panicking if a
+/// lookbehind expression of `rules` does not have a fixed width.
+pub fn try_optimize(rules: Vec<Rule>) -> Result<Vec<OptimizedRule>, OptimizerError> {
+    try_optimize_with_passes(rules, &[])
+}
+
+/// Takes pest's ASTs and optimizes them, then runs the custom `passes` on the optimized rules, in
+/// order.
+///
+/// This makes it possible to apply domain-specific rewrites to a grammar before generating code
+/// or constructing a VM from it. Custom passes must keep the grammar valid, e.g. they should not
+/// remove rules that are still referenced.
+///
+/// # Examples
+///
+/// ```
+/// # use pest_meta::ast::{Expr, Rule, RuleType};
+/// # use pest_meta::optimizer::{optimize_with_passes, OptimizedExpr, OptimizedRule};
+/// fn uppercase(rules: Vec<OptimizedRule>) -> Vec<OptimizedRule> {
+///     rules
+///         .into_iter()
+///         .map(|rule| OptimizedRule {
+///             expr: rule.expr.map_bottom_up(|expr| match expr {
+///                 OptimizedExpr::Str(string) => OptimizedExpr::Str(string.to_uppercase()),
+///                 expr => expr,
+///             }),
+///             ..rule
+///         })
+///         .collect()
+/// }
+///
+/// let rules = vec![Rule {
+///     name: "a".to_owned(),
+///     ty: RuleType::Normal,
+///     expr: Expr::Str("a".to_owned()),
+/// }];
+///
+/// let optimized = optimize_with_passes(rules, &[uppercase]);
+///
+/// assert_eq!(optimized[0].expr, OptimizedExpr::Str("A".to_owned()));
+/// ```
+///
+/// # Panics
+///
+/// Panics if a lookbehind expression of `rules` does not have a fixed width. See
+/// [`try_optimize_with_passes`](fn.try_optimize_with_passes.html).
+pub fn optimize_with_passes(rules: Vec<Rule>, passes: &[OptimizerPass]) -> Vec<OptimizedRule> {
+    try_optimize_with_passes(rules, passes).unwrap_or_else(|error| panic!("{}", error))
+}
+
+/// Same as [`optimize_with_passes`](fn.optimize_with_passes.html), but returns an error instead of
+/// panicking if a lookbehind expression of `rules` does not have a fixed width.
+pub fn try_optimize_with_passes(
+    rules: Vec<Rule>,
+    passes: &[OptimizerPass],
+) -> Result<Vec<OptimizedRule>, OptimizerError> {
+    let optimized = rules
         .into_iter()
         .map(rotater::rotate)
         .map(skipper::skip)
@@ -38,48 +127,72 @@ pub fn optimize(rules: Vec<Rule>) -> Vec<OptimizedRule> {
         .map(concatenator::concatenate)
         .map(factorizer::factor)
         .map(lister::list)
-        .map(rule_to_optimized_rule)
-        .collect();
+        .map(|rule| rule_to_optimized_rule(rule).map(complementer::complement))
+        .collect::<Result<Vec<_>, _>>()?;
 
     let rules = to_hash_map(&optimized);
-    optimized
+    let optimized = optimized
         .into_iter()
         .map(|rule| restorer::restore_on_err(rule, &rules))
-        .collect()
+        .collect();
+
+    Ok(passes.iter().fold(optimized, |rules, pass| pass(rules)))
 }
 
-fn rule_to_optimized_rule(rule: Rule) -> OptimizedRule {
-    fn to_optimized(expr: Expr) -> OptimizedExpr {
-        match expr {
+fn rule_to_optimized_rule(rule: Rule) -> Result<OptimizedRule, OptimizerError> {
+    // Returns `None` if a lookbehind expression does not have a fixed width.
+    fn to_optimized(expr: Expr) -> Option<OptimizedExpr> {
+        Some(match expr {
             Expr::Str(string) => OptimizedExpr::Str(string),
             Expr::Insens(string) => OptimizedExpr::Insens(string),
             Expr::Range(start, end) => OptimizedExpr::Range(start, end),
             Expr::Ident(ident) => OptimizedExpr::Ident(ident),
             Expr::PeekSlice(start, end) => OptimizedExpr::PeekSlice(start, end),
-            Expr::PosPred(expr) => OptimizedExpr::PosPred(Box::new(to_optimized(*expr))),
-            Expr::NegPred(expr) => OptimizedExpr::NegPred(Box::new(to_optimized(*expr))),
+            Expr::PosPred(expr) => OptimizedExpr::PosPred(Box::new(to_optimized(*expr)?)),
+            Expr::NegPred(expr) => OptimizedExpr::NegPred(Box::new(to_optimized(*expr)?)),
+            Expr::PosLookbehind(expr) => {
+                let width = expr.fixed_width()?;
+                OptimizedExpr::PosLookbehind(Box::new(to_optimized(*expr)?), width)
+            }
             Expr::Seq(lhs, rhs) => {
-                OptimizedExpr::Seq(Box::new(to_optimized(*lhs)), Box::new(to_optimized(*rhs)))
+                OptimizedExpr::Seq(Box::new(to_optimized(*lhs)?), Box::new(to_optimized(*rhs)?))
             }
             Expr::Choice(lhs, rhs) => {
-                OptimizedExpr::Choice(Box::new(to_optimized(*lhs)), Box::new(to_optimized(*rhs)))
+                OptimizedExpr::Choice(Box::new(to_optimized(*lhs)?), Box::new(to_optimized(*rhs)?))
             }
-            Expr::Opt(expr) => OptimizedExpr::Opt(Box::new(to_optimized(*expr))),
-            Expr::Rep(expr) => OptimizedExpr::Rep(Box::new(to_optimized(*expr))),
+            Expr::Opt(expr) => OptimizedExpr::Opt(Box::new(to_optimized(*expr)?)),
+            Expr::Rep(expr) => OptimizedExpr::Rep(Box::new(to_optimized(*expr)?)),
             Expr::Skip(strings) => OptimizedExpr::Skip(strings),
-            Expr::Push(expr) => OptimizedExpr::Push(Box::new(to_optimized(*expr))),
+            Expr::Push(expr) => OptimizedExpr::Push(Box::new(to_optimized(*expr)?)),
+            Expr::Labeled(label, expr) => {
+                OptimizedExpr::Labeled(label, Box::new(to_optimized(*expr)?))
+            }
+            Expr::Predicate(name, expr) => {
+                OptimizedExpr::Predicate(name, Box::new(to_optimized(*expr)?))
+            }
+            Expr::Dialect(dialects, then, otherwise) => OptimizedExpr::Dialect(
+                dialects,
+                Box::new(to_optimized(*then)?),
+                Box::new(to_optimized(*otherwise)?),
+            ),
             Expr::RepOnce(_)
             | Expr::RepExact(..)
             | Expr::RepMin(..)
             | Expr::RepMax(..)
             | Expr::RepMinMax(..) => unreachable!("No valid transformation to OptimizedRule"),
-        }
+        })
     }
 
-    OptimizedRule {
-        name: rule.name,
-        ty: rule.ty,
-        expr: to_optimized(rule.expr),
+    match to_optimized(rule.expr) {
+        Some(expr) => Ok(OptimizedRule {
+            name: rule.name,
+            ty: rule.ty,
+            expr,
+        }),
+        None => Err(OptimizerError {
+            rule: rule.name,
+            message: "lookbehind expressions must have a fixed width".to_owned(),
+        }),
     }
 }
 
@@ -118,6 +231,9 @@ pub enum OptimizedExpr {
     PosPred(Box<OptimizedExpr>),
     /// Negative lookahead; matches if expression doesn't match, without making progress, e.g. `!e`
     NegPred(Box<OptimizedExpr>),
+    /// Positive lookbehind; matches if the input right before the current position matches the
+    /// expression, which is the given number of bytes wide, without making progress, e.g. `<&e`
+    PosLookbehind(Box<OptimizedExpr>, usize),
     /// Matches a sequence of two expressions, e.g. `e1 ~ e2`
     Seq(Box<OptimizedExpr>, Box<OptimizedExpr>),
     /// Matches either of two expressions, e.g. `e1 | e2`
@@ -128,8 +244,20 @@ pub enum OptimizedExpr {
     Rep(Box<OptimizedExpr>),
     /// Continues to match expressions until one of the strings in the `Vec` is found
     Skip(Vec<String>),
+    /// Matches one character outside of the sorted and disjoint inclusive ranges, e.g.
+    /// `!("_" | 'a'..'z') ~ ANY` in an atomic rule
+    Complement(Vec<(char, char)>),
     /// Matches an expression and pushes it to the stack, e.g. `push(e)`
     Push(Box<OptimizedExpr>),
+    /// Matches an expression, labeling the errors within it for context without producing a
+    /// pair, e.g. `#"argument list" e`
+    Labeled(String, Box<OptimizedExpr>),
+    /// Matches an expression, then fails unless the semantic predicate with the given name
+    /// accepts the input it matched, e.g. `#{is_type_name} e`
+    Predicate(String, Box<OptimizedExpr>),
+    /// Matches the first expression when parsing one of the given dialects, and the second one
+    /// otherwise, e.g. for the definitions of a rule annotated with `@dialect(lenient)`
+    Dialect(Vec<String>, Box<OptimizedExpr>, Box<OptimizedExpr>),
     /// Restores an expression's checkpoint
     RestoreOnErr(Box<OptimizedExpr>),
 }
@@ -161,6 +289,10 @@ impl OptimizedExpr {
                     let mapped = Box::new(map_internal(*expr, f));
                     OptimizedExpr::NegPred(mapped)
                 }
+                OptimizedExpr::PosLookbehind(expr, width) => {
+                    let mapped = Box::new(map_internal(*expr, f));
+                    OptimizedExpr::PosLookbehind(mapped, width)
+                }
                 OptimizedExpr::Seq(lhs, rhs) => {
                     let mapped_lhs = Box::new(map_internal(*lhs, f));
                     let mapped_rhs = Box::new(map_internal(*rhs, f));
@@ -183,6 +315,19 @@ impl OptimizedExpr {
                     let mapped = Box::new(map_internal(*expr, f));
                     OptimizedExpr::Push(mapped)
                 }
+                OptimizedExpr::Labeled(label, expr) => {
+                    let mapped = Box::new(map_internal(*expr, f));
+                    OptimizedExpr::Labeled(label, mapped)
+                }
+                OptimizedExpr::Predicate(name, expr) => {
+                    let mapped = Box::new(map_internal(*expr, f));
+                    OptimizedExpr::Predicate(name, mapped)
+                }
+                OptimizedExpr::Dialect(dialects, then, otherwise) => {
+                    let mapped_then = Box::new(map_internal(*then, f));
+                    let mapped_otherwise = Box::new(map_internal(*otherwise, f));
+                    OptimizedExpr::Dialect(dialects, mapped_then, mapped_otherwise)
+                }
                 expr => expr,
             }
         }
@@ -209,6 +354,10 @@ impl OptimizedExpr {
                     let mapped = Box::new(map_internal(*expr, f));
                     OptimizedExpr::NegPred(mapped)
                 }
+                OptimizedExpr::PosLookbehind(expr, width) => {
+                    let mapped = Box::new(map_internal(*expr, f));
+                    OptimizedExpr::PosLookbehind(mapped, width)
+                }
                 OptimizedExpr::Seq(lhs, rhs) => {
                     let mapped_lhs = Box::new(map_internal(*lhs, f));
                     let mapped_rhs = Box::new(map_internal(*rhs, f));
@@ -231,6 +380,19 @@ impl OptimizedExpr {
                     let mapped = Box::new(map_internal(*expr, f));
                     OptimizedExpr::Push(mapped)
                 }
+                OptimizedExpr::Labeled(label, expr) => {
+                    let mapped = Box::new(map_internal(*expr, f));
+                    OptimizedExpr::Labeled(label, mapped)
+                }
+                OptimizedExpr::Predicate(name, expr) => {
+                    let mapped = Box::new(map_internal(*expr, f));
+                    OptimizedExpr::Predicate(name, mapped)
+                }
+                OptimizedExpr::Dialect(dialects, then, otherwise) => {
+                    let mapped_then = Box::new(map_internal(*then, f));
+                    let mapped_otherwise = Box::new(map_internal(*otherwise, f));
+                    OptimizedExpr::Dialect(dialects, mapped_then, mapped_otherwise)
+                }
                 expr => expr,
             };
 
@@ -267,15 +429,18 @@ impl OptimizedExprTopDownIterator {
                 self.right_branches.push(*rhs);
                 self.next = Some(*lhs);
             }
-            OptimizedExpr::Choice(lhs, rhs) => {
+            OptimizedExpr::Choice(lhs, rhs) | OptimizedExpr::Dialect(_, lhs, rhs) => {
                 self.right_branches.push(*rhs);
                 self.next = Some(*lhs);
             }
             OptimizedExpr::PosPred(expr)
             | OptimizedExpr::NegPred(expr)
+            | OptimizedExpr::PosLookbehind(expr, _)
             | OptimizedExpr::Rep(expr)
             | OptimizedExpr::Opt(expr)
-            | OptimizedExpr::Push(expr) => {
+            | OptimizedExpr::Push(expr)
+            | OptimizedExpr::Labeled(_, expr)
+            | OptimizedExpr::Predicate(_, expr) => {
                 self.next = Some(*expr);
             }
             _ => {
@@ -305,6 +470,23 @@ impl Iterator for OptimizedExprTopDownIterator {
 mod tests {
     use super::*;
 
+    #[test]
+    fn lookbehind_without_fixed_width() {
+        let rules = vec![Rule {
+            name: "rule".to_owned(),
+            ty: RuleType::Normal,
+            expr: Expr::PosLookbehind(Box::new(Expr::Rep(Box::new(Expr::Str("a".to_owned()))))),
+        }];
+
+        assert_eq!(
+            try_optimize(rules),
+            Err(OptimizerError {
+                rule: "rule".to_owned(),
+                message: "lookbehind expressions must have a fixed width".to_owned(),
+            })
+        );
+    }
+
     #[test]
     fn rotate() {
         let rules = {
@@ -361,6 +543,87 @@ mod tests {
         assert_eq!(optimize(rules), skipped);
     }
 
+    #[test]
+    fn complement() {
+        let rules = {
+            use crate::ast::Expr::*;
+            vec![
+                Rule {
+                    name: "line".to_owned(),
+                    ty: RuleType::Atomic,
+                    expr: box_tree!(Seq(
+                        Opt(Seq(
+                            NegPred(Ident(String::from("NEWLINE"))),
+                            Ident(String::from("ANY"))
+                        )),
+                        Seq(
+                            Seq(
+                                NegPred(Choice(
+                                    Range(String::from("a"), String::from("z")),
+                                    Choice(Insens(String::from("_")), Insens(String::from("A")))
+                                )),
+                                Ident(String::from("ANY"))
+                            ),
+                            Str(String::from(";"))
+                        )
+                    )),
+                },
+                Rule {
+                    name: "word".to_owned(),
+                    ty: RuleType::Atomic,
+                    expr: box_tree!(Seq(
+                        NegPred(Str(String::from("ab"))),
+                        Ident(String::from("ANY"))
+                    )),
+                },
+                Rule {
+                    name: "spaced".to_owned(),
+                    ty: RuleType::Normal,
+                    expr: box_tree!(Seq(
+                        NegPred(Str(String::from("a"))),
+                        Ident(String::from("ANY"))
+                    )),
+                },
+            ]
+        };
+        let complemented = {
+            use crate::optimizer::OptimizedExpr::*;
+            vec![
+                OptimizedRule {
+                    name: "line".to_owned(),
+                    ty: RuleType::Atomic,
+                    expr: box_tree!(Seq(
+                        Opt(Complement(vec![('\n', '\n'), ('\r', '\r')])),
+                        Seq(
+                            Complement(vec![('A', 'A'), ('_', '_'), ('a', 'z')]),
+                            Str(String::from(";"))
+                        )
+                    )),
+                },
+                // Multi-character strings are matched as usual.
+                OptimizedRule {
+                    name: "word".to_owned(),
+                    ty: RuleType::Atomic,
+                    expr: box_tree!(Seq(
+                        NegPred(Str(String::from("ab"))),
+                        Ident(String::from("ANY"))
+                    )),
+                },
+                // So are the expressions of non-atomic rules, which skip whitespace before `ANY`.
+                OptimizedRule {
+                    name: "spaced".to_owned(),
+                    ty: RuleType::Normal,
+                    expr: box_tree!(Seq(
+                        NegPred(Str(String::from("a"))),
+                        Ident(String::from("ANY"))
+                    )),
+                },
+            ]
+        };
+
+        assert_eq!(optimize(rules), complemented);
+    }
+
     #[test]
     fn concat_strings() {
         let rules = {
@@ -614,4 +877,45 @@ mod tests {
 
         assert_eq!(optimize(rules), optimized);
     }
+
+    #[test]
+    fn custom_passes() {
+        fn rename(rules: Vec<OptimizedRule>) -> Vec<OptimizedRule> {
+            rules
+                .into_iter()
+                .map(|rule| OptimizedRule {
+                    name: rule.name + "_renamed",
+                    ..rule
+                })
+                .collect()
+        }
+
+        fn reverse(mut rules: Vec<OptimizedRule>) -> Vec<OptimizedRule> {
+            rules.reverse();
+            rules
+        }
+
+        let rules = vec![
+            Rule {
+                name: "a".to_owned(),
+                ty: RuleType::Normal,
+                expr: Expr::Str("a".to_owned()),
+            },
+            Rule {
+                name: "b".to_owned(),
+                ty: RuleType::Normal,
+                expr: Expr::Str("b".to_owned()),
+            },
+        ];
+
+        let optimized = optimize_with_passes(rules, &[rename, reverse]);
+
+        assert_eq!(
+            optimized
+                .iter()
+                .map(|rule| rule.name.as_str())
+                .collect::<Vec<_>>(),
+            ["b_renamed", "a_renamed"]
+        );
+    }
 }
@@ -11,6 +11,7 @@
 
 use std::char;
 use std::iter::Peekable;
+use std::mem;
 
 use pest::error::{Error, ErrorVariant};
 use pest::iterators::{Pair, Pairs};
@@ -18,7 +19,7 @@ use pest::pratt_parser::{Assoc, Op, PrattParser};
 use pest::{Parser, Span};
 
 use crate::ast::{Expr, Rule as AstRule, RuleType};
-use crate::validator;
+use crate::validator::{self, LeftRecursion};
 
 /// TODO: fix the generator to at least add explicit lifetimes
 #[allow(
@@ -46,12 +47,31 @@ pub struct ParserRule<'i> {
     pub name: String,
     /// The rule's span
     pub span: Span<'i>,
+    docs: Vec<&'i str>,
     /// The rule's type
     pub ty: RuleType,
     /// The rule's parser node
     pub node: ParserNode<'i>,
 }
 
+impl<'i> ParserRule<'i> {
+    /// Creates a rule without doc comments.
+    pub fn new(name: String, span: Span<'i>, ty: RuleType, node: ParserNode<'i>) -> Self {
+        ParserRule {
+            name,
+            span,
+            docs: Vec::new(),
+            ty,
+            node,
+        }
+    }
+
+    /// Returns the rule's doc comments (`/// ...`), one per line.
+    pub fn docs(&self) -> &[&'i str] {
+        &self.docs
+    }
+}
+
 /// The pest grammar node
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub struct ParserNode<'i> {
@@ -83,6 +103,9 @@ impl<'i> ParserNode<'i> {
                 ParserExpr::NegPred(node) => {
                     filter_internal(*node, f, result);
                 }
+                ParserExpr::PosLookbehind(node) => {
+                    filter_internal(*node, f, result);
+                }
                 ParserExpr::Seq(lhs, rhs) => {
                     filter_internal(*lhs, f, result);
                     filter_internal(*rhs, f, result);
@@ -115,6 +138,16 @@ impl<'i> ParserNode<'i> {
                 ParserExpr::Push(node) => {
                     filter_internal(*node, f, result);
                 }
+                ParserExpr::Labeled(_, node) => {
+                    filter_internal(*node, f, result);
+                }
+                ParserExpr::Predicate(_, node) => {
+                    filter_internal(*node, f, result);
+                }
+                ParserExpr::Dialect(_, then, otherwise) => {
+                    filter_internal(*then, f, result);
+                    filter_internal(*otherwise, f, result);
+                }
                 _ => (),
             }
         }
@@ -144,6 +177,9 @@ pub enum ParserExpr<'i> {
     PosPred(Box<ParserNode<'i>>),
     /// Negative lookahead; matches if expression doesn't match, without making progress, e.g. `!e`
     NegPred(Box<ParserNode<'i>>),
+    /// Positive lookbehind; matches if the input right before the current position matches the
+    /// fixed-width expression, without making progress, e.g. `<&e`
+    PosLookbehind(Box<ParserNode<'i>>),
     /// Matches a sequence of two expressions, e.g. `e1 ~ e2`
     Seq(Box<ParserNode<'i>>, Box<ParserNode<'i>>),
     /// Matches either of two expressions, e.g. `e1 | e2`
@@ -164,6 +200,16 @@ pub enum ParserExpr<'i> {
     RepMinMax(Box<ParserNode<'i>>, u32, u32),
     /// Matches an expression and pushes it to the stack, e.g. `push(e)`
     Push(Box<ParserNode<'i>>),
+    /// Matches an expression, labeling the errors within it for context without producing a
+    /// pair, e.g. `#"argument list" e`
+    Labeled(String, Box<ParserNode<'i>>),
+    /// Matches an expression, then fails unless the semantic predicate with the given name
+    /// accepts the input it matched, e.g. `#{is_type_name} e`
+    Predicate(String, Box<ParserNode<'i>>),
+    /// Matches the first expression when parsing one of the given dialects, and the second one
+    /// otherwise. This is how the definitions of a rule for the dialects of `@dialect`
+    /// annotations are merged into a single rule.
+    Dialect(Vec<String>, Box<ParserNode<'i>>, Box<ParserNode<'i>>),
 }
 
 fn convert_rule(rule: ParserRule<'_>) -> AstRule {
@@ -181,6 +227,7 @@ fn convert_node(node: ParserNode<'_>) -> Expr {
         ParserExpr::PeekSlice(start, end) => Expr::PeekSlice(start, end),
         ParserExpr::PosPred(node) => Expr::PosPred(Box::new(convert_node(*node))),
         ParserExpr::NegPred(node) => Expr::NegPred(Box::new(convert_node(*node))),
+        ParserExpr::PosLookbehind(node) => Expr::PosLookbehind(Box::new(convert_node(*node))),
         ParserExpr::Seq(node1, node2) => Expr::Seq(
             Box::new(convert_node(*node1)),
             Box::new(convert_node(*node2)),
@@ -199,13 +246,32 @@ fn convert_node(node: ParserNode<'_>) -> Expr {
             Expr::RepMinMax(Box::new(convert_node(*node)), min, max)
         }
         ParserExpr::Push(node) => Expr::Push(Box::new(convert_node(*node))),
+        ParserExpr::Labeled(label, node) => Expr::Labeled(label, Box::new(convert_node(*node))),
+        ParserExpr::Predicate(name, node) => Expr::Predicate(name, Box::new(convert_node(*node))),
+        ParserExpr::Dialect(dialects, then, otherwise) => Expr::Dialect(
+            dialects,
+            Box::new(convert_node(*then)),
+            Box::new(convert_node(*otherwise)),
+        ),
     }
 }
 
 /// Converts a parser's result (`Pairs`) to an AST
 pub fn consume_rules(pairs: Pairs<'_, Rule>) -> Result<Vec<AstRule>, Vec<Error<Rule>>> {
+    consume_rules_with(pairs, LeftRecursion::Error)
+}
+
+/// Same as [`consume_rules`], but left-recursive rules are only reported with
+/// [`LeftRecursion::Error`].
+///
+/// [`consume_rules`]: fn.consume_rules.html
+/// [`LeftRecursion::Error`]: ../validator/enum.LeftRecursion.html#variant.Error
+pub fn consume_rules_with(
+    pairs: Pairs<'_, Rule>,
+    left_recursion: LeftRecursion,
+) -> Result<Vec<AstRule>, Vec<Error<Rule>>> {
     let rules = consume_rules_with_spans(pairs)?;
-    let errors = validator::validate_ast(&rules);
+    let errors = validator::validate_ast_with(&rules, left_recursion);
     if errors.is_empty() {
         Ok(rules.into_iter().map(convert_rule).collect())
     } else {
@@ -220,6 +286,7 @@ pub fn rename_meta_rule(rule: &Rule) -> String {
     match *rule {
         Rule::grammar_rule => "rule".to_owned(),
         Rule::_push => "PUSH".to_owned(),
+        Rule::list => "LIST".to_owned(),
         Rule::assignment_operator => "`=`".to_owned(),
         Rule::silent_modifier => "`_`".to_owned(),
         Rule::atomic_modifier => "`@`".to_owned(),
@@ -232,6 +299,9 @@ pub fn rename_meta_rule(rule: &Rule) -> String {
         Rule::opening_paren => "`(`".to_owned(),
         Rule::positive_predicate_operator => "`&`".to_owned(),
         Rule::negative_predicate_operator => "`!`".to_owned(),
+        Rule::positive_lookbehind_operator => "`<&`".to_owned(),
+        Rule::group_label => "`#`".to_owned(),
+        Rule::semantic_predicate => "`#{`".to_owned(),
         Rule::sequence_operator => "`&`".to_owned(),
         Rule::choice_operator => "`|`".to_owned(),
         Rule::optional_operator => "`?`".to_owned(),
@@ -247,16 +317,37 @@ pub fn rename_meta_rule(rule: &Rule) -> String {
     }
 }
 
-fn consume_rules_with_spans(
+/// Converts a parser's result (`Pairs`) to the spanned `ParserRule`s, without validating them
+///
+/// The rules generated by `@keywords`, `@alias` and `@island` declarations are appended after the
+/// grammar's rules.
+pub fn consume_rules_with_spans(
     pairs: Pairs<'_, Rule>,
 ) -> Result<Vec<ParserRule<'_>>, Vec<Error<Rule>>> {
     let pratt = PrattParser::new()
         .op(Op::infix(Rule::choice_operator, Assoc::Left))
         .op(Op::infix(Rule::sequence_operator, Assoc::Left));
 
-    pairs
-        .filter(|pair| pair.as_rule() == Rule::grammar_rule)
-        .map(|pair| {
+    let keywords = consume_keywords(pairs.clone());
+    let aliases = consume_aliases(pairs.clone());
+    let islands = consume_islands(pairs.clone());
+    let mut docs = vec![];
+    let mut dialects = vec![];
+
+    let rules = pairs
+        .filter_map(|pair| match pair.as_rule() {
+            Rule::line_doc => {
+                docs.push(consume_doc(pair));
+                None
+            }
+            Rule::dialect => {
+                dialects = consume_dialect_names(pair);
+                None
+            }
+            Rule::grammar_rule => Some((mem::take(&mut docs), mem::take(&mut dialects), pair)),
+            _ => None,
+        })
+        .map(|(docs, dialects, pair)| {
             let mut pairs = pair.into_inner().peekable();
 
             let span = pairs.next().unwrap().as_span();
@@ -286,16 +377,521 @@ fn consume_rules_with_spans(
 
             let node = consume_expr(inner_nodes, &pratt)?;
 
-            Ok(ParserRule {
+            Ok((
+                dialects,
+                ParserRule {
+                    name,
+                    span,
+                    docs,
+                    ty,
+                    node,
+                },
+            ))
+        })
+        .collect::<Result<Vec<_>, Vec<Error<Rule>>>>()?;
+    let mut rules = merge_dialects(rules)?;
+
+    for keywords in &keywords {
+        expand_keywords(&mut rules, keywords);
+    }
+    for alias in &aliases {
+        expand_alias(&mut rules, alias);
+    }
+    for island in &islands {
+        expand_island(&mut rules, island);
+    }
+
+    Ok(rules)
+}
+
+/// Returns the dialects named by the grammar's `@dialect` annotations, e.g.
+/// `@dialect(strict, lenient)`, in the order they are first named
+pub fn consume_dialects(pairs: Pairs<'_, Rule>) -> Vec<String> {
+    let mut dialects: Vec<String> = vec![];
+
+    for name in pairs
+        .filter(|pair| pair.as_rule() == Rule::dialect)
+        .flat_map(consume_dialect_names)
+    {
+        if !dialects.iter().any(|dialect| dialect == name.as_str()) {
+            dialects.push(name.as_str().to_owned());
+        }
+    }
+
+    dialects
+}
+
+fn consume_dialect_names(pair: Pair<'_, Rule>) -> Vec<Span<'_>> {
+    pair.into_inner()
+        .filter(|pair| pair.as_rule() == Rule::dialect_name)
+        .map(|pair| pair.as_span())
+        .collect()
+}
+
+// Merges the definitions of every rule annotated with `@dialect` into the first of them, matching
+// the definition of the dialect being parsed, or the definition without an annotation if there is
+// none for it. Without such a definition, the rule never matches in the other dialects.
+#[allow(clippy::type_complexity)]
+fn merge_dialects<'i>(
+    rules: Vec<(Vec<Span<'i>>, ParserRule<'i>)>,
+) -> Result<Vec<ParserRule<'i>>, Vec<Error<Rule>>> {
+    // The definitions of every rule, in the order the rules are first defined.
+    let mut definitions: Vec<(Vec<(Vec<Span<'i>>, ParserRule<'i>)>, Option<ParserRule<'i>>)> =
+        vec![];
+    let mut names: Vec<String> = vec![];
+    let mut errors = vec![];
+
+    for (dialects, rule) in rules {
+        let index = match names.iter().position(|name| *name == rule.name) {
+            Some(index) => index,
+            None => {
+                names.push(rule.name.clone());
+                definitions.push((vec![], None));
+                definitions.len() - 1
+            }
+        };
+        let (variants, base) = &mut definitions[index];
+
+        if dialects.is_empty() {
+            *base = Some(rule);
+            continue;
+        }
+
+        for dialect in &dialects {
+            if variants
+                .iter()
+                .any(|(other, _)| other.iter().any(|other| other.as_str() == dialect.as_str()))
+            {
+                errors.push(Error::new_from_span(
+                    ErrorVariant::CustomError {
+                        message: format!(
+                            "rule {} already defined for dialect {}",
+                            rule.name,
+                            dialect.as_str()
+                        ),
+                    },
+                    *dialect,
+                ));
+            }
+        }
+        variants.push((dialects, rule));
+    }
+
+    let rules: Vec<_> = definitions
+        .into_iter()
+        .map(|(variants, base)| {
+            let (_, first) = match variants.first() {
+                Some(first) => first,
+                None => return base.unwrap(),
+            };
+            let name = first.name.clone();
+            let span = first.span;
+            let ty = base.as_ref().map_or(first.ty, |base| base.ty);
+            let docs: Vec<_> = base
+                .iter()
+                .chain(variants.iter().map(|(_, variant)| variant))
+                .flat_map(|rule| rule.docs.iter().cloned())
+                .collect();
+
+            for (_, variant) in variants.iter().filter(|(_, variant)| variant.ty != ty) {
+                errors.push(Error::new_from_span(
+                    ErrorVariant::CustomError {
+                        message: format!(
+                            "the definitions of rule {} for its dialects must have the same type",
+                            name
+                        ),
+                    },
+                    variant.span,
+                ));
+            }
+
+            // `!""` never matches.
+            let otherwise = match base {
+                Some(base) => base.node,
+                None => ParserNode {
+                    expr: ParserExpr::NegPred(Box::new(ParserNode {
+                        expr: ParserExpr::Str(String::new()),
+                        span,
+                    })),
+                    span,
+                },
+            };
+            let node =
+                variants
+                    .into_iter()
+                    .rev()
+                    .fold(otherwise, |otherwise, (dialects, variant)| ParserNode {
+                        expr: ParserExpr::Dialect(
+                            dialects
+                                .iter()
+                                .map(|dialect| dialect.as_str().to_owned())
+                                .collect(),
+                            Box::new(variant.node),
+                            Box::new(otherwise),
+                        ),
+                        span: variant.span,
+                    });
+
+            ParserRule {
                 name,
                 span,
+                docs,
                 ty,
                 node,
-            })
+            }
+        })
+        .collect();
+
+    if errors.is_empty() {
+        Ok(rules)
+    } else {
+        Err(errors)
+    }
+}
+
+/// An `@alias` declaration, e.g. `@alias(string) { single_quoted double_quoted }`, which makes the
+/// pairs of its rules carry the alias' name instead of their own
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ParserAlias<'i> {
+    /// The declaration's span
+    pub span: Span<'i>,
+    /// The span of the alias' name
+    pub name: Span<'i>,
+    /// The spans of the aliased rules
+    pub rules: Vec<Span<'i>>,
+}
+
+/// Returns the grammar's `@alias` declarations
+pub fn consume_aliases(pairs: Pairs<'_, Rule>) -> Vec<ParserAlias<'_>> {
+    pairs
+        .filter(|pair| pair.as_rule() == Rule::alias)
+        .map(|pair| {
+            let span = pair.as_span();
+            let mut identifiers = pair
+                .into_inner()
+                .filter(|pair| pair.as_rule() == Rule::identifier)
+                .map(|pair| pair.as_span());
+
+            ParserAlias {
+                span,
+                name: identifiers.next().unwrap(),
+                rules: identifiers.collect(),
+            }
+        })
+        .collect()
+}
+
+/// Returns the spans of the names declared by the grammar's `@extern` declarations, e.g.
+/// `@extern { expr }`, whose rules are matched by other parsers bound at runtime
+pub fn consume_externs(pairs: Pairs<'_, Rule>) -> Vec<Span<'_>> {
+    pairs
+        .filter(|pair| pair.as_rule() == Rule::extern_rules)
+        .flat_map(|pair| {
+            pair.into_inner()
+                .filter(|pair| pair.as_rule() == Rule::identifier)
+                .map(|pair| pair.as_span())
+        })
+        .collect()
+}
+
+/// Returns the name of the atomic rule generated by an `@island` declaration named `island`, which
+/// matches the text between code regions, e.g. `template_text` for `template`
+pub fn island_text_rule(island: &str) -> String {
+    format!("{}_text", island)
+}
+
+/// An `@island` declaration, e.g. `@island(template) { "{{" expr "}}" "<%" stmt "%>" }`, for
+/// documents of opaque text with code regions delimited by markers
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ParserIsland<'i> {
+    /// The declaration's span
+    pub span: Span<'i>,
+    /// The span of the island rule's name
+    pub name: Span<'i>,
+    /// The code regions, with their opening markers, the spans of their rules and their closing
+    /// markers
+    pub regions: Vec<(String, Span<'i>, String)>,
+}
+
+/// Returns the grammar's `@island` declarations
+pub fn consume_islands(pairs: Pairs<'_, Rule>) -> Vec<ParserIsland<'_>> {
+    pairs
+        .filter(|pair| pair.as_rule() == Rule::island)
+        .map(|pair| {
+            let span = pair.as_span();
+            let mut inner = pair.into_inner().filter(|pair| {
+                pair.as_rule() == Rule::identifier || pair.as_rule() == Rule::string
+            });
+
+            let name = inner.next().unwrap().as_span();
+            let mut regions = vec![];
+            while let Some(open) = inner.next() {
+                let rule = inner.next().unwrap().as_span();
+                let close = inner.next().unwrap();
+                regions.push((consume_string(open), rule, consume_string(close)));
+            }
+
+            ParserIsland {
+                span,
+                name,
+                regions,
+            }
+        })
+        .collect()
+}
+
+// `LIST(item, separator)` is `(item ~ (separator ~ item)* ~ separator?)?`, so that lists can be
+// empty and end with a separator, and their items and separators are matched in the pairs of the
+// rule using them.
+fn expand_list<'i>(
+    span: Span<'i>,
+    item: ParserNode<'i>,
+    separator: ParserNode<'i>,
+) -> ParserNode<'i> {
+    let node = |expr| ParserNode { expr, span };
+
+    let rest = node(ParserExpr::Rep(Box::new(node(ParserExpr::Seq(
+        Box::new(separator.clone()),
+        Box::new(item.clone()),
+    )))));
+    let trailing = node(ParserExpr::Opt(Box::new(separator)));
+    let list = node(ParserExpr::Seq(
+        Box::new(item),
+        Box::new(node(ParserExpr::Seq(Box::new(rest), Box::new(trailing)))),
+    ));
+
+    node(ParserExpr::Opt(Box::new(list)))
+}
+
+// The island gets a compound-atomic rule matching text and code regions in any order, so that no
+// whitespace is skipped around the text, which is matched by an atomic `<island>_text` rule.
+fn expand_island<'i>(rules: &mut Vec<ParserRule<'i>>, island: &ParserIsland<'i>) {
+    let node = |expr| ParserNode {
+        expr,
+        span: island.span,
+    };
+    let text = island_text_rule(island.name.as_str());
+
+    // `(!("a" | "b") ~ ANY)*` is optimized into skipping to the next `"a"` or `"b"`.
+    let markers = island
+        .regions
+        .iter()
+        .rev()
+        .map(|(open, _, _)| node(ParserExpr::Str(open.clone())))
+        .reduce(|rhs, lhs| node(ParserExpr::Choice(Box::new(lhs), Box::new(rhs))))
+        .unwrap();
+    let char = node(ParserExpr::Seq(
+        Box::new(node(ParserExpr::NegPred(Box::new(markers)))),
+        Box::new(node(ParserExpr::Ident("ANY".to_owned()))),
+    ));
+    let text_expr = ParserExpr::Seq(
+        Box::new(char.clone()),
+        Box::new(node(ParserExpr::Rep(Box::new(char)))),
+    );
+
+    let choice = island
+        .regions
+        .iter()
+        .map(|(open, rule, close)| {
+            let code = ParserNode {
+                expr: ParserExpr::Ident(rule.as_str().to_owned()),
+                span: *rule,
+            };
+            node(ParserExpr::Seq(
+                Box::new(node(ParserExpr::Seq(
+                    Box::new(node(ParserExpr::Str(open.clone()))),
+                    Box::new(code),
+                ))),
+                Box::new(node(ParserExpr::Str(close.clone()))),
+            ))
+        })
+        .fold(node(ParserExpr::Ident(text.clone())), |lhs, rhs| {
+            node(ParserExpr::Choice(Box::new(lhs), Box::new(rhs)))
+        });
+
+    rules.push(ParserRule {
+        name: text,
+        span: island.name,
+        docs: vec![],
+        ty: RuleType::Atomic,
+        node: node(text_expr),
+    });
+    rules.push(ParserRule {
+        name: island.name.as_str().to_owned(),
+        span: island.name,
+        docs: vec![],
+        ty: RuleType::CompoundAtomic,
+        node: node(ParserExpr::Rep(Box::new(choice))),
+    });
+}
+
+/// Returns the names of the aliased rules along with the names of their aliases, e.g.
+/// `[("single_quoted", "string"), ("double_quoted", "string")]`
+pub fn alias_names(aliases: &[ParserAlias<'_>]) -> Vec<(String, String)> {
+    aliases
+        .iter()
+        .flat_map(|alias| {
+            alias
+                .rules
+                .iter()
+                .map(move |rule| (rule.as_str().to_owned(), alias.name.as_str().to_owned()))
         })
         .collect()
 }
 
+// The alias gets a silent rule matching any of its rules, so that it can be called and parsed like
+// any other rule.
+fn expand_alias<'i>(rules: &mut Vec<ParserRule<'i>>, alias: &ParserAlias<'i>) {
+    let node = |expr, span| ParserNode { expr, span };
+
+    let choice = alias
+        .rules
+        .iter()
+        .map(|rule| node(ParserExpr::Ident(rule.as_str().to_owned()), *rule))
+        .reduce(|lhs, rhs| node(ParserExpr::Choice(Box::new(lhs), Box::new(rhs)), alias.span))
+        .unwrap();
+
+    rules.push(ParserRule {
+        name: alias.name.as_str().to_owned(),
+        span: alias.name,
+        docs: vec![],
+        ty: RuleType::Silent,
+        node: choice,
+    });
+}
+
+/// The name of the silent rule generated by a `@keywords` declaration, which matches any of the
+/// keywords
+pub const KEYWORD_RULE: &str = "keyword";
+
+/// Returns the name of the atomic rule generated for `keyword` by a `@keywords` declaration,
+/// e.g. `kw_if` for `if`
+pub fn keyword_rule(keyword: &str) -> String {
+    format!("kw_{}", keyword)
+}
+
+/// A `@keywords` declaration, e.g. `@keywords(identifier) { "if" "else" }`
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ParserKeywords<'i> {
+    /// The declaration's span
+    pub span: Span<'i>,
+    /// The span of the rule that the keywords are excluded from, if any
+    pub identifier: Option<Span<'i>>,
+    /// The keywords, along with their spans
+    pub keywords: Vec<(String, Span<'i>)>,
+}
+
+/// Returns the grammar's `@keywords` declarations
+pub fn consume_keywords(pairs: Pairs<'_, Rule>) -> Vec<ParserKeywords<'_>> {
+    pairs
+        .filter(|pair| pair.as_rule() == Rule::keywords)
+        .map(|pair| {
+            let span = pair.as_span();
+            let inner = pair.into_inner();
+
+            let identifier = inner
+                .clone()
+                .find(|pair| pair.as_rule() == Rule::identifier)
+                .map(|pair| pair.as_span());
+            let keywords = inner
+                .filter(|pair| pair.as_rule() == Rule::string)
+                .map(|pair| (consume_string(pair.clone()), pair.as_span()))
+                .collect();
+
+            ParserKeywords {
+                span,
+                identifier,
+                keywords,
+            }
+        })
+        .collect()
+}
+
+// Every keyword gets an atomic `kw_<keyword>` rule which only matches whole words, while the
+// silent `keyword` rule matches any of them and is excluded from the identifier rule.
+fn expand_keywords<'i>(rules: &mut Vec<ParserRule<'i>>, keywords: &ParserKeywords<'i>) {
+    let node = |expr, span| ParserNode { expr, span };
+
+    let mut choice = None;
+    for (keyword, span) in &keywords.keywords {
+        let boundary = ParserExpr::Choice(
+            Box::new(node(
+                ParserExpr::Ident("ASCII_ALPHANUMERIC".to_owned()),
+                *span,
+            )),
+            Box::new(node(ParserExpr::Str("_".to_owned()), *span)),
+        );
+        let expr = ParserExpr::Seq(
+            Box::new(node(ParserExpr::Str(keyword.clone()), *span)),
+            Box::new(node(
+                ParserExpr::NegPred(Box::new(node(boundary, *span))),
+                *span,
+            )),
+        );
+        let name = keyword_rule(keyword);
+
+        let ident = node(ParserExpr::Ident(name.clone()), *span);
+        choice = Some(match choice {
+            None => ident,
+            Some(lhs) => node(
+                ParserExpr::Choice(Box::new(lhs), Box::new(ident)),
+                keywords.span,
+            ),
+        });
+
+        rules.push(ParserRule {
+            name,
+            span: *span,
+            docs: vec![],
+            ty: RuleType::Atomic,
+            node: node(expr, *span),
+        });
+    }
+
+    if let Some(choice) = choice {
+        rules.push(ParserRule {
+            name: KEYWORD_RULE.to_owned(),
+            span: keywords.span,
+            docs: vec![],
+            ty: RuleType::Silent,
+            node: choice,
+        });
+    }
+
+    if let Some(identifier) = keywords.identifier {
+        if let Some(rule) = rules
+            .iter_mut()
+            .find(|rule| rule.name == identifier.as_str())
+        {
+            let excluded = node(
+                ParserExpr::NegPred(Box::new(node(
+                    ParserExpr::Ident(KEYWORD_RULE.to_owned()),
+                    identifier,
+                ))),
+                identifier,
+            );
+            let body = rule.node.clone();
+
+            rule.node = node(
+                ParserExpr::Seq(Box::new(excluded), Box::new(body)),
+                rule.node.span,
+            );
+        }
+    }
+}
+
+/// Returns the grammar's doc comments (`//! ...`), one per line
+pub fn consume_grammar_docs(pairs: Pairs<'_, Rule>) -> Vec<&str> {
+    pairs
+        .filter(|pair| pair.as_rule() == Rule::grammar_doc)
+        .map(consume_doc)
+        .collect()
+}
+
+fn consume_doc(pair: Pair<'_, Rule>) -> &str {
+    pair.into_inner().next().unwrap().as_str()
+}
+
 fn consume_expr<'i>(
     pairs: Peekable<Pairs<'i, Rule>>,
     pratt: &PrattParser<Rule>,
@@ -334,6 +930,50 @@ fn consume_expr<'i>(
                     span: pair.as_span().start_pos().span(&end),
                 }
             }
+            Rule::positive_lookbehind_operator => {
+                let node = unaries(pairs, pratt)?;
+                let end = node.span.end_pos();
+
+                if convert_node(node.clone()).fixed_width().is_none() {
+                    return Err(vec![Error::new_from_span(
+                        ErrorVariant::CustomError {
+                            message: "lookbehind expressions must have a fixed width".to_owned(),
+                        },
+                        node.span,
+                    )]);
+                }
+
+                ParserNode {
+                    expr: ParserExpr::PosLookbehind(Box::new(node)),
+                    span: pair.as_span().start_pos().span(&end),
+                }
+            }
+            Rule::group_label => {
+                let label = consume_string(pair.clone().into_inner().next().unwrap());
+                let node = unaries(pairs, pratt)?;
+                let end = node.span.end_pos();
+
+                ParserNode {
+                    expr: ParserExpr::Labeled(label, Box::new(node)),
+                    span: pair.as_span().start_pos().span(&end),
+                }
+            }
+            Rule::semantic_predicate => {
+                let name = pair
+                    .clone()
+                    .into_inner()
+                    .next()
+                    .unwrap()
+                    .as_str()
+                    .to_owned();
+                let node = unaries(pairs, pratt)?;
+                let end = node.span.end_pos();
+
+                ParserNode {
+                    expr: ParserExpr::Predicate(name, Box::new(node)),
+                    span: pair.as_span().start_pos().span(&end),
+                }
+            }
             other_rule => {
                 let node = match other_rule {
                     Rule::expression => consume_expr(pair.into_inner().peekable(), pratt)?,
@@ -351,6 +991,18 @@ fn consume_expr<'i>(
                             span: start.span(&end),
                         }
                     }
+                    Rule::list => {
+                        let span = pair.as_span();
+                        let mut pairs = pair.into_inner();
+                        pairs.next().unwrap(); // opening_paren
+                        let item =
+                            consume_expr(pairs.next().unwrap().into_inner().peekable(), pratt)?;
+                        pairs.next().unwrap(); // comma
+                        let separator =
+                            consume_expr(pairs.next().unwrap().into_inner().peekable(), pratt)?;
+
+                        expand_list(span, item, separator)
+                    }
                     Rule::peek_slice => {
                         let mut pairs = pair.clone().into_inner();
                         pairs.next().unwrap(); // opening_brack
@@ -381,20 +1033,16 @@ fn consume_expr<'i>(
                         expr: ParserExpr::Ident(pair.as_str().to_owned()),
                         span: pair.clone().as_span(),
                     },
-                    Rule::string => {
-                        let string = unescape(pair.as_str()).expect("incorrect string literal");
-                        ParserNode {
-                            expr: ParserExpr::Str(string[1..string.len() - 1].to_owned()),
-                            span: pair.clone().as_span(),
-                        }
-                    }
-                    Rule::insensitive_string => {
-                        let string = unescape(pair.as_str()).expect("incorrect string literal");
-                        ParserNode {
-                            expr: ParserExpr::Insens(string[2..string.len() - 1].to_owned()),
-                            span: pair.clone().as_span(),
-                        }
-                    }
+                    Rule::string => ParserNode {
+                        expr: ParserExpr::Str(consume_string(pair.clone())),
+                        span: pair.as_span(),
+                    },
+                    Rule::insensitive_string => ParserNode {
+                        expr: ParserExpr::Insens(consume_string(
+                            pair.clone().into_inner().next().unwrap(),
+                        )),
+                        span: pair.as_span(),
+                    },
                     Rule::range => {
                         let mut pairs = pair.into_inner();
                         let pair = pairs.next().unwrap();
@@ -637,6 +1285,22 @@ fn consume_expr<'i>(
     pratt.map_primary(term).map_infix(infix).parse(pairs)
 }
 
+fn consume_string(pair: Pair<'_, Rule>) -> String {
+    let raw = pair
+        .clone()
+        .into_inner()
+        .find(|pair| pair.as_rule() == Rule::raw_inner_str);
+
+    match raw {
+        // Raw strings are taken verbatim, without any escapes.
+        Some(raw) => raw.as_str().to_owned(),
+        None => {
+            let string = unescape(pair.as_str()).expect("incorrect string literal");
+            string[1..string.len() - 1].to_owned()
+        }
+    }
+}
+
 fn unescape(string: &str) -> Option<String> {
     let mut result = String::new();
     let mut chars = string.chars();
@@ -907,6 +1571,59 @@ mod tests {
         };
     }
 
+    #[test]
+    fn list() {
+        parses_to! {
+            parser: PestParser,
+            input: "LIST(a, \",\")",
+            rule: Rule::list,
+            tokens: [
+                list(0, 12, [
+                    opening_paren(4, 5),
+                    expression(5, 6, [
+                        term(5, 6, [
+                            identifier(5, 6)
+                        ])
+                    ]),
+                    comma(6, 7),
+                    expression(8, 11, [
+                        term(8, 11, [
+                            string(8, 11, [
+                                quote(8, 9),
+                                inner_str(9, 10),
+                                quote(10, 11)
+                            ])
+                        ])
+                    ]),
+                    closing_paren(11, 12)
+                ])
+            ]
+        };
+    }
+
+    #[test]
+    fn push_prefixed_identifier() {
+        parses_to! {
+            parser: PestParser,
+            input: "PUSH_INDENT",
+            rule: Rule::term,
+            tokens: [
+                term(0, 11, [
+                    identifier(0, 11)
+                ])
+            ]
+        };
+
+        fails_with! {
+            parser: PestParser,
+            input: "PUSH",
+            rule: Rule::identifier,
+            positives: vec![Rule::identifier],
+            negatives: vec![],
+            pos: 0
+        };
+    }
+
     #[test]
     fn peek_slice_all() {
         parses_to! {
@@ -1003,6 +1720,22 @@ mod tests {
         };
     }
 
+    #[test]
+    fn raw_string() {
+        parses_to! {
+            parser: PestParser,
+            input: "r##\"a\"#\\\"##",
+            rule: Rule::string,
+            tokens: [
+                string(0, 11, [
+                    quote(3, 4),
+                    raw_inner_str(4, 8),
+                    quote(8, 9)
+                ])
+            ]
+        };
+    }
+
     #[test]
     fn insensitive_string() {
         parses_to! {
@@ -1093,6 +1826,55 @@ mod tests {
         };
     }
 
+    #[test]
+    fn nested_block_comment() {
+        parses_to! {
+            parser: PestParser,
+            input: "a ~ /* x /* y */ z */ b",
+            rule: Rule::expression,
+            tokens: [
+                expression(0, 23, [
+                    term(0, 2, [
+                        identifier(0, 1)
+                    ]),
+                    sequence_operator(2, 3),
+                    term(22, 23, [
+                        identifier(22, 23)
+                    ])
+                ])
+            ]
+        };
+    }
+
+    #[test]
+    fn doc_comments() {
+        parses_to! {
+            parser: PestParser,
+            input: "//! grammar\n//// comment\n/// rule\na = { b }",
+            rule: Rule::grammar_rules,
+            tokens: [
+                grammar_doc(0, 11, [
+                    inner_doc(4, 11)
+                ]),
+                line_doc(25, 33, [
+                    inner_doc(29, 33)
+                ]),
+                grammar_rule(34, 43, [
+                    identifier(34, 35),
+                    assignment_operator(36, 37),
+                    opening_brace(38, 39),
+                    expression(40, 42, [
+                        term(40, 42, [
+                            identifier(40, 41)
+                        ])
+                    ]),
+                    closing_brace(42, 43)
+                ]),
+                EOI(43, 43)
+            ]
+        };
+    }
+
     #[test]
     fn wrong_identifier() {
         fails_with! {
@@ -1233,7 +2015,11 @@ mod tests {
                 Rule::opening_paren,
                 Rule::positive_predicate_operator,
                 Rule::negative_predicate_operator,
+                Rule::positive_lookbehind_operator,
+                Rule::group_label,
+                Rule::semantic_predicate,
                 Rule::_push,
+                Rule::list,
                 Rule::peek_slice,
                 Rule::identifier,
                 Rule::insensitive_string,
@@ -1352,6 +2138,387 @@ mod tests {
         );
     }
 
+    #[test]
+    fn ast_raw_string() {
+        let input = r###"rule = { r"\n" ~ r#"say "hi""# ~ ^r"\" ~ rule2 ~ r }"###;
+
+        let pairs = PestParser::parse(Rule::grammar_rules, input).unwrap();
+        let ast = consume_rules_with_spans(pairs).unwrap();
+        let ast: Vec<_> = ast.into_iter().map(convert_rule).collect();
+
+        assert_eq!(
+            ast,
+            vec![AstRule {
+                name: "rule".to_owned(),
+                ty: RuleType::Normal,
+                expr: Expr::Seq(
+                    Box::new(Expr::Seq(
+                        Box::new(Expr::Seq(
+                            Box::new(Expr::Seq(
+                                Box::new(Expr::Str(r"\n".to_owned())),
+                                Box::new(Expr::Str(r#"say "hi""#.to_owned()))
+                            )),
+                            Box::new(Expr::Insens(r"\".to_owned()))
+                        )),
+                        Box::new(Expr::Ident("rule2".to_owned()))
+                    )),
+                    Box::new(Expr::Ident("r".to_owned()))
+                )
+            }],
+        );
+    }
+
+    #[test]
+    fn ast_lookbehind() {
+        let input = r#"rule = { <&("a" ~ 'b'..'c' | ^"de"){2} ~ <&"é" }"#;
+
+        let pairs = PestParser::parse(Rule::grammar_rules, input).unwrap();
+        let ast = consume_rules_with_spans(pairs).unwrap();
+        let ast: Vec<_> = ast.into_iter().map(convert_rule).collect();
+
+        assert_eq!(
+            ast,
+            vec![AstRule {
+                name: "rule".to_owned(),
+                ty: RuleType::Normal,
+                expr: Expr::Seq(
+                    Box::new(Expr::PosLookbehind(Box::new(Expr::RepExact(
+                        Box::new(Expr::Choice(
+                            Box::new(Expr::Seq(
+                                Box::new(Expr::Str("a".to_owned())),
+                                Box::new(Expr::Range("b".to_owned(), "c".to_owned()))
+                            )),
+                            Box::new(Expr::Insens("de".to_owned()))
+                        )),
+                        2
+                    )))),
+                    Box::new(Expr::PosLookbehind(Box::new(Expr::Str("é".to_owned()))))
+                )
+            }],
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "grammar error
+
+ --> 1:12
+  |
+1 | rule = { <&(\"a\" | \"bc\") }
+  |            ^----------^
+  |
+  = lookbehind expressions must have a fixed width")]
+    fn lookbehind_variable_width() {
+        let input = r#"rule = { <&("a" | "bc") }"#;
+
+        let pairs = PestParser::parse(Rule::grammar_rules, input).unwrap();
+        unwrap_or_report(consume_rules_with_spans(pairs));
+    }
+
+    #[test]
+    fn ast_keywords() {
+        let input = "@keywords(ident) { \"if\" \"else\" }\nident = @{ ASCII_ALPHA+ }";
+
+        let pairs = PestParser::parse(Rule::grammar_rules, input).unwrap();
+        let ast = consume_rules(pairs).unwrap();
+
+        let kw = |keyword: &str| AstRule {
+            name: format!("kw_{}", keyword),
+            ty: RuleType::Atomic,
+            expr: Expr::Seq(
+                Box::new(Expr::Str(keyword.to_owned())),
+                Box::new(Expr::NegPred(Box::new(Expr::Choice(
+                    Box::new(Expr::Ident("ASCII_ALPHANUMERIC".to_owned())),
+                    Box::new(Expr::Str("_".to_owned())),
+                )))),
+            ),
+        };
+
+        assert_eq!(
+            ast,
+            vec![
+                AstRule {
+                    name: "ident".to_owned(),
+                    ty: RuleType::Atomic,
+                    expr: Expr::Seq(
+                        Box::new(Expr::NegPred(Box::new(Expr::Ident("keyword".to_owned())))),
+                        Box::new(Expr::RepOnce(Box::new(Expr::Ident(
+                            "ASCII_ALPHA".to_owned()
+                        ))))
+                    )
+                },
+                kw("if"),
+                kw("else"),
+                AstRule {
+                    name: "keyword".to_owned(),
+                    ty: RuleType::Silent,
+                    expr: Expr::Choice(
+                        Box::new(Expr::Ident("kw_if".to_owned())),
+                        Box::new(Expr::Ident("kw_else".to_owned()))
+                    )
+                },
+            ],
+        );
+    }
+
+    #[test]
+    fn ast_labeled() {
+        let input = "rule = { \"(\" ~ #\"argument list\" (a ~ b)? ~ \")\" }";
+
+        let pairs = PestParser::parse(Rule::grammar_rules, input).unwrap();
+        let ast = consume_rules_with_spans(pairs).unwrap();
+        let ast: Vec<_> = ast.into_iter().map(convert_rule).collect();
+
+        assert_eq!(
+            ast[0].expr,
+            Expr::Seq(
+                Box::new(Expr::Seq(
+                    Box::new(Expr::Str("(".to_owned())),
+                    Box::new(Expr::Labeled(
+                        "argument list".to_owned(),
+                        Box::new(Expr::Opt(Box::new(Expr::Seq(
+                            Box::new(Expr::Ident("a".to_owned())),
+                            Box::new(Expr::Ident("b".to_owned()))
+                        ))))
+                    ))
+                )),
+                Box::new(Expr::Str(")".to_owned()))
+            )
+        );
+    }
+
+    #[test]
+    fn ast_predicate() {
+        let input = "declaration = { #{is_type_name} name ~ !#{is_keyword} name }";
+
+        let pairs = PestParser::parse(Rule::grammar_rules, input).unwrap();
+        let ast = consume_rules_with_spans(pairs).unwrap();
+        let ast: Vec<_> = ast.into_iter().map(convert_rule).collect();
+
+        assert_eq!(
+            ast[0].expr,
+            Expr::Seq(
+                Box::new(Expr::Predicate(
+                    "is_type_name".to_owned(),
+                    Box::new(Expr::Ident("name".to_owned()))
+                )),
+                Box::new(Expr::NegPred(Box::new(Expr::Predicate(
+                    "is_keyword".to_owned(),
+                    Box::new(Expr::Ident("name".to_owned()))
+                ))))
+            )
+        );
+    }
+
+    #[test]
+    fn ast_alias() {
+        let input = "@alias(string) { single double }\nsingle = { \"'\" }\ndouble = { \"\\\"\" }";
+
+        let pairs = PestParser::parse(Rule::grammar_rules, input).unwrap();
+        assert_eq!(
+            alias_names(&consume_aliases(pairs.clone())),
+            vec![
+                ("single".to_owned(), "string".to_owned()),
+                ("double".to_owned(), "string".to_owned())
+            ]
+        );
+
+        let ast = consume_rules(pairs).unwrap();
+        assert_eq!(
+            ast[2],
+            AstRule {
+                name: "string".to_owned(),
+                ty: RuleType::Silent,
+                expr: Expr::Choice(
+                    Box::new(Expr::Ident("single".to_owned())),
+                    Box::new(Expr::Ident("double".to_owned()))
+                )
+            }
+        );
+    }
+
+    #[test]
+    fn ast_dialects() {
+        let input =
+            "@dialect(lenient, legacy)\nnumber = { ASCII_DIGIT ~ (\"_\" | ASCII_DIGIT)* }\n\
+                     number = { ASCII_DIGIT+ }\n@dialect(strict) number = { \"0\" }\n\
+                     @dialect(legacy) octal = { \"0o\" }";
+
+        let pairs = PestParser::parse(Rule::grammar_rules, input).unwrap();
+        assert_eq!(
+            consume_dialects(pairs.clone()),
+            ["lenient", "legacy", "strict"]
+        );
+
+        let ast = consume_rules(pairs).unwrap();
+        assert_eq!(ast.len(), 2);
+        assert_eq!(
+            ast[0].expr,
+            Expr::Dialect(
+                vec!["lenient".to_owned(), "legacy".to_owned()],
+                Box::new(Expr::Seq(
+                    Box::new(Expr::Ident("ASCII_DIGIT".to_owned())),
+                    Box::new(Expr::Rep(Box::new(Expr::Choice(
+                        Box::new(Expr::Str("_".to_owned())),
+                        Box::new(Expr::Ident("ASCII_DIGIT".to_owned()))
+                    ))))
+                )),
+                Box::new(Expr::Dialect(
+                    vec!["strict".to_owned()],
+                    Box::new(Expr::Str("0".to_owned())),
+                    Box::new(Expr::RepOnce(Box::new(Expr::Ident(
+                        "ASCII_DIGIT".to_owned()
+                    ))))
+                ))
+            )
+        );
+        // Without a base definition, the rule never matches outside of its dialects.
+        assert_eq!(
+            ast[1].expr,
+            Expr::Dialect(
+                vec!["legacy".to_owned()],
+                Box::new(Expr::Str("0o".to_owned())),
+                Box::new(Expr::NegPred(Box::new(Expr::Str(String::new()))))
+            )
+        );
+    }
+
+    #[test]
+    fn ast_dialect_errors() {
+        let input =
+            "@dialect(a) x = { \"a\" }\n@dialect(b, a) x = { \"b\" }\n@dialect(c) x = @{ \"c\" }";
+
+        let pairs = PestParser::parse(Rule::grammar_rules, input).unwrap();
+        let messages: Vec<_> = consume_rules(pairs)
+            .unwrap_err()
+            .into_iter()
+            .map(|error| error.variant.message().into_owned())
+            .collect();
+
+        assert_eq!(
+            messages,
+            [
+                "rule x already defined for dialect a",
+                "the definitions of rule x for its dialects must have the same type"
+            ]
+        );
+    }
+
+    #[test]
+    fn ast_externs() {
+        let input = "@extern { expr query }\na = { expr ~ query }";
+
+        let pairs = PestParser::parse(Rule::grammar_rules, input).unwrap();
+        let externs: Vec<_> = consume_externs(pairs.clone())
+            .iter()
+            .map(|span| span.as_str())
+            .collect();
+
+        assert_eq!(externs, vec!["expr", "query"]);
+        assert_eq!(consume_rules(pairs).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn ast_island() {
+        let input = "@island(doc) { \"{{\" expr \"}}\" \"<%\" stmt \"%>\" }\nexpr = { \"e\" }\nstmt = { \"s\" }";
+
+        let pairs = PestParser::parse(Rule::grammar_rules, input).unwrap();
+        let ast = consume_rules(pairs).unwrap();
+
+        // The text is skipped up to the next opening marker at once.
+        let optimized = crate::optimizer::optimize(ast.clone());
+        assert_eq!(
+            format!("{:?}", optimized[2].expr),
+            "Seq(NegPred(Choice(Str(\"{{\"), Str(\"<%\"))), \
+             Seq(Ident(\"ANY\"), Skip([\"{{\", \"<%\"])))"
+        );
+
+        let char = Expr::Seq(
+            Box::new(Expr::NegPred(Box::new(Expr::Choice(
+                Box::new(Expr::Str("{{".to_owned())),
+                Box::new(Expr::Str("<%".to_owned())),
+            )))),
+            Box::new(Expr::Ident("ANY".to_owned())),
+        );
+        assert_eq!(
+            ast[2],
+            AstRule {
+                name: "doc_text".to_owned(),
+                ty: RuleType::Atomic,
+                expr: Expr::Seq(Box::new(char.clone()), Box::new(Expr::Rep(Box::new(char)))),
+            }
+        );
+
+        let region = |open: &str, rule: &str, close: &str| {
+            Expr::Seq(
+                Box::new(Expr::Seq(
+                    Box::new(Expr::Str(open.to_owned())),
+                    Box::new(Expr::Ident(rule.to_owned())),
+                )),
+                Box::new(Expr::Str(close.to_owned())),
+            )
+        };
+        assert_eq!(
+            ast[3],
+            AstRule {
+                name: "doc".to_owned(),
+                ty: RuleType::CompoundAtomic,
+                expr: Expr::Rep(Box::new(Expr::Choice(
+                    Box::new(Expr::Choice(
+                        Box::new(Expr::Ident("doc_text".to_owned())),
+                        Box::new(region("{{", "expr", "}}")),
+                    )),
+                    Box::new(region("<%", "stmt", "%>")),
+                ))),
+            }
+        );
+    }
+
+    #[test]
+    fn ast_docs() {
+        let input = "//! A grammar\n//!\n/// Matches `a`.\n///\n///  Indented.\na = { \"a\" }\n// not a doc\nb = { a }";
+
+        let pairs = PestParser::parse(Rule::grammar_rules, input).unwrap();
+        let docs = consume_grammar_docs(pairs.clone());
+        let rules = consume_rules_with_spans(pairs).unwrap();
+
+        assert_eq!(docs, vec!["A grammar", ""]);
+        assert_eq!(rules[0].docs, vec!["Matches `a`.", "", " Indented."]);
+        assert!(rules[1].docs.is_empty());
+    }
+
+    #[test]
+    fn ast_docs_outside_doc_positions() {
+        let input = "//! A grammar\na = { \"a\" /// not a doc\n ~ b }\n//! not a doc\n\
+                     /// Matches `b`.\nb = { \"b\" }\n/// not a doc";
+
+        let pairs = PestParser::parse(Rule::grammar_rules, input).unwrap();
+        let docs = consume_grammar_docs(pairs.clone());
+        let rules = consume_rules_with_spans(pairs).unwrap();
+
+        assert_eq!(docs, vec!["A grammar"]);
+        assert!(rules[0].docs.is_empty());
+        assert_eq!(rules[1].docs, vec!["Matches `b`."]);
+    }
+
+    #[test]
+    fn doc_comment_after_last_rule() {
+        assert!(PestParser::parse(Rule::grammar_rules, "a = { \"a\" }\n/// trailing").is_ok());
+    }
+
+    #[test]
+    fn doc_comment_in_rule_body() {
+        assert!(
+            PestParser::parse(Rule::grammar_rules, "a = { \"a\" ~\n/// inner\n\"b\" }").is_ok()
+        );
+    }
+
+    #[test]
+    fn grammar_doc_after_first_rule() {
+        assert!(
+            PestParser::parse(Rule::grammar_rules, "a = { \"a\" }\n//! late\nb = { a }").is_ok()
+        );
+    }
+
     #[test]
     fn ast_peek_slice() {
         let input = "rule = _{ PEEK[-04..] ~ PEEK[..3] }";
@@ -1373,6 +2540,35 @@ mod tests {
         );
     }
 
+    #[test]
+    fn ast_list() {
+        let input = "args = { \"(\" ~ LIST(arg, \",\") ~ \")\" } LIST = { \"list\" } x = { LIST }";
+
+        let pairs = PestParser::parse(Rule::grammar_rules, input).unwrap();
+        let ast = consume_rules(pairs).unwrap();
+
+        let arg = || Box::new(Expr::Ident("arg".to_owned()));
+        let comma = || Box::new(Expr::Str(",".to_owned()));
+        assert_eq!(
+            ast[0].expr,
+            Expr::Seq(
+                Box::new(Expr::Seq(
+                    Box::new(Expr::Str("(".to_owned())),
+                    Box::new(Expr::Opt(Box::new(Expr::Seq(
+                        arg(),
+                        Box::new(Expr::Seq(
+                            Box::new(Expr::Rep(Box::new(Expr::Seq(comma(), arg())))),
+                            Box::new(Expr::Opt(comma()))
+                        ))
+                    ))))
+                )),
+                Box::new(Expr::Str(")".to_owned()))
+            )
+        );
+        // Rules can still be named `LIST`.
+        assert_eq!(ast[2].expr, Expr::Ident("LIST".to_owned()));
+    }
+
     #[test]
     #[should_panic(expected = "grammar error
 
@@ -0,0 +1,425 @@
+// pest. The Elegant Parser
+// Copyright (c) 2018 Dragoș Tiselice
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Source-level refactorings of pest grammars.
+//!
+//! These work on the grammar's text rather than on its AST, so the formatting and comments of
+//! the grammar are left untouched. They are meant to be used by tooling, e.g. to implement
+//! "rename symbol" in grammar editors.
+
+use std::collections::HashSet;
+
+use pest::error::{Error, ErrorVariant};
+use pest::{Position, Span};
+
+use crate::parser::{self, Rule};
+use crate::validator::{self, BUILTINS, PEST_KEYWORDS};
+
+/// A replacement of the text between two byte positions of a grammar.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct TextEdit {
+    /// The byte position where the replaced text starts
+    pub start: usize,
+    /// The byte position where the replaced text ends
+    pub end: usize,
+    /// The replacement text
+    pub text: String,
+}
+
+/// Renames the rule `old` of `grammar` to `new`, along with all references to it, returning the
+/// new grammar and the edits that were applied to it, in source order.
+///
+/// It fails if `grammar` cannot be parsed, if `old` is not defined, or if `new` is not a valid
+/// rule name, is a pest keyword or a built-in rule, or is already defined.
+///
+/// # Examples
+///
+/// ```
+/// # use pest_meta::refactor::{rename_rule, TextEdit};
+/// let grammar = "a = { b ~ b } // uses b\nb = { \"b\" }";
+/// let (grammar, edits) = rename_rule(grammar, "b", "letter").unwrap();
+///
+/// assert_eq!(grammar, "a = { letter ~ letter } // uses b\nletter = { \"b\" }");
+/// assert_eq!(
+///     edits[0],
+///     TextEdit {
+///         start: 6,
+///         end: 7,
+///         text: "letter".to_owned(),
+///     }
+/// );
+/// ```
+pub fn rename_rule(
+    grammar: &str,
+    old: &str,
+    new: &str,
+) -> Result<(String, Vec<TextEdit>), Vec<Error<Rule>>> {
+    let pairs = parser::parse(Rule::grammar_rules, grammar).map_err(|error| vec![error])?;
+
+    let definitions: Vec<Span<'_>> = pairs
+        .clone()
+        .filter(|pair| pair.as_rule() == Rule::grammar_rule)
+        .map(|pair| pair.into_inner().next().unwrap().as_span())
+        .collect();
+
+    let definition = match definitions.iter().find(|span| span.as_str() == old) {
+        Some(definition) => *definition,
+        None => {
+            return Err(vec![Error::new_from_pos(
+                ErrorVariant::CustomError {
+                    message: format!("rule {} is undefined", old),
+                },
+                Position::from_start(grammar),
+            )])
+        }
+    };
+
+    let is_identifier = parser::parse(Rule::identifier, new)
+        .map(|pairs| pairs.as_str() == new)
+        .unwrap_or(false);
+
+    let message = if !is_identifier {
+        Some(format!("{} is not a valid rule name", new))
+    } else if PEST_KEYWORDS.contains(new) {
+        Some(format!("{} is a pest keyword", new))
+    } else if BUILTINS.contains(new) {
+        Some(format!("{} is a built-in rule", new))
+    } else {
+        None
+    };
+
+    if let Some(message) = message {
+        return Err(vec![Error::new_from_span(
+            ErrorVariant::CustomError { message },
+            definition,
+        )]);
+    }
+
+    let conflicts: Vec<_> = definitions
+        .iter()
+        .filter(|span| span.as_str() == new && new != old)
+        .map(|span| {
+            Error::new_from_span(
+                ErrorVariant::CustomError {
+                    message: format!("rule {} already defined", new),
+                },
+                *span,
+            )
+        })
+        .collect();
+
+    if !conflicts.is_empty() {
+        return Err(conflicts);
+    }
+
+    let edits: Vec<_> = pairs
+        .flatten()
+        .filter(|pair| pair.as_rule() == Rule::identifier && pair.as_str() == old)
+        .map(|pair| TextEdit {
+            start: pair.as_span().start(),
+            end: pair.as_span().end(),
+            text: new.to_owned(),
+        })
+        .collect();
+
+    Ok((apply(grammar, &edits), edits))
+}
+
+/// A rule removed from a grammar by [`remove_unreachable`].
+///
+/// [`remove_unreachable`]: fn.remove_unreachable.html
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RemovedRule<'i> {
+    /// The rule's name
+    pub name: String,
+    /// The span of the rule's definition in the original grammar, along with its doc comments
+    pub span: Span<'i>,
+}
+
+/// Removes the rules of `grammar` which cannot be reached from the rules named `entry_points`,
+/// returning the new grammar and the removed rules, in source order. See
+/// [`validator::unreachable_rules`].
+///
+/// The rules referenced by `@keywords`, `@alias`, `@extern` and `@island` declarations are kept.
+/// The lines of the removed rules are removed as well, when they are not shared with other rules.
+///
+/// It fails if `grammar` cannot be parsed or if one of `entry_points` is not defined.
+///
+/// [`validator::unreachable_rules`]: ../validator/fn.unreachable_rules.html
+///
+/// # Examples
+///
+/// ```
+/// # use pest_meta::refactor::remove_unreachable;
+/// let grammar = "a = { b }\n/// Not used anymore\nc = { b }\nb = { \"b\" }";
+/// let (grammar, removed) = remove_unreachable(grammar, &["a"]).unwrap();
+///
+/// assert_eq!(grammar, "a = { b }\nb = { \"b\" }");
+/// assert_eq!(removed.len(), 1);
+/// assert_eq!(removed[0].name, "c");
+/// assert_eq!(removed[0].span.as_str(), "/// Not used anymore\nc = { b }");
+/// ```
+#[allow(clippy::type_complexity)]
+pub fn remove_unreachable<'i>(
+    grammar: &'i str,
+    entry_points: &[&str],
+) -> Result<(String, Vec<RemovedRule<'i>>), Vec<Error<Rule>>> {
+    let pairs = parser::parse(Rule::grammar_rules, grammar).map_err(|error| vec![error])?;
+    let rules = parser::consume_rules_with_spans(pairs.clone())?;
+
+    let mut definitions = vec![];
+    let mut roots: Vec<&str> = entry_points.to_vec();
+    let mut docs_start = None;
+
+    for pair in pairs {
+        match pair.as_rule() {
+            Rule::line_doc => {
+                docs_start.get_or_insert(pair.as_span().start());
+            }
+            Rule::grammar_rule => {
+                let span = pair.as_span();
+                let start = docs_start.take().unwrap_or_else(|| span.start());
+                let name = pair.into_inner().next().unwrap().as_str();
+                // Spans of the grammar's pairs are always valid.
+                definitions.push((name, Span::new(grammar, start, span.end()).unwrap()));
+            }
+            Rule::keywords | Rule::alias | Rule::extern_rules | Rule::island => roots.extend(
+                pair.into_inner()
+                    .flatten()
+                    .filter(|pair| pair.as_rule() == Rule::identifier)
+                    .map(|pair| pair.as_str()),
+            ),
+            _ => {}
+        }
+    }
+
+    let undefined: Vec<_> = entry_points
+        .iter()
+        .filter(|&&name| definitions.iter().all(|&(other, _)| other != name))
+        .map(|name| {
+            Error::new_from_pos(
+                ErrorVariant::CustomError {
+                    message: format!("rule {} is undefined", name),
+                },
+                Position::from_start(grammar),
+            )
+        })
+        .collect();
+
+    if !undefined.is_empty() {
+        return Err(undefined);
+    }
+
+    // The rules generated by declarations are kept along with the ones they reference.
+    let defined: HashSet<&str> = definitions.iter().map(|&(name, _)| name).collect();
+    roots.extend(
+        rules
+            .iter()
+            .map(|rule| rule.name.as_str())
+            .filter(|name| !defined.contains(name)),
+    );
+
+    let unreachable: HashSet<&str> = validator::unreachable_rules(&rules, &roots)
+        .into_iter()
+        .map(|rule| rule.name.as_str())
+        .collect();
+
+    let removed: Vec<_> = definitions
+        .into_iter()
+        .filter(|(name, _)| unreachable.contains(name))
+        .map(|(name, span)| RemovedRule {
+            name: name.to_owned(),
+            span,
+        })
+        .collect();
+
+    let edits: Vec<_> = removed
+        .iter()
+        .map(|rule| {
+            let (start, end) = whole_lines(grammar, rule.span.start(), rule.span.end());
+            TextEdit {
+                start,
+                end,
+                text: String::new(),
+            }
+        })
+        .collect();
+
+    Ok((apply(grammar, &edits), removed))
+}
+
+// Extends `start..end` over the lines it spans if it is alone on them.
+fn whole_lines(source: &str, start: usize, end: usize) -> (usize, usize) {
+    let blank = &[' ', '\t'][..];
+    let line_start = source[..start].trim_end_matches(blank).len();
+    let rest = source[end..].trim_start_matches(blank);
+    let line_end = source.len() - rest.len();
+
+    let newline = if rest.starts_with("\r\n") {
+        2
+    } else if rest.starts_with('\n') {
+        1
+    } else if rest.is_empty() {
+        0
+    } else {
+        return (start, end);
+    };
+
+    if line_start == 0 || source[..line_start].ends_with('\n') {
+        (line_start, line_end + newline)
+    } else {
+        (start, end)
+    }
+}
+
+/// Applies `edits`, which must be sorted and must not overlap, to `source`.
+fn apply(source: &str, edits: &[TextEdit]) -> String {
+    let mut result = String::with_capacity(source.len());
+    let mut last = 0;
+
+    for edit in edits {
+        result.push_str(&source[last..edit.start]);
+        result.push_str(&edit.text);
+        last = edit.end;
+    }
+
+    result.push_str(&source[last..]);
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rename() {
+        let grammar = "/// Doc of a, uses b\n\
+                       a = { b ~ PUSH(b) ~ (\"b\" | b_c) }\n\
+                       @keywords(b) { \"if\" }\n\
+                       b = @{ ASCII_ALPHA+ } // b";
+        let (grammar, edits) = rename_rule(grammar, "b", "c").unwrap();
+
+        assert_eq!(
+            grammar,
+            "/// Doc of a, uses b\n\
+             a = { c ~ PUSH(c) ~ (\"b\" | b_c) }\n\
+             @keywords(c) { \"if\" }\n\
+             c = @{ ASCII_ALPHA+ } // b"
+        );
+        assert_eq!(edits.len(), 4);
+    }
+
+    #[test]
+    fn rename_same() {
+        let (grammar, edits) = rename_rule("a = { a }", "a", "a").unwrap();
+
+        assert_eq!(grammar, "a = { a }");
+        assert_eq!(edits.len(), 2);
+    }
+
+    #[test]
+    #[should_panic(expected = "grammar error
+
+ --> 1:1
+  |
+1 | a = { b }
+  | ^---
+  |
+  = rule b is undefined")]
+    fn rename_undefined() {
+        crate::unwrap_or_report(rename_rule("a = { b }", "b", "c"));
+    }
+
+    #[test]
+    #[should_panic(expected = "grammar error
+
+ --> 1:1
+  |
+1 | a = { b }
+  | ^
+  |
+  = 1b is not a valid rule name")]
+    fn rename_invalid() {
+        crate::unwrap_or_report(rename_rule("a = { b }", "a", "1b"));
+    }
+
+    #[test]
+    #[should_panic(expected = "grammar error
+
+ --> 1:1
+  |
+1 | a = { b }
+  | ^
+  |
+  = ANY is a pest keyword")]
+    fn rename_keyword() {
+        crate::unwrap_or_report(rename_rule("a = { b }", "a", "ANY"));
+    }
+
+    #[test]
+    fn remove() {
+        let grammar = "//! Grammar\n\
+                       @keywords(ident) { \"if\" }\n\
+                       @alias(name) { ident }\n\
+                       main = { SOI ~ item* ~ EOI }\n\
+                       item = { ident | \"0\" } old = { ident } /* old */\n  \
+                       /// Unused\n\
+                       /// since ever\n  \
+                       unused = { old ~ unused }  \r\n\
+                       ident = @{ ASCII_ALPHA+ }\n\
+                       WHITESPACE = _{ \" \" }\n\
+                       other = { ident }";
+        let (pruned, removed) = remove_unreachable(grammar, &["main"]).unwrap();
+
+        assert_eq!(
+            pruned,
+            "//! Grammar\n\
+             @keywords(ident) { \"if\" }\n\
+             @alias(name) { ident }\n\
+             main = { SOI ~ item* ~ EOI }\n\
+             item = { ident | \"0\" }  /* old */\n\
+             ident = @{ ASCII_ALPHA+ }\n\
+             WHITESPACE = _{ \" \" }\n"
+        );
+        let removed: Vec<_> = removed
+            .iter()
+            .map(|rule| (rule.name.as_str(), rule.span.start_pos().line_col()))
+            .collect();
+        assert_eq!(
+            removed,
+            [("old", (5, 24)), ("unused", (6, 3)), ("other", (11, 1))]
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "grammar error
+
+ --> 1:1
+  |
+1 | a = { b }
+  | ^---
+  |
+  = rule c is undefined")]
+    fn remove_undefined() {
+        crate::unwrap_or_report(remove_unreachable("a = { b }", &["c"]));
+    }
+
+    #[test]
+    #[should_panic(expected = "grammar error
+
+ --> 2:1
+  |
+2 | b = { a }
+  | ^
+  |
+  = rule b already defined")]
+    fn rename_conflict() {
+        crate::unwrap_or_report(rename_rule("a = { b }\nb = { a }", "a", "b"));
+    }
+}
@@ -12,12 +12,17 @@
 
 use once_cell::sync::Lazy;
 use std::collections::{HashMap, HashSet};
+use std::mem;
 
 use pest::error::{Error, ErrorVariant, InputLocation};
 use pest::iterators::Pairs;
 use pest::Span;
 
-use crate::parser::{ParserExpr, ParserNode, ParserRule, Rule};
+use crate::ast::{Expr, Rule as AstRule, RuleType};
+use crate::parser::{
+    self, ParserAlias, ParserExpr, ParserIsland, ParserKeywords, ParserNode, ParserRule, Rule,
+    KEYWORD_RULE,
+};
 use crate::UNICODE_PROPERTY_NAMES;
 
 static RUST_KEYWORDS: Lazy<HashSet<&'static str>> = Lazy::new(|| {
@@ -33,7 +38,7 @@ static RUST_KEYWORDS: Lazy<HashSet<&'static str>> = Lazy::new(|| {
     .collect()
 });
 
-static PEST_KEYWORDS: Lazy<HashSet<&'static str>> = Lazy::new(|| {
+pub(crate) static PEST_KEYWORDS: Lazy<HashSet<&'static str>> = Lazy::new(|| {
     [
         "_", "ANY", "DROP", "EOI", "PEEK", "PEEK_ALL", "POP", "POP_ALL", "PUSH", "SOI",
     ]
@@ -42,7 +47,7 @@ static PEST_KEYWORDS: Lazy<HashSet<&'static str>> = Lazy::new(|| {
     .collect()
 });
 
-static BUILTINS: Lazy<HashSet<&'static str>> = Lazy::new(|| {
+pub(crate) static BUILTINS: Lazy<HashSet<&'static str>> = Lazy::new(|| {
     [
         "ANY",
         "DROP",
@@ -52,6 +57,9 @@ static BUILTINS: Lazy<HashSet<&'static str>> = Lazy::new(|| {
         "POP",
         "POP_ALL",
         "SOI",
+        "PUSH_INDENT",
+        "MATCH_INDENT",
+        "DEDENT",
         "ASCII_DIGIT",
         "ASCII_NONZERO_DIGIT",
         "ASCII_BIN_DIGIT",
@@ -70,18 +78,141 @@ static BUILTINS: Lazy<HashSet<&'static str>> = Lazy::new(|| {
     .collect::<HashSet<&str>>()
 });
 
+/// Builds the sets of keywords and built-in rules which validation initializes lazily, so that the
+/// first grammar validated does not pay for it, e.g. in services building parsers at runtime.
+pub fn warm_up() {
+    Lazy::force(&RUST_KEYWORDS);
+    Lazy::force(&PEST_KEYWORDS);
+    Lazy::force(&BUILTINS);
+}
+
+// Rust keywords which cannot be raw identifiers, and thus cannot name generated `Rule` variants.
+const NON_RAW_KEYWORDS: [&str; 4] = ["crate", "self", "Self", "super"];
+
+/// How rules named after pest keywords, e.g. `ANY` or `EOI`, are handled by
+/// [`validate_pairs_with`] and [`rename_reserved`].
+///
+/// [`validate_pairs_with`]: fn.validate_pairs_with.html
+/// [`rename_reserved`]: fn.rename_reserved.html
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum ReservedNames {
+    /// The rules are reported as errors
+    Error,
+    /// The rules are renamed, and references to their names call them instead of the keywords
+    Rename,
+}
+
+impl Default for ReservedNames {
+    fn default() -> ReservedNames {
+        ReservedNames::Error
+    }
+}
+
 /// It checks the parsed grammar for common mistakes:
 /// - using Pest keywords
 /// - duplicate rules
 /// - undefined rules
+/// - invalid `@keywords` declarations
+/// - invalid `@alias` declarations
+/// - invalid `@island` declarations
+///
+/// The names of `@extern` declarations count as rule definitions.
 ///
 /// It returns a `Result` with a `Vec` of `Error`s if any of the above is found.
 /// If no errors are found, it returns the vector of names of used builtin rules.
 pub fn validate_pairs(pairs: Pairs<'_, Rule>) -> Result<Vec<&str>, Vec<Error<Rule>>> {
-    let definitions: Vec<_> = pairs
+    validate_pairs_with(pairs, ReservedNames::Error)
+}
+
+/// Same as [`validate_pairs`], but rules named after pest keywords are only reported with
+/// [`ReservedNames::Error`]. With [`ReservedNames::Rename`], they are expected to be renamed with
+/// [`rename_reserved`] once consumed.
+///
+/// [`validate_pairs`]: fn.validate_pairs.html
+/// [`ReservedNames::Error`]: enum.ReservedNames.html#variant.Error
+/// [`ReservedNames::Rename`]: enum.ReservedNames.html#variant.Rename
+/// [`rename_reserved`]: fn.rename_reserved.html
+pub fn validate_pairs_with(
+    pairs: Pairs<'_, Rule>,
+    reserved: ReservedNames,
+) -> Result<Vec<&str>, Vec<Error<Rule>>> {
+    validate(pairs, reserved, &[])
+}
+
+/// Same as [`validate_pairs`], but calls to the rules named in `builtins` are not reported as
+/// undefined, nor returned as used builtin rules. This is meant for built-in rules provided at
+/// runtime, e.g. with `pest_vm`'s `Vm::add_builtin`.
+///
+/// [`validate_pairs`]: fn.validate_pairs.html
+pub fn validate_pairs_with_builtins<'i>(
+    pairs: Pairs<'i, Rule>,
+    builtins: &[&str],
+) -> Result<Vec<&'i str>, Vec<Error<Rule>>> {
+    validate(pairs, ReservedNames::Error, builtins)
+}
+
+fn validate<'i>(
+    pairs: Pairs<'i, Rule>,
+    reserved: ReservedNames,
+    builtins: &[&str],
+) -> Result<Vec<&'i str>, Vec<Error<Rule>>> {
+    let aliases = parser::consume_aliases(pairs.clone());
+    let islands = parser::consume_islands(pairs.clone());
+
+    // Rules annotated with `@dialect` are defined once for every dialect on top of their definition
+    // without an annotation, which `consume_rules` checks when merging them.
+    let mut definitions = vec![];
+    let mut dialect_definitions = vec![];
+    let mut annotated = false;
+    for pair in pairs.clone() {
+        match pair.as_rule() {
+            Rule::dialect => annotated = true,
+            Rule::grammar_rule => {
+                let name = pair.into_inner().next().unwrap().as_span();
+                if mem::take(&mut annotated) {
+                    dialect_definitions.push(name);
+                } else {
+                    definitions.push(name);
+                }
+            }
+            _ => (),
+        }
+    }
+    for name in dialect_definitions {
+        if definitions
+            .iter()
+            .all(|definition| definition.as_str() != name.as_str())
+        {
+            definitions.push(name);
+        }
+    }
+
+    // Aliases are defined as silent rules, and thus cannot share their names with other rules.
+    definitions.extend(aliases.iter().map(|alias| alias.name));
+    definitions.extend(parser::consume_externs(pairs.clone()));
+    definitions.extend(islands.iter().map(|island| island.name));
+    let silent: HashSet<_> = pairs
         .clone()
         .filter(|pair| pair.as_rule() == Rule::grammar_rule)
-        .map(|pair| pair.into_inner().next().unwrap().as_span())
+        .filter(|pair| {
+            pair.clone()
+                .into_inner()
+                .any(|pair| pair.as_rule() == Rule::silent_modifier)
+        })
+        .map(|pair| pair.into_inner().next().unwrap().as_str())
+        .chain(aliases.iter().map(|alias| alias.name.as_str()))
+        .collect();
+
+    let keywords = parser::consume_keywords(pairs.clone());
+    let keyword_rules = keyword_rules(&keywords);
+    let generated: HashSet<_> = keyword_rules
+        .iter()
+        .cloned()
+        .chain(
+            islands
+                .iter()
+                .map(|island| parser::island_text_rule(island.name.as_str())),
+        )
         .collect();
 
     let called_rules: Vec<_> = pairs
@@ -94,26 +225,303 @@ pub fn validate_pairs(pairs: Pairs<'_, Rule>) -> Result<Vec<&str>, Vec<Error<Rul
                 .filter(|pair| pair.as_rule() == Rule::identifier)
                 .map(|pair| pair.as_span())
         })
+        .filter(|span| !generated.contains(span.as_str()) && !builtins.contains(&span.as_str()))
+        .chain(keywords.iter().filter_map(|keywords| keywords.identifier))
+        .chain(
+            islands
+                .iter()
+                .flat_map(|island| island.regions.iter().map(|(_, rule, _)| *rule)),
+        )
         .collect();
 
     let mut errors = vec![];
 
-    errors.extend(validate_pest_keywords(&definitions));
+    if reserved == ReservedNames::Error {
+        errors.extend(validate_pest_keywords(&definitions));
+    }
     errors.extend(validate_already_defined(&definitions));
     errors.extend(validate_undefined(&definitions, &called_rules));
+    errors.extend(validate_keywords(&keywords, &definitions));
+    errors.extend(validate_aliases(&aliases, &definitions, &silent));
+    errors.extend(validate_islands(&islands, &definitions));
 
     if !errors.is_empty() {
         return Err(errors);
     }
 
     let definitions: HashSet<_> = definitions.iter().map(|span| span.as_str()).collect();
-    let called_rules: HashSet<_> = called_rules.iter().map(|span| span.as_str()).collect();
+    let mut called_rules: HashSet<_> = called_rules.iter().map(|span| span.as_str()).collect();
+
+    // Generated keyword rules check for word boundaries with `ASCII_ALPHANUMERIC`.
+    if !keyword_rules.is_empty() {
+        called_rules.insert("ASCII_ALPHANUMERIC");
+    }
+    // Generated island text rules match any character up to a marker.
+    if !islands.is_empty() {
+        called_rules.insert("ANY");
+    }
 
     let defaults = called_rules.difference(&definitions);
 
     Ok(defaults.cloned().collect())
 }
 
+/// Renames the `rules` whose names cannot be used for the variants of a generated `Rule` enum,
+/// updating the references to them. It returns the renamed rules along with the mapping from the
+/// original names to the new ones.
+///
+/// Rust keywords are escaped as raw identifiers, e.g. `r#type`, by the generator, except for `crate`,
+/// `self`, `Self` and `super`, which are always renamed. Rules named after pest keywords
+/// are renamed with [`ReservedNames::Rename`]. A rule is renamed by appending `_` to its name until
+/// it does not collide with another rule.
+///
+/// [`ReservedNames::Rename`]: enum.ReservedNames.html#variant.Rename
+pub fn rename_reserved(
+    rules: Vec<AstRule>,
+    reserved: ReservedNames,
+) -> (Vec<AstRule>, Vec<(String, String)>) {
+    let mut names: HashSet<String> = rules.iter().map(|rule| rule.name.clone()).collect();
+    let mut renamed = HashMap::new();
+    let mut mapping = vec![];
+
+    for rule in &rules {
+        let name = rule.name.as_str();
+        let is_reserved = NON_RAW_KEYWORDS.contains(&name)
+            || (reserved == ReservedNames::Rename && PEST_KEYWORDS.contains(name));
+
+        if is_reserved {
+            let mut new_name = format!("{}_", name);
+            while names.contains(&new_name) {
+                new_name.push('_');
+            }
+
+            names.insert(new_name.clone());
+            renamed.insert(name.to_owned(), new_name.clone());
+            mapping.push((name.to_owned(), new_name));
+        }
+    }
+
+    if renamed.is_empty() {
+        return (rules, mapping);
+    }
+
+    let rules = rules
+        .into_iter()
+        .map(|rule| AstRule {
+            name: renamed.get(&rule.name).cloned().unwrap_or(rule.name),
+            ty: rule.ty,
+            expr: rule.expr.map_top_down(|expr| match expr {
+                Expr::Ident(name) => Expr::Ident(renamed.get(&name).cloned().unwrap_or(name)),
+                expr => expr,
+            }),
+        })
+        .collect();
+
+    (rules, mapping)
+}
+
+fn keyword_rules(keywords: &[ParserKeywords<'_>]) -> HashSet<String> {
+    keywords
+        .iter()
+        .flat_map(|keywords| {
+            keywords
+                .keywords
+                .iter()
+                .map(|(keyword, _)| parser::keyword_rule(keyword))
+                .chain(Some(KEYWORD_RULE.to_owned()))
+        })
+        .collect()
+}
+
+/// Validates that there is at most one `@keywords` declaration, that its keywords are distinct
+/// words of ASCII alphanumeric characters and underscores, and that the rules it generates are
+/// not already defined.
+#[allow(clippy::ptr_arg)]
+pub fn validate_keywords(
+    keywords: &[ParserKeywords<'_>],
+    definitions: &Vec<Span<'_>>,
+) -> Vec<Error<Rule>> {
+    let mut errors = vec![];
+
+    for duplicate in keywords.iter().skip(1) {
+        errors.push(Error::new_from_span(
+            ErrorVariant::CustomError {
+                message: "only one @keywords declaration is allowed".to_owned(),
+            },
+            duplicate.span,
+        ));
+    }
+
+    let mut declared = HashSet::new();
+
+    for (keyword, span) in keywords.iter().flat_map(|keywords| &keywords.keywords) {
+        if keyword.is_empty()
+            || !keyword
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_')
+        {
+            errors.push(Error::new_from_span(
+                ErrorVariant::CustomError {
+                    message: format!(
+                        "keyword {:?} can only contain ASCII alphanumeric characters and \
+                         underscores",
+                        keyword
+                    ),
+                },
+                *span,
+            ));
+        } else if !declared.insert(keyword.as_str()) {
+            errors.push(Error::new_from_span(
+                ErrorVariant::CustomError {
+                    message: format!("keyword {:?} already declared", keyword),
+                },
+                *span,
+            ));
+        }
+    }
+
+    let generated = keyword_rules(keywords);
+
+    for definition in definitions {
+        let name = definition.as_str();
+
+        if generated.contains(name) {
+            errors.push(Error::new_from_span(
+                ErrorVariant::CustomError {
+                    message: format!("rule {} already defined by @keywords", name),
+                },
+                *definition,
+            ));
+        }
+    }
+
+    errors
+}
+
+/// Validates that the rules of `@alias` declarations are defined in the grammar, are not silent,
+/// and have only one alias.
+#[allow(clippy::ptr_arg)]
+pub fn validate_aliases(
+    aliases: &[ParserAlias<'_>],
+    definitions: &Vec<Span<'_>>,
+    silent: &HashSet<&str>,
+) -> Vec<Error<Rule>> {
+    let definitions: HashSet<_> = definitions.iter().map(|span| span.as_str()).collect();
+    let mut aliased = HashSet::new();
+    let mut errors = vec![];
+
+    for rule in aliases.iter().flat_map(|alias| &alias.rules) {
+        let name = rule.as_str();
+
+        let message = if !definitions.contains(name) {
+            format!("rule {} is undefined", name)
+        } else if silent.contains(name) {
+            format!("rule {} is silent and cannot be aliased", name)
+        } else if !aliased.insert(name) {
+            format!("rule {} already aliased", name)
+        } else {
+            continue;
+        };
+
+        errors.push(Error::new_from_span(
+            ErrorVariant::CustomError { message },
+            *rule,
+        ));
+    }
+
+    errors
+}
+
+/// Validates that the markers of `@island` declarations are not empty, and that the text rules
+/// they generate are not already defined.
+#[allow(clippy::ptr_arg)]
+pub fn validate_islands(
+    islands: &[ParserIsland<'_>],
+    definitions: &Vec<Span<'_>>,
+) -> Vec<Error<Rule>> {
+    let mut errors = vec![];
+
+    for island in islands {
+        if island
+            .regions
+            .iter()
+            .any(|(open, _, close)| open.is_empty() || close.is_empty())
+        {
+            errors.push(Error::new_from_span(
+                ErrorVariant::CustomError {
+                    message: "island markers cannot be empty".to_owned(),
+                },
+                island.span,
+            ));
+        }
+
+        let text = parser::island_text_rule(island.name.as_str());
+        for definition in definitions.iter().filter(|span| span.as_str() == text) {
+            errors.push(Error::new_from_span(
+                ErrorVariant::CustomError {
+                    message: format!("rule {} already defined by @island", text),
+                },
+                *definition,
+            ));
+        }
+    }
+
+    errors
+}
+
+/// Validates that the terminals of a grammar parsing bytes, derived with `#[bytes = true]`, only
+/// match bytes, every byte being parsed as the `char` of the same value: that its strings only
+/// contain chars up to `'\u{ff}'`, its ranges are within `'\u{00}'..'\u{ff}'` and it matches no
+/// Unicode properties.
+pub fn validate_bytes(rules: &[ParserRule<'_>]) -> Vec<Error<Rule>> {
+    let defined: HashSet<_> = rules.iter().map(|rule| rule.name.as_str()).collect();
+    let is_byte = |c: char| u32::from(c) <= 0xff;
+
+    rules
+        .iter()
+        .flat_map(|rule| {
+            rule.node.clone().filter_map_top_down(|node| {
+                let message = match node.expr {
+                    ParserExpr::Str(ref string) | ParserExpr::Insens(ref string)
+                        if !string.chars().all(is_byte) =>
+                    {
+                        format!(
+                            "{:?} contains chars other than bytes, which cannot be matched by a \
+                             grammar parsing bytes",
+                            string
+                        )
+                    }
+                    ParserExpr::Range(ref start, ref end)
+                        if !start.chars().chain(end.chars()).all(is_byte) =>
+                    {
+                        format!(
+                            "'{}'..'{}' contains chars other than bytes, which cannot be matched \
+                             by a grammar parsing bytes",
+                            start, end
+                        )
+                    }
+                    ParserExpr::Ident(ref name)
+                        if UNICODE_PROPERTY_NAMES.contains(&name.as_str())
+                            && !defined.contains(name.as_str()) =>
+                    {
+                        format!(
+                            "{} is a Unicode property, which cannot be matched by a grammar \
+                             parsing bytes",
+                            name
+                        )
+                    }
+                    _ => return None,
+                };
+
+                Some(Error::new_from_span(
+                    ErrorVariant::CustomError { message },
+                    node.span,
+                ))
+            })
+        })
+        .collect()
+}
+
 /// Validates that the given `definitions` do not contain any Rust keywords.
 #[allow(clippy::ptr_arg)]
 #[deprecated = "Rust keywords are no longer restricted from the pest grammar"]
@@ -206,18 +614,52 @@ pub fn validate_undefined<'i>(
     errors
 }
 
+/// How left-recursive rules are handled by [`validate_ast_with`].
+///
+/// [`validate_ast_with`]: fn.validate_ast_with.html
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum LeftRecursion {
+    /// The rules are reported as errors
+    Error,
+    /// The rules are allowed, and are expected to be matched by growing seeds with
+    /// `ParserState::left_recursive`. They are listed by [`left_recursive_rules`].
+    ///
+    /// [`left_recursive_rules`]: fn.left_recursive_rules.html
+    Grow,
+}
+
+impl Default for LeftRecursion {
+    fn default() -> LeftRecursion {
+        LeftRecursion::Error
+    }
+}
+
 /// Validates the abstract syntax tree for common mistakes:
 /// - infinite repetitions
 /// - choices that cannot be reached
 /// - left recursion
 #[allow(clippy::ptr_arg)]
 pub fn validate_ast<'a, 'i: 'a>(rules: &'a Vec<ParserRule<'i>>) -> Vec<Error<Rule>> {
+    validate_ast_with(rules, LeftRecursion::Error)
+}
+
+/// Same as [`validate_ast`], but left recursion is only reported with [`LeftRecursion::Error`].
+///
+/// [`validate_ast`]: fn.validate_ast.html
+/// [`LeftRecursion::Error`]: enum.LeftRecursion.html#variant.Error
+#[allow(clippy::ptr_arg)]
+pub fn validate_ast_with<'a, 'i: 'a>(
+    rules: &'a Vec<ParserRule<'i>>,
+    left_recursion: LeftRecursion,
+) -> Vec<Error<Rule>> {
     let mut errors = vec![];
 
     errors.extend(validate_repetition(rules));
     errors.extend(validate_choices(rules));
     errors.extend(validate_whitespace_comment(rules));
-    errors.extend(validate_left_recursion(rules));
+    if left_recursion == LeftRecursion::Error {
+        errors.extend(validate_left_recursion(rules));
+    }
 
     errors.sort_by_key(|error| match error.location {
         InputLocation::Span(span) => span,
@@ -227,6 +669,190 @@ pub fn validate_ast<'a, 'i: 'a>(rules: &'a Vec<ParserRule<'i>>) -> Vec<Error<Rul
     errors
 }
 
+/// Checks the abstract syntax tree for valid constructs which likely do not behave as intended,
+/// returning them as warnings rather than errors:
+/// - literals of non-atomic rules containing whitespace which `WHITESPACE` also matches, e.g.
+///   `"else if"`, since implicit whitespace does not apply within literals
+#[allow(clippy::ptr_arg)]
+pub fn warn_ast<'a, 'i: 'a>(rules: &'a Vec<ParserRule<'i>>) -> Vec<Error<Rule>> {
+    let mut warnings = vec![];
+
+    warnings.extend(warn_whitespace_literals(rules));
+
+    warnings.sort_by_key(|warning| match warning.location {
+        InputLocation::Span(span) => span,
+        _ => unreachable!(),
+    });
+
+    warnings
+}
+
+/// Returns the rules which cannot be reached from the rules named `entry_points`, in the order of
+/// `rules`. `WHITESPACE` and `COMMENT` are always reachable, since non-atomic rules reference them
+/// implicitly.
+pub fn unreachable_rules<'a, 'i: 'a>(
+    rules: &'a [ParserRule<'i>],
+    entry_points: &[&str],
+) -> Vec<&'a ParserRule<'i>> {
+    let map = to_hash_map(rules);
+    let mut reachable = HashSet::new();
+    let mut pending: Vec<String> = entry_points
+        .iter()
+        .chain(&["WHITESPACE", "COMMENT"])
+        .map(|&name| name.to_owned())
+        .collect();
+
+    while let Some(name) = pending.pop() {
+        if let Some(node) = map.get(&name) {
+            if reachable.insert(name) {
+                pending.extend((*node).clone().filter_map_top_down(|node| match node.expr {
+                    ParserExpr::Ident(name) => Some(name),
+                    _ => None,
+                }));
+            }
+        }
+    }
+
+    rules
+        .iter()
+        .filter(|rule| !reachable.contains(&rule.name))
+        .collect()
+}
+
+/// Warns about the rules which cannot be reached from the rules named `entry_points`, like
+/// [`unreachable_rules`], e.g. rules left over from earlier versions of a grammar.
+///
+/// [`unreachable_rules`]: fn.unreachable_rules.html
+pub fn warn_unreachable<'a, 'i: 'a>(
+    rules: &'a [ParserRule<'i>],
+    entry_points: &[&str],
+) -> Vec<Error<Rule>> {
+    unreachable_rules(rules, entry_points)
+        .into_iter()
+        .map(|rule| {
+            Error::new_from_span(
+                ErrorVariant::CustomError {
+                    message: format!(
+                        "rule {} cannot be reached from {}",
+                        rule.name,
+                        entry_points.join(", ")
+                    ),
+                },
+                rule.span,
+            )
+        })
+        .collect()
+}
+
+fn warn_whitespace_literals<'a, 'i: 'a>(rules: &'a [ParserRule<'i>]) -> Vec<Error<Rule>> {
+    let map = to_hash_map(rules);
+    let whitespace = match map.get("WHITESPACE") {
+        Some(node) => &node.expr,
+        None => return vec![],
+    };
+
+    rules
+        .iter()
+        .filter(|rule| {
+            matches!(
+                rule.ty,
+                RuleType::Normal | RuleType::Silent | RuleType::NonAtomic
+            ) && rule.name != "WHITESPACE"
+                && rule.name != "COMMENT"
+        })
+        .flat_map(|rule| {
+            rule.node
+                .clone()
+                .filter_map_top_down(|node| match node.expr {
+                    ParserExpr::Str(ref string) | ParserExpr::Insens(ref string) => {
+                        if string.chars().any(|c| {
+                            c.is_whitespace() && can_start_with(whitespace, c, &map, &mut vec![])
+                        }) {
+                            Some(Error::new_from_span(
+                                ErrorVariant::CustomError {
+                                    message: format!(
+                                    "{:?} contains whitespace which WHITESPACE also matches, but \
+                                     implicit whitespace does not apply within literals; consider \
+                                     splitting it into several literals or matching it in an \
+                                     atomic rule",
+                                    string
+                                ),
+                                },
+                                node.span,
+                            ))
+                        } else {
+                            None
+                        }
+                    }
+                    _ => None,
+                })
+        })
+        .collect()
+}
+
+// Whether `expr` can match text starting with `c`, as far as literals, ranges and the built-in
+// rules matching whitespace can tell.
+fn can_start_with<'i>(
+    expr: &ParserExpr<'i>,
+    c: char,
+    rules: &HashMap<String, &ParserNode<'i>>,
+    trace: &mut Vec<String>,
+) -> bool {
+    match *expr {
+        ParserExpr::Str(ref string) => string.starts_with(c),
+        ParserExpr::Insens(ref string) => string
+            .chars()
+            .next()
+            .map_or(false, |first| first.eq_ignore_ascii_case(&c)),
+        ParserExpr::Range(ref start, ref end) => match (start.chars().next(), end.chars().next()) {
+            (Some(start), Some(end)) => start <= c && c <= end,
+            _ => false,
+        },
+        ParserExpr::Ident(ref ident) => match ident.as_str() {
+            "ANY" => true,
+            "ASCII" => c.is_ascii(),
+            "NEWLINE" => c == '\n' || c == '\r',
+            "WHITE_SPACE" | "SPACE_SEPARATOR" => c.is_whitespace(),
+            _ => {
+                if trace.contains(ident) {
+                    return false;
+                }
+                match rules.get(ident) {
+                    Some(node) => {
+                        trace.push(ident.clone());
+                        let result = can_start_with(&node.expr, c, rules, trace);
+                        trace.pop().unwrap();
+                        result
+                    }
+                    None => false,
+                }
+            }
+        },
+        ParserExpr::Seq(ref lhs, ref rhs) => {
+            can_start_with(&lhs.expr, c, rules, trace)
+                || (is_non_progressing(&lhs.expr, rules, &mut vec![])
+                    && can_start_with(&rhs.expr, c, rules, trace))
+        }
+        ParserExpr::Choice(ref lhs, ref rhs) | ParserExpr::Dialect(_, ref lhs, ref rhs) => {
+            can_start_with(&lhs.expr, c, rules, trace) || can_start_with(&rhs.expr, c, rules, trace)
+        }
+        ParserExpr::Opt(ref node)
+        | ParserExpr::Rep(ref node)
+        | ParserExpr::RepOnce(ref node)
+        | ParserExpr::RepExact(ref node, _)
+        | ParserExpr::RepMin(ref node, _)
+        | ParserExpr::RepMax(ref node, _)
+        | ParserExpr::RepMinMax(ref node, _, _)
+        | ParserExpr::Push(ref node)
+        | ParserExpr::Labeled(_, ref node)
+        | ParserExpr::Predicate(_, ref node) => can_start_with(&node.expr, c, rules, trace),
+        ParserExpr::PosPred(_)
+        | ParserExpr::NegPred(_)
+        | ParserExpr::PosLookbehind(_)
+        | ParserExpr::PeekSlice(..) => false,
+    }
+}
+
 fn is_non_progressing<'i>(
     expr: &ParserExpr<'i>,
     rules: &HashMap<String, &ParserNode<'i>>,
@@ -253,14 +879,18 @@ fn is_non_progressing<'i>(
         }
         ParserExpr::PosPred(_) => true,
         ParserExpr::NegPred(_) => true,
+        ParserExpr::PosLookbehind(_) => true,
         ParserExpr::Seq(ref lhs, ref rhs) => {
             is_non_progressing(&lhs.expr, rules, trace)
                 && is_non_progressing(&rhs.expr, rules, trace)
         }
-        ParserExpr::Choice(ref lhs, ref rhs) => {
+        ParserExpr::Choice(ref lhs, ref rhs) | ParserExpr::Dialect(_, ref lhs, ref rhs) => {
             is_non_progressing(&lhs.expr, rules, trace)
                 || is_non_progressing(&rhs.expr, rules, trace)
         }
+        ParserExpr::Labeled(_, ref node) | ParserExpr::Predicate(_, ref node) => {
+            is_non_progressing(&node.expr, rules, trace)
+        }
         _ => false,
     }
 }
@@ -290,9 +920,10 @@ fn is_non_failing<'i>(
         ParserExpr::Seq(ref lhs, ref rhs) => {
             is_non_failing(&lhs.expr, rules, trace) && is_non_failing(&rhs.expr, rules, trace)
         }
-        ParserExpr::Choice(ref lhs, ref rhs) => {
+        ParserExpr::Choice(ref lhs, ref rhs) | ParserExpr::Dialect(_, ref lhs, ref rhs) => {
             is_non_failing(&lhs.expr, rules, trace) || is_non_failing(&rhs.expr, rules, trace)
         }
+        ParserExpr::Labeled(_, ref node) => is_non_failing(&node.expr, rules, trace),
         _ => false,
     }
 }
@@ -417,13 +1048,34 @@ fn validate_whitespace_comment<'a, 'i: 'a>(rules: &'a [ParserRule<'i>]) -> Vec<E
 
 fn validate_left_recursion<'a, 'i: 'a>(rules: &'a [ParserRule<'i>]) -> Vec<Error<Rule>> {
     left_recursion(to_hash_map(rules))
+        .into_iter()
+        .map(|(_, error)| error)
+        .collect()
+}
+
+/// Returns the names of the left-recursive rules of `rules`, in order, i.e. the rules which can
+/// call themselves before matching any input, directly or through other rules.
+pub fn left_recursive_rules(rules: &[ParserRule<'_>]) -> Vec<String> {
+    let recursive: HashSet<_> = left_recursion(to_hash_map(rules))
+        .into_iter()
+        .map(|(name, _)| name)
+        .collect();
+
+    rules
+        .iter()
+        .filter(|rule| recursive.contains(&rule.name))
+        .map(|rule| rule.name.clone())
+        .collect()
 }
 
 fn to_hash_map<'a, 'i: 'a>(rules: &'a [ParserRule<'i>]) -> HashMap<String, &'a ParserNode<'i>> {
     rules.iter().map(|r| (r.name.clone(), &r.node)).collect()
 }
 
-fn left_recursion<'a, 'i: 'a>(rules: HashMap<String, &'a ParserNode<'i>>) -> Vec<Error<Rule>> {
+// The left-recursive rules, along with the errors reporting them.
+fn left_recursion<'a, 'i: 'a>(
+    rules: HashMap<String, &'a ParserNode<'i>>,
+) -> Vec<(String, Error<Rule>)> {
     fn check_expr<'a, 'i: 'a>(
         node: &'a ParserNode<'i>,
         rules: &'a HashMap<String, &ParserNode<'i>>,
@@ -471,7 +1123,7 @@ fn left_recursion<'a, 'i: 'a>(rules: HashMap<String, &'a ParserNode<'i>>) -> Vec
                     check_expr(lhs, rules, trace)
                 }
             }
-            ParserExpr::Choice(ref lhs, ref rhs) => {
+            ParserExpr::Choice(ref lhs, ref rhs) | ParserExpr::Dialect(_, ref lhs, ref rhs) => {
                 check_expr(lhs, rules, trace).or_else(|| check_expr(rhs, rules, trace))
             }
             ParserExpr::Rep(ref node) => check_expr(node, rules, trace),
@@ -479,7 +1131,10 @@ fn left_recursion<'a, 'i: 'a>(rules: HashMap<String, &'a ParserNode<'i>>) -> Vec
             ParserExpr::Opt(ref node) => check_expr(node, rules, trace),
             ParserExpr::PosPred(ref node) => check_expr(node, rules, trace),
             ParserExpr::NegPred(ref node) => check_expr(node, rules, trace),
+            ParserExpr::PosLookbehind(ref node) => check_expr(node, rules, trace),
             ParserExpr::Push(ref node) => check_expr(node, rules, trace),
+            ParserExpr::Labeled(_, ref node) => check_expr(node, rules, trace),
+            ParserExpr::Predicate(_, ref node) => check_expr(node, rules, trace),
             _ => None,
         }
     }
@@ -487,10 +1142,8 @@ fn left_recursion<'a, 'i: 'a>(rules: HashMap<String, &'a ParserNode<'i>>) -> Vec
     let mut errors = vec![];
 
     for (name, node) in &rules {
-        let name = name.clone();
-
-        if let Some(error) = check_expr(node, &rules, &mut vec![name]) {
-            errors.push(error);
+        if let Some(error) = check_expr(node, &rules, &mut vec![name.clone()]) {
+            errors.push((name.clone(), error));
         }
     }
 
@@ -536,6 +1189,231 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn rename_pest_keyword() {
+        let input = "ANY = { \"a\" } ANY_ = { ANY ~ self } self = { EOI }";
+        let pairs = PestParser::parse(Rule::grammar_rules, input).unwrap();
+        let defaults = validate_pairs_with(pairs.clone(), ReservedNames::Rename).unwrap();
+        let (rules, renamed) =
+            rename_reserved(consume_rules(pairs).unwrap(), ReservedNames::Rename);
+
+        assert_eq!(defaults, vec!["EOI"]);
+        assert_eq!(
+            renamed,
+            vec![
+                ("ANY".to_owned(), "ANY__".to_owned()),
+                ("self".to_owned(), "self_".to_owned())
+            ]
+        );
+        assert_eq!(
+            rules.iter().map(|rule| &rule.name[..]).collect::<Vec<_>>(),
+            vec!["ANY__", "ANY_", "self_"]
+        );
+        assert_eq!(
+            rules[1].expr,
+            Expr::Seq(
+                Box::new(Expr::Ident("ANY__".to_owned())),
+                Box::new(Expr::Ident("self_".to_owned()))
+            )
+        );
+    }
+
+    #[test]
+    fn rename_rust_keyword_only() {
+        let input = "ANY_ = { ANY } type = { \"a\" } Self = { type }";
+        let pairs = PestParser::parse(Rule::grammar_rules, input).unwrap();
+        let (rules, renamed) = rename_reserved(consume_rules(pairs).unwrap(), ReservedNames::Error);
+
+        assert_eq!(renamed, vec![("Self".to_owned(), "Self_".to_owned())]);
+        assert_eq!(rules[0].expr, Expr::Ident("ANY".to_owned()));
+        assert_eq!(rules[2].expr, Expr::Ident("type".to_owned()));
+    }
+
+    #[test]
+    fn keywords() {
+        let input = "@keywords(ident) { \"if\" \"else\" }\nident = @{ ASCII_ALPHA+ }\na = { kw_if ~ keyword }";
+        let mut defaults =
+            validate_pairs(PestParser::parse(Rule::grammar_rules, input).unwrap()).unwrap();
+        defaults.sort_unstable();
+
+        assert_eq!(defaults, vec!["ASCII_ALPHA", "ASCII_ALPHANUMERIC"]);
+    }
+
+    #[test]
+    #[should_panic(expected = "grammar error
+
+ --> 1:18
+  |
+1 | @keywords { \"if\" \"if\" }
+  |                  ^--^
+  |
+  = keyword \"if\" already declared")]
+    fn keywords_duplicate() {
+        let input = "@keywords { \"if\" \"if\" }";
+        unwrap_or_report(validate_pairs(
+            PestParser::parse(Rule::grammar_rules, input).unwrap(),
+        ));
+    }
+
+    #[test]
+    #[should_panic(expected = "grammar error
+
+ --> 1:18
+  |
+1 | @keywords { \"if\" \"a-b\" }
+  |                  ^---^
+  |
+  = keyword \"a-b\" can only contain ASCII alphanumeric characters and underscores")]
+    fn keywords_invalid() {
+        let input = "@keywords { \"if\" \"a-b\" }";
+        unwrap_or_report(validate_pairs(
+            PestParser::parse(Rule::grammar_rules, input).unwrap(),
+        ));
+    }
+
+    #[test]
+    #[should_panic(expected = "grammar error
+
+ --> 2:11
+  |
+2 | @keywords(ident) { \"if\" }
+  |           ^---^
+  |
+  = rule ident is undefined
+
+ --> 2:1
+  |
+2 | @keywords(ident) { \"if\" }
+  | ^-----------------------^
+  |
+  = only one @keywords declaration is allowed
+
+ --> 3:1
+  |
+3 | kw_if = { \"if\" }
+  | ^---^
+  |
+  = rule kw_if already defined by @keywords")]
+    fn keywords_conflicts() {
+        let input = "@keywords { \"else\" }\n@keywords(ident) { \"if\" }\nkw_if = { \"if\" }";
+        unwrap_or_report(validate_pairs(
+            PestParser::parse(Rule::grammar_rules, input).unwrap(),
+        ));
+    }
+
+    #[test]
+    fn externs() {
+        let input = "@extern { expr }\na = { expr ~ EOI }";
+        let defaults =
+            validate_pairs(PestParser::parse(Rule::grammar_rules, input).unwrap()).unwrap();
+
+        assert_eq!(defaults, vec!["EOI"]);
+    }
+
+    #[test]
+    #[should_panic(expected = "grammar error
+
+ --> 1:11
+  |
+1 | @extern { expr }
+  |           ^--^
+  |
+  = rule expr already defined")]
+    fn externs_already_defined() {
+        let input = "@extern { expr }\nexpr = { \"a\" }";
+        unwrap_or_report(validate_pairs(
+            PestParser::parse(Rule::grammar_rules, input).unwrap(),
+        ));
+    }
+
+    #[test]
+    fn islands() {
+        let input =
+            "@island(doc) { \"{{\" expr \"}}\" }\nexpr = { ASCII_DIGIT+ }\na = { doc | doc_text }";
+        let mut defaults =
+            validate_pairs(PestParser::parse(Rule::grammar_rules, input).unwrap()).unwrap();
+        defaults.sort_unstable();
+
+        assert_eq!(defaults, vec!["ANY", "ASCII_DIGIT"]);
+    }
+
+    #[test]
+    #[should_panic(expected = "grammar error
+
+ --> 1:19
+  |
+1 | @island(doc) { \"\" expr \"}}\" }
+  |                   ^--^
+  |
+  = rule expr is undefined
+
+ --> 1:1
+  |
+1 | @island(doc) { \"\" expr \"}}\" }
+  | ^---------------------------^
+  |
+  = island markers cannot be empty
+
+ --> 2:1
+  |
+2 | doc_text = { \"a\" }
+  | ^------^
+  |
+  = rule doc_text already defined by @island")]
+    fn islands_invalid() {
+        let input = "@island(doc) { \"\" expr \"}}\" }\ndoc_text = { \"a\" }";
+        unwrap_or_report(validate_pairs(
+            PestParser::parse(Rule::grammar_rules, input).unwrap(),
+        ));
+    }
+
+    #[test]
+    fn aliases() {
+        let input = "@alias(string) { single double }\nsingle = { \"'\" }\ndouble = { ASCII }\na = { string }";
+        let defaults =
+            validate_pairs(PestParser::parse(Rule::grammar_rules, input).unwrap()).unwrap();
+
+        assert_eq!(defaults, vec!["ASCII"]);
+    }
+
+    #[test]
+    #[should_panic(expected = "grammar error
+
+ --> 1:8
+  |
+1 | @alias(a) { b c d }
+  |        ^
+  |
+  = rule a already defined
+
+ --> 1:15
+  |
+1 | @alias(a) { b c d }
+  |               ^
+  |
+  = rule c is silent and cannot be aliased
+
+ --> 1:17
+  |
+1 | @alias(a) { b c d }
+  |                 ^
+  |
+  = rule d is undefined
+
+ --> 2:13
+  |
+2 | @alias(e) { b }
+  |             ^
+  |
+  = rule b already aliased")]
+    fn aliases_invalid() {
+        let input =
+            "@alias(a) { b c d }\n@alias(e) { b }\na = { \"a\" }\nb = { \"b\" }\nc = _{ \"c\" }";
+        unwrap_or_report(validate_pairs(
+            PestParser::parse(Rule::grammar_rules, input).unwrap(),
+        ));
+    }
+
     #[test]
     #[should_panic(expected = "grammar error
 
@@ -743,6 +1621,19 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn grown_left_recursion() {
+        let input =
+            "sum = { sum ~ \"+\" ~ term | term } term = { call | \"x\" } call = { term ~ \"()\" }";
+        let pairs = PestParser::parse(Rule::grammar_rules, input).unwrap();
+
+        assert!(parser::consume_rules_with(pairs.clone(), LeftRecursion::Grow).is_ok());
+        assert_eq!(
+            left_recursive_rules(&parser::consume_rules_with_spans(pairs).unwrap()),
+            ["sum", "term", "call"]
+        );
+    }
+
     #[test]
     #[should_panic(expected = "grammar error
 
@@ -805,4 +1696,100 @@ mod tests {
             PestParser::parse(Rule::grammar_rules, input).unwrap(),
         ));
     }
+
+    fn warnings(input: &str) -> Vec<String> {
+        let pairs = PestParser::parse(Rule::grammar_rules, input).unwrap();
+        let rules = parser::consume_rules_with_spans(pairs).unwrap();
+        warn_ast(&rules)
+            .into_iter()
+            .map(|warning| format!("{}", warning))
+            .collect()
+    }
+
+    #[test]
+    fn bytes() {
+        let input = "a = { \"\\x01\\u{ff}\" ~ ^\"get\" ~ '\\u{80}'..'\\u{ff}' ~ ASCII_DIGIT ~ b }\n\
+                     b = { \"\\u{100}\" | ^\"\\u{e9}\\u{3b1}\" | 'a'..'\\u{100}' | XID_START | LETTER }\n\
+                     LETTER = { 'a'..'z' }";
+        let pairs = PestParser::parse(Rule::grammar_rules, input).unwrap();
+        let rules = parser::consume_rules_with_spans(pairs).unwrap();
+
+        let messages: Vec<_> = validate_bytes(&rules)
+            .into_iter()
+            .map(|error| error.variant.message().into_owned())
+            .collect();
+        assert_eq!(
+            messages,
+            [
+                "\"\u{100}\" contains chars other than bytes, which cannot be matched by a grammar \
+                 parsing bytes",
+                "\"éα\" contains chars other than bytes, which cannot be matched by a grammar \
+                 parsing bytes",
+                "'a'..'\u{100}' contains chars other than bytes, which cannot be matched by a \
+                 grammar parsing bytes",
+                "XID_START is a Unicode property, which cannot be matched by a grammar parsing \
+                 bytes",
+            ]
+        );
+    }
+
+    #[test]
+    fn unreachable() {
+        let input = "a = { b ~ c } b = { \"b\" } c = _{ b } d = { e } e = { d }\n\
+                     WHITESPACE = _{ f } f = { \" \" } g = { a }";
+        let pairs = PestParser::parse(Rule::grammar_rules, input).unwrap();
+        let rules = parser::consume_rules_with_spans(pairs).unwrap();
+
+        let names: Vec<_> = unreachable_rules(&rules, &["a"])
+            .into_iter()
+            .map(|rule| rule.name.as_str())
+            .collect();
+        assert_eq!(names, ["d", "e", "g"]);
+        assert_eq!(unreachable_rules(&rules, &["d", "g"]).len(), 0);
+
+        let warnings = warn_unreachable(&rules, &["a", "d"]);
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(
+            format!("{}", warnings[0]),
+            " --> 2:33
+  |
+2 | WHITESPACE = _{ f } f = { \" \" } g = { a }
+  |                                 ^
+  |
+  = rule g cannot be reached from a, d"
+        );
+    }
+
+    #[test]
+    fn whitespace_literal() {
+        let input = "WHITESPACE = _{ \" \" | \"\\t\" } a = { \"else if\" }";
+        assert_eq!(
+            warnings(input),
+            vec![" --> 1:36
+  |
+1 | WHITESPACE = _{ \" \" | \"\\t\" } a = { \"else if\" }
+  |                                    ^-------^
+  |
+  = \"else if\" contains whitespace which WHITESPACE also matches, but implicit whitespace does not apply within literals; consider splitting it into several literals or matching it in an atomic rule"]
+        );
+    }
+
+    #[test]
+    fn whitespace_literal_through_rules() {
+        let input = "WHITESPACE = _{ space* ~ NEWLINE } space = { \" \" } a = { ^\"a\\nb\" }";
+        assert_eq!(warnings(input).len(), 1);
+    }
+
+    #[test]
+    fn whitespace_literal_ignored() {
+        assert!(warnings("a = { \"else if\" }").is_empty());
+        assert!(
+            warnings("WHITESPACE = _{ \" \" } a = @{ \"else if\" } b = ${ \"else if\" }")
+                .is_empty()
+        );
+        assert!(
+            warnings("WHITESPACE = _{ \" \" } a = { \"else\\nif\" ~ \"else\" ~ \"if\" }")
+                .is_empty()
+        );
+    }
 }
@@ -0,0 +1,88 @@
+// pest. The Elegant Parser
+// Copyright (c) 2018 Dragoș Tiselice
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Regression benchmarks for line and column computations on pathological inputs, which should
+//! all scale linearly with the size of the input.
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+
+use pest::error::{Error, ErrorVariant};
+use pest::{ColumnMode, LineIndex, Position, Span};
+
+const SIZES: [usize; 3] = [100_000, 200_000, 400_000];
+
+fn error(input: &str) -> Error<()> {
+    let pos = Position::new(input, input.len() - 1).unwrap();
+    Error::new_from_pos(
+        ErrorVariant::CustomError {
+            message: "error".to_owned(),
+        },
+        pos,
+    )
+}
+
+fn criterion_benchmark(c: &mut Criterion) {
+    let mut group = c.benchmark_group("minified line error");
+    for &size in &SIZES {
+        let input = "{\"a\":[1,\"é\u{200D}👍🏽\"]},".repeat(size / 20);
+        group.bench_with_input(BenchmarkId::from_parameter(size), &input, |b, input| {
+            b.iter(|| {
+                format!(
+                    "{}",
+                    error(input)
+                        .with_column_mode(ColumnMode::DisplayWidth)
+                        .with_snippet_width(80)
+                )
+            })
+        });
+    }
+    group.finish();
+
+    let mut group = c.benchmark_group("span lines");
+    for &size in &SIZES {
+        let input = "ab\n".repeat(size / 3);
+        group.bench_with_input(BenchmarkId::from_parameter(size), &input, |b, input| {
+            b.iter(|| Span::new(input, 0, input.len()).unwrap().lines().count())
+        });
+    }
+    group.finish();
+
+    let mut group = c.benchmark_group("long line index");
+    for &size in &SIZES {
+        let input = "a\u{200D}e\u{301}".repeat(size / 6);
+        group.bench_with_input(BenchmarkId::from_parameter(size), &input, |b, input| {
+            b.iter(|| {
+                let index = LineIndex::new(input).with_column_mode(ColumnMode::Graphemes);
+                (0..input.len())
+                    .step_by(101)
+                    .filter_map(|pos| index.try_line_col(pos))
+                    .count()
+            })
+        });
+    }
+    group.finish();
+
+    let mut group = c.benchmark_group("zero-width joiners");
+    for &size in &SIZES {
+        let input = "\u{200D}".repeat(size / 3);
+        group.bench_with_input(BenchmarkId::from_parameter(size), &input, |b, input| {
+            b.iter(|| {
+                let index = LineIndex::new(input).with_column_mode(ColumnMode::Graphemes);
+                (0..input.len())
+                    .step_by(99)
+                    .filter_map(|pos| index.try_line_col(pos))
+                    .count()
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, criterion_benchmark);
+criterion_main!(benches);
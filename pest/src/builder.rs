@@ -0,0 +1,263 @@
+// pest. The Elegant Parser
+// Copyright (c) 2018 Dragoș Tiselice
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::fmt;
+use core::marker::PhantomData;
+
+use crate::error::Error;
+use crate::external::{self, SharedExternFn};
+use crate::iterators::Pairs;
+use crate::parser::Parser;
+use crate::pratt_parser::PrattParser;
+use crate::RuleType;
+
+struct Setup<R: RuleType> {
+    pratt: Option<PrattParser<R>>,
+    externs: Vec<(R, SharedExternFn<R>)>,
+}
+
+/// A builder for a [`ParserHandle`], which gathers the setup of a parser which is expensive to
+/// repeat, like its [`PrattParser`] and the parsers bound to its `@extern` rules.
+///
+/// [`ParserHandle`]: struct.ParserHandle.html
+/// [`PrattParser`]: pratt_parser/struct.PrattParser.html
+pub struct ParserBuilder<P, R: RuleType> {
+    setup: Setup<R>,
+    parser: PhantomData<fn() -> P>,
+}
+
+impl<P: Parser<R>, R: RuleType + 'static> ParserBuilder<P, R> {
+    /// Creates a builder for a handle of `parser`.
+    pub fn new(parser: P) -> ParserBuilder<P, R> {
+        let _ = parser;
+        ParserBuilder {
+            setup: Setup {
+                pratt: None,
+                externs: Vec::new(),
+            },
+            parser: PhantomData,
+        }
+    }
+
+    /// Sets the `PrattParser` returned by [`ParserHandle::pratt`].
+    ///
+    /// [`ParserHandle::pratt`]: struct.ParserHandle.html#method.pratt
+    pub fn with_pratt(mut self, pratt: PrattParser<R>) -> ParserBuilder<P, R> {
+        self.setup.pratt = Some(pratt);
+        self
+    }
+
+    /// Binds the `@extern` rule `rule` to another `parser` for the parses of the handle, like
+    /// [`Parser::bind`], but without affecting other parses on the same thread.
+    ///
+    /// [`Parser::bind`]: trait.Parser.html#method.bind
+    #[allow(clippy::perf)]
+    pub fn with_extern<Q, S, F>(
+        mut self,
+        rule: R,
+        parser: Q,
+        start_rule: S,
+        map: F,
+    ) -> ParserBuilder<P, R>
+    where
+        Q: Parser<S>,
+        S: RuleType + Send + Sync + 'static,
+        F: Fn(S) -> Option<R> + Send + Sync + 'static,
+    {
+        let _ = parser;
+        let f: SharedExternFn<R> =
+            Arc::new(move |state| state.nested(|input| Q::parse(start_rule, input), &map));
+
+        self.setup.externs.retain(|(bound, _)| *bound != rule);
+        self.setup.externs.push((rule, f));
+        self
+    }
+
+    /// Builds the handle.
+    pub fn build(self) -> ParserHandle<P, R> {
+        ParserHandle {
+            setup: Arc::new(self.setup),
+            parser: PhantomData,
+        }
+    }
+}
+
+impl<P, R: RuleType> fmt::Debug for ParserBuilder<P, R> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ParserBuilder")
+            .field("pratt", &self.setup.pratt.is_some())
+            .field(
+                "externs",
+                &self
+                    .setup
+                    .externs
+                    .iter()
+                    .map(|(rule, _)| rule)
+                    .collect::<Vec<_>>(),
+            )
+            .finish()
+    }
+}
+
+/// An immutable handle of a parser along with its setup, built with a [`ParserBuilder`].
+///
+/// A handle is `Send + Sync` whenever its `Rule` type is, so that it can be built once, e.g. in a
+/// `OnceLock`, and shared between threads. Cloning it is cheap, since clones share the same setup.
+///
+/// [`ParserBuilder`]: struct.ParserBuilder.html
+///
+/// # Examples
+///
+/// ```
+/// # use pest::{Parser, ParserBuilder, iterators::Pairs, error::Error};
+/// # use pest::pratt_parser::{Assoc, Op, PrattParser};
+/// # #[allow(non_camel_case_types)]
+/// # #[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+/// # enum Rule { template, expr, number, add }
+/// # struct TemplateParser;
+/// # impl Parser<Rule> for TemplateParser {
+/// #     fn parse(_: Rule, input: &str) -> Result<Pairs<'_, Rule>, Error<Rule>> {
+/// #         pest::state(input, |s| {
+/// #             s.rule(Rule::template, |s| {
+/// #                 s.match_string("{{")
+/// #                     .and_then(|s| s.rule(Rule::expr, |s| s.external(Rule::expr)))
+/// #                     .and_then(|s| s.match_string("}}"))
+/// #             })
+/// #         })
+/// #     }
+/// # }
+/// # #[allow(non_camel_case_types)]
+/// # #[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+/// # enum ExprRule { main, digits }
+/// # struct ExprParser;
+/// # impl Parser<ExprRule> for ExprParser {
+/// #     fn parse(_: ExprRule, input: &str) -> Result<Pairs<'_, ExprRule>, Error<ExprRule>> {
+/// #         pest::state(input, |s| {
+/// #             s.rule(ExprRule::main, |s| s.rule(ExprRule::digits, |s| s.match_string("42")))
+/// #         })
+/// #     }
+/// # }
+/// let handle = ParserBuilder::new(TemplateParser)
+///     .with_pratt(PrattParser::new().op(Op::infix(Rule::add, Assoc::Left)))
+///     .with_extern(Rule::expr, ExprParser, ExprRule::main, |rule| match rule {
+///         ExprRule::digits => Some(Rule::number),
+///         _ => None,
+///     })
+///     .build();
+///
+/// let threads: Vec<_> = (0..2)
+///     .map(|_| {
+///         let handle = handle.clone();
+///         std::thread::spawn(move || {
+///             let pairs = handle.parse(Rule::template, "{{42}}").unwrap();
+///             format!("{}", pairs)
+///         })
+///     })
+///     .collect();
+///
+/// for thread in threads {
+///     assert_eq!(thread.join().unwrap(), "[template(0, 6, [expr(2, 4, [number(2, 4)])])]");
+/// }
+/// ```
+pub struct ParserHandle<P, R: RuleType> {
+    setup: Arc<Setup<R>>,
+    parser: PhantomData<fn() -> P>,
+}
+
+impl<P: Parser<R>, R: RuleType + 'static> ParserHandle<P, R> {
+    /// Parses `input` from `rule` with the parser, while the `@extern` rules of the handle are
+    /// bound on the current thread.
+    #[allow(clippy::perf)]
+    pub fn parse<'i>(&self, rule: R, input: &'i str) -> Result<Pairs<'i, R>, Error<R>> {
+        external::with_bindings(&self.setup.externs, || P::parse(rule, input))
+    }
+
+    /// Returns the `PrattParser` of the handle, if there is any.
+    pub fn pratt(&self) -> Option<&PrattParser<R>> {
+        self.setup.pratt.as_ref()
+    }
+}
+
+impl<P, R: RuleType> Clone for ParserHandle<P, R> {
+    fn clone(&self) -> ParserHandle<P, R> {
+        ParserHandle {
+            setup: Arc::clone(&self.setup),
+            parser: PhantomData,
+        }
+    }
+}
+
+impl<P, R: RuleType> fmt::Debug for ParserHandle<P, R> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ParserHandle")
+            .field("pratt", &self.setup.pratt.is_some())
+            .field(
+                "externs",
+                &self
+                    .setup
+                    .externs
+                    .iter()
+                    .map(|(rule, _)| rule)
+                    .collect::<Vec<_>>(),
+            )
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::macros::tests::{AbcParser, Rule};
+    use alloc::format;
+
+    struct OuterParser;
+
+    impl Parser<Rule> for OuterParser {
+        fn parse(_: Rule, input: &str) -> Result<Pairs<'_, Rule>, Error<Rule>> {
+            crate::state(input, |s| {
+                s.rule(Rule::a, |s| {
+                    s.match_string("(")
+                        .and_then(|s| s.rule(Rule::b, |s| s.external(Rule::b)))
+                        .and_then(|s| s.match_string(")"))
+                })
+            })
+        }
+    }
+
+    #[test]
+    fn send_sync() {
+        fn assert_send_sync<T: Send + Sync>(_: &T) {}
+
+        let handle = ParserBuilder::new(OuterParser)
+            .with_extern(Rule::b, AbcParser, Rule::c, Some)
+            .build();
+        assert_send_sync(&handle);
+    }
+
+    #[test]
+    fn scoped_externs() {
+        let handle = ParserBuilder::new(OuterParser)
+            .with_extern(Rule::b, AbcParser, Rule::a, |rule| match rule {
+                Rule::b => Some(Rule::c),
+                _ => None,
+            })
+            .build();
+
+        OuterParser::bind(Rule::b, AbcParser, Rule::a, |_| None);
+
+        let pairs = handle.parse(Rule::a, "(abcde)").unwrap();
+        assert_eq!(format!("{}", pairs), "[a(0, 7, [b(1, 6, [c(2, 3)])])]");
+
+        // The binding of the thread is restored.
+        let pairs = OuterParser::parse(Rule::a, "(abcde)").unwrap();
+        assert_eq!(format!("{}", pairs), "[a(0, 7, [b(1, 6)])]");
+    }
+}
@@ -0,0 +1,712 @@
+// pest. The Elegant Parser
+// Copyright (c) 2018 Dragoș Tiselice
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! A `serde` `Deserializer` over `Pairs`, so that simple data formats defined in pest deserialize
+//! straight into types deriving `Deserialize`.
+//!
+//! A [`Mapping`] gives the [`Shape`] of the pairs of some of the rules: maps, sequences or
+//! scalars. The pairs of the other rules are transparent, with their inner pairs taking their
+//! place, so that the rules structuring the grammar, e.g. `value = { object | array | string }`
+//! or `entry = { key ~ ":" ~ value }`, need no shape. The items of a map or of a sequence are the
+//! pairs with a shape found in its inner pairs this way, the ones of a map alternating between
+//! keys and values.
+//!
+//! Scalars are deserialized from the strings they matched, which are parsed with `FromStr` for
+//! numbers, `bool`s and `char`s. Enums are deserialized from scalars for unit variants, and from
+//! maps with a single entry, keyed by the variant, otherwise. Errors point at the pairs they are
+//! about.
+//!
+//! [`Mapping`]: struct.Mapping.html
+//! [`Shape`]: enum.Shape.html
+
+use alloc::borrow::ToOwned;
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::{self, Vec};
+use core::fmt;
+
+use serde::de::{
+    self, DeserializeSeed, EnumAccess, IntoDeserializer, MapAccess, SeqAccess, VariantAccess,
+    Visitor,
+};
+use serde::{forward_to_deserialize_any, Deserialize};
+
+use crate::iterators::{pairs, Pair, Pairs};
+use crate::span::Span;
+use crate::spanned::SpannedError;
+use crate::RuleType;
+
+/// How the pairs of a rule are deserialized.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum Shape {
+    /// A map, e.g. an object, whose items alternate between keys and values
+    Map,
+    /// A sequence, e.g. an array
+    Seq,
+    /// A scalar, deserialized from the string it matched
+    Scalar,
+}
+
+/// The [`Shape`]s of the rules of a grammar, as used by [`from_pairs`].
+///
+/// [`Shape`]: enum.Shape.html
+/// [`from_pairs`]: fn.from_pairs.html
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Mapping<R> {
+    shapes: Vec<(R, Shape)>,
+}
+
+impl<R: RuleType> Mapping<R> {
+    /// Creates a `Mapping` without any rule, whose pairs are all transparent.
+    pub fn new() -> Mapping<R> {
+        Mapping { shapes: Vec::new() }
+    }
+
+    /// Returns the `Mapping` with the pairs of `rule` deserialized as `shape`. Setting it again
+    /// replaces the previous shape.
+    pub fn with_rule(mut self, rule: R, shape: Shape) -> Mapping<R> {
+        self.shapes.retain(|&(other, _)| other != rule);
+        self.shapes.push((rule, shape));
+        self
+    }
+
+    /// Returns the shape of `rule`, if it has any.
+    pub fn shape(&self, rule: R) -> Option<Shape> {
+        self.shapes
+            .iter()
+            .find(|&&(other, _)| other == rule)
+            .map(|&(_, shape)| shape)
+    }
+
+    // Collects the pairs with a shape in `pairs`, looking through the ones without one.
+    fn items<'i>(&self, pairs: Pairs<'i, R>, items: &mut Vec<Pair<'i, R>>) {
+        for pair in pairs {
+            if self.shape(pair.as_rule()).is_some() {
+                items.push(pair);
+            } else {
+                self.items(pair.into_inner(), items);
+            }
+        }
+    }
+}
+
+impl<R: RuleType> Default for Mapping<R> {
+    fn default() -> Mapping<R> {
+        Mapping::new()
+    }
+}
+
+/// An error of a [`Deserializer`], pointing at the span of the pair it is about.
+///
+/// [`Deserializer`]: struct.Deserializer.html
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct Error<'i> {
+    /// The message of the error
+    pub message: String,
+    /// The span of the pair the error is about, which is only missing for errors created by
+    /// `Deserialize` implementations before they are returned by the `Deserializer`
+    pub span: Option<Span<'i>>,
+}
+
+impl<'i> Error<'i> {
+    fn new(message: String, span: Span<'i>) -> Error<'i> {
+        Error {
+            message,
+            span: Some(span),
+        }
+    }
+
+    // Points the error at `span` if it points nowhere yet, so that it points at the innermost pair.
+    fn at(mut self, span: Span<'i>) -> Error<'i> {
+        self.span.get_or_insert(span);
+        self
+    }
+}
+
+impl<'i> fmt::Display for Error<'i> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.span {
+            Some(span) => {
+                let (line, col) = span.start_pos().line_col();
+                write!(f, "{}:{}: {}", line, col, self.message)
+            }
+            None => f.write_str(&self.message),
+        }
+    }
+}
+
+impl<'i> de::StdError for Error<'i> {}
+
+impl<'i> de::Error for Error<'i> {
+    fn custom<T: fmt::Display>(message: T) -> Error<'i> {
+        Error {
+            message: message.to_string(),
+            span: None,
+        }
+    }
+}
+
+/// A `serde` `Deserializer` over a `Pair`, shaped by a [`Mapping`]. See the [module-level
+/// documentation](index.html).
+///
+/// [`Mapping`]: struct.Mapping.html
+pub struct Deserializer<'m, 'i, R> {
+    pair: Pair<'i, R>,
+    mapping: &'m Mapping<R>,
+}
+
+impl<'m, 'i, R: RuleType> Deserializer<'m, 'i, R> {
+    /// Creates a `Deserializer` over `pair`. If the rule of `pair` has no shape, its inner pairs
+    /// must contain exactly one pair with a shape, which it deserializes.
+    pub fn new(pair: Pair<'i, R>, mapping: &'m Mapping<R>) -> Deserializer<'m, 'i, R> {
+        Deserializer { pair, mapping }
+    }
+
+    fn items(&self) -> Vec<Pair<'i, R>> {
+        let mut items = Vec::new();
+        self.mapping
+            .items(self.pair.clone().into_inner(), &mut items);
+        items
+    }
+
+    // Returns the `Deserializer` of the pair with a shape it stands for, along with its shape.
+    fn shaped(self) -> Result<(Deserializer<'m, 'i, R>, Shape), Error<'i>> {
+        match self.mapping.shape(self.pair.as_rule()) {
+            Some(shape) => Ok((self, shape)),
+            None => {
+                let pair = single(self.items(), self.pair.as_span())?;
+                Deserializer::new(pair, self.mapping).shaped()
+            }
+        }
+    }
+
+    fn scalar<T, V>(
+        self,
+        visitor: V,
+        visit: fn(V, T) -> Result<V::Value, Error<'i>>,
+    ) -> Result<V::Value, Error<'i>>
+    where
+        T: core::str::FromStr,
+        T::Err: fmt::Display,
+        V: Visitor<'i>,
+    {
+        let (deserializer, shape) = self.shaped()?;
+        if shape != Shape::Scalar {
+            return de::Deserializer::deserialize_any(deserializer, visitor);
+        }
+
+        let span = deserializer.pair.as_span();
+        let value = span.as_str().parse().map_err(|error| {
+            Error::new(
+                format!(
+                    "invalid {} `{}`: {}",
+                    core::any::type_name::<T>(),
+                    span.as_str(),
+                    error
+                ),
+                span,
+            )
+        })?;
+
+        visit(visitor, value).map_err(|error| error.at(span))
+    }
+}
+
+// Returns the single item of the map, sequence or transparent pair spanning `span`.
+fn single<'i, R: RuleType>(
+    mut items: Vec<Pair<'i, R>>,
+    span: Span<'i>,
+) -> Result<Pair<'i, R>, Error<'i>> {
+    if items.len() == 1 {
+        Ok(items.remove(0))
+    } else {
+        Err(Error::new(
+            format!("expected a single value, found {}", items.len()),
+            span,
+        ))
+    }
+}
+
+macro_rules! deserialize_scalars {
+    ( $( $method:ident => $visit:ident, )* ) => {
+        $(
+            fn $method<V: Visitor<'i>>(self, visitor: V) -> Result<V::Value, Error<'i>> {
+                self.scalar(visitor, V::$visit)
+            }
+        )*
+    };
+}
+
+impl<'m, 'i, R: RuleType> de::Deserializer<'i> for Deserializer<'m, 'i, R> {
+    type Error = Error<'i>;
+
+    fn deserialize_any<V: Visitor<'i>>(self, visitor: V) -> Result<V::Value, Error<'i>> {
+        let (deserializer, shape) = self.shaped()?;
+        let span = deserializer.pair.as_span();
+
+        let result = match shape {
+            Shape::Scalar => visitor.visit_borrowed_str(span.as_str()),
+            Shape::Seq => {
+                let mut items = Items::new(deserializer.items(), deserializer.mapping);
+                visitor
+                    .visit_seq(&mut items)
+                    .and_then(|value| items.end().map(|_| value))
+            }
+            Shape::Map => {
+                let items = deserializer.items();
+                if items.len() % 2 != 0 {
+                    return Err(Error::new(
+                        "expected a value after the last key".to_owned(),
+                        span,
+                    ));
+                }
+                visitor.visit_map(Items::new(items, deserializer.mapping))
+            }
+        };
+
+        result.map_err(|error| error.at(span))
+    }
+
+    deserialize_scalars! {
+        deserialize_bool => visit_bool,
+        deserialize_i8 => visit_i8,
+        deserialize_i16 => visit_i16,
+        deserialize_i32 => visit_i32,
+        deserialize_i64 => visit_i64,
+        deserialize_u8 => visit_u8,
+        deserialize_u16 => visit_u16,
+        deserialize_u32 => visit_u32,
+        deserialize_u64 => visit_u64,
+        deserialize_f32 => visit_f32,
+        deserialize_f64 => visit_f64,
+        deserialize_char => visit_char,
+    }
+
+    fn deserialize_option<V: Visitor<'i>>(self, visitor: V) -> Result<V::Value, Error<'i>> {
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_unit<V: Visitor<'i>>(self, visitor: V) -> Result<V::Value, Error<'i>> {
+        visitor.visit_unit()
+    }
+
+    fn deserialize_unit_struct<V: Visitor<'i>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Error<'i>> {
+        visitor.visit_unit()
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'i>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Error<'i>> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V: Visitor<'i>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error<'i>> {
+        let (deserializer, shape) = self.shaped()?;
+        let span = deserializer.pair.as_span();
+
+        let result = match shape {
+            Shape::Scalar => visitor.visit_enum(span.as_str().into_deserializer()),
+            Shape::Map => {
+                let mut items = deserializer.items();
+                if items.len() != 2 {
+                    return Err(Error::new(
+                        "expected a map with a single entry".to_owned(),
+                        span,
+                    ));
+                }
+                let value = items.pop().unwrap();
+                let variant = items.pop().unwrap();
+
+                visitor.visit_enum(Variant {
+                    variant,
+                    value,
+                    mapping: deserializer.mapping,
+                })
+            }
+            Shape::Seq => de::Deserializer::deserialize_any(deserializer, visitor),
+        };
+
+        result.map_err(|error| error.at(span))
+    }
+
+    fn deserialize_ignored_any<V: Visitor<'i>>(self, visitor: V) -> Result<V::Value, Error<'i>> {
+        visitor.visit_unit()
+    }
+
+    forward_to_deserialize_any! {
+        <V: Visitor<'i>>
+        str string bytes byte_buf seq tuple tuple_struct map struct identifier
+    }
+}
+
+impl<'m, 'i, R: RuleType> fmt::Debug for Deserializer<'m, 'i, R> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Deserializer")
+            .field("pair", &self.pair)
+            .field("mapping", self.mapping)
+            .finish()
+    }
+}
+
+impl<'m, 'i, R: RuleType> VariantAccess<'i> for Deserializer<'m, 'i, R> {
+    type Error = Error<'i>;
+
+    fn unit_variant(self) -> Result<(), Error<'i>> {
+        Ok(())
+    }
+
+    fn newtype_variant_seed<T: DeserializeSeed<'i>>(self, seed: T) -> Result<T::Value, Error<'i>> {
+        seed.deserialize(self)
+    }
+
+    fn tuple_variant<V: Visitor<'i>>(self, _len: usize, visitor: V) -> Result<V::Value, Error<'i>> {
+        de::Deserializer::deserialize_any(self, visitor)
+    }
+
+    fn struct_variant<V: Visitor<'i>>(
+        self,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error<'i>> {
+        de::Deserializer::deserialize_any(self, visitor)
+    }
+}
+
+// The items of a map or of a sequence.
+struct Items<'m, 'i, R> {
+    items: vec::IntoIter<Pair<'i, R>>,
+    mapping: &'m Mapping<R>,
+    len: usize,
+}
+
+impl<'m, 'i, R: RuleType> Items<'m, 'i, R> {
+    fn new(items: Vec<Pair<'i, R>>, mapping: &'m Mapping<R>) -> Items<'m, 'i, R> {
+        Items {
+            len: items.len(),
+            items: items.into_iter(),
+            mapping,
+        }
+    }
+
+    fn next(&mut self) -> Option<Deserializer<'m, 'i, R>> {
+        self.items
+            .next()
+            .map(|pair| Deserializer::new(pair, self.mapping))
+    }
+
+    // Fails if the visitor of a sequence left some of its items.
+    fn end(&self) -> Result<(), Error<'i>> {
+        match self.items.as_slice().first() {
+            Some(pair) => Err(Error::new(
+                format!(
+                    "expected {} items, found {}",
+                    self.len - self.items.len(),
+                    self.len
+                ),
+                pair.as_span(),
+            )),
+            None => Ok(()),
+        }
+    }
+}
+
+impl<'m, 'i, R: RuleType> SeqAccess<'i> for Items<'m, 'i, R> {
+    type Error = Error<'i>;
+
+    fn next_element_seed<T: DeserializeSeed<'i>>(
+        &mut self,
+        seed: T,
+    ) -> Result<Option<T::Value>, Error<'i>> {
+        self.next()
+            .map(|deserializer| seed.deserialize(deserializer))
+            .transpose()
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.items.len())
+    }
+}
+
+impl<'m, 'i, R: RuleType> MapAccess<'i> for Items<'m, 'i, R> {
+    type Error = Error<'i>;
+
+    fn next_key_seed<K: DeserializeSeed<'i>>(
+        &mut self,
+        seed: K,
+    ) -> Result<Option<K::Value>, Error<'i>> {
+        self.next()
+            .map(|deserializer| seed.deserialize(deserializer))
+            .transpose()
+    }
+
+    // Maps have an even number of items, so every key is followed by a value.
+    fn next_value_seed<V: DeserializeSeed<'i>>(&mut self, seed: V) -> Result<V::Value, Error<'i>> {
+        seed.deserialize(self.next().expect("map without value"))
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.items.len() / 2)
+    }
+}
+
+// The variant of an enum deserialized from a map with a single entry.
+struct Variant<'m, 'i, R> {
+    variant: Pair<'i, R>,
+    value: Pair<'i, R>,
+    mapping: &'m Mapping<R>,
+}
+
+impl<'m, 'i, R: RuleType> EnumAccess<'i> for Variant<'m, 'i, R> {
+    type Error = Error<'i>;
+    type Variant = Deserializer<'m, 'i, R>;
+
+    fn variant_seed<V: DeserializeSeed<'i>>(
+        self,
+        seed: V,
+    ) -> Result<(V::Value, Deserializer<'m, 'i, R>), Error<'i>> {
+        let variant = seed.deserialize(Deserializer::new(self.variant, self.mapping))?;
+        Ok((variant, Deserializer::new(self.value, self.mapping)))
+    }
+}
+
+/// Deserializes `T` from the single pair with a shape in `pairs`, looking through the pairs of
+/// the rules without one, e.g. from the `Pairs` returned by [`Parser::parse`].
+///
+/// [`Parser::parse`]: ../trait.Parser.html#tymethod.parse
+///
+/// # Examples
+///
+/// ```
+/// # use pest::de::{self, Mapping, Shape};
+/// # use serde::Deserialize;
+/// # #[allow(non_camel_case_types)]
+/// # #[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+/// # enum Rule { point, number }
+/// // point = { "(" ~ number ~ "," ~ number ~ ")" }, number = { ASCII_DIGIT+ }
+/// # type State<'i> = Box<pest::ParserState<'i, Rule>>;
+/// # fn number(s: State<'_>) -> pest::ParseResult<State<'_>> {
+/// #     s.rule(Rule::number, |s| s.repeat(|s| s.match_range('0'..'9')))
+/// # }
+/// # fn parse(input: &str) -> Result<pest::iterators::Pairs<'_, Rule>, pest::error::Error<Rule>> {
+/// #     pest::state(input, |s| {
+/// #         s.rule(Rule::point, |s| {
+/// #             s.sequence(|s| {
+/// #                 s.match_string("(")
+/// #                     .and_then(number)
+/// #                     .and_then(|s| s.match_string(","))
+/// #                     .and_then(number)
+/// #                     .and_then(|s| s.match_string(")"))
+/// #             })
+/// #         })
+/// #     })
+/// # }
+/// #[derive(Debug, Deserialize, PartialEq)]
+/// struct Point(u8, u8);
+///
+/// let mapping = Mapping::new()
+///     .with_rule(Rule::point, Shape::Seq)
+///     .with_rule(Rule::number, Shape::Scalar);
+///
+/// let point: Point = de::from_pairs(parse("(1,2)").unwrap(), &mapping).unwrap();
+/// assert_eq!(point, Point(1, 2));
+///
+/// let error = de::from_pairs::<_, Point>(parse("(1,300)").unwrap(), &mapping).unwrap_err();
+/// assert_eq!(error.span.as_str(), "300");
+/// ```
+pub fn from_pairs<'i, R, T>(
+    pairs: Pairs<'i, R>,
+    mapping: &Mapping<R>,
+) -> Result<T, SpannedError<'i>>
+where
+    R: RuleType,
+    T: Deserialize<'i>,
+{
+    let start = pairs::next_pos(&pairs);
+    let end = pairs::end_pos(&pairs).unwrap_or(start);
+    // Positions come from the parse of the input.
+    let span = Span::new(pairs::input(&pairs), start, end).unwrap();
+
+    let mut items = Vec::new();
+    mapping.items(pairs, &mut items);
+
+    single(items, span)
+        .and_then(|pair| T::deserialize(Deserializer::new(pair, mapping)))
+        .map_err(|error| SpannedError::new(error.message, error.span.unwrap_or(span)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser_state::{state, ParseResult, ParserState};
+    use alloc::borrow::ToOwned;
+    use alloc::boxed::Box;
+    use alloc::vec;
+
+    #[allow(non_camel_case_types)]
+    #[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+    enum Rule {
+        doc,
+        entry,
+        key,
+        value,
+        list,
+        word,
+    }
+
+    type State<'i> = Box<ParserState<'i, Rule>>;
+
+    fn word(state: State<'_>, rule: Rule) -> ParseResult<State<'_>> {
+        state.rule(rule, |state| {
+            state
+                .match_char_by(|c| c.is_ascii_alphanumeric())
+                .and_then(|state| {
+                    state.repeat(|state| state.match_char_by(|c| c.is_ascii_alphanumeric()))
+                })
+        })
+    }
+
+    fn list(state: State<'_>) -> ParseResult<State<'_>> {
+        state.rule(Rule::list, |state| {
+            state.sequence(|state| {
+                state
+                    .match_string("[")
+                    .and_then(|state| {
+                        state.optional(|state| {
+                            word(state, Rule::word).and_then(|state| {
+                                state.repeat(|state| {
+                                    state.sequence(|state| {
+                                        state
+                                            .match_string(",")
+                                            .and_then(|state| word(state, Rule::word))
+                                    })
+                                })
+                            })
+                        })
+                    })
+                    .and_then(|state| state.match_string("]"))
+            })
+        })
+    }
+
+    // doc = { entry* }, entry = { key ~ "=" ~ value ~ ";" }, value = { list | word },
+    // list = { "[" ~ (word ~ ("," ~ word)*)? ~ "]" }, key and word = { ASCII_ALPHANUMERIC+ }
+    fn parse(input: &str) -> Pairs<'_, Rule> {
+        state(input, |state| {
+            state.rule(Rule::doc, |state| {
+                state.repeat(|state| {
+                    state.rule(Rule::entry, |state| {
+                        state.sequence(|state| {
+                            word(state, Rule::key)
+                                .and_then(|state| state.match_string("="))
+                                .and_then(|state| {
+                                    state.rule(Rule::value, |state| {
+                                        list(state).or_else(|state| word(state, Rule::word))
+                                    })
+                                })
+                                .and_then(|state| state.match_string(";"))
+                        })
+                    })
+                })
+            })
+        })
+        .unwrap()
+    }
+
+    fn mapping() -> Mapping<Rule> {
+        Mapping::new()
+            .with_rule(Rule::doc, Shape::Map)
+            .with_rule(Rule::key, Shape::Scalar)
+            .with_rule(Rule::list, Shape::Seq)
+            .with_rule(Rule::word, Shape::Scalar)
+    }
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    #[serde(rename_all = "lowercase")]
+    enum Kind {
+        Lib,
+        Bin,
+    }
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Package<'i> {
+        name: &'i str,
+        version: u8,
+        kind: Kind,
+        tags: Vec<String>,
+        license: Option<String>,
+    }
+
+    #[test]
+    fn deserialize() {
+        let pairs = parse("name=pest;version=2;kind=lib;tags=[parser,peg];");
+        let package: Package<'_> = from_pairs(pairs, &mapping()).unwrap();
+
+        assert_eq!(
+            package,
+            Package {
+                name: "pest",
+                version: 2,
+                kind: Kind::Lib,
+                tags: vec!["parser".to_owned(), "peg".to_owned()],
+                license: None,
+            }
+        );
+    }
+
+    #[test]
+    fn errors() {
+        let mapping = mapping();
+
+        let error =
+            from_pairs::<_, Package<'_>>(parse("name=pest;version=x;"), &mapping).unwrap_err();
+        assert_eq!(error.span.as_str(), "x");
+        assert_eq!(
+            error.message,
+            "invalid u8 `x`: invalid digit found in string"
+        );
+
+        let error = from_pairs::<_, Package<'_>>(parse("name=pest;version=2;kind=dll;"), &mapping)
+            .unwrap_err();
+        assert_eq!(error.span.as_str(), "dll");
+
+        let error =
+            from_pairs::<_, Package<'_>>(parse("name=pest;version=2;"), &mapping).unwrap_err();
+        assert_eq!(error.span.as_str(), "name=pest;version=2;");
+        assert_eq!(error.message, "missing field `kind`");
+
+        let error = from_pairs::<_, (String,)>(
+            parse("tags=[a,b];"),
+            &mapping.with_rule(Rule::doc, Shape::Seq),
+        )
+        .unwrap_err();
+        assert_eq!(error.span.as_str(), "[a,b]");
+    }
+
+    #[test]
+    fn transparent() {
+        let mapping = Mapping::new().with_rule(Rule::word, Shape::Scalar);
+
+        let word: u8 = from_pairs(parse("a=7;"), &mapping).unwrap();
+        assert_eq!(word, 7);
+
+        let error = from_pairs::<_, u8>(parse("a=7;b=8;"), &mapping).unwrap_err();
+        assert_eq!(error.message, "expected a single value, found 2");
+    }
+}
@@ -0,0 +1,317 @@
+// pest. The Elegant Parser
+// Copyright (c) 2018 Dragoș Tiselice
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Cleaning up lists of errors for display, e.g. the ones recovered from by
+//! [`Parser::parse_recoverable`], which tend to be noisy: a single failure can be reported
+//! several times, or cause a cascade of errors after it.
+//!
+//! [`clean`] runs all of the functions of this module in order. Errors are only ever compared
+//! with the errors of the same path, so that lists gathered from several files can be cleaned up
+//! at once.
+//!
+//! [`Parser::parse_recoverable`]: ../trait.Parser.html#method.parse_recoverable
+//! [`clean`]: fn.clean.html
+
+use alloc::borrow::ToOwned;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use crate::error::{Error, InputLocation};
+use crate::RuleType;
+
+// The byte range of the location of `error`, which is empty for a position.
+fn range<R>(error: &Error<R>) -> (usize, usize) {
+    match error.location {
+        InputLocation::Pos(pos) => (pos, pos),
+        InputLocation::Span(span) => span,
+    }
+}
+
+// Whether `a` and `b` are about the same part of the same input: they start at the same position,
+// or their locations intersect.
+fn overlap<R: RuleType>(a: &Error<R>, b: &Error<R>) -> bool {
+    let (a_start, a_end) = range(a);
+    let (b_start, b_end) = range(b);
+
+    a.path() == b.path() && (a_start == b_start || (a_start < b_end && b_start < a_end))
+}
+
+/// Sorts `errors` by path and position, keeping the errors at the same location in order.
+pub fn sort<R: RuleType>(errors: &mut [Error<R>]) {
+    errors.sort_by(|a, b| (a.path(), range(a)).cmp(&(b.path(), range(b))));
+}
+
+/// Removes the errors overlapping an error before them in `errors`, e.g. the same failure reported
+/// twice, keeping the first of them.
+///
+/// # Examples
+///
+/// ```
+/// # use pest::diagnostics;
+/// # use pest::error::{Error, ErrorVariant};
+/// # use pest::{Position, Span};
+/// let input = "let x = ;";
+/// let error = |message: &str| ErrorVariant::<()>::CustomError {
+///     message: message.to_owned(),
+/// };
+///
+/// let mut errors = vec![
+///     Error::new_from_span(error("expected an expression"), Span::new(input, 8, 9).unwrap()),
+///     Error::new_from_pos(error("expected a number"), Position::new(input, 8).unwrap()),
+///     Error::new_from_pos(error("expected `;`"), Position::new(input, 9).unwrap()),
+/// ];
+/// diagnostics::dedup(&mut errors);
+///
+/// assert_eq!(errors.len(), 2);
+/// assert_eq!(errors[1].variant.message(), "expected `;`");
+/// ```
+pub fn dedup<R: RuleType>(errors: &mut Vec<Error<R>>) {
+    let mut kept: Vec<Error<R>> = Vec::with_capacity(errors.len());
+
+    for error in errors.drain(..) {
+        if !kept.iter().any(|other| overlap(other, &error)) {
+            kept.push(error);
+        }
+    }
+
+    *errors = kept;
+}
+
+/// Keeps at most `max` errors per line of each path in `errors`, the first ones, noting the number
+/// of the errors left out on the last one kept. `max` is at least 1.
+pub fn cap_per_line<R: RuleType>(errors: &mut Vec<Error<R>>, max: usize) {
+    // A line with errors, along with the index of the last error kept on it and the numbers of
+    // the errors kept and left out.
+    struct Line {
+        path: Option<String>,
+        number: usize,
+        last: usize,
+        kept: usize,
+        left_out: usize,
+    }
+
+    let max = max.max(1);
+    let mut lines: Vec<Line> = Vec::new();
+    let mut kept = Vec::with_capacity(errors.len());
+
+    for error in errors.drain(..) {
+        let path = error.path().map(str::to_owned);
+        let number = error.line_col.start().0;
+
+        match lines
+            .iter_mut()
+            .find(|line| line.path == path && line.number == number)
+        {
+            Some(line) if line.kept == max => line.left_out += 1,
+            Some(line) => {
+                line.last = kept.len();
+                line.kept += 1;
+                kept.push(error);
+            }
+            None => {
+                lines.push(Line {
+                    path,
+                    number,
+                    last: kept.len(),
+                    kept: 1,
+                    left_out: 0,
+                });
+                kept.push(error);
+            }
+        }
+    }
+
+    for line in lines.into_iter().filter(|line| line.left_out > 0) {
+        let note = if line.left_out == 1 {
+            "1 more error on this line".to_owned()
+        } else {
+            format!("{} more errors on this line", line.left_out)
+        };
+        let error = kept.remove(line.last);
+        kept.insert(line.last, error.with_note(&note));
+    }
+
+    *errors = kept;
+}
+
+/// Merges the errors of `errors` caused by the same root failure into the first of them: the
+/// errors with the same label, e.g. all the errors pointing at the same unclosed delimiter, as
+/// these are the cascades of the failure it points at. The merged errors are noted on the first
+/// one.
+pub fn merge_cascades<R: RuleType>(errors: &mut Vec<Error<R>>) {
+    let mut kept: Vec<(Error<R>, usize)> = Vec::with_capacity(errors.len());
+
+    for error in errors.drain(..) {
+        let root = kept.iter_mut().find(|(root, _)| {
+            root.path() == error.path()
+                && root.labels().iter().any(|label| {
+                    error.labels().iter().any(|other| {
+                        label.location() == other.location() && label.message() == other.message()
+                    })
+                })
+        });
+
+        match root {
+            Some((_, merged)) => *merged += 1,
+            None => kept.push((error, 0)),
+        }
+    }
+
+    *errors = kept
+        .into_iter()
+        .map(|(error, merged)| match merged {
+            0 => error,
+            1 => error.with_note("1 more error caused by the same failure"),
+            _ => error.with_note(&format!(
+                "{} more errors caused by the same failure",
+                merged
+            )),
+        })
+        .collect();
+}
+
+/// Cleans up `errors` for display: sorts them, removes the ones overlapping others, merges the
+/// cascades of the same failure and keeps at most `max_per_line` of them per line.
+///
+/// # Examples
+///
+/// ```
+/// # use pest::diagnostics;
+/// # use pest::error::{Error, ErrorVariant};
+/// # use pest::Position;
+/// let input = "a b c\nd";
+/// let error = |pos| {
+///     Error::new_from_pos(
+///         ErrorVariant::<()>::CustomError {
+///             message: "unexpected word".to_owned(),
+///         },
+///         Position::new(input, pos).unwrap(),
+///     )
+/// };
+///
+/// let errors = diagnostics::clean(vec![error(6), error(4), error(2), error(0), error(2)], 2);
+///
+/// let positions: Vec<_> = errors.iter().map(|error| error.line_col.start()).collect();
+/// assert_eq!(positions, [(1, 1), (1, 3), (2, 1)]);
+/// assert_eq!(errors[1].notes(), ["1 more error on this line"]);
+/// ```
+pub fn clean<R: RuleType>(mut errors: Vec<Error<R>>, max_per_line: usize) -> Vec<Error<R>> {
+    sort(&mut errors);
+    dedup(&mut errors);
+    merge_cascades(&mut errors);
+    cap_per_line(&mut errors, max_per_line);
+    errors
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ErrorVariant;
+    use crate::position::Position;
+    use crate::span::Span;
+    use alloc::vec;
+
+    const INPUT: &str = "f(a, b\ng(c\nd";
+
+    fn error(pos: usize, message: &str) -> Error<()> {
+        Error::new_from_pos(
+            ErrorVariant::CustomError {
+                message: message.to_owned(),
+            },
+            Position::new(INPUT, pos).unwrap(),
+        )
+    }
+
+    fn messages(errors: &[Error<()>]) -> Vec<&str> {
+        errors
+            .iter()
+            .map(|error| match error.variant {
+                ErrorVariant::CustomError { ref message } => message.as_str(),
+                ErrorVariant::ParsingError { .. } => unreachable!(),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn sort_by_path() {
+        let mut errors = vec![
+            error(4, "b"),
+            error(0, "c").with_path("b.txt"),
+            error(0, "a"),
+            error(4, "d"),
+        ];
+        sort(&mut errors);
+
+        assert_eq!(messages(&errors), ["a", "b", "d", "c"]);
+    }
+
+    #[test]
+    fn dedup_spans() {
+        let span = |start, end, message: &str| {
+            Error::new_from_span(
+                ErrorVariant::CustomError {
+                    message: message.to_owned(),
+                },
+                Span::new(INPUT, start, end).unwrap(),
+            )
+        };
+        let mut errors = vec![
+            span(2, 6, "a"),
+            error(4, "b"),
+            error(6, "c"),
+            span(5, 9, "d"),
+            error(4, "e").with_path("b.txt"),
+        ];
+        dedup(&mut errors);
+
+        assert_eq!(messages(&errors), ["a", "c", "e"]);
+    }
+
+    #[test]
+    fn cap() {
+        let mut errors = vec![
+            error(0, "a"),
+            error(2, "b"),
+            error(7, "c"),
+            error(4, "d"),
+            error(5, "e"),
+            error(9, "f"),
+        ];
+        cap_per_line(&mut errors, 1);
+
+        assert_eq!(messages(&errors), ["a", "c"]);
+        assert_eq!(errors[0].notes(), ["3 more errors on this line"]);
+        assert_eq!(errors[1].notes(), ["1 more error on this line"]);
+    }
+
+    #[test]
+    fn cascades() {
+        let unclosed = |error: Error<()>| {
+            error.with_label(
+                Span::new(INPUT, 1, 2).unwrap(),
+                "unclosed delimiter opened here",
+            )
+        };
+        let mut errors = vec![
+            unclosed(error(6, "a")),
+            error(8, "b"),
+            unclosed(error(10, "c")),
+            unclosed(error(11, "d")),
+        ];
+        merge_cascades(&mut errors);
+
+        assert_eq!(messages(&errors), ["a", "b"]);
+        assert_eq!(
+            errors[0].notes(),
+            ["2 more errors caused by the same failure"]
+        );
+        assert!(errors[1].notes().is_empty());
+    }
+}
@@ -0,0 +1,151 @@
+// pest. The Elegant Parser
+// Copyright (c) 2018 Dragoș Tiselice
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Types for using parsers with different `Rule` types through a common, object-safe interface.
+
+use alloc::format;
+use alloc::vec::Vec;
+
+use crate::error::{Error, ErrorVariant};
+use crate::iterators::{pairs, Pairs};
+use crate::position::Position;
+use crate::RuleType;
+
+/// `Pairs` whose rules are identified by their names.
+pub type DynPairs<'i, 'r> = Pairs<'i, &'r str>;
+
+/// An `Error` whose rules are identified by their names.
+pub type DynError<'r> = Error<&'r str>;
+
+/// An object-safe facade over a [`Parser`], with rules identified by their names instead of a
+/// `Rule` enum. This makes it possible to store parsers of different grammars side by side, e.g.
+/// as `Box<dyn DynParser>`.
+///
+/// `DynParser` is implemented by `pest_derive` for every generated parser.
+///
+/// [`Parser`]: ../trait.Parser.html
+pub trait DynParser {
+    /// Returns the names of all the rules that can be passed to [`parse_dyn`].
+    ///
+    /// [`parse_dyn`]: #tymethod.parse_dyn
+    fn rule_names(&self) -> Vec<&str>;
+
+    /// Parses a `&str` starting from the rule named `rule_name`. Returns an error at the start of
+    /// the `input` if there is no such rule.
+    #[allow(clippy::perf)]
+    fn parse_dyn<'a, 'i>(
+        &'a self,
+        rule_name: &str,
+        input: &'i str,
+    ) -> Result<DynPairs<'i, 'a>, DynError<'a>>;
+}
+
+/// Converts the result of a `Parser` to the result of a `DynParser`, with `name` returning the
+/// name of every rule.
+#[doc(hidden)]
+#[allow(clippy::perf)]
+pub fn into_dyn<'i, 'r, R, F>(
+    result: Result<Pairs<'i, R>, Error<R>>,
+    mut name: F,
+) -> Result<DynPairs<'i, 'r>, DynError<'r>>
+where
+    R: RuleType,
+    F: FnMut(R) -> &'r str,
+{
+    match result {
+        Ok(pairs) => Ok(pairs::map_rules(pairs, name)),
+        Err(error) => Err(error.map_rules(&mut name)),
+    }
+}
+
+/// Creates the error returned by a `DynParser` for an unknown rule name.
+#[doc(hidden)]
+pub fn unknown_rule<'r>(rule_name: &str, input: &str) -> DynError<'r> {
+    Error::new_from_pos(
+        ErrorVariant::CustomError {
+            message: format!("unknown rule `{}`", rule_name),
+        },
+        Position::from_start(input),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::macros::tests::*;
+    use super::super::Parser;
+    use super::*;
+    use alloc::boxed::Box;
+    use alloc::format;
+    use alloc::vec;
+    use alloc::vec::Vec;
+
+    impl DynParser for AbcParser {
+        fn rule_names(&self) -> Vec<&str> {
+            vec!["a", "b", "c"]
+        }
+
+        fn parse_dyn<'a, 'i>(
+            &'a self,
+            rule_name: &str,
+            input: &'i str,
+        ) -> Result<DynPairs<'i, 'a>, DynError<'a>> {
+            let rule = match rule_name {
+                "a" => Rule::a,
+                "b" => Rule::b,
+                "c" => Rule::c,
+                _ => return Err(unknown_rule(rule_name, input)),
+            };
+
+            into_dyn(AbcParser::parse(rule, input), |rule| match rule {
+                Rule::a => "a",
+                Rule::b => "b",
+                Rule::c => "c",
+            })
+        }
+    }
+
+    #[test]
+    fn parse_dyn() {
+        let parser: Box<dyn DynParser> = Box::new(AbcParser);
+        let pairs = parser.parse_dyn("a", "abcde").unwrap();
+
+        assert_eq!(
+            pairs
+                .flatten()
+                .map(|pair| pair.as_rule())
+                .collect::<Vec<_>>(),
+            ["a", "b", "c"]
+        );
+    }
+
+    #[test]
+    fn parse_dyn_unknown_rule() {
+        let parser: Box<dyn DynParser> = Box::new(AbcParser);
+        let error = parser.parse_dyn("d", "abcde").unwrap_err();
+
+        assert_eq!(
+            format!("{}", error),
+            " --> 1:1\n  |\n1 | abcde\n  | ^---\n  |\n  = unknown rule `d`"
+        );
+    }
+
+    #[test]
+    fn parse_dyn_error() {
+        let parser: Box<dyn DynParser> = Box::new(AbcParser);
+        let error = parser.parse_dyn("a", "abcdx").unwrap_err();
+
+        assert_eq!(
+            error.variant,
+            ErrorVariant::ParsingError {
+                positives: vec!["c"],
+                negatives: vec![],
+            }
+        );
+    }
+}
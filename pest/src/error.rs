@@ -11,20 +11,29 @@
 
 use alloc::borrow::Cow;
 use alloc::borrow::ToOwned;
+use alloc::boxed::Box;
 use alloc::format;
 use alloc::string::String;
 use alloc::string::ToString;
 use alloc::vec::Vec;
 use core::cmp;
 use core::fmt;
+use core::hash::{Hash, Hasher};
 use core::mem;
 
+use crate::explain::Explanation;
+use crate::line_index::{self, ColumnMode};
 use crate::position::Position;
 use crate::span::Span;
 use crate::RuleType;
 
 /// Parse-related error type.
-#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+///
+/// Errors are compared and hashed by what they report and how it is displayed. What the parse
+/// recorded besides, i.e. the explanation, the attempted literals, the rule stack, the failure
+/// candidates and whether the input was incomplete, is left out, so that the error of a parse
+/// equals the one built from its variant and position.
+#[derive(Clone, Debug, Eq)]
 #[cfg_attr(feature = "std", derive(thiserror::Error))]
 pub struct Error<R> {
     /// Variant of the error
@@ -36,6 +45,121 @@ pub struct Error<R> {
     path: Option<String>,
     line: String,
     continued_line: Option<String>,
+    column_mode: ColumnMode,
+    snippet_width: Option<usize>,
+    labels: Vec<ErrorLabel>,
+    explanation: Option<Box<Explanation<R>>>,
+    literals: Vec<(String, bool)>,
+    annotations: Annotations,
+    incomplete: bool,
+    rule_stack: Vec<R>,
+    failure_candidates: Vec<(ErrorBias, usize)>,
+    limit_exceeded: Option<(LimitKind, usize)>,
+    deadline_exceeded: bool,
+}
+
+impl<R: PartialEq> PartialEq for Error<R> {
+    fn eq(&self, other: &Error<R>) -> bool {
+        self.variant == other.variant
+            && self.location == other.location
+            && self.line_col == other.line_col
+            && self.path == other.path
+            && self.line == other.line
+            && self.continued_line == other.continued_line
+            && self.column_mode == other.column_mode
+            && self.snippet_width == other.snippet_width
+            && self.labels == other.labels
+            && self.annotations == other.annotations
+    }
+}
+
+impl<R: Hash> Hash for Error<R> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.variant.hash(state);
+        self.location.hash(state);
+        self.line_col.hash(state);
+        self.path.hash(state);
+        self.line.hash(state);
+        self.continued_line.hash(state);
+        self.column_mode.hash(state);
+        self.snippet_width.hash(state);
+        self.labels.hash(state);
+        self.annotations.hash(state);
+    }
+}
+
+// The messages attached to an `Error` besides its variant: the label of its primary span, its
+// notes, its help and the word found at its position along with the literals suggested for it.
+#[derive(Clone, Debug, Default, Eq, Hash, PartialEq)]
+struct Annotations {
+    label: Option<String>,
+    notes: Vec<String>,
+    help: Option<String>,
+    suggestions: Option<(String, Vec<String>)>,
+}
+
+/// Something expected at the position of a parsing error, as returned by [`Error::expected`].
+///
+/// [`Error::expected`]: struct.Error.html#method.expected
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub enum Expected<R> {
+    /// A literal, e.g. `"("`
+    Literal(String),
+    /// A case-insensitive literal, e.g. `^"select"`
+    Insensitive(String),
+    /// A rule other than `EOI`
+    Rule(R),
+    /// The end of the input, i.e. the `EOI` rule
+    Eoi,
+}
+
+impl<R: RuleType> fmt::Display for Expected<R> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Expected::Literal(literal) => write!(f, "`{}`", literal.escape_debug()),
+            Expected::Insensitive(literal) => {
+                write!(f, "`{}` (case-insensitive)", literal.escape_debug())
+            }
+            Expected::Rule(rule) => write!(f, "{:?}", rule),
+            Expected::Eoi => write!(f, "end of input"),
+        }
+    }
+}
+
+/// A secondary label of an [`Error`], pointing at a related span of the input, e.g. where an
+/// unclosed delimiter was opened.
+///
+/// [`Error`]: struct.Error.html
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct ErrorLabel {
+    location: (usize, usize),
+    line_col: (usize, usize),
+    line: String,
+    // The byte range of the label within `line`.
+    range: (usize, usize),
+    message: String,
+}
+
+impl ErrorLabel {
+    /// Returns the byte span of the input that the label points at.
+    pub fn location(&self) -> (usize, usize) {
+        self.location
+    }
+
+    /// Returns the 1-based `(line, col)` of the start of the label, with its column in `char`s.
+    pub fn line_col(&self) -> (usize, usize) {
+        self.line_col
+    }
+
+    /// Returns the line that the label starts on.
+    pub fn line(&self) -> &str {
+        &self.line
+    }
+
+    /// Returns the message of the label.
+    pub fn message(&self) -> &str {
+        &self.message
+    }
 }
 
 /// Different kinds of parsing errors.
@@ -56,6 +180,58 @@ pub enum ErrorVariant<R> {
     },
 }
 
+/// The limits of a parse which stop it once they are exceeded, as reported by
+/// [`Error::limit_exceeded`].
+///
+/// [`Error::limit_exceeded`]: struct.Error.html#method.limit_exceeded
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum LimitKind {
+    /// The number of calls of the parse, as set with [`set_call_limit`] or
+    /// [`ParseOptions::with_call_limit`].
+    ///
+    /// [`set_call_limit`]: ../fn.set_call_limit.html
+    /// [`ParseOptions::with_call_limit`]: ../struct.ParseOptions.html#method.with_call_limit
+    Calls,
+    /// The number of rules a rule is matched within, as set with
+    /// [`ParseOptions::with_max_depth`].
+    ///
+    /// [`ParseOptions::with_max_depth`]: ../struct.ParseOptions.html#method.with_max_depth
+    Depth,
+}
+
+impl fmt::Display for LimitKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LimitKind::Calls => write!(f, "call"),
+            LimitKind::Depth => write!(f, "depth"),
+        }
+    }
+}
+
+/// Which of the positions where a parse could have failed its `Error` reports, as set with
+/// [`ParseOptions::with_error_bias`]. Every position is returned by [`Error::failure_candidates`].
+///
+/// [`ParseOptions::with_error_bias`]: ../struct.ParseOptions.html#method.with_error_bias
+/// [`Error::failure_candidates`]: struct.Error.html#method.failure_candidates
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum ErrorBias {
+    /// The furthest position where a rule reported in errors failed, i.e. where the positives and
+    /// negatives of the error were attempted. This is the default.
+    Attempt,
+    /// The furthest position where the parse attempted anything, including the rules left out of
+    /// errors, e.g. within atomic rules or negative lookaheads, and the literals.
+    Farthest,
+    /// The end of the longest match of a rule, i.e. the point after which no alternative matched,
+    /// which is often where the input went wrong when errors point past it.
+    LongestMatch,
+}
+
+impl Default for ErrorBias {
+    fn default() -> ErrorBias {
+        ErrorBias::Attempt
+    }
+}
+
 /// Where an `Error` has occurred.
 #[derive(Clone, Debug, Eq, Hash, PartialEq)]
 pub enum InputLocation {
@@ -74,6 +250,47 @@ pub enum LineColLocation {
     Span((usize, usize), (usize, usize)),
 }
 
+impl LineColLocation {
+    /// Returns the line/column where the `Error` starts, regardless of whether it was created
+    /// from a `Position` or a `Span`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use pest::error::LineColLocation;
+    /// let line_col = LineColLocation::Span((1, 2), (3, 4));
+    ///
+    /// assert_eq!(line_col.start(), (1, 2));
+    /// assert_eq!(LineColLocation::Pos((1, 2)).start(), (1, 2));
+    /// ```
+    pub fn start(&self) -> (usize, usize) {
+        match *self {
+            LineColLocation::Pos(line_col) => line_col,
+            LineColLocation::Span(start, _) => start,
+        }
+    }
+
+    /// Returns the line/column where the `Error` ends, which is where it starts if it was
+    /// created from a `Position`. The end of a `Span` ending right after a newline points at the
+    /// newline, i.e. past the end of the line it ends.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use pest::error::LineColLocation;
+    /// let line_col = LineColLocation::Span((1, 2), (3, 4));
+    ///
+    /// assert_eq!(line_col.end(), (3, 4));
+    /// assert_eq!(LineColLocation::Pos((1, 2)).end(), (1, 2));
+    /// ```
+    pub fn end(&self) -> (usize, usize) {
+        match *self {
+            LineColLocation::Pos(line_col) => line_col,
+            LineColLocation::Span(_, end) => end,
+        }
+    }
+}
+
 impl<R: RuleType> Error<R> {
     /// Creates `Error` from `ErrorVariant` and `Position`.
     ///
@@ -116,6 +333,17 @@ impl<R: RuleType> Error<R> {
             line,
             continued_line: None,
             line_col: LineColLocation::Pos(pos.line_col()),
+            column_mode: ColumnMode::Chars,
+            snippet_width: None,
+            labels: Vec::new(),
+            explanation: None,
+            literals: Vec::new(),
+            annotations: Annotations::default(),
+            incomplete: false,
+            rule_stack: Vec::new(),
+            failure_candidates: Vec::new(),
+            limit_exceeded: None,
+            deadline_exceeded: false,
         }
     }
 
@@ -182,6 +410,17 @@ impl<R: RuleType> Error<R> {
             line: start_line,
             continued_line,
             line_col: LineColLocation::Span(span.start_pos().line_col(), end_line_col),
+            column_mode: ColumnMode::Chars,
+            snippet_width: None,
+            labels: Vec::new(),
+            explanation: None,
+            literals: Vec::new(),
+            annotations: Annotations::default(),
+            incomplete: false,
+            rule_stack: Vec::new(),
+            failure_candidates: Vec::new(),
+            limit_exceeded: None,
+            deadline_exceeded: false,
         }
     }
 
@@ -244,24 +483,17 @@ impl<R: RuleType> Error<R> {
         self.path.as_deref()
     }
 
-    /// Returns the line that the error is on.
-    pub fn line(&self) -> &str {
-        self.line.as_str()
-    }
-
-    /// Renames all `Rule`s if this is a [`ParsingError`]. It does nothing when called on a
-    /// [`CustomError`].
-    ///
-    /// Useful in order to rename verbose rules or have detailed per-`Rule` formatting.
+    /// Returns `Error` with its columns counted in `column_mode`, both in `line_col` and when
+    /// formatted with `Display`. Counting in [`ColumnMode::DisplayWidth`] keeps the caret aligned
+    /// in terminals for inputs containing combining characters, emoji, or East Asian text.
     ///
-    /// [`ParsingError`]: enum.ErrorVariant.html#variant.ParsingError
-    /// [`CustomError`]: enum.ErrorVariant.html#variant.CustomError
+    /// [`ColumnMode::DisplayWidth`]: ../enum.ColumnMode.html#variant.DisplayWidth
     ///
     /// # Examples
     ///
     /// ```
-    /// # use pest::error::{Error, ErrorVariant};
-    /// # use pest::Position;
+    /// # use pest::error::{Error, ErrorVariant, LineColLocation};
+    /// # use pest::{ColumnMode, Position};
     /// # #[allow(non_camel_case_types)]
     /// # #[allow(dead_code)]
     /// # #[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
@@ -269,215 +501,1556 @@ impl<R: RuleType> Error<R> {
     /// #     open_paren,
     /// #     closed_paren
     /// # }
-    /// # let input = "";
-    /// # let pos = Position::from_start(input);
-    /// Error::new_from_pos(
-    ///     ErrorVariant::ParsingError {
-    ///         positives: vec![Rule::open_paren],
-    ///         negatives: vec![Rule::closed_paren]
+    /// let input = "漢字 ) ";
+    /// let pos = Position::new(input, 7).unwrap();
+    /// let error = Error::new_from_pos(
+    ///     ErrorVariant::<Rule>::CustomError {
+    ///         message: "unexpected paren".to_owned(),
     ///     },
-    ///     pos
-    /// ).renamed_rules(|rule| {
-    ///     match *rule {
-    ///         Rule::open_paren => "(".to_owned(),
-    ///         Rule::closed_paren => "closed paren".to_owned()
-    ///     }
-    /// });
+    ///     pos,
+    /// );
+    /// assert_eq!(error.line_col, LineColLocation::Pos((1, 4)));
+    ///
+    /// let error = error.with_column_mode(ColumnMode::DisplayWidth);
+    /// assert_eq!(error.line_col, LineColLocation::Pos((1, 6)));
     /// ```
-    pub fn renamed_rules<F>(mut self, f: F) -> Error<R>
-    where
-        F: FnMut(&R) -> String,
-    {
-        let variant = match self.variant {
-            ErrorVariant::ParsingError {
-                positives,
-                negatives,
-            } => {
-                let message = Error::parsing_error_message(&positives, &negatives, f);
-                ErrorVariant::CustomError { message }
+    pub fn with_column_mode(mut self, column_mode: ColumnMode) -> Error<R> {
+        let from = self.column_mode;
+        let convert = |line: &str, (row, col): (usize, usize)| {
+            let (len, past_end) = line_index::prefix_len(line, col - 1, from);
+            (
+                row,
+                line_index::columns(&line[..len], column_mode) + past_end + 1,
+            )
+        };
+
+        self.line_col = match self.line_col {
+            LineColLocation::Pos(line_col) => LineColLocation::Pos(convert(&self.line, line_col)),
+            LineColLocation::Span(start, end) => {
+                let end_line = self.continued_line.as_ref().unwrap_or(&self.line);
+                LineColLocation::Span(convert(&self.line, start), convert(end_line, end))
             }
-            variant => variant,
         };
+        self.column_mode = column_mode;
 
-        self.variant = variant;
+        self
+    }
+
+    /// Returns the unit in which the columns of the `Error` are counted.
+    pub fn column_mode(&self) -> ColumnMode {
+        self.column_mode
+    }
+
+    /// Returns `Error` with a secondary label pointing at `span`, which is shown along with
+    /// `message` after the error's own snippet when formatted with `Display`. Labels spanning
+    /// multiple lines are only underlined on their first line.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use pest::error::{Error, ErrorVariant};
+    /// # use pest::{Position, Span};
+    /// # #[allow(non_camel_case_types)]
+    /// # #[allow(dead_code)]
+    /// # #[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+    /// # enum Rule {
+    /// #     open_paren,
+    /// #     closed_paren
+    /// # }
+    /// let input = "(a\n b";
+    /// let error = Error::new_from_pos(
+    ///     ErrorVariant::ParsingError {
+    ///         positives: vec![Rule::closed_paren],
+    ///         negatives: vec![],
+    ///     },
+    ///     Position::new(input, 5).unwrap(),
+    /// )
+    /// .with_label(Span::new(input, 0, 1).unwrap(), "unclosed delimiter opened here");
+    ///
+    /// assert_eq!(
+    ///     format!("{}", error),
+    ///     [
+    ///         " --> 2:3",
+    ///         "  |",
+    ///         "2 |  b",
+    ///         "  |   ^---",
+    ///         "  |",
+    ///         " ::: 1:1",
+    ///         "  |",
+    ///         "1 | (a",
+    ///         "  | ^ unclosed delimiter opened here",
+    ///         "  |",
+    ///         "  = expected closed_paren",
+    ///     ]
+    ///     .join("\n")
+    /// );
+    /// ```
+    pub fn with_label(mut self, span: Span<'_>, message: &str) -> Error<R> {
+        let start = span.start_pos();
+        let line_start = span.get_input()[..start.pos()]
+            .rfind('\n')
+            .map_or(0, |i| i + 1);
+        let line = start.line_of().replace(&['\r', '\n'][..], "");
+        let range_start = start.pos() - line_start;
+        let range_end = cmp::min(span.end() - line_start, line.len());
+
+        self.labels.push(ErrorLabel {
+            location: (span.start(), span.end()),
+            line_col: start.line_col(),
+            line,
+            range: (range_start, cmp::max(range_start, range_end)),
+            message: message.to_owned(),
+        });
 
         self
     }
 
-    fn start(&self) -> (usize, usize) {
-        match self.line_col {
-            LineColLocation::Pos(line_col) => line_col,
-            LineColLocation::Span(start_line_col, _) => start_line_col,
-        }
+    /// Returns the secondary labels of the `Error`, in the order they were added.
+    pub fn labels(&self) -> &[ErrorLabel] {
+        &self.labels
     }
 
-    fn spacing(&self) -> String {
-        let line = match self.line_col {
-            LineColLocation::Pos((line, _)) => line,
-            LineColLocation::Span((start_line, _), (end_line, _)) => cmp::max(start_line, end_line),
-        };
+    /// Returns `Error` which, when formatted with `Display`, shows `message` next to the
+    /// underline of its own location, the primary one, like the [secondary labels]. The message
+    /// of the variant is still shown below.
+    ///
+    /// [secondary labels]: #method.with_label
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use pest::error::{Error, ErrorVariant};
+    /// # use pest::{Position, Span};
+    /// # #[allow(non_camel_case_types)]
+    /// # #[allow(dead_code)]
+    /// # #[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+    /// # enum Rule {
+    /// #     closed_paren
+    /// # }
+    /// let input = "(a\n b";
+    /// let error: Error<Rule> = Error::new_from_span(
+    ///     ErrorVariant::CustomError {
+    ///         message: "unclosed delimiter".to_owned(),
+    ///     },
+    ///     Span::new(input, 4, 5).unwrap(),
+    /// )
+    /// .with_primary_label("expected `)` before here")
+    /// .with_label(Span::new(input, 0, 1).unwrap(), "opened here")
+    /// .with_note("delimiters must be closed before the end of the input")
+    /// .with_help("add a `)`");
+    ///
+    /// assert_eq!(
+    ///     format!("{}", error),
+    ///     [
+    ///         " --> 2:2",
+    ///         "  |",
+    ///         "2 |  b",
+    ///         "  |  ^ expected `)` before here",
+    ///         "  |",
+    ///         " ::: 1:1",
+    ///         "  |",
+    ///         "1 | (a",
+    ///         "  | ^ opened here",
+    ///         "  |",
+    ///         "  = unclosed delimiter",
+    ///         "  = note: delimiters must be closed before the end of the input",
+    ///         "  = help: add a `)`",
+    ///     ]
+    ///     .join("\n")
+    /// );
+    /// ```
+    pub fn with_primary_label(mut self, message: &str) -> Error<R> {
+        self.annotations.label = Some(message.to_owned());
+        self
+    }
 
-        let line_str_len = format!("{}", line).len();
+    /// Returns the label of the primary location set with [`with_primary_label`].
+    ///
+    /// [`with_primary_label`]: #method.with_primary_label
+    pub fn primary_label(&self) -> Option<&str> {
+        self.annotations.label.as_deref()
+    }
 
-        let mut spacing = String::new();
-        for _ in 0..line_str_len {
-            spacing.push(' ');
-        }
+    /// Returns `Error` with a note, which is shown after its message when formatted with `Display`.
+    /// Notes are shown in the order they were added.
+    pub fn with_note(mut self, note: &str) -> Error<R> {
+        self.annotations.notes.push(note.to_owned());
+        self
+    }
 
-        spacing
+    /// Returns the notes of the `Error`, in the order they were added.
+    pub fn notes(&self) -> &[String] {
+        &self.annotations.notes
     }
 
-    fn underline(&self) -> String {
-        let mut underline = String::new();
+    /// Returns `Error` with a help message, which is shown last when formatted with `Display`,
+    /// e.g. to suggest a fix. Setting it again replaces the previous one.
+    pub fn with_help(mut self, help: &str) -> Error<R> {
+        self.annotations.help = Some(help.to_owned());
+        self
+    }
 
-        let mut start = self.start().1;
-        let end = match self.line_col {
-            LineColLocation::Span(_, (_, mut end)) => {
-                let inverted_cols = start > end;
-                if inverted_cols {
-                    mem::swap(&mut start, &mut end);
-                    start -= 1;
-                    end += 1;
-                }
+    /// Returns the help message of the `Error`, if any.
+    pub fn help(&self) -> Option<&str> {
+        self.annotations.help.as_deref()
+    }
 
-                Some(end)
-            }
-            _ => None,
-        };
-        let offset = start - 1;
-        let line_chars = self.line.chars();
+    /// Returns the explanation of the `Error` if it was returned by a parse in explain mode. See
+    /// [`ParseOptions::with_explain`].
+    ///
+    /// [`ParseOptions::with_explain`]: ../struct.ParseOptions.html#method.with_explain
+    pub fn explanation(&self) -> Option<&Explanation<R>> {
+        self.explanation.as_deref()
+    }
 
-        for c in line_chars.take(offset) {
-            match c {
-                '\t' => underline.push('\t'),
-                _ => underline.push(' '),
-            }
-        }
+    /// Returns whether the input of the parse which returned the `Error` ended where it could
+    /// still have matched, e.g. within a string of the grammar, so that more input might make it
+    /// parse. See [`Parser::parse_resumable`].
+    ///
+    /// [`Parser::parse_resumable`]: ../trait.Parser.html#method.parse_resumable
+    pub fn is_incomplete(&self) -> bool {
+        self.incomplete
+    }
 
-        if let Some(end) = end {
-            underline.push('^');
-            if end - start > 1 {
-                for _ in 2..(end - start) {
-                    underline.push('-');
-                }
-                underline.push('^');
-            }
-        } else {
-            underline.push_str("^---")
-        }
+    /// Returns the rules the parse was in when its furthest rule failed, from the outermost one
+    /// down to the failed rule. It is only recorded by parses tracking attempts with
+    /// [`AttemptTracking::Farthest`], and is empty otherwise.
+    ///
+    /// [`AttemptTracking::Farthest`]: ../enum.AttemptTracking.html#variant.Farthest
+    pub fn rule_stack(&self) -> &[R] {
+        &self.rule_stack
+    }
 
-        underline
+    /// Returns the limit which stopped the parse that returned the `Error`, along with its value,
+    /// if it was stopped by its call limit or its depth limit, as set with [`set_call_limit`],
+    /// [`ParseOptions::with_call_limit`] or [`ParseOptions::with_max_depth`].
+    ///
+    /// [`set_call_limit`]: ../fn.set_call_limit.html
+    /// [`ParseOptions::with_call_limit`]: ../struct.ParseOptions.html#method.with_call_limit
+    /// [`ParseOptions::with_max_depth`]: ../struct.ParseOptions.html#method.with_max_depth
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use pest::error::LimitKind;
+    /// # use pest::ParseOptions;
+    /// let options = ParseOptions::new().with_max_depth(1);
+    /// let error = pest::state::<&str, _>("a", |s| {
+    ///     s.with_options(&options)
+    ///         .rule("a", |s| s.rule("b", |s| s.rule("c", |s| s.match_string("a"))))
+    /// })
+    /// .unwrap_err();
+    ///
+    /// assert_eq!(error.limit_exceeded(), Some((LimitKind::Depth, 1)));
+    /// assert_eq!(error.variant.message(), "depth limit reached");
+    /// ```
+    pub fn limit_exceeded(&self) -> Option<(LimitKind, usize)> {
+        self.limit_exceeded
     }
 
-    fn message(&self) -> String {
-        self.variant.message().to_string()
+    /// Returns whether the parse which returned the `Error` was stopped by its deadline, as set with
+    /// [`ParseOptions::with_timeout`].
+    ///
+    /// [`ParseOptions::with_timeout`]: ../struct.ParseOptions.html#method.with_timeout
+    pub fn deadline_exceeded(&self) -> bool {
+        self.deadline_exceeded
     }
 
-    fn parsing_error_message<F>(positives: &[R], negatives: &[R], mut f: F) -> String
-    where
-        F: FnMut(&R) -> String,
-    {
-        match (negatives.is_empty(), positives.is_empty()) {
-            (false, false) => format!(
-                "unexpected {}; expected {}",
-                Error::enumerate(negatives, &mut f),
-                Error::enumerate(positives, &mut f)
-            ),
-            (false, true) => format!("unexpected {}", Error::enumerate(negatives, &mut f)),
-            (true, false) => format!("expected {}", Error::enumerate(positives, &mut f)),
-            (true, true) => "unknown parsing error".to_owned(),
-        }
+    /// Returns the positions where the parse which returned the `Error` could be said to have
+    /// failed, along with the [`ErrorBias`] choosing each of them, in the order of the variants of
+    /// `ErrorBias`. The location of the `Error` is the one of the bias of the parse. They are empty
+    /// for the errors not returned by a failed parse, e.g. the ones made with `new_from_pos`, or
+    /// when the call limit is reached.
+    ///
+    /// [`ErrorBias`]: enum.ErrorBias.html
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use pest;
+    /// # use pest::error::ErrorBias;
+    /// let input = "ab c";
+    /// let error = pest::state::<&str, _>(input, |s| {
+    ///     s.sequence(|s| {
+    ///         s.rule("word", |s| s.match_string("ab"))
+    ///             .and_then(|s| s.match_string(" "))
+    ///             .and_then(|s| s.rule("word", |s| s.match_string("ab")))
+    ///     })
+    /// })
+    /// .unwrap_err();
+    ///
+    /// assert_eq!(
+    ///     error.failure_candidates(),
+    ///     [
+    ///         (ErrorBias::Attempt, 3),
+    ///         (ErrorBias::Farthest, 3),
+    ///         (ErrorBias::LongestMatch, 2)
+    ///     ]
+    /// );
+    /// ```
+    pub fn failure_candidates(&self) -> &[(ErrorBias, usize)] {
+        &self.failure_candidates
     }
 
-    fn enumerate<F>(rules: &[R], f: &mut F) -> String
-    where
-        F: FnMut(&R) -> String,
-    {
-        match rules.len() {
-            1 => f(&rules[0]),
-            2 => format!("{} or {}", f(&rules[0]), f(&rules[1])),
-            l => {
-                let non_separated = f(&rules[l - 1]);
-                let separated = rules
-                    .iter()
-                    .take(l - 1)
-                    .map(f)
-                    .collect::<Vec<_>>()
-                    .join(", ");
-                format!("{}, or {}", separated, non_separated)
+    /// Returns what was expected at the position of a [`ParsingError`]: the literals which failed
+    /// to match there, sorted, followed by its `positives`, with the `EOI` rule as
+    /// [`Expected::Eoi`]. It returns nothing for a [`CustomError`].
+    ///
+    /// Literals are only recorded outside of atomic rules and negative predicates, as far as the
+    /// attempts are tracked.
+    ///
+    /// [`ParsingError`]: enum.ErrorVariant.html#variant.ParsingError
+    /// [`CustomError`]: enum.ErrorVariant.html#variant.CustomError
+    /// [`Expected::Eoi`]: enum.Expected.html#variant.Eoi
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use pest::error::Expected;
+    /// # #[allow(non_camel_case_types)]
+    /// # #[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+    /// # enum Rule { number }
+    /// let error = pest::state("x", |s| {
+    ///     s.match_string("(").or_else(|s| s.rule(Rule::number, |s| s.match_range('0'..'9')))
+    /// })
+    /// .unwrap_err();
+    ///
+    /// assert_eq!(
+    ///     error.expected(),
+    ///     vec![Expected::Literal("(".to_owned()), Expected::Rule(Rule::number)]
+    /// );
+    /// ```
+    pub fn expected(&self) -> Vec<Expected<R>> {
+        let positives = match self.variant {
+            ErrorVariant::ParsingError { ref positives, .. } => positives,
+            ErrorVariant::CustomError { .. } => return Vec::new(),
+        };
+
+        let mut literals = self.literals.clone();
+        literals.sort();
+        literals.dedup();
+
+        let mut expected: Vec<_> = literals
+            .into_iter()
+            .map(|(literal, insensitive)| {
+                if insensitive {
+                    Expected::Insensitive(literal)
+                } else {
+                    Expected::Literal(literal)
+                }
+            })
+            .collect();
+        let mut eoi = false;
+
+        for &rule in positives {
+            if format!("{:?}", rule) == "EOI" {
+                eoi = true;
+            } else {
+                expected.push(Expected::Rule(rule));
             }
         }
+        if eoi {
+            expected.push(Expected::Eoi);
+        }
+
+        expected
     }
 
-    pub(crate) fn format(&self) -> String {
+    /// Returns `Error` with "did you mean" suggestions: the expected literals closest to the word
+    /// at the error's position in `input`, the input the `Error` was created from. They are
+    /// appended to its message when formatted with `Display`, e.g. ``expected `return`, found
+    /// `retun`; did you mean `return`?``. The `Error` is returned as-is if it is not a
+    /// [`ParsingError`], if there is no word at its position, or if no literal is close enough.
+    ///
+    /// The candidates are the literals which failed to match at the position, as returned by
+    /// [`expected`], and the literals of its `positives`, given by `literal`. The `Rule` enums
+    /// generated by `pest_derive` have a `literal` method returning the string of rules which
+    /// match a single string, so `Rule::literal` can be passed as-is. A word is a run of
+    /// alphanumeric characters and underscores, and a literal is close enough to it if they are
+    /// at most a third of the literal's length apart in edit distance, rounded up.
+    ///
+    /// [`ParsingError`]: enum.ErrorVariant.html#variant.ParsingError
+    /// [`expected`]: #method.expected
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use pest::error::{Error, ErrorVariant};
+    /// # use pest::Position;
+    /// # #[allow(non_camel_case_types)]
+    /// # #[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+    /// # enum Rule { return_kw, ident }
+    /// let input = "retun x";
+    /// let error = Error::new_from_pos(
+    ///     ErrorVariant::ParsingError {
+    ///         positives: vec![Rule::return_kw, Rule::ident],
+    ///         negatives: vec![],
+    ///     },
+    ///     Position::new(input, 0).unwrap(),
+    /// )
+    /// .with_suggestions(input, |rule| match rule {
+    ///     Rule::return_kw => Some("return"),
+    ///     Rule::ident => None,
+    /// });
+    ///
+    /// assert_eq!(error.suggestions(), ["return"]);
+    /// assert!(error
+    ///     .to_string()
+    ///     .ends_with("found `retun`; did you mean `return`?"));
+    /// ```
+    pub fn with_suggestions<F, S>(mut self, input: &str, mut literal: F) -> Error<R>
+    where
+        F: FnMut(R) -> Option<S>,
+        S: AsRef<str>,
+    {
+        let positives = match self.variant {
+            ErrorVariant::ParsingError { ref positives, .. } => positives,
+            ErrorVariant::CustomError { .. } => return self,
+        };
+
+        let start = match self.location {
+            InputLocation::Pos(pos) => pos,
+            InputLocation::Span((start, _)) => start,
+        };
+        let rest = &input[start..];
+        let end = rest
+            .find(|c: char| !c.is_alphanumeric() && c != '_')
+            .unwrap_or(rest.len());
+        let found = &rest[..end];
+
+        if found.is_empty() {
+            return self;
+        }
+
+        let mut candidates = self.literals.clone();
+        candidates.extend(
+            positives
+                .iter()
+                .filter_map(|&rule| literal(rule))
+                .map(|literal| (literal.as_ref().to_owned(), false)),
+        );
+
+        let mut suggestions: Vec<_> = candidates
+            .into_iter()
+            .filter_map(|(literal, insensitive)| {
+                let distance = if insensitive {
+                    edit_distance(&literal.to_lowercase(), &found.to_lowercase())
+                } else {
+                    edit_distance(&literal, found)
+                };
+                let max = (literal.chars().count() + 2) / 3;
+
+                if distance > 0 && distance <= max {
+                    Some((distance, literal))
+                } else {
+                    None
+                }
+            })
+            .collect();
+        suggestions.sort();
+        suggestions.dedup();
+
+        if !suggestions.is_empty() {
+            let suggestions = suggestions
+                .into_iter()
+                .map(|(_, literal)| literal)
+                .collect();
+            self.annotations.suggestions = Some((found.to_owned(), suggestions));
+        }
+
+        self
+    }
+
+    /// Returns the literals suggested for the word at the position of the `Error` by
+    /// [`with_suggestions`], closest first.
+    ///
+    /// [`with_suggestions`]: #method.with_suggestions
+    pub fn suggestions(&self) -> &[String] {
+        self.annotations
+            .suggestions
+            .as_ref()
+            .map_or(&[], |(_, suggestions)| suggestions)
+    }
+
+    /// Returns `Error` with a label pointing at the innermost of the paired `delimiters` which is
+    /// still open at the error's position in `input`, the input the `Error` was created from. The
+    /// `Error` is returned as-is if every delimiter is closed, or if it already has a label at
+    /// that delimiter.
+    ///
+    /// `delimiters` are `(open, close)` pairs, e.g. as detected from a grammar by
+    /// `pest_meta::delimiters::delimiters`. Since the input is only scanned for the delimiters,
+    /// ones appearing e.g. inside of string literals are counted as well.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use pest::error::{Error, ErrorVariant};
+    /// # use pest::Position;
+    /// # #[allow(non_camel_case_types)]
+    /// # #[allow(dead_code)]
+    /// # #[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+    /// # enum Rule {
+    /// #     expr
+    /// # }
+    /// let input = "f(a, [b], (c)";
+    /// let error = Error::new_from_pos(
+    ///     ErrorVariant::ParsingError {
+    ///         positives: vec![Rule::expr],
+    ///         negatives: vec![],
+    ///     },
+    ///     Position::new(input, 13).unwrap(),
+    /// )
+    /// .with_unclosed_delimiter(input, &[("(", ")"), ("[", "]")]);
+    ///
+    /// assert_eq!(error.labels()[0].location(), (1, 2));
+    /// ```
+    pub fn with_unclosed_delimiter<S: AsRef<str>>(
+        self,
+        input: &str,
+        delimiters: &[(S, S)],
+    ) -> Error<R> {
+        let end = match self.location {
+            InputLocation::Pos(pos) => pos,
+            InputLocation::Span((start, _)) => start,
+        };
+        let scanned = &input[..end];
+
+        // The indices in `delimiters` and the positions of the delimiters still open.
+        let mut open: Vec<(usize, usize)> = Vec::new();
+        let mut pos = 0;
+
+        while pos < end {
+            let rest = &scanned[pos..];
+            let closes = |&(index, _): &(usize, usize)| {
+                let close = delimiters[index].1.as_ref();
+                !close.is_empty() && rest.starts_with(close)
+            };
+
+            if let Some(&(index, _)) = open.last().filter(|top| closes(top)) {
+                open.pop();
+                pos += delimiters[index].1.as_ref().len();
+            } else if let Some(index) = delimiters
+                .iter()
+                .position(|(open, _)| !open.as_ref().is_empty() && rest.starts_with(open.as_ref()))
+            {
+                open.push((index, pos));
+                pos += delimiters[index].0.as_ref().len();
+            } else if let Some(depth) = open.iter().rposition(closes) {
+                let (index, _) = open[depth];
+                open.truncate(depth);
+                pos += delimiters[index].1.as_ref().len();
+            } else {
+                pos += rest.chars().next().map_or(1, char::len_utf8);
+            }
+        }
+
+        let (index, start) = match open.last() {
+            Some(&top) => top,
+            None => return self,
+        };
+        let location = (start, start + delimiters[index].0.as_ref().len());
+
+        if self.labels.iter().any(|label| label.location == location) {
+            return self;
+        }
+
+        let span = Span::new(input, location.0, location.1).expect("invalid delimiter span");
+        self.with_label(span, "unclosed delimiter opened here")
+    }
+
+    /// Returns `Error` which, when formatted with `Display`, only shows a window of `width`
+    /// columns of lines longer than that, around the error's column, with ellipses marking the
+    /// omitted text. This keeps errors on very long lines, e.g. in minified files, readable.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use pest::error::{Error, ErrorVariant};
+    /// # use pest::Position;
+    /// # #[allow(non_camel_case_types)]
+    /// # #[allow(dead_code)]
+    /// # #[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+    /// # enum Rule {
+    /// #     open_paren,
+    /// #     closed_paren
+    /// # }
+    /// let input = format!("{}){}", "(".repeat(1000), "(".repeat(1000));
+    /// let pos = Position::new(&input, 1000).unwrap();
+    /// let error = Error::new_from_pos(
+    ///     ErrorVariant::ParsingError {
+    ///         positives: vec![Rule::open_paren],
+    ///         negatives: vec![Rule::closed_paren],
+    ///     },
+    ///     pos,
+    /// )
+    /// .with_snippet_width(80);
+    ///
+    /// assert_eq!(error.snippet_window(), Some((974, 1054)));
+    /// assert!(format!("{}", error).lines().all(|line| line.len() < 100));
+    /// ```
+    pub fn with_snippet_width(mut self, width: usize) -> Error<R> {
+        self.snippet_width = Some(cmp::max(width, 1));
+        self
+    }
+
+    /// Returns the byte range of [`line`] which is shown when the `Error` is formatted with
+    /// `Display`, or `None` if the entire line is shown. See [`with_snippet_width`].
+    ///
+    /// [`line`]: #method.line
+    /// [`with_snippet_width`]: #method.with_snippet_width
+    pub fn snippet_window(&self) -> Option<(usize, usize)> {
+        self.window().map(|(start, end)| {
+            (
+                line_index::prefix_len(&self.line, start, self.column_mode).0,
+                line_index::prefix_len(&self.line, end, self.column_mode).0,
+            )
+        })
+    }
+
+    // Returns the range of columns of `line` which is shown, if it is not entirely shown.
+    fn window(&self) -> Option<(usize, usize)> {
+        let width = self.snippet_width?;
+        let columns = line_index::columns(&self.line, self.column_mode);
+
+        if columns <= width {
+            return None;
+        }
+
+        let start = cmp::min(
+            (self.start().1 - 1).saturating_sub(width / 3),
+            columns - width,
+        );
+
+        Some((start, start + width))
+    }
+
+    fn windowed(&self, text: &str, (start, end): (usize, usize)) -> String {
+        let (start, _) = line_index::prefix_len(text, start, self.column_mode);
+        let (end, _) = line_index::prefix_len(text, end, self.column_mode);
+
+        format!(
+            "{}{}{}",
+            if start > 0 { "..." } else { "" },
+            &text[start..end],
+            if end < text.len() { "..." } else { "" }
+        )
+    }
+
+    /// Returns the line that the error is on, which is the one it starts on if it spans several
+    /// lines.
+    pub fn line(&self) -> &str {
+        self.line.as_str()
+    }
+
+    /// Returns the line that the error ends on if it spans several lines, which is shown below
+    /// [`line`] when the `Error` is formatted with `Display`, or `None` otherwise.
+    ///
+    /// [`line`]: #method.line
+    pub fn continued_line(&self) -> Option<&str> {
+        self.continued_line.as_deref()
+    }
+
+    /// Renders the `Error` in a stable format, which is guaranteed not to change between patch
+    /// releases. This is meant for golden tests of error output.
+    ///
+    /// The layout is the same as the `Display` one, with the following normalizations:
+    ///
+    /// * the rules of a [`ParsingError`] are formatted with `Debug`, sorted by their formatted name
+    ///   and deduplicated
+    /// * tabs are replaced with single spaces
+    /// * trailing whitespace is removed from every line
+    /// * lines are separated by `\n`, without a trailing newline
+    ///
+    /// [`ParsingError`]: enum.ErrorVariant.html#variant.ParsingError
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use pest::error::{Error, ErrorVariant};
+    /// # use pest::Position;
+    /// # #[allow(non_camel_case_types)]
+    /// # #[allow(dead_code)]
+    /// # #[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+    /// # enum Rule {
+    /// #     open_paren,
+    /// #     closed_paren
+    /// # }
+    /// let input = "\t(";
+    /// let pos = Position::new(input, 1).unwrap();
+    /// let error = Error::new_from_pos(
+    ///     ErrorVariant::ParsingError {
+    ///         positives: vec![Rule::open_paren, Rule::closed_paren, Rule::open_paren],
+    ///         negatives: vec![]
+    ///     },
+    ///     pos
+    /// );
+    ///
+    /// assert_eq!(
+    ///     error.stable_display(),
+    ///     [
+    ///         " --> 1:2",
+    ///         "  |",
+    ///         "1 |  (",
+    ///         "  |  ^---",
+    ///         "  |",
+    ///         "  = expected closed_paren or open_paren",
+    ///     ]
+    ///     .join("\n")
+    /// );
+    /// ```
+    pub fn stable_display(&self) -> String {
+        let variant: ErrorVariant<R> = match self.variant {
+            ErrorVariant::ParsingError {
+                ref positives,
+                ref negatives,
+            } => ErrorVariant::CustomError {
+                message: parsing_error_message(
+                    &Error::stable_rules(positives),
+                    &Error::stable_rules(negatives),
+                    |rule| rule.clone(),
+                ),
+            },
+            ErrorVariant::CustomError { ref message } => ErrorVariant::CustomError {
+                message: message.clone(),
+            },
+        };
+
+        let error = Error {
+            variant,
+            location: self.location.clone(),
+            line_col: self.line_col.clone(),
+            path: self.path.clone(),
+            line: self.line.clone(),
+            continued_line: self.continued_line.clone(),
+            column_mode: self.column_mode,
+            snippet_width: self.snippet_width,
+            labels: self.labels.clone(),
+            explanation: None,
+            literals: Vec::new(),
+            annotations: self.annotations.clone(),
+            incomplete: self.incomplete,
+            rule_stack: self.rule_stack.clone(),
+            failure_candidates: self.failure_candidates.clone(),
+            limit_exceeded: self.limit_exceeded,
+            deadline_exceeded: self.deadline_exceeded,
+        };
+
+        error
+            .format()
+            .replace('\t', " ")
+            .lines()
+            .map(str::trim_end)
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// Renders the `Error` like `Display`, but colored with ANSI escape codes for a terminal: the
+    /// underline of the error in red and the rest of the margin, along with the labels, in blue.
+    ///
+    /// Columns are counted in [`ColumnMode::DisplayWidth`], so that the underline stays aligned
+    /// under East Asian wide characters and emoji, while tabs are kept in the underline, so that
+    /// they line up with the ones of the line. Unless set with [`with_snippet_width`], the snippet
+    /// width is the width of the terminal given by the `COLUMNS` environment variable, if any, so
+    /// that long lines are trimmed around the error instead of wrapping.
+    ///
+    /// [`ColumnMode::DisplayWidth`]: ../enum.ColumnMode.html#variant.DisplayWidth
+    /// [`with_snippet_width`]: #method.with_snippet_width
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use pest::error::{Error, ErrorVariant};
+    /// # use pest::Position;
+    /// # #[allow(non_camel_case_types)]
+    /// # #[allow(dead_code)]
+    /// # #[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+    /// # enum Rule {
+    /// #     number
+    /// # }
+    /// let input = "価格 = x";
+    /// let pos = Position::new(input, 9).unwrap();
+    /// let error = Error::new_from_pos(
+    ///     ErrorVariant::<Rule>::CustomError {
+    ///         message: "expected a number".to_owned(),
+    ///     },
+    ///     pos,
+    /// );
+    ///
+    /// let colored = error.to_colored_string();
+    /// assert!(colored.contains("\u{1b}[1;31m       ^---\u{1b}[0m"));
+    /// ```
+    #[cfg(feature = "std")]
+    pub fn to_colored_string(&self) -> String {
+        let mut error = self.clone().with_column_mode(ColumnMode::DisplayWidth);
+
+        if error.snippet_width.is_none() {
+            let columns = std::env::var("COLUMNS")
+                .ok()
+                .and_then(|columns| columns.parse::<usize>().ok());
+            if let Some(columns) = columns {
+                // The margin, along with the ellipses of a trimmed line, takes up the rest.
+                let margin = error.spacing().len() + " | ".len() + 2 * "...".len();
+                error.snippet_width = Some(cmp::max(columns.saturating_sub(margin), 1));
+            }
+        }
+
+        error.render(true)
+    }
+
+    fn stable_rules(rules: &[R]) -> Vec<String> {
+        let mut names: Vec<String> = rules.iter().map(|rule| format!("{:?}", rule)).collect();
+        names.sort();
+        names.dedup();
+        names
+    }
+
+    /// Renames all `Rule`s if this is a [`ParsingError`]. It does nothing when called on a
+    /// [`CustomError`].
+    ///
+    /// Useful in order to rename verbose rules or have detailed per-`Rule` formatting.
+    ///
+    /// [`ParsingError`]: enum.ErrorVariant.html#variant.ParsingError
+    /// [`CustomError`]: enum.ErrorVariant.html#variant.CustomError
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use pest::error::{Error, ErrorVariant};
+    /// # use pest::Position;
+    /// # #[allow(non_camel_case_types)]
+    /// # #[allow(dead_code)]
+    /// # #[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+    /// # enum Rule {
+    /// #     open_paren,
+    /// #     closed_paren
+    /// # }
+    /// # let input = "";
+    /// # let pos = Position::from_start(input);
+    /// Error::new_from_pos(
+    ///     ErrorVariant::ParsingError {
+    ///         positives: vec![Rule::open_paren],
+    ///         negatives: vec![Rule::closed_paren]
+    ///     },
+    ///     pos
+    /// ).renamed_rules(|rule| {
+    ///     match *rule {
+    ///         Rule::open_paren => "(".to_owned(),
+    ///         Rule::closed_paren => "closed paren".to_owned()
+    ///     }
+    /// });
+    /// ```
+    pub fn renamed_rules<F>(mut self, f: F) -> Error<R>
+    where
+        F: FnMut(&R) -> String,
+    {
+        let variant = match self.variant {
+            ErrorVariant::ParsingError {
+                positives,
+                negatives,
+            } => {
+                let message = parsing_error_message(&positives, &negatives, f);
+                ErrorVariant::CustomError { message }
+            }
+            variant => variant,
+        };
+
+        self.variant = variant;
+
+        self
+    }
+
+    /// Turns a [`ParsingError`] into a [`CustomError`] listing what was [`expected`], renaming
+    /// rules with `f`, e.g. "expected one of: `(`, `-`, a number, an identifier". It does nothing
+    /// when called on a [`CustomError`].
+    ///
+    /// [`ParsingError`]: enum.ErrorVariant.html#variant.ParsingError
+    /// [`CustomError`]: enum.ErrorVariant.html#variant.CustomError
+    /// [`expected`]: #method.expected
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[allow(non_camel_case_types)]
+    /// # #[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+    /// # enum Rule { number }
+    /// let error = pest::state("x", |s| {
+    ///     s.match_string("(").or_else(|s| s.rule(Rule::number, |s| s.match_range('0'..'9')))
+    /// })
+    /// .unwrap_err()
+    /// .renamed_expected(|rule| match *rule {
+    ///     Rule::number => "a number".to_owned(),
+    /// });
+    ///
+    /// assert_eq!(error.variant.message(), "expected one of: `(`, a number");
+    /// ```
+    pub fn renamed_expected<F>(mut self, mut f: F) -> Error<R>
+    where
+        F: FnMut(&R) -> String,
+    {
+        let negatives = match self.variant {
+            ErrorVariant::ParsingError { ref negatives, .. } => negatives,
+            ErrorVariant::CustomError { .. } => return self,
+        };
+
+        let expected: Vec<_> = self
+            .expected()
+            .into_iter()
+            .map(|expected| match expected {
+                Expected::Rule(ref rule) => f(rule),
+                expected => expected.to_string(),
+            })
+            .collect();
+        let expected = match expected.len() {
+            0 => None,
+            1 => Some(format!("expected {}", expected[0])),
+            _ => Some(format!("expected one of: {}", expected.join(", "))),
+        };
+
+        let message = match (negatives.is_empty(), expected) {
+            (false, Some(expected)) => {
+                format!("unexpected {}; {}", enumerate(negatives, &mut f), expected)
+            }
+            (false, None) => format!("unexpected {}", enumerate(negatives, &mut f)),
+            (true, Some(expected)) => expected,
+            (true, None) => "unknown parsing error".to_owned(),
+        };
+
+        self.variant = ErrorVariant::CustomError { message };
+
+        self
+    }
+
+    /// Maps the rules of the `Error` with `f`, keeping everything else intact.
+    pub(crate) fn map_rules<T, F>(self, mut f: F) -> Error<T>
+    where
+        F: FnMut(R) -> T,
+    {
+        let variant = match self.variant {
+            ErrorVariant::ParsingError {
+                positives,
+                negatives,
+            } => ErrorVariant::ParsingError {
+                positives: positives.into_iter().map(&mut f).collect(),
+                negatives: negatives.into_iter().map(&mut f).collect(),
+            },
+            ErrorVariant::CustomError { message } => ErrorVariant::CustomError { message },
+        };
+
+        Error {
+            variant,
+            location: self.location,
+            line_col: self.line_col,
+            path: self.path,
+            line: self.line,
+            continued_line: self.continued_line,
+            column_mode: self.column_mode,
+            snippet_width: self.snippet_width,
+            labels: self.labels,
+            explanation: self
+                .explanation
+                .map(|explanation| Box::new(explanation.map_rules(&mut f))),
+            literals: self.literals,
+            annotations: self.annotations,
+            incomplete: self.incomplete,
+            rule_stack: self.rule_stack.into_iter().map(&mut f).collect(),
+            failure_candidates: self.failure_candidates,
+            limit_exceeded: self.limit_exceeded,
+            deadline_exceeded: self.deadline_exceeded,
+        }
+    }
+
+    /// Returns `Error` with its location and labels moved within `span`, e.g. for tools which parse
+    /// a selection of a larger document and must not report positions outside of it. A location
+    /// outside of `span` moves to its closest end, labels outside of it are left out and the
+    /// explanation, whose attempts may be outside of it, is dropped.
+    ///
+    /// The lines and columns are still those of the whole input, i.e. of the input of `span`,
+    /// which must be the one the `Error` was created from.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use pest::error::{Error, ErrorVariant, InputLocation, LineColLocation};
+    /// # use pest::{Position, Span};
+    /// # #[allow(non_camel_case_types)]
+    /// # #[allow(dead_code)]
+    /// # #[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+    /// # enum Rule {
+    /// #     closed_paren
+    /// # }
+    /// let input = "(a\n(b\nc";
+    /// let error = Error::new_from_pos(
+    ///     ErrorVariant::ParsingError {
+    ///         positives: vec![Rule::closed_paren],
+    ///         negatives: vec![],
+    ///     },
+    ///     Position::new(input, 7).unwrap(),
+    /// )
+    /// .with_label(Span::new(input, 0, 1).unwrap(), "unclosed delimiter opened here")
+    /// .with_label(Span::new(input, 3, 4).unwrap(), "unclosed delimiter opened here");
+    ///
+    /// let error = error.clamp_to(Span::new(input, 3, 4).unwrap());
+    /// assert_eq!(error.location, InputLocation::Pos(4));
+    /// assert_eq!(error.line_col, LineColLocation::Pos((2, 2)));
+    /// assert_eq!(error.labels().len(), 1);
+    /// assert_eq!(error.labels()[0].location(), (3, 4));
+    /// ```
+    pub fn clamp_to(self, span: Span<'_>) -> Error<R> {
+        let path = self.path.clone();
+        let snippet_width = self.snippet_width;
+        let column_mode = self.column_mode;
+        let annotations = self.annotations.clone();
+
+        let mut error = ErrorHead::from(self)
+            .clamped((span.start(), span.end()))
+            .into_error(span.get_input());
+        error.path = path;
+        error.snippet_width = snippet_width;
+        error.annotations = annotations;
+
+        error.with_column_mode(column_mode)
+    }
+
+    /// Moves an `Error` created from a substring of `input` starting at byte `offset` over to
+    /// `input`.
+    pub(crate) fn rebase(self, input: &str, offset: usize) -> Error<R> {
+        let mut error = match self.location {
+            InputLocation::Pos(pos) => Error::new_from_pos(
+                self.variant,
+                Position::new(input, pos + offset).expect("invalid rebased position"),
+            ),
+            InputLocation::Span((start, end)) => Error::new_from_span(
+                self.variant,
+                Span::new(input, start + offset, end + offset).expect("invalid rebased span"),
+            ),
+        };
+        error.path = self.path;
+        error.snippet_width = self.snippet_width;
+        error.labels = self
+            .labels
+            .into_iter()
+            .map(|mut label| {
+                label.location = (label.location.0 + offset, label.location.1 + offset);
+                label.line_col = Position::new(input, label.location.0)
+                    .expect("invalid rebased label")
+                    .line_col();
+                label
+            })
+            .collect();
+        error.explanation = self
+            .explanation
+            .map(|explanation| Box::new(explanation.rebase(offset)));
+        error.literals = self.literals;
+        error.annotations = self.annotations;
+        error.incomplete = self.incomplete;
+        error.rule_stack = self.rule_stack;
+        error.failure_candidates = self
+            .failure_candidates
+            .into_iter()
+            .map(|(bias, pos)| (bias, pos + offset))
+            .collect();
+        error.limit_exceeded = self.limit_exceeded;
+        error.deadline_exceeded = self.deadline_exceeded;
+
+        error.with_column_mode(self.column_mode)
+    }
+
+    fn start(&self) -> (usize, usize) {
+        self.line_col.start()
+    }
+
+    fn spacing(&self) -> String {
+        let line = cmp::max(self.line_col.start().0, self.line_col.end().0);
+        let line = self
+            .labels
+            .iter()
+            .map(|label| label.line_col.0)
+            .fold(line, cmp::max);
+
+        let line_str_len = format!("{}", line).len();
+
+        let mut spacing = String::new();
+        for _ in 0..line_str_len {
+            spacing.push(' ');
+        }
+
+        spacing
+    }
+
+    fn underline(&self) -> String {
+        let mut underline = String::new();
+
+        let mut start = self.start().1;
+        let end = match self.line_col {
+            LineColLocation::Span(_, (_, mut end)) => {
+                let inverted_cols = start > end;
+                if inverted_cols {
+                    mem::swap(&mut start, &mut end);
+                    start -= 1;
+                    end += 1;
+                }
+
+                Some(end)
+            }
+            _ => None,
+        };
+        let (skip, limit) = self.window().unwrap_or((0, usize::MAX));
+        let end = end.map(|end| cmp::min(end, limit.saturating_add(1)));
+        let offset = start - 1;
+        let mut columns = 0;
+
+        if skip > 0 {
+            underline.push_str("   ");
+        }
+
+        for (cluster, width) in line_index::clusters(&self.line, self.column_mode) {
+            if columns >= offset {
+                break;
+            }
+
+            if columns >= skip {
+                match cluster {
+                    "\t" => underline.push('\t'),
+                    _ => underline.extend(core::iter::repeat(' ').take(width)),
+                }
+            }
+            columns += width;
+        }
+
+        if let Some(end) = end {
+            underline.push('^');
+            if end - start > 1 {
+                for _ in 2..(end - start) {
+                    underline.push('-');
+                }
+                underline.push('^');
+            }
+        } else {
+            underline.push_str("^---")
+        }
+
+        if let Some(ref label) = self.annotations.label {
+            underline.push(' ');
+            underline.push_str(label);
+        }
+
+        underline
+    }
+
+    fn label_underline(&self, label: &ErrorLabel) -> String {
+        let mut underline = String::new();
+
+        for (cluster, width) in line_index::clusters(&label.line[..label.range.0], self.column_mode)
+        {
+            match cluster {
+                "\t" => underline.push('\t'),
+                _ => underline.extend(core::iter::repeat(' ').take(width)),
+            }
+        }
+
+        let len = line_index::columns(&label.line[label.range.0..label.range.1], self.column_mode);
+        underline.push('^');
+        if len > 1 {
+            for _ in 2..len {
+                underline.push('-');
+            }
+            underline.push('^');
+        }
+
+        underline
+    }
+
+    fn message(&self) -> String {
+        let message = self.variant.message();
+
+        match self.annotations.suggestions {
+            Some((ref found, ref suggestions)) => format!(
+                "{}, found `{}`; did you mean {}?",
+                message,
+                found,
+                enumerate(suggestions, &mut |literal| format!("`{}`", literal))
+            ),
+            None => message.to_string(),
+        }
+    }
+
+    pub(crate) fn format(&self) -> String {
+        self.render(false)
+    }
+
+    // Renders the `Error` like `Display` does, coloring it with ANSI escape codes if `colored`.
+    fn render(&self, colored: bool) -> String {
+        let paint = |color: &str, text: &str| {
+            if colored {
+                format!("{}{}{}", color, text, RESET)
+            } else {
+                text.to_owned()
+            }
+        };
+
         let spacing = self.spacing();
         let path = self
             .path
             .as_ref()
             .map(|path| format!("{}:", path))
             .unwrap_or_default();
+        let arrow = paint(GUTTER, "-->");
+        let bar = paint(GUTTER, " |");
+        let number = |line: usize| paint(GUTTER, &format!("{:w$}", line, w = spacing.len()));
 
-        let pair = (self.line_col.clone(), &self.continued_line);
-        if let (LineColLocation::Span(_, end), &Some(ref continued_line)) = pair {
+        let line = match self.window() {
+            Some(window) => Cow::Owned(self.windowed(&self.line, window)),
+            None => Cow::Borrowed(self.line.as_str()),
+        };
+        let continued_line =
+            self.continued_line
+                .as_ref()
+                .map(|continued_line| match self.snippet_width {
+                    Some(width)
+                        if line_index::columns(continued_line, self.column_mode) > width =>
+                    {
+                        Cow::Owned(self.windowed(continued_line, (0, width)))
+                    }
+                    _ => Cow::Borrowed(continued_line.as_str()),
+                });
+        let underline = paint(PRIMARY, &self.underline());
+
+        let pair = (self.line_col.clone(), continued_line);
+        let mut result = if let (LineColLocation::Span(_, end), Some(continued_line)) = pair {
             let has_line_gap = end.0 - self.start().0 > 1;
             if has_line_gap {
                 format!(
-                    "{s    }--> {p}{ls}:{c}\n\
-                     {s    } |\n\
-                     {ls:w$} | {line}\n\
-                     {s    } | ...\n\
-                     {le:w$} | {continued_line}\n\
-                     {s    } | {underline}\n\
-                     {s    } |\n\
-                     {s    } = {message}",
+                    "{s}{arrow} {p}{ls}:{c}\n\
+                     {s}{bar}\n\
+                     {nls}{bar} {line}\n\
+                     {s}{bar} ...\n\
+                     {nle}{bar} {continued_line}\n\
+                     {s}{bar} {underline}",
                     s = spacing,
-                    w = spacing.len(),
+                    arrow = arrow,
+                    bar = bar,
                     p = path,
                     ls = self.start().0,
-                    le = end.0,
+                    nls = number(self.start().0),
+                    nle = number(end.0),
                     c = self.start().1,
-                    line = self.line,
+                    line = line,
                     continued_line = continued_line,
-                    underline = self.underline(),
-                    message = self.message()
+                    underline = underline,
                 )
             } else {
                 format!(
-                    "{s    }--> {p}{ls}:{c}\n\
-                     {s    } |\n\
-                     {ls:w$} | {line}\n\
-                     {le:w$} | {continued_line}\n\
-                     {s    } | {underline}\n\
-                     {s    } |\n\
-                     {s    } = {message}",
+                    "{s}{arrow} {p}{ls}:{c}\n\
+                     {s}{bar}\n\
+                     {nls}{bar} {line}\n\
+                     {nle}{bar} {continued_line}\n\
+                     {s}{bar} {underline}",
                     s = spacing,
-                    w = spacing.len(),
+                    arrow = arrow,
+                    bar = bar,
                     p = path,
                     ls = self.start().0,
-                    le = end.0,
+                    nls = number(self.start().0),
+                    nle = number(end.0),
                     c = self.start().1,
-                    line = self.line,
+                    line = line,
                     continued_line = continued_line,
-                    underline = self.underline(),
-                    message = self.message()
+                    underline = underline,
                 )
             }
-        } else {
-            format!(
-                "{s}--> {p}{l}:{c}\n\
-                 {s} |\n\
-                 {l} | {line}\n\
-                 {s} | {underline}\n\
-                 {s} |\n\
-                 {s} = {message}",
-                s = spacing,
-                p = path,
-                l = self.start().0,
-                c = self.start().1,
-                line = self.line,
-                underline = self.underline(),
-                message = self.message()
-            )
+        } else {
+            format!(
+                "{s}{arrow} {p}{l}:{c}\n\
+                 {s}{bar}\n\
+                 {nl}{bar} {line}\n\
+                 {s}{bar} {underline}",
+                s = spacing,
+                arrow = arrow,
+                bar = bar,
+                p = path,
+                l = self.start().0,
+                nl = number(self.start().0),
+                c = self.start().1,
+                line = line,
+                underline = underline,
+            )
+        };
+
+        for label in &self.labels {
+            result.push_str(&format!(
+                "\n{s}{bar}\n\
+                 {s}{colons} {p}{l}:{c}\n\
+                 {s}{bar}\n\
+                 {nl}{bar} {line}\n\
+                 {s}{bar} {underline}",
+                s = spacing,
+                bar = bar,
+                colons = paint(GUTTER, ":::"),
+                p = path,
+                l = label.line_col.0,
+                nl = number(label.line_col.0),
+                c = line_index::columns(&label.line[..label.range.0], self.column_mode) + 1,
+                line = label.line,
+                underline = paint(
+                    GUTTER,
+                    &format!("{} {}", self.label_underline(label), label.message)
+                ),
+            ));
+        }
+
+        let equals = paint(GUTTER, "=");
+        result.push_str(&format!(
+            "\n{s}{bar}\n{s} {equals} {message}",
+            s = spacing,
+            bar = bar,
+            equals = equals,
+            message = paint(BOLD, &self.message())
+        ));
+
+        for note in &self.annotations.notes {
+            result.push_str(&format!(
+                "\n{s} {equals} {kind} {note}",
+                s = spacing,
+                equals = equals,
+                kind = paint(BOLD, "note:"),
+                note = note
+            ));
+        }
+        if let Some(ref help) = self.annotations.help {
+            result.push_str(&format!(
+                "\n{s} {equals} {kind} {help}",
+                s = spacing,
+                equals = equals,
+                kind = paint(BOLD, "help:"),
+                help = help
+            ));
+        }
+
+        result
+    }
+}
+
+/// The variant and location of an [`Error`], without the lines, columns and snippets of the input
+/// which make up the rest of it. It is cheap to create, which suits hot paths that retry or discard
+/// most errors, and can be turned into a full `Error` with [`into_error`] once it is needed.
+/// Conversely, an `Error` converts into its `ErrorHead` with `From`.
+///
+/// Like errors, `ErrorHead`s are compared and hashed without what the parse recorded besides their
+/// variant, location and labels.
+///
+/// [`Error`]: struct.Error.html
+/// [`into_error`]: #method.into_error
+#[derive(Clone, Debug, Eq)]
+pub struct ErrorHead<R> {
+    /// Variant of the error
+    pub variant: ErrorVariant<R>,
+    /// Location within the input string
+    pub location: InputLocation,
+    labels: Vec<((usize, usize), String)>,
+    explanation: Option<Box<Explanation<R>>>,
+    literals: Vec<(String, bool)>,
+    incomplete: bool,
+    rule_stack: Vec<R>,
+    failure_candidates: Vec<(ErrorBias, usize)>,
+    limit_exceeded: Option<(LimitKind, usize)>,
+    deadline_exceeded: bool,
+}
+
+impl<R: PartialEq> PartialEq for ErrorHead<R> {
+    fn eq(&self, other: &ErrorHead<R>) -> bool {
+        self.variant == other.variant
+            && self.location == other.location
+            && self.labels == other.labels
+    }
+}
+
+impl<R: Hash> Hash for ErrorHead<R> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.variant.hash(state);
+        self.location.hash(state);
+        self.labels.hash(state);
+    }
+}
+
+impl<R: RuleType> ErrorHead<R> {
+    /// Creates `ErrorHead` from `ErrorVariant` and `InputLocation`.
+    pub fn new(variant: ErrorVariant<R>, location: InputLocation) -> ErrorHead<R> {
+        ErrorHead {
+            variant,
+            location,
+            labels: Vec::new(),
+            explanation: None,
+            literals: Vec::new(),
+            incomplete: false,
+            rule_stack: Vec::new(),
+            failure_candidates: Vec::new(),
+            limit_exceeded: None,
+            deadline_exceeded: false,
+        }
+    }
+
+    /// Returns whether the input ended where the parse could still have matched, like
+    /// [`Error::is_incomplete`].
+    ///
+    /// [`Error::is_incomplete`]: struct.Error.html#method.is_incomplete
+    pub fn is_incomplete(&self) -> bool {
+        self.incomplete
+    }
+
+    pub(crate) fn with_incomplete(mut self, incomplete: bool) -> ErrorHead<R> {
+        self.incomplete = incomplete;
+        self
+    }
+
+    pub(crate) fn with_rule_stack(mut self, rule_stack: Vec<R>) -> ErrorHead<R> {
+        self.rule_stack = rule_stack;
+        self
+    }
+
+    /// Returns the limit which stopped the parse, like [`Error::limit_exceeded`].
+    ///
+    /// [`Error::limit_exceeded`]: struct.Error.html#method.limit_exceeded
+    pub fn limit_exceeded(&self) -> Option<(LimitKind, usize)> {
+        self.limit_exceeded
+    }
+
+    pub(crate) fn with_limit_exceeded(
+        mut self,
+        limit_exceeded: Option<(LimitKind, usize)>,
+    ) -> ErrorHead<R> {
+        self.limit_exceeded = limit_exceeded;
+        self
+    }
+
+    /// Returns whether the parse was stopped by its deadline, like [`Error::deadline_exceeded`].
+    ///
+    /// [`Error::deadline_exceeded`]: struct.Error.html#method.deadline_exceeded
+    pub fn deadline_exceeded(&self) -> bool {
+        self.deadline_exceeded
+    }
+
+    pub(crate) fn with_deadline_exceeded(mut self, deadline_exceeded: bool) -> ErrorHead<R> {
+        self.deadline_exceeded = deadline_exceeded;
+        self
+    }
+
+    pub(crate) fn with_failure_candidates(
+        mut self,
+        failure_candidates: Vec<(ErrorBias, usize)>,
+    ) -> ErrorHead<R> {
+        self.failure_candidates = failure_candidates;
+        self
+    }
+
+    pub(crate) fn with_label(mut self, location: (usize, usize), message: String) -> ErrorHead<R> {
+        self.labels.push((location, message));
+        self
+    }
+
+    pub(crate) fn with_explanation(mut self, explanation: Explanation<R>) -> ErrorHead<R> {
+        self.explanation = Some(Box::new(explanation));
+        self
+    }
+
+    // Moves the location and labels into the byte range `window`, leaving out the labels outside of
+    // it and the explanation, whose attempts may be outside of it.
+    pub(crate) fn clamped(mut self, (start, end): (usize, usize)) -> ErrorHead<R> {
+        let clamp = |pos: usize| cmp::min(cmp::max(pos, start), end);
+
+        self.location = match self.location {
+            InputLocation::Pos(pos) => InputLocation::Pos(clamp(pos)),
+            InputLocation::Span((from, to)) if clamp(from) < clamp(to) => {
+                InputLocation::Span((clamp(from), clamp(to)))
+            }
+            InputLocation::Span((from, _)) => InputLocation::Pos(clamp(from)),
+        };
+        self.labels = mem::take(&mut self.labels)
+            .into_iter()
+            .filter(|&((from, to), _)| {
+                clamp(from) < clamp(to) || (from == to && clamp(from) == from)
+            })
+            .map(|((from, to), message)| ((clamp(from), clamp(to)), message))
+            .collect();
+        self.explanation = None;
+        for (_, pos) in &mut self.failure_candidates {
+            *pos = clamp(*pos);
+        }
+        self
+    }
+
+    // The literals are given as their text and whether they are case-insensitive.
+    pub(crate) fn with_literals(mut self, literals: Vec<(String, bool)>) -> ErrorHead<R> {
+        self.literals = literals;
+        self
+    }
+
+    /// Creates the full `Error` for `input`, the input the `ErrorHead` was created from.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the location or the labels are not at `char` boundaries of `input`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use pest::error::{ErrorHead, ErrorVariant, InputLocation, LineColLocation};
+    /// # #[allow(non_camel_case_types)]
+    /// # #[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+    /// # enum Rule { number }
+    /// let input = "1\n2 x";
+    /// let head = ErrorHead::new(
+    ///     ErrorVariant::ParsingError {
+    ///         positives: vec![Rule::number],
+    ///         negatives: vec![],
+    ///     },
+    ///     InputLocation::Pos(4),
+    /// );
+    ///
+    /// let error = head.into_error(input);
+    /// assert_eq!(error.line_col, LineColLocation::Pos((2, 3)));
+    /// assert_eq!(error.line(), "2 x");
+    /// ```
+    pub fn into_error(self, input: &str) -> Error<R> {
+        let mut error = match self.location {
+            InputLocation::Pos(pos) => {
+                Error::new_from_pos(self.variant, Position::new(input, pos).unwrap())
+            }
+            InputLocation::Span((start, end)) => {
+                Error::new_from_span(self.variant, Span::new(input, start, end).unwrap())
+            }
+        };
+
+        for ((start, end), message) in &self.labels {
+            error = error.with_label(Span::new(input, *start, *end).unwrap(), message);
+        }
+        error.explanation = self.explanation;
+        error.literals = self.literals;
+        error.incomplete = self.incomplete;
+        error.rule_stack = self.rule_stack;
+        error.failure_candidates = self.failure_candidates;
+        error.limit_exceeded = self.limit_exceeded;
+        error.deadline_exceeded = self.deadline_exceeded;
+        error
+    }
+}
+
+impl<R: RuleType> From<Error<R>> for ErrorHead<R> {
+    fn from(error: Error<R>) -> ErrorHead<R> {
+        ErrorHead {
+            variant: error.variant,
+            location: error.location,
+            labels: error
+                .labels
+                .into_iter()
+                .map(|label| (label.location, label.message))
+                .collect(),
+            explanation: error.explanation,
+            literals: error.literals,
+            incomplete: error.incomplete,
+            rule_stack: error.rule_stack,
+            failure_candidates: error.failure_candidates,
+            limit_exceeded: error.limit_exceeded,
+            deadline_exceeded: error.deadline_exceeded,
         }
     }
 }
@@ -510,7 +2083,7 @@ impl<R: RuleType> ErrorVariant<R> {
             ErrorVariant::ParsingError {
                 ref positives,
                 ref negatives,
-            } => Cow::Owned(Error::parsing_error_message(positives, negatives, |r| {
+            } => Cow::Owned(parsing_error_message(positives, negatives, |r| {
                 format!("{:?}", r)
             })),
             ErrorVariant::CustomError { ref message } => Cow::Borrowed(message),
@@ -533,6 +2106,67 @@ impl<R: RuleType> fmt::Display for ErrorVariant<R> {
     }
 }
 
+// The ANSI escape codes of `Error::to_colored_string`.
+const GUTTER: &str = "\x1b[1;34m";
+const PRIMARY: &str = "\x1b[1;31m";
+const BOLD: &str = "\x1b[1m";
+const RESET: &str = "\x1b[0m";
+
+fn parsing_error_message<T, F>(positives: &[T], negatives: &[T], mut f: F) -> String
+where
+    F: FnMut(&T) -> String,
+{
+    match (negatives.is_empty(), positives.is_empty()) {
+        (false, false) => format!(
+            "unexpected {}; expected {}",
+            enumerate(negatives, &mut f),
+            enumerate(positives, &mut f)
+        ),
+        (false, true) => format!("unexpected {}", enumerate(negatives, &mut f)),
+        (true, false) => format!("expected {}", enumerate(positives, &mut f)),
+        (true, true) => "unknown parsing error".to_owned(),
+    }
+}
+
+fn enumerate<T, F>(rules: &[T], f: &mut F) -> String
+where
+    F: FnMut(&T) -> String,
+{
+    match rules.len() {
+        1 => f(&rules[0]),
+        2 => format!("{} or {}", f(&rules[0]), f(&rules[1])),
+        l => {
+            let non_separated = f(&rules[l - 1]);
+            let separated = rules
+                .iter()
+                .take(l - 1)
+                .map(f)
+                .collect::<Vec<_>>()
+                .join(", ");
+            format!("{}, or {}", separated, non_separated)
+        }
+    }
+}
+
+// The Levenshtein distance between `a` and `b`, counted in `char`s.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<_> = b.chars().collect();
+    let mut row: Vec<_> = (0..=b.len()).collect();
+
+    for (i, ca) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+
+        for (j, &cb) in b.iter().enumerate() {
+            let substitution = diagonal + usize::from(ca != cb);
+            diagonal = row[j + 1];
+            row[j + 1] = substitution.min(row[j] + 1).min(diagonal + 1);
+        }
+    }
+
+    row[b.len()]
+}
+
 fn visualize_whitespace(input: &str) -> String {
     input.to_owned().replace('\r', "␍").replace('\n', "␊")
 }
@@ -711,6 +2345,10 @@ mod tests {
             start.span(&end),
         );
 
+        assert_eq!(error.line_col.start(), (1, 2));
+        assert_eq!(error.line_col.end(), (3, 4));
+        assert_eq!(error.line(), "ab");
+        assert_eq!(error.continued_line(), Some("efgh"));
         assert_eq!(
             format!("{}", error),
             vec![
@@ -892,4 +2530,477 @@ mod tests {
             .join("\n")
         );
     }
+
+    #[test]
+    fn stable_display_parsing_error() {
+        let input = "a\txbc  ";
+        let pos = Position::new(input, 2).unwrap();
+        let error: Error<u32> = Error::new_from_pos(
+            ErrorVariant::ParsingError {
+                positives: vec![3, 10, 2, 3],
+                negatives: vec![6, 4, 6],
+            },
+            pos,
+        )
+        .with_path("file.rs");
+
+        assert_eq!(
+            error.stable_display(),
+            [
+                " --> file.rs:1:3",
+                "  |",
+                "1 | a xbc",
+                "  |   ^---",
+                "  |",
+                "  = unexpected 4 or 6; expected 10, 2, or 3",
+            ]
+            .join("\n")
+        );
+    }
+
+    #[test]
+    fn stable_display_custom_span() {
+        let input = "ab \n\tcd\nef";
+        let start = Position::new(input, 1).unwrap();
+        let end = Position::new(input, 6).unwrap();
+        let error: Error<u32> = Error::new_from_span(
+            ErrorVariant::CustomError {
+                message: "error: big one".to_owned(),
+            },
+            start.span(&end),
+        );
+
+        assert_eq!(
+            error.stable_display(),
+            [
+                " --> 1:2",
+                "  |",
+                "1 | ab",
+                "2 |  cd␊",
+                "  |  ^",
+                "  |",
+                "  = error: big one",
+            ]
+            .join("\n")
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn display_width_span() {
+        let input = "é漢字 👍🏽x = y";
+        let start = Position::new(input, 17).unwrap();
+        let end = Position::new(input, 22).unwrap();
+        let error: Error<u32> = Error::new_from_span(
+            ErrorVariant::CustomError {
+                message: "error: big one".to_owned(),
+            },
+            start.span(&end),
+        )
+        .with_column_mode(ColumnMode::DisplayWidth);
+
+        assert_eq!(error.line_col, LineColLocation::Span((1, 9), (1, 14)));
+        assert_eq!(
+            format!("{}", error),
+            [
+                " --> 1:9",
+                "  |",
+                "1 | é漢字 👍🏽x = y",
+                "  |         ^---^",
+                "  |",
+                "  = error: big one",
+            ]
+            .join("\n")
+        );
+
+        let error = error.with_column_mode(ColumnMode::Graphemes);
+        assert_eq!(error.line_col, LineColLocation::Span((1, 6), (1, 11)));
+        assert_eq!(
+            strip_colors(&error.to_colored_string()),
+            error
+                .clone()
+                .with_column_mode(ColumnMode::DisplayWidth)
+                .to_string()
+        );
+        let error = error.with_column_mode(ColumnMode::Chars);
+        assert_eq!(error.line_col, LineColLocation::Span((1, 7), (1, 12)));
+    }
+
+    #[test]
+    fn snippet_window() {
+        let input = format!("{}X{}", "a".repeat(50), "b".repeat(50));
+        let pos = Position::new(&input, 50).unwrap();
+        let error: Error<u32> = Error::new_from_pos(
+            ErrorVariant::CustomError {
+                message: "error: big one".to_owned(),
+            },
+            pos,
+        )
+        .with_snippet_width(20);
+
+        assert_eq!(error.snippet_window(), Some((44, 64)));
+        assert_eq!(
+            format!("{}", error),
+            [
+                " --> 1:51",
+                "  |",
+                "1 | ...aaaaaaXbbbbbbbbbbbbb...",
+                "  |          ^---",
+                "  |",
+                "  = error: big one",
+            ]
+            .join("\n")
+        );
+    }
+
+    #[test]
+    fn snippet_window_span_end() {
+        let input = format!("{}X{}", "a".repeat(10), "b".repeat(50));
+        let start = Position::new(&input, 10).unwrap();
+        let end = Position::new(&input, 40).unwrap();
+        let error: Error<u32> = Error::new_from_span(
+            ErrorVariant::CustomError {
+                message: "error: big one".to_owned(),
+            },
+            start.span(&end),
+        )
+        .with_snippet_width(20);
+
+        assert_eq!(error.snippet_window(), Some((4, 24)));
+        assert_eq!(
+            format!("{}", error),
+            [
+                " --> 1:11",
+                "  |",
+                "1 | ...aaaaaaXbbbbbbbbbbbbb...",
+                "  |          ^------------^",
+                "  |",
+                "  = error: big one",
+            ]
+            .join("\n")
+        );
+    }
+
+    #[test]
+    fn snippet_window_short_line() {
+        let input = "abc";
+        let pos = Position::new(input, 1).unwrap();
+        let error: Error<u32> = Error::new_from_pos(
+            ErrorVariant::CustomError {
+                message: "error".to_owned(),
+            },
+            pos,
+        )
+        .with_snippet_width(20);
+
+        assert_eq!(error.snippet_window(), None);
+        assert_eq!(
+            format!("{}", error),
+            [
+                " --> 1:2",
+                "  |",
+                "1 | abc",
+                "  |  ^---",
+                "  |",
+                "  = error"
+            ]
+            .join("\n")
+        );
+    }
+
+    #[test]
+    fn label_spacing() {
+        let input = "\t(a\n\n\n\n\n\n\n\n\nb))";
+        let span = Span::new(input, 1, 3).unwrap();
+        let error: Error<u32> = Error::new_from_pos(
+            ErrorVariant::CustomError {
+                message: "error".to_owned(),
+            },
+            Position::new(input, 0).unwrap(),
+        )
+        .with_label(span, "opened here")
+        .with_label(Span::new(input, 13, 15).unwrap(), "closed here");
+
+        assert_eq!(error.labels().len(), 2);
+        assert_eq!(error.labels()[0].location(), (1, 3));
+        assert_eq!(error.labels()[1].line_col(), (10, 2));
+        assert_eq!(error.labels()[1].line(), "b))");
+        assert_eq!(
+            format!("{}", error),
+            [
+                "  --> 1:1",
+                "   |",
+                " 1 | \t(a",
+                "   | ^---",
+                "   |",
+                "  ::: 1:2",
+                "   |",
+                " 1 | \t(a",
+                "   | \t^^ opened here",
+                "   |",
+                "  ::: 10:2",
+                "   |",
+                "10 | b))",
+                "   |  ^^ closed here",
+                "   |",
+                "   = error"
+            ]
+            .join("\n")
+        );
+    }
+
+    #[test]
+    fn annotations() {
+        let input = "ab";
+        let error: Error<u32> = Error::new_from_pos(
+            ErrorVariant::ParsingError {
+                positives: vec![1],
+                negatives: vec![],
+            },
+            Position::new(input, 1).unwrap(),
+        )
+        .with_primary_label("here")
+        .with_note("first")
+        .with_note("second")
+        .with_help("fix it")
+        .renamed_rules(|rule| format!("rule {}", rule))
+        .clamp_to(Span::new(input, 0, 2).unwrap());
+
+        assert_eq!(error.primary_label(), Some("here"));
+        assert_eq!(error.notes(), ["first", "second"]);
+        assert_eq!(error.help(), Some("fix it"));
+        assert_eq!(
+            format!("{}", error),
+            [
+                " --> 1:2",
+                "  |",
+                "1 | ab",
+                "  |  ^--- here",
+                "  |",
+                "  = expected rule 1",
+                "  = note: first",
+                "  = note: second",
+                "  = help: fix it"
+            ]
+            .join("\n")
+        );
+    }
+
+    #[test]
+    fn suggestions() {
+        let input = "SELCT x";
+        let mut error: Error<u32> = Error::new_from_pos(
+            ErrorVariant::ParsingError {
+                positives: vec![1, 2],
+                negatives: vec![],
+            },
+            Position::new(input, 0).unwrap(),
+        );
+        error.literals = vec![("select".to_owned(), true), ("delete".to_owned(), true)];
+
+        let error = error.with_suggestions(input, |rule| match rule {
+            1 => Some("SELECT"),
+            _ => Some("x"),
+        });
+
+        assert_eq!(error.suggestions(), ["SELECT", "select"]);
+        assert_eq!(
+            format!("{}", error),
+            [
+                " --> 1:1",
+                "  |",
+                "1 | SELCT x",
+                "  | ^---",
+                "  |",
+                "  = expected 1 or 2, found `SELCT`; did you mean `SELECT` or `select`?"
+            ]
+            .join("\n")
+        );
+
+        let missing = Error::<u32>::new_from_pos(
+            ErrorVariant::ParsingError {
+                positives: vec![1],
+                negatives: vec![],
+            },
+            Position::new(input, 5).unwrap(),
+        )
+        .with_suggestions(input, |_| Some("SELECT"));
+        assert!(missing.suggestions().is_empty());
+    }
+
+    #[test]
+    fn edit_distance() {
+        assert_eq!(super::edit_distance("", "abc"), 3);
+        assert_eq!(super::edit_distance("return", "retun"), 1);
+        assert_eq!(super::edit_distance("kitten", "sitting"), 3);
+        assert_eq!(super::edit_distance("été", "ete"), 2);
+    }
+
+    #[test]
+    fn clamp_to() {
+        let input = "ab\ncd\nef";
+        let error: Error<u32> = Error::new_from_span(
+            ErrorVariant::CustomError {
+                message: "error".to_owned(),
+            },
+            Span::new(input, 1, 5).unwrap(),
+        )
+        .with_path("file")
+        .with_label(Span::new(input, 0, 4).unwrap(), "overlapping")
+        .with_label(Span::new(input, 6, 8).unwrap(), "outside");
+
+        let clamped = error.clone().clamp_to(Span::new(input, 3, 6).unwrap());
+        assert_eq!(clamped.location, InputLocation::Span((3, 5)));
+        assert_eq!(clamped.line_col, LineColLocation::Span((2, 1), (2, 3)));
+        assert_eq!(clamped.path(), Some("file"));
+        assert_eq!(clamped.labels().len(), 1);
+        assert_eq!(clamped.labels()[0].location(), (3, 4));
+        assert_eq!(clamped.labels()[0].line_col(), (2, 1));
+
+        let clamped = error.clamp_to(Span::new(input, 6, 8).unwrap());
+        assert_eq!(clamped.location, InputLocation::Pos(6));
+        assert_eq!(clamped.labels().len(), 1);
+        assert_eq!(clamped.labels()[0].message(), "outside");
+    }
+
+    #[test]
+    fn unclosed_delimiter() {
+        let input = "{ /* ( */ [a)] ( b '(' }";
+        let delimiters = [("(", ")"), ("[", "]"), ("{", "}"), ("/*", "*/"), ("'", "'")];
+        let error = |pos| -> Error<u32> {
+            Error::new_from_pos(
+                ErrorVariant::CustomError {
+                    message: "error".to_owned(),
+                },
+                Position::new(input, pos).unwrap(),
+            )
+        };
+
+        let labelled = error(19).with_unclosed_delimiter(input, &delimiters);
+        assert_eq!(labelled.labels()[0].location(), (15, 16));
+        assert_eq!(
+            format!("{}", labelled),
+            [
+                " --> 1:20",
+                "  |",
+                "1 | { /* ( */ [a)] ( b '(' }",
+                "  |                    ^---",
+                "  |",
+                " ::: 1:16",
+                "  |",
+                "1 | { /* ( */ [a)] ( b '(' }",
+                "  |                ^ unclosed delimiter opened here",
+                "  |",
+                "  = error"
+            ]
+            .join("\n")
+        );
+
+        let relabelled = labelled.with_unclosed_delimiter(input, &delimiters);
+        assert_eq!(relabelled.labels().len(), 1);
+
+        let closed = error(15).with_unclosed_delimiter(input, &[("(", ")")]);
+        assert!(closed.labels().is_empty());
+    }
+
+    #[test]
+    fn renamed_expected() {
+        #[allow(clippy::upper_case_acronyms, non_camel_case_types)]
+        #[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+        enum Rule {
+            EOI,
+            number,
+            keyword,
+        }
+
+        let head = ErrorHead::new(
+            ErrorVariant::ParsingError {
+                positives: vec![Rule::EOI, Rule::number],
+                negatives: vec![Rule::keyword],
+            },
+            InputLocation::Pos(0),
+        )
+        .with_literals(vec![
+            ("select".to_owned(), true),
+            ("\n".to_owned(), false),
+            ("(".to_owned(), false),
+        ]);
+
+        let error = head.into_error("x").renamed_expected(|rule| match *rule {
+            Rule::number => "a number".to_owned(),
+            rule => format!("{:?}", rule),
+        });
+        assert_eq!(
+            error.variant.message(),
+            "unexpected keyword; expected one of: `\\n`, `(`, `select` (case-insensitive), a \
+             number, end of input"
+        );
+
+        let error: Error<Rule> = Error::new_from_pos(
+            ErrorVariant::ParsingError {
+                positives: vec![Rule::EOI],
+                negatives: vec![],
+            },
+            Position::from_start(""),
+        );
+        assert_eq!(
+            error.renamed_expected(|_| unreachable!()).variant.message(),
+            "expected end of input"
+        );
+    }
+
+    #[cfg(feature = "std")]
+    fn strip_colors(colored: &str) -> String {
+        let mut stripped = String::new();
+        let mut rest = colored;
+        while let Some(start) = rest.find('\x1b') {
+            stripped.push_str(&rest[..start]);
+            let end = rest[start..].find('m').unwrap();
+            rest = &rest[start + end + 1..];
+        }
+        stripped.push_str(rest);
+        stripped
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn colored() {
+        let input = "a\t価格 = x;\nb";
+        let start = Position::new(input, 2).unwrap();
+        let end = Position::new(input, 8).unwrap();
+        let error: Error<u32> = Error::new_from_span(
+            ErrorVariant::CustomError {
+                message: "unknown name".to_owned(),
+            },
+            start.span(&end),
+        )
+        .with_label(Span::new(input, 14, 15).unwrap(), "used here")
+        .with_note("names are case-sensitive")
+        .with_snippet_width(20);
+
+        let colored = error.to_colored_string();
+        assert_eq!(
+            strip_colors(&colored),
+            [
+                " --> 1:3",
+                "  |",
+                "1 | a\t価格 = x;",
+                "  |  \t^--^",
+                "  |",
+                " ::: 2:1",
+                "  |",
+                "2 | b",
+                "  | ^ used here",
+                "  |",
+                "  = unknown name",
+                "  = note: names are case-sensitive",
+            ]
+            .join("\n")
+        );
+        assert!(colored.contains("\x1b[1;31m \t^--^\x1b[0m"));
+        assert!(colored.contains("\x1b[1;34m^ used here\x1b[0m"));
+        assert!(colored.contains("\x1b[1;34m2\x1b[0m\x1b[1;34m |\x1b[0m b"));
+        // The error itself still counts columns in `char`s.
+        assert_eq!(error.line_col, LineColLocation::Span((1, 3), (1, 5)));
+    }
 }
@@ -0,0 +1,428 @@
+// pest. The Elegant Parser
+// Copyright (c) 2018 Dragoș Tiselice
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Explanations of parsing errors.
+//!
+//! The `positives` and `negatives` of an [`Error`] only list the rules attempted at the furthest
+//! position the parser reached. In explain mode, enabled for a parse with
+//! [`ParseOptions::with_explain`] or [`state_explained`], the parser also records every rule
+//! attempt, so that failed parses can be explained with a tree of the rules which were tried, how
+//! far each of them got and why they failed.
+//!
+//! Recording every attempt is slow, so explain mode is mostly meant for parsing a failed input a
+//! second time in order to show the explanation.
+//!
+//! [`Error`]: ../error/struct.Error.html
+//! [`ParseOptions::with_explain`]: ../struct.ParseOptions.html#method.with_explain
+//! [`state_explained`]: ../fn.state_explained.html
+
+use crate::RuleType;
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt;
+use core::mem::{self, size_of};
+
+/// The explanation of a parsing error, as a tree of the rule attempts leading to the position of
+/// the error.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct Explanation<R> {
+    /// The byte position of the error
+    pub pos: usize,
+    /// The top-level rule attempts
+    pub attempts: Vec<Attempt<R>>,
+}
+
+/// An attempt to match a rule.
+///
+/// Only the attempts which failed after reaching the position of the error, the attempts which
+/// matched but failed to go on where the parse got furthest, and the attempts containing them
+/// are kept in an [`Explanation`].
+///
+/// [`Explanation`]: struct.Explanation.html
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct Attempt<R> {
+    /// The attempted rule
+    pub rule: R,
+    /// The byte position where the attempt started
+    pub start: usize,
+    /// The furthest byte position the attempt reached
+    pub reached: usize,
+    /// The outcome of the attempt
+    pub outcome: Outcome,
+    /// The terminals, like strings or ranges, which failed to match at `reached`
+    pub expected: Vec<String>,
+    /// The terminals which matched at `reached` inside a negative predicate
+    pub unexpected: Vec<String>,
+    /// The attempts made by the rule
+    pub children: Vec<Attempt<R>>,
+}
+
+/// The outcome of an [`Attempt`].
+///
+/// [`Attempt`]: struct.Attempt.html
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum Outcome {
+    /// The rule matched
+    Matched,
+    /// The rule failed to match
+    Failed,
+    /// The rule matched inside a negative predicate, making the predicate fail
+    Forbidden,
+}
+
+impl<R> Explanation<R> {
+    pub(crate) fn map_rules<T, F>(self, f: &mut F) -> Explanation<T>
+    where
+        F: FnMut(R) -> T,
+    {
+        Explanation {
+            pos: self.pos,
+            attempts: self
+                .attempts
+                .into_iter()
+                .map(|attempt| attempt.map_rules(f))
+                .collect(),
+        }
+    }
+
+    pub(crate) fn rebase(mut self, offset: usize) -> Explanation<R> {
+        self.pos += offset;
+        for attempt in &mut self.attempts {
+            attempt.rebase(offset);
+        }
+        self
+    }
+}
+
+impl<R> Attempt<R> {
+    fn map_rules<T, F>(self, f: &mut F) -> Attempt<T>
+    where
+        F: FnMut(R) -> T,
+    {
+        Attempt {
+            rule: f(self.rule),
+            start: self.start,
+            reached: self.reached,
+            outcome: self.outcome,
+            expected: self.expected,
+            unexpected: self.unexpected,
+            children: self
+                .children
+                .into_iter()
+                .map(|child| child.map_rules(f))
+                .collect(),
+        }
+    }
+
+    fn rebase(&mut self, offset: usize) {
+        self.start += offset;
+        self.reached += offset;
+        for child in &mut self.children {
+            child.rebase(offset);
+        }
+    }
+
+    fn fmt_indented(&self, f: &mut fmt::Formatter<'_>, depth: usize) -> fmt::Result
+    where
+        R: fmt::Debug,
+    {
+        write!(
+            f,
+            "{:indent$}{:?} at {}, reached {}: ",
+            "",
+            self.rule,
+            self.start,
+            self.reached,
+            indent = depth * 2
+        )?;
+        match self.outcome {
+            Outcome::Matched => write!(f, "matched")?,
+            Outcome::Failed => write!(f, "failed")?,
+            Outcome::Forbidden => write!(f, "matched, but a negative predicate forbids it")?,
+        }
+        if !self.expected.is_empty() {
+            write!(f, ", expected {}", self.expected.join(" or "))?;
+        }
+        if !self.unexpected.is_empty() {
+            write!(f, ", unexpected {}", self.unexpected.join(" or "))?;
+        }
+        writeln!(f)?;
+
+        for child in &self.children {
+            child.fmt_indented(f, depth + 1)?;
+        }
+        Ok(())
+    }
+}
+
+impl<R: fmt::Debug> fmt::Display for Explanation<R> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for attempt in &self.attempts {
+            attempt.fmt_indented(f, 0)?;
+        }
+        Ok(())
+    }
+}
+
+/// Records the rule attempts of a parse in explain mode.
+#[derive(Debug)]
+pub(crate) struct Recorder<R> {
+    // All attempts, in the order they were started.
+    nodes: Vec<Node<R>>,
+    current: Option<usize>,
+}
+
+#[derive(Debug)]
+struct Node<R> {
+    attempt: Attempt<R>,
+    parent: Option<usize>,
+    // Whether the attempt was made inside a negative predicate.
+    negated: bool,
+}
+
+impl<R: RuleType> Recorder<R> {
+    pub(crate) fn new() -> Recorder<R> {
+        Recorder {
+            nodes: Vec::new(),
+            current: None,
+        }
+    }
+
+    pub(crate) fn heap_size(&self) -> usize {
+        self.nodes.capacity() * size_of::<Node<R>>()
+    }
+
+    pub(crate) fn enter(&mut self, rule: R, pos: usize, negated: bool) {
+        self.nodes.push(Node {
+            attempt: Attempt {
+                rule,
+                start: pos,
+                reached: pos,
+                outcome: Outcome::Failed,
+                expected: vec![],
+                unexpected: vec![],
+                children: vec![],
+            },
+            parent: self.current,
+            negated,
+        });
+        self.current = Some(self.nodes.len() - 1);
+    }
+
+    pub(crate) fn exit(&mut self, matched: bool, pos: usize) {
+        let index = match self.current {
+            Some(index) => index,
+            None => return,
+        };
+
+        let node = &mut self.nodes[index];
+        node.attempt.outcome = match (matched, node.negated) {
+            (true, false) => Outcome::Matched,
+            (true, true) => Outcome::Forbidden,
+            (false, _) => Outcome::Failed,
+        };
+        if matched {
+            reach(&mut node.attempt, pos);
+        }
+
+        let (negated, reached) = (node.negated, node.attempt.reached);
+        self.current = node.parent;
+        if !negated {
+            self.reach(reached);
+        }
+    }
+
+    /// Records that the current attempt reached `pos`.
+    pub(crate) fn reach(&mut self, pos: usize) {
+        if let Some(index) = self.current {
+            reach(&mut self.nodes[index].attempt, pos);
+        }
+    }
+
+    /// Records a terminal which failed, or matched unexpectedly if not `expected`, at `pos`.
+    pub(crate) fn terminal(&mut self, pos: usize, description: String, expected: bool) {
+        if let Some(index) = self.current {
+            let node = &mut self.nodes[index];
+            // Terminals matching inside negated rules are part of the rules' own matches.
+            if node.negated && !expected {
+                return;
+            }
+
+            let attempt = &mut node.attempt;
+            reach(attempt, pos);
+            if attempt.reached == pos {
+                let terminals = if expected {
+                    &mut attempt.expected
+                } else {
+                    &mut attempt.unexpected
+                };
+                if !terminals.contains(&description) {
+                    terminals.push(description);
+                }
+            }
+        }
+    }
+
+    /// Builds the explanation of an error at `pos`.
+    pub(crate) fn finish(self, pos: usize) -> Explanation<R> {
+        let furthest = self
+            .nodes
+            .iter()
+            .filter(|node| !node.negated)
+            .map(|node| node.attempt.reached)
+            .max()
+            .unwrap_or(pos);
+
+        // Children always come after their parents, so visiting the nodes backwards propagates
+        // whether they are kept up the tree.
+        let mut kept = vec![false; self.nodes.len()];
+        for (index, node) in self.nodes.iter().enumerate().rev() {
+            let attempt = &node.attempt;
+            let failed = match attempt.outcome {
+                Outcome::Failed => !node.negated && attempt.reached >= pos,
+                Outcome::Forbidden => attempt.start >= pos,
+                // Matched rules which failed to go on are only kept where the parse got furthest.
+                Outcome::Matched => attempt.reached >= furthest && !attempt.expected.is_empty(),
+            };
+            if failed {
+                kept[index] = true;
+            }
+            if let (true, Some(parent)) = (kept[index], node.parent) {
+                kept[parent] = true;
+            }
+        }
+
+        let mut children: Vec<Vec<Attempt<R>>> = self.nodes.iter().map(|_| vec![]).collect();
+        let mut attempts = vec![];
+        for (index, node) in self.nodes.into_iter().enumerate().rev() {
+            if !kept[index] {
+                continue;
+            }
+
+            let mut attempt = node.attempt;
+            attempt.children = mem::take(&mut children[index]);
+            attempt.children.reverse();
+            match node.parent {
+                Some(parent) => children[parent].push(attempt),
+                None => attempts.push(attempt),
+            }
+        }
+        attempts.reverse();
+
+        Explanation { pos, attempts }
+    }
+}
+
+fn reach<R>(attempt: &mut Attempt<R>, pos: usize) {
+    if pos > attempt.reached {
+        attempt.reached = pos;
+        attempt.expected.clear();
+        attempt.unexpected.clear();
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::almost_complete_range)]
+mod tests {
+    use super::*;
+    use crate::{state, state_explained};
+    use alloc::format;
+
+    #[allow(non_camel_case_types)]
+    #[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+    enum Rule {
+        stmt,
+        ident,
+        keyword,
+        expr,
+        num,
+    }
+
+    // stmt = { ident ~ "=" ~ expr ~ ";" }
+    // ident = @{ !keyword ~ 'a'..'z'+ }
+    // keyword = { "let" }
+    // expr = { num ~ ("+" ~ num)* }
+    // num = @{ '0'..'9'+ }
+    fn explain(input: &str) -> String {
+        let error = state_explained(input, |state| {
+            state.rule(Rule::stmt, |s| {
+                s.sequence(|s| {
+                    s.rule(Rule::ident, |s| {
+                        s.sequence(|s| {
+                            s.lookahead(false, |s| s.rule(Rule::keyword, |s| s.match_string("let")))
+                                .and_then(|s| s.match_range('a'..'z'))
+                                .and_then(|s| s.repeat(|s| s.match_range('a'..'z')))
+                        })
+                    })
+                    .and_then(|s| s.match_string("="))
+                    .and_then(|s| {
+                        s.rule(Rule::expr, |s| {
+                            s.sequence(|s| {
+                                s.rule(Rule::num, |s| s.match_range('0'..'9'))
+                                    .and_then(|s| {
+                                        s.repeat(|s| {
+                                            s.sequence(|s| {
+                                                s.match_string("+").and_then(|s| {
+                                                    s.rule(Rule::num, |s| s.match_range('0'..'9'))
+                                                })
+                                            })
+                                        })
+                                    })
+                            })
+                        })
+                    })
+                    .and_then(|s| s.match_string(";"))
+                })
+            })
+        })
+        .unwrap_err();
+
+        format!("{}", error.explanation().unwrap())
+    }
+
+    #[test]
+    fn failed() {
+        assert_eq!(
+            explain("x=1+;"),
+            "stmt at 0, reached 4: failed\n  \
+               expr at 2, reached 4: matched\n    \
+                 num at 4, reached 4: failed, expected '0'..'9'\n"
+        );
+    }
+
+    #[test]
+    fn expected() {
+        assert_eq!(
+            explain("x=1-"),
+            "stmt at 0, reached 3: failed, expected \";\"\n  \
+               expr at 2, reached 3: matched, expected \"+\"\n"
+        );
+    }
+
+    #[test]
+    fn forbidden() {
+        assert_eq!(
+            explain("let=1;"),
+            "stmt at 0, reached 0: failed\n  \
+               ident at 0, reached 0: failed\n    \
+                 keyword at 0, reached 3: matched, but a negative predicate forbids it\n"
+        );
+    }
+
+    #[test]
+    fn disabled() {
+        let error = state::<Rule, _>("x", |state| {
+            state.rule(Rule::num, |s| s.match_range('0'..'9'))
+        })
+        .unwrap_err();
+
+        assert_eq!(error.explanation(), None);
+    }
+}
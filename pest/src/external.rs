@@ -0,0 +1,119 @@
+// pest. The Elegant Parser
+// Copyright (c) 2018 Dragoș Tiselice
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! The parsers bound to `@extern` rules by `Parser::bind`, per thread.
+
+use alloc::boxed::Box;
+use alloc::rc::Rc;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::any::{Any, TypeId};
+use core::cell::RefCell;
+
+use crate::parser_state::{ParseResult, ParserState};
+use crate::RuleType;
+
+pub(crate) type ExternFn<R> =
+    Rc<dyn for<'i> Fn(Box<ParserState<'i, R>>) -> ParseResult<Box<ParserState<'i, R>>>>;
+
+/// An `ExternFn` which can be shared between threads, e.g. by a `ParserHandle`.
+pub(crate) type SharedExternFn<R> = Arc<
+    dyn for<'i> Fn(Box<ParserState<'i, R>>) -> ParseResult<Box<ParserState<'i, R>>> + Send + Sync,
+>;
+
+std::thread_local! {
+    // Every value is a `Vec<(R, ExternFn<R>)>` for the `R` of its `TypeId`.
+    static BINDINGS: RefCell<Vec<(TypeId, Box<dyn Any>)>> = RefCell::new(Vec::new());
+}
+
+pub(crate) fn warm_up() {
+    BINDINGS.with(|_| ());
+}
+
+pub(crate) fn bind<R: RuleType + 'static>(rule: R, f: ExternFn<R>) {
+    BINDINGS.with(|bindings| {
+        let mut bindings = bindings.borrow_mut();
+        let index = match bindings.iter().position(|(id, _)| *id == TypeId::of::<R>()) {
+            Some(index) => index,
+            None => {
+                let rules: Vec<(R, ExternFn<R>)> = Vec::new();
+                bindings.push((TypeId::of::<R>(), Box::new(rules)));
+                bindings.len() - 1
+            }
+        };
+
+        let rules = bindings[index]
+            .1
+            .downcast_mut::<Vec<(R, ExternFn<R>)>>()
+            .unwrap();
+        rules.retain(|(bound, _)| *bound != rule);
+        rules.push((rule, f));
+    });
+}
+
+fn unbind<R: RuleType + 'static>(rule: R) {
+    BINDINGS.with(|bindings| {
+        if let Some(rules) = bindings
+            .borrow_mut()
+            .iter_mut()
+            .find(|(id, _)| *id == TypeId::of::<R>())
+            .and_then(|(_, rules)| rules.downcast_mut::<Vec<(R, ExternFn<R>)>>())
+        {
+            rules.retain(|(bound, _)| *bound != rule);
+        }
+    });
+}
+
+/// Binds the rules of `bindings` while `f` runs on the current thread, restoring the previous
+/// bindings afterwards, even if `f` panics.
+pub(crate) fn with_bindings<R, T, F>(bindings: &[(R, SharedExternFn<R>)], f: F) -> T
+where
+    R: RuleType + 'static,
+    F: FnOnce() -> T,
+{
+    struct Restore<R: RuleType + 'static> {
+        previous: Vec<(R, Option<ExternFn<R>>)>,
+    }
+
+    impl<R: RuleType + 'static> Drop for Restore<R> {
+        fn drop(&mut self) {
+            for (rule, previous) in self.previous.drain(..).rev() {
+                match previous {
+                    Some(f) => bind(rule, f),
+                    None => unbind(rule),
+                }
+            }
+        }
+    }
+
+    let mut restore = Restore {
+        previous: Vec::with_capacity(bindings.len()),
+    };
+    for (rule, shared) in bindings {
+        restore.previous.push((*rule, lookup(*rule)));
+
+        let shared = Arc::clone(shared);
+        let f: ExternFn<R> = Rc::new(move |state| shared(state));
+        bind(*rule, f);
+    }
+
+    f()
+}
+
+pub(crate) fn lookup<R: RuleType + 'static>(rule: R) -> Option<ExternFn<R>> {
+    BINDINGS.with(|bindings| {
+        bindings
+            .borrow()
+            .iter()
+            .find(|(id, _)| *id == TypeId::of::<R>())
+            .and_then(|(_, rules)| rules.downcast_ref::<Vec<(R, ExternFn<R>)>>())
+            .and_then(|rules| rules.iter().find(|(bound, _)| *bound == rule))
+            .map(|(_, f)| Rc::clone(f))
+    })
+}
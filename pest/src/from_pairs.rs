@@ -0,0 +1,271 @@
+// pest. The Elegant Parser
+// Copyright (c) 2018 Dragoș Tiselice
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Building typed syntax trees from `Pairs`.
+//!
+//! [`FromPairs`] is implemented by the types of a syntax tree, usually with the `FromPest` derive
+//! of `pest_derive`, and by the generic containers composing them: `Option<T>`, `Vec<T>`,
+//! `Box<T>` and [`Spanned<T>`], which also keeps the span of the pairs `T` was built from.
+//!
+//! The functions of this module are the building blocks of the derive, and help with implementing
+//! [`FromPairs`] by hand as well.
+//!
+//! [`FromPairs`]: trait.FromPairs.html
+//! [`Spanned<T>`]: ../struct.Spanned.html
+
+use alloc::boxed::Box;
+use alloc::string::ToString;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt;
+use core::str::FromStr;
+
+use crate::error::{Error, ErrorVariant, InputLocation};
+use crate::iterators::{pairs, Pair, Pairs};
+use crate::position::Position;
+use crate::spanned::Spanned;
+use crate::RuleType;
+
+/// A type which can be built from the next pairs of a `Pairs` iterator.
+///
+/// # Examples
+///
+/// ```
+/// # use pest::{FromPairs, Parser, iterators::Pairs, error::Error};
+/// # #[allow(non_camel_case_types)]
+/// # #[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+/// # enum Rule { number }
+/// # struct NumberParser;
+/// # impl Parser<Rule> for NumberParser {
+/// #     fn parse(_: Rule, input: &str) -> Result<Pairs<'_, Rule>, Error<Rule>> {
+/// #         pest::state(input, |s| {
+/// #             s.repeat(|s| {
+/// #                 s.rule(Rule::number, |s| {
+/// #                     s.match_char_by(|c| c.is_ascii_digit())
+/// #                         .and_then(|s| s.repeat(|s| s.match_char_by(|c| c.is_ascii_digit())))
+/// #                 })
+/// #                 .and_then(|s| s.optional(|s| s.match_string(",")))
+/// #             })
+/// #         })
+/// #     }
+/// # }
+/// use pest::from_pairs;
+///
+/// struct Number(u32);
+///
+/// impl<'i> FromPairs<'i, Rule> for Number {
+///     fn from_pairs(pairs: &mut Pairs<'i, Rule>) -> Result<Number, Error<Rule>> {
+///         from_pairs::attempt(pairs, |pairs| {
+///             let pair = from_pairs::next_pair(pairs, Rule::number)?;
+///             Ok(Number(from_pairs::parse(&pair)?))
+///         })
+///     }
+/// }
+///
+/// let mut pairs = NumberParser::parse(Rule::number, "1,22,333").unwrap();
+/// let numbers = Vec::<Number>::from_pairs(&mut pairs).unwrap();
+///
+/// assert_eq!(numbers.iter().map(|number| number.0).sum::<u32>(), 356);
+/// ```
+pub trait FromPairs<'i, R: RuleType>: Sized {
+    /// Builds a value from the next pairs of `pairs`, consuming them. On failure, `pairs` should be
+    /// left as they were, so that alternatives can be tried, which [`attempt`] takes care of.
+    ///
+    /// [`attempt`]: from_pairs/fn.attempt.html
+    #[allow(clippy::perf)]
+    fn from_pairs(pairs: &mut Pairs<'i, R>) -> Result<Self, Error<R>>;
+}
+
+/// The next pair, whatever its rule.
+impl<'i, R: RuleType> FromPairs<'i, R> for Pair<'i, R> {
+    #[allow(clippy::perf)]
+    fn from_pairs(pairs: &mut Pairs<'i, R>) -> Result<Self, Error<R>> {
+        match pairs.next() {
+            Some(pair) => Ok(pair),
+            None => Err(error_at(
+                pairs,
+                ErrorVariant::ParsingError {
+                    positives: vec![],
+                    negatives: vec![],
+                },
+            )),
+        }
+    }
+}
+
+/// `None` if the next pairs are not those of a `T`, leaving them as they were, i.e. if building
+/// `T` fails with a parsing error at the next pair. Other errors are returned.
+impl<'i, R: RuleType, T: FromPairs<'i, R>> FromPairs<'i, R> for Option<T> {
+    #[allow(clippy::perf)]
+    fn from_pairs(pairs: &mut Pairs<'i, R>) -> Result<Self, Error<R>> {
+        match attempt(pairs, T::from_pairs) {
+            Ok(value) => Ok(Some(value)),
+            Err(error) if is_absent(pairs, &error) => Ok(None),
+            Err(error) => Err(error),
+        }
+    }
+}
+
+/// As many `T` as the next pairs are those of, like `Option<T>`, stopping early if one of them
+/// consumes no pairs.
+impl<'i, R: RuleType, T: FromPairs<'i, R>> FromPairs<'i, R> for Vec<T> {
+    #[allow(clippy::perf)]
+    fn from_pairs(pairs: &mut Pairs<'i, R>) -> Result<Self, Error<R>> {
+        let mut values = Vec::new();
+
+        loop {
+            let cursor = pairs::cursor(pairs);
+            match attempt(pairs, T::from_pairs) {
+                Ok(value) => {
+                    values.push(value);
+                    if pairs::cursor(pairs) == cursor {
+                        return Ok(values);
+                    }
+                }
+                Err(error) if is_absent(pairs, &error) => return Ok(values),
+                Err(error) => return Err(error),
+            }
+        }
+    }
+}
+
+impl<'i, R: RuleType, T: FromPairs<'i, R>> FromPairs<'i, R> for Box<T> {
+    #[allow(clippy::perf)]
+    fn from_pairs(pairs: &mut Pairs<'i, R>) -> Result<Self, Error<R>> {
+        T::from_pairs(pairs).map(Box::new)
+    }
+}
+
+/// `T` along with the span of the pairs it was built from.
+impl<'i, R: RuleType, T: FromPairs<'i, R>> FromPairs<'i, R> for Spanned<'i, T> {
+    #[allow(clippy::perf)]
+    fn from_pairs(pairs: &mut Pairs<'i, R>) -> Result<Self, Error<R>> {
+        let before = pairs.clone();
+        let value = T::from_pairs(pairs)?;
+        Ok(Spanned::new(value, pairs::consumed_span(&before, pairs)))
+    }
+}
+
+/// Runs `f` on `pairs`, leaving them as they were if it fails.
+#[allow(clippy::perf)]
+pub fn attempt<'i, R: RuleType, T, F>(pairs: &mut Pairs<'i, R>, f: F) -> Result<T, Error<R>>
+where
+    F: FnOnce(&mut Pairs<'i, R>) -> Result<T, Error<R>>,
+{
+    let mut attempted = pairs.clone();
+    let value = f(&mut attempted)?;
+    *pairs = attempted;
+    Ok(value)
+}
+
+/// Returns the next pair if it is a pair of `rule`, or an error expecting `rule` otherwise, in
+/// which case `pairs` are left as they were.
+#[allow(clippy::perf)]
+pub fn next_pair<'i, R: RuleType>(
+    pairs: &mut Pairs<'i, R>,
+    rule: R,
+) -> Result<Pair<'i, R>, Error<R>> {
+    match pairs.peek() {
+        Some(ref pair) if pair.as_rule() == rule => Ok(pairs.next().unwrap()),
+        _ => Err(error_at(
+            pairs,
+            ErrorVariant::ParsingError {
+                positives: vec![rule],
+                negatives: vec![],
+            },
+        )),
+    }
+}
+
+/// Returns an error if a pair is left in `pairs`, which is not expected by the value built from
+/// them.
+#[allow(clippy::perf)]
+pub fn finish<R: RuleType>(pairs: &Pairs<'_, R>) -> Result<(), Error<R>> {
+    match pairs.peek() {
+        Some(pair) => Err(error_at(
+            pairs,
+            ErrorVariant::ParsingError {
+                positives: vec![],
+                negatives: vec![pair.as_rule()],
+            },
+        )),
+        None => Ok(()),
+    }
+}
+
+/// Parses the text of `pair` with `FromStr`, turning a failure into an error pointing at `pair`.
+#[allow(clippy::perf)]
+pub fn parse<T, R>(pair: &Pair<'_, R>) -> Result<T, Error<R>>
+where
+    T: FromStr,
+    T::Err: fmt::Display,
+    R: RuleType,
+{
+    pair.as_str().parse().map_err(|error: T::Err| {
+        Error::new_from_span(
+            ErrorVariant::CustomError {
+                message: error.to_string(),
+            },
+            pair.as_span(),
+        )
+    })
+}
+
+/// Merges the errors of two alternatives, keeping the furthest one, or both of their expected
+/// and unexpected rules if they are parsing errors at the same position.
+#[allow(clippy::perf)]
+pub fn merge<R: RuleType>(mut error: Error<R>, other: Error<R>) -> Error<R> {
+    let (pos, other_pos) = (start(&error), start(&other));
+    if other_pos > pos {
+        return other;
+    }
+    if other_pos < pos {
+        return error;
+    }
+
+    if let (
+        ErrorVariant::ParsingError {
+            positives,
+            negatives,
+        },
+        ErrorVariant::ParsingError {
+            positives: other_positives,
+            negatives: other_negatives,
+        },
+    ) = (&mut error.variant, other.variant)
+    {
+        positives.extend(other_positives);
+        positives.sort();
+        positives.dedup();
+        negatives.extend(other_negatives);
+        negatives.sort();
+        negatives.dedup();
+    }
+
+    error
+}
+
+// Whether `error` only tells that the next pairs of `pairs` are not those of the value built.
+fn is_absent<R: RuleType>(pairs: &Pairs<'_, R>, error: &Error<R>) -> bool {
+    matches!(error.variant, ErrorVariant::ParsingError { .. })
+        && start(error) == pairs::next_pos(pairs)
+}
+
+fn start<R>(error: &Error<R>) -> usize {
+    match error.location {
+        InputLocation::Pos(pos) => pos,
+        InputLocation::Span((start, _)) => start,
+    }
+}
+
+fn error_at<R: RuleType>(pairs: &Pairs<'_, R>, variant: ErrorVariant<R>) -> Error<R> {
+    // Positions come from the parse of the input.
+    let pos = Position::new(pairs::input(pairs), pairs::next_pos(pairs)).unwrap();
+    Error::new_from_pos(variant, pos)
+}
@@ -0,0 +1,542 @@
+// pest. The Elegant Parser
+// Copyright (c) 2018 Dragoș Tiselice
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Building lossless syntax trees, such as the green trees of [rowan] or [cstree], from `Pairs`.
+//!
+//! Pairs with inner pairs become nodes, while the other ones become tokens. The input that is not
+//! covered by any pair, e.g. implicit whitespace and comments, is kept as trivia tokens, so that
+//! the text of the tree is exactly the text of the `Pairs`.
+//!
+//! [`GreenBuilder`] mirrors the builders of these libraries, so that plugging one in is a matter
+//! of forwarding its methods, e.g. for rowan:
+//!
+//! ```ignore
+//! struct Builder(rowan::GreenNodeBuilder<'static>);
+//!
+//! impl GreenBuilder<rowan::SyntaxKind> for Builder {
+//!     fn start_node(&mut self, kind: rowan::SyntaxKind) {
+//!         self.0.start_node(kind);
+//!     }
+//!
+//!     fn token(&mut self, kind: rowan::SyntaxKind, text: &str) {
+//!         self.0.token(kind, text);
+//!     }
+//!
+//!     fn finish_node(&mut self) {
+//!         self.0.finish_node();
+//!     }
+//! }
+//! ```
+//!
+//! This module also provides [`GreenPair`], an owned, interned tree which is cheap to compare and
+//! to hash, and which can be stored by incremental computation frameworks such as [salsa].
+//!
+//! [rowan]: https://docs.rs/rowan
+//! [cstree]: https://docs.rs/cstree
+//! [salsa]: https://docs.rs/salsa
+//! [`GreenBuilder`]: trait.GreenBuilder.html
+//! [`GreenPair`]: struct.GreenPair.html
+
+use alloc::collections::BTreeSet;
+use alloc::string::String;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::cmp::Ordering;
+use core::fmt;
+use core::hash::{Hash, Hasher};
+
+use crate::iterators::{pairs, Pair, Pairs};
+use crate::RuleType;
+
+/// A mapping from rules to the syntax kinds of a syntax tree.
+pub trait SyntaxKindMapping<R> {
+    /// The syntax kind type
+    type Kind: Copy;
+
+    /// Returns the kind of the nodes and tokens created for pairs of `rule`.
+    fn kind(&self, rule: R) -> Self::Kind;
+
+    /// Returns the kind of the tokens created for the input not covered by any pair.
+    fn trivia_kind(&self) -> Self::Kind;
+}
+
+/// A builder of syntax trees, called by [`build_green_tree`] in pre-order.
+///
+/// [`build_green_tree`]: fn.build_green_tree.html
+pub trait GreenBuilder<K> {
+    /// Starts a new node of `kind`, which becomes the parent of the following nodes and tokens.
+    fn start_node(&mut self, kind: K);
+
+    /// Adds a token of `kind` with the text `text` to the current node.
+    fn token(&mut self, kind: K, text: &str);
+
+    /// Finishes the current node.
+    fn finish_node(&mut self);
+}
+
+/// Feeds `pairs` to `builder`, mapping their rules with `mapping`. The text of the built tree goes
+/// from the start of the first pair to the end of the last one, so `builder` should usually be
+/// wrapped in a root node, started before and finished after the call.
+///
+/// # Examples
+///
+/// ```
+/// # use pest;
+/// # use pest::green::{build_green_tree, GreenBuilder, SyntaxKindMapping};
+/// # #[allow(non_camel_case_types)]
+/// # #[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+/// enum Rule {
+///     a,
+///     b
+/// }
+///
+/// struct Mapping;
+///
+/// impl SyntaxKindMapping<Rule> for Mapping {
+///     type Kind = &'static str;
+///
+///     fn kind(&self, rule: Rule) -> &'static str {
+///         match rule {
+///             Rule::a => "A",
+///             Rule::b => "B",
+///         }
+///     }
+///
+///     fn trivia_kind(&self) -> &'static str {
+///         "TRIVIA"
+///     }
+/// }
+///
+/// struct Printer(String);
+///
+/// impl GreenBuilder<&'static str> for Printer {
+///     fn start_node(&mut self, kind: &'static str) {
+///         self.0.push_str(kind);
+///         self.0.push('(');
+///     }
+///
+///     fn token(&mut self, kind: &'static str, text: &str) {
+///         self.0.push_str(&format!("{}{:?} ", kind, text));
+///     }
+///
+///     fn finish_node(&mut self) {
+///         self.0.push_str(") ");
+///     }
+/// }
+///
+/// let input = "a b";
+/// let pairs = pest::state(input, |state| {
+///     // generating Token pair with Rule::a containing Rule::b ...
+/// #     state.rule(Rule::a, |s| s.skip(2).and_then(|s| s.rule(Rule::b, |s| s.skip(1))))
+/// }).unwrap();
+///
+/// let mut printer = Printer(String::new());
+/// build_green_tree(&pairs, &Mapping, &mut printer);
+///
+/// assert_eq!(printer.0, "A(TRIVIA\"a \" B\"b\" ) ");
+/// ```
+pub fn build_green_tree<R, M, B>(pairs: &Pairs<'_, R>, mapping: &M, builder: &mut B)
+where
+    R: RuleType,
+    M: SyntaxKindMapping<R>,
+    B: GreenBuilder<M::Kind>,
+{
+    let input = pairs::input(pairs);
+    let mut tokens = pairs.raw_tokens().enumerate().peekable();
+    // The indices of the currently open nodes, along with their end positions.
+    let mut open: Vec<(usize, usize)> = Vec::new();
+    let mut pos = match tokens.peek() {
+        Some((_, token)) => token.start,
+        None => return,
+    };
+
+    let trivia = |builder: &mut B, start: usize, end: usize| {
+        if start < end {
+            builder.token(mapping.trivia_kind(), &input[start..end]);
+        }
+    };
+
+    while let Some((index, token)) = tokens.next() {
+        while open.last().map(|&(index, _)| index) != token.parent_index {
+            let (_, end) = open.pop().unwrap();
+            trivia(builder, pos, end);
+            builder.finish_node();
+            pos = end;
+        }
+
+        trivia(builder, pos, token.start);
+        pos = token.start;
+
+        let kind = mapping.kind(token.rule);
+
+        if matches!(tokens.peek(), Some((_, next)) if next.parent_index == Some(index)) {
+            builder.start_node(kind);
+            open.push((index, token.end));
+        } else {
+            builder.token(kind, &input[token.start..token.end]);
+            pos = token.end;
+        }
+    }
+
+    while let Some((_, end)) = open.pop() {
+        trivia(builder, pos, end);
+        builder.finish_node();
+        pos = end;
+    }
+}
+
+/// An owned, immutable pair which does not borrow the input, created by a [`GreenInterner`].
+///
+/// The positions of a `GreenPair` are not stored, only the lengths of its text, so that the same
+/// text matched by the same rules results in equal `GreenPair`s wherever it appears in the input.
+/// `GreenPair`s are reference counted, so they are cheap to clone, and interned ones are compared
+/// by pointer first, so that equality and ordering are cheap as well.
+///
+/// [`GreenInterner`]: struct.GreenInterner.html
+pub struct GreenPair<R: RuleType> {
+    data: Arc<GreenPairData<R>>,
+}
+
+#[derive(Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+struct GreenPairData<R: RuleType> {
+    rule: R,
+    text_len: usize,
+    children: Vec<GreenChild<R>>,
+}
+
+/// A child of a [`GreenPair`]: either an inner pair or the text in between inner pairs.
+///
+/// [`GreenPair`]: struct.GreenPair.html
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum GreenChild<R: RuleType> {
+    /// An inner pair
+    Pair(GreenPair<R>),
+    /// Text which is not covered by any inner pair
+    Text(Arc<str>),
+}
+
+impl<R: RuleType> GreenChild<R> {
+    /// Returns the length of the text of the child, in bytes.
+    pub fn text_len(&self) -> usize {
+        match self {
+            GreenChild::Pair(pair) => pair.text_len(),
+            GreenChild::Text(text) => text.len(),
+        }
+    }
+}
+
+impl<R: RuleType> GreenPair<R> {
+    /// Returns the rule of the `GreenPair`.
+    #[inline]
+    pub fn rule(&self) -> R {
+        self.data.rule
+    }
+
+    /// Returns the length of the text of the `GreenPair`, in bytes.
+    #[inline]
+    pub fn text_len(&self) -> usize {
+        self.data.text_len
+    }
+
+    /// Returns the children of the `GreenPair`, which cover its entire text.
+    #[inline]
+    pub fn children(&self) -> &[GreenChild<R>] {
+        &self.data.children
+    }
+
+    /// Returns an iterator over the inner pairs of the `GreenPair`.
+    pub fn inner(&self) -> impl Iterator<Item = &GreenPair<R>> {
+        self.data.children.iter().filter_map(|child| match child {
+            GreenChild::Pair(pair) => Some(pair),
+            GreenChild::Text(_) => None,
+        })
+    }
+
+    /// Returns the text of the `GreenPair`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use pest;
+    /// # use pest::green::GreenInterner;
+    /// # #[allow(non_camel_case_types)]
+    /// # #[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+    /// enum Rule {
+    ///     a,
+    ///     b
+    /// }
+    ///
+    /// let input = "a b";
+    /// let pair = pest::state(input, |state| {
+    ///     // generating Token pair with Rule::a containing Rule::b ...
+    /// #     state.rule(Rule::a, |s| s.skip(2).and_then(|s| s.rule(Rule::b, |s| s.skip(1))))
+    /// }).unwrap().next().unwrap();
+    ///
+    /// let green = GreenInterner::new().intern(pair);
+    ///
+    /// assert_eq!(green.text(), "a b");
+    /// assert_eq!(green.inner().next().unwrap().rule(), Rule::b);
+    /// ```
+    pub fn text(&self) -> String {
+        let mut text = String::with_capacity(self.text_len());
+        self.push_text(&mut text);
+        text
+    }
+
+    fn push_text(&self, text: &mut String) {
+        for child in self.children() {
+            match child {
+                GreenChild::Pair(pair) => pair.push_text(text),
+                GreenChild::Text(piece) => text.push_str(piece),
+            }
+        }
+    }
+}
+
+impl<R: RuleType> Clone for GreenPair<R> {
+    fn clone(&self) -> GreenPair<R> {
+        GreenPair {
+            data: Arc::clone(&self.data),
+        }
+    }
+}
+
+impl<R: RuleType> PartialEq for GreenPair<R> {
+    fn eq(&self, other: &GreenPair<R>) -> bool {
+        Arc::ptr_eq(&self.data, &other.data) || self.data == other.data
+    }
+}
+
+impl<R: RuleType> Eq for GreenPair<R> {}
+
+impl<R: RuleType> PartialOrd for GreenPair<R> {
+    fn partial_cmp(&self, other: &GreenPair<R>) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<R: RuleType> Ord for GreenPair<R> {
+    fn cmp(&self, other: &GreenPair<R>) -> Ordering {
+        if Arc::ptr_eq(&self.data, &other.data) {
+            Ordering::Equal
+        } else {
+            self.data.cmp(&other.data)
+        }
+    }
+}
+
+impl<R: RuleType> Hash for GreenPair<R> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.data.hash(state);
+    }
+}
+
+impl<R: RuleType> fmt::Debug for GreenPair<R> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("GreenPair")
+            .field("rule", &self.data.rule)
+            .field("text_len", &self.data.text_len)
+            .field("children", &self.data.children)
+            .finish()
+    }
+}
+
+/// An interner of [`GreenPair`]s, which makes identical pairs and texts share their allocations.
+/// Reusing a `GreenInterner` across parses, e.g. across revisions of a document, shares the
+/// unchanged parts of their trees.
+///
+/// [`GreenPair`]: struct.GreenPair.html
+#[derive(Debug)]
+pub struct GreenInterner<R: RuleType> {
+    pairs: BTreeSet<GreenPair<R>>,
+    texts: BTreeSet<Arc<str>>,
+}
+
+impl<R: RuleType> Default for GreenInterner<R> {
+    fn default() -> GreenInterner<R> {
+        GreenInterner {
+            pairs: BTreeSet::new(),
+            texts: BTreeSet::new(),
+        }
+    }
+}
+
+impl<R: RuleType> GreenInterner<R> {
+    /// Creates an empty `GreenInterner`.
+    pub fn new() -> GreenInterner<R> {
+        GreenInterner::default()
+    }
+
+    /// Returns the number of distinct pairs interned so far.
+    pub fn len(&self) -> usize {
+        self.pairs.len()
+    }
+
+    /// Returns `true` if no pairs were interned so far.
+    pub fn is_empty(&self) -> bool {
+        self.pairs.is_empty()
+    }
+
+    /// Interns `pair`, along with its inner pairs.
+    pub fn intern(&mut self, pair: Pair<'_, R>) -> GreenPair<R> {
+        let rule = pair.as_rule();
+        let span = pair.as_span();
+        let input = span.get_input();
+        let mut pos = span.start();
+        let mut children = Vec::new();
+
+        for inner in pair.into_inner() {
+            let start = inner.as_span().start();
+            let end = inner.as_span().end();
+
+            if pos < start {
+                children.push(GreenChild::Text(self.intern_text(&input[pos..start])));
+            }
+            children.push(GreenChild::Pair(self.intern(inner)));
+            pos = end;
+        }
+
+        if pos < span.end() {
+            children.push(GreenChild::Text(self.intern_text(&input[pos..span.end()])));
+        }
+
+        let pair = GreenPair {
+            data: Arc::new(GreenPairData {
+                rule,
+                text_len: span.end() - span.start(),
+                children,
+            }),
+        };
+
+        match self.pairs.get(&pair) {
+            Some(interned) => interned.clone(),
+            None => {
+                self.pairs.insert(pair.clone());
+                pair
+            }
+        }
+    }
+
+    /// Interns all `pairs`, along with their inner pairs.
+    pub fn intern_pairs(&mut self, pairs: Pairs<'_, R>) -> Vec<GreenPair<R>> {
+        pairs.map(|pair| self.intern(pair)).collect()
+    }
+
+    fn intern_text(&mut self, text: &str) -> Arc<str> {
+        match self.texts.get(text) {
+            Some(text) => Arc::clone(text),
+            None => {
+                let text: Arc<str> = Arc::from(text);
+                self.texts.insert(Arc::clone(&text));
+                text
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::macros::tests::{AbcParser, Rule};
+    use crate::Parser;
+    use alloc::format;
+    use alloc::string::String;
+
+    struct Mapping;
+
+    impl SyntaxKindMapping<Rule> for Mapping {
+        type Kind = Option<Rule>;
+
+        fn kind(&self, rule: Rule) -> Option<Rule> {
+            Some(rule)
+        }
+
+        fn trivia_kind(&self) -> Option<Rule> {
+            None
+        }
+    }
+
+    #[derive(Default)]
+    struct Printer(String, String);
+
+    impl GreenBuilder<Option<Rule>> for Printer {
+        fn start_node(&mut self, kind: Option<Rule>) {
+            self.0.push_str(&format!("{:?}(", kind));
+        }
+
+        fn token(&mut self, kind: Option<Rule>, text: &str) {
+            self.0.push_str(&format!("{:?}{:?} ", kind, text));
+            self.1.push_str(text);
+        }
+
+        fn finish_node(&mut self) {
+            self.0.push_str(") ");
+        }
+    }
+
+    #[test]
+    fn build() {
+        let pairs = AbcParser::parse(Rule::a, "abcde").unwrap();
+        let mut printer = Printer::default();
+        build_green_tree(&pairs, &Mapping, &mut printer);
+
+        assert_eq!(
+            printer.0,
+            "Some(a)(None\"a\" Some(b)\"b\" None\"c\" ) None\"d\" Some(c)\"e\" "
+        );
+        assert_eq!(printer.1, "abcde");
+    }
+
+    #[test]
+    fn intern() {
+        let mut interner = GreenInterner::new();
+        let first = interner.intern_pairs(AbcParser::parse(Rule::a, "abcde").unwrap());
+        let second = interner.intern_pairs(AbcParser::parse(Rule::a, "abcde").unwrap());
+
+        assert_eq!(first.len(), 2);
+        assert_eq!(interner.len(), 3);
+        assert!(Arc::ptr_eq(&first[0].data, &second[0].data));
+        assert_eq!(first[0].text(), "abc");
+        assert_eq!(first[0].text_len(), 3);
+        assert_eq!(first[0].children().len(), 3);
+        assert_eq!(first[0].inner().next().unwrap().rule(), Rule::b);
+        assert_eq!(first[1].text(), "e");
+        assert_ne!(first[0], first[1]);
+    }
+
+    #[test]
+    fn intern_positions() {
+        let pairs = AbcParser::parse(Rule::a, "abcde").unwrap();
+        let shifted = pairs::rebase(pairs.clone(), "xxabcde", 2);
+
+        let first = GreenInterner::new().intern_pairs(pairs);
+        let second = GreenInterner::new().intern_pairs(shifted);
+
+        assert_eq!(first, second);
+    }
+
+    #[test]
+    fn build_empty() {
+        let mut pairs = AbcParser::parse(Rule::a, "abcde").unwrap();
+        let mut printer = Printer::default();
+        build_green_tree(
+            &pairs
+                .next()
+                .unwrap()
+                .into_inner()
+                .next()
+                .unwrap()
+                .into_inner(),
+            &Mapping,
+            &mut printer,
+        );
+
+        assert!(printer.0.is_empty());
+    }
+}
@@ -0,0 +1,365 @@
+// pest. The Elegant Parser
+// Copyright (c) 2018 Dragoș Tiselice
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+use alloc::rc::Rc;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt;
+use core::marker::PhantomData;
+use core::ops::Range;
+
+use crate::error::Error;
+use crate::iterators::{pairs, Pairs, QueueableToken};
+use crate::parser::Parser;
+use crate::span::Span;
+use crate::RuleType;
+
+/// An edit of the input of an [`IncrementalParser`], replacing the bytes of `range` with
+/// `new_text`.
+///
+/// [`IncrementalParser`]: struct.IncrementalParser.html
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct TextEdit {
+    /// The byte range of the replaced input
+    pub range: Range<usize>,
+    /// The text replacing the range
+    pub new_text: String,
+}
+
+impl TextEdit {
+    /// Creates an edit replacing the bytes of `range` with `new_text`.
+    pub fn new(range: Range<usize>, new_text: String) -> TextEdit {
+        TextEdit { range, new_text }
+    }
+}
+
+/// A parser which keeps its input along with the tokens of its last parse, so that an edit of the
+/// input only re-parses the innermost pair around it whose rule was registered with
+/// [`with_reparse_rule`], splicing the new tokens into the cached ones.
+///
+/// A reparse rule is parsed from the start of its pair up to the end of the input, and its new
+/// pair is only kept if it ends right where the edited pair now ends. Otherwise, the next
+/// enclosing reparse rule is tried, and eventually the whole input is parsed again. Reparse rules
+/// should therefore not depend on what surrounds them, e.g. through `SOI`, the stack, or the
+/// atomicity of the rules calling them, and edits within them should not change how the input
+/// before them parses, which is usually the case of rules like statements or blocks.
+///
+/// [`with_reparse_rule`]: struct.IncrementalParser.html#method.with_reparse_rule
+///
+/// # Examples
+///
+/// ```
+/// # use pest::{IncrementalParser, Parser, TextEdit, iterators::Pairs, error::Error};
+/// # #[allow(non_camel_case_types)]
+/// # #[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+/// # enum Rule { list, item }
+/// # struct ListParser;
+/// # impl Parser<Rule> for ListParser {
+/// #     fn parse(rule: Rule, input: &str) -> Result<Pairs<'_, Rule>, Error<Rule>> {
+/// #         fn item(s: Box<pest::ParserState<'_, Rule>>) -> pest::ParseResult<Box<pest::ParserState<'_, Rule>>> {
+/// #             s.rule(Rule::item, |s| {
+/// #                 s.sequence(|s| {
+/// #                     s.match_string("[")
+/// #                         .and_then(|s| s.repeat(|s| s.match_range('a'..'z')))
+/// #                         .and_then(|s| s.match_string("]"))
+/// #                 })
+/// #             })
+/// #         }
+/// #         pest::state(input, |s| match rule {
+/// #             Rule::list => s.rule(Rule::list, |s| s.repeat(item).and_then(|s| s.end_of_input())),
+/// #             Rule::item => item(s),
+/// #         })
+/// #     }
+/// # }
+/// let mut parser = IncrementalParser::new(ListParser, Rule::list, "[ab][cd]".to_owned())
+///     .with_reparse_rule(Rule::item);
+///
+/// // Only the second item is parsed again.
+/// assert_eq!(parser.edit(TextEdit::new(5..6, "xyz".to_owned())), 4..10);
+/// assert_eq!(parser.input(), "[ab][xyzd]");
+/// assert_eq!(
+///     format!("{}", parser.pairs().unwrap()),
+///     "[list(0, 10, [item(0, 4), item(4, 10)])]"
+/// );
+/// ```
+pub struct IncrementalParser<P, R> {
+    rule: R,
+    input: String,
+    result: Result<Rc<Vec<QueueableToken<R>>>, Error<R>>,
+    reparse_rules: Vec<R>,
+    parser: PhantomData<fn() -> P>,
+}
+
+impl<P: Parser<R>, R: RuleType> IncrementalParser<P, R> {
+    /// Parses `input` from `rule` with `parser`.
+    pub fn new(parser: P, rule: R, input: String) -> IncrementalParser<P, R> {
+        let _ = parser;
+        let result = parse::<P, R>(rule, &input);
+        IncrementalParser {
+            rule,
+            input,
+            result,
+            reparse_rules: Vec::new(),
+            parser: PhantomData,
+        }
+    }
+
+    /// Registers `rule` as a rule whose pairs are parsed again on their own when they enclose an
+    /// edit.
+    pub fn with_reparse_rule(mut self, rule: R) -> IncrementalParser<P, R> {
+        if !self.reparse_rules.contains(&rule) {
+            self.reparse_rules.push(rule);
+        }
+        self
+    }
+
+    /// Returns the current input.
+    pub fn input(&self) -> &str {
+        &self.input
+    }
+
+    /// Returns the pairs of the current input, or the error of its last parse.
+    #[allow(clippy::perf)]
+    pub fn pairs(&self) -> Result<Pairs<'_, R>, &Error<R>> {
+        match self.result {
+            Ok(ref queue) => Ok(pairs::new(Rc::clone(queue), &self.input, 0, queue.len())),
+            Err(ref error) => Err(error),
+        }
+    }
+
+    /// Applies `edit` to the input and parses it again, returning the byte range of the new input
+    /// which was parsed: the pair of a reparse rule, or the whole input.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the range of `edit` is out of the input or does not lie on `char` boundaries.
+    pub fn edit(&mut self, edit: TextEdit) -> Range<usize> {
+        self.input.replace_range(edit.range.clone(), &edit.new_text);
+
+        let removed = edit.range.end - edit.range.start;
+        let added = edit.new_text.len();
+
+        if let Ok(ref queue) = self.result {
+            for (start_index, end_index) in enclosing(queue, &edit.range, &self.reparse_rules) {
+                let (start, end, rule) = match (&queue[start_index], &queue[end_index]) {
+                    (
+                        &QueueableToken::Start { input_pos, .. },
+                        &QueueableToken::End {
+                            rule,
+                            input_pos: end,
+                            ..
+                        },
+                    ) => (input_pos, end + added - removed, rule),
+                    _ => unreachable!(),
+                };
+
+                // The input before the edit is unchanged, so `start` is still a char boundary.
+                let span = Span::new(&self.input, start, self.input.len()).unwrap();
+                let new = match P::parse_span(rule, span) {
+                    Ok(pairs) if is_pair_of(pairs::queue(&pairs), rule, end) => {
+                        (**pairs::queue(&pairs)).clone()
+                    }
+                    _ => continue,
+                };
+
+                let queue = splice(queue, start_index, end_index, new, removed, added);
+                self.result = Ok(Rc::new(queue));
+
+                return start..end;
+            }
+        }
+
+        self.result = parse::<P, R>(self.rule, &self.input);
+
+        0..self.input.len()
+    }
+}
+
+impl<P, R: RuleType> fmt::Debug for IncrementalParser<P, R> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("IncrementalParser")
+            .field("rule", &self.rule)
+            .field("input", &self.input)
+            .field("reparse_rules", &self.reparse_rules)
+            .finish()
+    }
+}
+
+#[allow(clippy::perf)]
+fn parse<P: Parser<R>, R: RuleType>(
+    rule: R,
+    input: &str,
+) -> Result<Rc<Vec<QueueableToken<R>>>, Error<R>> {
+    P::parse(rule, input).map(|pairs| Rc::clone(pairs::queue(&pairs)))
+}
+
+// Returns the token indices of the pairs of reparse rules strictly enclosing `range`, innermost
+// first, skipping the pairs which do not.
+fn enclosing<R: RuleType>(
+    queue: &[QueueableToken<R>],
+    range: &Range<usize>,
+    reparse_rules: &[R],
+) -> Vec<(usize, usize)> {
+    let mut result = Vec::new();
+    let mut index = 0;
+
+    while index < queue.len() {
+        let (end_index, start) = match queue[index] {
+            QueueableToken::Start {
+                end_token_index,
+                input_pos,
+            } => (end_token_index, input_pos),
+            QueueableToken::End { .. } => {
+                index += 1;
+                continue;
+            }
+        };
+        let (rule, end) = match queue[end_index] {
+            QueueableToken::End {
+                rule, input_pos, ..
+            } => (rule, input_pos),
+            QueueableToken::Start { .. } => unreachable!(),
+        };
+
+        if start < range.start && range.end < end {
+            if reparse_rules.contains(&rule) {
+                result.push((index, end_index));
+            }
+            index += 1;
+        } else if start > range.start {
+            break;
+        } else {
+            index = end_index + 1;
+        }
+    }
+
+    result.reverse();
+    result
+}
+
+// Whether `queue` is made of a single pair of `rule` ending at `end`.
+fn is_pair_of<R: RuleType>(queue: &[QueueableToken<R>], rule: R, end: usize) -> bool {
+    match (queue.first(), queue.last()) {
+        (
+            Some(&QueueableToken::Start {
+                end_token_index, ..
+            }),
+            Some(&QueueableToken::End {
+                rule: last_rule,
+                input_pos,
+                ..
+            }),
+        ) => end_token_index == queue.len() - 1 && last_rule == rule && input_pos == end,
+        _ => false,
+    }
+}
+
+// Replaces the tokens from `start_index` to `end_index` with `new`, whose positions are already
+// those of the edited input, and shifts the indices and positions of the tokens after them.
+fn splice<R: RuleType>(
+    queue: &[QueueableToken<R>],
+    start_index: usize,
+    end_index: usize,
+    new: Vec<QueueableToken<R>>,
+    removed: usize,
+    added: usize,
+) -> Vec<QueueableToken<R>> {
+    let removed_tokens = end_index + 1 - start_index;
+    let added_tokens = new.len();
+    let shift = |index: usize| {
+        if index > end_index {
+            index + added_tokens - removed_tokens
+        } else {
+            index
+        }
+    };
+
+    let mut result = Vec::with_capacity(queue.len() + added_tokens - removed_tokens);
+
+    for token in &queue[..start_index] {
+        result.push(match *token {
+            QueueableToken::Start {
+                end_token_index,
+                input_pos,
+            } => QueueableToken::Start {
+                end_token_index: shift(end_token_index),
+                input_pos,
+            },
+            ref token => token.clone(),
+        });
+    }
+
+    result.extend(new.into_iter().map(|token| match token {
+        QueueableToken::Start {
+            end_token_index,
+            input_pos,
+        } => QueueableToken::Start {
+            end_token_index: end_token_index + start_index,
+            input_pos,
+        },
+        QueueableToken::End {
+            start_token_index,
+            rule,
+            input_pos,
+        } => QueueableToken::End {
+            start_token_index: start_token_index + start_index,
+            rule,
+            input_pos,
+        },
+    }));
+
+    for token in &queue[end_index + 1..] {
+        result.push(match *token {
+            QueueableToken::Start {
+                end_token_index,
+                input_pos,
+            } => QueueableToken::Start {
+                end_token_index: shift(end_token_index),
+                input_pos: input_pos + added - removed,
+            },
+            QueueableToken::End {
+                start_token_index,
+                rule,
+                input_pos,
+            } => QueueableToken::End {
+                start_token_index: shift(start_token_index),
+                rule,
+                input_pos: input_pos + added - removed,
+            },
+        });
+    }
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::macros::tests::{AbcParser, Rule};
+    use alloc::format;
+
+    #[test]
+    fn full_parse() {
+        let mut parser = IncrementalParser::new(AbcParser, Rule::a, String::from("abcde"));
+        assert_eq!(
+            format!("{}", parser.pairs().unwrap()),
+            "[a(0, 3, [b(1, 2)]), c(4, 5)]"
+        );
+
+        assert_eq!(parser.edit(TextEdit::new(4..5, String::new())), 0..4);
+        assert!(parser.pairs().is_err());
+
+        assert_eq!(parser.edit(TextEdit::new(4..4, String::from("ex"))), 0..6);
+        assert_eq!(parser.input(), "abcdex");
+        assert_eq!(
+            format!("{}", parser.pairs().unwrap()),
+            "[a(0, 3, [b(1, 2)]), c(4, 5)]"
+        );
+    }
+}
@@ -0,0 +1,282 @@
+// pest. The Elegant Parser
+// Copyright (c) 2018 Dragoș Tiselice
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+use alloc::borrow::Cow;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::str;
+
+use super::Input;
+use crate::position::Position;
+use crate::span::Span;
+
+/// A raw `&[u8]` input, e.g. of a binary protocol, to be parsed by [`ByteParser::parse_bytes`]
+/// without validating it as UTF-8.
+///
+/// Every byte is parsed as the `char` of the same value, which the terminals of the grammars of
+/// `ByteParser`s are validated to match as bytes: ASCII bytes are matched by ASCII terminals like
+/// they would be in a `&str`, and any other byte by `ANY` or, e.g. for bytes `0x80` to `0xFF`, by
+/// `'\u{80}'..'\u{ff}'`. Inputs made only of ASCII bytes are parsed in place, while others are
+/// decoded into a copy.
+///
+/// Positions in the parsed text, like those of `Pair`s and `Error`s, are converted back into
+/// positions in the bytes by [`byte_pos`], [`position`] and [`span`].
+///
+/// # Examples
+///
+/// ```
+/// # use pest::{ByteInput, Input, Span};
+/// let input = ByteInput::new(b"GET \xff\xfe /");
+///
+/// assert_eq!(input.as_str(), "GET \u{ff}\u{fe} /");
+///
+/// let span = Span::new(input.as_str(), 4, 8).unwrap();
+/// assert_eq!(input.span(&span).as_bytes(), b"\xff\xfe");
+/// assert_eq!(input.byte_pos(9), 7);
+/// ```
+///
+/// [`ByteParser::parse_bytes`]: ../trait.ByteParser.html#method.parse_bytes
+/// [`byte_pos`]: #method.byte_pos
+/// [`position`]: #method.position
+/// [`span`]: #method.span
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ByteInput<'i> {
+    bytes: &'i [u8],
+    text: Cow<'i, str>,
+    // The positions in `text` of the chars decoded from non-ASCII bytes, which take two bytes.
+    wide: Vec<usize>,
+}
+
+impl<'i> ByteInput<'i> {
+    /// Creates a `ByteInput` out of `bytes`.
+    pub fn new(bytes: &'i [u8]) -> ByteInput<'i> {
+        if bytes.is_ascii() {
+            return ByteInput {
+                bytes,
+                // ASCII is valid UTF-8.
+                text: Cow::Borrowed(unsafe { str::from_utf8_unchecked(bytes) }),
+                wide: Vec::new(),
+            };
+        }
+
+        let mut text = String::with_capacity(bytes.len() * 2);
+        let mut wide = Vec::new();
+
+        for &byte in bytes {
+            if !byte.is_ascii() {
+                wide.push(text.len());
+            }
+            text.push(char::from(byte));
+        }
+
+        ByteInput {
+            bytes,
+            text: Cow::Owned(text),
+            wide,
+        }
+    }
+
+    /// Returns the bytes of the `ByteInput`.
+    #[inline]
+    pub fn as_bytes(&self) -> &'i [u8] {
+        self.bytes
+    }
+
+    /// Returns the byte offset corresponding to the position `pos` in the parsed text.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `pos` is past the end of the parsed text.
+    pub fn byte_pos(&self, pos: usize) -> usize {
+        assert!(pos <= self.text.len(), "position out of bounds");
+
+        pos - self.wide.partition_point(|&wide| wide < pos)
+    }
+
+    /// Returns the `BytePosition` corresponding to `position`, a position in the parsed text.
+    pub fn position(&self, position: &Position<'_>) -> BytePosition<'i> {
+        BytePosition {
+            input: self.bytes,
+            pos: self.byte_pos(position.pos()),
+        }
+    }
+
+    /// Returns the `ByteSpan` corresponding to `span`, a span of the parsed text.
+    pub fn span(&self, span: &Span<'_>) -> ByteSpan<'i> {
+        ByteSpan {
+            input: self.bytes,
+            start: self.byte_pos(span.start()),
+            end: self.byte_pos(span.end()),
+        }
+    }
+}
+
+impl<'i> Input for ByteInput<'i> {
+    #[inline]
+    fn as_str(&self) -> &str {
+        &self.text
+    }
+}
+
+/// A position in a `&[u8]` input, like a `Position` of a `&str`.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct BytePosition<'i> {
+    input: &'i [u8],
+    pos: usize,
+}
+
+impl<'i> BytePosition<'i> {
+    /// Attempts to create a new `BytePosition` at the given position. If `pos` is past the end of
+    /// `input`, this method returns `None`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use pest::BytePosition;
+    /// assert!(BytePosition::new(b"\xff\n", 2).is_some());
+    /// assert_eq!(BytePosition::new(b"\xff\n", 3), None);
+    /// ```
+    pub fn new(input: &'i [u8], pos: usize) -> Option<BytePosition<'i>> {
+        if pos <= input.len() {
+            Some(BytePosition { input, pos })
+        } else {
+            None
+        }
+    }
+
+    /// Returns the byte position of the `BytePosition`.
+    #[inline]
+    pub fn pos(&self) -> usize {
+        self.pos
+    }
+
+    /// Returns the line and column number of the `BytePosition`, with columns counted in bytes.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use pest::BytePosition;
+    /// let position = BytePosition::new(b"\xff\xfe\n\x00\x01", 4).unwrap();
+    /// assert_eq!(position.line_col(), (2, 2));
+    /// ```
+    pub fn line_col(&self) -> (usize, usize) {
+        let before = &self.input[..self.pos];
+        let line_start = before
+            .iter()
+            .rposition(|&byte| byte == b'\n')
+            .map_or(0, |i| i + 1);
+        let line = before.iter().filter(|&&byte| byte == b'\n').count() + 1;
+
+        (line, self.pos - line_start + 1)
+    }
+}
+
+/// A span of a `&[u8]` input, like a `Span` of a `&str`.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct ByteSpan<'i> {
+    input: &'i [u8],
+    start: usize,
+    end: usize,
+}
+
+impl<'i> ByteSpan<'i> {
+    /// Attempts to create a new `ByteSpan` from `start` to `end`. If `start` is after `end` or
+    /// `end` is past the end of `input`, this method returns `None`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use pest::ByteSpan;
+    /// assert_eq!(ByteSpan::new(b"\x00\x01", 1, 2).unwrap().as_bytes(), b"\x01");
+    /// assert_eq!(ByteSpan::new(b"\x00\x01", 2, 1), None);
+    /// ```
+    pub fn new(input: &'i [u8], start: usize, end: usize) -> Option<ByteSpan<'i>> {
+        if start <= end && end <= input.len() {
+            Some(ByteSpan { input, start, end })
+        } else {
+            None
+        }
+    }
+
+    /// Returns the `ByteSpan`'s start byte position as a `usize`.
+    #[inline]
+    pub fn start(&self) -> usize {
+        self.start
+    }
+
+    /// Returns the `ByteSpan`'s end byte position as a `usize`.
+    #[inline]
+    pub fn end(&self) -> usize {
+        self.end
+    }
+
+    /// Returns the `ByteSpan`'s start `BytePosition`.
+    #[inline]
+    pub fn start_pos(&self) -> BytePosition<'i> {
+        BytePosition {
+            input: self.input,
+            pos: self.start,
+        }
+    }
+
+    /// Returns the `ByteSpan`'s end `BytePosition`.
+    #[inline]
+    pub fn end_pos(&self) -> BytePosition<'i> {
+        BytePosition {
+            input: self.input,
+            pos: self.end,
+        }
+    }
+
+    /// Captures a slice from the `&[u8]` defined by the `ByteSpan`.
+    #[inline]
+    pub fn as_bytes(&self) -> &'i [u8] {
+        &self.input[self.start..self.end]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::macros::tests::{AbcParser, Rule};
+    use crate::parser::ByteParser;
+    use alloc::vec::Vec;
+
+    impl ByteParser<Rule> for AbcParser {}
+
+    #[test]
+    fn ascii_in_place() {
+        let bytes = b"abcde";
+        let input = ByteInput::new(bytes);
+
+        assert_eq!(input.as_str().as_ptr(), bytes.as_ptr());
+        assert_eq!(input.byte_pos(5), 5);
+    }
+
+    #[test]
+    fn parse_bytes() {
+        let input = ByteInput::new(b"\xffbc\x80e\x00");
+        let pairs = AbcParser::parse_bytes(Rule::a, &input).unwrap();
+        let spans: Vec<_> = pairs
+            .flatten()
+            .map(|pair| input.span(&pair.as_span()))
+            .map(|span| (span.start(), span.as_bytes()))
+            .collect();
+
+        assert_eq!(spans, [(0, &b"\xffbc"[..]), (1, &b"b"[..]), (4, &b"e"[..])]);
+
+        let input = ByteInput::new(b"\xff\xffc\xfff");
+        let error = AbcParser::parse_bytes(Rule::a, &input).unwrap_err();
+        let pos = match error.location {
+            crate::error::InputLocation::Pos(pos) => pos,
+            _ => unreachable!(),
+        };
+        assert_eq!(input.byte_pos(pos), 4);
+    }
+}
@@ -0,0 +1,317 @@
+// pest. The Elegant Parser
+// Copyright (c) 2018 Dragoș Tiselice
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+use core::str::{self, Utf8Error};
+use core::sync::atomic::{AtomicUsize, Ordering};
+use std::fs::File;
+use std::io;
+use std::path::Path;
+
+use super::Input;
+
+/// How the contents of a [`MmapInput`] are checked to be valid UTF-8.
+///
+/// [`MmapInput`]: struct.MmapInput.html
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum Utf8Validation {
+    /// The whole file is validated when it is opened
+    Eager,
+    /// The file is validated as it is accessed, one prefix at a time through
+    /// [`MmapInput::try_prefix`]. Bytes are never validated twice.
+    ///
+    /// Parsers match against the whole file, so [`Parser::parse_input`] still validates all of it
+    /// before the parse starts, like `Eager`, but reports invalid UTF-8 as a parse error instead of
+    /// failing to open the file. This only saves work when accessing prefixes of the file.
+    ///
+    /// [`MmapInput::try_prefix`]: struct.MmapInput.html#method.try_prefix
+    /// [`Parser::parse_input`]: ../trait.Parser.html#method.parse_input
+    Lazy,
+    /// The file is assumed to be valid UTF-8 and is never validated
+    AssumeValid,
+}
+
+/// An [`Input`] backed by a read-only memory-mapped file, which lets the OS page the file in as it
+/// is parsed instead of reading it fully into memory.
+///
+/// On platforms other than Unix, the file is read into memory instead.
+///
+/// # Examples
+///
+/// ```no_run
+/// # use pest::input::{Input, MmapInput, Utf8Validation};
+/// let input = unsafe { MmapInput::open("archive.log", Utf8Validation::Eager) }.unwrap();
+///
+/// println!("{} bytes", input.len());
+/// // e.g. LogParser::parse_input(Rule::log, &input)
+/// ```
+///
+/// [`Input`]: trait.Input.html
+#[derive(Debug)]
+pub struct MmapInput {
+    map: Map,
+    validation: Utf8Validation,
+    // The length of the prefix of the file known to be valid UTF-8.
+    valid_up_to: AtomicUsize,
+}
+
+impl MmapInput {
+    /// Memory-maps the file at `path`, validating it according to `validation`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be opened or mapped, or, with
+    /// [`Utf8Validation::Eager`], if it is not valid UTF-8, in which case the error is of kind
+    /// `InvalidData`.
+    ///
+    /// # Safety
+    ///
+    /// The file must not be modified or truncated while the `MmapInput` exists, since the `&str`s
+    /// borrowed from it would then change under them. With [`Utf8Validation::AssumeValid`], the
+    /// file must also be valid UTF-8.
+    ///
+    /// [`Utf8Validation::Eager`]: enum.Utf8Validation.html#variant.Eager
+    /// [`Utf8Validation::AssumeValid`]: enum.Utf8Validation.html#variant.AssumeValid
+    pub unsafe fn open<P: AsRef<Path>>(
+        path: P,
+        validation: Utf8Validation,
+    ) -> io::Result<MmapInput> {
+        let file = File::open(path)?;
+        let map = Map::new(&file)?;
+        let valid_up_to = match validation {
+            Utf8Validation::Eager => {
+                str::from_utf8(map.as_bytes())
+                    .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))?;
+                map.as_bytes().len()
+            }
+            Utf8Validation::Lazy => 0,
+            Utf8Validation::AssumeValid => map.as_bytes().len(),
+        };
+
+        Ok(MmapInput {
+            map,
+            validation,
+            valid_up_to: AtomicUsize::new(valid_up_to),
+        })
+    }
+
+    /// Returns the `Utf8Validation` of the `MmapInput`.
+    #[inline]
+    pub fn validation(&self) -> Utf8Validation {
+        self.validation
+    }
+
+    /// Returns the raw bytes of the file.
+    #[inline]
+    pub fn as_bytes(&self) -> &[u8] {
+        self.map.as_bytes()
+    }
+
+    /// Returns the first `len` bytes of the file as a `&str`, validating whatever part of them
+    /// was not validated before.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the prefix is not valid UTF-8, including when `len` splits a `char`.
+    /// The error's offsets are relative to the start of the file.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `len` is greater than the length of the file.
+    pub fn try_prefix(&self, len: usize) -> Result<&str, Utf8Error> {
+        let all = self.as_bytes();
+        let bytes = &all[..len];
+        let valid_up_to = self.valid_up_to.load(Ordering::Acquire);
+
+        if len > valid_up_to {
+            if str::from_utf8(&bytes[valid_up_to..]).is_err() {
+                // Validate again from the start to report offsets relative to the file.
+                return str::from_utf8(bytes);
+            }
+
+            self.valid_up_to.fetch_max(len, Ordering::AcqRel);
+        } else if len < all.len() && all[len] & 0xc0 == 0x80 {
+            // `len` splits a `char` of the already validated prefix.
+            return str::from_utf8(bytes);
+        }
+
+        // All `len` bytes are valid UTF-8 at this point.
+        Ok(unsafe { str::from_utf8_unchecked(bytes) })
+    }
+}
+
+impl Input for MmapInput {
+    /// Returns the text of the file.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the file is not valid UTF-8. Use [`try_as_str`] to handle invalid files with
+    /// [`Utf8Validation::Lazy`].
+    ///
+    /// [`try_as_str`]: #method.try_as_str
+    /// [`Utf8Validation::Lazy`]: enum.Utf8Validation.html#variant.Lazy
+    fn as_str(&self) -> &str {
+        self.try_as_str()
+            .expect("memory-mapped input is not valid UTF-8")
+    }
+
+    /// Returns the whole file as a `&str`, validating whatever part of it was not validated before.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file is not valid UTF-8.
+    fn try_as_str(&self) -> Result<&str, Utf8Error> {
+        self.try_prefix(self.as_bytes().len())
+    }
+
+    #[inline]
+    fn len(&self) -> usize {
+        self.as_bytes().len()
+    }
+}
+
+#[cfg(unix)]
+#[derive(Debug)]
+struct Map {
+    ptr: *mut libc::c_void,
+    len: usize,
+}
+
+// The mapping is read-only and owned by the `Map`.
+#[cfg(unix)]
+unsafe impl Send for Map {}
+#[cfg(unix)]
+unsafe impl Sync for Map {}
+
+#[cfg(unix)]
+impl Map {
+    unsafe fn new(file: &File) -> io::Result<Map> {
+        use std::os::unix::io::AsRawFd;
+
+        let len = file.metadata()?.len() as usize;
+        if len == 0 {
+            // Empty files cannot be mapped.
+            return Ok(Map {
+                ptr: core::ptr::null_mut(),
+                len,
+            });
+        }
+
+        let ptr = libc::mmap(
+            core::ptr::null_mut(),
+            len,
+            libc::PROT_READ,
+            libc::MAP_PRIVATE,
+            file.as_raw_fd(),
+            0,
+        );
+        if ptr == libc::MAP_FAILED {
+            return Err(io::Error::last_os_error());
+        }
+
+        Ok(Map { ptr, len })
+    }
+
+    fn as_bytes(&self) -> &[u8] {
+        if self.len == 0 {
+            return &[];
+        }
+
+        unsafe { core::slice::from_raw_parts(self.ptr as *const u8, self.len) }
+    }
+}
+
+#[cfg(unix)]
+impl Drop for Map {
+    fn drop(&mut self) {
+        if self.len != 0 {
+            unsafe {
+                libc::munmap(self.ptr, self.len);
+            }
+        }
+    }
+}
+
+#[cfg(not(unix))]
+#[derive(Debug)]
+struct Map {
+    bytes: std::vec::Vec<u8>,
+}
+
+#[cfg(not(unix))]
+impl Map {
+    unsafe fn new(mut file: &File) -> io::Result<Map> {
+        use std::io::Read;
+
+        let mut bytes = std::vec::Vec::new();
+        file.read_to_end(&mut bytes)?;
+
+        Ok(Map { bytes })
+    }
+
+    fn as_bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::macros::tests::{AbcParser, Rule};
+    use crate::Parser;
+    use alloc::format;
+    use std::path::PathBuf;
+
+    fn write(name: &str, contents: &[u8]) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("pest-mmap-{}-{}", std::process::id(), name));
+        std::fs::write(&path, contents).unwrap();
+        path
+    }
+
+    #[test]
+    fn parse_mapped() {
+        let path = write("parse", b"abcde");
+        let input = unsafe { MmapInput::open(&path, Utf8Validation::Eager) }.unwrap();
+        let pairs = AbcParser::parse_input(Rule::a, &input).unwrap();
+
+        assert_eq!(format!("{}", pairs), "[a(0, 3, [b(1, 2)]), c(4, 5)]");
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn lazy_validation() {
+        let path = write("lazy", b"ab\xc3\xa4c\xff");
+        let input = unsafe { MmapInput::open(&path, Utf8Validation::Lazy) }.unwrap();
+
+        assert_eq!(input.try_prefix(2), Ok("ab"));
+        assert_eq!(input.try_prefix(4), Ok("ab\u{e4}"));
+        assert_eq!(input.try_prefix(3).unwrap_err().valid_up_to(), 2);
+        assert_eq!(input.try_as_str().unwrap_err().valid_up_to(), 5);
+        assert_eq!(input.try_prefix(5), Ok("ab\u{e4}c"));
+
+        let error = AbcParser::parse_input(Rule::a, &input).unwrap_err();
+        assert_eq!(
+            error.variant.message(),
+            "input is not valid UTF-8: invalid utf-8 sequence of 1 bytes from index 5"
+        );
+
+        let error = unsafe { MmapInput::open(&path, Utf8Validation::Eager) }.unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn empty_file() {
+        let path = write("empty", b"");
+        let input = unsafe { MmapInput::open(&path, Utf8Validation::Lazy) }.unwrap();
+
+        assert!(input.is_empty());
+        assert_eq!(input.as_str(), "");
+        std::fs::remove_file(path).unwrap();
+    }
+}
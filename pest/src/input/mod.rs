@@ -0,0 +1,477 @@
+// pest. The Elegant Parser
+// Copyright (c) 2018 Dragoș Tiselice
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Sources of input text for parsers.
+
+use alloc::borrow::Cow;
+use alloc::boxed::Box;
+use alloc::rc::Rc;
+use alloc::string::String;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::ops::Range;
+use core::str::Utf8Error;
+
+use crate::span::Span;
+
+pub use self::bytes::{ByteInput, BytePosition, ByteSpan};
+#[cfg(feature = "mmap")]
+pub use self::mmap::{MmapInput, Utf8Validation};
+#[cfg(feature = "std")]
+pub use self::reader::ReaderInput;
+
+mod bytes;
+#[cfg(feature = "mmap")]
+mod mmap;
+#[cfg(feature = "std")]
+mod reader;
+
+/// A source of input text to be parsed by [`Parser::parse_input`], without first copying it into
+/// a `String`.
+///
+/// Parsers currently match against a single contiguous UTF-8 buffer, so an `Input` only needs to
+/// expose its text as a `&str`. This is free for owned strings and shared buffers, as well as for
+/// memory-mapped files, which are supported by `MmapInput` with the `mmap` feature. Chunked inputs
+/// like ropes have to provide a contiguous view of their text, e.g. through [`ChunkedInput`], while
+/// inputs too large to be held in memory can be parsed one piece at a time as a [`PieceInput`].
+///
+/// # Examples
+///
+/// ```
+/// # use pest::Input;
+/// struct Mapped {
+///     bytes: Vec<u8>,
+/// }
+///
+/// impl Input for Mapped {
+///     fn as_str(&self) -> &str {
+///         std::str::from_utf8(&self.bytes).expect("input is not valid UTF-8")
+///     }
+/// }
+///
+/// let input = Mapped { bytes: b"abc".to_vec() };
+/// assert_eq!(input.len(), 3);
+/// ```
+///
+/// [`Parser::parse_input`]: ../trait.Parser.html#method.parse_input
+/// [`ChunkedInput`]: struct.ChunkedInput.html
+/// [`PieceInput`]: trait.PieceInput.html
+pub trait Input {
+    /// Returns the text of the `Input`.
+    fn as_str(&self) -> &str;
+
+    /// Returns the text of the `Input`, or an error if it is not valid UTF-8. This is what
+    /// [`Parser::parse_input`] parses, and is overridden by inputs validating their text as it is
+    /// accessed, whose `as_str` panics on invalid text. By default, this returns `as_str`.
+    ///
+    /// [`Parser::parse_input`]: ../trait.Parser.html#method.parse_input
+    #[inline]
+    fn try_as_str(&self) -> Result<&str, Utf8Error> {
+        Ok(self.as_str())
+    }
+
+    /// Returns the length of the `Input` in bytes.
+    fn len(&self) -> usize {
+        self.as_str().len()
+    }
+
+    /// Returns `true` if the `Input` is empty.
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl Input for str {
+    #[inline]
+    fn as_str(&self) -> &str {
+        self
+    }
+}
+
+impl Input for String {
+    #[inline]
+    fn as_str(&self) -> &str {
+        self
+    }
+}
+
+impl Input for Box<str> {
+    #[inline]
+    fn as_str(&self) -> &str {
+        self
+    }
+}
+
+impl Input for Rc<str> {
+    #[inline]
+    fn as_str(&self) -> &str {
+        self
+    }
+}
+
+impl Input for Arc<str> {
+    #[inline]
+    fn as_str(&self) -> &str {
+        self
+    }
+}
+
+impl Input for Cow<'_, str> {
+    #[inline]
+    fn as_str(&self) -> &str {
+        self
+    }
+}
+
+impl<I: Input + ?Sized> Input for &I {
+    #[inline]
+    fn as_str(&self) -> &str {
+        (**self).as_str()
+    }
+
+    #[inline]
+    fn try_as_str(&self) -> Result<&str, Utf8Error> {
+        (**self).try_as_str()
+    }
+}
+
+/// A source of independent pieces of input text, e.g. the records of a log file too large to be
+/// held in memory as a whole, which [`Parser::parse_pieces`] parses one at a time.
+///
+/// Every piece is a separate input to the grammar, which parses whole pieces, e.g. single log
+/// entries, so only the current piece is held in memory. Pieces come with their byte offset in the
+/// whole input, since the positions of their `Pairs` and errors are relative to the piece.
+/// [`ReaderInput`] reads pieces from any `BufRead`.
+///
+/// # Examples
+///
+/// ```
+/// # use pest::input::PieceInput;
+/// // Splits a `&str` on commas.
+/// struct Fields<'a> {
+///     rest: &'a str,
+///     offset: usize,
+/// }
+///
+/// impl<'a> PieceInput for Fields<'a> {
+///     type Error = std::convert::Infallible;
+///
+///     fn next_piece(&mut self) -> Option<Result<(usize, &str), Self::Error>> {
+///         if self.rest.is_empty() {
+///             return None;
+///         }
+///
+///         let (piece, rest) = self.rest.split_once(',').unwrap_or((self.rest, ""));
+///         let offset = self.offset;
+///         self.rest = rest;
+///         self.offset += piece.len() + 1;
+///         Some(Ok((offset, piece)))
+///     }
+/// }
+///
+/// let mut fields = Fields { rest: "a,bc", offset: 0 };
+/// assert_eq!(fields.next_piece(), Some(Ok((0, "a"))));
+/// assert_eq!(fields.next_piece(), Some(Ok((2, "bc"))));
+/// assert_eq!(fields.next_piece(), None);
+/// ```
+///
+/// [`Parser::parse_pieces`]: ../trait.Parser.html#method.parse_pieces
+/// [`ReaderInput`]: struct.ReaderInput.html
+pub trait PieceInput {
+    /// The error returned when the next piece cannot be read
+    type Error;
+
+    /// Returns the next piece of the input along with its byte offset in the whole input, or
+    /// `None` at the end of the input.
+    fn next_piece(&mut self) -> Option<Result<(usize, &str), Self::Error>>;
+}
+
+impl<P: PieceInput + ?Sized> PieceInput for &mut P {
+    type Error = P::Error;
+
+    #[inline]
+    fn next_piece(&mut self) -> Option<Result<(usize, &str), Self::Error>> {
+        (**self).next_piece()
+    }
+}
+
+/// An [`Input`] assembled from the chunks of a chunked text buffer, e.g. the chunks of a rope,
+/// which keeps track of where every chunk starts in order to map positions back to the buffer.
+///
+/// Parsers match against contiguous text, so the chunks are joined into a single buffer once, when
+/// the `ChunkedInput` is created. The chunks are only used to map offsets back to the buffer. Later
+/// edits of the buffer are applied to the `ChunkedInput` with [`replace`], which updates it in
+/// place instead of joining all the chunks again before the next parse. With the `ropey` feature,
+/// `ropey::Rope`s and `ropey::RopeSlice`s convert into `ChunkedInput`s directly.
+///
+/// # Examples
+///
+/// ```
+/// # use pest::{ChunkedInput, Input, Span};
+/// // e.g. `ChunkedInput::new(rope.chunks())`
+/// let input = ChunkedInput::new(vec!["fn mäin", "() {}"]);
+///
+/// assert_eq!(input.as_str(), "fn mäin() {}");
+/// assert_eq!(input.chunk_count(), 2);
+/// assert_eq!(input.chunk_at(8), (1, 8, 7));
+///
+/// let span = Span::new(input.as_str(), 3, 10).unwrap();
+/// assert_eq!(input.char_range(&span), (3, 9));
+/// ```
+///
+/// [`Input`]: trait.Input.html
+/// [`replace`]: #method.replace
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct ChunkedInput {
+    text: String,
+    // The byte and char offsets at which every chunk starts.
+    starts: Vec<(usize, usize)>,
+}
+
+impl ChunkedInput {
+    /// Creates a `ChunkedInput` out of `chunks`, in order.
+    pub fn new<'a, I: IntoIterator<Item = &'a str>>(chunks: I) -> ChunkedInput {
+        let mut input = ChunkedInput::default();
+        let mut chars = 0;
+
+        for chunk in chunks {
+            input.starts.push((input.text.len(), chars));
+            input.text.push_str(chunk);
+            chars += chunk.chars().count();
+        }
+
+        input
+    }
+
+    /// Returns the number of chunks of the `ChunkedInput`.
+    #[inline]
+    pub fn chunk_count(&self) -> usize {
+        self.starts.len()
+    }
+
+    /// Returns the `(index, byte, char)` of the start of the chunk containing the byte offset
+    /// `byte`. Offsets past the end of the input belong to the last chunk.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the `ChunkedInput` has no chunks.
+    pub fn chunk_at(&self, byte: usize) -> (usize, usize, usize) {
+        let index = match self.starts.binary_search_by(|&(start, _)| start.cmp(&byte)) {
+            Ok(index) => {
+                // Empty chunks start where the next chunk does, so skip to the last of them.
+                let mut index = index;
+                while index + 1 < self.starts.len() && self.starts[index + 1].0 == byte {
+                    index += 1;
+                }
+                index
+            }
+            Err(index) => index - 1,
+        };
+        let (start, chars) = self.starts[index];
+
+        (index, start, chars)
+    }
+
+    /// Returns the char offset corresponding to the byte offset `byte`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `byte` is not on a char boundary.
+    pub fn byte_to_char(&self, byte: usize) -> usize {
+        if self.starts.is_empty() {
+            return 0;
+        }
+
+        let (_, start, chars) = self.chunk_at(byte);
+        chars + self.text[start..byte].chars().count()
+    }
+
+    /// Returns the `(start, end)` char offsets of `span`, as used to index a rope.
+    pub fn char_range(&self, span: &Span<'_>) -> (usize, usize) {
+        let start = self.byte_to_char(span.start());
+        let end = start + span.as_str().chars().count();
+
+        (start, end)
+    }
+
+    /// Replaces the text in the byte range `bytes` with `text`, e.g. to mirror an edit of the rope
+    /// the `ChunkedInput` was created from. The text after `bytes` is moved within the buffer, while
+    /// the chunks before it are kept as they are and the following chunks are shifted, so that the
+    /// edited text belongs to the chunk containing `bytes.start`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the start or end of `bytes` is out of bounds or not on a char boundary.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use pest::{ChunkedInput, Input};
+    /// let mut input = ChunkedInput::new(vec!["fn main", "() {}"]);
+    /// input.replace(3..7, "mäin");
+    ///
+    /// assert_eq!(input.as_str(), "fn mäin() {}");
+    /// assert_eq!(input.chunk_at(8), (1, 8, 7));
+    /// assert_eq!(input.byte_to_char(8), 7);
+    /// ```
+    pub fn replace(&mut self, bytes: Range<usize>, text: &str) {
+        let removed = self.text[bytes.clone()].chars().count();
+        let added = text.chars().count();
+
+        if self.starts.is_empty() {
+            self.starts.push((0, 0));
+        }
+
+        let (index, _, _) = self.chunk_at(bytes.start);
+        let next = index + 1;
+        // Chunks starting within the replaced text are merged into the edited one.
+        while next < self.starts.len() && self.starts[next].0 < bytes.end {
+            self.starts.remove(next);
+        }
+        for start in &mut self.starts[next..] {
+            start.0 = start.0 - bytes.end + bytes.start + text.len();
+            start.1 = start.1 - removed + added;
+        }
+
+        self.text.replace_range(bytes, text);
+    }
+}
+
+impl Input for ChunkedInput {
+    #[inline]
+    fn as_str(&self) -> &str {
+        &self.text
+    }
+}
+
+#[cfg(feature = "ropey")]
+impl From<&ropey::Rope> for ChunkedInput {
+    /// Creates a `ChunkedInput` out of the chunks of `rope`, whose byte and char offsets are the
+    /// same as the ones of the `ChunkedInput`.
+    fn from(rope: &ropey::Rope) -> ChunkedInput {
+        ChunkedInput::new(rope.chunks())
+    }
+}
+
+#[cfg(feature = "ropey")]
+impl From<ropey::RopeSlice<'_>> for ChunkedInput {
+    /// Creates a `ChunkedInput` out of the chunks of `slice`, whose byte and char offsets are
+    /// relative to the start of `slice`.
+    fn from(slice: ropey::RopeSlice<'_>) -> ChunkedInput {
+        ChunkedInput::new(slice.chunks())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::macros::tests::{AbcParser, Rule};
+    use crate::Parser;
+    use alloc::format;
+    use alloc::vec;
+
+    #[test]
+    fn chunked_parse() {
+        let input = ChunkedInput::new(vec!["a", "", "bc", "de"]);
+        let pairs = AbcParser::parse_input(Rule::a, &input).unwrap();
+
+        assert_eq!(format!("{}", pairs), "[a(0, 3, [b(1, 2)]), c(4, 5)]");
+        assert_eq!(input.chunk_at(1), (2, 1, 1));
+        assert_eq!(input.chunk_at(5), (3, 3, 3));
+    }
+
+    #[test]
+    fn chunked_chars() {
+        let input = ChunkedInput::new(vec!["ää", "ö", "", "ü"]);
+
+        assert_eq!(input.byte_to_char(0), 0);
+        assert_eq!(input.byte_to_char(2), 1);
+        assert_eq!(input.byte_to_char(4), 2);
+        assert_eq!(input.byte_to_char(6), 3);
+        assert_eq!(input.byte_to_char(8), 4);
+
+        let span = Span::new(input.as_str(), 2, 8).unwrap();
+        assert_eq!(input.char_range(&span), (1, 4));
+        assert_eq!(ChunkedInput::new(vec![]).byte_to_char(0), 0);
+    }
+
+    #[test]
+    fn chunked_replace() {
+        let mut input = ChunkedInput::new(vec!["ab", "", "cd", "é", "f"]);
+        input.replace(1..4, "ü");
+
+        assert_eq!(input.as_str(), "aüéf");
+        assert_eq!(input.chunk_count(), 3);
+        assert_eq!(input.chunk_at(3), (1, 3, 2));
+        assert_eq!(input.byte_to_char(5), 3);
+
+        input.replace(5..5, "gh");
+        assert_eq!(input.as_str(), "aüéghf");
+        assert_eq!(input.chunk_at(6), (2, 5, 3));
+
+        let mut input = ChunkedInput::new(vec![]);
+        input.replace(0..0, "ab");
+        assert_eq!(input.as_str(), "ab");
+        assert_eq!(input.byte_to_char(2), 2);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn reader_pieces() {
+        let mut input = ReaderInput::new("abcde\nbad\nabcde".as_bytes());
+        let mut results = vec![];
+
+        AbcParser::parse_pieces(Rule::a, &mut input, |offset, result| {
+            results.push((offset, result.map(|pairs| format!("{}", pairs))));
+        })
+        .unwrap();
+
+        assert_eq!(results.len(), 3);
+        assert_eq!(
+            results[0],
+            (0, Ok(String::from("[a(0, 3, [b(1, 2)]), c(4, 5)]")))
+        );
+        assert_eq!(results[1].0, 6);
+        assert!(results[1].1.is_err());
+        assert_eq!(results[2].0, 10);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn reader_invalid_utf8() {
+        let mut input = ReaderInput::new(&[b'a', b'\n', 0xff][..]);
+
+        assert_eq!(input.next_piece().unwrap().unwrap(), (0, "a\n"));
+        assert_eq!(
+            input.next_piece().unwrap().unwrap_err().kind(),
+            std::io::ErrorKind::InvalidData
+        );
+    }
+
+    #[cfg(feature = "ropey")]
+    #[test]
+    fn rope() {
+        let mut rope = ropey::Rope::from_str("ab");
+        rope.insert(2, "cde");
+        rope.insert(5, "é");
+        let mut input = ChunkedInput::from(&rope);
+        let pairs = AbcParser::parse_input(Rule::a, &input).unwrap();
+
+        assert_eq!(format!("{}", pairs), "[a(0, 3, [b(1, 2)]), c(4, 5)]");
+
+        let span = Span::new(input.as_str(), 3, 7).unwrap();
+        let (start, end) = input.char_range(&span);
+        assert_eq!(rope.slice(start..end), "deé");
+        assert_eq!(ChunkedInput::from(rope.slice(1..)).as_str(), "bcdeé");
+
+        rope.remove(1..2);
+        input.replace(rope.char_to_byte(1)..rope.char_to_byte(1) + 1, "");
+        assert_eq!(rope, input.as_str());
+    }
+}
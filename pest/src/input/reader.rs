@@ -0,0 +1,86 @@
+// pest. The Elegant Parser
+// Copyright (c) 2018 Dragoș Tiselice
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+use alloc::vec::Vec;
+use core::str;
+use std::io::{self, BufRead};
+
+use super::PieceInput;
+
+/// A [`PieceInput`] reading its pieces from a `BufRead`, one record at a time, where records end
+/// with a delimiter byte, `b'\n'` by default. Every piece keeps its delimiter, except for a last
+/// record which lacks one.
+///
+/// Only the current record is held in memory, in a buffer which is reused from one record to the
+/// next.
+///
+/// # Examples
+///
+/// ```
+/// # use pest::input::{PieceInput, ReaderInput};
+/// let mut input = ReaderInput::new("a;b".as_bytes()).with_delimiter(b';');
+///
+/// assert_eq!(input.next_piece().unwrap().unwrap(), (0, "a;"));
+/// assert_eq!(input.next_piece().unwrap().unwrap(), (2, "b"));
+/// assert!(input.next_piece().is_none());
+/// ```
+///
+/// [`PieceInput`]: trait.PieceInput.html
+#[derive(Debug)]
+pub struct ReaderInput<B> {
+    reader: B,
+    delimiter: u8,
+    buffer: Vec<u8>,
+    // The byte offset of the next record.
+    offset: usize,
+}
+
+impl<B: BufRead> ReaderInput<B> {
+    /// Creates a `ReaderInput` reading lines from `reader`.
+    pub fn new(reader: B) -> ReaderInput<B> {
+        ReaderInput {
+            reader,
+            delimiter: b'\n',
+            buffer: Vec::new(),
+            offset: 0,
+        }
+    }
+
+    /// Ends records with `delimiter` instead of `b'\n'`.
+    pub fn with_delimiter(mut self, delimiter: u8) -> ReaderInput<B> {
+        self.delimiter = delimiter;
+        self
+    }
+
+    /// Returns the underlying reader.
+    pub fn into_inner(self) -> B {
+        self.reader
+    }
+}
+
+impl<B: BufRead> PieceInput for ReaderInput<B> {
+    type Error = io::Error;
+
+    fn next_piece(&mut self) -> Option<Result<(usize, &str), io::Error>> {
+        self.buffer.clear();
+
+        let read = match self.reader.read_until(self.delimiter, &mut self.buffer) {
+            Ok(0) => return None,
+            Ok(read) => read,
+            Err(error) => return Some(Err(error)),
+        };
+        let offset = self.offset;
+        self.offset += read;
+
+        Some(match str::from_utf8(&self.buffer) {
+            Ok(piece) => Ok((offset, piece)),
+            Err(error) => Err(io::Error::new(io::ErrorKind::InvalidData, error)),
+        })
+    }
+}
@@ -0,0 +1,293 @@
+// pest. The Elegant Parser
+// Copyright (c) 2018 Dragoș Tiselice
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+use alloc::rc::Rc;
+use alloc::vec::Vec;
+use core::mem::size_of;
+
+use super::pairs::{self, Pairs};
+use super::queueable_token::QueueableToken;
+use crate::RuleType;
+
+/// A compact, immutable encoding of [`Pairs`], meant for holding on to the result of very large
+/// parses. It is created by [`Pairs::compact`] and [`Parser::parse_compact`].
+///
+/// Every token is stored as a variable-length delta from the position of the previous token,
+/// while token indices are not stored at all and are recomputed on decoding. For most inputs this
+/// takes less than a quarter of the memory of the regular token queue. The tokens are decoded back
+/// into [`Pairs`] by [`CompactPairs::to_pairs`].
+///
+/// [`Parser::parse_compact`] and [`state_compact`] parse in compact mode, where the tokens are kept
+/// in a queue of `u32` positions and rules while parsing, at a third of the memory of the regular
+/// token queue, and encoded from it once the parse succeeds. `Pairs::compact` encodes the regular
+/// token queue of `Pairs` instead, so the memory used while parsing is the same as for `Pairs`.
+///
+/// [`Pairs`]: struct.Pairs.html
+/// [`Pairs::compact`]: struct.Pairs.html#method.compact
+/// [`Parser::parse_compact`]: ../trait.Parser.html#method.parse_compact
+/// [`CompactPairs::to_pairs`]: struct.CompactPairs.html#method.to_pairs
+/// [`state_compact`]: ../fn.state_compact.html
+#[derive(Clone, Debug)]
+pub struct CompactPairs<'i, R> {
+    deltas: Vec<u8>,
+    rules: Vec<R>,
+    base: usize,
+    input: &'i str,
+}
+
+pub fn new<'i, R: RuleType>(
+    queue: &[QueueableToken<R>],
+    input: &'i str,
+    start: usize,
+    end: usize,
+) -> CompactPairs<'i, R> {
+    let tokens = queue[start..end].iter().map(|token| match *token {
+        QueueableToken::Start { input_pos, .. } => (input_pos, None),
+        QueueableToken::End {
+            rule, input_pos, ..
+        } => (input_pos, Some(rule)),
+    });
+
+    encode(tokens, end - start, input)
+}
+
+// Encodes the tokens of a parse in compact mode, as their position and the rule of the pair they
+// end, or `None` if they start one.
+pub(crate) fn from_tokens<'i, R: RuleType>(
+    tokens: &[(u32, Option<R>)],
+    input: &'i str,
+) -> CompactPairs<'i, R> {
+    let len = tokens.len();
+    let tokens = tokens
+        .iter()
+        .map(|&(input_pos, rule)| (input_pos as usize, rule));
+
+    encode(tokens, len, input)
+}
+
+fn encode<'i, R: RuleType, I: Iterator<Item = (usize, Option<R>)> + Clone>(
+    tokens: I,
+    len: usize,
+    input: &'i str,
+) -> CompactPairs<'i, R> {
+    let base = tokens.clone().next().map_or(0, |(input_pos, _)| input_pos);
+
+    let mut deltas = Vec::new();
+    let mut rules = Vec::with_capacity(len / 2);
+    let mut last_pos = base;
+
+    for (input_pos, rule) in tokens {
+        let is_end = match rule {
+            Some(rule) => {
+                rules.push(rule);
+                true
+            }
+            None => false,
+        };
+
+        // Positions only ever grow in the queue, but zigzag encoding keeps this correct anyway.
+        let delta = (input_pos as isize).wrapping_sub(last_pos as isize);
+        let zigzag = ((delta << 1) ^ (delta >> (isize::BITS - 1))) as usize;
+        write_varint(&mut deltas, zigzag << 1 | is_end as usize);
+        last_pos = input_pos;
+    }
+
+    deltas.shrink_to_fit();
+    rules.shrink_to_fit();
+
+    CompactPairs {
+        deltas,
+        rules,
+        base,
+        input,
+    }
+}
+
+impl<'i, R: RuleType> CompactPairs<'i, R> {
+    /// Returns the number of tokens in the `CompactPairs`, i.e. twice the number of pairs.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use pest;
+    /// # #[allow(non_camel_case_types)]
+    /// # #[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+    /// enum Rule {
+    ///     a
+    /// }
+    ///
+    /// let input = "a";
+    /// let pairs = pest::state(input, |state| {
+    ///     // generating Token pair with Rule::a ...
+    /// #     state.rule(Rule::a, |s| s.match_string("a"))
+    /// }).unwrap();
+    ///
+    /// assert_eq!(pairs.compact().len(), 2);
+    /// ```
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.rules.len() * 2
+    }
+
+    /// Returns `true` if the `CompactPairs` contain no pairs.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// Returns the number of heap bytes held by the `CompactPairs`.
+    #[inline]
+    pub fn heap_size(&self) -> usize {
+        self.deltas.capacity() + self.rules.capacity() * size_of::<R>()
+    }
+
+    /// Decodes the `CompactPairs` back into regular `Pairs`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use pest;
+    /// # #[allow(non_camel_case_types)]
+    /// # #[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+    /// enum Rule {
+    ///     a,
+    ///     b
+    /// }
+    ///
+    /// let input = "ab";
+    /// let pairs = pest::state(input, |state| {
+    ///     // generating Token pairs with Rule::a and Rule::b ...
+    /// #     state.rule(Rule::a, |s| s.match_string("a"))
+    /// #         .and_then(|s| s.rule(Rule::b, |s| s.match_string("b")))
+    /// }).unwrap();
+    /// let compact = pairs.clone().compact();
+    ///
+    /// assert_eq!(format!("{}", compact.to_pairs()), format!("{}", pairs));
+    /// ```
+    pub fn to_pairs(&self) -> Pairs<'i, R> {
+        let len = self.len();
+        let mut queue = Vec::with_capacity(len);
+        let mut starts = Vec::new();
+        let mut rules = self.rules.iter();
+        let mut bytes = self.deltas.iter();
+        let mut last_pos = self.base;
+
+        while let Some(value) = read_varint(&mut bytes) {
+            let is_end = value & 1 == 1;
+            let zigzag = value >> 1;
+            let delta = ((zigzag >> 1) as isize) ^ -((zigzag & 1) as isize);
+            let input_pos = (last_pos as isize).wrapping_add(delta) as usize;
+            last_pos = input_pos;
+
+            if is_end {
+                let start_token_index = starts.pop().expect("unbalanced compact tokens");
+                let end_token_index = queue.len();
+
+                if let QueueableToken::Start {
+                    end_token_index: ref mut index,
+                    ..
+                } = queue[start_token_index]
+                {
+                    *index = end_token_index;
+                }
+
+                queue.push(QueueableToken::End {
+                    start_token_index,
+                    rule: *rules.next().expect("missing compact rule"),
+                    input_pos,
+                });
+            } else {
+                starts.push(queue.len());
+                queue.push(QueueableToken::Start {
+                    end_token_index: 0,
+                    input_pos,
+                });
+            }
+        }
+
+        pairs::new(Rc::new(queue), self.input, 0, len)
+    }
+}
+
+pub(super) fn write_varint(bytes: &mut Vec<u8>, mut value: usize) {
+    while value >= 0x80 {
+        bytes.push((value as u8) | 0x80);
+        value >>= 7;
+    }
+    bytes.push(value as u8);
+}
+
+pub(super) fn read_varint<'a, I: Iterator<Item = &'a u8>>(bytes: &mut I) -> Option<usize> {
+    let mut value = 0;
+    let mut shift = 0;
+
+    loop {
+        if shift >= usize::BITS {
+            return None;
+        }
+
+        let byte = *bytes.next()?;
+        value |= ((byte & 0x7f) as usize) << shift;
+
+        if byte & 0x80 == 0 {
+            return Some(value);
+        }
+
+        shift += 7;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::super::macros::tests::*;
+    use super::super::super::Parser;
+    use alloc::format;
+    use alloc::vec::Vec;
+
+    #[test]
+    fn round_trip() {
+        let pairs = AbcParser::parse(Rule::a, "abcde").unwrap();
+        let compact = pairs.clone().compact();
+
+        assert_eq!(compact.len(), 6);
+        assert_eq!(format!("{}", compact.to_pairs()), format!("{}", pairs));
+    }
+
+    #[test]
+    fn round_trip_inner() {
+        let pairs = AbcParser::parse(Rule::a, "abcde").unwrap();
+        let inner = pairs.clone().next().unwrap().into_inner();
+        let decoded = inner.compact().to_pairs();
+
+        assert_eq!(format!("{}", decoded), "[b(1, 2)]");
+        assert_eq!(decoded.as_str(), "b");
+    }
+
+    #[test]
+    fn round_trip_empty() {
+        let mut pairs = AbcParser::parse(Rule::a, "abcde").unwrap();
+        let compact = pairs.nth(1).unwrap().into_inner().compact();
+
+        assert!(compact.is_empty());
+        assert_eq!(compact.to_pairs().count(), 0);
+    }
+
+    #[test]
+    fn tokens_match() {
+        let pairs = AbcParser::parse(Rule::a, "abcde").unwrap();
+        let decoded = AbcParser::parse_compact(Rule::a, "abcde")
+            .unwrap()
+            .to_pairs();
+
+        assert_eq!(
+            decoded.tokens().collect::<Vec<_>>(),
+            pairs.tokens().collect::<Vec<_>>()
+        );
+    }
+}
@@ -72,7 +72,7 @@ impl<'i, R: RuleType> FlatPairs<'i, R> {
     /// ```
     #[inline]
     pub fn tokens(self) -> Tokens<'i, R> {
-        tokens::new(self.queue, self.input, self.start, self.end)
+        tokens::new_flat(self.queue, self.input, self.start, self.end)
     }
 
     fn next_start(&mut self) {
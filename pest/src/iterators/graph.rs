@@ -0,0 +1,284 @@
+// pest. The Elegant Parser
+// Copyright (c) 2018 Dragoș Tiselice
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+use alloc::format;
+use alloc::rc::Rc;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt;
+
+use super::pairs::Pairs;
+use crate::RuleType;
+
+/// Options for rendering [`Pairs`] as a graph with [`Pairs::to_dot_with`] and
+/// [`Pairs::to_mermaid_with`].
+///
+/// By default, every pair is shown, labeled with its rule and with its text truncated to 20
+/// characters.
+///
+/// [`Pairs`]: struct.Pairs.html
+/// [`Pairs::to_dot_with`]: struct.Pairs.html#method.to_dot_with
+/// [`Pairs::to_mermaid_with`]: struct.Pairs.html#method.to_mermaid_with
+pub struct GraphOptions<R> {
+    max_depth: Option<usize>,
+    text_width: usize,
+    filter: Option<Rc<dyn Fn(R) -> bool>>,
+}
+
+impl<R> GraphOptions<R> {
+    /// Creates the default options.
+    pub fn new() -> GraphOptions<R> {
+        GraphOptions {
+            max_depth: None,
+            text_width: 20,
+            filter: None,
+        }
+    }
+
+    /// Only shows the pairs nested under fewer than `max_depth` shown pairs, e.g. `1` only shows
+    /// the top-level pairs.
+    pub fn with_max_depth(mut self, max_depth: usize) -> GraphOptions<R> {
+        self.max_depth = Some(max_depth);
+        self
+    }
+
+    /// Truncates the text of the pairs to `text_width` characters in labels, where `0` leaves the
+    /// text out.
+    pub fn with_text_width(mut self, text_width: usize) -> GraphOptions<R> {
+        self.text_width = text_width;
+        self
+    }
+
+    /// Only shows the pairs whose rule matches `filter`. The inner pairs of hidden pairs are
+    /// attached to their closest shown ancestor.
+    pub fn with_rule_filter<F>(mut self, filter: F) -> GraphOptions<R>
+    where
+        F: Fn(R) -> bool + 'static,
+    {
+        self.filter = Some(Rc::new(filter));
+        self
+    }
+}
+
+impl<R> Clone for GraphOptions<R> {
+    fn clone(&self) -> GraphOptions<R> {
+        GraphOptions {
+            max_depth: self.max_depth,
+            text_width: self.text_width,
+            filter: self.filter.clone(),
+        }
+    }
+}
+
+impl<R> Default for GraphOptions<R> {
+    fn default() -> GraphOptions<R> {
+        GraphOptions::new()
+    }
+}
+
+impl<R> fmt::Debug for GraphOptions<R> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("GraphOptions")
+            .field("max_depth", &self.max_depth)
+            .field("text_width", &self.text_width)
+            .field("filter", &self.filter.is_some())
+            .finish()
+    }
+}
+
+struct Node<R> {
+    rule: R,
+    text: String,
+    parent: Option<usize>,
+}
+
+pub(crate) fn to_dot<R: RuleType>(pairs: &Pairs<'_, R>, options: &GraphOptions<R>) -> String {
+    let nodes = nodes(pairs, options);
+    let mut dot = String::from("digraph pairs {\n    node [shape=box];\n");
+
+    for (i, node) in nodes.iter().enumerate() {
+        let mut label = dot_escape(&format!("{:?}", node.rule));
+        if !node.text.is_empty() {
+            label.push_str("\\n");
+            label.push_str(&dot_escape(&node.text));
+        }
+        dot.push_str(&format!("    n{} [label=\"{}\"];\n", i, label));
+    }
+    for (i, node) in nodes.iter().enumerate() {
+        if let Some(parent) = node.parent {
+            dot.push_str(&format!("    n{} -> n{};\n", parent, i));
+        }
+    }
+
+    dot.push_str("}\n");
+    dot
+}
+
+pub(crate) fn to_mermaid<R: RuleType>(pairs: &Pairs<'_, R>, options: &GraphOptions<R>) -> String {
+    let nodes = nodes(pairs, options);
+    let mut mermaid = String::from("graph TD\n");
+
+    for (i, node) in nodes.iter().enumerate() {
+        let mut label = mermaid_escape(&format!("{:?}", node.rule));
+        if !node.text.is_empty() {
+            label.push_str("<br/>");
+            label.push_str(&mermaid_escape(&node.text));
+        }
+        mermaid.push_str(&format!("    n{}[\"{}\"]\n", i, label));
+    }
+    for (i, node) in nodes.iter().enumerate() {
+        if let Some(parent) = node.parent {
+            mermaid.push_str(&format!("    n{} --> n{}\n", parent, i));
+        }
+    }
+
+    mermaid
+}
+
+// Collects the shown pairs in pre-order, so that parents always come before their children.
+fn nodes<R: RuleType>(pairs: &Pairs<'_, R>, options: &GraphOptions<R>) -> Vec<Node<R>> {
+    let mut nodes = Vec::new();
+    collect(pairs.clone(), None, 0, options, &mut nodes);
+    nodes
+}
+
+fn collect<R: RuleType>(
+    pairs: Pairs<'_, R>,
+    parent: Option<usize>,
+    depth: usize,
+    options: &GraphOptions<R>,
+    nodes: &mut Vec<Node<R>>,
+) {
+    if options
+        .max_depth
+        .map_or(false, |max_depth| depth >= max_depth)
+    {
+        return;
+    }
+
+    for pair in pairs {
+        let shown = options
+            .filter
+            .as_ref()
+            .map_or(true, |filter| filter(pair.as_rule()));
+
+        if shown {
+            nodes.push(Node {
+                rule: pair.as_rule(),
+                text: truncate(pair.as_str(), options.text_width),
+                parent,
+            });
+            let index = nodes.len() - 1;
+            collect(pair.into_inner(), Some(index), depth + 1, options, nodes);
+        } else {
+            collect(pair.into_inner(), parent, depth, options, nodes);
+        }
+    }
+}
+
+// Quotes `text`, escaping it like a Rust string and truncating it to `width` characters.
+pub(super) fn truncate(text: &str, width: usize) -> String {
+    if width == 0 {
+        return String::new();
+    }
+
+    let mut truncated = String::from("\"");
+    for (i, c) in text.chars().enumerate() {
+        if i == width {
+            truncated.push('…');
+            break;
+        }
+        truncated.extend(c.escape_debug());
+    }
+    truncated.push('"');
+    truncated
+}
+
+fn dot_escape(label: &str) -> String {
+    let mut escaped = String::new();
+    for c in label.chars() {
+        if c == '"' || c == '\\' {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+fn mermaid_escape(label: &str) -> String {
+    let mut escaped = String::new();
+    for c in label.chars() {
+        match c {
+            '"' => escaped.push_str("#quot;"),
+            '#' => escaped.push_str("#35;"),
+            '<' => escaped.push_str("#lt;"),
+            '>' => escaped.push_str("#gt;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::super::macros::tests::*;
+    use super::super::super::Parser;
+    use super::GraphOptions;
+
+    #[test]
+    fn dot() {
+        let pairs = AbcParser::parse(Rule::a, "abcde").unwrap();
+
+        assert_eq!(
+            pairs.to_dot(),
+            "digraph pairs {\n    \
+                node [shape=box];\n    \
+                n0 [label=\"a\\n\\\"abc\\\"\"];\n    \
+                n1 [label=\"b\\n\\\"b\\\"\"];\n    \
+                n2 [label=\"c\\n\\\"e\\\"\"];\n    \
+                n0 -> n1;\n\
+             }\n"
+        );
+    }
+
+    #[test]
+    fn mermaid() {
+        let pairs = AbcParser::parse(Rule::a, "abcde").unwrap();
+
+        assert_eq!(
+            pairs.to_mermaid(),
+            "graph TD\n    \
+                n0[\"a<br/>#quot;abc#quot;\"]\n    \
+                n1[\"b<br/>#quot;b#quot;\"]\n    \
+                n2[\"c<br/>#quot;e#quot;\"]\n    \
+                n0 --> n1\n"
+        );
+    }
+
+    #[test]
+    fn options() {
+        let pairs = AbcParser::parse(Rule::a, "abcde").unwrap();
+
+        let options = GraphOptions::new().with_max_depth(1).with_text_width(2);
+        assert_eq!(
+            pairs.to_mermaid_with(&options),
+            "graph TD\n    \
+                n0[\"a<br/>#quot;ab…#quot;\"]\n    \
+                n1[\"c<br/>#quot;e#quot;\"]\n"
+        );
+
+        let options = GraphOptions::new()
+            .with_rule_filter(|rule| rule != Rule::a)
+            .with_text_width(0);
+        assert_eq!(
+            pairs.to_mermaid_with(&options),
+            "graph TD\n    n0[\"b\"]\n    n1[\"c\"]\n"
+        );
+    }
+}
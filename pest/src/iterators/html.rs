@@ -0,0 +1,192 @@
+// pest. The Elegant Parser
+// Copyright (c) 2018 Dragoș Tiselice
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+use alloc::format;
+use alloc::string::String;
+
+use super::graph::truncate;
+use super::pairs::{self, Pairs};
+use crate::RuleType;
+
+const STYLE: &str = "\
+body { display: flex; margin: 0; height: 100vh; font-family: sans-serif; }
+#input, #tree { flex: 1; margin: 0; padding: 1em; overflow: auto; }
+#input { border-right: 1px solid #ccc; font-family: monospace; white-space: pre-wrap; }
+#input .pair { border-bottom: 1px solid #ddd; }
+#tree ul { margin: 0; padding-left: 1.2em; list-style: none; }
+#tree code { color: #666; }
+#tree .node { display: list-item; list-style: none; cursor: pointer; }
+summary.node { display: list-item; }
+.hover { background: #ffe08a; }
+.selected { outline: 2px solid #e0a000; }
+";
+
+const SCRIPT: &str = "\
+function nodes(id) { return document.querySelectorAll('[data-id=\"' + id + '\"]'); }
+function closest(event) { return event.target.closest('[data-id]'); }
+document.addEventListener('mouseover', function (event) {
+  document.querySelectorAll('.hover').forEach(function (e) { e.classList.remove('hover'); });
+  var target = closest(event);
+  if (target) { nodes(target.dataset.id).forEach(function (e) { e.classList.add('hover'); }); }
+});
+document.getElementById('input').addEventListener('click', function (event) {
+  var target = closest(event);
+  if (!target) { return; }
+  document.querySelectorAll('.selected').forEach(function (e) { e.classList.remove('selected'); });
+  var node = document.querySelector('#tree [data-id=\"' + target.dataset.id + '\"]');
+  for (var e = node.parentElement; e; e = e.parentElement) {
+    if (e.tagName === 'DETAILS') { e.open = true; }
+  }
+  node.classList.add('selected');
+  node.scrollIntoView({ block: 'center' });
+});
+";
+
+pub(crate) fn to_html<R: RuleType>(pairs: &Pairs<'_, R>) -> String {
+    let input = pairs::input(pairs);
+    let mut source = String::new();
+    let mut tree = String::new();
+    let mut pos = 0;
+
+    write_pairs(
+        pairs.clone(),
+        input,
+        &mut pos,
+        &mut 0,
+        &mut source,
+        &mut tree,
+    );
+    source.push_str(&escape(&input[pos..]));
+
+    format!(
+        "<!DOCTYPE html>\n\
+         <html>\n\
+         <head>\n\
+         <meta charset=\"utf-8\">\n\
+         <title>pest parse tree</title>\n\
+         <style>\n{}</style>\n\
+         </head>\n\
+         <body>\n\
+         <pre id=\"input\">{}</pre>\n\
+         <div id=\"tree\"><ul>\n{}</ul></div>\n\
+         <script>\n{}</script>\n\
+         </body>\n\
+         </html>\n",
+        STYLE, source, tree, SCRIPT
+    )
+}
+
+// Writes the input covered by `pairs` from `pos` onwards, wrapping every pair in a span, along
+// with the matching tree items.
+fn write_pairs<R: RuleType>(
+    pairs: Pairs<'_, R>,
+    input: &str,
+    pos: &mut usize,
+    next_id: &mut usize,
+    source: &mut String,
+    tree: &mut String,
+) {
+    for pair in pairs {
+        let id = *next_id;
+        *next_id += 1;
+
+        let span = pair.as_span();
+        let rule = escape(&format!("{:?}", pair.as_rule()));
+        source.push_str(&escape(&input[*pos..span.start()]));
+        source.push_str(&format!(
+            "<span class=\"pair\" data-id=\"{}\" title=\"{} ({}..{})\">",
+            id,
+            rule,
+            span.start(),
+            span.end()
+        ));
+        *pos = span.start();
+
+        let label = format!(
+            "{} <code>{}</code>",
+            rule,
+            escape(&truncate(span.as_str(), 40))
+        );
+        let inner = pair.into_inner();
+        if inner.peek().is_some() {
+            tree.push_str(&format!(
+                "<li><details open><summary class=\"node\" data-id=\"{}\">{}</summary><ul>\n",
+                id, label
+            ));
+            write_pairs(inner, input, pos, next_id, source, tree);
+            tree.push_str("</ul></details></li>\n");
+        } else {
+            tree.push_str(&format!(
+                "<li class=\"node\" data-id=\"{}\">{}</li>\n",
+                id, label
+            ));
+        }
+
+        source.push_str(&escape(&input[*pos..span.end()]));
+        source.push_str("</span>");
+        *pos = span.end();
+    }
+}
+
+fn escape(text: &str) -> String {
+    let mut escaped = String::new();
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::super::macros::tests::*;
+    use super::super::super::Parser;
+
+    #[test]
+    fn html() {
+        let pairs = AbcParser::parse(Rule::a, "abcde").unwrap();
+        let html = pairs.to_html();
+
+        assert!(html.contains(
+            "<pre id=\"input\">\
+             <span class=\"pair\" data-id=\"0\" title=\"a (0..3)\">\
+             a<span class=\"pair\" data-id=\"1\" title=\"b (1..2)\">b</span>c\
+             </span>d\
+             <span class=\"pair\" data-id=\"2\" title=\"c (4..5)\">e</span>\
+             </pre>"
+        ));
+        assert!(html.contains(
+            "<li><details open><summary class=\"node\" data-id=\"0\">\
+             a <code>&quot;abc&quot;</code></summary><ul>\n\
+             <li class=\"node\" data-id=\"1\">b <code>&quot;b&quot;</code></li>\n\
+             </ul></details></li>\n\
+             <li class=\"node\" data-id=\"2\">c <code>&quot;e&quot;</code></li>\n"
+        ));
+    }
+
+    #[test]
+    fn escaped() {
+        use super::super::super::state;
+
+        let pairs = state("<&>", |state| {
+            state.rule(Rule::a, |s| s.match_string("<&>"))
+        })
+        .unwrap();
+        let html = pairs.to_html();
+
+        assert!(html.contains(">&lt;&amp;&gt;</span></pre>"));
+        assert!(!html.contains("<&>"));
+    }
+}
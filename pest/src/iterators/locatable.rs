@@ -0,0 +1,226 @@
+// pest. The Elegant Parser
+// Copyright (c) 2018 Dragoș Tiselice
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+use alloc::rc::Rc;
+use core::fmt;
+use core::ops::Deref;
+use core::ptr;
+
+use super::pair::Pair;
+use super::pairs::{self, Pairs};
+use crate::line_index::LineIndex;
+use crate::RuleType;
+
+/// An iterator over [`LocatablePair`]s, i.e. `Pairs` along with a shared [`LineIndex`] of their
+/// input, created by [`Pairs::locatable`] or [`Pairs::locatable_with`].
+///
+/// [`LocatablePair`]: struct.LocatablePair.html
+/// [`LineIndex`]: ../struct.LineIndex.html
+/// [`Pairs::locatable`]: struct.Pairs.html#method.locatable
+/// [`Pairs::locatable_with`]: struct.Pairs.html#method.locatable_with
+#[derive(Clone)]
+pub struct LocatablePairs<'i, R> {
+    pairs: Pairs<'i, R>,
+    index: Rc<LineIndex<'i>>,
+}
+
+pub fn new<'i, R: RuleType>(
+    pairs: Pairs<'i, R>,
+    index: Rc<LineIndex<'i>>,
+) -> LocatablePairs<'i, R> {
+    assert!(
+        ptr::eq(index.input(), pairs::input(&pairs)),
+        "the LineIndex is not of the input of the pairs"
+    );
+
+    LocatablePairs { pairs, index }
+}
+
+impl<'i, R: RuleType> LocatablePairs<'i, R> {
+    /// Returns the `LineIndex` of the input, which can be shared with other `LocatablePairs`.
+    #[inline]
+    pub fn line_index(&self) -> &Rc<LineIndex<'i>> {
+        &self.index
+    }
+
+    /// Returns the underlying `Pairs`.
+    #[inline]
+    pub fn into_pairs(self) -> Pairs<'i, R> {
+        self.pairs
+    }
+
+    fn locate(&self, pair: Pair<'i, R>) -> LocatablePair<'i, R> {
+        LocatablePair {
+            pair,
+            index: Rc::clone(&self.index),
+        }
+    }
+}
+
+impl<'i, R: RuleType> Iterator for LocatablePairs<'i, R> {
+    type Item = LocatablePair<'i, R>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let pair = self.pairs.next()?;
+        Some(self.locate(pair))
+    }
+}
+
+impl<'i, R: RuleType> DoubleEndedIterator for LocatablePairs<'i, R> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        let pair = self.pairs.next_back()?;
+        Some(self.locate(pair))
+    }
+}
+
+impl<'i, R: RuleType> fmt::Debug for LocatablePairs<'i, R> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.clone()).finish()
+    }
+}
+
+/// A `Pair` along with a shared [`LineIndex`] of its input, which locates it in constant time
+/// per line instead of scanning the input from its start. It dereferences to its `Pair`.
+///
+/// [`LineIndex`]: ../struct.LineIndex.html
+#[derive(Clone)]
+pub struct LocatablePair<'i, R> {
+    pair: Pair<'i, R>,
+    index: Rc<LineIndex<'i>>,
+}
+
+impl<'i, R: RuleType> LocatablePair<'i, R> {
+    /// Returns the 1-based `(line, col)` of the start of the pair.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use pest;
+    /// # #[allow(non_camel_case_types)]
+    /// # #[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+    /// enum Rule {
+    ///     a
+    /// }
+    ///
+    /// let input = "\nab";
+    /// let pairs = pest::state(input, |state| {
+    ///     // generating Token pair with Rule::a ...
+    /// #     state.match_string("\n").and_then(|s| s.rule(Rule::a, |s| s.match_string("ab")))
+    /// }).unwrap();
+    ///
+    /// let pair = pairs.locatable().next().unwrap();
+    /// assert_eq!(pair.line_col(), (2, 1));
+    /// assert_eq!(pair.end_line_col(), (2, 3));
+    /// ```
+    #[inline]
+    pub fn line_col(&self) -> (usize, usize) {
+        self.index.line_col(self.pair.as_span().start())
+    }
+
+    /// Returns the 1-based `(line, col)` of the end of the pair.
+    #[inline]
+    pub fn end_line_col(&self) -> (usize, usize) {
+        self.index.line_col(self.pair.as_span().end())
+    }
+
+    /// Returns the inner pairs of the pair, sharing its `LineIndex`.
+    #[inline]
+    pub fn into_inner(self) -> LocatablePairs<'i, R> {
+        LocatablePairs {
+            pairs: self.pair.into_inner(),
+            index: self.index,
+        }
+    }
+
+    /// Returns the `LineIndex` of the input, which can be shared with other `LocatablePairs`.
+    #[inline]
+    pub fn line_index(&self) -> &Rc<LineIndex<'i>> {
+        &self.index
+    }
+
+    /// Returns the underlying `Pair`.
+    #[inline]
+    pub fn into_pair(self) -> Pair<'i, R> {
+        self.pair
+    }
+}
+
+impl<'i, R> Deref for LocatablePair<'i, R> {
+    type Target = Pair<'i, R>;
+
+    fn deref(&self) -> &Pair<'i, R> {
+        &self.pair
+    }
+}
+
+impl<'i, R: RuleType> fmt::Debug for LocatablePair<'i, R> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LocatablePair")
+            .field("pair", &self.pair)
+            .field("line_col", &self.line_col())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::line_index::LineIndex;
+    use crate::macros::tests::*;
+    use crate::parser::Parser;
+    use alloc::rc::Rc;
+    use alloc::string::String;
+    use alloc::vec::Vec;
+
+    #[test]
+    fn shared_index() {
+        let input = "abc\ne\nabcde";
+        let index = Rc::new(LineIndex::new(input));
+
+        let first = AbcParser::parse_span(Rule::a, crate::Span::new(input, 0, 5).unwrap())
+            .unwrap()
+            .locatable_with(Rc::clone(&index));
+        let second = AbcParser::parse_span(Rule::a, crate::Span::new(input, 6, 11).unwrap())
+            .unwrap()
+            .locatable_with(Rc::clone(&index));
+
+        let line_cols: Vec<_> = first
+            .chain(second)
+            .map(|pair| (pair.as_rule(), pair.line_col()))
+            .collect();
+        assert_eq!(
+            line_cols,
+            [
+                (Rule::a, (1, 1)),
+                (Rule::c, (2, 1)),
+                (Rule::a, (3, 1)),
+                (Rule::c, (3, 5))
+            ]
+        );
+        assert_eq!(Rc::strong_count(&index), 1);
+
+        let pair = AbcParser::parse(Rule::a, input)
+            .unwrap()
+            .next()
+            .unwrap()
+            .locatable_with(Rc::clone(&index));
+        assert!(Rc::ptr_eq(pair.line_index(), &index));
+        let inner: Vec<_> = pair.into_inner().map(|pair| pair.end_line_col()).collect();
+        assert_eq!(inner, [(1, 3)]);
+    }
+
+    #[test]
+    #[should_panic(expected = "the LineIndex is not of the input of the pairs")]
+    fn other_input() {
+        let other = String::from("abcde");
+        let index = Rc::new(LineIndex::new(&other));
+        let _ = AbcParser::parse(Rule::a, "abcde")
+            .unwrap()
+            .locatable_with(index);
+    }
+}
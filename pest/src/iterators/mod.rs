@@ -9,14 +9,31 @@
 
 //! Types and iterators for parser output.
 
+pub(crate) mod compact;
 mod flat_pairs;
+mod graph;
+mod html;
+mod locatable;
+#[cfg(feature = "std")]
+mod owned;
 mod pair;
 pub(crate) mod pairs;
 mod queueable_token;
+mod raw_tokens;
+mod rule_path;
+#[cfg(feature = "serde")]
+mod serialize;
 mod tokens;
 
+pub use self::compact::CompactPairs;
 pub use self::flat_pairs::FlatPairs;
+pub use self::graph::GraphOptions;
+pub use self::locatable::{LocatablePair, LocatablePairs};
+#[cfg(feature = "std")]
+pub use self::owned::OwnedPairs;
 pub use self::pair::Pair;
 pub use self::pairs::Pairs;
 pub(crate) use self::queueable_token::QueueableToken;
-pub use self::tokens::Tokens;
+pub use self::raw_tokens::{RawToken, RawTokens};
+pub use self::rule_path::RulePath;
+pub use self::tokens::{TokenPairs, Tokens};
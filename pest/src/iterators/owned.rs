@@ -0,0 +1,342 @@
+// pest. The Elegant Parser
+// Copyright (c) 2018 Dragoș Tiselice
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+use alloc::collections::{BTreeMap, BTreeSet};
+use alloc::format;
+use alloc::rc::Rc;
+use alloc::string::String;
+use alloc::vec::Vec;
+use std::io;
+
+use super::compact::{read_varint, write_varint};
+use super::pairs::{self, Pairs};
+use super::queueable_token::QueueableToken;
+use crate::RuleCode;
+
+const MAGIC: &[u8; 4] = b"pest";
+const VERSION: u8 = 2;
+
+/// Parse results read back from the binary format written by [`Pairs::write_to`], together with
+/// the input they were parsed from.
+///
+/// The format only uses variable-length integers and little-endian rule codes, so it reads the
+/// same on every platform. It starts with a header made of the version of the format and of the
+/// name of the rule of every [code] it uses, followed by the input and by every token as a delta
+/// from the position of the previous token, with the code of its rule for end tokens. Data whose
+/// codes name other rules than those of `R`, e.g. because the grammar changed since it was
+/// written, is rejected.
+///
+/// [`Pairs::write_to`]: struct.Pairs.html#method.write_to
+/// [code]: ../trait.RuleCode.html
+#[derive(Clone, Debug)]
+pub struct OwnedPairs<R> {
+    input: String,
+    queue: Rc<Vec<QueueableToken<R>>>,
+}
+
+pub fn write<R: RuleCode, W: io::Write>(
+    queue: &[QueueableToken<R>],
+    input: &str,
+    writer: &mut W,
+) -> io::Result<()> {
+    let rules: BTreeMap<_, _> = queue
+        .iter()
+        .filter_map(|token| match *token {
+            QueueableToken::End { rule, .. } => Some((rule.code(), rule)),
+            QueueableToken::Start { .. } => None,
+        })
+        .collect();
+
+    let mut bytes = Vec::with_capacity(MAGIC.len() + 1 + input.len() + queue.len() * 2);
+    bytes.extend_from_slice(MAGIC);
+    bytes.push(VERSION);
+    write_varint(&mut bytes, rules.len());
+    for (code, rule) in rules {
+        let name = format!("{:?}", rule);
+        bytes.extend_from_slice(&code.to_le_bytes());
+        write_varint(&mut bytes, name.len());
+        bytes.extend_from_slice(name.as_bytes());
+    }
+    write_varint(&mut bytes, input.len());
+    bytes.extend_from_slice(input.as_bytes());
+    write_varint(&mut bytes, queue.len());
+
+    let mut last_pos = 0;
+    for token in queue {
+        match *token {
+            QueueableToken::Start { input_pos, .. } => {
+                write_varint(&mut bytes, (input_pos - last_pos) << 1);
+                last_pos = input_pos;
+            }
+            QueueableToken::End {
+                rule, input_pos, ..
+            } => {
+                write_varint(&mut bytes, (input_pos - last_pos) << 1 | 1);
+                bytes.extend_from_slice(&rule.code().to_le_bytes());
+                last_pos = input_pos;
+            }
+        }
+    }
+
+    writer.write_all(&bytes)
+}
+
+impl<R: RuleCode> OwnedPairs<R> {
+    /// Reads `OwnedPairs` written by [`Pairs::write_to`] from `reader`. Byte slices implement
+    /// [`io::Read`], so memory-mapped files can be read directly.
+    ///
+    /// Fails with [`io::ErrorKind::InvalidData`] if the data is not in the format, e.g. because
+    /// it was written in another version of the format, or its rule codes are unknown to `R` or
+    /// name other rules.
+    ///
+    /// [`Pairs::write_to`]: struct.Pairs.html#method.write_to
+    /// [`io::Read`]: https://doc.rust-lang.org/std/io/trait.Read.html
+    /// [`io::ErrorKind::InvalidData`]: https://doc.rust-lang.org/std/io/enum.ErrorKind.html#variant.InvalidData
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use pest::RuleCode;
+    /// # use pest::iterators::OwnedPairs;
+    /// # #[allow(non_camel_case_types)]
+    /// # #[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+    /// enum Rule {
+    ///     a
+    /// }
+    ///
+    /// impl RuleCode for Rule {
+    ///     fn code(self) -> u16 {
+    ///         self as u16
+    ///     }
+    ///
+    ///     fn from_code(code: u16) -> Option<Rule> {
+    ///         match code {
+    ///             0 => Some(Rule::a),
+    ///             _ => None,
+    ///         }
+    ///     }
+    /// }
+    ///
+    /// let input = "a";
+    /// let pairs = pest::state(input, |state| {
+    ///     // generating Token pair with Rule::a ...
+    /// #     state.rule(Rule::a, |s| s.match_string("a"))
+    /// }).unwrap();
+    ///
+    /// let mut bytes = vec![];
+    /// pairs.write_to(&mut bytes).unwrap();
+    /// let owned = OwnedPairs::<Rule>::read_from(&bytes[..]).unwrap();
+    ///
+    /// assert_eq!(format!("{}", owned.pairs()), format!("{}", pairs));
+    /// ```
+    pub fn read_from<Rd: io::Read>(mut reader: Rd) -> io::Result<OwnedPairs<R>> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes)?;
+
+        if !bytes.starts_with(MAGIC) {
+            return Err(invalid("missing pest header"));
+        }
+        let mut bytes = bytes[MAGIC.len()..].iter();
+        if bytes.next() != Some(&VERSION) {
+            return Err(invalid("unsupported format version"));
+        }
+
+        let len = read_varint(&mut bytes).ok_or_else(|| invalid("truncated rule table"))?;
+        let mut codes = BTreeSet::new();
+        for _ in 0..len {
+            let code = read_code(&mut bytes)?;
+            let len = read_varint(&mut bytes).ok_or_else(|| invalid("truncated rule table"))?;
+            let rest = bytes.as_slice();
+            if rest.len() < len {
+                return Err(invalid("truncated rule table"));
+            }
+            let (name, rest) = rest.split_at(len);
+            bytes = rest.iter();
+
+            match R::from_code(code) {
+                Some(rule) if format!("{:?}", rule).as_bytes() == name => codes.insert(code),
+                _ => return Err(invalid("rule table does not match the rules")),
+            };
+        }
+
+        let len = read_varint(&mut bytes).ok_or_else(|| invalid("truncated input length"))?;
+        let rest = bytes.as_slice();
+        if rest.len() < len {
+            return Err(invalid("truncated input"));
+        }
+        let (input, rest) = rest.split_at(len);
+        let input = String::from_utf8(input.to_vec()).map_err(|_| invalid("input is not UTF-8"))?;
+        let mut bytes = rest.iter();
+
+        let len = read_varint(&mut bytes).ok_or_else(|| invalid("truncated token count"))?;
+        let mut queue = Vec::with_capacity(len.min(bytes.len()));
+        let mut starts = Vec::new();
+        let mut last_pos = 0usize;
+
+        for _ in 0..len {
+            let value = read_varint(&mut bytes).ok_or_else(|| invalid("truncated token"))?;
+            let input_pos = last_pos
+                .checked_add(value >> 1)
+                .filter(|&pos| input.is_char_boundary(pos))
+                .ok_or_else(|| invalid("token position out of the input"))?;
+            last_pos = input_pos;
+
+            if value & 1 == 1 {
+                let code = read_code(&mut bytes)?;
+                let rule = R::from_code(code)
+                    .filter(|_| codes.contains(&code))
+                    .ok_or_else(|| invalid("unknown rule code"))?;
+                let start_token_index = starts.pop().ok_or_else(|| invalid("unbalanced tokens"))?;
+                let end_token_index = queue.len();
+
+                if let QueueableToken::Start {
+                    end_token_index: ref mut index,
+                    ..
+                } = queue[start_token_index]
+                {
+                    *index = end_token_index;
+                }
+
+                queue.push(QueueableToken::End {
+                    start_token_index,
+                    rule,
+                    input_pos,
+                });
+            } else {
+                starts.push(queue.len());
+                queue.push(QueueableToken::Start {
+                    end_token_index: 0,
+                    input_pos,
+                });
+            }
+        }
+
+        if !starts.is_empty() {
+            return Err(invalid("unbalanced tokens"));
+        }
+        if bytes.next().is_some() {
+            return Err(invalid("trailing bytes"));
+        }
+
+        Ok(OwnedPairs {
+            input,
+            queue: Rc::new(queue),
+        })
+    }
+
+    /// Returns the input the `OwnedPairs` were parsed from.
+    #[inline]
+    pub fn input(&self) -> &str {
+        &self.input
+    }
+
+    /// Returns the `Pairs` borrowing the input of the `OwnedPairs`.
+    #[inline]
+    pub fn pairs(&self) -> Pairs<'_, R> {
+        pairs::new(Rc::clone(&self.queue), &self.input, 0, self.queue.len())
+    }
+}
+
+fn read_code(bytes: &mut core::slice::Iter<'_, u8>) -> io::Result<u16> {
+    match (bytes.next(), bytes.next()) {
+        (Some(&low), Some(&high)) => Ok(u16::from_le_bytes([low, high])),
+        _ => Err(invalid("truncated rule code")),
+    }
+}
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::super::macros::tests::*;
+    use super::super::super::{Parser, RuleCode};
+    use super::OwnedPairs;
+    use alloc::format;
+    use alloc::vec::Vec;
+
+    impl RuleCode for Rule {
+        fn code(self) -> u16 {
+            self as u16
+        }
+
+        fn from_code(code: u16) -> Option<Rule> {
+            match code {
+                0 => Some(Rule::a),
+                1 => Some(Rule::b),
+                2 => Some(Rule::c),
+                _ => None,
+            }
+        }
+    }
+
+    #[test]
+    fn round_trip() {
+        let pairs = AbcParser::parse(Rule::a, "abcde").unwrap();
+        let mut bytes = Vec::new();
+        pairs.write_to(&mut bytes).unwrap();
+
+        let owned = OwnedPairs::<Rule>::read_from(&bytes[..]).unwrap();
+
+        assert_eq!(owned.input(), "abcde");
+        assert_eq!(format!("{}", owned.pairs()), format!("{}", pairs));
+        assert_eq!(
+            format!("{:?}", owned.pairs().tokens().collect::<Vec<_>>()),
+            format!("{:?}", pairs.tokens().collect::<Vec<_>>())
+        );
+    }
+
+    #[test]
+    fn round_trip_inner() {
+        let pairs = AbcParser::parse(Rule::a, "abcde").unwrap();
+        let inner = pairs.clone().next().unwrap().into_inner();
+        let mut bytes = Vec::new();
+        inner.write_to(&mut bytes).unwrap();
+
+        let owned = OwnedPairs::<Rule>::read_from(&bytes[..]).unwrap();
+
+        assert_eq!(owned.input(), "abcde");
+        assert_eq!(format!("{}", owned.pairs()), "[b(1, 2)]");
+    }
+
+    #[test]
+    fn invalid() {
+        let pairs = AbcParser::parse(Rule::a, "abcde").unwrap();
+        let mut bytes = Vec::new();
+        pairs.write_to(&mut bytes).unwrap();
+
+        assert!(OwnedPairs::<Rule>::read_from(&bytes[1..]).is_err());
+        assert!(OwnedPairs::<Rule>::read_from(&bytes[..bytes.len() - 1]).is_err());
+
+        let last = bytes.len() - 2;
+        bytes[last] = 3;
+        let error = OwnedPairs::<Rule>::read_from(&bytes[..]).unwrap_err();
+        assert_eq!(format!("{}", error), "unknown rule code");
+    }
+
+    #[test]
+    fn header() {
+        let pairs = AbcParser::parse(Rule::a, "abcde").unwrap();
+        let mut bytes = Vec::new();
+        pairs.write_to(&mut bytes).unwrap();
+
+        // The version, then the codes and names of `a`, `b` and `c`.
+        assert!(bytes.starts_with(b"pest\x02\x03\x00\x00\x01a\x01\x00\x01b\x02\x00\x01c"));
+
+        let mut renamed = bytes.clone();
+        renamed[9] = b'z';
+        let error = OwnedPairs::<Rule>::read_from(&renamed[..]).unwrap_err();
+        assert_eq!(format!("{}", error), "rule table does not match the rules");
+
+        bytes[4] = 1;
+        let error = OwnedPairs::<Rule>::read_from(&bytes[..]).unwrap_err();
+        assert_eq!(format!("{}", error), "unsupported format version");
+    }
+}
@@ -17,13 +17,13 @@ use core::hash::{Hash, Hasher};
 use core::ptr;
 use core::str;
 
-#[cfg(feature = "pretty-print")]
-use serde::ser::SerializeStruct;
-
+use super::locatable::LocatablePair;
 use super::pairs::{self, Pairs};
 use super::queueable_token::QueueableToken;
+use super::rule_path::RulePath;
 use super::tokens::{self, Tokens};
-use crate::span::{self, Span};
+use crate::line_index::LineIndex;
+use crate::span::Span;
 use crate::RuleType;
 
 /// A matching pair of [`Token`]s and everything between them.
@@ -120,6 +120,94 @@ impl<'i, R: RuleType> Pair<'i, R> {
         &self.input[start..end]
     }
 
+    /// Captures a slice from the `&str` defined by the token `Pair`, without its leading and
+    /// trailing whitespace, e.g. implicit whitespace matched at the end of a rule ending with an
+    /// optional expression. Whitespace is recognized with [`char::is_whitespace`].
+    ///
+    /// [`char::is_whitespace`]: https://doc.rust-lang.org/std/primitive.char.html#method.is_whitespace
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use std::rc::Rc;
+    /// # use pest;
+    /// # #[allow(non_camel_case_types)]
+    /// # #[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+    /// enum Rule {
+    ///     ab
+    /// }
+    ///
+    /// let input = "ab  ";
+    /// let pair = pest::state(input, |state| {
+    ///     // generating Token pair with Rule::ab ...
+    /// #     state.rule(Rule::ab, |s| s.match_string("ab  "))
+    /// }).unwrap().next().unwrap();
+    ///
+    /// assert_eq!(pair.as_str_trimmed(), "ab");
+    /// ```
+    #[inline]
+    pub fn as_str_trimmed(&self) -> &'i str {
+        self.as_str().trim()
+    }
+
+    /// Returns the `Span` defined by the `Pair` without the inner pairs of the given `rules` at
+    /// its edges, e.g. without the quotes of a string literal. Inner pairs are only excluded when
+    /// nothing but whitespace separates them from the corresponding edge, or from another excluded
+    /// pair.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use std::rc::Rc;
+    /// # use pest;
+    /// # #[allow(non_camel_case_types)]
+    /// # #[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+    /// enum Rule {
+    ///     string,
+    ///     quote
+    /// }
+    ///
+    /// let input = "\"ab\"";
+    /// let pair = pest::state(input, |state| {
+    ///     state.rule(Rule::string, |s| {
+    ///         s.rule(Rule::quote, |s| s.match_string("\""))
+    ///             .and_then(|s| s.match_string("ab"))
+    ///             .and_then(|s| s.rule(Rule::quote, |s| s.match_string("\"")))
+    ///     })
+    /// }).unwrap().next().unwrap();
+    ///
+    /// assert_eq!(pair.text_range_excluding(&[Rule::quote]).as_str(), "ab");
+    /// ```
+    pub fn text_range_excluding(&self, rules: &[R]) -> Span<'i> {
+        let mut start = self.pos(self.start);
+        let mut end = self.pos(self.pair());
+        let mut inner = self.clone().into_inner();
+
+        let excluded = |pair: &Pair<'i, R>| rules.contains(&pair.as_rule());
+
+        while let Some(pair) = inner.peek() {
+            let span = pair.as_span();
+            if !excluded(&pair) || !self.input[start..span.start()].trim().is_empty() {
+                break;
+            }
+
+            start = span.end();
+            inner.next();
+        }
+        while let Some(pair) = inner.clone().next_back() {
+            let span = pair.as_span();
+            if !excluded(&pair) || !self.input[span.end()..end].trim().is_empty() {
+                break;
+            }
+
+            end = span.start();
+            inner.next_back();
+        }
+
+        // Generated positions always come from Positions and are UTF-8 borders.
+        unsafe { Span::new_unchecked(self.input, start, end) }
+    }
+
     /// Returns the `Span` defined by the `Pair`, consuming it.
     ///
     /// # Examples
@@ -174,7 +262,7 @@ impl<'i, R: RuleType> Pair<'i, R> {
         let end = self.pos(self.pair());
 
         // Generated positions always come from Positions and are UTF-8 borders.
-        unsafe { span::Span::new_unchecked(self.input, start, end) }
+        unsafe { Span::new_unchecked(self.input, start, end) }
     }
 
     /// Returns the inner `Pairs` between the `Pair`, consuming it.
@@ -205,6 +293,41 @@ impl<'i, R: RuleType> Pair<'i, R> {
         pairs::new(self.queue, self.input, self.start + 1, pair)
     }
 
+    /// Returns the pairs of `rule` nested anywhere in the `Pair`, not including itself, like
+    /// [`Pairs::find_rule_path`] with `&[rule]` for its inner pairs.
+    ///
+    /// [`Pairs::find_rule_path`]: struct.Pairs.html#method.find_rule_path
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use pest;
+    /// # #[allow(non_camel_case_types)]
+    /// # #[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+    /// enum Rule {
+    ///     list,
+    ///     item
+    /// }
+    ///
+    /// let input = "a[b]";
+    /// let pair = pest::state(input, |state| {
+    ///     // generating Token pairs for an item a and a nested list with item b ...
+    /// #     state.rule(Rule::list, |s| {
+    /// #         s.rule(Rule::item, |s| s.match_string("a"))
+    /// #             .and_then(|s| s.match_string("["))
+    /// #             .and_then(|s| s.rule(Rule::list, |s| s.rule(Rule::item, |s| s.match_string("b"))))
+    /// #             .and_then(|s| s.match_string("]"))
+    /// #     })
+    /// }).unwrap().next().unwrap();
+    ///
+    /// let items: Vec<_> = pair.descendants(Rule::item).map(|item| item.as_str()).collect();
+    /// assert_eq!(items, ["a", "b"]);
+    /// ```
+    #[inline]
+    pub fn descendants(&self, rule: R) -> RulePath<'i, R> {
+        self.clone().into_inner().find_rule_path(&[rule])
+    }
+
     /// Returns the `Tokens` for the `Pair`.
     ///
     /// # Examples
@@ -267,6 +390,24 @@ impl<'i, R: RuleType> Pairs<'i, R> {
     }
 }
 
+impl<'i, R: RuleType> Pair<'i, R> {
+    /// Returns the `Pair` as a `LocatablePair` sharing `index`, like
+    /// [`Pairs::locatable_with`], e.g. to locate a pair of `FlatPairs`.
+    ///
+    /// [`Pairs::locatable_with`]: struct.Pairs.html#method.locatable_with
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is a `LineIndex` of another input than the one of the `Pair`.
+    #[inline]
+    pub fn locatable_with(self, index: Rc<LineIndex<'i>>) -> LocatablePair<'i, R> {
+        Pairs::single(self)
+            .locatable_with(index)
+            .next()
+            .expect("a single pair")
+    }
+}
+
 impl<'i, R: RuleType> fmt::Debug for Pair<'i, R> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_struct("Pair")
@@ -320,31 +461,6 @@ impl<'i, R: Hash> Hash for Pair<'i, R> {
     }
 }
 
-#[cfg(feature = "pretty-print")]
-impl<'i, R: RuleType> ::serde::Serialize for Pair<'i, R> {
-    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
-    where
-        S: ::serde::Serializer,
-    {
-        let start = self.pos(self.start);
-        let end = self.pos(self.pair());
-        let rule = format!("{:?}", self.as_rule());
-        let inner = self.clone().into_inner();
-
-        let mut ser = serializer.serialize_struct("Pairs", 3)?;
-        ser.serialize_field("pos", &(start, end))?;
-        ser.serialize_field("rule", &rule)?;
-
-        if inner.peek().is_none() {
-            ser.serialize_field("inner", &self.as_str())?;
-        } else {
-            ser.serialize_field("inner", &inner)?;
-        }
-
-        ser.end()
-    }
-}
-
 #[cfg(test)]
 mod tests {
     use crate::macros::tests::*;
@@ -382,6 +498,33 @@ mod tests {
         assert_eq!(expected, pair.to_json());
     }
 
+    #[test]
+    fn text_range_excluding() {
+        let pair = AbcParser::parse(Rule::a, "abcde").unwrap().next().unwrap();
+
+        // `b` is surrounded by `a` and `c`, which are not whitespace.
+        assert_eq!(pair.text_range_excluding(&[Rule::b]).as_str(), "abc");
+        assert_eq!(pair.text_range_excluding(&[]).as_str(), "abc");
+
+        let pair = crate::state("'x' '", |state| {
+            state.rule(Rule::a, |state| {
+                state
+                    .rule(Rule::b, |state| state.match_string("'"))
+                    .and_then(|state| state.match_string("x"))
+                    .and_then(|state| state.rule(Rule::b, |state| state.match_string("'")))
+                    .and_then(|state| state.match_string(" "))
+                    .and_then(|state| state.rule(Rule::b, |state| state.match_string("'")))
+            })
+        })
+        .unwrap()
+        .next()
+        .unwrap();
+
+        assert_eq!(pair.text_range_excluding(&[Rule::b]).as_str(), "x");
+        assert_eq!(pair.text_range_excluding(&[Rule::c]).as_str(), "'x' '");
+        assert_eq!(pair.as_str_trimmed(), "'x' '");
+    }
+
     #[test]
     fn pair_into_inner() {
         let pair = AbcParser::parse(Rule::a, "abcde").unwrap().next().unwrap(); // the tokens a(b())
@@ -16,13 +16,22 @@ use core::hash::{Hash, Hasher};
 use core::ptr;
 use core::str;
 
-#[cfg(feature = "pretty-print")]
-use serde::ser::SerializeStruct;
-
+use super::compact::{self, CompactPairs};
 use super::flat_pairs::{self, FlatPairs};
+use super::graph::{self, GraphOptions};
+use super::html;
+use super::locatable::{self, LocatablePairs};
+#[cfg(feature = "std")]
+use super::owned;
 use super::pair::{self, Pair};
 use super::queueable_token::QueueableToken;
+use super::raw_tokens::{self, RawTokens};
+use super::rule_path::{self, RulePath};
 use super::tokens::{self, Tokens};
+use crate::line_index::LineIndex;
+use crate::span::Span;
+#[cfg(feature = "std")]
+use crate::RuleCode;
 use crate::RuleType;
 
 /// An iterator over [`Pair`]s. It is created by [`pest::state`] and [`Pair::into_inner`].
@@ -36,6 +45,8 @@ pub struct Pairs<'i, R> {
     input: &'i str,
     start: usize,
     end: usize,
+    // The index of the input precomputed by the parse, if any, which `locatable` shares.
+    line_index: Option<Rc<LineIndex<'i>>>,
 }
 
 pub fn new<R: RuleType>(
@@ -49,6 +60,121 @@ pub fn new<R: RuleType>(
         input,
         start,
         end,
+        line_index: None,
+    }
+}
+
+/// Gives `pairs` the precomputed `index` of their input.
+pub fn with_line_index<'i, R>(mut pairs: Pairs<'i, R>, index: Rc<LineIndex<'i>>) -> Pairs<'i, R> {
+    pairs.line_index = Some(index);
+    pairs
+}
+
+/// Takes the shared queue of `pairs` out of them, unless other pairs share it.
+pub fn into_queue<R>(pairs: Pairs<'_, R>) -> Option<Rc<Vec<QueueableToken<R>>>> {
+    let mut queue = pairs.queue;
+    Rc::get_mut(&mut queue)?;
+    Some(queue)
+}
+
+/// Maps the rules of `pairs` with `f`.
+pub fn map_rules<'i, R: RuleType, T: RuleType, F>(pairs: Pairs<'i, R>, mut f: F) -> Pairs<'i, T>
+where
+    F: FnMut(R) -> T,
+{
+    let queue = pairs
+        .queue
+        .iter()
+        .map(|token| match *token {
+            QueueableToken::Start {
+                end_token_index,
+                input_pos,
+            } => QueueableToken::Start {
+                end_token_index,
+                input_pos,
+            },
+            QueueableToken::End {
+                start_token_index,
+                rule,
+                input_pos,
+            } => QueueableToken::End {
+                start_token_index,
+                rule: f(rule),
+                input_pos,
+            },
+        })
+        .collect();
+
+    new(Rc::new(queue), pairs.input, pairs.start, pairs.end)
+}
+
+/// Moves `pairs` parsed from a substring of `input` starting at byte `offset` over to `input`.
+pub fn rebase<'i, R: RuleType>(pairs: Pairs<'i, R>, input: &'i str, offset: usize) -> Pairs<'i, R> {
+    let mut queue = Rc::try_unwrap(pairs.queue).unwrap_or_else(|queue| (*queue).clone());
+
+    for token in &mut queue {
+        match token {
+            QueueableToken::Start { input_pos, .. } | QueueableToken::End { input_pos, .. } => {
+                *input_pos += offset
+            }
+        }
+    }
+
+    new(Rc::new(queue), input, pairs.start, pairs.end)
+}
+
+/// Returns the token queue of `pairs`.
+pub fn queue<'a, R>(pairs: &'a Pairs<'_, R>) -> &'a Rc<Vec<QueueableToken<R>>> {
+    &pairs.queue
+}
+
+/// Returns the input of `pairs`.
+pub fn input<'i, R>(pairs: &Pairs<'i, R>) -> &'i str {
+    pairs.input
+}
+
+/// Returns the index of the next token of `pairs`, which grows as they are consumed.
+pub fn cursor<R>(pairs: &Pairs<'_, R>) -> usize {
+    pairs.start
+}
+
+/// Returns the index one past the last token of `pairs`.
+pub fn end<R>(pairs: &Pairs<'_, R>) -> usize {
+    pairs.end
+}
+
+/// Returns the byte position where the next `Pair` of `pairs` starts, or, if there is none, where
+/// the last consumed one ends, or where their parent starts if none was consumed.
+pub fn next_pos<R: RuleType>(pairs: &Pairs<'_, R>) -> usize {
+    if pairs.start < pairs.end {
+        pairs.pos(pairs.start)
+    } else if pairs.start > 0 {
+        pairs.pos(pairs.start - 1)
+    } else {
+        0
+    }
+}
+
+/// Returns the span of the `Pair`s consumed from `before` to get `after`, or an empty span at the
+/// next position of `before` if there are none.
+pub fn consumed_span<'i, R: RuleType>(before: &Pairs<'i, R>, after: &Pairs<'i, R>) -> Span<'i> {
+    let start = next_pos(before);
+    let end = if after.start > before.start {
+        after.pos(after.start - 1)
+    } else {
+        start
+    };
+
+    // Positions come from the parse of the input.
+    Span::new(before.input, start, end).unwrap()
+}
+
+/// Returns the byte position where the last `Pair` of `pairs` ends, if there is any.
+pub fn end_pos<R: RuleType>(pairs: &Pairs<'_, R>) -> Option<usize> {
+    if pairs.start < pairs.end {
+        Some(pairs.pos(pairs.end - 1))
+    } else {
+        None
     }
 }
 
@@ -177,6 +303,162 @@ impl<'i, R: RuleType> Pairs<'i, R> {
         tokens::new(self.queue, self.input, self.start, self.end)
     }
 
+    /// Returns a read-only view of the token queue backing the `Pairs`, yielding one
+    /// [`RawToken`] per pair, nested ones included, in pre-order. This is meant for consumers
+    /// that build their own trees and want to skip the construction of `Pair`s.
+    ///
+    /// [`RawToken`]: struct.RawToken.html
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use std::rc::Rc;
+    /// # use pest;
+    /// # use pest::iterators::RawToken;
+    /// # #[allow(non_camel_case_types)]
+    /// # #[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+    /// enum Rule {
+    ///     a,
+    ///     b
+    /// }
+    ///
+    /// let input = "ab";
+    /// let pairs = pest::state(input, |state| {
+    ///     // generating Token pair with Rule::a containing Rule::b ...
+    /// #     state.rule(Rule::a, |s| s.skip(1).and_then(|s| s.rule(Rule::b, |s| s.skip(1))))
+    /// }).unwrap();
+    /// let tokens: Vec<_> = pairs.raw_tokens().collect();
+    ///
+    /// assert_eq!(
+    ///     tokens[1],
+    ///     RawToken { rule: Rule::b, start: 1, end: 2, parent_index: Some(0) }
+    /// );
+    /// ```
+    #[inline]
+    pub fn raw_tokens(&self) -> RawTokens<'_, R> {
+        raw_tokens::new(&self.queue, self.start, self.end)
+    }
+
+    /// Encodes the `Pairs` into `CompactPairs`, which use a fraction of the memory of the token queue
+    /// backing `Pairs` and can be decoded back with [`CompactPairs::to_pairs`].
+    ///
+    /// [`CompactPairs::to_pairs`]: struct.CompactPairs.html#method.to_pairs
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use pest;
+    /// # #[allow(non_camel_case_types)]
+    /// # #[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+    /// enum Rule {
+    ///     a
+    /// }
+    ///
+    /// let input = "a";
+    /// let pairs = pest::state(input, |state| {
+    ///     // generating Token pair with Rule::a ...
+    /// #     state.rule(Rule::a, |s| s.match_string("a"))
+    /// }).unwrap();
+    /// let compact = pairs.compact();
+    ///
+    /// assert_eq!(compact.to_pairs().as_str(), "a");
+    /// ```
+    #[inline]
+    pub fn compact(self) -> CompactPairs<'i, R> {
+        compact::new(&self.queue, self.input, self.start, self.end)
+    }
+
+    /// Searches the `Pairs` and all their nested pairs for the pairs of the last rule of `path`
+    /// which are nested in pairs of the other rules of `path`, in order, like a CSS descendant
+    /// selector, e.g. `[Rule::function, Rule::ident]` finds the identifiers anywhere in a
+    /// function. The pairs are found lazily, in the order in which they start, and nothing is
+    /// found for an empty `path`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use pest;
+    /// # #[allow(non_camel_case_types)]
+    /// # #[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+    /// enum Rule {
+    ///     function,
+    ///     param,
+    ///     ident
+    /// }
+    ///
+    /// let input = "f(x)";
+    /// let pairs = pest::state(input, |state| {
+    ///     // generating nested Token pairs for function f with param x ...
+    /// #     state.rule(Rule::function, |s| {
+    /// #         s.rule(Rule::ident, |s| s.match_string("f"))
+    /// #             .and_then(|s| s.match_string("("))
+    /// #             .and_then(|s| s.rule(Rule::param, |s| s.rule(Rule::ident, |s| s.match_string("x"))))
+    /// #             .and_then(|s| s.match_string(")"))
+    /// #     })
+    /// }).unwrap();
+    ///
+    /// let params: Vec<_> = pairs
+    ///     .find_rule_path(&[Rule::function, Rule::param, Rule::ident])
+    ///     .map(|pair| pair.as_str())
+    ///     .collect();
+    /// assert_eq!(params, ["x"]);
+    /// ```
+    #[inline]
+    pub fn find_rule_path(&self, path: &[R]) -> RulePath<'i, R> {
+        rule_path::new(self, path)
+    }
+
+    /// Returns the `Pairs` as `LocatablePairs`, which locate their pairs with a [`LineIndex`] of
+    /// the input instead of scanning it up to every pair. The index is built here, unless the parse
+    /// precomputed it, see [`ParseOptions::with_line_index`].
+    ///
+    /// [`ParseOptions::with_line_index`]: ../struct.ParseOptions.html#method.with_line_index
+    ///
+    /// [`LineIndex`]: ../struct.LineIndex.html
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use pest;
+    /// # #[allow(non_camel_case_types)]
+    /// # #[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+    /// enum Rule {
+    ///     a
+    /// }
+    ///
+    /// let input = "a\na";
+    /// let pairs = pest::state(input, |state| {
+    ///     // generating Token pairs with Rule::a ...
+    /// #     state.rule(Rule::a, |s| s.match_string("a")).and_then(|s| s.skip(1))
+    /// #         .and_then(|s| s.rule(Rule::a, |s| s.match_string("a")))
+    /// }).unwrap();
+    /// let line_cols: Vec<_> = pairs.locatable().map(|pair| pair.line_col()).collect();
+    ///
+    /// assert_eq!(line_cols, [(1, 1), (2, 1)]);
+    /// ```
+    #[inline]
+    pub fn locatable(mut self) -> LocatablePairs<'i, R> {
+        let index = match self.line_index.take() {
+            Some(index) => index,
+            None => Rc::new(LineIndex::new(self.input)),
+        };
+
+        locatable::new(self, index)
+    }
+
+    /// Returns the `Pairs` as `LocatablePairs` sharing `index`, e.g. the
+    /// [`LocatablePairs::line_index`] of an earlier parse of the same input.
+    ///
+    /// [`LocatablePairs::line_index`]: struct.LocatablePairs.html#method.line_index
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is a `LineIndex` of another input than the one of the `Pairs`.
+    #[inline]
+    pub fn locatable_with(self, index: Rc<LineIndex<'i>>) -> LocatablePairs<'i, R> {
+        locatable::new(self, index)
+    }
+
     /// Peek at the first inner `Pair` without changing the position of this iterator.
     #[inline]
     pub fn peek(&self) -> Option<Pair<'i, R>> {
@@ -187,6 +469,114 @@ impl<'i, R: RuleType> Pairs<'i, R> {
         }
     }
 
+    /// Renders the parse tree of `self` as a Graphviz DOT graph, with nodes labeled with their
+    /// rule and truncated text. See [`to_dot_with`] for filtering the tree.
+    ///
+    /// [`to_dot_with`]: #method.to_dot_with
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use pest;
+    /// # #[allow(non_camel_case_types)]
+    /// # #[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+    /// enum Rule {
+    ///     a,
+    ///     b
+    /// }
+    ///
+    /// let input = "ab";
+    /// let pairs = pest::state(input, |state| {
+    ///     // generating Token pair with Rule::a containing Rule::b ...
+    /// #     state.rule(Rule::a, |s| s.match_string("a").and_then(|s| {
+    /// #         s.rule(Rule::b, |s| s.match_string("b"))
+    /// #     }))
+    /// }).unwrap();
+    ///
+    /// assert_eq!(
+    ///     pairs.to_dot(),
+    ///     r#"digraph pairs {
+    ///     node [shape=box];
+    ///     n0 [label="a\n\"ab\""];
+    ///     n1 [label="b\n\"b\""];
+    ///     n0 -> n1;
+    /// }
+    /// "#
+    /// );
+    /// ```
+    pub fn to_dot(&self) -> String {
+        self.to_dot_with(&GraphOptions::new())
+    }
+
+    /// Renders the parse tree of `self` as a Graphviz DOT graph, according to `options`.
+    pub fn to_dot_with(&self, options: &GraphOptions<R>) -> String {
+        graph::to_dot(self, options)
+    }
+
+    /// Renders the parse tree of `self` as a Mermaid flowchart, with nodes labeled with their
+    /// rule and truncated text. See [`to_mermaid_with`] for filtering the tree.
+    ///
+    /// [`to_mermaid_with`]: #method.to_mermaid_with
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use pest;
+    /// # use pest::iterators::GraphOptions;
+    /// # #[allow(non_camel_case_types)]
+    /// # #[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+    /// enum Rule {
+    ///     a,
+    ///     b
+    /// }
+    ///
+    /// let input = "ab";
+    /// let pairs = pest::state(input, |state| {
+    ///     // generating Token pair with Rule::a containing Rule::b ...
+    /// #     state.rule(Rule::a, |s| s.match_string("a").and_then(|s| {
+    /// #         s.rule(Rule::b, |s| s.match_string("b"))
+    /// #     }))
+    /// }).unwrap();
+    ///
+    /// let options = GraphOptions::new().with_max_depth(1).with_text_width(0);
+    /// assert_eq!(pairs.to_mermaid_with(&options), "graph TD\n    n0[\"a\"]\n");
+    /// ```
+    pub fn to_mermaid(&self) -> String {
+        self.to_mermaid_with(&GraphOptions::new())
+    }
+
+    /// Renders the parse tree of `self` as a Mermaid flowchart, according to `options`.
+    pub fn to_mermaid_with(&self, options: &GraphOptions<R>) -> String {
+        graph::to_mermaid(self, options)
+    }
+
+    /// Renders `self` as a self-contained HTML page showing the whole input next to a collapsible
+    /// tree of the pairs. Hovering over a pair highlights both its span in the input and its tree
+    /// node, while clicking on the input reveals the innermost pair containing it in the tree.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use pest;
+    /// # #[allow(non_camel_case_types)]
+    /// # #[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+    /// enum Rule {
+    ///     a
+    /// }
+    ///
+    /// let input = "a";
+    /// let pairs = pest::state(input, |state| {
+    ///     // generating Token pair with Rule::a ...
+    /// #     state.rule(Rule::a, |s| s.match_string("a"))
+    /// }).unwrap();
+    ///
+    /// let html = pairs.to_html();
+    /// assert!(html.contains(r#"<span class="pair" data-id="0" title="a (0..1)">a</span>"#));
+    /// ```
+    pub fn to_html(&self) -> String {
+        html::to_html(self)
+    }
+
     /// Generates a string that stores the lexical information of `self` in
     /// a pretty-printed JSON format.
     #[cfg(feature = "pretty-print")]
@@ -221,6 +611,44 @@ impl<'i, R: RuleType> Pairs<'i, R> {
     }
 }
 
+#[cfg(feature = "std")]
+impl<'i, R: RuleCode> Pairs<'i, R> {
+    /// Writes the `Pairs` and the whole input they were parsed from to `writer` in a compact
+    /// binary format, which can be read back with [`OwnedPairs::read_from`] without parsing the
+    /// input again, e.g. to cache parse trees on disk between runs.
+    ///
+    /// [`OwnedPairs::read_from`]: struct.OwnedPairs.html#method.read_from
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use pest::RuleCode;
+    /// # #[allow(non_camel_case_types)]
+    /// # #[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+    /// enum Rule {
+    ///     a
+    /// }
+    ///
+    /// # impl RuleCode for Rule {
+    /// #     fn code(self) -> u16 { 0 }
+    /// #     fn from_code(code: u16) -> Option<Rule> { Some(Rule::a) }
+    /// # }
+    /// let input = "a";
+    /// let pairs = pest::state(input, |state| {
+    ///     // generating Token pair with Rule::a ...
+    /// #     state.rule(Rule::a, |s| s.match_string("a"))
+    /// }).unwrap();
+    ///
+    /// let mut bytes = vec![];
+    /// pairs.write_to(&mut bytes).unwrap();
+    ///
+    /// assert!(bytes.ends_with(b"a\x02\x00\x03\x00\x00"));
+    /// ```
+    pub fn write_to<W: std::io::Write>(&self, writer: &mut W) -> std::io::Result<()> {
+        owned::write(&self.queue[self.start..self.end], self.input, writer)
+    }
+}
+
 impl<'i, R: RuleType> Iterator for Pairs<'i, R> {
     type Item = Pair<'i, R>;
 
@@ -284,23 +712,6 @@ impl<'i, R: Hash> Hash for Pairs<'i, R> {
     }
 }
 
-#[cfg(feature = "pretty-print")]
-impl<'i, R: RuleType> ::serde::Serialize for Pairs<'i, R> {
-    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
-    where
-        S: ::serde::Serializer,
-    {
-        let start = self.pos(self.start);
-        let end = self.pos(self.end - 1);
-        let pairs = self.clone().collect::<Vec<_>>();
-
-        let mut ser = serializer.serialize_struct("Pairs", 2)?;
-        ser.serialize_field("pos", &(start, end))?;
-        ser.serialize_field("pairs", &pairs)?;
-        ser.end()
-    }
-}
-
 #[cfg(test)]
 mod tests {
     use super::super::super::macros::tests::*;
@@ -0,0 +1,153 @@
+// pest. The Elegant Parser
+// Copyright (c) 2018 Dragoș Tiselice
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+use alloc::vec::Vec;
+
+use super::queueable_token::QueueableToken;
+use crate::RuleType;
+
+/// A record of a single pair of the token queue, as yielded by [`RawTokens`].
+///
+/// [`RawTokens`]: struct.RawTokens.html
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct RawToken<R> {
+    /// The rule of the pair
+    pub rule: R,
+    /// The byte position where the pair starts
+    pub start: usize,
+    /// The byte position where the pair ends
+    pub end: usize,
+    /// The index of the record of the enclosing pair, in the order yielded by [`RawTokens`], or
+    /// `None` for top-level pairs
+    ///
+    /// [`RawTokens`]: struct.RawTokens.html
+    pub parent_index: Option<usize>,
+}
+
+/// A read-only iterator over the token queue, yielding one [`RawToken`] per pair in pre-order
+/// (i.e. in the order of their start tokens), without constructing any `Pair`. It is created by
+/// [`Pairs::raw_tokens`].
+///
+/// [`RawToken`]: struct.RawToken.html
+/// [`Pairs::raw_tokens`]: struct.Pairs.html#method.raw_tokens
+#[derive(Clone, Debug)]
+pub struct RawTokens<'a, R> {
+    queue: &'a [QueueableToken<R>],
+    start: usize,
+    end: usize,
+    index: usize,
+    // The end token indices of the currently open pairs, along with the indices of their records.
+    open: Vec<(usize, usize)>,
+}
+
+pub fn new<R: RuleType>(queue: &[QueueableToken<R>], start: usize, end: usize) -> RawTokens<'_, R> {
+    RawTokens {
+        queue,
+        start,
+        end,
+        index: 0,
+        open: Vec::new(),
+    }
+}
+
+impl<'a, R: RuleType> Iterator for RawTokens<'a, R> {
+    type Item = RawToken<R>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.start < self.end {
+            let token_index = self.start;
+            self.start += 1;
+
+            if let QueueableToken::Start {
+                end_token_index,
+                input_pos,
+            } = self.queue[token_index]
+            {
+                let (rule, end) = match self.queue[end_token_index] {
+                    QueueableToken::End {
+                        rule, input_pos, ..
+                    } => (rule, input_pos),
+                    _ => unreachable!(),
+                };
+
+                while matches!(self.open.last(), Some(&(open_end, _)) if open_end < token_index) {
+                    self.open.pop();
+                }
+
+                let parent_index = self.open.last().map(|&(_, index)| index);
+                let index = self.index;
+                self.index += 1;
+                self.open.push((end_token_index, index));
+
+                return Some(RawToken {
+                    rule,
+                    start: input_pos,
+                    end,
+                    parent_index,
+                });
+            }
+        }
+
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::super::macros::tests::*;
+    use super::super::super::Parser;
+    use super::RawToken;
+    use alloc::vec;
+    use alloc::vec::Vec;
+
+    #[test]
+    fn raw_tokens() {
+        let pairs = AbcParser::parse(Rule::a, "abcde").unwrap();
+
+        assert_eq!(
+            pairs.raw_tokens().collect::<Vec<_>>(),
+            vec![
+                RawToken {
+                    rule: Rule::a,
+                    start: 0,
+                    end: 3,
+                    parent_index: None,
+                },
+                RawToken {
+                    rule: Rule::b,
+                    start: 1,
+                    end: 2,
+                    parent_index: Some(0),
+                },
+                RawToken {
+                    rule: Rule::c,
+                    start: 4,
+                    end: 5,
+                    parent_index: None,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn raw_tokens_inner() {
+        let pair = AbcParser::parse(Rule::a, "abcde").unwrap().next().unwrap();
+        let tokens: Vec<_> = pair.into_inner().raw_tokens().collect();
+
+        assert_eq!(
+            tokens,
+            vec![RawToken {
+                rule: Rule::b,
+                start: 1,
+                end: 2,
+                parent_index: None,
+            }]
+        );
+    }
+}
@@ -0,0 +1,152 @@
+// pest. The Elegant Parser
+// Copyright (c) 2018 Dragoș Tiselice
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+use alloc::rc::Rc;
+use alloc::vec::Vec;
+use core::fmt;
+
+use super::pair::{self, Pair};
+use super::pairs::{self, Pairs};
+use super::queueable_token::QueueableToken;
+use crate::RuleType;
+
+/// An iterator over the nested [`Pair`]s matching a path of rules, in the order in which they
+/// start. It is created by [`Pairs::find_rule_path`] and [`Pair::descendants`].
+///
+/// [`Pair`]: struct.Pair.html
+/// [`Pairs::find_rule_path`]: struct.Pairs.html#method.find_rule_path
+/// [`Pair::descendants`]: struct.Pair.html#method.descendants
+#[derive(Clone)]
+pub struct RulePath<'i, R> {
+    /// # Safety
+    ///
+    /// All `QueueableToken`s' `input_pos` must be valid character boundary indices into `input`.
+    queue: Rc<Vec<QueueableToken<R>>>,
+    input: &'i str,
+    start: usize,
+    end: usize,
+    path: Vec<R>,
+    // The end token indices of the ancestors of the next pair, along with how many rules of
+    // `path` each of them and its own ancestors match.
+    ancestors: Vec<(usize, usize)>,
+}
+
+pub fn new<'i, R: RuleType>(pairs: &Pairs<'i, R>, path: &[R]) -> RulePath<'i, R> {
+    RulePath {
+        queue: Rc::clone(pairs::queue(pairs)),
+        input: pairs::input(pairs),
+        start: pairs::cursor(pairs),
+        end: pairs::end(pairs),
+        path: path.to_vec(),
+        ancestors: Vec::new(),
+    }
+}
+
+impl<'i, R: RuleType> Iterator for RulePath<'i, R> {
+    type Item = Pair<'i, R>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let last = self.path.len().checked_sub(1)?;
+
+        while self.start < self.end {
+            let index = self.start;
+            self.start += 1;
+
+            let end_token_index = match self.queue[index] {
+                QueueableToken::Start {
+                    end_token_index, ..
+                } => end_token_index,
+                QueueableToken::End { .. } => continue,
+            };
+            let rule = match self.queue[end_token_index] {
+                QueueableToken::End { rule, .. } => rule,
+                QueueableToken::Start { .. } => unreachable!(),
+            };
+
+            while matches!(self.ancestors.last(), Some(&(end, _)) if end < index) {
+                self.ancestors.pop();
+            }
+
+            // Matching the rules of the path as early as possible along the ancestors leaves the
+            // most rules to match for the descendants.
+            let matched = self.ancestors.last().map_or(0, |&(_, matched)| matched);
+            let found = matched == last && rule == self.path[last];
+            let matched = if matched < last && rule == self.path[matched] {
+                matched + 1
+            } else {
+                matched
+            };
+            self.ancestors.push((end_token_index, matched));
+
+            if found {
+                return Some(unsafe { pair::new(Rc::clone(&self.queue), self.input, index) });
+            }
+        }
+
+        None
+    }
+}
+
+impl<'i, R: RuleType> fmt::Debug for RulePath<'i, R> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RulePath")
+            .field("path", &self.path)
+            .field("pairs", &self.clone().collect::<Vec<_>>())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::iterators::Pair;
+    use crate::macros::tests::*;
+    use crate::parser::Parser;
+    use alloc::vec::Vec;
+
+    fn spans(pairs: impl Iterator<Item = Pair<'static, Rule>>) -> Vec<(usize, usize)> {
+        pairs
+            .map(|pair| (pair.as_span().start(), pair.as_span().end()))
+            .collect()
+    }
+
+    #[test]
+    fn rule_path() {
+        let pairs = AbcParser::parse(Rule::a, "abcde").unwrap();
+
+        assert_eq!(spans(pairs.find_rule_path(&[Rule::a, Rule::b])), [(1, 2)]);
+        assert_eq!(spans(pairs.find_rule_path(&[Rule::b])), [(1, 2)]);
+        assert_eq!(spans(pairs.find_rule_path(&[Rule::c])), [(4, 5)]);
+        assert!(pairs.find_rule_path(&[Rule::c, Rule::b]).next().is_none());
+        assert!(pairs.find_rule_path(&[]).next().is_none());
+    }
+
+    #[test]
+    fn nested_matches() {
+        let input = "aab";
+        let pairs = crate::state(input, |state| {
+            state.rule(Rule::a, |s| {
+                s.skip(1).and_then(|s| {
+                    s.rule(Rule::a, |s| {
+                        s.skip(1)
+                            .and_then(|s| s.rule(Rule::b, |s| s.match_string("b")))
+                    })
+                })
+            })
+        })
+        .unwrap();
+
+        assert_eq!(spans(pairs.find_rule_path(&[Rule::a])), [(0, 3), (1, 3)]);
+        assert_eq!(spans(pairs.find_rule_path(&[Rule::a, Rule::a])), [(1, 3)]);
+        assert_eq!(spans(pairs.find_rule_path(&[Rule::a, Rule::b])), [(2, 3)]);
+
+        let pair = pairs.clone().next().unwrap();
+        assert_eq!(spans(pair.descendants(Rule::a)), [(1, 3)]);
+        assert_eq!(spans(pair.descendants(Rule::b)), [(2, 3)]);
+    }
+}
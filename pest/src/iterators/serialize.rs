@@ -0,0 +1,266 @@
+// pest. The Elegant Parser
+// Copyright (c) 2018 Dragoș Tiselice
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! `Serialize` implementations of `Pair` and `Pairs`, which dump a parse tree, e.g. to JSON.
+//!
+//! A `Pair` is serialized as a struct with the fields:
+//!
+//! * `pos`: its start and end byte positions
+//! * `rule`: the name of its rule
+//! * `inner`: its inner `Pairs`, or its text if it has none
+//!
+//! `Pairs` are serialized as a struct with the same `pos` field, spanning all of them, and with
+//! their `pairs` as a sequence.
+//!
+//! `LocatablePair`s and `LocatablePairs` also have a `line_col` field after `pos`, with the
+//! 1-based `(line, col)` of their start and end, which are looked up in their shared `LineIndex`.
+
+use alloc::format;
+use alloc::vec::Vec;
+
+use serde::ser::{Serialize, SerializeSeq, SerializeStruct, Serializer};
+
+use super::locatable::{LocatablePair, LocatablePairs};
+use super::pair::Pair;
+use super::pairs::{self, Pairs};
+use crate::line_index::LineIndex;
+use crate::RuleType;
+
+impl<'i, R: RuleType> Serialize for Pair<'i, R> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        Node {
+            node: self,
+            index: None,
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'i, R: RuleType> Serialize for Pairs<'i, R> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        Node {
+            node: self,
+            index: None,
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'i, R: RuleType> Serialize for LocatablePair<'i, R> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        Node {
+            node: &**self,
+            index: Some(self.line_index().as_ref()),
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'i, R: RuleType> Serialize for LocatablePairs<'i, R> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        Node {
+            node: &self.clone().into_pairs(),
+            index: Some(self.line_index().as_ref()),
+        }
+        .serialize(serializer)
+    }
+}
+
+// A node of the tree along with the index of its input for locatable pairs, which is shared by the
+// whole tree.
+struct Node<'a, 'i, T> {
+    node: &'a T,
+    index: Option<&'a LineIndex<'i>>,
+}
+
+impl<'a, 'i, T> Node<'a, 'i, T> {
+    // The number of fields of the struct, counting `line_col` if the node is locatable.
+    fn len(&self, fields: usize) -> usize {
+        fields + self.index.map_or(0, |_| 1)
+    }
+
+    fn serialize_line_col<S: SerializeStruct>(
+        &self,
+        ser: &mut S,
+        start: usize,
+        end: usize,
+    ) -> Result<(), S::Error> {
+        match self.index {
+            Some(index) => {
+                ser.serialize_field("line_col", &(index.line_col(start), index.line_col(end)))
+            }
+            None => Ok(()),
+        }
+    }
+}
+
+impl<'a, 'i, R: RuleType> Serialize for Node<'a, 'i, Pair<'i, R>> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let span = self.node.as_span();
+        let rule = format!("{:?}", self.node.as_rule());
+        let inner = self.node.clone().into_inner();
+
+        let mut ser = serializer.serialize_struct("Pair", self.len(3))?;
+        ser.serialize_field("pos", &(span.start(), span.end()))?;
+        self.serialize_line_col(&mut ser, span.start(), span.end())?;
+        ser.serialize_field("rule", &rule)?;
+
+        if inner.peek().is_none() {
+            ser.serialize_field("inner", span.as_str())?;
+        } else {
+            let inner = Node {
+                node: &inner,
+                index: self.index,
+            };
+            ser.serialize_field("inner", &inner)?;
+        }
+
+        ser.end()
+    }
+}
+
+impl<'a, 'i, R: RuleType> Serialize for Node<'a, 'i, Pairs<'i, R>> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let start = pairs::next_pos(self.node);
+        let end = pairs::end_pos(self.node).unwrap_or(start);
+        let pairs = Sequence {
+            pairs: self.node.clone().collect(),
+            index: self.index,
+        };
+
+        let mut ser = serializer.serialize_struct("Pairs", self.len(2))?;
+        ser.serialize_field("pos", &(start, end))?;
+        self.serialize_line_col(&mut ser, start, end)?;
+        ser.serialize_field("pairs", &pairs)?;
+        ser.end()
+    }
+}
+
+struct Sequence<'a, 'i, R> {
+    pairs: Vec<Pair<'i, R>>,
+    index: Option<&'a LineIndex<'i>>,
+}
+
+impl<'a, 'i, R: RuleType> Serialize for Sequence<'a, 'i, R> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut seq = serializer.serialize_seq(Some(self.pairs.len()))?;
+        for pair in &self.pairs {
+            seq.serialize_element(&Node {
+                node: pair,
+                index: self.index,
+            })?;
+        }
+        seq.end()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::macros::tests::*;
+    use crate::parser::Parser;
+    use serde_json::json;
+
+    #[test]
+    fn pairs() {
+        let pairs = AbcParser::parse(Rule::a, "abc\ne").unwrap();
+
+        assert_eq!(
+            serde_json::to_value(&pairs).unwrap(),
+            json!({
+                "pos": [0, 5],
+                "pairs": [
+                    {
+                        "pos": [0, 3],
+                        "rule": "a",
+                        "inner": {
+                            "pos": [1, 2],
+                            "pairs": [{ "pos": [1, 2], "rule": "b", "inner": "b" }]
+                        }
+                    },
+                    { "pos": [4, 5], "rule": "c", "inner": "e" }
+                ]
+            })
+        );
+    }
+
+    #[test]
+    fn locatable_pairs() {
+        let pairs = AbcParser::parse(Rule::a, "abc\ne").unwrap().locatable();
+
+        assert_eq!(
+            serde_json::to_value(&pairs).unwrap(),
+            json!({
+                "pos": [0, 5],
+                "line_col": [[1, 1], [2, 2]],
+                "pairs": [
+                    {
+                        "pos": [0, 3],
+                        "line_col": [[1, 1], [1, 4]],
+                        "rule": "a",
+                        "inner": {
+                            "pos": [1, 2],
+                            "line_col": [[1, 2], [1, 3]],
+                            "pairs": [
+                                {
+                                    "pos": [1, 2],
+                                    "line_col": [[1, 2], [1, 3]],
+                                    "rule": "b",
+                                    "inner": "b"
+                                }
+                            ]
+                        }
+                    },
+                    {
+                        "pos": [4, 5],
+                        "line_col": [[2, 1], [2, 2]],
+                        "rule": "c",
+                        "inner": "e"
+                    }
+                ]
+            })
+        );
+
+        let pair = pairs.clone().nth(1).unwrap();
+        assert_eq!(
+            serde_json::to_value(&pair).unwrap(),
+            json!({ "pos": [4, 5], "line_col": [[2, 1], [2, 2]], "rule": "c", "inner": "e" })
+        );
+    }
+
+    #[test]
+    fn empty_pairs() {
+        let pair = AbcParser::parse(Rule::a, "abcde").unwrap().nth(1).unwrap();
+
+        assert_eq!(
+            serde_json::to_value(pair.into_inner()).unwrap(),
+            json!({ "pos": [4, 4], "pairs": [] })
+        );
+    }
+}
@@ -14,6 +14,7 @@ use core::str;
 
 use super::queueable_token::QueueableToken;
 use crate::position;
+use crate::span::Span;
 use crate::token::Token;
 use crate::RuleType;
 
@@ -31,6 +32,9 @@ pub struct Tokens<'i, R> {
     input: &'i str,
     start: usize,
     end: usize,
+    // Only the tokens of the pairs whose start tokens are in this range are yielded.
+    pairs_start: usize,
+    pairs_end: usize,
 }
 
 // TODO(safety): QueueableTokens must be valid indices into input.
@@ -58,10 +62,80 @@ pub fn new<R: RuleType>(
         input,
         start,
         end,
+        pairs_start: start,
+        pairs_end: end,
     }
 }
 
+/// Returns the `Tokens` of the pairs whose start tokens are between `start` and `end`, e.g. of the
+/// pairs left in `FlatPairs`, where the tokens in the range may belong to other pairs and the end
+/// tokens of the pairs may come after the range.
+pub fn new_flat<R: RuleType>(
+    queue: Rc<Vec<QueueableToken<R>>>,
+    input: &str,
+    start: usize,
+    end: usize,
+) -> Tokens<'_, R> {
+    let token_end = queue[start..end]
+        .iter()
+        .filter_map(|token| match *token {
+            QueueableToken::Start {
+                end_token_index, ..
+            } => Some(end_token_index + 1),
+            QueueableToken::End { .. } => None,
+        })
+        .max()
+        .unwrap_or(start);
+
+    let mut tokens = new(queue, input, start, token_end);
+    tokens.pairs_end = end;
+    tokens
+}
+
 impl<'i, R: RuleType> Tokens<'i, R> {
+    /// Returns the `TokenPairs` of the pairs whose start and end tokens are both left in the
+    /// `Tokens`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use pest;
+    /// # #[allow(non_camel_case_types)]
+    /// # #[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+    /// enum Rule {
+    ///     a,
+    ///     b
+    /// }
+    ///
+    /// let input = "ab";
+    /// let pairs = pest::state(input, |state| {
+    ///     // generating Token pair with Rule::b inside Rule::a ...
+    /// #     state.rule(Rule::a, |s| s.skip(1).and_then(|s| s.rule(Rule::b, |s| s.match_string("b"))))
+    /// }).unwrap();
+    /// let spans: Vec<_> = pairs
+    ///     .tokens()
+    ///     .pairs()
+    ///     .map(|(rule, span)| (rule, span.as_str()))
+    ///     .collect();
+    ///
+    /// assert_eq!(spans, [(Rule::a, "ab"), (Rule::b, "b")]);
+    /// ```
+    #[inline]
+    pub fn pairs(self) -> TokenPairs<'i, R> {
+        TokenPairs { tokens: self }
+    }
+
+    fn is_included(&self, index: usize) -> bool {
+        let pair_start = match self.queue[index] {
+            QueueableToken::Start { .. } => index,
+            QueueableToken::End {
+                start_token_index, ..
+            } => start_token_index,
+        };
+
+        self.pairs_start <= pair_start && pair_start < self.pairs_end
+    }
+
     fn create_token(&self, index: usize) -> Token<'i, R> {
         match self.queue[index] {
             QueueableToken::Start {
@@ -96,33 +170,86 @@ impl<'i, R: RuleType> Iterator for Tokens<'i, R> {
     type Item = Token<'i, R>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        if self.start >= self.end {
-            return None;
-        }
-
-        let token = self.create_token(self.start);
+        while self.start < self.end {
+            let index = self.start;
+            self.start += 1;
 
-        self.start += 1;
+            if self.is_included(index) {
+                return Some(self.create_token(index));
+            }
+        }
 
-        Some(token)
+        None
     }
 }
 
 impl<'i, R: RuleType> DoubleEndedIterator for Tokens<'i, R> {
     fn next_back(&mut self) -> Option<Self::Item> {
-        if self.end <= self.start {
-            return None;
+        while self.end > self.start {
+            self.end -= 1;
+
+            if self.is_included(self.end) {
+                return Some(self.create_token(self.end));
+            }
         }
 
-        let token = self.create_token(self.end - 1);
+        None
+    }
+}
+
+impl<'i, R: RuleType> fmt::Debug for Tokens<'i, R> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.clone()).finish()
+    }
+}
+
+/// An iterator over the rules and `Span`s of the pairs of [`Token`]s, in the order in which they
+/// start, e.g. for highlighting. It is created by [`Tokens::pairs`].
+///
+/// [`Token`]: ../enum.Token.html
+/// [`Tokens::pairs`]: struct.Tokens.html#method.pairs
+#[derive(Clone)]
+pub struct TokenPairs<'i, R> {
+    tokens: Tokens<'i, R>,
+}
 
-        self.end -= 1;
+impl<'i, R: RuleType> Iterator for TokenPairs<'i, R> {
+    type Item = (R, Span<'i>);
 
-        Some(token)
+    fn next(&mut self) -> Option<Self::Item> {
+        let tokens = &mut self.tokens;
+
+        while tokens.start < tokens.end {
+            let index = tokens.start;
+            tokens.start += 1;
+
+            if let QueueableToken::Start {
+                end_token_index,
+                input_pos,
+            } = tokens.queue[index]
+            {
+                if !tokens.is_included(index) || end_token_index >= tokens.end {
+                    continue;
+                }
+
+                if let QueueableToken::End {
+                    rule,
+                    input_pos: end_pos,
+                    ..
+                } = tokens.queue[end_token_index]
+                {
+                    // QueueableTokens are safely created.
+                    let span = unsafe { Span::new_unchecked(tokens.input, input_pos, end_pos) };
+                    return Some((rule, span));
+                }
+            }
+        }
+
+        None
     }
 }
 
-impl<'i, R: RuleType> fmt::Debug for Tokens<'i, R> {
+impl<'i, R: RuleType> fmt::Debug for TokenPairs<'i, R> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_list().entries(self.clone()).finish()
     }
@@ -132,7 +259,7 @@ impl<'i, R: RuleType> fmt::Debug for Tokens<'i, R> {
 mod tests {
     use super::super::super::macros::tests::*;
     use super::super::super::Parser;
-    use super::Token;
+    use super::{Token, Tokens};
     use alloc::vec::Vec;
 
     #[test]
@@ -143,4 +270,65 @@ mod tests {
         let reverse_tokens = pairs.tokens().rev().collect::<Vec<Token<'_, Rule>>>();
         assert_eq!(tokens, reverse_tokens);
     }
+
+    fn rules(tokens: impl Iterator<Item = Token<'static, Rule>>) -> Vec<(bool, Rule, usize)> {
+        tokens
+            .map(|token| match token {
+                Token::Start { rule, pos } => (true, rule, pos.pos()),
+                Token::End { rule, pos } => (false, rule, pos.pos()),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn partially_consumed_flat_pairs() {
+        let pairs = AbcParser::parse(Rule::a, "abcde").unwrap();
+
+        let mut flat = pairs.clone().flatten();
+        flat.next();
+        assert_eq!(
+            rules(flat.tokens()),
+            [
+                (true, Rule::b, 1),
+                (false, Rule::b, 2),
+                (true, Rule::c, 4),
+                (false, Rule::c, 5)
+            ]
+        );
+
+        let mut flat = pairs.flatten();
+        flat.next_back();
+        flat.next_back();
+        assert_eq!(
+            rules(flat.clone().tokens()),
+            [(true, Rule::a, 0), (false, Rule::a, 3)]
+        );
+        assert_eq!(
+            rules(flat.tokens().rev()),
+            [(false, Rule::a, 3), (true, Rule::a, 0)]
+        );
+    }
+
+    #[test]
+    fn token_pairs() {
+        let pairs = AbcParser::parse(Rule::a, "abcde").unwrap();
+        let spans = |tokens: Tokens<'static, Rule>| {
+            tokens
+                .pairs()
+                .map(|(rule, span)| (rule, span.as_str()))
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            spans(pairs.clone().tokens()),
+            [(Rule::a, "abc"), (Rule::b, "b"), (Rule::c, "e")]
+        );
+
+        // The pairs whose end tokens were consumed are left out.
+        let mut tokens = pairs.tokens();
+        tokens.next_back();
+        tokens.next_back();
+        tokens.next_back();
+        assert_eq!(spans(tokens), [(Rule::b, "b")]);
+    }
 }
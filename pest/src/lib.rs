@@ -75,10 +75,22 @@
 //!
 //! ### Comments
 //!
-//! Comments start with `//` and end at the end of the line.
+//! Line comments start with `//` and end at the end of the line, while block comments start with
+//! `/*`, end with `*/`, and can be nested.
 //!
 //! ```text
 //! // a comment
+//! /* a /* nested */ block comment */
+//! ```
+//!
+//! Doc comments are kept by `pest_meta` for tooling: `//!` comments at the top of the grammar
+//! document the grammar, while `///` comments document the rule that follows them.
+//!
+//! ```text
+//! //! A grammar for greetings.
+//!
+//! /// Matches a greeting.
+//! greeting = { "hello" }
 //! ```
 //!
 //! ### Rules
@@ -165,17 +177,19 @@
 //!
 //! 1. Terminals
 //!
-//! | Terminal   | Usage                                                          |
-//! |------------|----------------------------------------------------------------|
-//! | `"a"`      | matches the exact string `"a"`                                 |
-//! | `^"a"`     | matches the exact string `"a"` case insensitively (ASCII only) |
-//! | `'a'..'z'` | matches one character between `'a'` and `'z'`                  |
-//! | `a`        | matches rule `a`                                               |
+//! | Terminal     | Usage                                                          |
+//! |--------------|----------------------------------------------------------------|
+//! | `"a"`        | matches the exact string `"a"`                                 |
+//! | `^"a"`       | matches the exact string `"a"` case insensitively (ASCII only) |
+//! | `r#"a"b"#`   | matches the exact string `a"b`, without escapes                |
+//! | `'a'..'z'`   | matches one character between `'a'` and `'z'`                  |
+//! | `a`          | matches rule `a`                                               |
 //!
 //! Strings and characters follow
-//! [Rust's escape mechanisms](https://doc.rust-lang.org/reference/tokens.html#byte-escapes), while
-//! identifiers can contain alphanumeric characters and underscores (`_`), as long as they do not
-//! start with a digit.
+//! [Rust's escape mechanisms](https://doc.rust-lang.org/reference/tokens.html#byte-escapes)
+//! (including `\x7F` and `\u{XXXX}`) and strings can also be written as raw strings (`r"..."`,
+//! `r#"..."#`, etc.), while identifiers can contain alphanumeric characters and underscores
+//! (`_`), as long as they do not start with a digit.
 //!
 //! 2. Non-terminals
 //!
@@ -193,7 +207,11 @@
 //! | `e?`                  | optionally matches `e`                                     |
 //! | `&e`                  | matches `e` without making progress                        |
 //! | `!e`                  | matches if `e` doesn't match without making progress       |
+//! | `<&e`                 | matches if the input right before matches `e`, without     |
+//! |                       | making progress; `e` must have a fixed width (see below)   |
 //! | `PUSH(e)`             | matches `e` and pushes it's captured string down the stack |
+//! | `#"label" e`          | matches `e`, labeling errors within it (see below)         |
+//! | `#{name} e`           | matches `e` if the function `name` accepts it (see below)  |
 //!
 //! where `e`, `e1`, and `e2` are expressions.
 //!
@@ -214,13 +232,65 @@
 //! if `e1` in the compound expression `e1 | e2` does not match the input, then
 //! it does not modify the stack, so `e2` sees the stack in the same state as
 //! `e1` did. Repetitions and optionals (`e*`, `e+`, `e{, n}`, `e{n,}`,
-//! `e{m,n}`, `e?`) can modify the stack each time `e` matches. The `!e` and `&e`
-//! expressions are a special case; they never modify the stack.
+//! `e{m,n}`, `e?`) can modify the stack each time `e` matches. The `!e`, `&e`, and
+//! `<&e` expressions are a special case; they never modify the stack.
+//!
+//! The lookbehind `<&e` matches `e` atomically, right before the current position, e.g.
+//! `<&"/"` only matches right after a `/`. It is bounded: `e` must always match the same
+//! number of bytes, so it can only contain strings, ranges of characters of the same UTF-8
+//! width, and sequences, same-width choices, and exact repetitions (`e{n}`) of those.
+//!
+//! A labeled group `#"label" e` produces no pair of its own, but gives context to error messages:
+//! when a parse fails furthest within `e`, the error points back at where the innermost such group
+//! started with `while parsing label`:
+//!
+//! ```ignore
+//! call = { identifier ~ "(" ~ #"argument list" (expr ~ ("," ~ expr)*)? ~ ")" }
+//! ```
+//!
+//! A semantic predicate `#{name} e` matches `e`, then calls the function `name` of the module of
+//! the parser with the input it matched and the context given to `Parser::parse_with_context`,
+//! failing unless it returns `true`. It lets context-sensitive parts of a language be parsed, e.g.
+//! telling C declarations from multiplications by the type names declared so far:
+//!
+//! ```ignore
+//! declaration = { #{is_type_name} identifier ~ "*" ~ identifier }
+//! // fn is_type_name(text: &str, type_names: &TypeNames) -> bool
+//! ```
+//!
+//! The context is downcast to the type the function takes, panicking if it is of another type.
+//! `Parser::parse` gives `&()` as the context.
+//!
 //! Many languages have "keyword" tokens (e.g. if, for, while) as well as general
 //! tokens (e.g. identifier) that matches any word. In order to match a keyword,
 //! generally, you may need to restrict that is not immediately followed by another
 //! letter or digit (otherwise it would be matched as an identifier).
 //!
+//! A `@keywords` declaration takes care of this. It generates an atomic `kw_<keyword>` rule for
+//! every keyword, which is not followed by `ASCII_ALPHANUMERIC | "_"`, and a silent `keyword`
+//! rule matching any of them. When given a rule, e.g. an atomic `identifier` rule, keywords are
+//! also excluded from it, as if its expression was prefixed with `!keyword ~`:
+//!
+//! ```ignore
+//! @keywords(identifier) { "if" "for" "while" }
+//! identifier = @{ ASCII_ALPHA ~ ASCII_ALPHANUMERIC* }
+//! statement = { kw_if ~ identifier }
+//! ```
+//!
+//! ## Aliases
+//!
+//! An `@alias` declaration makes several rules produce pairs of the same kind. The aliased rules
+//! don't get `Rule` variants of their own; their pairs carry the alias' variant instead, so they
+//! can be matched in a single arm. The alias can also be called like a silent rule matching any of
+//! the aliased rules in order:
+//!
+//! ```ignore
+//! @alias(string) { single_quoted double_quoted }
+//! single_quoted = @{ "'" ~ (!"'" ~ ANY)* ~ "'" }
+//! double_quoted = @{ "\"" ~ (!"\"" ~ ANY)* ~ "\"" }
+//! value = { string | number }
+//! ```
+//!
 //! ## Special rules
 //!
 //! Special rules can be called within the grammar. They are:
@@ -236,6 +306,9 @@
 //! * `PEEK[a..b]` - peeks part of the stack and matches it
 //! * `PEEK_ALL` - peeks the entire state of the stack and matches it
 //! * `DROP` - drops the top of the stack (fails to match if the stack is empty)
+//! * `PUSH_INDENT` - matches and pushes an indentation deeper than the top of the stack
+//! * `MATCH_INDENT` - matches the indentation on top of the stack exactly
+//! * `DEDENT` - drops the top of the stack when the indentation is shallower, matching nothing
 //!
 //! `WHITESPACE` and `COMMENT` should be defined manually if needed. All other rules cannot be
 //! overridden.
@@ -307,6 +380,27 @@
 //! For historical reasons, `PEEK_ALL` matches from top to bottom, while `PEEK[start..end]` matches
 //! from bottom to top. There is currently no syntax to match a slice of the stack top to bottom.
 //!
+//! ### Indentation
+//!
+//! `PUSH_INDENT`, `MATCH_INDENT` and `DEDENT` use the stack to match blocks of lines indented
+//! like in Python or YAML. An indentation is made of spaces and tabs, and an empty stack stands
+//! for no indentation:
+//!
+//! * `PUSH_INDENT` matches the indentation at the start of a line if it is deeper than the one on
+//!   top of the stack, i.e. starts with it and is longer, and pushes it
+//! * `MATCH_INDENT` matches the indentation on top of the stack, when it is not followed by more
+//!   spaces or tabs
+//! * `DEDENT` drops the top of the stack if the indentation at the start of a line is shallower,
+//!   i.e. the top of the stack starts with it and is longer, matching nothing; it also matches at
+//!   the end of the input
+//!
+//! With statements ending in a `NEWLINE`, a block could then be:
+//!
+//! ```ignore
+//! statement = { MATCH_INDENT ~ line ~ NEWLINE ~ block? }
+//! block = { PUSH_INDENT ~ line ~ NEWLINE ~ block? ~ statement* ~ DEDENT }
+//! ```
+//!
 //! ## `Rule`
 //!
 //! All rules defined or used in the grammar populate a generated `enum` called `Rule`. This
@@ -334,18 +428,50 @@ extern crate alloc;
 #[cfg(feature = "std")]
 extern crate std;
 
-pub use crate::parser::Parser;
+#[cfg(feature = "std")]
+pub use crate::builder::{ParserBuilder, ParserHandle};
+pub use crate::dyn_parser::DynParser;
+pub use crate::from_pairs::FromPairs;
+pub use crate::incremental::{IncrementalParser, TextEdit};
+pub use crate::input::{ByteInput, BytePosition, ByteSpan, ChunkedInput, Input, PieceInput};
+pub use crate::line_index::{ColumnMode, LineIndex};
+#[doc(hidden)]
+pub use crate::macros::ExpectedPosition;
+pub use crate::parser::{grammar_hash, ByteParser, Parser};
+pub use crate::parser_state::warm_up;
 pub use crate::parser_state::{
-    set_call_limit, state, Atomicity, Lookahead, MatchDir, ParseResult, ParserState,
+    set_call_limit, state, state_compact, state_explained, state_head, state_recoverable,
+    state_reusing, state_tolerant, state_with_stats, Atomicity, AttemptTracking, Lookahead,
+    MatchDir, ParseOptions, ParseResult, ParseReuse, ParseStats, ParserState, Recovery, Repair,
+    RepairKind, Tolerance,
 };
+#[cfg(feature = "std")]
+pub use crate::parser_state::{with_attempt_tracking, with_error_window};
 pub use crate::position::Position;
-pub use crate::span::{Lines, LinesSpan, Span};
+pub use crate::resumable::{Continuation, Resumable};
+pub use crate::span::{CharIndices, Lines, LinesSpan, Span};
+pub use crate::span_map::SpanMap;
+pub use crate::spanned::{Spanned, SpannedError, SpannedResult};
 pub use crate::token::Token;
 use core::fmt::Debug;
 use core::hash::Hash;
 
+#[cfg(feature = "std")]
+mod builder;
+#[cfg(feature = "serde")]
+pub mod de;
+pub mod diagnostics;
+pub mod dyn_parser;
 pub mod error;
+pub mod explain;
+#[cfg(feature = "std")]
+mod external;
+pub mod from_pairs;
+pub mod green;
+mod incremental;
+pub mod input;
 pub mod iterators;
+mod line_index;
 mod macros;
 mod parser;
 mod parser_state;
@@ -357,8 +483,13 @@ pub mod pratt_parser;
 While prec_climber is going to be kept in 2.x minor and patch releases, it may be removed in a future major release."
 )]
 pub mod prec_climber;
+pub mod registry;
+mod resumable;
 mod span;
+pub mod span_map;
+mod spanned;
 mod stack;
+pub mod testing;
 mod token;
 #[doc(hidden)]
 pub mod unicode;
@@ -373,3 +504,20 @@ pub mod unicode;
 pub trait RuleType: Copy + Debug + Eq + Hash + Ord {}
 
 impl<T: Copy + Debug + Eq + Hash + Ord> RuleType for T {}
+
+/// A trait for rules with stable numeric codes, which lets [`Pairs::write_to`] and
+/// [`OwnedPairs::read_from`] store parse trees outside of the process.
+///
+/// The `Rule` enums generated by `pest_derive` number their variants in the order of the grammar,
+/// so a stored tree can be read back as long as no rules were inserted, removed or reordered in
+/// between.
+///
+/// [`Pairs::write_to`]: iterators/struct.Pairs.html#method.write_to
+/// [`OwnedPairs::read_from`]: iterators/struct.OwnedPairs.html#method.read_from
+pub trait RuleCode: RuleType {
+    /// Returns the code of the rule.
+    fn code(self) -> u16;
+
+    /// Returns the rule with the given `code`, if there is any.
+    fn from_code(code: u16) -> Option<Self>;
+}
@@ -0,0 +1,607 @@
+// pest. The Elegant Parser
+// Copyright (c) 2018 Dragoș Tiselice
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+use alloc::vec::Vec;
+
+use crate::unicode;
+
+/// The unit in which columns are counted by a [`LineIndex`] and in [`Error`]s.
+///
+/// [`LineIndex`]: struct.LineIndex.html
+/// [`Error`]: error/struct.Error.html
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum ColumnMode {
+    /// Every `char` is one column, which is the default
+    Chars,
+    /// Every grapheme cluster, e.g. a letter followed by combining marks or an emoji sequence,
+    /// is one column
+    Graphemes,
+    /// Every grapheme cluster is as many columns as it takes up in a terminal, i.e. two columns
+    /// for East Asian wide characters and emoji, and one column otherwise
+    DisplayWidth,
+}
+
+impl Default for ColumnMode {
+    fn default() -> ColumnMode {
+        ColumnMode::Chars
+    }
+}
+
+/// An index of the line starts of an input, which computes the `(line, col)` of many positions
+/// without scanning the input from its start every time.
+///
+/// Long lines, e.g. of minified files, are also indexed every few kilobytes, so that computing a
+/// column only scans a bounded part of its line.
+///
+/// # Examples
+///
+/// ```
+/// # use pest::{ColumnMode, LineIndex};
+/// let input = "a\n漢字 = é";
+/// let index = LineIndex::new(input);
+///
+/// assert_eq!(index.line_col(input.len()), (2, 7));
+/// assert_eq!(
+///     index.with_column_mode(ColumnMode::DisplayWidth).line_col(input.len()),
+///     (2, 9)
+/// );
+/// ```
+#[derive(Clone, Debug)]
+pub struct LineIndex<'i> {
+    input: &'i str,
+    line_starts: Vec<usize>,
+    column_mode: ColumnMode,
+    // The byte positions of cluster boundaries inside long lines, along with their 0-based
+    // columns, sorted by position.
+    checkpoints: Vec<(usize, usize)>,
+}
+
+// The minimum number of bytes between two checkpoints of a line.
+const CHECKPOINT_LEN: usize = 4096;
+
+// The maximum number of `char`s joined to the first one of a grapheme cluster, like in the
+// Stream-Safe Text Format of UAX #15, which bounds the work spent on pathological clusters.
+const MAX_CLUSTER_EXTENSION: usize = 31;
+
+impl<'i> LineIndex<'i> {
+    /// Creates a `LineIndex` of `input`, counting columns in `char`s.
+    pub fn new(input: &'i str) -> LineIndex<'i> {
+        let line_starts = core::iter::once(0)
+            .chain(input.match_indices('\n').map(|(i, _)| i + 1))
+            .collect();
+
+        let mut index = LineIndex {
+            input,
+            line_starts,
+            column_mode: ColumnMode::Chars,
+            checkpoints: Vec::new(),
+        };
+        index.checkpoints = index.checkpoints();
+
+        index
+    }
+
+    /// Returns the `LineIndex` counting columns in `column_mode`.
+    pub fn with_column_mode(mut self, column_mode: ColumnMode) -> LineIndex<'i> {
+        if column_mode != self.column_mode {
+            self.column_mode = column_mode;
+            self.checkpoints = self.checkpoints();
+        }
+        self
+    }
+
+    fn checkpoints(&self) -> Vec<(usize, usize)> {
+        let mut checkpoints = Vec::new();
+
+        for (line, &start) in self.line_starts.iter().enumerate() {
+            let end = self
+                .line_starts
+                .get(line + 1)
+                .cloned()
+                .unwrap_or(self.input.len());
+            if end - start <= CHECKPOINT_LEN {
+                continue;
+            }
+
+            let (mut len, mut columns, mut last) = (0, 0, 0);
+            for (cluster, width) in clusters(&self.input[start..end], self.column_mode) {
+                len += cluster.len();
+                columns += width;
+                if len - last >= CHECKPOINT_LEN {
+                    checkpoints.push((start + len, columns));
+                    last = len;
+                }
+            }
+        }
+
+        checkpoints
+    }
+
+    /// Returns the unit in which the `LineIndex` counts columns.
+    #[inline]
+    pub fn column_mode(&self) -> ColumnMode {
+        self.column_mode
+    }
+
+    /// Returns the number of lines of the input.
+    #[inline]
+    pub fn line_count(&self) -> usize {
+        self.line_starts.len()
+    }
+
+    /// Returns the input the `LineIndex` was created from.
+    #[inline]
+    pub(crate) fn input(&self) -> &'i str {
+        self.input
+    }
+
+    /// Returns the 1-based `(line, col)` of the byte position `pos`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `pos` is out of bounds or is not a UTF-8 border. See [`try_line_col`] for a
+    /// non-panicking version.
+    ///
+    /// [`try_line_col`]: #method.try_line_col
+    pub fn line_col(&self, pos: usize) -> (usize, usize) {
+        if pos > self.input.len() {
+            panic!("position out of bounds");
+        }
+
+        self.try_line_col(pos)
+            .expect("position is not a UTF-8 border")
+    }
+
+    /// Returns the 1-based `(line, col)` of the byte position `pos`, or `None` if `pos` is out
+    /// of bounds or is not a UTF-8 border.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use pest::LineIndex;
+    /// let input = "é\nb";
+    /// let index = LineIndex::new(input);
+    ///
+    /// assert_eq!(index.try_line_col(3), Some((2, 1)));
+    /// assert_eq!(index.try_line_col(1), None);
+    /// assert_eq!(index.try_line_col(5), None);
+    /// ```
+    pub fn try_line_col(&self, pos: usize) -> Option<(usize, usize)> {
+        if !self.input.is_char_boundary(pos) {
+            return None;
+        }
+
+        let line = match self.line_starts.binary_search(&pos) {
+            Ok(line) => line,
+            Err(line) => line - 1,
+        };
+        let line_start = self.line_starts[line];
+        let (start, start_columns) = self.checkpoint(line_start, pos);
+
+        Some((
+            line + 1,
+            start_columns + columns(&self.input[start..pos], self.column_mode) + 1,
+        ))
+    }
+
+    /// Returns the byte position of the 1-based `(line, col)`, the inverse of [`line_col`], or
+    /// `None` if there is no such position, e.g. if the line is too short or if the column falls
+    /// in the middle of a wide character in [`ColumnMode::DisplayWidth`]. The column right after
+    /// the end of a line is its line break, if it has one.
+    ///
+    /// [`line_col`]: #method.line_col
+    /// [`ColumnMode::DisplayWidth`]: enum.ColumnMode.html#variant.DisplayWidth
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use pest::LineIndex;
+    /// let input = "é\nab";
+    /// let index = LineIndex::new(input);
+    ///
+    /// assert_eq!(index.offset_of_line_col(1, 2), Some(2));
+    /// assert_eq!(index.offset_of_line_col(2, 3), Some(5));
+    /// assert_eq!(index.offset_of_line_col(2, 4), None);
+    /// assert_eq!(index.offset_of_line_col(3, 1), None);
+    /// ```
+    pub fn offset_of_line_col(&self, line: usize, col: usize) -> Option<usize> {
+        let line_start = *self.line_starts.get(line.checked_sub(1)?)?;
+        let line_end = self
+            .line_starts
+            .get(line)
+            .cloned()
+            .unwrap_or(self.input.len());
+        let col = col.checked_sub(1)?;
+
+        // Skips to the last checkpoint of the line before the column.
+        let i = self.checkpoints.partition_point(|&(checkpoint, columns)| {
+            checkpoint <= line_start || (checkpoint < line_end && columns <= col)
+        });
+        let (start, start_columns) = match i {
+            0 => (line_start, 0),
+            i if self.checkpoints[i - 1].0 <= line_start => (line_start, 0),
+            i => self.checkpoints[i - 1],
+        };
+
+        let (len, past_end) = prefix_len(
+            &self.input[start..line_end],
+            col - start_columns,
+            self.column_mode,
+        );
+        let pos = start + len;
+
+        if past_end == 0 && self.try_line_col(pos) == Some((line, col + 1)) {
+            Some(pos)
+        } else {
+            None
+        }
+    }
+
+    // Returns the last checkpoint of the line starting at `line_start` up to `pos`, or the line
+    // start itself, as a byte position along with its 0-based column.
+    fn checkpoint(&self, line_start: usize, pos: usize) -> (usize, usize) {
+        match self
+            .checkpoints
+            .partition_point(|&(checkpoint, _)| checkpoint <= pos)
+        {
+            0 => (line_start, 0),
+            i if self.checkpoints[i - 1].0 < line_start => (line_start, 0),
+            i => self.checkpoints[i - 1],
+        }
+    }
+}
+
+/// Returns the number of columns taken up by `text` in `mode`.
+pub(crate) fn columns(text: &str, mode: ColumnMode) -> usize {
+    clusters(text, mode).map(|(_, width)| width).sum()
+}
+
+/// Returns the byte length of the prefix of `text` taking up `columns` columns in `mode`, along
+/// with the number of columns that go past the end of `text`.
+pub(crate) fn prefix_len(text: &str, columns: usize, mode: ColumnMode) -> (usize, usize) {
+    let mut len = 0;
+    let mut remaining = columns;
+
+    for (cluster, width) in clusters(text, mode) {
+        if remaining == 0 || width > remaining {
+            return (len, 0);
+        }
+
+        len += cluster.len();
+        remaining -= width;
+    }
+
+    (len, remaining)
+}
+
+/// Iterates over the units of `text` in `mode`, i.e. `char`s or grapheme clusters, along with
+/// the number of columns they take up.
+pub(crate) fn clusters(text: &str, mode: ColumnMode) -> impl Iterator<Item = (&str, usize)> {
+    let mut rest = text;
+
+    core::iter::from_fn(move || {
+        if rest.is_empty() {
+            return None;
+        }
+
+        let len = match mode {
+            ColumnMode::Chars => rest.chars().next().unwrap().len_utf8(),
+            ColumnMode::Graphemes | ColumnMode::DisplayWidth => cluster_len(rest),
+        };
+        let (cluster, tail) = rest.split_at(len);
+        rest = tail;
+
+        let width = match mode {
+            ColumnMode::Chars | ColumnMode::Graphemes => 1,
+            ColumnMode::DisplayWidth => cluster_width(cluster),
+        };
+
+        Some((cluster, width))
+    })
+}
+
+// Approximates the extended grapheme cluster boundaries of UAX #29 with the available Unicode
+// tables: combining and spacing marks, emoji modifiers and ZWJ sequences, and flags.
+fn cluster_len(text: &str) -> usize {
+    let mut chars = text.char_indices().peekable();
+    let first = match chars.next() {
+        Some((_, c)) => c,
+        None => return 0,
+    };
+    let mut len = first.len_utf8();
+
+    match first {
+        '\r' => {
+            if let Some(&(_, '\n')) = chars.peek() {
+                len += 1;
+            }
+            return len;
+        }
+        '\n' => return len,
+        c if unicode::REGIONAL_INDICATOR(c) => {
+            if let Some(&(i, next)) = chars.peek() {
+                if unicode::REGIONAL_INDICATOR(next) {
+                    chars.next();
+                    len = i + next.len_utf8();
+                }
+            }
+        }
+        _ => (),
+    }
+
+    let mut prev = first;
+    let mut extension = 0;
+    while let Some(&(i, c)) = chars.peek() {
+        if extension == MAX_CLUSTER_EXTENSION {
+            break;
+        }
+
+        let joins = unicode::GRAPHEME_EXTEND(c)
+            || unicode::SPACING_MARK(c)
+            || c == '\u{200D}'
+            || ('\u{1F3FB}'..='\u{1F3FF}').contains(&c)
+            || (prev == '\u{200D}' && unicode::OTHER_SYMBOL(c));
+
+        if !joins {
+            break;
+        }
+
+        chars.next();
+        len = i + c.len_utf8();
+        prev = c;
+        extension += 1;
+    }
+
+    len
+}
+
+fn cluster_width(cluster: &str) -> usize {
+    let first = match cluster.chars().next() {
+        Some(c) => c,
+        None => return 0,
+    };
+
+    if cluster.contains('\u{FE0F}') || unicode::REGIONAL_INDICATOR(first) || is_wide(first) {
+        2
+    } else if unicode::GRAPHEME_EXTEND(first) || first == '\u{200B}' {
+        0
+    } else {
+        1
+    }
+}
+
+// The East Asian Wide and Fullwidth ranges, including the emoji presented as wide by default.
+static WIDE: &[(u32, u32)] = &[
+    (0x1100, 0x115F),
+    (0x231A, 0x231B),
+    (0x2329, 0x232A),
+    (0x23E9, 0x23EC),
+    (0x23F0, 0x23F0),
+    (0x23F3, 0x23F3),
+    (0x25FD, 0x25FE),
+    (0x2614, 0x2615),
+    (0x2648, 0x2653),
+    (0x267F, 0x267F),
+    (0x2693, 0x2693),
+    (0x26A1, 0x26A1),
+    (0x26AA, 0x26AB),
+    (0x26BD, 0x26BE),
+    (0x26C4, 0x26C5),
+    (0x26CE, 0x26CE),
+    (0x26D4, 0x26D4),
+    (0x26EA, 0x26EA),
+    (0x26F2, 0x26F3),
+    (0x26F5, 0x26F5),
+    (0x26FA, 0x26FA),
+    (0x26FD, 0x26FD),
+    (0x2705, 0x2705),
+    (0x270A, 0x270B),
+    (0x2728, 0x2728),
+    (0x274C, 0x274C),
+    (0x274E, 0x274E),
+    (0x2753, 0x2755),
+    (0x2757, 0x2757),
+    (0x2795, 0x2797),
+    (0x27B0, 0x27B0),
+    (0x27BF, 0x27BF),
+    (0x2B1B, 0x2B1C),
+    (0x2B50, 0x2B50),
+    (0x2B55, 0x2B55),
+    (0x2E80, 0x303E),
+    (0x3041, 0x33FF),
+    (0x3400, 0x4DBF),
+    (0x4E00, 0x9FFF),
+    (0xA000, 0xA4CF),
+    (0xA960, 0xA97F),
+    (0xAC00, 0xD7A3),
+    (0xF900, 0xFAFF),
+    (0xFE10, 0xFE19),
+    (0xFE30, 0xFE6F),
+    (0xFF00, 0xFF60),
+    (0xFFE0, 0xFFE6),
+    (0x16FE0, 0x16FE4),
+    (0x17000, 0x18AFF),
+    (0x1B000, 0x1B2FF),
+    (0x1F004, 0x1F004),
+    (0x1F0CF, 0x1F0CF),
+    (0x1F18E, 0x1F18E),
+    (0x1F191, 0x1F19A),
+    (0x1F200, 0x1F202),
+    (0x1F210, 0x1F23B),
+    (0x1F240, 0x1F248),
+    (0x1F250, 0x1F251),
+    (0x1F260, 0x1F265),
+    (0x1F300, 0x1F320),
+    (0x1F32D, 0x1F335),
+    (0x1F337, 0x1F37C),
+    (0x1F37E, 0x1F393),
+    (0x1F3A0, 0x1F3CA),
+    (0x1F3CF, 0x1F3D3),
+    (0x1F3E0, 0x1F3F0),
+    (0x1F3F4, 0x1F3F4),
+    (0x1F3F8, 0x1F43E),
+    (0x1F440, 0x1F440),
+    (0x1F442, 0x1F4FC),
+    (0x1F4FF, 0x1F53D),
+    (0x1F54B, 0x1F54E),
+    (0x1F550, 0x1F567),
+    (0x1F57A, 0x1F57A),
+    (0x1F595, 0x1F596),
+    (0x1F5A4, 0x1F5A4),
+    (0x1F5FB, 0x1F64F),
+    (0x1F680, 0x1F6C5),
+    (0x1F6CC, 0x1F6CC),
+    (0x1F6D0, 0x1F6D2),
+    (0x1F6D5, 0x1F6D7),
+    (0x1F6EB, 0x1F6EC),
+    (0x1F6F4, 0x1F6FC),
+    (0x1F7E0, 0x1F7EB),
+    (0x1F90C, 0x1F93A),
+    (0x1F93C, 0x1F945),
+    (0x1F947, 0x1F9FF),
+    (0x1FA70, 0x1FAFF),
+    (0x20000, 0x2FFFD),
+    (0x30000, 0x3FFFD),
+];
+
+fn is_wide(c: char) -> bool {
+    let c = c as u32;
+
+    WIDE.binary_search_by(|&(start, end)| {
+        if end < c {
+            core::cmp::Ordering::Less
+        } else if start > c {
+            core::cmp::Ordering::Greater
+        } else {
+            core::cmp::Ordering::Equal
+        }
+    })
+    .is_ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::position::Position;
+    use alloc::format;
+
+    #[test]
+    fn line_col_chars() {
+        let input = "a\r\nbc\n\ndé";
+        let index = LineIndex::new(input);
+
+        for pos in (0..=input.len()).filter(|&pos| input.is_char_boundary(pos)) {
+            if pos != 2 {
+                assert_eq!(
+                    index.line_col(pos),
+                    Position::new(input, pos).unwrap().line_col()
+                );
+            }
+        }
+        assert_eq!(index.line_count(), 4);
+    }
+
+    #[test]
+    fn line_col_graphemes() {
+        let input = "e\u{301}👍🏽🇫🇷👨‍👩‍👧x";
+        let index = LineIndex::new(input).with_column_mode(ColumnMode::Graphemes);
+
+        assert_eq!(index.line_col(input.len()), (1, 6));
+        assert_eq!(index.line_col(input.len() - 1), (1, 5));
+    }
+
+    #[test]
+    fn line_col_display_width() {
+        let input = "e\u{301}👍🏽漢ｘ❤\u{FE0F}!";
+        let index = LineIndex::new(input).with_column_mode(ColumnMode::DisplayWidth);
+
+        assert_eq!(index.line_col(input.len()), (1, 11));
+        assert_eq!(index.line_col(input.len() - 1), (1, 10));
+    }
+
+    #[test]
+    fn try_line_col() {
+        let input = "a\n漢";
+        let index = LineIndex::new(input);
+
+        assert_eq!(index.try_line_col(2), Some((2, 1)));
+        assert_eq!(index.try_line_col(5), Some((2, 2)));
+        assert_eq!(index.try_line_col(3), None);
+        assert_eq!(index.try_line_col(6), None);
+    }
+
+    #[test]
+    fn line_col_long_line() {
+        let line = "a漢e\u{301}👍🏽🇫🇷\t".repeat(1000);
+        let input = format!("x\n{}\n{}", line, line);
+        let start = line.len() + 3;
+
+        for mode in [
+            ColumnMode::Chars,
+            ColumnMode::Graphemes,
+            ColumnMode::DisplayWidth,
+        ] {
+            let index = LineIndex::new(&input).with_column_mode(mode);
+            assert!(!index.checkpoints.is_empty());
+
+            for pos in (start..=input.len()).step_by(997) {
+                if input.is_char_boundary(pos) {
+                    assert_eq!(
+                        index.line_col(pos),
+                        (3, columns(&input[start..pos], mode) + 1)
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn offset_of_line_col_long_line() {
+        let line = "a漢e\u{301}👍🏽🇫🇷\t".repeat(1000);
+        let input = format!("x\n{}\n{}", line, line);
+
+        for mode in [
+            ColumnMode::Chars,
+            ColumnMode::Graphemes,
+            ColumnMode::DisplayWidth,
+        ] {
+            let index = LineIndex::new(&input).with_column_mode(mode);
+
+            for pos in (0..=input.len()).step_by(331) {
+                if let Some((line, col)) = index.try_line_col(pos) {
+                    let offset = index.offset_of_line_col(line, col).unwrap();
+
+                    // Positions inside of a cluster share a column with one of its boundaries.
+                    assert_eq!(index.line_col(offset), (line, col));
+                    if mode == ColumnMode::Chars {
+                        assert_eq!(offset, pos);
+                    }
+                }
+            }
+            assert_eq!(index.offset_of_line_col(2, 0), None);
+        }
+    }
+
+    #[test]
+    fn long_cluster() {
+        let input = format!("e{}x", "\u{301}".repeat(100));
+        let index = LineIndex::new(&input).with_column_mode(ColumnMode::Graphemes);
+
+        // The marks are split into clusters of at most 32 `char`s.
+        assert_eq!(index.line_col(input.len()), (1, 6));
+        assert_eq!(columns(&input, ColumnMode::Chars), 102);
+    }
+
+    #[test]
+    fn prefix() {
+        assert_eq!(prefix_len("漢字a", 2, ColumnMode::DisplayWidth), (3, 0));
+        assert_eq!(prefix_len("漢字a", 3, ColumnMode::DisplayWidth), (3, 0));
+        assert_eq!(prefix_len("漢字a", 7, ColumnMode::DisplayWidth), (7, 2));
+    }
+}
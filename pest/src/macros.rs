@@ -7,31 +7,74 @@
 // option. All files in the project carrying such notice may not be copied,
 // modified, or distributed except according to those terms.
 
+use alloc::format;
+use alloc::string::String;
+
+/// A position expected by [`parses_to!`] and [`fails_with!`], which is either a byte position
+/// (`usize`) or a 1-based `(line, col)` pair.
+///
+/// [`parses_to!`]: macro.parses_to.html
+/// [`fails_with!`]: macro.fails_with.html
+#[doc(hidden)]
+pub trait ExpectedPosition {
+    fn matches<F: FnOnce() -> (usize, usize)>(&self, pos: usize, line_col: F) -> bool;
+    fn describe(&self) -> String;
+    fn describe_found<F: FnOnce() -> (usize, usize)>(&self, pos: usize, line_col: F) -> String;
+}
+
+impl ExpectedPosition for usize {
+    fn matches<F: FnOnce() -> (usize, usize)>(&self, pos: usize, _: F) -> bool {
+        *self == pos
+    }
+
+    fn describe(&self) -> String {
+        format!("pos: {}", self)
+    }
+
+    fn describe_found<F: FnOnce() -> (usize, usize)>(&self, pos: usize, _: F) -> String {
+        format!("{}", pos)
+    }
+}
+
+impl ExpectedPosition for (usize, usize) {
+    fn matches<F: FnOnce() -> (usize, usize)>(&self, _: usize, line_col: F) -> bool {
+        *self == line_col()
+    }
+
+    fn describe(&self) -> String {
+        format!("line_col: {:?}", self)
+    }
+
+    fn describe_found<F: FnOnce() -> (usize, usize)>(&self, _: usize, line_col: F) -> String {
+        format!("line_col: {:?}", line_col())
+    }
+}
+
 #[doc(hidden)]
 #[macro_export]
 macro_rules! consumes_to {
     ( $_rules:ident, $tokens:expr, [] ) => ();
     ( $rules:ident, $tokens:expr, [ $name:ident ( $start:expr, $end:expr ) ] ) => {
-        let expected = format!("expected Start {{ rule: {:?}, pos: Position {{ pos: {} }} }}",
-                               $rules::$name, $start);
+        let expected = format!("expected Start {{ rule: {:?}, pos: Position {{ {} }} }}",
+                               $rules::$name, $crate::ExpectedPosition::describe(&$start));
         match $tokens.next().expect(&format!("{} but found nothing", expected)) {
             $crate::Token::Start { rule, pos } => {
                 assert!(
-                    rule == $rules::$name && pos.pos() == $start,
+                    rule == $rules::$name && $crate::ExpectedPosition::matches(&$start, pos.pos(), || pos.line_col()),
                     "{} but found Start {{ rule: {:?}, pos: Position {{ {} }} }}",
-                    expected, rule, pos.pos(),
+                    expected, rule, $crate::ExpectedPosition::describe_found(&$start, pos.pos(), || pos.line_col()),
                 )
             },
             token => panic!("{} but found {:?}", expected, token)
         };
 
-        let expected = format!("expected End {{ rule: {:?}, pos: Position {{ pos: {} }} }}",
-                               $rules::$name, $end);
+        let expected = format!("expected End {{ rule: {:?}, pos: Position {{ {} }} }}",
+                               $rules::$name, $crate::ExpectedPosition::describe(&$end));
         match $tokens.next().expect(&format!("{} but found nothing", expected)) {
             $crate::Token::End { rule, pos } => {
-                assert!(rule == $rules::$name && pos.pos() == $end,
+                assert!(rule == $rules::$name && $crate::ExpectedPosition::matches(&$end, pos.pos(), || pos.line_col()),
                     "{} but found End {{ rule: {:?}, pos: Position {{ {} }} }}",
-                    expected, rule, pos.pos(),
+                    expected, rule, $crate::ExpectedPosition::describe_found(&$end, pos.pos(), || pos.line_col()),
                 );
             },
             token => panic!("{} but found {:?}", expected, token)
@@ -40,25 +83,25 @@ macro_rules! consumes_to {
     ( $rules:ident, $tokens:expr, [ $name:ident ( $start:expr, $end:expr ),
                                     $( $names:ident $calls:tt ),* $(,)* ] ) => {
 
-        let expected = format!("expected Start {{ rule: {:?}, pos: Position {{ pos: {} }} }}",
-                               $rules::$name, $start);
+        let expected = format!("expected Start {{ rule: {:?}, pos: Position {{ {} }} }}",
+                               $rules::$name, $crate::ExpectedPosition::describe(&$start));
         match $tokens.next().expect(&format!("{} but found nothing", expected)) {
             $crate::Token::Start { rule, pos } => {
-                assert!(rule == $rules::$name && pos.pos() == $start,
+                assert!(rule == $rules::$name && $crate::ExpectedPosition::matches(&$start, pos.pos(), || pos.line_col()),
                     "{} but found Start {{ rule: {:?}, pos: Position {{ {} }} }}",
-                    expected, rule, pos.pos(),
+                    expected, rule, $crate::ExpectedPosition::describe_found(&$start, pos.pos(), || pos.line_col()),
                 );
             },
             token => panic!("{} but found {:?}", expected, token)
         };
 
-        let expected = format!("expected End {{ rule: {:?}, pos: Position {{ pos: {} }} }}",
-                               $rules::$name, $end);
+        let expected = format!("expected End {{ rule: {:?}, pos: Position {{ {} }} }}",
+                               $rules::$name, $crate::ExpectedPosition::describe(&$end));
         match $tokens.next().expect(&format!("{} but found nothing", expected)) {
             $crate::Token::End { rule, pos } => {
-                assert!(rule == $rules::$name && pos.pos() == $end,
+                assert!(rule == $rules::$name && $crate::ExpectedPosition::matches(&$end, pos.pos(), || pos.line_col()),
                     "{} but found End {{ rule: {:?}, pos: Position {{ {} }} }}",
-                    expected, rule, pos.pos(),
+                    expected, rule, $crate::ExpectedPosition::describe_found(&$end, pos.pos(), || pos.line_col()),
                 );
             },
             token => panic!("{} but found {:?}", expected, token)
@@ -68,13 +111,13 @@ macro_rules! consumes_to {
     };
     ( $rules:ident, $tokens:expr, [ $name:ident ( $start:expr, $end:expr,
                                                   [ $( $names:ident $calls:tt ),* $(,)* ] ) ] ) => {
-        let expected = format!("expected Start {{ rule: {:?}, pos: Position {{ pos: {} }} }}",
-                               $rules::$name, $start);
+        let expected = format!("expected Start {{ rule: {:?}, pos: Position {{ {} }} }}",
+                               $rules::$name, $crate::ExpectedPosition::describe(&$start));
         match $tokens.next().expect(&format!("{} but found nothing", expected)) {
             $crate::Token::Start { rule, pos } => {
-                assert!(rule == $rules::$name && pos.pos() == $start,
+                assert!(rule == $rules::$name && $crate::ExpectedPosition::matches(&$start, pos.pos(), || pos.line_col()),
                     "{} but found Start {{ rule: {:?}, pos: Position {{ {} }} }}",
-                    expected, rule, pos.pos(),
+                    expected, rule, $crate::ExpectedPosition::describe_found(&$start, pos.pos(), || pos.line_col()),
                 );
             },
             token => panic!("{} but found {:?}", expected, token)
@@ -82,13 +125,13 @@ macro_rules! consumes_to {
 
         consumes_to!($rules, $tokens, [ $( $names $calls ),* ]);
 
-        let expected = format!("expected End {{ rule: {:?}, pos: Position {{ pos: {} }} }}",
-                               $rules::$name, $end);
+        let expected = format!("expected End {{ rule: {:?}, pos: Position {{ {} }} }}",
+                               $rules::$name, $crate::ExpectedPosition::describe(&$end));
         match $tokens.next().expect(&format!("{} but found nothing", expected)) {
             $crate::Token::End { rule, pos } => {
-                assert!(rule == $rules::$name && pos.pos() == $end,
+                assert!(rule == $rules::$name && $crate::ExpectedPosition::matches(&$end, pos.pos(), || pos.line_col()),
                     "{} but found End {{ rule: {:?}, pos: Position {{ {} }} }}",
-                    expected, rule, pos.pos(),
+                    expected, rule, $crate::ExpectedPosition::describe_found(&$end, pos.pos(), || pos.line_col()),
                 );
             },
             token => panic!("{} but found {:?}", expected, token)
@@ -99,13 +142,13 @@ macro_rules! consumes_to {
                                                   $(,)* ] ),
                                     $( $names:ident $calls:tt ),* ] ) => {
 
-        let expected = format!("expected Start {{ rule: {:?}, pos: Position {{ pos: {} }} }}",
-                               $rules::$name, $start);
+        let expected = format!("expected Start {{ rule: {:?}, pos: Position {{ {} }} }}",
+                               $rules::$name, $crate::ExpectedPosition::describe(&$start));
         match $tokens.next().expect(&format!("{} but found nothing", expected)) {
             $crate::Token::Start { rule, pos } => {
-                assert!(rule == $rules::$name && pos.pos() == $start,
+                assert!(rule == $rules::$name && $crate::ExpectedPosition::matches(&$start, pos.pos(), || pos.line_col()),
                     "{} but found Start {{ rule: {:?}, pos: Position {{ {} }} }}",
-                    expected, rule, pos.pos(),
+                    expected, rule, $crate::ExpectedPosition::describe_found(&$start, pos.pos(), || pos.line_col()),
                 );
             },
             token => panic!("{} but found {:?}", expected, token)
@@ -113,13 +156,13 @@ macro_rules! consumes_to {
 
         consumes_to!($rules, $tokens, [ $( $nested_names $nested_calls ),* ]);
 
-        let expected = format!("expected End {{ rule: {:?}, pos: Position {{ pos: {} }} }}",
-                               $rules::$name, $end);
+        let expected = format!("expected End {{ rule: {:?}, pos: Position {{ {} }} }}",
+                               $rules::$name, $crate::ExpectedPosition::describe(&$end));
         match $tokens.next().expect(&format!("{} but found nothing", expected)) {
             $crate::Token::End { rule, pos } => {
-                assert!(rule == $rules::$name && pos.pos() == $end,
+                assert!(rule == $rules::$name && $crate::ExpectedPosition::matches(&$end, pos.pos(), || pos.line_col()),
                     "{} but found End {{ rule: {:?}, pos: Position {{ {} }} }}",
-                    expected, rule, pos.pos(),
+                    expected, rule, $crate::ExpectedPosition::describe_found(&$end, pos.pos(), || pos.line_col()),
                 );
             },
             token => panic!("{} but found {:?}", expected, token)
@@ -138,7 +181,8 @@ macro_rules! consumes_to {
 /// * `rule` - `Rule` which will be run
 /// * `tokens` - token pairs of the form `name(start_pos, end_pos, [nested_child_tokens])`
 ///
-/// *Note:* `start_pos` and `end_pos` are byte positions.
+/// *Note:* `start_pos` and `end_pos` are either byte positions or 1-based `(line, col)` pairs, which
+/// can be mixed freely.
 ///
 /// # Examples
 ///
@@ -239,7 +283,7 @@ macro_rules! parses_to {
 /// * `rule` - `Rule` which will be run
 /// * `positives` - positive `Rule` attempts that failed
 /// * `negatives` - negative `Rule` attempts that failed
-/// * `pos` - byte position of failure
+/// * `pos` - byte position or 1-based `(line, col)` pair of failure
 ///
 /// # Examples
 ///
@@ -307,13 +351,103 @@ macro_rules! fails_with {
             };
 
             match error.location {
-                $crate::error::InputLocation::Pos(pos) => assert_eq!(pos, $pos, "pos"),
+                $crate::error::InputLocation::Pos(pos) => {
+                    let line_col = match error.line_col {
+                        $crate::error::LineColLocation::Pos(line_col) => line_col,
+                        _ => unreachable!(),
+                    };
+                    assert!(
+                        $crate::ExpectedPosition::matches(&$pos, pos, || line_col),
+                        "pos: expected {} but found {}",
+                        $crate::ExpectedPosition::describe(&$pos),
+                        $crate::ExpectedPosition::describe_found(&$pos, pos, || line_col),
+                    );
+                }
                 _ => unreachable!(),
             }
         }
     };
 }
 
+/// Matches the rule of a `pair` against every rule which can appear as a direct child of the
+/// `parent` rule, failing to compile when one of them is missing or is not a child of `parent`.
+///
+/// The children are taken from the `rule_children` module generated next to `Rule` by
+/// `pest_derive`, which needs to be in scope along with `Rule`. This way, adding a rule to the
+/// grammar where `parent` can produce it breaks every `match_rule!` over `parent` until it is
+/// handled.
+///
+/// # Panics
+///
+/// Panics if the rule of `pair` is not a child of `parent`.
+///
+/// # Examples
+///
+/// ```
+/// # #[macro_use]
+/// # extern crate pest;
+/// # use pest::Parser;
+/// # use pest::error::Error;
+/// # use pest::iterators::Pairs;
+/// # fn main() {
+/// # #[allow(non_camel_case_types)]
+/// # #[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+/// # enum Rule {
+/// #     a,
+/// #     b,
+/// #     c
+/// # }
+/// #
+/// # #[allow(non_camel_case_types)]
+/// # mod rule_children {
+/// #     pub enum a { b }
+/// # }
+/// #
+/// # struct AbcParser;
+/// #
+/// # impl Parser<Rule> for AbcParser {
+/// #     fn parse<'i>(_: Rule, input: &'i str) -> Result<Pairs<'i, Rule>, Error<Rule>> {
+/// #         pest::state(input, |state| {
+/// #             state.rule(Rule::a, |state| {
+/// #                 state.skip(1).unwrap().rule(Rule::b, |s| {
+/// #                     s.skip(1)
+/// #                 }).unwrap().skip(1)
+/// #             })
+/// #         })
+/// #     }
+/// # }
+/// // a = { "a" ~ b ~ "c" }
+/// let a = AbcParser::parse(Rule::a, "abc").unwrap().next().unwrap();
+///
+/// for pair in a.into_inner() {
+///     let text = match_rule!(pair, Rule::a {
+///         b => pair.as_str(),
+///     });
+///     assert_eq!(text, "b");
+/// }
+/// # }
+/// ```
+#[macro_export]
+macro_rules! match_rule {
+    ( $pair:expr, $rules:ident :: $parent:ident { $( $child:ident => $body:expr ),* $(,)? } ) => {
+        {
+            #[allow(dead_code)]
+            fn check_children(child: rule_children::$parent) {
+                match child {
+                    $( rule_children::$parent::$child => (), )*
+                }
+            }
+
+            #[allow(unreachable_patterns)]
+            let result = match $crate::iterators::Pair::as_rule(&$pair) {
+                $( $rules::$child => $body, )*
+                rule => panic!("{:?} is not a child of {:?}", rule, $rules::$parent),
+            };
+            result
+        }
+    };
+}
+
 #[cfg(test)]
 pub mod tests {
     use super::super::error::Error;
@@ -440,4 +574,75 @@ pub mod tests {
             pos: 3
         };
     }
+
+    #[test]
+    fn parses_to_line_col() {
+        parses_to! {
+            parser: AbcParser,
+            input: "a\ncde",
+            rule: Rule::a,
+            tokens: [
+                a((1, 1), 3, [
+                    b((1, 2), (2, 1)),
+                ]),
+                c(4, (2, 4))
+            ]
+        };
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "expected End { rule: b, pos: Position { line_col: (1, 3) } } \
+                               but found End { rule: b, pos: Position { line_col: (2, 1) } }"
+    )]
+    fn wrong_line_col() {
+        parses_to! {
+            parser: AbcParser,
+            input: "a\ncde",
+            rule: Rule::a,
+            tokens: [
+                a(0, 3, [
+                    b(1, (1, 3)),
+                ]),
+                c(4, 5)
+            ]
+        };
+    }
+
+    #[test]
+    fn fails_with_line_col() {
+        fails_with! {
+            parser: AbcParser,
+            input: "a\ncdf",
+            rule: Rule::a,
+            positives: vec![Rule::c],
+            negatives: vec![],
+            pos: (2, 3)
+        };
+    }
+
+    #[allow(dead_code, non_camel_case_types)]
+    mod rule_children {
+        pub enum a {
+            b,
+        }
+    }
+
+    #[test]
+    fn match_rule() {
+        let a = AbcParser::parse(Rule::a, "abcde").unwrap().next().unwrap();
+        let rules: Vec<_> = a
+            .into_inner()
+            .map(|pair| match_rule!(pair, Rule::a { b => pair.as_str() }))
+            .collect();
+
+        assert_eq!(rules, ["b"]);
+    }
+
+    #[test]
+    #[should_panic(expected = "c is not a child of a")]
+    fn match_rule_not_child() {
+        let c = AbcParser::parse(Rule::a, "abcde").unwrap().nth(1).unwrap();
+        match_rule!(c, Rule::a { b => () });
+    }
 }
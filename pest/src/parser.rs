@@ -7,8 +7,25 @@
 // option. All files in the project carrying such notice may not be copied,
 // modified, or distributed except according to those terms.
 
-use crate::error::Error;
-use crate::iterators::Pairs;
+use alloc::boxed::Box;
+use alloc::format;
+use alloc::rc::Rc;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::any::Any;
+
+use crate::error::{Error, ErrorHead, ErrorVariant, InputLocation};
+#[cfg(feature = "std")]
+use crate::external;
+use crate::input::{ByteInput, Input, PieceInput};
+use crate::iterators::{pairs, CompactPairs, Pairs};
+use crate::line_index::LineIndex;
+use crate::parser_state::{
+    ParseOptions, ParseResult, ParseReuse, ParseStats, ParserState, Recovery, Repair, Tolerance,
+};
+use crate::position::Position;
+use crate::resumable::{self, Resumable};
+use crate::span::Span;
 use crate::RuleType;
 
 /// A trait with a single method that parses strings.
@@ -16,4 +33,909 @@ pub trait Parser<R: RuleType> {
     /// Parses a `&str` starting from `rule`.
     #[allow(clippy::perf)]
     fn parse(rule: R, input: &str) -> Result<Pairs<'_, R>, Error<R>>;
+
+    /// Parses a `&str` starting from `rule`, only returning the [`ErrorHead`] of a failed parse,
+    /// which is cheaper to create than an `Error` when most errors are retried or discarded.
+    ///
+    /// Parsers generated by `pest_derive` never build the full `Error` here, while the default
+    /// implementation converts the one returned by [`parse`].
+    ///
+    /// [`ErrorHead`]: error/struct.ErrorHead.html
+    /// [`parse`]: #tymethod.parse
+    #[allow(clippy::perf)]
+    fn parse_head(rule: R, input: &str) -> Result<Pairs<'_, R>, ErrorHead<R>> {
+        Self::parse(rule, input).map_err(ErrorHead::from)
+    }
+
+    /// Parses an [`Input`] starting from `rule`, borrowing its text instead of copying it.
+    ///
+    /// Inputs whose text is not valid UTF-8, which [`Input::try_as_str`] reports, fail with an
+    /// error at the start of the input.
+    ///
+    /// [`Input`]: input/trait.Input.html
+    /// [`Input::try_as_str`]: input/trait.Input.html#method.try_as_str
+    #[allow(clippy::perf)]
+    fn parse_input<I: Input + ?Sized>(rule: R, input: &I) -> Result<Pairs<'_, R>, Error<R>> {
+        match input.try_as_str() {
+            Ok(text) => Self::parse(rule, text),
+            Err(error) => Err(Error::new_from_pos(
+                ErrorVariant::CustomError {
+                    message: format!("input is not valid UTF-8: {}", error),
+                },
+                Position::from_start(""),
+            )),
+        }
+    }
+
+    /// Parses every piece of the [`PieceInput`] `input` starting from `rule`, one at a time,
+    /// passing the byte offset of every piece in the whole input to `f` along with the result of
+    /// its parse. This parses inputs which are not held in memory as a whole, like large log files
+    /// read line by line, as long as `rule` matches whole pieces, since no match spans two pieces.
+    ///
+    /// The positions of the `Pairs` and errors passed to `f` are relative to their piece. Stops at
+    /// the first piece which cannot be read, returning its error.
+    ///
+    /// [`PieceInput`]: input/trait.PieceInput.html
+    #[allow(clippy::perf)]
+    fn parse_pieces<I, F>(rule: R, input: &mut I, mut f: F) -> Result<(), I::Error>
+    where
+        I: PieceInput + ?Sized,
+        F: FnMut(usize, Result<Pairs<'_, R>, Error<R>>),
+    {
+        while let Some(piece) = input.next_piece() {
+            let (offset, piece) = piece?;
+            f(offset, Self::parse(rule, piece));
+        }
+
+        Ok(())
+    }
+
+    /// Parses the text covered by `span` starting from `rule`. Unlike parsing `span.as_str()`, the
+    /// positions of the resulting `Pairs` and of the returned error are expressed relative to the
+    /// full input of the `span`, which keeps two-stage parses in a single coordinate system.
+    ///
+    /// The parse itself only sees the text covered by `span`, so `SOI` and `EOI` match at the
+    /// span's boundaries.
+    #[allow(clippy::perf)]
+    fn parse_span(rule: R, span: Span<'_>) -> Result<Pairs<'_, R>, Error<R>> {
+        let input = span.get_input();
+        let offset = span.start();
+
+        match Self::parse(rule, span.as_str()) {
+            Ok(pairs) => Ok(pairs::rebase(pairs, input, offset)),
+            Err(error) => Err(error.rebase(input, offset)),
+        }
+    }
+
+    /// Parses a `&str` starting from `rule` in compact mode, returning the result as
+    /// [`CompactPairs`], which are better suited for holding on to the output of very large
+    /// parses. The parse keeps its tokens in a compact queue instead of the regular one, which
+    /// lowers its peak memory as well as the memory held after it. See [`state_compact`].
+    ///
+    /// [`CompactPairs`]: iterators/struct.CompactPairs.html
+    /// [`state_compact`]: fn.state_compact.html
+    #[allow(clippy::perf)]
+    fn parse_compact(rule: R, input: &str) -> Result<CompactPairs<'_, R>, Error<R>> {
+        crate::parser_state::state_compact(input, |state| Self::parse_state(rule, state))
+    }
+
+    /// Parses a `&str` trying each of the start `rules` in order, returning the `Pairs` of the
+    /// first one that matches.
+    ///
+    /// If every rule fails, the errors which got the furthest into the input are combined into one
+    /// expecting all of their rules, while the others are kept as labels pointing at where their
+    /// rules failed, in the order of `rules`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `rules` is empty.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use pest::{Parser, iterators::Pairs, error::Error};
+    /// # #[allow(non_camel_case_types)]
+    /// # #[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+    /// # enum Rule { expr, stmt }
+    /// # struct AnyParser;
+    /// # impl Parser<Rule> for AnyParser {
+    /// #     fn parse(rule: Rule, input: &str) -> Result<Pairs<'_, Rule>, Error<Rule>> {
+    /// #         pest::state(input, |s| match rule {
+    /// #             Rule::expr => s.rule(Rule::expr, |s| s.match_string("1")),
+    /// #             Rule::stmt => s.rule(Rule::stmt, |s| s.match_string("1;")),
+    /// #         })
+    /// #     }
+    /// # }
+    /// // Rule::expr matches "1" while Rule::stmt matches "1;"
+    /// let pairs = AnyParser::parse_any(&[Rule::expr, Rule::stmt], "1;").unwrap();
+    ///
+    /// assert_eq!(pairs.peek().unwrap().as_rule(), Rule::expr);
+    /// ```
+    #[allow(clippy::perf)]
+    fn parse_any<'i>(rules: &[R], input: &'i str) -> Result<Pairs<'i, R>, Error<R>> {
+        let mut errors = Vec::with_capacity(rules.len());
+
+        for &rule in rules {
+            match Self::parse(rule, input) {
+                Ok(pairs) => return Ok(pairs),
+                Err(error) => errors.push((rule, error)),
+            }
+        }
+
+        Err(combine_errors(input, errors))
+    }
+
+    /// Parses a `&str` made of consecutive items, each starting from `rule`, collecting up to
+    /// `max_errors` errors instead of stopping at the first one. Returns the `Pairs` of every
+    /// successfully parsed item along with the errors, all expressed relative to `input`.
+    ///
+    /// Items are parsed back to back, so `rule` should consume whatever separates two items, and
+    /// should not be silent, since an item ends where its last `Pair` ends. When an item fails,
+    /// parsing resumes right after the next occurrence of one of the `boundaries`, starting from
+    /// the error's position. Parsing stops at the end of the input, after `max_errors` errors, or
+    /// when no boundary follows an error.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use pest::{Parser, iterators::Pairs, error::Error};
+    /// # #[allow(non_camel_case_types)]
+    /// # #[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+    /// # enum Rule { item }
+    /// # struct ItemParser;
+    /// # impl Parser<Rule> for ItemParser {
+    /// #     fn parse(_: Rule, input: &str) -> Result<Pairs<'_, Rule>, Error<Rule>> {
+    /// #         pest::state(input, |s| s.rule(Rule::item, |s| s.match_string("ok;")))
+    /// #     }
+    /// # }
+    /// // Rule::item matches "ok;"
+    /// let (items, errors) = ItemParser::parse_items(Rule::item, "ok;no;ok;nope;", &[";"], 10);
+    ///
+    /// assert_eq!(items.len(), 2);
+    /// assert_eq!(errors.len(), 2);
+    /// ```
+    #[allow(clippy::perf)]
+    fn parse_items<'i>(
+        rule: R,
+        input: &'i str,
+        boundaries: &[&str],
+        max_errors: usize,
+    ) -> (Vec<Pairs<'i, R>>, Vec<Error<R>>) {
+        let mut items = Vec::new();
+        let mut errors = Vec::new();
+        let mut start = 0;
+
+        while start < input.len() && errors.len() < max_errors {
+            // Spans between item boundaries are always valid, since positions come from parses.
+            let span = Span::new(input, start, input.len()).unwrap();
+
+            let error = match Self::parse_span(rule, span) {
+                Ok(pairs) => match pairs::end_pos(&pairs) {
+                    Some(end) if end > start => {
+                        items.push(pairs);
+                        start = end;
+                        continue;
+                    }
+                    _ => Error::new_from_pos(
+                        ErrorVariant::ParsingError {
+                            positives: vec![rule],
+                            negatives: vec![],
+                        },
+                        Position::new(input, start).unwrap(),
+                    ),
+                },
+                Err(error) => error,
+            };
+
+            let from = match error.location {
+                InputLocation::Pos(pos) => pos,
+                InputLocation::Span((pos, _)) => pos,
+            }
+            .max(start);
+            errors.push(error);
+
+            let next = boundaries
+                .iter()
+                .filter(|boundary| !boundary.is_empty())
+                .filter_map(|boundary| {
+                    input[from..]
+                        .find(boundary)
+                        .map(|index| from + index + boundary.len())
+                })
+                .min();
+
+            match next {
+                Some(next) => start = next,
+                None => break,
+            }
+        }
+
+        (items, errors)
+    }
+
+    /// Parses a `&str` starting from `rule` in recovery mode, returning the `Pairs`, where the input
+    /// skipped after each error is covered by a pair of the error rule of `recovery`, along with
+    /// the errors which were recovered from. If the parse still fails, the `Pairs` are empty and
+    /// its error comes last. See [`Recovery`] for how the parse resynchronizes.
+    ///
+    /// Parsers generated by `pest_derive` support recovery, while the default implementation stops
+    /// at the first error, like [`parse`].
+    ///
+    /// [`Recovery`]: struct.Recovery.html
+    /// [`parse`]: #tymethod.parse
+    #[allow(clippy::perf)]
+    fn parse_recoverable<'i>(
+        rule: R,
+        input: &'i str,
+        recovery: &Recovery<R>,
+    ) -> (Pairs<'i, R>, Vec<Error<R>>) {
+        let _ = recovery;
+        match Self::parse(rule, input) {
+            Ok(pairs) => (pairs, vec![]),
+            Err(error) => (pairs::new(Rc::new(vec![]), input, 0, 0), vec![error]),
+        }
+    }
+
+    /// Parses a `&str` starting from `rule` in tolerant mode, an experimental mode where literals
+    /// may deviate from the input by one character within the rules of `tolerance`, returning the
+    /// `Pairs` along with the repairs made to match them. The input is only parsed tolerantly if
+    /// it fails to parse strictly. See [`Tolerance`].
+    ///
+    /// Parsers generated by `pest_derive` support tolerant mode, while the default implementation
+    /// parses strictly, like [`parse`].
+    ///
+    /// [`Tolerance`]: struct.Tolerance.html
+    /// [`parse`]: #tymethod.parse
+    #[allow(clippy::perf)]
+    fn parse_tolerant<'i>(
+        rule: R,
+        input: &'i str,
+        tolerance: &Tolerance<R>,
+    ) -> Result<(Pairs<'i, R>, Vec<Repair>), Error<R>> {
+        let _ = tolerance;
+        Self::parse(rule, input).map(|pairs| (pairs, vec![]))
+    }
+
+    /// Parses a `&str` starting from `rule`, like [`parse`], but returns a [`Continuation`] which
+    /// can be resumed with more input if the input fails to parse at its end, where more of it
+    /// might make it parse.
+    ///
+    /// [`parse`]: #tymethod.parse
+    /// [`Continuation`]: struct.Continuation.html
+    #[allow(clippy::perf)]
+    fn parse_resumable(rule: R, input: &str) -> Result<Resumable<'_, R>, Error<R>> {
+        resumable::parse(rule, input, Self::parse)
+    }
+
+    /// Parses a `&str` starting from `rule`, like [`parse`], handing `context` to the semantic
+    /// predicates of the grammar, e.g. `#{is_type_name} identifier`. Predicates without a context
+    /// get `&()`. See [`ParserState::predicate`].
+    ///
+    /// [`parse`]: #tymethod.parse
+    /// [`ParserState::predicate`]: struct.ParserState.html#method.predicate
+    #[allow(clippy::perf)]
+    fn parse_with_context<'i>(
+        rule: R,
+        input: &'i str,
+        context: &'i dyn Any,
+    ) -> Result<Pairs<'i, R>, Error<R>> {
+        crate::parser_state::state(input, |state| {
+            Self::parse_state(rule, state.with_context(context))
+        })
+    }
+
+    /// Parses a `&str` starting from `rule`, like [`parse`], in the dialect `dialect` of the
+    /// grammar, which selects the definitions of its rules annotated with `@dialect(...)`. See
+    /// [`ParserState::in_dialect`].
+    ///
+    /// [`parse`]: #tymethod.parse
+    /// [`ParserState::in_dialect`]: struct.ParserState.html#method.in_dialect
+    #[allow(clippy::perf)]
+    fn parse_with_dialect<'i>(
+        rule: R,
+        input: &'i str,
+        dialect: &'i str,
+    ) -> Result<Pairs<'i, R>, Error<R>> {
+        crate::parser_state::state(input, |state| {
+            Self::parse_state(rule, state.with_dialect(dialect))
+        })
+    }
+
+    /// Parses a `&str` starting from `rule`, like [`parse`], with the `options`, e.g. to limit the
+    /// depth of the parse or to memoize the matches of rules. See [`ParseOptions`].
+    ///
+    /// [`parse`]: #tymethod.parse
+    /// [`ParseOptions`]: struct.ParseOptions.html
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use pest::{Parser, ParseOptions, ParseResult, ParserState, iterators::Pairs, error::Error};
+    /// # #[allow(non_camel_case_types)]
+    /// # #[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+    /// # enum Rule { list }
+    /// # struct ListParser;
+    /// # fn list(s: Box<ParserState<'_, Rule>>) -> ParseResult<Box<ParserState<'_, Rule>>> {
+    /// #     s.rule(Rule::list, |s| {
+    /// #         s.sequence(|s| {
+    /// #             s.match_string("[")
+    /// #                 .and_then(|s| s.optional(list))
+    /// #                 .and_then(|s| s.match_string("]"))
+    /// #         })
+    /// #     })
+    /// # }
+    /// # impl Parser<Rule> for ListParser {
+    /// #     fn parse(rule: Rule, input: &str) -> Result<Pairs<'_, Rule>, Error<Rule>> {
+    /// #         pest::state(input, |s| Self::parse_state(rule, s))
+    /// #     }
+    /// #     fn parse_state<'i>(_: Rule, s: Box<ParserState<'i, Rule>>) -> ParseResult<Box<ParserState<'i, Rule>>> {
+    /// #         list(s)
+    /// #     }
+    /// # }
+    /// // list = { "[" ~ list? ~ "]" }
+    /// let options = ParseOptions::new().with_max_depth(2).with_line_index();
+    ///
+    /// assert!(ListParser::parse_with(Rule::list, "[[]]", &options).is_ok());
+    /// let error = ListParser::parse_with(Rule::list, "[[[]]]", &options).unwrap_err();
+    /// assert_eq!(error.variant.message(), "depth limit reached");
+    /// ```
+    #[allow(clippy::perf)]
+    fn parse_with<'i>(
+        rule: R,
+        input: &'i str,
+        options: &ParseOptions<R>,
+    ) -> Result<Pairs<'i, R>, Error<R>> {
+        let pairs = crate::parser_state::state(input, |state| {
+            Self::parse_state(rule, state.with_options(options))
+        })?;
+
+        if options.line_index() {
+            Ok(pairs::with_line_index(
+                pairs,
+                Rc::new(LineIndex::new(input)),
+            ))
+        } else {
+            Ok(pairs)
+        }
+    }
+
+    /// Parses a `&str` starting from `rule`, like [`parse`], with the state and buffers kept in
+    /// `reuse` by earlier parses instead of newly allocated ones. See [`ParseReuse`].
+    ///
+    /// [`parse`]: #tymethod.parse
+    /// [`ParseReuse`]: struct.ParseReuse.html
+    #[allow(clippy::perf)]
+    fn parse_reusing<'i>(
+        rule: R,
+        input: &'i str,
+        reuse: &mut ParseReuse<R>,
+    ) -> Result<Pairs<'i, R>, Error<R>> {
+        crate::parser_state::state_reusing(input, reuse, |state| Self::parse_state(rule, state))
+    }
+
+    /// Parses a `&str` starting from `rule`, like [`parse`], also reporting the memory allocated
+    /// by the parse. See [`state_with_stats`].
+    ///
+    /// [`parse`]: #tymethod.parse
+    /// [`state_with_stats`]: fn.state_with_stats.html
+    #[allow(clippy::perf)]
+    fn parse_with_stats<'i>(
+        rule: R,
+        input: &'i str,
+    ) -> (Result<Pairs<'i, R>, Error<R>>, ParseStats) {
+        crate::parser_state::state_with_stats(input, |state| Self::parse_state(rule, state))
+    }
+
+    /// Parses a `&str` starting from `rule`, like [`parse`], giving the parse the user data `data`,
+    /// which is returned along with the result once the parse ends, with the changes the
+    /// parse-time callbacks made to it. See [`ParserState::with_user_data`].
+    ///
+    /// [`parse`]: #tymethod.parse
+    /// [`ParserState::with_user_data`]: struct.ParserState.html#method.with_user_data
+    #[allow(clippy::perf)]
+    fn parse_with_user_data<'i, T: Any>(
+        rule: R,
+        input: &'i str,
+        data: T,
+    ) -> (Result<Pairs<'i, R>, Error<R>>, T) {
+        let mut data = Some(data);
+        let result = crate::parser_state::state(input, |state| {
+            let state = state.with_user_data(data.take().unwrap());
+            let mut take = |mut state: Box<ParserState<'i, R>>| {
+                data = state.take_user_data();
+                state
+            };
+            Self::parse_state(rule, state).map(&mut take).map_err(take)
+        });
+
+        (
+            result,
+            data.expect("the user data of the parse was replaced"),
+        )
+    }
+
+    /// Initializes everything the parses of this parser initialize lazily, so that the first one
+    /// does not pay for it, e.g. in latency-sensitive services before they take requests. This is
+    /// to be called on every thread which parses, as part of the state is thread-local.
+    ///
+    /// Parsers derived from a grammar also look up the Unicode tables of the properties it uses,
+    /// e.g. `LETTER`. By default, this calls [`warm_up`].
+    ///
+    /// [`warm_up`]: fn.warm_up.html
+    fn warm_up() {
+        crate::parser_state::warm_up();
+    }
+
+    /// Matches `rule` at the current position of `state`, e.g. from a callback bound to an
+    /// `@extern` rule by [`bind_fn`], so that hand-written code can hand parts of the input back
+    /// to the grammar. The pairs of `rule` are appended to those of `state`, whose position moves
+    /// to their end.
+    ///
+    /// Parsers derived from a grammar match `rule` within `state`, which keeps its stack and
+    /// lookbehind context. By default, `rule` is parsed from the rest of the input like
+    /// [`ParserState::nested`].
+    ///
+    /// [`bind_fn`]: #method.bind_fn
+    /// [`ParserState::nested`]: struct.ParserState.html#method.nested
+    #[allow(clippy::perf)]
+    fn parse_state<'i>(
+        rule: R,
+        state: Box<ParserState<'i, R>>,
+    ) -> ParseResult<Box<ParserState<'i, R>>> {
+        state.nested(|input| Self::parse(rule, input), Some)
+    }
+
+    /// Binds the `@extern` rule `rule` to another `parser` on the current thread, so that `rule`
+    /// parses the input from `start_rule` of `parser`. The pairs of `parser` are grafted into the
+    /// pair of `rule`, with their rules converted by `map`, while the pairs which `map` returns
+    /// `None` for are left out, keeping their inner pairs.
+    ///
+    /// Binding `rule` again replaces the previous parser. A [`ParserHandle`] carries its own
+    /// bindings instead, which makes it usable from any thread.
+    ///
+    /// [`ParserHandle`]: struct.ParserHandle.html
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use pest::{Parser, iterators::Pairs, error::Error};
+    /// # #[allow(non_camel_case_types)]
+    /// # #[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+    /// # enum Rule { template, expr, number }
+    /// # struct TemplateParser;
+    /// # impl Parser<Rule> for TemplateParser {
+    /// #     fn parse(_: Rule, input: &str) -> Result<Pairs<'_, Rule>, Error<Rule>> {
+    /// #         pest::state(input, |s| {
+    /// #             s.rule(Rule::template, |s| {
+    /// #                 s.match_string("{{")
+    /// #                     .and_then(|s| s.rule(Rule::expr, |s| s.external(Rule::expr)))
+    /// #                     .and_then(|s| s.match_string("}}"))
+    /// #             })
+    /// #         })
+    /// #     }
+    /// # }
+    /// # #[allow(non_camel_case_types)]
+    /// # #[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+    /// # enum ExprRule { main, digits }
+    /// # struct ExprParser;
+    /// # impl Parser<ExprRule> for ExprParser {
+    /// #     fn parse(_: ExprRule, input: &str) -> Result<Pairs<'_, ExprRule>, Error<ExprRule>> {
+    /// #         pest::state(input, |s| {
+    /// #             s.rule(ExprRule::main, |s| s.rule(ExprRule::digits, |s| s.match_string("42")))
+    /// #         })
+    /// #     }
+    /// # }
+    /// // template = { "{{" ~ expr ~ "}}" } with @extern { expr }
+    /// TemplateParser::bind(Rule::expr, ExprParser, ExprRule::main, |rule| match rule {
+    ///     ExprRule::digits => Some(Rule::number),
+    ///     _ => None,
+    /// });
+    ///
+    /// let pairs = TemplateParser::parse(Rule::template, "{{42}}").unwrap();
+    /// assert_eq!(format!("{}", pairs), "[template(0, 6, [expr(2, 4, [number(2, 4)])])]");
+    /// ```
+    #[cfg(feature = "std")]
+    #[allow(clippy::perf)]
+    fn bind<P, S, F>(rule: R, parser: P, start_rule: S, map: F)
+    where
+        R: 'static,
+        P: Parser<S>,
+        S: RuleType + 'static,
+        F: Fn(S) -> Option<R> + 'static,
+    {
+        let _ = parser;
+        external::bind(
+            rule,
+            Rc::new(move |state| state.nested(|input| P::parse(start_rule, input), &map)),
+        );
+    }
+
+    /// Binds the `@extern` rule `rule` to the callback `f` on the current thread, which matches
+    /// the input at the current position of its state by hand, like the closures given to
+    /// [`state`], e.g. for context-sensitive parts of a language. `f` can hand parts of the input
+    /// back to the grammar with [`parse_state`].
+    ///
+    /// Binding `rule` again replaces the previous callback or parser.
+    ///
+    /// [`state`]: fn.state.html
+    /// [`parse_state`]: #method.parse_state
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use pest::{Parser, iterators::Pairs, error::Error};
+    /// # #[allow(non_camel_case_types)]
+    /// # #[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+    /// # enum Rule { statement, declaration, expression }
+    /// # struct CParser;
+    /// # impl Parser<Rule> for CParser {
+    /// #     fn parse(rule: Rule, input: &str) -> Result<Pairs<'_, Rule>, Error<Rule>> {
+    /// #         pest::state(input, |s| match rule {
+    /// #             Rule::statement => s.rule(Rule::statement, |s| s.external(Rule::statement)),
+    /// #             Rule::declaration => s.rule(Rule::declaration, |s| s.match_string("T * x")),
+    /// #             Rule::expression => s.rule(Rule::expression, |s| s.match_string("a * b")),
+    /// #         })
+    /// #     }
+    /// # }
+    /// // statement = { declaration | expression } with @extern { statement }, where `T * x`
+    /// // declares a pointer if `T` is a type and multiplies otherwise.
+    /// CParser::bind_fn(Rule::statement, |state| {
+    ///     // Looks `T` up in the typedef names, which the grammar cannot do.
+    ///     match state.lookahead(true, |s| s.match_string("T")) {
+    ///         Ok(state) => CParser::parse_state(Rule::declaration, state),
+    ///         Err(state) => CParser::parse_state(Rule::expression, state),
+    ///     }
+    /// });
+    ///
+    /// let pairs = CParser::parse(Rule::statement, "T * x").unwrap();
+    /// assert_eq!(format!("{}", pairs), "[statement(0, 5, [declaration(0, 5)])]");
+    /// ```
+    #[cfg(feature = "std")]
+    fn bind_fn<F>(rule: R, f: F)
+    where
+        R: 'static,
+        F: for<'i> Fn(Box<ParserState<'i, R>>) -> ParseResult<Box<ParserState<'i, R>>> + 'static,
+    {
+        external::bind(rule, Rc::new(f));
+    }
+}
+
+/// A [`Parser`] whose grammar only has byte terminals, which parses raw bytes. It is implemented
+/// by the parsers derived with `#[bytes = true]`, whose grammar is validated to only match bytes,
+/// each byte being matched as the `char` of the same value.
+///
+/// [`Parser`]: trait.Parser.html
+pub trait ByteParser<R: RuleType>: Parser<R> {
+    /// Parses the raw bytes of a [`ByteInput`] starting from `rule`, without validating them as
+    /// UTF-8. Positions of the result are in the parsed text and convert back into byte positions
+    /// through the `ByteInput`.
+    ///
+    /// [`ByteInput`]: input/struct.ByteInput.html
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use pest::{ByteInput, ByteParser, Parser, iterators::Pairs, error::Error};
+    /// # #[allow(non_camel_case_types)]
+    /// # #[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+    /// # enum Rule { payload }
+    /// # struct FrameParser;
+    /// # impl Parser<Rule> for FrameParser {
+    /// #     fn parse(_: Rule, input: &str) -> Result<Pairs<'_, Rule>, Error<Rule>> {
+    /// #         pest::state(input, |s| {
+    /// #             s.match_string("\x01")
+    /// #                 .and_then(|s| s.rule(Rule::payload, |s| s.repeat(|s| s.match_range('\u{80}'..'\u{ff}'))))
+    /// #         })
+    /// #     }
+    /// # }
+    /// # impl ByteParser<Rule> for FrameParser {}
+    /// // frame = { "\x01" ~ payload }
+    /// // payload = { ('\u{80}'..'\u{ff}')* }
+    /// let input = ByteInput::new(b"\x01\xca\xfe\x00");
+    /// let payload = FrameParser::parse_bytes(Rule::payload, &input).unwrap().next().unwrap();
+    ///
+    /// assert_eq!(input.span(&payload.as_span()).as_bytes(), b"\xca\xfe");
+    /// ```
+    #[allow(clippy::perf)]
+    fn parse_bytes<'i>(rule: R, input: &'i ByteInput<'_>) -> Result<Pairs<'i, R>, Error<R>> {
+        Self::parse(rule, input.as_str())
+    }
+}
+
+/// Hashes the content of a grammar with the 64-bit FNV-1a hash, like the `GRAMMAR_HASH` constant
+/// of derived parsers, e.g. to check that trees cached with a grammar which is loaded at runtime
+/// are still up to date. The hash is the same on every platform and version of pest.
+///
+/// # Examples
+///
+/// ```
+/// assert_eq!(pest::grammar_hash(""), 0xcbf2_9ce4_8422_2325);
+/// assert_ne!(pest::grammar_hash("a = { \"a\" }"), pest::grammar_hash("a = { \"b\" }"));
+/// ```
+pub const fn grammar_hash(grammar: &str) -> u64 {
+    let bytes = grammar.as_bytes();
+    let mut hash = 0xcbf2_9ce4_8422_2325;
+    let mut i = 0;
+
+    while i < bytes.len() {
+        hash ^= bytes[i] as u64;
+        hash = hash.wrapping_mul(0x0100_0000_01b3);
+        i += 1;
+    }
+
+    hash
+}
+
+fn error_pos<R>(error: &Error<R>) -> usize {
+    match error.location {
+        InputLocation::Pos(pos) => pos,
+        InputLocation::Span((pos, _)) => pos,
+    }
+}
+
+// Keeps the first of the furthest errors, expecting the rules of all of them, and labels it with
+// where the other rules failed.
+#[allow(clippy::perf)]
+fn combine_errors<R: RuleType>(input: &str, errors: Vec<(R, Error<R>)>) -> Error<R> {
+    let furthest = errors
+        .iter()
+        .map(|(_, error)| error_pos(error))
+        .max()
+        .expect("no start rules to parse");
+
+    let mut combined: Option<Error<R>> = None;
+    let mut behind = vec![];
+
+    for (rule, error) in errors {
+        let pos = error_pos(&error);
+        if pos < furthest {
+            behind.push((rule, pos));
+            continue;
+        }
+
+        match combined {
+            None => combined = Some(error),
+            Some(ref mut combined) => {
+                if let (
+                    ErrorVariant::ParsingError {
+                        positives,
+                        negatives,
+                    },
+                    ErrorVariant::ParsingError {
+                        positives: other_positives,
+                        negatives: other_negatives,
+                    },
+                ) = (&mut combined.variant, error.variant)
+                {
+                    positives.extend(other_positives);
+                    negatives.extend(other_negatives);
+                }
+            }
+        }
+    }
+
+    let mut combined = combined.unwrap();
+    if let ErrorVariant::ParsingError {
+        positives,
+        negatives,
+    } = &mut combined.variant
+    {
+        positives.sort();
+        positives.dedup();
+        negatives.sort();
+        negatives.dedup();
+    }
+
+    behind.into_iter().fold(combined, |combined, (rule, pos)| {
+        // Error positions always come from parses of `input`.
+        combined.with_label(
+            Span::new(input, pos, pos).unwrap(),
+            &format!("{:?} failed here", rule),
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::LimitKind;
+    use crate::macros::tests::{AbcParser, Rule};
+    use crate::parser_state::AttemptTracking;
+    use alloc::format;
+    use alloc::sync::Arc;
+    use core::num::NonZeroUsize;
+
+    #[test]
+    fn parse_items() {
+        let (items, errors) = AbcParser::parse_items(Rule::a, "abcdeabcdx;abcdeabcdx", &[";"], 10);
+
+        assert_eq!(items.len(), 2);
+        assert_eq!(
+            format!("{}", items[1]),
+            "[a(11, 14, [b(12, 13)]), c(15, 16)]"
+        );
+        assert_eq!(errors.len(), 2);
+        assert_eq!(errors[0].location, InputLocation::Pos(9));
+        assert_eq!(errors[1].location, InputLocation::Pos(20));
+    }
+
+    #[test]
+    fn parse_items_max_errors() {
+        let (items, errors) = AbcParser::parse_items(Rule::a, "abcdx;abcdx;abcde", &[";"], 1);
+
+        assert!(items.is_empty());
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].location, InputLocation::Pos(4));
+    }
+
+    struct AnyParser;
+
+    impl Parser<Rule> for AnyParser {
+        fn parse(rule: Rule, input: &str) -> Result<Pairs<'_, Rule>, Error<Rule>> {
+            crate::state(input, |s| match rule {
+                Rule::a => s
+                    .match_string("ab")
+                    .and_then(|s| s.rule(Rule::a, |s| s.match_string("c"))),
+                Rule::b => s
+                    .match_string("a")
+                    .and_then(|s| s.rule(Rule::b, |s| s.match_string("x"))),
+                Rule::c => s
+                    .match_string("ab")
+                    .and_then(|s| s.rule(Rule::c, |s| s.match_string("d"))),
+            })
+        }
+    }
+
+    #[test]
+    fn parse_any() {
+        let pairs = AnyParser::parse_any(&[Rule::a, Rule::b, Rule::c], "abd").unwrap();
+        assert_eq!(format!("{}", pairs), "[c(2, 3)]");
+
+        let error = AnyParser::parse_any(&[Rule::c, Rule::b, Rule::a], "abx").unwrap_err();
+        assert_eq!(error.location, InputLocation::Pos(2));
+        assert_eq!(
+            error.variant,
+            ErrorVariant::ParsingError {
+                positives: vec![Rule::a, Rule::c],
+                negatives: vec![],
+            }
+        );
+        assert_eq!(error.labels().len(), 1);
+        assert_eq!(error.labels()[0].location(), (1, 1));
+        assert_eq!(error.labels()[0].message(), "b failed here");
+    }
+
+    #[test]
+    fn parse_input() {
+        let input: Arc<str> = Arc::from("abcde");
+        let pairs = AbcParser::parse_input(Rule::a, &input).unwrap();
+
+        assert_eq!(format!("{}", pairs), "[a(0, 3, [b(1, 2)]), c(4, 5)]");
+        assert_eq!(pairs.as_str().as_ptr(), input.as_ptr());
+    }
+
+    // Counts the `a`s it matches in its user data.
+    struct CountingParser;
+
+    impl Parser<Rule> for CountingParser {
+        fn parse(rule: Rule, input: &str) -> Result<Pairs<'_, Rule>, Error<Rule>> {
+            crate::state(input, |state| Self::parse_state(rule, state))
+        }
+
+        fn parse_state<'i>(
+            rule: Rule,
+            state: Box<ParserState<'i, Rule>>,
+        ) -> ParseResult<Box<ParserState<'i, Rule>>> {
+            state.repeat(|state| {
+                state
+                    .rule(rule, |state| state.match_string("a"))
+                    .map(|mut state| {
+                        if let Some(count) = state.user_data_mut::<usize>() {
+                            *count += 1;
+                        }
+                        state
+                    })
+            })
+        }
+    }
+
+    #[test]
+    fn parse_with_user_data() {
+        let (result, count) = CountingParser::parse_with_user_data(Rule::a, "aaa", 1_usize);
+
+        assert_eq!(result.unwrap().count(), 3);
+        assert_eq!(count, 4);
+        assert!(CountingParser::parse(Rule::a, "aa").is_ok());
+    }
+
+    #[test]
+    fn parse_with_stats() {
+        let (result, stats) = CountingParser::parse_with_stats(Rule::a, "aaa");
+
+        assert_eq!(result.unwrap().count(), 3);
+        assert!(stats.queue_bytes > 0);
+    }
+
+    // a = { "(" ~ a? ~ ")" }
+    struct NestedParser;
+
+    impl Parser<Rule> for NestedParser {
+        fn parse(rule: Rule, input: &str) -> Result<Pairs<'_, Rule>, Error<Rule>> {
+            crate::state(input, |state| Self::parse_state(rule, state))
+        }
+
+        fn parse_state<'i>(
+            rule: Rule,
+            state: Box<ParserState<'i, Rule>>,
+        ) -> ParseResult<Box<ParserState<'i, Rule>>> {
+            state.rule(rule, |state| {
+                state.sequence(|state| {
+                    state
+                        .match_string("(")
+                        .and_then(|state| state.optional(|state| Self::parse_state(rule, state)))
+                        .and_then(|state| state.match_string(")"))
+                })
+            })
+        }
+    }
+
+    #[test]
+    fn parse_with() {
+        let limited = ParseOptions::new().with_call_limit(NonZeroUsize::new(6).unwrap());
+        let error = NestedParser::parse_with(Rule::a, "((()))", &limited).unwrap_err();
+        assert_eq!(error.variant.message(), "call limit reached");
+        assert_eq!(error.limit_exceeded(), Some((LimitKind::Calls, 6)));
+        assert!(NestedParser::parse_with(Rule::a, "(())", &limited).is_ok());
+
+        let shallow = ParseOptions::new().with_max_depth(1);
+        let error = NestedParser::parse_with(Rule::a, "(())", &shallow).unwrap_err();
+        assert_eq!(error.variant.message(), "depth limit reached");
+        assert_eq!(error.limit_exceeded(), Some((LimitKind::Depth, 1)));
+        assert!(NestedParser::parse(Rule::a, "(())").is_ok());
+
+        let untracked = ParseOptions::new().with_attempt_tracking(AttemptTracking::Off);
+        let error = NestedParser::parse_with(Rule::a, ")", &untracked).unwrap_err();
+        assert_eq!(
+            error.variant,
+            ErrorVariant::ParsingError {
+                positives: vec![],
+                negatives: vec![],
+            }
+        );
+        let error = NestedParser::parse(Rule::a, ")").unwrap_err();
+        assert_eq!(
+            error.variant,
+            ErrorVariant::ParsingError {
+                positives: vec![Rule::a],
+                negatives: vec![],
+            }
+        );
+        assert_eq!(error.limit_exceeded(), None);
+
+        let indexed = ParseOptions::new().with_line_index();
+        let pairs = NestedParser::parse_with(Rule::a, "()", &indexed).unwrap();
+        let locatable = pairs.locatable();
+        assert_eq!(locatable.line_index().line_count(), 1);
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn parse_with_timeout() {
+        use std::time::Duration;
+
+        let timed = ParseOptions::new().with_timeout(Duration::from_secs(60));
+        assert!(NestedParser::parse_with(Rule::a, "((()))", &timed).is_ok());
+        let unbounded = ParseOptions::new().with_timeout(Duration::MAX);
+        assert!(NestedParser::parse_with(Rule::a, "((()))", &unbounded).is_ok());
+
+        let input = format!("{}{}", "(".repeat(2_000), ")".repeat(2_000));
+        let expired = ParseOptions::new().with_timeout(Duration::from_secs(0));
+        let error = NestedParser::parse_with(Rule::a, &input, &expired).unwrap_err();
+        assert_eq!(error.variant.message(), "timeout of 0ns exceeded");
+        assert!(error.deadline_exceeded());
+        assert_eq!(error.limit_exceeded(), None);
+
+        let error = NestedParser::parse_with(Rule::a, "(()", &timed).unwrap_err();
+        assert!(!error.deadline_exceeded());
+    }
 }
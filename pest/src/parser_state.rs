@@ -9,18 +9,32 @@
 
 use alloc::borrow::ToOwned;
 use alloc::boxed::Box;
+use alloc::collections::BTreeMap;
+use alloc::format;
 use alloc::rc::Rc;
+use alloc::string::String;
 use alloc::vec;
 use alloc::vec::Vec;
+use core::any::Any;
+use core::fmt;
+use core::mem::{self, size_of};
 use core::num::NonZeroUsize;
 use core::ops::Range;
+use core::panic::AssertUnwindSafe;
 use core::sync::atomic::{AtomicUsize, Ordering};
+use core::time::Duration;
+#[cfg(feature = "std")]
+use std::time::Instant;
 
-use crate::error::{Error, ErrorVariant};
-use crate::iterators::{pairs, QueueableToken};
-use crate::position::{self, Position};
+use crate::error::{Error, ErrorBias, ErrorHead, ErrorVariant, InputLocation, LimitKind};
+use crate::explain::Recorder;
+#[cfg(feature = "std")]
+use crate::external;
+use crate::iterators::{compact, pairs, CompactPairs, Pairs, QueueableToken};
+use crate::position::Position;
 use crate::span::Span;
 use crate::stack::Stack;
+use crate::token::Token;
 use crate::RuleType;
 
 /// The current lookahead status of a [`ParserState`].
@@ -79,6 +93,385 @@ pub enum MatchDir {
     TopToBottom,
 }
 
+/// How much of the attempted rules a parse tracks, in order to report them in its error.
+///
+/// Tracking the attempts is needed for the positives and negatives of
+/// [`ErrorVariant::ParsingError`], but has a runtime cost. Without it, errors still point at the
+/// furthest attempted rule, with no positives or negatives.
+///
+/// [`ErrorVariant::ParsingError`]: error/enum.ErrorVariant.html#variant.ParsingError
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum AttemptTracking {
+    /// Tracks every attempted rule.
+    Full,
+    /// Tracks no attempted rule.
+    Off,
+    /// Only tracks the attempted rules nested in fewer than the given number of rules, e.g. `1`
+    /// only tracks the rules attempted first.
+    MaxDepth(usize),
+    /// Tracks every rule failing at the furthest position, including the ones nested in the
+    /// others, which [`Full`] only reports through the outer rule, along with the stack of rules
+    /// the parse was in when the first of them failed, as returned by [`Error::rule_stack`]. The
+    /// positives and negatives are sorted and deduplicated by their names.
+    ///
+    /// [`Full`]: #variant.Full
+    /// [`Error::rule_stack`]: error/struct.Error.html#method.rule_stack
+    Farthest,
+}
+
+impl Default for AttemptTracking {
+    fn default() -> AttemptTracking {
+        AttemptTracking::Full
+    }
+}
+
+#[cfg(feature = "std")]
+std::thread_local! {
+    static ATTEMPT_TRACKING: core::cell::Cell<AttemptTracking> =
+        core::cell::Cell::new(AttemptTracking::Full);
+}
+
+/// Runs `f` with the parses it starts on the current thread tracking attempts according to
+/// `tracking`, e.g. to opt out of tracking on a hot path, while other parses keep
+/// [`AttemptTracking::Full`].
+///
+/// [`AttemptTracking::Full`]: enum.AttemptTracking.html#variant.Full
+///
+/// # Examples
+///
+/// ```
+/// # use pest::{self, AttemptTracking};
+/// # use pest::error::{ErrorVariant, InputLocation};
+/// let parse = || {
+///     pest::state::<&str, _>("ab", |s| {
+///         s.rule("a", |s| s.match_string("a")).and_then(|s| s.rule("c", |s| s.match_string("c")))
+///     })
+///     .unwrap_err()
+/// };
+///
+/// let error = pest::with_attempt_tracking(AttemptTracking::Off, parse);
+/// assert_eq!(error.location, InputLocation::Pos(1));
+/// assert_eq!(
+///     error.variant,
+///     ErrorVariant::ParsingError { positives: vec![], negatives: vec![] }
+/// );
+///
+/// let error = parse();
+/// assert_eq!(
+///     error.variant,
+///     ErrorVariant::ParsingError { positives: vec!["c"], negatives: vec![] }
+/// );
+/// ```
+#[cfg(feature = "std")]
+pub fn with_attempt_tracking<T, F>(tracking: AttemptTracking, f: F) -> T
+where
+    F: FnOnce() -> T,
+{
+    struct Restore(AttemptTracking);
+
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            ATTEMPT_TRACKING.with(|current| current.set(self.0));
+        }
+    }
+
+    let _restore = Restore(ATTEMPT_TRACKING.with(|current| current.replace(tracking)));
+    f()
+}
+
+fn attempt_tracking() -> AttemptTracking {
+    #[cfg(feature = "std")]
+    {
+        ATTEMPT_TRACKING.with(|current| current.get())
+    }
+    #[cfg(not(feature = "std"))]
+    {
+        AttemptTracking::Full
+    }
+}
+
+#[cfg(feature = "std")]
+std::thread_local! {
+    static ERROR_WINDOW: core::cell::Cell<Option<(usize, usize)>> = core::cell::Cell::new(None);
+}
+
+/// Runs `f` with the parses it starts on the current thread only reporting errors within the
+/// byte range `window` of their input, e.g. for tools which parse a whole document but only check
+/// a selection of it, and must not report positions outside of it.
+///
+/// Attempts outside of `window` are not tracked, so errors point at the furthest attempt within
+/// it, and their labels are clamped to it like with [`Error::clamp_to`].
+///
+/// [`Error::clamp_to`]: error/struct.Error.html#method.clamp_to
+///
+/// # Panics
+///
+/// Panics when a parse fails if the bounds of `window` are not at `char` boundaries of its input.
+///
+/// # Examples
+///
+/// ```
+/// # use pest;
+/// # use pest::error::{ErrorVariant, InputLocation};
+/// let parse = || {
+///     pest::state::<&str, _>("a;b;c", |s| {
+///         s.repeat(|s| {
+///             s.rule("a", |s| s.match_string("a"))
+///                 .or_else(|s| s.rule("b", |s| s.match_string("b")))
+///                 .and_then(|s| s.match_string(";"))
+///         })
+///         .and_then(|s| s.rule("EOI", |s| s.end_of_input()))
+///     })
+///     .unwrap_err()
+/// };
+///
+/// assert_eq!(parse().location, InputLocation::Pos(4));
+///
+/// let error = pest::with_error_window(0..3, parse);
+/// assert_eq!(error.location, InputLocation::Pos(2));
+/// assert_eq!(
+///     error.variant,
+///     ErrorVariant::ParsingError { positives: vec!["a"], negatives: vec![] }
+/// );
+/// ```
+#[cfg(feature = "std")]
+pub fn with_error_window<T, F>(window: Range<usize>, f: F) -> T
+where
+    F: FnOnce() -> T,
+{
+    struct Restore(Option<(usize, usize)>);
+
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            ERROR_WINDOW.with(|current| current.set(self.0));
+        }
+    }
+
+    let window = (window.start, window.end.max(window.start));
+    let _restore = Restore(ERROR_WINDOW.with(|current| current.replace(Some(window))));
+    f()
+}
+
+fn error_window() -> Option<(usize, usize)> {
+    #[cfg(feature = "std")]
+    {
+        ERROR_WINDOW.with(|current| current.get())
+    }
+    #[cfg(not(feature = "std"))]
+    {
+        None
+    }
+}
+
+/// Initializes the lazily-initialized state of the parses on the current thread, i.e. their
+/// thread-local options and bindings, so that the first parse does not pay for it, e.g. in
+/// latency-sensitive services warming up their worker threads. This does nothing without the
+/// `std` feature.
+///
+/// [`Parser::warm_up`] also looks up the Unicode tables of the grammar of a generated parser,
+/// and should be preferred.
+///
+/// [`Parser::warm_up`]: trait.Parser.html#method.warm_up
+pub fn warm_up() {
+    #[cfg(feature = "std")]
+    {
+        ATTEMPT_TRACKING.with(|_| ());
+        ERROR_WINDOW.with(|_| ());
+        external::warm_up();
+    }
+}
+
+/// The configuration of a parse in recovery mode, started with [`state_recoverable`] or
+/// [`Parser::parse_recoverable`].
+///
+/// When one of the sync rules fails after matching part of the input, the parse records its error
+/// instead of backtracking and resumes right after the next occurrence of one of the boundaries,
+/// starting from the error's position, or at the end of the input if no boundary follows. The
+/// skipped input is covered by a pair of the error rule, which takes the place of the failed one.
+///
+/// [`state_recoverable`]: fn.state_recoverable.html
+/// [`Parser::parse_recoverable`]: trait.Parser.html#method.parse_recoverable
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Recovery<R> {
+    error_rule: R,
+    sync_rules: Vec<R>,
+    boundaries: Vec<String>,
+    max_errors: usize,
+}
+
+impl<R: RuleType> Recovery<R> {
+    /// Creates a configuration which covers skipped input with pairs of `error_rule`, with no sync
+    /// rules or boundaries and no limit on the number of errors.
+    pub fn new(error_rule: R) -> Recovery<R> {
+        Recovery {
+            error_rule,
+            sync_rules: Vec::new(),
+            boundaries: Vec::new(),
+            max_errors: usize::MAX,
+        }
+    }
+
+    /// Adds a sync rule, whose failures are recovered from.
+    pub fn with_sync_rule(mut self, rule: R) -> Recovery<R> {
+        self.sync_rules.push(rule);
+        self
+    }
+
+    /// Adds a boundary, right after which the parse resumes. Empty boundaries are ignored.
+    pub fn with_boundary(mut self, boundary: &str) -> Recovery<R> {
+        if !boundary.is_empty() {
+            self.boundaries.push(boundary.to_owned());
+        }
+        self
+    }
+
+    /// Stops recovering after `max_errors` errors, so that the next failure of a sync rule
+    /// backtracks as usual.
+    pub fn with_max_errors(mut self, max_errors: usize) -> Recovery<R> {
+        self.max_errors = max_errors;
+        self
+    }
+}
+
+// An error recovered from during a parse, along with the index of the `Start` token of its pair
+// and the span of the skipped input.
+#[derive(Debug)]
+struct Recovered<R> {
+    index: usize,
+    start: usize,
+    end: usize,
+    head: ErrorHead<R>,
+}
+
+/// The configuration of a parse in tolerant mode, started with [`state_tolerant`] or
+/// [`Parser::parse_tolerant`]. This mode is experimental.
+///
+/// When the parse fails, it is run again, this time letting the literals matched within the
+/// tolerant rules, or the rules they call, deviate from the input by one character: an extra one
+/// within the literal or, failing that, a missing one. Each deviation is recorded as a [`Repair`]
+/// instead of failing the parse, e.g. to ingest messy human-written data where typos would
+/// otherwise lose data. Literals of fewer than 3 characters, and those matched in lookaheads, are
+/// never repaired.
+///
+/// Since a repaired literal matches where it would not strictly, it may take the place of a later
+/// alternative of a choice which would have matched.
+///
+/// [`state_tolerant`]: fn.state_tolerant.html
+/// [`Parser::parse_tolerant`]: trait.Parser.html#method.parse_tolerant
+/// [`Repair`]: struct.Repair.html
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Tolerance<R> {
+    rules: Vec<R>,
+    max_repairs: usize,
+}
+
+impl<R: RuleType> Tolerance<R> {
+    /// Creates a configuration with no tolerant rules and no limit on the number of repairs.
+    pub fn new() -> Tolerance<R> {
+        Tolerance {
+            rules: Vec::new(),
+            max_repairs: usize::MAX,
+        }
+    }
+
+    /// Adds a tolerant rule, within which literals may deviate from the input.
+    pub fn with_rule(mut self, rule: R) -> Tolerance<R> {
+        self.rules.push(rule);
+        self
+    }
+
+    /// Stops repairing literals after `max_repairs` repairs, so that the next deviation fails as
+    /// usual.
+    pub fn with_max_repairs(mut self, max_repairs: usize) -> Tolerance<R> {
+        self.max_repairs = max_repairs;
+        self
+    }
+}
+
+impl<R: RuleType> Default for Tolerance<R> {
+    fn default() -> Tolerance<R> {
+        Tolerance::new()
+    }
+}
+
+/// How the input deviates from a literal repaired in tolerant mode.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum RepairKind {
+    /// The input has an extra character within the literal
+    Extra(char),
+    /// The input misses a character of the literal
+    Missing(char),
+}
+
+/// A literal matched despite a deviation from the input in tolerant mode. See [`Tolerance`].
+///
+/// [`Tolerance`]: struct.Tolerance.html
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct Repair {
+    /// The repaired literal
+    pub literal: String,
+    /// The byte position where the input matched by the literal starts
+    pub start: usize,
+    /// The byte position where the input matched by the literal ends
+    pub end: usize,
+    /// The byte position of the extra character, or where the missing one was expected
+    pub pos: usize,
+    /// How the input deviates from the literal
+    pub kind: RepairKind,
+}
+
+impl fmt::Display for Repair {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.kind {
+            RepairKind::Extra(c) => write!(f, "extra {:?} in {:?}", c, self.literal),
+            RepairKind::Missing(c) => write!(f, "missing {:?} in {:?}", c, self.literal),
+        }
+    }
+}
+
+// The number of characters a literal needs to be repaired in tolerant mode.
+const MIN_REPAIR_CHARS: usize = 3;
+
+// Matches `literal` at the start of `input` with one extra character within it or, failing that,
+// with one missing character, returning the length of the matched input along with the offset and
+// the kind of the deviation.
+fn fuzzy_match(
+    input: &str,
+    literal: &str,
+    insensitive: bool,
+) -> Option<(usize, usize, RepairKind)> {
+    let starts_with = |input: &str, literal: &str| match input.get(..literal.len()) {
+        Some(prefix) if insensitive => prefix.eq_ignore_ascii_case(literal),
+        Some(prefix) => prefix == literal,
+        None => false,
+    };
+
+    for (i, _) in literal.char_indices().skip(1) {
+        if !starts_with(input, &literal[..i]) {
+            break;
+        }
+        // `input` starts with `literal[..i]`, which ends at a `char` boundary.
+        if let Some(extra) = input[i..].chars().next() {
+            if starts_with(&input[i + extra.len_utf8()..], &literal[i..]) {
+                let len = literal.len() + extra.len_utf8();
+                return Some((len, i, RepairKind::Extra(extra)));
+            }
+        }
+    }
+
+    for (i, missing) in literal.char_indices() {
+        if !starts_with(input, &literal[..i]) {
+            break;
+        }
+        if starts_with(&input[i..], &literal[i + missing.len_utf8()..]) {
+            let len = literal.len() - missing.len_utf8();
+            return Some((len, i, RepairKind::Missing(missing)));
+        }
+    }
+
+    None
+}
+
 static CALL_LIMIT: AtomicUsize = AtomicUsize::new(0);
 
 /// Sets the maximum call limit for the parser state
@@ -91,30 +484,125 @@ static CALL_LIMIT: AtomicUsize = AtomicUsize::new(0);
 /// # Arguments
 ///
 /// * `limit` - The maximum number of calls. If None,
-///             the number of calls is unlimited.
+///   the number of calls is unlimited.
+///
+/// The limit applies to the parses of every thread. [`ParseOptions::with_call_limit`] limits
+/// a single parse instead.
+///
+/// [`ParseOptions::with_call_limit`]: struct.ParseOptions.html#method.with_call_limit
 pub fn set_call_limit(limit: Option<NonZeroUsize>) {
     CALL_LIMIT.store(limit.map(|f| f.get()).unwrap_or(0), Ordering::Relaxed);
 }
 
+// The number of calls after which the deadline of a parse is checked again.
+#[cfg(feature = "std")]
+const DEADLINE_CHECK_INTERVAL: usize = 1024;
+
+// Rules and sequences only compare the calls made so far to a checkpoint, the call limit or the
+// next check of the deadline, whichever comes first, or none if there is neither. The limits are
+// only checked once it is reached, or once a rule was nested too deep, which moves it back.
 #[derive(Debug)]
 struct CallLimitTracker {
     current_call_limit: Option<(usize, usize)>,
+    call_limit: Option<usize>,
+    // The maximum number of nested rules, and whether a rule was nested deeper.
+    max_depth: Option<usize>,
+    depth_limit_reached: bool,
+    // The instant after which the parse fails along with its timeout, and the timeout once it
+    // passed.
+    #[cfg(feature = "std")]
+    deadline: Option<(Instant, Duration)>,
+    timed_out: Option<Duration>,
 }
 
 impl Default for CallLimitTracker {
     fn default() -> Self {
         let limit = CALL_LIMIT.load(Ordering::Relaxed);
-        let current_call_limit = if limit > 0 { Some((0, limit)) } else { None };
-        Self { current_call_limit }
+        let call_limit = if limit > 0 { Some(limit) } else { None };
+        Self {
+            current_call_limit: call_limit.map(|limit| (0, limit)),
+            call_limit,
+            max_depth: None,
+            depth_limit_reached: false,
+            #[cfg(feature = "std")]
+            deadline: None,
+            timed_out: None,
+        }
     }
 }
 
 impl CallLimitTracker {
     fn limit_reached(&self) -> bool {
-        self.current_call_limit
-            .map_or(false, |(current, limit)| current >= limit)
+        self.timed_out.is_some()
+            || self.depth_limit_reached
+            || match (self.current_call_limit, self.call_limit) {
+                (Some((current, _)), Some(limit)) => current >= limit,
+                _ => false,
+            }
+    }
+
+    // The call or depth limit which stopped the parse, along with its value.
+    fn exceeded(&self) -> Option<(LimitKind, usize)> {
+        match (self.max_depth, self.current_call_limit, self.call_limit) {
+            (Some(max_depth), _, _) if self.depth_limit_reached => {
+                Some((LimitKind::Depth, max_depth))
+            }
+            (_, Some((current, _)), Some(limit)) if current >= limit => {
+                Some((LimitKind::Calls, limit))
+            }
+            _ => None,
+        }
     }
 
+    // Whether the parse has to stop, which is only checked once the calls reach the checkpoint.
+    #[inline]
+    fn checkpoint_reached(&mut self) -> bool {
+        match self.current_call_limit {
+            Some((current, checkpoint)) if current >= checkpoint => self.check(),
+            _ => false,
+        }
+    }
+
+    #[cold]
+    #[inline(never)]
+    fn check(&mut self) -> bool {
+        if self.limit_reached() {
+            return true;
+        }
+        #[cfg(feature = "std")]
+        if let Some((deadline, timeout)) = self.deadline {
+            if Instant::now() >= deadline {
+                self.timed_out = Some(timeout);
+                return true;
+            }
+        }
+        self.set_checkpoint();
+        false
+    }
+
+    // Moves the checkpoint to the call limit or to the next check of the deadline.
+    fn set_checkpoint(&mut self) {
+        let current = self.current_call_limit.map_or(0, |(current, _)| current);
+        #[allow(unused_mut)]
+        let mut checkpoint = self.call_limit;
+        #[cfg(feature = "std")]
+        if self.deadline.is_some() {
+            let check = current.saturating_add(DEADLINE_CHECK_INTERVAL);
+            checkpoint = Some(checkpoint.map_or(check, |limit| limit.min(check)));
+        }
+        self.current_call_limit = checkpoint.map(|checkpoint| (current, checkpoint));
+    }
+
+    // Records entering a rule at `depth`, after which the parse fails if it is nested too deep.
+    fn enter(&mut self, depth: usize) {
+        if self.max_depth.map_or(false, |max_depth| depth > max_depth) {
+            self.depth_limit_reached = true;
+            let current = self.current_call_limit.map_or(0, |(current, _)| current);
+            self.current_call_limit = Some((current, 0));
+        }
+    }
+
+    #[inline]
     fn increment_depth(&mut self) {
         if let Some((current, _)) = &mut self.current_call_limit {
             *current += 1;
@@ -122,6 +610,349 @@ impl CallLimitTracker {
     }
 }
 
+// A match of a rule which can be replayed, as its end, its tokens, numbered from the first one,
+// and its repairs.
+#[derive(Debug)]
+struct RuleMatch<'i, R> {
+    end: Position<'i>,
+    tokens: Vec<QueueableToken<R>>,
+    repairs: Vec<Repair>,
+}
+
+impl<'i, R> RuleMatch<'i, R> {
+    // The approximate memory used by the match.
+    fn size(&self) -> usize {
+        size_of::<Self>()
+            + self.tokens.len() * size_of::<QueueableToken<R>>()
+            + self
+                .repairs
+                .iter()
+                .map(|repair| size_of::<Repair>() + repair.literal.len())
+                .sum::<usize>()
+    }
+}
+
+// The longest match so far of the left-recursive rule `name` grown at `pos`, or `None` before the
+// first one.
+#[derive(Debug)]
+struct Seed<'i, R> {
+    name: &'static str,
+    pos: usize,
+    result: Option<RuleMatch<'i, R>>,
+}
+
+/// The options of a parse with [`Parser::parse_with`] or [`ParserState::with_options`]: its limits,
+/// how its attempts are tracked for errors and where its error is reported, whether it precomputes
+/// a [`LineIndex`] and what it memoizes. Unlike [`set_call_limit`] and [`with_attempt_tracking`],
+/// they only apply to that parse, e.g. to the parse of a request on a server handling others at the
+/// same time. The options left unset keep their defaults.
+///
+/// By default, nothing is memoized. With memoization, the match of every rule, or of the
+/// memoized rules only, is stored the first time the rule is matched at a position, so that
+/// matching it there again replays it instead, which makes grammars backtracking heavily parse in
+/// linear time. The match of a rule must then only depend on its position, so rules using the
+/// stack, e.g. with `PEEK` or `POP`, should be left out. Rules are not memoized while left-recursive
+/// rules are being matched, nor in recovery mode, and failures that are replayed are reported as
+/// failures of the memoized rule rather than of its inner rules.
+///
+/// [`Parser::parse_with`]: trait.Parser.html#method.parse_with
+/// [`ParserState::with_options`]: struct.ParserState.html#method.with_options
+/// [`LineIndex`]: struct.LineIndex.html
+/// [`set_call_limit`]: fn.set_call_limit.html
+/// [`with_attempt_tracking`]: fn.with_attempt_tracking.html
+///
+/// # Examples
+///
+/// ```
+/// # use pest::{AttemptTracking, ParseOptions};
+/// # use std::num::NonZeroUsize;
+/// # #[allow(non_camel_case_types)]
+/// # #[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+/// # enum Rule { expr, term }
+/// let options = ParseOptions::new()
+///     .with_call_limit(NonZeroUsize::new(100_000).unwrap())
+///     .with_max_depth(200)
+///     .with_attempt_tracking(AttemptTracking::Off)
+///     .with_memoized_rule(Rule::term)
+///     .with_memo_limit(1 << 20);
+///
+/// assert!(options.memoizes(Rule::term));
+/// assert!(!options.memoizes(Rule::expr));
+/// ```
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ParseOptions<R> {
+    call_limit: Option<NonZeroUsize>,
+    max_depth: Option<usize>,
+    timeout: Option<Duration>,
+    tracking: Option<AttemptTracking>,
+    error_bias: Option<ErrorBias>,
+    explain: bool,
+    line_index: bool,
+    memoize: bool,
+    memoized_rules: Vec<R>,
+    memo_limit: Option<usize>,
+}
+
+impl<R: RuleType> ParseOptions<R> {
+    /// Creates the default options, which set no limits and memoize nothing.
+    pub fn new() -> ParseOptions<R> {
+        ParseOptions {
+            call_limit: None,
+            max_depth: None,
+            timeout: None,
+            tracking: None,
+            error_bias: None,
+            explain: false,
+            line_index: false,
+            memoize: false,
+            memoized_rules: Vec::new(),
+            memo_limit: None,
+        }
+    }
+
+    /// Fails the parse with a "call limit reached" error once it made `limit` calls, counted like
+    /// those limited by [`set_call_limit`] for every parse. See [`Error::limit_exceeded`].
+    ///
+    /// [`set_call_limit`]: fn.set_call_limit.html
+    /// [`Error::limit_exceeded`]: error/struct.Error.html#method.limit_exceeded
+    pub fn with_call_limit(mut self, limit: NonZeroUsize) -> ParseOptions<R> {
+        self.call_limit = Some(limit);
+        self
+    }
+
+    /// Fails the parse with a "depth limit reached" error once a rule is matched within `depth`
+    /// others, e.g. to reject deeply nested inputs before they overflow the stack. See
+    /// [`Error::limit_exceeded`].
+    ///
+    /// [`Error::limit_exceeded`]: error/struct.Error.html#method.limit_exceeded
+    pub fn with_max_depth(mut self, depth: usize) -> ParseOptions<R> {
+        self.max_depth = Some(depth);
+        self
+    }
+
+    /// Fails the parse with a "timeout of {timeout:?} exceeded" error once it ran for longer than
+    /// `timeout`, measured from when the options are applied to it, e.g. to bound the time spent
+    /// on untrusted input even when it stays within the call limit. The clock is only read every
+    /// so many calls, so the parse may run slightly past its deadline, and further while a single
+    /// call scans the input, e.g. with `skip_until`. See [`Error::deadline_exceeded`].
+    ///
+    /// [`Error::deadline_exceeded`]: error/struct.Error.html#method.deadline_exceeded
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use pest::ParseOptions;
+    /// # use std::time::Duration;
+    /// let input = "a".repeat(100_000);
+    /// let options = ParseOptions::new().with_timeout(Duration::from_secs(0));
+    /// let error = pest::state::<&str, _>(&input, |s| {
+    ///     s.with_options(&options)
+    ///         .repeat(|s| s.rule("a", |s| s.match_string("a")))
+    ///         .and_then(|s| s.end_of_input())
+    /// })
+    /// .unwrap_err();
+    ///
+    /// assert_eq!(error.variant.message(), "timeout of 0ns exceeded");
+    /// assert!(error.deadline_exceeded());
+    /// ```
+    #[cfg(feature = "std")]
+    pub fn with_timeout(mut self, timeout: Duration) -> ParseOptions<R> {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Tracks the attempted rules of the parse according to `tracking`, like
+    /// [`with_attempt_tracking`] does for the parses of a thread.
+    ///
+    /// [`with_attempt_tracking`]: fn.with_attempt_tracking.html
+    pub fn with_attempt_tracking(mut self, tracking: AttemptTracking) -> ParseOptions<R> {
+        self.tracking = Some(tracking);
+        self
+    }
+
+    /// Reports the error of the parse at the position chosen by `bias` among the positions where it
+    /// could be said to have failed, e.g. at the end of the longest match for grammars whose
+    /// attempts reach past the actual mistake. The other positions are still returned by
+    /// [`Error::failure_candidates`]. The positives and negatives of the error are only kept if it
+    /// is at the furthest attempt.
+    ///
+    /// [`Error::failure_candidates`]: error/struct.Error.html#method.failure_candidates
+    pub fn with_error_bias(mut self, bias: ErrorBias) -> ParseOptions<R> {
+        self.error_bias = Some(bias);
+        self
+    }
+
+    /// Parses in explain mode, recording every rule attempt so that the error of the parse carries
+    /// an [`Explanation`], available through [`Error::explanation`]. See the [`explain`] module.
+    ///
+    /// [`Explanation`]: explain/struct.Explanation.html
+    /// [`Error::explanation`]: error/struct.Error.html#method.explanation
+    /// [`explain`]: explain/index.html
+    pub fn with_explain(mut self) -> ParseOptions<R> {
+        self.explain = true;
+        self
+    }
+
+    /// Precomputes a [`LineIndex`] of the input along with the parse, which the `Pairs` it returns
+    /// share once made [`locatable`]. Only [`Parser::parse_with`] does.
+    ///
+    /// [`LineIndex`]: struct.LineIndex.html
+    /// [`locatable`]: iterators/struct.Pairs.html#method.locatable
+    /// [`Parser::parse_with`]: trait.Parser.html#method.parse_with
+    pub fn with_line_index(mut self) -> ParseOptions<R> {
+        self.line_index = true;
+        self
+    }
+
+    /// Returns whether a [`LineIndex`] of the input is precomputed.
+    ///
+    /// [`LineIndex`]: struct.LineIndex.html
+    pub fn line_index(&self) -> bool {
+        self.line_index
+    }
+
+    /// Memoizes the matches of every rule.
+    pub fn memoize(mut self) -> ParseOptions<R> {
+        self.memoize = true;
+        self.memoized_rules.clear();
+        self
+    }
+
+    /// Memoizes the matches of `rule`, along with those of the other rules added this way, but not
+    /// those of the rest of the rules.
+    pub fn with_memoized_rule(mut self, rule: R) -> ParseOptions<R> {
+        self.memoize = true;
+        self.memoized_rules.push(rule);
+        self
+    }
+
+    /// Caps the memory used by memoization to about `bytes` bytes: the stored matches are dropped
+    /// whenever storing another one would use more.
+    pub fn with_memo_limit(mut self, bytes: usize) -> ParseOptions<R> {
+        self.memo_limit = Some(bytes);
+        self
+    }
+
+    /// Returns whether the matches of `rule` are memoized.
+    pub fn memoizes(&self, rule: R) -> bool {
+        self.memoize && (self.memoized_rules.is_empty() || self.memoized_rules.contains(&rule))
+    }
+}
+
+impl<R: RuleType> Default for ParseOptions<R> {
+    fn default() -> ParseOptions<R> {
+        ParseOptions::new()
+    }
+}
+
+// The matches of the rules memoized by a parse, by rule, position, whether they were matched in a
+// lookahead, where no tokens are produced, and atomicity, along with the memory they use.
+// Failures are stored as `None`.
+#[derive(Debug)]
+struct Memo<'i, R> {
+    options: ParseOptions<R>,
+    matches: BTreeMap<(R, usize, bool, u8), Option<RuleMatch<'i, R>>>,
+    // The numbers of repairs made before the rules being matched for the first time.
+    pending: Vec<usize>,
+    size: usize,
+}
+
+impl<'i, R: RuleType> Memo<'i, R> {
+    fn insert(&mut self, key: (R, usize, bool, u8), result: Option<RuleMatch<'i, R>>) {
+        let size = size_of::<((R, usize, bool, u8), Option<RuleMatch<'i, R>>)>()
+            + result.as_ref().map_or(0, RuleMatch::size);
+
+        if let Some(limit) = self.options.memo_limit {
+            if size > limit {
+                return;
+            }
+            if self.size + size > limit {
+                self.matches.clear();
+                self.size = 0;
+            }
+        }
+
+        self.size += size;
+        self.matches.insert(key, result);
+    }
+}
+
+// Numbers `token` and the index of the token it pairs with from `to` instead of `from`.
+fn rebase<R>(token: QueueableToken<R>, from: usize, to: usize) -> QueueableToken<R> {
+    match token {
+        QueueableToken::Start {
+            end_token_index,
+            input_pos,
+        } => QueueableToken::Start {
+            end_token_index: end_token_index - from + to,
+            input_pos,
+        },
+        QueueableToken::End {
+            start_token_index,
+            rule,
+            input_pos,
+        } => QueueableToken::End {
+            start_token_index: start_token_index - from + to,
+            rule,
+            input_pos,
+        },
+    }
+}
+
+// Appends the tokens and repairs of `result` to `queue` and `repairs`, which are only kept in
+// tolerant mode, moving `position` to its end.
+fn replay<'i, R: Clone>(
+    result: &RuleMatch<'i, R>,
+    queue: &mut Vec<QueueableToken<R>>,
+    repairs: Option<&mut Vec<Repair>>,
+    position: &mut Position<'i>,
+) {
+    let index = queue.len();
+    queue.extend(
+        result
+            .tokens
+            .iter()
+            .cloned()
+            .map(|token| rebase(token, 0, index)),
+    );
+    if let Some(repairs) = repairs {
+        repairs.extend(result.repairs.iter().cloned());
+    }
+    *position = result.end;
+}
+
+// A token of a parse in compact mode, as its position and the rule of the pair it ends, or `None`
+// if it starts one, which takes a third of the memory of a `QueueableToken`. Pairs are only matched
+// up once the parse is over.
+type CompactToken<R> = (u32, Option<R>);
+
+// The state of the modes a parse opted into. It is boxed behind a single `Option`, so that rules,
+// terminals and sequences only check that it is unset in parses in none of them.
+#[derive(Debug)]
+struct Modes<'i, R> {
+    explain: Option<Recorder<R>>,
+    memo: Option<Memo<'i, R>>,
+    tolerance: Option<Tolerance<R>>,
+    // The number of tolerant rules the current position is nested in.
+    tolerant: usize,
+    repairs: Vec<Repair>,
+    // Whether the tokens of the parse are kept in the compact queue.
+    compact: bool,
+}
+
+impl<'i, R> Default for Modes<'i, R> {
+    fn default() -> Self {
+        Modes {
+            explain: None,
+            memo: None,
+            tolerance: None,
+            tolerant: 0,
+            repairs: vec![],
+            compact: false,
+        }
+    }
+}
+
 /// The complete state of a [`Parser`].
 ///
 /// [`Parser`]: trait.Parser.html
@@ -129,13 +960,193 @@ impl CallLimitTracker {
 pub struct ParserState<'i, R: RuleType> {
     position: Position<'i>,
     queue: Vec<QueueableToken<R>>,
+    // The tokens of the parse in compact mode, which come after the ones in `queue`, if any.
+    compact: Vec<CompactToken<R>>,
     lookahead: Lookahead,
     pos_attempts: Vec<R>,
     neg_attempts: Vec<R>,
     attempt_pos: usize,
+    // The literals which failed to match at `literal_pos`, the furthest position where one did, as
+    // the range of their text in `literal_text` and whether they are case-insensitive. They are only
+    // turned into strings for the error, and only reported if the furthest rule attempt was made at
+    // the same position.
+    literal_attempts: Vec<(usize, usize, bool)>,
+    literal_text: String,
+    literal_pos: usize,
+    // The furthest position where a rule failed or a literal was attempted, tracked or not, and the
+    // furthest end of a rule match outside of lookaheads, along with which of them and
+    // `attempt_pos` errors report.
+    farthest_pos: usize,
+    matched_pos: usize,
+    error_bias: ErrorBias,
     atomicity: Atomicity,
     stack: Stack<Span<'i>>,
+    // The furthest position at which a `POP` failed to match, along with the span that was popped.
+    failed_pop: Option<(usize, Span<'i>)>,
+    // The innermost labeled group containing the furthest attempt, as its start, the position of
+    // the attempt and its label.
+    failed_label: Option<(usize, usize, String)>,
     call_tracker: CallLimitTracker,
+    modes: Option<Box<Modes<'i, R>>>,
+    tracking: AttemptTracking,
+    // The byte range outside of which attempts are not tracked and errors are not reported.
+    window: Option<(usize, usize)>,
+    // The number of rules the current position is nested in.
+    depth: usize,
+    // The furthest position reached by a failed sequence in the current rule, which tells whether
+    // the rule failed after matching part of the input.
+    reached_pos: usize,
+    recovery: Option<Box<Recovery<R>>>,
+    recovered: Vec<Recovered<R>>,
+    // Whether a match failed for lack of input, so that more of it might make the parse succeed.
+    starved: bool,
+    // With `AttemptTracking::Farthest`, the rules entered at each depth up to the current one, and
+    // the stack of the first rule which failed at `attempt_pos`.
+    rule_stack: Vec<R>,
+    farthest_stack: Vec<R>,
+    // The value handed to semantic predicates, `&()` by default. It is only ever read, so it does
+    // not keep the `ParserState` from being unwind safe.
+    context: AssertUnwindSafe<&'i dyn Any>,
+    // The value owned by the parse which its callbacks can change, e.g. a symbol table. Changes
+    // made before a panic are only seen through `catch_unwind` if the state is kept.
+    user_data: AssertUnwindSafe<Option<Box<dyn Any>>>,
+    // The dialect of the grammar being parsed, if any.
+    dialect: Option<&'i str>,
+    // The left-recursive rules being matched, innermost last.
+    seeds: Vec<Seed<'i, R>>,
+}
+
+/// The memory allocated by a single parse, as reported by [`state_with_stats`].
+///
+/// Since the buffers of a [`ParserState`] only ever grow, these are also the peak amounts of
+/// memory used by the parse, apart from the resulting `Pairs` or `Error`.
+///
+/// [`state_with_stats`]: fn.state_with_stats.html
+/// [`ParserState`]: struct.ParserState.html
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub struct ParseStats {
+    /// Bytes allocated for the token queue, which is then owned by the resulting `Pairs`
+    pub queue_bytes: usize,
+    /// Bytes allocated for tracking the attempted rules, used for error reporting
+    pub attempts_bytes: usize,
+    /// Bytes allocated for the `PUSH`/`POP` stack
+    pub stack_bytes: usize,
+    /// Bytes allocated for the `ParserState` itself
+    pub state_bytes: usize,
+}
+
+impl ParseStats {
+    /// Returns the total number of bytes allocated by the parse.
+    pub fn total_bytes(&self) -> usize {
+        self.queue_bytes + self.attempts_bytes + self.stack_bytes + self.state_bytes
+    }
+}
+
+/// The state of the parses made with [`state_reusing`] or [`Parser::parse_reusing`], kept from one
+/// parse to the next so that parsing many small inputs does not allocate it every time.
+///
+/// The boxed `ParserState` is kept after every parse along with its buffers, and the token queue
+/// once the `Pairs` it was moved to are handed back with [`recycle`], together with the `Rc` they
+/// shared it through. A successful parse which fits in the buffers of earlier ones then allocates
+/// nothing, unless it is made in one of the modes of [`ParseOptions`], while a failed parse builds
+/// its `Error`.
+///
+/// [`state_reusing`]: fn.state_reusing.html
+/// [`Parser::parse_reusing`]: trait.Parser.html#method.parse_reusing
+/// [`recycle`]: #method.recycle
+/// [`ParseOptions`]: struct.ParseOptions.html
+///
+/// # Examples
+///
+/// ```
+/// # use pest::{self, ParseReuse};
+/// let mut reuse = ParseReuse::new();
+///
+/// for input in &["aa", "aaa", "b"] {
+///     let result = pest::state_reusing::<&str, _>(input, &mut reuse, |s| {
+///         s.repeat(|s| s.rule("a", |s| s.match_string("a")))
+///     });
+///
+///     let pairs = result.unwrap();
+///     assert_eq!(pairs.clone().count(), input.matches('a').count());
+///     assert!(reuse.recycle(pairs));
+/// }
+/// ```
+#[derive(Debug)]
+pub struct ParseReuse<R: RuleType> {
+    // The state of the last parse, restarted on the empty input so that it no longer borrows the
+    // one it parsed.
+    state: Option<Box<ParserState<'static, R>>>,
+    // The queue of the recycled `Pairs`, only kept while nothing else shares it. It holds the queue
+    // lent to a parse until the parse hands its own back.
+    queue: Option<Rc<Vec<QueueableToken<R>>>>,
+}
+
+impl<R: RuleType> ParseReuse<R> {
+    /// Creates an empty `ParseReuse`, whose state is allocated by the first parse using it.
+    pub fn new() -> ParseReuse<R> {
+        ParseReuse {
+            state: None,
+            queue: None,
+        }
+    }
+
+    /// Takes back the token queue of `pairs` returned by a parse, for the next parse to reuse.
+    /// Returns `false`, dropping `pairs`, if other `Pairs` or `Pair`s still share their queue.
+    pub fn recycle(&mut self, pairs: Pairs<'_, R>) -> bool {
+        match pairs::into_queue(pairs) {
+            Some(queue) => {
+                self.queue = Some(queue);
+                true
+            }
+            None => false,
+        }
+    }
+
+    // Lends the state of the last parse, restarted on `input`, along with the recycled queue.
+    fn lend<'i>(&mut self, input: &'i str) -> Box<ParserState<'i, R>> {
+        let mut state: Box<ParserState<'i, R>> = match self.state.take() {
+            Some(state) => state,
+            None => return ParserState::new(input),
+        };
+        state.restart(input);
+
+        if let Some(queue) = self.queue.as_mut().and_then(Rc::get_mut) {
+            mem::swap(queue, &mut state.queue);
+            state.queue.clear();
+        }
+
+        state
+    }
+
+    // Swaps the queue of a successful parse into the recycled `Rc`, which is returned to be shared
+    // by its `Pairs`.
+    fn share(&mut self, state: &mut ParserState<'_, R>) -> Rc<Vec<QueueableToken<R>>> {
+        let mut queue = self.queue.take().unwrap_or_default();
+        let tokens = Rc::get_mut(&mut queue).expect("kept queues are not shared");
+        mem::swap(tokens, &mut state.queue);
+        queue
+    }
+
+    // Keeps `state` for the next parse, once it no longer borrows its input, handing back the queue
+    // it was lent if it failed.
+    fn park(&mut self, mut state: Box<ParserState<'_, R>>) {
+        if let Some(queue) = self.queue.as_mut().and_then(Rc::get_mut) {
+            mem::swap(queue, &mut state.queue);
+        }
+        state.restart("");
+
+        // Restarted on the empty input, which is `'static`, the state only borrows it, while its
+        // buffers, the only parts left by the previous parse, are empty.
+        let state = Box::into_raw(state).cast::<ParserState<'static, R>>();
+        self.state = Some(unsafe { Box::from_raw(state) });
+    }
+}
+
+impl<R: RuleType> Default for ParseReuse<R> {
+    fn default() -> ParseReuse<R> {
+        ParseReuse::new()
+    }
 }
 
 /// Creates a `ParserState` from a `&str`, supplying it to a closure `f`.
@@ -148,43 +1159,621 @@ pub struct ParserState<'i, R: RuleType> {
 /// pest::state::<(), _>(input, |s| Ok(s)).unwrap();
 /// ```
 #[allow(clippy::perf)]
-pub fn state<'i, R: RuleType, F>(input: &'i str, f: F) -> Result<pairs::Pairs<'i, R>, Error<R>>
+pub fn state<'i, R: RuleType, F>(input: &'i str, f: F) -> Result<Pairs<'i, R>, Error<R>>
 where
     F: FnOnce(Box<ParserState<'i, R>>) -> ParseResult<Box<ParserState<'i, R>>>,
 {
-    let state = ParserState::new(input);
+    state_with_stats(input, f).0
+}
+
+/// Same as [`state`], but parses in explain mode, so that the returned error carries an
+/// [`Explanation`]. See [`ParseOptions::with_explain`].
+///
+/// [`state`]: fn.state.html
+/// [`Explanation`]: explain/struct.Explanation.html
+/// [`ParseOptions::with_explain`]: struct.ParseOptions.html#method.with_explain
+///
+/// # Examples
+///
+/// ```
+/// # use pest;
+/// let error = pest::state_explained::<&str, _>("ab", |s| {
+///     s.rule("a", |s| s.match_string("a").and_then(|s| s.match_string("c")))
+/// })
+/// .unwrap_err();
+///
+/// let attempt = &error.explanation().unwrap().attempts[0];
+/// assert_eq!(attempt.rule, "a");
+/// assert_eq!(attempt.reached, 1);
+/// assert_eq!(attempt.expected, ["\"c\""]);
+/// ```
+#[allow(clippy::perf)]
+pub fn state_explained<'i, R: RuleType, F>(input: &'i str, f: F) -> Result<Pairs<'i, R>, Error<R>>
+where
+    F: FnOnce(Box<ParserState<'i, R>>) -> ParseResult<Box<ParserState<'i, R>>>,
+{
+    state(input, |state| f(state.explained()))
+}
+
+/// Same as [`state`], but parses in compact mode and returns [`CompactPairs`]. The tokens are kept
+/// as `u32` positions and rules while parsing, at a third of the memory of the regular token queue,
+/// and encoded from them once the parse succeeds, so that the regular queue is never built.
+///
+/// Inputs whose positions do not fit in a `u32` are parsed with the regular queue, and so are the
+/// parts of a parse which memoize matches, grow left-recursive seeds or graft nested pairs, from
+/// the first one on.
+///
+/// [`state`]: fn.state.html
+/// [`CompactPairs`]: iterators/struct.CompactPairs.html
+///
+/// # Examples
+///
+/// ```
+/// # use pest;
+/// let compact = pest::state_compact::<&str, _>("ab", |s| {
+///     s.rule("a", |s| s.rule("b", |s| s.match_string("a")).and_then(|s| s.match_string("b")))
+/// })
+/// .unwrap();
+///
+/// assert_eq!(compact.len(), 4);
+/// assert_eq!(format!("{}", compact.to_pairs()), r#"["a"(0, 2, ["b"(0, 1)])]"#);
+/// ```
+#[allow(clippy::perf)]
+pub fn state_compact<'i, R: RuleType, F>(
+    input: &'i str,
+    f: F,
+) -> Result<CompactPairs<'i, R>, Error<R>>
+where
+    F: FnOnce(Box<ParserState<'i, R>>) -> ParseResult<Box<ParserState<'i, R>>>,
+{
+    let mut state = ParserState::new(input);
+    if input.len() <= u32::MAX as usize {
+        state.modes().compact = true;
+    }
 
     match f(state) {
-        Ok(state) => {
-            let len = state.queue.len();
-            Ok(pairs::new(Rc::new(state.queue), input, 0, len))
+        Ok(state) => Ok(state.into_compact(input)),
+        Err(mut state) => Err(state.error_head().into_error(input)),
+    }
+}
+
+/// Same as [`state`], but also reports the memory allocated by the parse, so that it can be
+/// observed or bounded per request.
+///
+/// [`state`]: fn.state.html
+///
+/// # Examples
+///
+/// ```
+/// # use pest;
+/// let input = "aaa";
+/// let (result, stats) = pest::state_with_stats::<&str, _>(input, |s| {
+///     s.repeat(|s| s.rule("a", |s| s.match_string("a")))
+/// });
+///
+/// assert_eq!(result.unwrap().count(), 3);
+/// assert!(stats.queue_bytes > 0);
+/// assert!(stats.total_bytes() >= stats.queue_bytes);
+/// ```
+#[allow(clippy::perf)]
+pub fn state_with_stats<'i, R: RuleType, F>(
+    input: &'i str,
+    f: F,
+) -> (Result<Pairs<'i, R>, Error<R>>, ParseStats)
+where
+    F: FnOnce(Box<ParserState<'i, R>>) -> ParseResult<Box<ParserState<'i, R>>>,
+{
+    let (result, stats) = run(input, f);
+    (result.map_err(|head| head.into_error(input)), stats)
+}
+
+/// Same as [`state`], but with the state and buffers kept in `reuse` by earlier parses instead of
+/// newly allocated ones. See [`ParseReuse`].
+///
+/// [`state`]: fn.state.html
+/// [`ParseReuse`]: struct.ParseReuse.html
+#[allow(clippy::perf)]
+pub fn state_reusing<'i, R: RuleType, F>(
+    input: &'i str,
+    reuse: &mut ParseReuse<R>,
+    f: F,
+) -> Result<Pairs<'i, R>, Error<R>>
+where
+    F: FnOnce(Box<ParserState<'i, R>>) -> ParseResult<Box<ParserState<'i, R>>>,
+{
+    match f(reuse.lend(input)) {
+        Ok(mut state) => {
+            let queue = reuse.share(&mut state);
+            reuse.park(state);
+
+            let len = queue.len();
+            Ok(pairs::new(queue, input, 0, len))
         }
         Err(mut state) => {
-            let variant = if state.reached_call_limit() {
-                ErrorVariant::CustomError {
-                    message: "call limit reached".to_owned(),
-                }
+            let head = state.error_head();
+            reuse.park(state);
+            Err(head.into_error(input))
+        }
+    }
+}
+
+/// Same as [`state`], but only returns the [`ErrorHead`] of a failed parse, leaving out the lines
+/// and columns of the input which are only needed to display the error.
+///
+/// [`state`]: fn.state.html
+/// [`ErrorHead`]: error/struct.ErrorHead.html
+///
+/// # Examples
+///
+/// ```
+/// # use pest;
+/// # use pest::error::InputLocation;
+/// let input = "ab";
+/// let head = pest::state_head::<&str, _>(input, |s| {
+///     s.rule("a", |s| s.match_string("a")).and_then(|s| s.rule("c", |s| s.match_string("c")))
+/// })
+/// .unwrap_err();
+///
+/// assert_eq!(head.location, InputLocation::Pos(1));
+/// assert_eq!(head.into_error(input).line(), "ab");
+/// ```
+#[allow(clippy::perf)]
+pub fn state_head<'i, R: RuleType, F>(input: &'i str, f: F) -> Result<Pairs<'i, R>, ErrorHead<R>>
+where
+    F: FnOnce(Box<ParserState<'i, R>>) -> ParseResult<Box<ParserState<'i, R>>>,
+{
+    run(input, f).0
+}
+
+/// Same as [`state`], but parses in recovery mode, returning the `Pairs` along with the errors
+/// which were recovered from, in the order of the input. If the parse still fails, the `Pairs`
+/// are empty and its error comes last.
+///
+/// [`state`]: fn.state.html
+///
+/// # Examples
+///
+/// ```
+/// # use pest::{self, Recovery};
+/// let recovery = Recovery::new("error").with_sync_rule("item").with_boundary(";");
+/// let (pairs, errors) = pest::state_recoverable::<&str, _>("ab;a;x", &recovery, |s| {
+///     s.repeat(|s| {
+///         s.rule("item", |s| s.sequence(|s| s.match_string("a").and_then(|s| s.match_string(";"))))
+///     })
+/// });
+///
+/// let rules: Vec<_> = pairs.map(|pair| pair.as_rule()).collect();
+/// assert_eq!(rules, vec!["error", "item"]);
+/// assert_eq!(errors.len(), 1);
+/// assert_eq!(errors[0].line_col, pest::error::LineColLocation::Pos((1, 1)));
+/// ```
+#[allow(clippy::perf)]
+pub fn state_recoverable<'i, R: RuleType, F>(
+    input: &'i str,
+    recovery: &Recovery<R>,
+    f: F,
+) -> (Pairs<'i, R>, Vec<Error<R>>)
+where
+    F: FnOnce(Box<ParserState<'i, R>>) -> ParseResult<Box<ParserState<'i, R>>>,
+{
+    let mut state = ParserState::new(input);
+    state.recovery = Some(Box::new(recovery.clone()));
+
+    match f(state) {
+        Ok(mut state) => {
+            let recovered = mem::take(&mut state.recovered);
+            let errors = recovered
+                .into_iter()
+                .filter(|recovered| state.is_recovered(recovered))
+                .map(|recovered| recovered.head.into_error(input))
+                .collect();
+            (state.into_pairs(input), errors)
+        }
+        Err(mut state) => (
+            pairs::new(Rc::new(vec![]), input, 0, 0),
+            vec![state.error_head().into_error(input)],
+        ),
+    }
+}
+
+/// Same as [`state`], but parses in tolerant mode, an experimental mode where literals may deviate
+/// from the input within the rules of `tolerance`, returning the `Pairs` along with the repairs
+/// made to match them, in the order of the input. See [`Tolerance`].
+///
+/// `f` is only run again in tolerant mode if the parse fails strictly, in which case the error of
+/// the strict parse is returned if it still fails.
+///
+/// [`state`]: fn.state.html
+/// [`Tolerance`]: struct.Tolerance.html
+///
+/// # Examples
+///
+/// ```
+/// # use pest::{self, RepairKind, Tolerance};
+/// let tolerance = Tolerance::new().with_rule("level");
+/// let (pairs, repairs) = pest::state_tolerant::<&str, _>("EROR: disk full", &tolerance, |s| {
+///     s.rule("level", |s| s.match_string("ERROR").or_else(|s| s.match_string("WARNING")))
+///         .and_then(|s| s.match_string(": "))
+/// })
+/// .unwrap();
+///
+/// assert_eq!(pairs.as_str(), "EROR");
+/// assert_eq!(repairs.len(), 1);
+/// assert_eq!((repairs[0].pos, repairs[0].kind), (1, RepairKind::Missing('R')));
+/// assert_eq!(format!("{}", repairs[0]), "missing 'R' in \"ERROR\"");
+/// ```
+#[allow(clippy::perf)]
+pub fn state_tolerant<'i, R: RuleType, F>(
+    input: &'i str,
+    tolerance: &Tolerance<R>,
+    mut f: F,
+) -> Result<(Pairs<'i, R>, Vec<Repair>), Error<R>>
+where
+    F: FnMut(Box<ParserState<'i, R>>) -> ParseResult<Box<ParserState<'i, R>>>,
+{
+    let error = match state(input, &mut f) {
+        Ok(pairs) => return Ok((pairs, vec![])),
+        Err(error) => error,
+    };
+
+    let mut state = ParserState::new(input);
+    state.modes().tolerance = Some(tolerance.clone());
+
+    match f(state) {
+        Ok(mut state) => {
+            let repairs = mem::take(&mut state.modes().repairs);
+            Ok((state.into_pairs(input), repairs))
+        }
+        Err(_) => Err(error),
+    }
+}
+
+#[allow(clippy::perf)]
+fn run<'i, R: RuleType, F>(input: &'i str, f: F) -> (Result<Pairs<'i, R>, ErrorHead<R>>, ParseStats)
+where
+    F: FnOnce(Box<ParserState<'i, R>>) -> ParseResult<Box<ParserState<'i, R>>>,
+{
+    let state = ParserState::new(input);
+
+    let result = f(state);
+    let stats = match result {
+        Ok(ref state) | Err(ref state) => state.stats(),
+    };
+
+    let result = match result {
+        Ok(state) => Ok(state.into_pairs(input)),
+        Err(mut state) => Err(state.error_head()),
+    };
+
+    (result, stats)
+}
+
+impl<'i, R: RuleType> ParserState<'i, R> {
+    fn into_pairs(self: Box<Self>, input: &'i str) -> Pairs<'i, R> {
+        let len = self.queue.len();
+        pairs::new(Rc::new(self.queue), input, 0, len)
+    }
+
+    fn into_compact(self: Box<Self>, input: &'i str) -> CompactPairs<'i, R> {
+        if self.compacting() {
+            compact::from_tokens(&self.compact, input)
+        } else {
+            compact::new(&self.queue, input, 0, self.queue.len())
+        }
+    }
+
+    // Builds the error of the furthest attempt, taking the attempts out of the state.
+    fn error_head(&mut self) -> ErrorHead<R> {
+        let variant = if let Some(timeout) = self.call_tracker.timed_out {
+            ErrorVariant::CustomError {
+                message: format!("timeout of {:?} exceeded", timeout),
+            }
+        } else if let Some((kind, _)) = self.call_tracker.exceeded() {
+            ErrorVariant::CustomError {
+                message: format!("{} limit reached", kind),
+            }
+        } else {
+            if self.tracking == AttemptTracking::Farthest {
+                self.pos_attempts
+                    .sort_by_cached_key(|rule| format!("{:?}", rule));
+                self.neg_attempts
+                    .sort_by_cached_key(|rule| format!("{:?}", rule));
             } else {
-                state.pos_attempts.sort();
-                state.pos_attempts.dedup();
-                state.neg_attempts.sort();
-                state.neg_attempts.dedup();
+                self.pos_attempts.sort();
+                self.neg_attempts.sort();
+            }
+            self.pos_attempts.dedup();
+            self.neg_attempts.dedup();
+            ErrorVariant::ParsingError {
+                positives: mem::take(&mut self.pos_attempts),
+                negatives: mem::take(&mut self.neg_attempts),
+            }
+        };
+
+        let candidates = if self.reached_call_limit() {
+            vec![]
+        } else {
+            let farthest = self
+                .farthest_pos
+                .max(self.attempt_pos)
+                .max(self.literal_pos);
+            vec![
+                (ErrorBias::Attempt, self.attempt_pos),
+                (ErrorBias::Farthest, farthest),
+                (ErrorBias::LongestMatch, self.matched_pos),
+            ]
+        };
+        let pos = candidates
+            .iter()
+            .find(|&&(bias, _)| bias == self.error_bias)
+            .map_or(self.attempt_pos, |&(_, pos)| pos);
+
+        // The attempts are only reported at the position where they were made.
+        let variant = match variant {
+            ErrorVariant::ParsingError { .. } if pos != self.attempt_pos => {
                 ErrorVariant::ParsingError {
-                    positives: state.pos_attempts.clone(),
-                    negatives: state.neg_attempts.clone(),
+                    positives: vec![],
+                    negatives: vec![],
                 }
-            };
+            }
+            variant => variant,
+        };
+        let error =
+            ErrorHead::new(variant, InputLocation::Pos(pos)).with_failure_candidates(candidates);
+
+        let error = if self.literal_pos == pos && !self.reached_call_limit() {
+            let text = &self.literal_text;
+            error.with_literals(
+                self.literal_attempts
+                    .iter()
+                    .map(|&(start, end, insensitive)| (text[start..end].to_owned(), insensitive))
+                    .collect(),
+            )
+        } else {
+            error
+        };
+
+        let error = match self.modes.as_mut().and_then(|modes| modes.explain.take()) {
+            Some(recorder) => error.with_explanation(recorder.finish(pos)),
+            None => error,
+        };
+
+        let error = match self.failed_pop {
+            Some((failed, span)) if failed >= pos => error.with_label(
+                (span.start(), span.end()),
+                "unclosed delimiter opened here".to_owned(),
+            ),
+            _ => error,
+        };
+
+        let error = match self.failed_label {
+            Some((start, failed, ref label)) if failed == pos => {
+                error.with_label((start, failed), format!("while parsing {}", label))
+            }
+            _ => error,
+        };
+
+        let error = error
+            .with_incomplete(self.starved)
+            .with_rule_stack(mem::take(&mut self.farthest_stack))
+            .with_limit_exceeded(self.call_tracker.exceeded())
+            .with_deadline_exceeded(self.call_tracker.timed_out.is_some());
+
+        match self.window {
+            Some(window) => error.clamped(window),
+            None => error,
+        }
+    }
+
+    #[inline]
+    fn in_window(&self, pos: usize) -> bool {
+        self.window
+            .map_or(true, |(start, end)| start <= pos && pos <= end)
+    }
+
+    // Recovers from the failure of `rule`, started at `start`, if it is a sync rule. Failures which
+    // reached no further than `start` are left to backtrack, since they only mean that `rule` does
+    // not start there.
+    //
+    // On recovery, records the error of the furthest attempt and skips the input from `start` to
+    // right after the next boundary, covering it with a pair of the error rule.
+    #[inline(never)]
+    fn recover(
+        mut self: Box<Self>,
+        rule: R,
+        start: usize,
+        outer_reached_pos: usize,
+    ) -> ParseResult<Box<Self>> {
+        let reached_pos = self.reached_pos.max(self.position.pos());
+        self.reached_pos = outer_reached_pos.max(reached_pos);
+        let from = self.attempt_pos.max(reached_pos);
+
+        let (error_rule, end) = match self.recovery {
+            Some(ref recovery)
+                if self.lookahead == Lookahead::None
+                    && self.atomicity != Atomicity::Atomic
+                    && !self.reached_call_limit()
+                    && from > start
+                    && self.recovered.len() < recovery.max_errors
+                    && recovery.sync_rules.contains(&rule) =>
+            {
+                let input = self.position.input();
+                let end = recovery
+                    .boundaries
+                    .iter()
+                    .filter_map(|boundary| {
+                        input[from..]
+                            .find(boundary.as_str())
+                            .map(|index| from + index + boundary.len())
+                    })
+                    .min()
+                    .unwrap_or(input.len());
+                (recovery.error_rule, end)
+            }
+            _ => return Err(self),
+        };
+
+        // The explanation is only recorded for the error of the whole parse.
+        let explain = self.modes.as_mut().and_then(|modes| modes.explain.take());
+        let head = self.error_head();
+        if let Some(modes) = &mut self.modes {
+            modes.explain = explain;
+        }
+
+        let index = self.queue.len();
+        self.queue.push(QueueableToken::Start {
+            end_token_index: index + 1,
+            input_pos: start,
+        });
+        self.queue.push(QueueableToken::End {
+            start_token_index: index,
+            rule: error_rule,
+            input_pos: end,
+        });
+        self.recovered.push(Recovered {
+            index,
+            start,
+            end,
+            head,
+        });
+
+        // Positions found in the input are always valid.
+        self.position = Position::new(self.position.input(), end).unwrap();
+        self.pos_attempts.clear();
+        self.neg_attempts.clear();
+        self.attempt_pos = end;
+        self.farthest_pos = end;
+        self.matched_pos = end;
+        self.literal_attempts.clear();
+        self.literal_text.clear();
+        self.farthest_stack.clear();
+        self.failed_pop = None;
+        self.failed_label = None;
+
+        Ok(self)
+    }
+
+    // Enters `rule` with `AttemptTracking::Farthest`, where the rules entered before at the same
+    // depth or deeper have exited since, or with a maximum depth. It is kept out of `rule`, so that
+    // the stack frame of `rule` stays small for deeply nested inputs, and only called in these
+    // parses.
+    #[inline(never)]
+    fn enter(&mut self, rule: R) {
+        self.call_tracker.enter(self.depth);
+        if self.tracking == AttemptTracking::Farthest {
+            self.rule_stack.truncate(self.depth - 1);
+            self.rule_stack.push(rule);
+        }
+    }
+
+    // Enters `rule` in tolerant mode if it is tolerant, returning the number of tolerant rules
+    // the position was nested in before.
+    fn enter_tolerant(&mut self, rule: R) -> usize {
+        let modes = self.modes();
+        let tolerant = modes.tolerant;
+        if let Some(ref tolerance) = modes.tolerance {
+            if tolerance.rules.contains(&rule) {
+                modes.tolerant += 1;
+            }
+        }
+        tolerant
+    }
+
+    // Drops the repairs made after `pos`, which is being backtracked to. Repairs always consume
+    // input, so the ones made before end at or before it.
+    fn drop_repairs(&mut self, pos: usize) {
+        if let Some(modes) = &mut self.modes {
+            while matches!(modes.repairs.last(), Some(repair) if repair.start >= pos) {
+                modes.repairs.pop();
+            }
+        }
+    }
+
+    // The number of repairs made so far, which are only kept in tolerant mode.
+    fn repairs_len(&self) -> usize {
+        self.modes.as_ref().map_or(0, |modes| modes.repairs.len())
+    }
+
+    // Matches `literal`, which failed to match strictly within a tolerant rule, with one deviation
+    // from the input, recording the repair.
+    #[inline(never)]
+    fn repair(mut self: Box<Self>, literal: &str, insensitive: bool) -> ParseResult<Box<Self>> {
+        let max_repairs = self
+            .modes
+            .as_ref()
+            .and_then(|modes| modes.tolerance.as_ref())
+            .map_or(0, |tolerance| tolerance.max_repairs);
+        if self.lookahead != Lookahead::None
+            || self.repairs_len() >= max_repairs
+            || literal.chars().count() < MIN_REPAIR_CHARS
+        {
+            return Err(self);
+        }
+
+        let start = self.position.pos();
+        let input = self.position.input();
+
+        match fuzzy_match(&input[start..], literal, insensitive) {
+            Some((len, offset, kind)) => {
+                self.modes().repairs.push(Repair {
+                    literal: literal.to_owned(),
+                    start,
+                    end: start + len,
+                    pos: start + offset,
+                    kind,
+                });
+                // The match ends at a `char` boundary of the input.
+                self.position = Position::new(input, start + len).unwrap();
+                Ok(self)
+            }
+            None => Err(self),
+        }
+    }
+
+    // Whether the pair of a recovered error is still in the queue, rather than backtracked over.
+    fn is_recovered(&self, recovered: &Recovered<R>) -> bool {
+        let error_rule = match self.recovery {
+            Some(ref recovery) => recovery.error_rule,
+            None => return false,
+        };
+
+        match self.queue.get(recovered.index) {
+            Some(&QueueableToken::Start {
+                end_token_index,
+                input_pos,
+            }) if input_pos == recovered.start => matches!(
+                self.queue.get(end_token_index),
+                Some(&QueueableToken::End { rule, input_pos, .. })
+                    if rule == error_rule && input_pos == recovered.end
+            ),
+            _ => false,
+        }
+    }
 
-            Err(Error::new_from_pos(
-                variant,
-                // TODO(performance): Guarantee state.attempt_pos is a valid position
-                position::Position::new(input, state.attempt_pos).unwrap(),
-            ))
+    fn stats(&self) -> ParseStats {
+        ParseStats {
+            queue_bytes: self.queue.capacity() * size_of::<QueueableToken<R>>()
+                + self.compact.capacity() * size_of::<CompactToken<R>>(),
+            attempts_bytes: self.pos_attempts.capacity() * size_of::<R>()
+                + self.neg_attempts.capacity() * size_of::<R>()
+                + self.rule_stack.capacity() * size_of::<R>()
+                + self.farthest_stack.capacity() * size_of::<R>()
+                + self.literal_attempts.capacity() * size_of::<(usize, usize, bool)>()
+                + self.literal_text.capacity()
+                + self
+                    .modes
+                    .as_ref()
+                    .and_then(|modes| modes.explain.as_ref())
+                    .map_or(0, |recorder| recorder.heap_size())
+                + self
+                    .failed_label
+                    .as_ref()
+                    .map_or(0, |(_, _, label)| label.capacity()),
+            stack_bytes: self.stack.heap_size(),
+            state_bytes: size_of::<Self>(),
         }
     }
-}
 
-impl<'i, R: RuleType> ParserState<'i, R> {
     /// Allocates a fresh `ParserState` object to the heap and returns the owned `Box`. This `Box`
     /// will be passed from closure to closure based on the needs of the specified `Parser`.
     ///
@@ -196,17 +1785,124 @@ impl<'i, R: RuleType> ParserState<'i, R> {
     /// let state: Box<pest::ParserState<&str>> = pest::ParserState::new(input);
     /// ```
     pub fn new(input: &'i str) -> Box<Self> {
-        Box::new(ParserState {
+        Box::new(ParserState::unboxed(input))
+    }
+
+    fn unboxed(input: &'i str) -> Self {
+        ParserState {
             position: Position::from_start(input),
             queue: vec![],
+            compact: vec![],
             lookahead: Lookahead::None,
             pos_attempts: vec![],
             neg_attempts: vec![],
             attempt_pos: 0,
+            literal_attempts: vec![],
+            literal_text: String::new(),
+            literal_pos: 0,
+            farthest_pos: 0,
+            matched_pos: 0,
+            error_bias: ErrorBias::Attempt,
             atomicity: Atomicity::NonAtomic,
             stack: Stack::new(),
+            failed_pop: None,
+            failed_label: None,
             call_tracker: Default::default(),
-        })
+            modes: None,
+            tracking: attempt_tracking(),
+            window: error_window(),
+            depth: 0,
+            reached_pos: 0,
+            recovery: None,
+            recovered: vec![],
+            starved: false,
+            rule_stack: vec![],
+            farthest_stack: vec![],
+            context: AssertUnwindSafe(&()),
+            user_data: AssertUnwindSafe(None),
+            dialect: None,
+            seeds: vec![],
+        }
+    }
+
+    // Makes this the state of a new parse of `input`, keeping the buffers of the previous parse,
+    // emptied.
+    fn restart(&mut self, input: &'i str) {
+        fn emptied<T>(mut buffer: Vec<T>) -> Vec<T> {
+            buffer.clear();
+            buffer
+        }
+
+        let previous = mem::replace(self, ParserState::unboxed(input));
+        self.queue = emptied(previous.queue);
+        self.compact = emptied(previous.compact);
+        self.pos_attempts = emptied(previous.pos_attempts);
+        self.neg_attempts = emptied(previous.neg_attempts);
+        self.literal_attempts = emptied(previous.literal_attempts);
+        self.literal_text = previous.literal_text;
+        self.literal_text.clear();
+        self.stack = previous.stack;
+        self.stack.clear();
+        self.rule_stack = emptied(previous.rule_stack);
+        self.farthest_stack = emptied(previous.farthest_stack);
+    }
+
+    // Starts recording the rule attempts of the parse, unless they already are.
+    fn explained(mut self: Box<Self>) -> Box<Self> {
+        self.modes().explain.get_or_insert_with(Recorder::new);
+        self
+    }
+
+    // The modes of the parse, which are set up on first use.
+    fn modes(&mut self) -> &mut Modes<'i, R> {
+        self.modes.get_or_insert_with(Default::default)
+    }
+
+    fn compacting(&self) -> bool {
+        self.modes.as_ref().map_or(false, |modes| modes.compact)
+    }
+
+    // Leaves compact mode, moving the tokens of the compact queue into the regular one, which the
+    // parsing steps copying, replaying or grafting tokens work with.
+    #[cold]
+    #[inline(never)]
+    fn expand_compact(&mut self) {
+        match &mut self.modes {
+            Some(modes) if modes.compact => modes.compact = false,
+            _ => return,
+        }
+
+        let mut starts = vec![];
+        self.queue.reserve(self.compact.len());
+        for (input_pos, rule) in self.compact.drain(..) {
+            let input_pos = input_pos as usize;
+            let index = self.queue.len();
+            match rule {
+                None => {
+                    starts.push(index);
+                    self.queue.push(QueueableToken::Start {
+                        end_token_index: 0,
+                        input_pos,
+                    });
+                }
+                Some(rule) => {
+                    let start_token_index = starts.pop().expect("unbalanced compact tokens");
+                    if let QueueableToken::Start {
+                        ref mut end_token_index,
+                        ..
+                    } = self.queue[start_token_index]
+                    {
+                        *end_token_index = index;
+                    }
+                    self.queue.push(QueueableToken::End {
+                        start_token_index,
+                        rule,
+                        input_pos,
+                    });
+                }
+            }
+        }
+        self.compact = vec![];
     }
 
     /// Returns a reference to the current `Position` of the `ParserState`.
@@ -252,9 +1948,85 @@ impl<'i, R: RuleType> ParserState<'i, R> {
         self.atomicity
     }
 
+    /// Returns the length in bytes of the input left after the current position.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use pest;
+    /// # #[allow(non_camel_case_types)]
+    /// # #[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+    /// enum Rule {}
+    ///
+    /// let input = "aé";
+    /// let mut state: Box<pest::ParserState<'_, Rule>> = pest::ParserState::new(input);
+    /// assert_eq!(state.remaining_len(), 3);
+    /// let state = state.skip(1).unwrap();
+    /// assert_eq!(state.remaining_len(), 2);
+    /// ```
+    #[inline]
+    pub fn remaining_len(&self) -> usize {
+        self.remaining().len()
+    }
+
+    /// Returns the `char` at the current position without matching it, or `None` at the end of
+    /// the input.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use pest;
+    /// # #[allow(non_camel_case_types)]
+    /// # #[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+    /// enum Rule {}
+    ///
+    /// let input = "a";
+    /// let mut state: Box<pest::ParserState<'_, Rule>> = pest::ParserState::new(input);
+    /// assert_eq!(state.peek_char(), Some('a'));
+    /// let state = state.skip(1).unwrap();
+    /// assert_eq!(state.peek_char(), None);
+    /// ```
+    #[inline]
+    pub fn peek_char(&self) -> Option<char> {
+        self.remaining().chars().next()
+    }
+
+    /// Returns the next `n` `char`s of the input without matching them, or `None` if fewer are
+    /// left.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use pest;
+    /// # #[allow(non_camel_case_types)]
+    /// # #[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+    /// enum Rule {}
+    ///
+    /// let input = "aéb";
+    /// let mut state: Box<pest::ParserState<'_, Rule>> = pest::ParserState::new(input);
+    /// assert_eq!(state.peek_slice(2), Some("aé"));
+    /// assert_eq!(state.peek_slice(4), None);
+    /// assert_eq!(state.position().pos(), 0);
+    /// ```
+    #[inline]
+    pub fn peek_slice(&self, n: usize) -> Option<&'i str> {
+        let remaining = self.remaining();
+        let mut end = self.position;
+
+        if end.skip(n) {
+            Some(&remaining[..end.pos() - self.position.pos()])
+        } else {
+            None
+        }
+    }
+
+    fn remaining(&self) -> &'i str {
+        &self.position.input()[self.position.pos()..]
+    }
+
     #[inline]
     fn inc_call_check_limit(mut self: Box<Self>) -> ParseResult<Box<Self>> {
-        if self.call_tracker.limit_reached() {
+        if self.call_tracker.checkpoint_reached() {
             return Err(self);
         }
         self.call_tracker.increment_depth();
@@ -292,8 +2064,77 @@ impl<'i, R: RuleType> ParserState<'i, R> {
         F: FnOnce(Box<Self>) -> ParseResult<Box<Self>>,
     {
         self = self.inc_call_check_limit()?;
-        let actual_pos = self.position.pos();
+        if self.modes.is_some() {
+            return self.rule_in_modes(rule, f);
+        }
+
+        self.match_rule(rule, f, false)
+    }
+
+    // Matches `rule` in the modes of the parse, replaying or storing its memoized match, recording
+    // it in explain mode, entering tolerant mode if it is tolerant and keeping its tokens in the
+    // compact queue. Like `enter`, it is kept out of line, and so is the whole rule in these modes,
+    // so that `rule` only checks whether any is set.
+    #[cold]
+    #[inline(never)]
+    fn rule_in_modes<F>(mut self: Box<Self>, rule: R, f: F) -> ParseResult<Box<Self>>
+    where
+        F: FnOnce(Box<Self>) -> ParseResult<Box<Self>>,
+    {
+        if self.memoizing() {
+            // Memoized matches are copied out of the regular queue and replayed into it.
+            self.expand_compact();
+            self = match self.replay_memoized(rule) {
+                Ok(state) => state,
+                Err(result) => return result,
+            };
+        }
+
+        let pos = self.position.pos();
         let index = self.queue.len();
+        let compact = self.compacting();
+
+        let result = self.match_rule(
+            rule,
+            |mut state| {
+                let tolerant = state.enter_tolerant(rule);
+                state.explain_enter(rule, pos);
+                let (matched, mut state) = match f(state) {
+                    Ok(state) => (true, state),
+                    Err(state) => (false, state),
+                };
+                state.modes().tolerant = tolerant;
+                state.explain_exit(matched);
+                if matched {
+                    Ok(state)
+                } else {
+                    Err(state)
+                }
+            },
+            compact,
+        );
+
+        match result {
+            Ok(mut state) => {
+                state.memoize(rule, pos, index, true);
+                Ok(state)
+            }
+            Err(mut state) => {
+                state.memoize(rule, pos, index, false);
+                Err(state)
+            }
+        }
+    }
+
+    // Matches `rule`, keeping its tokens in the compact queue if `compact` is set, which it only is
+    // in `rule_in_modes`.
+    #[inline]
+    fn match_rule<F>(mut self: Box<Self>, rule: R, f: F, compact: bool) -> ParseResult<Box<Self>>
+    where
+        F: FnOnce(Box<Self>) -> ParseResult<Box<Self>>,
+    {
+        let actual_pos = self.position.pos();
+        let index = self.open_pair(actual_pos, compact);
 
         let (pos_attempts_index, neg_attempts_index) = if actual_pos == self.attempt_pos {
             (self.pos_attempts.len(), self.neg_attempts.len())
@@ -302,70 +2143,38 @@ impl<'i, R: RuleType> ParserState<'i, R> {
             (0, 0)
         };
 
-        if self.lookahead == Lookahead::None && self.atomicity != Atomicity::Atomic {
-            // Pair's position will only be known after running the closure.
-            self.queue.push(QueueableToken::Start {
-                end_token_index: 0,
-                input_pos: actual_pos,
-            });
-        }
+        let attempts = (
+            pos_attempts_index,
+            neg_attempts_index,
+            self.attempts_at(actual_pos),
+        );
 
-        let attempts = self.attempts_at(actual_pos);
+        let depth = self.depth;
+        self.depth += 1;
+        let outer_reached_pos = mem::replace(&mut self.reached_pos, actual_pos);
 
-        let result = f(self);
+        if self.call_tracker.max_depth.is_some() || self.tracking == AttemptTracking::Farthest {
+            self.enter(rule);
+        }
 
-        match result {
+        match f(self) {
             Ok(mut new_state) => {
-                if new_state.lookahead == Lookahead::Negative {
-                    new_state.track(
-                        rule,
-                        actual_pos,
-                        pos_attempts_index,
-                        neg_attempts_index,
-                        attempts,
-                    );
-                }
+                new_state.depth = depth;
+                new_state.reached_pos = outer_reached_pos;
 
-                if new_state.lookahead == Lookahead::None
-                    && new_state.atomicity != Atomicity::Atomic
-                {
-                    // Storing the pair's index in the first token that was added before the closure was
-                    // run.
-                    let new_index = new_state.queue.len();
-                    match new_state.queue[index] {
-                        QueueableToken::Start {
-                            ref mut end_token_index,
-                            ..
-                        } => *end_token_index = new_index,
-                        _ => unreachable!(),
-                    };
-
-                    let new_pos = new_state.position.pos();
-
-                    new_state.queue.push(QueueableToken::End {
-                        start_token_index: index,
-                        rule,
-                        input_pos: new_pos,
-                    });
-                }
+                new_state.exit(rule, actual_pos, index, attempts, true, compact);
 
                 Ok(new_state)
             }
             Err(mut new_state) => {
-                if new_state.lookahead != Lookahead::Negative {
-                    new_state.track(
-                        rule,
-                        actual_pos,
-                        pos_attempts_index,
-                        neg_attempts_index,
-                        attempts,
-                    );
-                }
+                new_state.depth = depth;
 
-                if new_state.lookahead == Lookahead::None
-                    && new_state.atomicity != Atomicity::Atomic
-                {
-                    new_state.queue.truncate(index);
+                new_state.exit(rule, actual_pos, index, attempts, false, compact);
+
+                // The furthest reached position is only carried over in recovery mode, where it's
+                // needed.
+                if new_state.recovery.is_some() {
+                    return new_state.recover(rule, actual_pos, outer_reached_pos);
                 }
 
                 Err(new_state)
@@ -373,6 +2182,208 @@ impl<'i, R: RuleType> ParserState<'i, R> {
         }
     }
 
+    // The number of tokens in the regular and the compact queue.
+    #[inline]
+    fn token_count(&self) -> usize {
+        self.queue.len() + self.compact.len()
+    }
+
+    // Starts the pair of a rule at `pos`, in the compact queue if `compact` is set, returning the
+    // number of tokens before it. Like `exit`, it is kept out of `match_rule` in debug builds, so
+    // that the stack frames of the nested calls stay small.
+    #[inline]
+    fn open_pair(&mut self, pos: usize, compact: bool) -> usize {
+        let index = if compact {
+            self.token_count()
+        } else {
+            self.queue.len()
+        };
+
+        if self.lookahead == Lookahead::None && self.atomicity != Atomicity::Atomic {
+            if compact {
+                self.compact.push((pos as u32, None));
+            } else {
+                // Pair's position will only be known after running the closure.
+                self.queue.push(QueueableToken::Start {
+                    end_token_index: 0,
+                    input_pos: pos,
+                });
+            }
+        }
+
+        index
+    }
+
+    // Exits `rule`, matched or not from `pos`, where the queue had `index` tokens and `attempts`
+    // were made: tracks the attempt and closes or drops the tokens of its pair, which are in the
+    // compact queue if `compact` is set and the parse was not expanded since.
+    #[inline]
+    fn exit(
+        &mut self,
+        rule: R,
+        pos: usize,
+        index: usize,
+        attempts: (usize, usize, usize),
+        matched: bool,
+        compact: bool,
+    ) {
+        if !matched {
+            self.farthest_pos = self.farthest_pos.max(pos);
+        } else if self.lookahead == Lookahead::None {
+            self.matched_pos = self.matched_pos.max(self.position.pos());
+        }
+
+        // Matches are tracked within negative lookaheads, and failures outside of them.
+        if matched == (self.lookahead == Lookahead::Negative) {
+            let (pos_attempts_index, neg_attempts_index, prev_attempts) = attempts;
+            self.track(
+                rule,
+                pos,
+                pos_attempts_index,
+                neg_attempts_index,
+                prev_attempts,
+            );
+        }
+
+        if self.lookahead == Lookahead::None && self.atomicity != Atomicity::Atomic {
+            if compact && self.compacting() {
+                if matched {
+                    self.compact.push((self.position.pos() as u32, Some(rule)));
+                } else {
+                    self.compact.truncate(index - self.queue.len());
+                }
+            } else if matched {
+                // Storing the pair's index in the first token that was added before the closure
+                // was run.
+                let new_index = self.queue.len();
+                match self.queue[index] {
+                    QueueableToken::Start {
+                        ref mut end_token_index,
+                        ..
+                    } => *end_token_index = new_index,
+                    _ => unreachable!(),
+                };
+
+                let new_pos = self.position.pos();
+
+                self.queue.push(QueueableToken::End {
+                    start_token_index: index,
+                    rule,
+                    input_pos: new_pos,
+                });
+            } else {
+                self.queue.truncate(index);
+            }
+        }
+    }
+
+    // Whether the match of `rule` at the current position is memoized. Left recursion grows its
+    // seeds by matching the same rules again, and recovery repairs the input, so neither is.
+    fn memoizes(&self, rule: R) -> bool {
+        match self.modes.as_ref().and_then(|modes| modes.memo.as_ref()) {
+            Some(memo) => {
+                memo.options.memoizes(rule) && self.seeds.is_empty() && self.recovery.is_none()
+            }
+            None => false,
+        }
+    }
+
+    fn memo_key(&self, rule: R, pos: usize) -> (R, usize, bool, u8) {
+        let atomicity = match self.atomicity {
+            Atomicity::Atomic => 0,
+            Atomicity::CompoundAtomic => 1,
+            Atomicity::NonAtomic => 2,
+        };
+        (rule, pos, self.lookahead != Lookahead::None, atomicity)
+    }
+
+    // Replays the memoized match of `rule` at the current position, returning the state as it was
+    // if there is none. Like `enter`, this and `memoize` are kept out of `rule`, which would
+    // otherwise need more stack for every rule.
+    #[inline(never)]
+    fn replay_memoized(mut self: Box<Self>, rule: R) -> Result<Box<Self>, ParseResult<Box<Self>>> {
+        if !self.memoizes(rule) {
+            return Ok(self);
+        }
+
+        let pos = self.position.pos();
+        let key = self.memo_key(rule, pos);
+        let cached = self
+            .modes
+            .as_ref()
+            .and_then(|modes| modes.memo.as_ref())
+            .and_then(|memo| memo.matches.get(&key))
+            .map(Option::is_some);
+        let matched = match cached {
+            Some(matched) => matched,
+            None => {
+                let repairs = self.repairs_len();
+                if let Some(memo) = self.modes.as_mut().and_then(|modes| modes.memo.as_mut()) {
+                    memo.pending.push(repairs);
+                }
+                return Ok(self);
+            }
+        };
+
+        // Like `rule`, failures are tracked outside of negative lookaheads, and matches within
+        // them.
+        if matched == (self.lookahead == Lookahead::Negative) {
+            let (pos_attempts_index, neg_attempts_index) = if pos == self.attempt_pos {
+                (self.pos_attempts.len(), self.neg_attempts.len())
+            } else {
+                (0, 0)
+            };
+            let attempts = self.attempts_at(pos);
+            self.track(rule, pos, pos_attempts_index, neg_attempts_index, attempts);
+        }
+
+        if !matched {
+            self.farthest_pos = self.farthest_pos.max(pos);
+            return Err(Err(self));
+        }
+
+        let ParserState {
+            modes,
+            queue,
+            position,
+            ..
+        } = &mut *self;
+        if let Some(modes) = modes {
+            if let Some(Some(result)) = modes.memo.as_ref().and_then(|memo| memo.matches.get(&key))
+            {
+                replay(result, queue, Some(&mut modes.repairs), position);
+            }
+        }
+        if self.lookahead == Lookahead::None {
+            self.matched_pos = self.matched_pos.max(self.position.pos());
+        }
+        Err(Ok(self))
+    }
+
+    // Stores the match of `rule` from `pos`, where the queue had `index` tokens, or its failure,
+    // if `replay_memoized` did not find it.
+    #[inline(never)]
+    fn memoize(&mut self, rule: R, pos: usize, index: usize, matched: bool) {
+        if !self.memoizes(rule) {
+            return;
+        }
+
+        let key = self.memo_key(rule, pos);
+        let memo = self.modes.as_mut().and_then(|modes| modes.memo.as_mut());
+        let repairs = match memo.and_then(|memo| memo.pending.pop()) {
+            Some(repairs) => repairs,
+            None => return,
+        };
+        let result = if matched {
+            Some(self.copy_match(index, repairs))
+        } else {
+            None
+        };
+        if let Some(memo) = self.modes.as_mut().and_then(|modes| modes.memo.as_mut()) {
+            memo.insert(key, result);
+        }
+    }
+
     fn attempts_at(&self, pos: usize) -> usize {
         if self.attempt_pos == pos {
             self.pos_attempts.len() + self.neg_attempts.len()
@@ -389,7 +2400,23 @@ impl<'i, R: RuleType> ParserState<'i, R> {
         neg_attempts_index: usize,
         prev_attempts: usize,
     ) {
-        if self.atomicity == Atomicity::Atomic {
+        if self.atomicity == Atomicity::Atomic || !self.in_window(pos) {
+            return;
+        }
+
+        let tracked = match self.tracking {
+            AttemptTracking::Full => true,
+            AttemptTracking::Off => false,
+            AttemptTracking::MaxDepth(depth) => self.depth < depth,
+            AttemptTracking::Farthest => return self.track_farthest(rule, pos),
+        };
+        if !tracked {
+            // The furthest attempt is still needed for the position of the error.
+            if pos > self.attempt_pos {
+                self.pos_attempts.clear();
+                self.neg_attempts.clear();
+                self.attempt_pos = pos;
+            }
             return;
         }
 
@@ -412,17 +2439,181 @@ impl<'i, R: RuleType> ParserState<'i, R> {
             self.attempt_pos = pos;
         }
 
-        let attempts = if self.lookahead != Lookahead::Negative {
-            &mut self.pos_attempts
-        } else {
-            &mut self.neg_attempts
-        };
+        let attempts = if self.lookahead != Lookahead::Negative {
+            &mut self.pos_attempts
+        } else {
+            &mut self.neg_attempts
+        };
+
+        if pos == self.attempt_pos {
+            attempts.push(rule);
+        }
+    }
+
+    // Tracks `rule` along with the rules nested in it, which failed at `pos` as well.
+    #[inline(never)]
+    fn track_farthest(&mut self, rule: R, pos: usize) {
+        if pos > self.attempt_pos {
+            self.pos_attempts.clear();
+            self.neg_attempts.clear();
+            self.farthest_stack.clear();
+            self.attempt_pos = pos;
+        }
+        if pos < self.attempt_pos {
+            return;
+        }
+
+        if self.farthest_stack.is_empty() {
+            let depth = (self.depth + 1).min(self.rule_stack.len());
+            self.farthest_stack
+                .extend_from_slice(&self.rule_stack[..depth]);
+        }
+
+        if self.lookahead != Lookahead::Negative {
+            self.pos_attempts.push(rule);
+        } else {
+            self.neg_attempts.push(rule);
+        }
+    }
+
+    // Records whether `literal`, which failed to match, could still match with more input, i.e.
+    // whether the rest of the input is a proper prefix of it.
+    #[inline]
+    fn starve_literal(&mut self, literal: &str, insensitive: bool) {
+        let rest = self.position.input().len() - self.position.pos();
+        if !self.starved && rest < literal.len() {
+            self.starve_prefix(literal, insensitive);
+        }
+    }
+
+    #[inline(never)]
+    fn starve_prefix(&mut self, literal: &str, insensitive: bool) {
+        let rest = &self.position.input().as_bytes()[self.position.pos()..];
+        let prefix = &literal.as_bytes()[..rest.len()];
+        self.starved = if insensitive {
+            prefix.eq_ignore_ascii_case(rest)
+        } else {
+            prefix == rest
+        };
+    }
+
+    // Records whether a character failed to match because the input ended.
+    #[inline]
+    fn starve_char(&mut self) {
+        if self.position.pos() == self.position.input().len() {
+            self.starved = true;
+        }
+    }
+
+    // Records the failure of a literal at `pos`, if it is at least as far as the furthest attempts.
+    // Literals fail far more often than rules do, so the terminals calling this are always inlined,
+    // leaving only the copy of tracked literals out of line.
+    #[inline]
+    fn track_literal(&mut self, pos: usize, literal: &str, insensitive: bool) {
+        self.farthest_pos = self.farthest_pos.max(pos);
+        if pos >= self.attempt_pos
+            && pos >= self.literal_pos
+            && self.lookahead != Lookahead::Negative
+            && self.atomicity != Atomicity::Atomic
+            && self.in_window(pos)
+        {
+            self.push_literal(pos, literal, insensitive);
+        }
+    }
+
+    #[inline(never)]
+    fn push_literal(&mut self, pos: usize, literal: &str, insensitive: bool) {
+        let tracked = match self.tracking {
+            AttemptTracking::Full | AttemptTracking::Farthest => true,
+            AttemptTracking::Off => false,
+            AttemptTracking::MaxDepth(depth) => self.depth < depth,
+        };
+        if !tracked {
+            return;
+        }
+
+        if pos > self.literal_pos {
+            self.literal_attempts.clear();
+            self.literal_text.clear();
+            self.literal_pos = pos;
+        }
+
+        let text = &self.literal_text;
+        let tracked = self
+            .literal_attempts
+            .iter()
+            .any(|&(start, end, other_insensitive)| {
+                &text[start..end] == literal && other_insensitive == insensitive
+            });
+        if !tracked {
+            let start = self.literal_text.len();
+            self.literal_text.push_str(literal);
+            self.literal_attempts
+                .push((start, self.literal_text.len(), insensitive));
+        }
+    }
+
+    // Records a terminal starting at `pos` in explain mode, if it explains a failure.
+    #[inline]
+    fn explain_terminal<F>(&mut self, pos: usize, matched: bool, describe: F)
+    where
+        F: FnOnce() -> String,
+    {
+        if self.modes.is_some() {
+            self.record_terminal(pos, matched, describe());
+        }
+    }
+
+    // The explain mode counterparts of `explain_terminal` and of the hooks in `rule_in_modes` and
+    // `sequence_in_modes`, kept out of line so that parses in no mode only check `modes`.
+    #[cold]
+    #[inline(never)]
+    fn record_terminal(&mut self, pos: usize, matched: bool, description: String) {
+        let negated = self.lookahead == Lookahead::Negative;
+        if let Some(recorder) = self.modes.as_mut().and_then(|modes| modes.explain.as_mut()) {
+            match (matched, negated) {
+                (false, false) => recorder.terminal(pos, description, true),
+                (true, true) => recorder.terminal(pos, description, false),
+                _ => (),
+            }
+        }
+    }
+
+    fn explain_enter(&mut self, rule: R, pos: usize) {
+        let negated = self.lookahead == Lookahead::Negative;
+        if let Some(recorder) = self.modes.as_mut().and_then(|modes| modes.explain.as_mut()) {
+            recorder.enter(rule, pos, negated);
+        }
+    }
+
+    fn explain_exit(&mut self, matched: bool) {
+        let pos = self.position.pos();
+        if let Some(recorder) = self.modes.as_mut().and_then(|modes| modes.explain.as_mut()) {
+            recorder.exit(matched, pos);
+        }
+    }
 
-        if pos == self.attempt_pos {
-            attempts.push(rule);
+    fn explain_reach(&mut self) {
+        let pos = self.position.pos();
+        if self.lookahead != Lookahead::Negative {
+            if let Some(recorder) = self.modes.as_mut().and_then(|modes| modes.explain.as_mut()) {
+                recorder.reach(pos);
+            }
         }
     }
 
+    // Records the furthest position reached by a failed sequence in explain mode, and drops the
+    // repairs made since it started at `pos` and its tokens in the compact queue, where there were
+    // `index` tokens. Like `rule_in_modes`, it is kept out of line.
+    #[cold]
+    #[inline(never)]
+    fn sequence_in_modes(&mut self, pos: usize, index: usize) {
+        self.explain_reach();
+        self.drop_repairs(pos);
+        let compact = index.saturating_sub(self.queue.len());
+        self.compact.truncate(compact);
+    }
+
     /// Starts a sequence of transformations provided by `f` from the `Box<ParserState>`. Returns
     /// the same `Result` returned by `f` in the case of an `Ok`, or `Err` with the current
     /// `Box<ParserState>` otherwise.
@@ -461,7 +2652,7 @@ impl<'i, R: RuleType> ParserState<'i, R> {
         F: FnOnce(Box<Self>) -> ParseResult<Box<Self>>,
     {
         self = self.inc_call_check_limit()?;
-        let token_index = self.queue.len();
+        let token_index = self.token_count();
         let initial_pos = self.position;
 
         let result = f(self);
@@ -469,6 +2660,11 @@ impl<'i, R: RuleType> ParserState<'i, R> {
         match result {
             Ok(new_state) => Ok(new_state),
             Err(mut new_state) => {
+                if new_state.modes.is_some() {
+                    new_state.sequence_in_modes(initial_pos.pos(), token_index);
+                }
+                new_state.reached_pos = new_state.reached_pos.max(new_state.position.pos());
+
                 // Restore the initial position and truncate the token queue.
                 new_state.position = initial_pos;
                 new_state.queue.truncate(token_index);
@@ -587,9 +2783,13 @@ impl<'i, R: RuleType> ParserState<'i, R> {
     where
         F: FnOnce(char) -> bool,
     {
-        if self.position.match_char_by(f) {
+        let pos = self.position.pos();
+        let matched = self.position.match_char_by(f);
+        self.explain_terminal(pos, matched, || "a matching character".to_owned());
+        if matched {
             Ok(self)
         } else {
+            self.starve_char();
             Err(self)
         }
     }
@@ -616,11 +2816,18 @@ impl<'i, R: RuleType> ParserState<'i, R> {
     /// assert!(result.is_err());
     /// assert_eq!(result.unwrap_err().position().pos(), 0);
     /// ```
-    #[inline]
+    #[inline(always)]
     pub fn match_string(mut self: Box<Self>, string: &str) -> ParseResult<Box<Self>> {
+        if self.modes.is_some() {
+            return self.match_literal_in_modes(string, false);
+        }
+
+        let pos = self.position.pos();
         if self.position.match_string(string) {
             Ok(self)
         } else {
+            self.track_literal(pos, string, false);
+            self.starve_literal(string, false);
             Err(self)
         }
     }
@@ -647,15 +2854,66 @@ impl<'i, R: RuleType> ParserState<'i, R> {
     /// assert!(result.is_err());
     /// assert_eq!(result.unwrap_err().position().pos(), 0);
     /// ```
-    #[inline]
+    #[inline(always)]
     pub fn match_insensitive(mut self: Box<Self>, string: &str) -> ParseResult<Box<Self>> {
+        if self.modes.is_some() {
+            return self.match_literal_in_modes(string, true);
+        }
+
+        let pos = self.position.pos();
         if self.position.match_insensitive(string) {
             Ok(self)
         } else {
+            self.track_literal(pos, string, true);
+            self.starve_literal(string, true);
             Err(self)
         }
     }
 
+    // Matches `literal` in the modes of the parse, recording it in explain mode and repairing it
+    // within tolerant rules. Like `rule_in_modes`, it is kept out of line, so that the terminals
+    // only check whether any mode is set.
+    #[cold]
+    #[inline(never)]
+    fn match_literal_in_modes(
+        mut self: Box<Self>,
+        literal: &str,
+        insensitive: bool,
+    ) -> ParseResult<Box<Self>> {
+        let pos = self.position.pos();
+        let matched = if insensitive {
+            self.position.match_insensitive(literal)
+        } else {
+            self.position.match_string(literal)
+        };
+        if self
+            .modes
+            .as_ref()
+            .map_or(false, |modes| modes.explain.is_some())
+        {
+            let description = if insensitive {
+                format!("^{:?}", literal)
+            } else {
+                format!("{:?}", literal)
+            };
+            self.record_terminal(pos, matched, description);
+        }
+        if matched {
+            return Ok(self);
+        }
+
+        self.track_literal(pos, literal, insensitive);
+        self.starve_literal(literal, insensitive);
+        if self
+            .modes
+            .as_ref()
+            .map_or(false, |modes| modes.tolerant > 0)
+        {
+            return self.repair(literal, insensitive);
+        }
+        Err(self)
+    }
+
     /// Attempts to match a single character from the given range. Returns `Ok` with the updated
     /// `Box<ParserState>` if successful, or `Err` with the updated `Box<ParserState>` otherwise.
     ///
@@ -683,9 +2941,14 @@ impl<'i, R: RuleType> ParserState<'i, R> {
     /// ```
     #[inline]
     pub fn match_range(mut self: Box<Self>, range: Range<char>) -> ParseResult<Box<Self>> {
-        if self.position.match_range(range) {
+        let pos = self.position.pos();
+        let (start, end) = (range.start, range.end);
+        let matched = self.position.match_range(range);
+        self.explain_terminal(pos, matched, || format!("{:?}..{:?}", start, end));
+        if matched {
             Ok(self)
         } else {
+            self.starve_char();
             Err(self)
         }
     }
@@ -714,9 +2977,17 @@ impl<'i, R: RuleType> ParserState<'i, R> {
     /// ```
     #[inline]
     pub fn skip(mut self: Box<Self>, n: usize) -> ParseResult<Box<Self>> {
-        if self.position.skip(n) {
+        let pos = self.position.pos();
+        let matched = self.position.skip(n);
+        self.explain_terminal(pos, matched, || match n {
+            1 => "any character".to_owned(),
+            n => format!("{} characters", n),
+        });
+        if matched {
             Ok(self)
         } else {
+            // Skipping only fails for lack of input.
+            self.starved = true;
             Err(self)
         }
     }
@@ -766,8 +3037,11 @@ impl<'i, R: RuleType> ParserState<'i, R> {
     /// assert!(result.is_err());
     /// ```
     #[inline]
-    pub fn start_of_input(self: Box<Self>) -> ParseResult<Box<Self>> {
-        if self.position.at_start() {
+    pub fn start_of_input(mut self: Box<Self>) -> ParseResult<Box<Self>> {
+        let pos = self.position.pos();
+        let matched = self.position.at_start();
+        self.explain_terminal(pos, matched, || "start of input".to_owned());
+        if matched {
             Ok(self)
         } else {
             Err(self)
@@ -796,8 +3070,11 @@ impl<'i, R: RuleType> ParserState<'i, R> {
     /// assert!(result.is_ok());
     /// ```
     #[inline]
-    pub fn end_of_input(self: Box<Self>) -> ParseResult<Box<Self>> {
-        if self.position.at_end() {
+    pub fn end_of_input(mut self: Box<Self>) -> ParseResult<Box<Self>> {
+        let pos = self.position.pos();
+        let matched = self.position.at_end();
+        self.explain_terminal(pos, matched, || "end of input".to_owned());
+        if matched {
             Ok(self)
         } else {
             Err(self)
@@ -850,7 +3127,7 @@ impl<'i, R: RuleType> ParserState<'i, R> {
 
         let initial_pos = self.position;
 
-        let result = f(self.checkpoint());
+        let result = f(self.snapshot());
 
         let result_state = match result {
             Ok(mut new_state) => {
@@ -875,6 +3152,649 @@ impl<'i, R: RuleType> ParserState<'i, R> {
         }
     }
 
+    /// Starts a lookbehind transformation provided by `f` from the `Box<ParserState>`, i.e. a
+    /// positive lookahead which starts `width` bytes before the current position. It returns `Ok`
+    /// with the current `Box<ParserState>` if `f` returns an `Ok` ending right at the current
+    /// position, or `Err` with the current `Box<ParserState>` otherwise.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use pest;
+    /// # #[allow(non_camel_case_types)]
+    /// # #[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+    /// enum Rule {}
+    ///
+    /// let input = "ab";
+    /// let mut state: Box<pest::ParserState<'_, Rule>> = pest::ParserState::new(input);
+    /// let result = state.match_string("ab").and_then(|state| {
+    ///     state.lookbehind(1, |state| state.match_string("b"))
+    /// });
+    ///
+    /// assert!(result.is_ok());
+    /// assert_eq!(result.unwrap().position().pos(), 2);
+    /// ```
+    #[inline]
+    pub fn lookbehind<F>(self: Box<Self>, width: usize, f: F) -> ParseResult<Box<Self>>
+    where
+        F: FnOnce(Box<Self>) -> ParseResult<Box<Self>>,
+    {
+        let initial_pos = self.position;
+        let start = match initial_pos.back(width) {
+            Some(start) => start,
+            None => return Err(self),
+        };
+
+        self.lookahead(true, |mut state| {
+            state.position = start;
+
+            f(state).and_then(|state| {
+                if state.position == initial_pos {
+                    Ok(state)
+                } else {
+                    Err(state)
+                }
+            })
+        })
+    }
+
+    /// Matches the input at the current position with another parser, e.g. for a language embedded
+    /// in another one. `parse` is called with the rest of the input, and the pairs it returns are
+    /// grafted at the current position, which moves to the end of the last one. The rules of the
+    /// pairs are converted by `map`, while the pairs which `map` returns `None` for are left out,
+    /// keeping their inner pairs.
+    ///
+    /// This fails without consuming input if `parse` returns an error.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use pest;
+    /// # #[allow(non_camel_case_types)]
+    /// # #[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+    /// enum Rule {
+    ///     template,
+    ///     number
+    /// }
+    ///
+    /// let input = "{{42}}";
+    /// let pairs = pest::state(input, |state| {
+    ///     state.rule(Rule::template, |state| {
+    ///         state
+    ///             .match_string("{{")
+    ///             .and_then(|state| {
+    ///                 state.nested(
+    ///                     // Another parser, with rules of another type
+    ///                     |input| pest::state(input, |s| s.rule("digits", |s| s.match_string("42"))),
+    ///                     |_| Some(Rule::number),
+    ///                 )
+    ///             })
+    ///             .and_then(|state| state.match_string("}}"))
+    ///     })
+    /// }).unwrap();
+    ///
+    /// assert_eq!(format!("{}", pairs), "[template(0, 6, [number(2, 4)])]");
+    /// ```
+    pub fn nested<S, P, F>(mut self: Box<Self>, parse: P, mut map: F) -> ParseResult<Box<Self>>
+    where
+        S: RuleType,
+        P: FnOnce(&'i str) -> Result<Pairs<'i, S>, Error<S>>,
+        F: FnMut(S) -> Option<R>,
+    {
+        self.expand_compact();
+        let start = self.position.pos();
+        let input = self.position.input();
+
+        let pairs = match parse(&input[start..]) {
+            Ok(pairs) => pairs,
+            Err(_) => return Err(self),
+        };
+        let end = start + pairs::end_pos(&pairs).unwrap_or(0);
+
+        if self.lookahead == Lookahead::None && self.atomicity != Atomicity::Atomic {
+            // The indices of the grafted start tokens, or `None` for left out pairs.
+            let mut starts = vec![];
+
+            for token in pairs.tokens() {
+                match token {
+                    Token::Start { rule, pos } => match map(rule) {
+                        Some(rule) => {
+                            starts.push(Some((self.queue.len(), rule)));
+                            self.queue.push(QueueableToken::Start {
+                                end_token_index: 0,
+                                input_pos: start + pos.pos(),
+                            });
+                        }
+                        None => starts.push(None),
+                    },
+                    Token::End { pos, .. } => {
+                        if let Some((index, rule)) = starts.pop().flatten() {
+                            let new_index = self.queue.len();
+                            if let QueueableToken::Start {
+                                ref mut end_token_index,
+                                ..
+                            } = self.queue[index]
+                            {
+                                *end_token_index = new_index;
+                            }
+
+                            self.queue.push(QueueableToken::End {
+                                start_token_index: index,
+                                rule,
+                                input_pos: start + pos.pos(),
+                            });
+                        }
+                    }
+                }
+            }
+        }
+
+        // `end` comes from pairs of the rest of the input.
+        self.position = Position::new(input, end).unwrap();
+        Ok(self)
+    }
+
+    /// Matches the input with the parser bound to the `@extern` rule `rule` by [`Parser::bind`] on
+    /// the current thread, grafting its pairs like [`nested`].
+    ///
+    /// [`Parser::bind`]: trait.Parser.html#method.bind
+    /// [`nested`]: #method.nested
+    ///
+    /// # Panics
+    ///
+    /// Panics if no parser is bound to `rule`.
+    #[cfg(feature = "std")]
+    pub fn external(self: Box<Self>, rule: R) -> ParseResult<Box<Self>>
+    where
+        R: 'static,
+    {
+        match external::lookup(rule) {
+            Some(f) => f(self),
+            None => panic!("no parser is bound to the extern rule {:?}", rule),
+        }
+    }
+
+    /// Starts a group labeled `label` with the transformations provided by `f`, which produces no
+    /// pair but gives context to errors: when the furthest attempt of a failed parse is made within
+    /// the group, the error gets a `while parsing <label>` label pointing from the start of the
+    /// innermost such group to the attempt.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use pest;
+    /// # #[allow(non_camel_case_types)]
+    /// # #[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+    /// enum Rule {
+    ///     arg
+    /// }
+    ///
+    /// let input = "f(a,)";
+    /// let error = pest::state(input, |state| {
+    ///     state.match_string("f(").and_then(|state| {
+    ///         state
+    ///             .labeled("argument list", |state| {
+    ///                 state.rule(Rule::arg, |s| s.match_string("a")).and_then(|state| {
+    ///                     state.repeat(|state| {
+    ///                         state.sequence(|state| {
+    ///                             state
+    ///                                 .match_string(",")
+    ///                                 .and_then(|state| state.rule(Rule::arg, |s| s.match_string("a")))
+    ///                         })
+    ///                     })
+    ///                 })
+    ///             })
+    ///             .and_then(|state| state.match_string(")"))
+    ///     })
+    /// })
+    /// .unwrap_err();
+    ///
+    /// assert_eq!(error.labels()[0].message(), "while parsing argument list");
+    /// assert_eq!(error.labels()[0].location(), (2, 4));
+    /// ```
+    #[inline]
+    pub fn labeled<F>(self: Box<Self>, label: &str, f: F) -> ParseResult<Box<Self>>
+    where
+        F: FnOnce(Box<Self>) -> ParseResult<Box<Self>>,
+    {
+        let start = self.position.pos();
+        let attempt_pos = self.attempt_pos;
+        let attempts = self.attempts_at(attempt_pos);
+
+        match f(self) {
+            Ok(state) => Ok(state.label_attempts(label, start, attempt_pos, attempts)),
+            Err(state) => Err(state.label_attempts(label, start, attempt_pos, attempts)),
+        }
+    }
+
+    // Remembers `label` if the furthest attempt was made after `start`, since the group was entered
+    // with `attempts` attempts at `attempt_pos`. Inner groups are labeled first and take precedence.
+    fn label_attempts(
+        mut self: Box<Self>,
+        label: &str,
+        start: usize,
+        attempt_pos: usize,
+        attempts: usize,
+    ) -> Box<Self> {
+        let reached = self.attempt_pos > attempt_pos
+            || (self.attempt_pos == attempt_pos && self.attempts_at(attempt_pos) > attempts);
+        let labeled = match self.failed_label {
+            Some((inner_start, pos, _)) => pos == self.attempt_pos && inner_start >= start,
+            None => false,
+        };
+
+        if reached && !labeled && self.attempt_pos >= start {
+            self.failed_label = Some((start, self.attempt_pos, label.to_owned()));
+        }
+
+        self
+    }
+
+    /// Sets the context of the parse, which [`predicate`] hands to semantic predicates, e.g. the
+    /// type names declared so far by a C program. The context is `&()` by default.
+    ///
+    /// [`predicate`]: #method.predicate
+    #[inline]
+    pub fn with_context(mut self: Box<Self>, context: &'i dyn Any) -> Box<Self> {
+        self.context = AssertUnwindSafe(context);
+        self
+    }
+
+    /// Returns the context of the parse, as set by [`with_context`].
+    ///
+    /// [`with_context`]: #method.with_context
+    #[inline]
+    pub fn context(&self) -> &'i dyn Any {
+        self.context.0
+    }
+
+    /// Gives the parse the user data `data`, e.g. a symbol table or feature flags, which the
+    /// parse-time callbacks can then read and change with [`user_data`] and [`user_data_mut`].
+    /// Changes are not undone on backtracking. This replaces the previous user data, if any.
+    ///
+    /// [`user_data`]: #method.user_data
+    /// [`user_data_mut`]: #method.user_data_mut
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use pest;
+    /// let input = "a=1 b=2";
+    /// let result = pest::state::<(), _>(input, |state| {
+    ///     let state = state.with_user_data(Vec::<char>::new());
+    ///     state.repeat(|state| {
+    ///         state
+    ///             .match_char_by(|c| c.is_ascii_lowercase())
+    ///             .and_then(|mut state| {
+    ///                 let name = input[..state.position().pos()].chars().last().unwrap();
+    ///                 state.user_data_mut::<Vec<char>>().unwrap().push(name);
+    ///                 state.match_string("=").and_then(|state| state.skip(1))
+    ///             })
+    ///             .and_then(|state| state.optional(|state| state.match_string(" ")))
+    ///     })
+    ///     .map(|state| {
+    ///         assert_eq!(state.user_data::<Vec<char>>().unwrap(), &['a', 'b']);
+    ///         state
+    ///     })
+    /// });
+    ///
+    /// assert!(result.is_ok());
+    /// ```
+    #[inline]
+    pub fn with_user_data<T: Any>(mut self: Box<Self>, data: T) -> Box<Self> {
+        *self.user_data = Some(Box::new(data));
+        self
+    }
+
+    /// Returns the user data of the parse, set by [`with_user_data`], or `None` if there is none
+    /// or it is not a `T`.
+    ///
+    /// [`with_user_data`]: #method.with_user_data
+    #[inline]
+    pub fn user_data<T: Any>(&self) -> Option<&T> {
+        self.user_data.as_ref()?.downcast_ref()
+    }
+
+    /// Returns the user data of the parse mutably, set by [`with_user_data`], or `None` if there
+    /// is none or it is not a `T`.
+    ///
+    /// [`with_user_data`]: #method.with_user_data
+    #[inline]
+    pub fn user_data_mut<T: Any>(&mut self) -> Option<&mut T> {
+        self.user_data.as_mut()?.downcast_mut()
+    }
+
+    /// Takes the user data out of the parse, set by [`with_user_data`], or returns `None` if there
+    /// is none or it is not a `T`, in which case it is left as it was.
+    ///
+    /// [`with_user_data`]: #method.with_user_data
+    #[inline]
+    pub fn take_user_data<T: Any>(&mut self) -> Option<T> {
+        match self.user_data.take()?.downcast() {
+            Ok(data) => Some(*data),
+            Err(data) => {
+                *self.user_data = Some(data);
+                None
+            }
+        }
+    }
+
+    /// Applies the `options` to the parse, setting its limits and attempt tracking if they do, and
+    /// starting to memoize the matches of rules if they do. See [`ParseOptions`].
+    ///
+    /// [`ParseOptions`]: struct.ParseOptions.html
+    #[inline]
+    pub fn with_options(mut self: Box<Self>, options: &ParseOptions<R>) -> Box<Self> {
+        if let Some(limit) = options.call_limit {
+            self.call_tracker.call_limit = Some(limit.get());
+        }
+        if let Some(max_depth) = options.max_depth {
+            self.call_tracker.max_depth = Some(max_depth);
+        }
+        #[cfg(feature = "std")]
+        if let Some(timeout) = options.timeout {
+            // A deadline too far in the future to be represented is never reached.
+            self.call_tracker.deadline = Instant::now()
+                .checked_add(timeout)
+                .map(|deadline| (deadline, timeout));
+        }
+        self.call_tracker.set_checkpoint();
+        if let Some(tracking) = options.tracking {
+            self.tracking = tracking;
+        }
+        if let Some(bias) = options.error_bias {
+            self.error_bias = bias;
+        }
+        if options.explain {
+            self = self.explained();
+        }
+        if options.memoize {
+            self.modes().memo = Some(Memo {
+                options: options.clone(),
+                matches: BTreeMap::new(),
+                pending: Vec::new(),
+                size: 0,
+            });
+        } else if let Some(modes) = &mut self.modes {
+            modes.memo = None;
+        }
+        self
+    }
+
+    /// Returns whether the matches of rules are memoized, as set by [`with_options`].
+    ///
+    /// [`with_options`]: #method.with_options
+    #[inline]
+    pub fn memoizing(&self) -> bool {
+        self.modes
+            .as_ref()
+            .map_or(false, |modes| modes.memo.is_some())
+    }
+
+    /// Sets the dialect of the grammar being parsed, which [`in_dialect`] matches. Grammars name
+    /// their dialects in `@dialect` annotations.
+    ///
+    /// [`in_dialect`]: #method.in_dialect
+    #[inline]
+    pub fn with_dialect(mut self: Box<Self>, dialect: &'i str) -> Box<Self> {
+        self.dialect = Some(dialect);
+        self
+    }
+
+    /// Returns the dialect of the grammar being parsed, as set by [`with_dialect`], or `None` if
+    /// there is none.
+    ///
+    /// [`with_dialect`]: #method.with_dialect
+    #[inline]
+    pub fn dialect(&self) -> Option<&'i str> {
+        self.dialect
+    }
+
+    /// Evaluates the result of closure `f` when parsing one of `dialects`, set by
+    /// [`with_dialect`], and of closure `otherwise` otherwise, including when there is no dialect.
+    /// This is how the definitions of a rule for the dialects of its `@dialect` annotations are
+    /// matched.
+    ///
+    /// [`with_dialect`]: #method.with_dialect
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use pest::{ParseResult, ParserState};
+    /// fn number(state: Box<ParserState<'_, ()>>) -> ParseResult<Box<ParserState<'_, ()>>> {
+    ///     state.in_dialect(
+    ///         &["lenient"],
+    ///         |state| state.repeat(|state| state.match_range('0'..'9').or_else(|s| s.match_string("_"))),
+    ///         |state| state.repeat(|state| state.match_range('0'..'9')),
+    ///     )
+    /// }
+    ///
+    /// let state = pest::ParserState::new("1_000").with_dialect("lenient");
+    /// assert_eq!(number(state).unwrap().position().pos(), 5);
+    ///
+    /// let state = pest::ParserState::new("1_000");
+    /// assert_eq!(number(state).unwrap().position().pos(), 1);
+    /// ```
+    #[inline]
+    pub fn in_dialect<F, G>(
+        self: Box<Self>,
+        dialects: &[&str],
+        f: F,
+        otherwise: G,
+    ) -> ParseResult<Box<Self>>
+    where
+        F: FnOnce(Box<Self>) -> ParseResult<Box<Self>>,
+        G: FnOnce(Box<Self>) -> ParseResult<Box<Self>>,
+    {
+        match self.dialect {
+            Some(dialect) if dialects.contains(&dialect) => f(self),
+            _ => otherwise(self),
+        }
+    }
+
+    /// Matches the left-recursive rule `name` at the current position with closure `f` by growing
+    /// a seed: `f` is first evaluated with the recursive calls of `name` at this position failing,
+    /// then again with them matching the previous result of `f`, for as long as it matches more of
+    /// the input. This is how the rules of parsers derived with `#[left_recursion = true]` can
+    /// call themselves first, e.g. `sum = { sum ~ "+" ~ "1" | "1" }`, which associates to the
+    /// left.
+    ///
+    /// The recursive calls replay the tokens and repairs of the previous result, but not its
+    /// changes to the stack, so left-recursive rules should not `PUSH` or `POP`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use pest::{ParseResult, ParserState};
+    /// # #[allow(non_camel_case_types)]
+    /// # #[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+    /// enum Rule {
+    ///     sum
+    /// }
+    ///
+    /// // sum = { sum ~ "+" ~ "1" | "1" }
+    /// fn sum(state: Box<ParserState<'_, Rule>>) -> ParseResult<Box<ParserState<'_, Rule>>> {
+    ///     state.left_recursive("sum", |state| {
+    ///         state.rule(Rule::sum, |state| {
+    ///             state
+    ///                 .sequence(|state| {
+    ///                     sum(state)
+    ///                         .and_then(|state| state.match_string("+"))
+    ///                         .and_then(|state| state.match_string("1"))
+    ///                 })
+    ///                 .or_else(|state| state.match_string("1"))
+    ///         })
+    ///     })
+    /// }
+    ///
+    /// let pairs = pest::state("1+1+1", sum).unwrap();
+    /// let spans: Vec<_> = pairs.flatten().map(|pair| pair.as_str()).collect();
+    ///
+    /// assert_eq!(spans, ["1+1+1", "1+1", "1"]);
+    /// ```
+    pub fn left_recursive<F>(
+        mut self: Box<Self>,
+        name: &'static str,
+        f: F,
+    ) -> ParseResult<Box<Self>>
+    where
+        F: Fn(Box<Self>) -> ParseResult<Box<Self>>,
+    {
+        // Seeds are copied out of the regular queue and replayed into it.
+        self.expand_compact();
+        let start = self.position;
+        let index = self.queue.len();
+        let repairs = self.repairs_len();
+
+        if let Some(seed) = self
+            .seeds
+            .iter()
+            .rposition(|seed| seed.name == name && seed.pos == start.pos())
+        {
+            return self.replay_seed(seed);
+        }
+
+        self.seeds.push(Seed {
+            name,
+            pos: start.pos(),
+            result: None,
+        });
+        let seed = self.seeds.len() - 1;
+
+        let mut state = loop {
+            let mut state = match f(self) {
+                Ok(state)
+                    if Some(state.position.pos())
+                        > state.seeds[seed]
+                            .result
+                            .as_ref()
+                            .map(|result| result.end.pos()) =>
+                {
+                    state
+                }
+                Ok(state) | Err(state) => break state,
+            };
+
+            state.seeds[seed].result = Some(state.copy_match(index, repairs));
+            self = state.backtrack(start, index, repairs);
+        };
+
+        state = state.backtrack(start, index, repairs);
+        let result = state.replay_seed(seed);
+
+        match result {
+            Ok(mut state) => {
+                state.seeds.truncate(seed);
+                Ok(state)
+            }
+            Err(mut state) => {
+                state.seeds.truncate(seed);
+                Err(state)
+            }
+        }
+    }
+
+    // Matches the current result of the seed at index `seed` from the current position.
+    fn replay_seed(mut self: Box<Self>, seed: usize) -> ParseResult<Box<Self>> {
+        let ParserState {
+            seeds,
+            queue,
+            modes,
+            position,
+            ..
+        } = &mut *self;
+
+        match seeds[seed].result {
+            Some(ref result) => {
+                let repairs = modes.as_mut().map(|modes| &mut modes.repairs);
+                replay(result, queue, repairs, position);
+                Ok(self)
+            }
+            None => Err(self),
+        }
+    }
+
+    // Moves back to `start`, where the queue had `index` tokens and `repairs` repairs.
+    fn backtrack(
+        mut self: Box<Self>,
+        start: Position<'i>,
+        index: usize,
+        repairs: usize,
+    ) -> Box<Self> {
+        self.position = start;
+        self.queue.truncate(index);
+        if let Some(modes) = &mut self.modes {
+            modes.repairs.truncate(repairs);
+        }
+        self
+    }
+
+    // The match made since the queue had `index` tokens and `repairs` repairs, up to the current
+    // position.
+    fn copy_match(&self, index: usize, repairs: usize) -> RuleMatch<'i, R> {
+        RuleMatch {
+            end: self.position,
+            tokens: self.queue[index..]
+                .iter()
+                .cloned()
+                .map(|token| rebase(token, index, 0))
+                .collect(),
+            repairs: self
+                .modes
+                .as_ref()
+                .map_or_else(Vec::new, |modes| modes.repairs[repairs..].to_vec()),
+        }
+    }
+
+    /// Evaluates the result of closure `f`, then calls `predicate` with the input it matched and
+    /// the context of the parse, set by [`with_context`]. Returns `Ok(Box<ParserState>)` if both
+    /// succeed, or `Err(Box<ParserState>)` at the position before `f` otherwise. This is the
+    /// `#{name} e` semantic predicate of grammars, for the context-sensitive parts of a language.
+    ///
+    /// [`with_context`]: #method.with_context
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use pest;
+    /// # #[allow(non_camel_case_types)]
+    /// # #[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+    /// enum Rule {}
+    ///
+    /// let type_names = vec!["size_t"];
+    /// let is_type_name = |text: &str, context: &dyn std::any::Any| {
+    ///     context.downcast_ref::<Vec<&str>>().unwrap().contains(&text)
+    /// };
+    ///
+    /// let state: Box<pest::ParserState<'_, Rule>> = pest::ParserState::new("size_t n");
+    /// let state = state.with_context(&type_names);
+    /// let result = state.predicate(|state| state.match_string("size_t"), is_type_name);
+    /// assert_eq!(result.unwrap().position().pos(), 6);
+    ///
+    /// let state: Box<pest::ParserState<'_, Rule>> = pest::ParserState::new("count n");
+    /// let state = state.with_context(&type_names);
+    /// let result = state.predicate(|state| state.match_string("count"), is_type_name);
+    /// assert_eq!(result.unwrap_err().position().pos(), 0);
+    /// ```
+    #[inline]
+    pub fn predicate<F, P>(self: Box<Self>, f: F, predicate: P) -> ParseResult<Box<Self>>
+    where
+        F: FnOnce(Box<Self>) -> ParseResult<Box<Self>>,
+        P: FnOnce(&'i str, &'i dyn Any) -> bool,
+    {
+        self.sequence(|state| {
+            let start = state.position;
+
+            f(state).and_then(|state| {
+                if predicate(start.span(&state.position).as_str(), state.context.0) {
+                    Ok(state)
+                } else {
+                    Err(state)
+                }
+            })
+        })
+    }
+
     /// Transformation which stops `Token`s from being generated according to `is_atomic`.
     ///
     /// # Examples
@@ -997,6 +3917,11 @@ impl<'i, R: RuleType> ParserState<'i, R> {
     /// Pops the top of the stack and attempts to match the string. Returns `Ok(Box<ParserState>)`
     /// if the string is matched successfully, or `Err(Box<ParserState>)` otherwise.
     ///
+    /// When the furthest error of a parse is a failed pop, the resulting [`Error`] carries a label
+    /// pointing at the span that was pushed, i.e. "unclosed delimiter opened here".
+    ///
+    /// [`Error`]: error/struct.Error.html
+    ///
     /// # Examples
     ///
     /// ```
@@ -1015,12 +3940,12 @@ impl<'i, R: RuleType> ParserState<'i, R> {
     /// ```
     #[inline]
     pub fn stack_pop(mut self: Box<Self>) -> ParseResult<Box<Self>> {
-        let string = self
-            .stack
-            .pop()
-            .expect("pop was called on empty stack")
-            .as_str();
-        self.match_string(string)
+        let span = self.stack.pop().expect("pop was called on empty stack");
+        self.match_string(span.as_str()).map_err(|mut state| {
+            let pos = state.position.pos();
+            state.record_failed_pop(pos, span);
+            state
+        })
     }
 
     /// Matches part of the state of the stack.
@@ -1129,6 +4054,7 @@ impl<'i, R: RuleType> ParserState<'i, R> {
         while let Some(span) = self.stack.pop() {
             result = position.match_string(span.as_str());
             if !result {
+                self.record_failed_pop(position.pos(), span);
                 break;
             }
         }
@@ -1141,6 +4067,17 @@ impl<'i, R: RuleType> ParserState<'i, R> {
         }
     }
 
+    // Records `span`, popped off the stack, as the one which failed to match at `pos` if it is the
+    // furthest so far. It is only called once a `POP` failed, and out of line, so that `stack_pop`
+    // and `stack_match_pop` match as they would without it.
+    #[cold]
+    #[inline(never)]
+    fn record_failed_pop(&mut self, pos: usize, span: Span<'i>) {
+        if self.failed_pop.map_or(true, |(other, _)| pos >= other) {
+            self.failed_pop = Some((pos, span));
+        }
+    }
+
     /// Drops the top of the stack. Returns `Ok(Box<ParserState>)` if there was a value to drop, or
     /// `Err(Box<ParserState>)` otherwise.
     ///
@@ -1168,6 +4105,180 @@ impl<'i, R: RuleType> ParserState<'i, R> {
         }
     }
 
+    /// Returns the spans on the stack, from the bottom to the top, like `PEEK_ALL` matches them in
+    /// reverse, e.g. for custom rules which compare indentations rather than match them.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use pest;
+    /// # #[allow(non_camel_case_types)]
+    /// # #[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+    /// enum Rule {}
+    ///
+    /// let input = "ab";
+    /// let state: Box<pest::ParserState<'_, Rule>> = pest::ParserState::new(input);
+    /// let state = state
+    ///     .stack_push(|state| state.match_string("a"))
+    ///     .and_then(|state| state.stack_push(|state| state.match_string("b")))
+    ///     .unwrap();
+    ///
+    /// let spans: Vec<_> = state.stack_spans().iter().map(|span| span.as_str()).collect();
+    /// assert_eq!(spans, ["a", "b"]);
+    /// ```
+    #[inline]
+    pub fn stack_spans(&self) -> &[Span<'i>] {
+        self.stack.as_slice()
+    }
+
+    /// Sets the checkpoint `name` to the current content of the stack, replacing the previous one
+    /// of the same name, so that [`restore_to`] can bring the stack back to it, e.g. to the
+    /// indentations of an enclosing block. Like pushes and pops, setting a checkpoint is undone
+    /// when the parse backtracks over it. Always returns `Ok(Box<ParserState>)`.
+    ///
+    /// [`restore_to`]: #method.restore_to
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use pest;
+    /// # #[allow(non_camel_case_types)]
+    /// # #[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+    /// enum Rule {}
+    ///
+    /// let input = "  a\n    b\n  c";
+    /// let state: Box<pest::ParserState<'_, Rule>> = pest::ParserState::new(input);
+    /// let state = state
+    ///     .stack_push(|state| state.match_string("  "))
+    ///     .and_then(|state| state.checkpoint("block"))
+    ///     .and_then(|state| state.match_string("a\n"))
+    ///     // The nested block is indented further.
+    ///     .and_then(|state| state.stack_push(|state| state.match_string("    ")))
+    ///     .and_then(|state| state.match_string("b"))
+    ///     .and_then(|state| state.restore_to("block"))
+    ///     .and_then(|state| state.match_string("\n"))
+    ///     .and_then(|state| state.stack_peek())
+    ///     .unwrap();
+    ///
+    /// assert_eq!(state.stack_spans().len(), 1);
+    /// assert_eq!(state.position().pos(), input.len() - 1);
+    /// ```
+    #[inline]
+    pub fn checkpoint(mut self: Box<Self>, name: &str) -> ParseResult<Box<Self>> {
+        self.stack.checkpoint(name);
+        Ok(self)
+    }
+
+    /// Pops and pushes spans until the stack is back to its content at the checkpoint `name`, set
+    /// by [`checkpoint`]. Returns `Ok(Box<ParserState>)` if there is such a checkpoint, or
+    /// `Err(Box<ParserState>)` otherwise.
+    ///
+    /// [`checkpoint`]: #method.checkpoint
+    #[inline]
+    pub fn restore_to(mut self: Box<Self>, name: &str) -> ParseResult<Box<Self>> {
+        if self.stack.restore_to(name) {
+            Ok(self)
+        } else {
+            Err(self)
+        }
+    }
+
+    /// Matches the indentation at the current position, i.e. its spaces and tabs, if it is deeper
+    /// than the indentation on top of the stack, and pushes it. This is the `PUSH_INDENT` built-in
+    /// rule, which opens an indented block. Returns `Ok(Box<ParserState>)` if the indentation is
+    /// deeper, or `Err(Box<ParserState>)` otherwise.
+    ///
+    /// An indentation is deeper than another one if it starts with it and is longer, so that tabs
+    /// and spaces have to be mixed the same way throughout a block. An empty stack stands for no
+    /// indentation.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use pest;
+    /// # #[allow(non_camel_case_types)]
+    /// # #[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+    /// enum Rule {}
+    ///
+    /// let input = "  a\n    b\n  c\nd";
+    /// let state: Box<pest::ParserState<'_, Rule>> = pest::ParserState::new(input);
+    /// let state = state
+    ///     .stack_push_indent()
+    ///     .and_then(|state| state.match_string("a\n"))
+    ///     .and_then(|state| state.stack_push_indent())
+    ///     .and_then(|state| state.match_string("b\n"))
+    ///     .and_then(|state| state.stack_dedent())
+    ///     .and_then(|state| state.stack_match_indent())
+    ///     .and_then(|state| state.match_string("c\n"))
+    ///     .and_then(|state| state.stack_dedent())
+    ///     .unwrap();
+    ///
+    /// assert!(state.stack_spans().is_empty());
+    /// assert_eq!(state.position().pos(), input.len() - 1);
+    /// ```
+    #[inline]
+    pub fn stack_push_indent(mut self: Box<Self>) -> ParseResult<Box<Self>> {
+        let indentation = self.indentation();
+        let top = self.stack.peek().map_or("", |span| span.as_str());
+
+        if indentation.as_str().len() > top.len() && indentation.as_str().starts_with(top) {
+            self.position = indentation.end_pos();
+            self.stack.push(indentation);
+            Ok(self)
+        } else {
+            Err(self)
+        }
+    }
+
+    /// Matches the indentation at the current position if it is the indentation on top of the
+    /// stack, not followed by more spaces or tabs. This is the `MATCH_INDENT` built-in rule, which
+    /// starts the lines of an indented block after its first one. Returns `Ok(Box<ParserState>)`
+    /// if the indentation is the same, or `Err(Box<ParserState>)` otherwise.
+    #[inline]
+    pub fn stack_match_indent(mut self: Box<Self>) -> ParseResult<Box<Self>> {
+        let indentation = self.indentation();
+        let top = self.stack.peek().map_or("", |span| span.as_str());
+
+        if indentation.as_str() == top {
+            self.position = indentation.end_pos();
+            Ok(self)
+        } else {
+            Err(self)
+        }
+    }
+
+    /// Drops the indentation on top of the stack if the indentation at the current position is
+    /// shallower, without matching anything. This is the `DEDENT` built-in rule, which closes an
+    /// indented block at the start of the line after it, or at the end of the input. Returns
+    /// `Ok(Box<ParserState>)` if the indentation is shallower, or `Err(Box<ParserState>)`
+    /// otherwise, as well as for an empty stack.
+    #[inline]
+    pub fn stack_dedent(mut self: Box<Self>) -> ParseResult<Box<Self>> {
+        let indentation = self.indentation();
+        let shallower = match self.stack.peek() {
+            Some(top) => {
+                indentation.as_str().len() < top.as_str().len()
+                    && top.as_str().starts_with(indentation.as_str())
+            }
+            None => false,
+        };
+
+        if shallower {
+            self.stack.pop();
+            Ok(self)
+        } else {
+            Err(self)
+        }
+    }
+
+    // The spaces and tabs at the current position.
+    fn indentation(&self) -> Span<'i> {
+        let mut end = self.position;
+        while end.match_char_by(|c| c == ' ' || c == '\t') {}
+
+        self.position.span(&end)
+    }
+
     /// Restores the original state of the `ParserState` when `f` returns an `Err`. Currently,
     /// this method only restores the stack.
     ///
@@ -1196,28 +4307,28 @@ impl<'i, R: RuleType> ParserState<'i, R> {
     where
         F: FnOnce(Box<Self>) -> ParseResult<Box<Self>>,
     {
-        match f(self.checkpoint()) {
-            Ok(state) => Ok(state.checkpoint_ok()),
+        match f(self.snapshot()) {
+            Ok(state) => Ok(state.snapshot_ok()),
             Err(state) => Err(state.restore()),
         }
     }
 
-    // Mark the current state as a checkpoint and return the `Box`.
+    // Mark the current state as a snapshot and return the `Box`.
     #[inline]
-    pub(crate) fn checkpoint(mut self: Box<Self>) -> Box<Self> {
+    pub(crate) fn snapshot(mut self: Box<Self>) -> Box<Self> {
         self.stack.snapshot();
         self
     }
 
-    // The checkpoint was cleared successfully
+    // The snapshot was cleared successfully
     // so remove it without touching other stack state.
     #[inline]
-    pub(crate) fn checkpoint_ok(mut self: Box<Self>) -> Box<Self> {
+    pub(crate) fn snapshot_ok(mut self: Box<Self>) -> Box<Self> {
         self.stack.clear_snapshot();
         self
     }
 
-    // Restore the current state to the most recent checkpoint.
+    // Restore the current state to the most recent snapshot.
     #[inline]
     pub(crate) fn restore(mut self: Box<Self>) -> Box<Self> {
         self.stack.restore();
@@ -1251,6 +4362,7 @@ fn normalize_index(i: i32, len: usize) -> Option<usize> {
 #[cfg(test)]
 mod test {
     use super::*;
+    use core::cell::Cell;
 
     #[test]
     fn normalize_index_pos() {
@@ -1265,4 +4377,668 @@ mod test {
         assert_eq!(normalize_index(-5, 5), Some(0));
         assert_eq!(normalize_index(-6, 3), None);
     }
+
+    #[test]
+    fn compact() {
+        fn parse(state: Box<ParserState<'_, u8>>) -> ParseResult<Box<ParserState<'_, u8>>> {
+            state.rule(0, |state| {
+                state
+                    .sequence(|state| {
+                        state
+                            .rule(1, |state| state.match_string("a"))
+                            .and_then(|state| state.match_string("c"))
+                    })
+                    .or_else(|state| {
+                        state
+                            .rule(2, |state| state.match_string("a"))
+                            .and_then(|state| {
+                                state
+                                    .lookahead(true, |state| state.rule(3, |s| s.match_string("b")))
+                            })
+                            .and_then(|state| {
+                                state.atomic(Atomicity::Atomic, |state| {
+                                    state.rule(4, |state| state.match_string("b"))
+                                })
+                            })
+                            .and_then(|state| state.rule(5, |state| state.match_string("c")))
+                    })
+            })
+        }
+
+        let pairs = state("abc", parse).unwrap();
+        let compact = state_compact("abc", parse).unwrap();
+        assert_eq!(
+            compact.to_pairs().tokens().collect::<Vec<_>>(),
+            pairs.tokens().collect::<Vec<_>>()
+        );
+        assert_eq!(compact.len(), 6);
+
+        // Growing left-recursive seeds moves the compact tokens into the regular queue.
+        let compact = state_compact("abc", |state| {
+            state.rule(0, |state| {
+                state
+                    .rule(1, |state| state.match_string("a"))
+                    .and_then(|state| {
+                        state.left_recursive("b", |state| state.rule(3, |s| s.match_string("b")))
+                    })
+                    .and_then(|state| state.rule(2, |state| state.match_string("c")))
+            })
+        })
+        .unwrap();
+        assert_eq!(
+            format!("{}", compact.to_pairs()),
+            "[0(0, 3, [1(0, 1), 3(1, 2), 2(2, 3)])]"
+        );
+        assert!(state_compact("abd", parse).is_err());
+    }
+
+    #[test]
+    fn stats() {
+        let (result, stats) = state_with_stats::<(), _>("aa", |state| {
+            state.stack_push(|state| state.match_string("a"))
+        });
+
+        assert!(result.is_ok());
+        assert_eq!(stats.queue_bytes, 0);
+        assert!(stats.stack_bytes > 0);
+        assert_eq!(stats.state_bytes, size_of::<ParserState<'_, ()>>());
+        assert_eq!(
+            stats.total_bytes(),
+            stats.attempts_bytes + stats.stack_bytes + stats.state_bytes
+        );
+    }
+
+    #[test]
+    fn stats_error() {
+        let (result, stats) =
+            state_with_stats::<u8, _>("b", |state| state.rule(1, |state| state.match_string("a")));
+
+        assert!(result.is_err());
+        assert!(stats.attempts_bytes > 0);
+        assert_eq!(stats.stack_bytes, 0);
+    }
+
+    #[test]
+    fn state_reusing() {
+        let mut reuse = ParseReuse::new();
+        let parse = |reuse: &mut ParseReuse<u8>, input| {
+            super::state_reusing(input, reuse, |state| {
+                state.repeat(|state| state.rule(0, |state| state.match_string("a")))
+            })
+            .unwrap()
+        };
+
+        let pairs = parse(&mut reuse, "aaaa");
+        assert!(reuse.queue.is_none());
+        assert!(reuse.recycle(pairs));
+        let capacity = reuse.queue.as_ref().unwrap().capacity();
+        assert!(capacity >= 8);
+
+        let pairs = parse(&mut reuse, "aa");
+        let pair = pairs.clone().next().unwrap();
+        assert_eq!(pairs.clone().count(), 2);
+        assert!(!reuse.recycle(pairs));
+        drop(pair);
+
+        let pairs = parse(&mut reuse, "a");
+        assert!(reuse.recycle(pairs));
+        assert!(reuse.state.as_ref().unwrap().pos_attempts.capacity() > 0);
+
+        let error = super::state_reusing::<u8, _>("b", &mut reuse, |state| {
+            state.rule(1, |state| state.match_string("a"))
+        })
+        .unwrap_err();
+        assert_eq!(error.location, InputLocation::Pos(0));
+        assert!(reuse.queue.as_ref().unwrap().capacity() > 0);
+        assert_eq!(reuse.state.as_ref().unwrap().position.pos(), 0);
+    }
+
+    #[test]
+    fn failed_pop_label() {
+        let input = "(a\n(b)]";
+        let result = state::<u8, _>(input, |state| {
+            state.sequence(|state| {
+                state
+                    .stack_push(|state| state.match_string("("))
+                    .and_then(|state| state.match_string("a\n"))
+                    .and_then(|state| state.stack_push(|state| state.match_string("(")))
+                    .and_then(|state| state.match_string("b"))
+                    .and_then(|state| state.restore_on_err(|state| state.stack_pop()))
+                    .and_then(|state| state.rule(1, |state| state.stack_match_pop()))
+            })
+        });
+        let error = result.unwrap_err();
+
+        assert_eq!(error.labels().len(), 1);
+        assert_eq!(error.labels()[0].location(), (3, 4));
+        assert_eq!(
+            error.labels()[0].message(),
+            "unclosed delimiter opened here"
+        );
+    }
+
+    #[test]
+    fn checkpoint_backtracking() {
+        let state = ParserState::<u8>::new("aab");
+        let state = state
+            .stack_push(|state| state.match_string("a"))
+            .and_then(|state| state.checkpoint("outer"))
+            .unwrap();
+
+        let state = state
+            .restore_on_err(|state| {
+                state.sequence(|state| {
+                    state
+                        .stack_push(|state| state.match_string("a"))
+                        .and_then(|state| state.checkpoint("inner"))
+                        .and_then(|state| state.match_string("c"))
+                })
+            })
+            .unwrap_err();
+        let state = state.restore_to("inner").unwrap_err();
+
+        let state = state
+            .stack_push(|state| state.match_string("a"))
+            .and_then(|state| state.restore_to("outer"))
+            .unwrap();
+        assert_eq!(state.stack_spans().len(), 1);
+        assert_eq!(state.position().pos(), 2);
+    }
+
+    #[test]
+    fn indentation() {
+        let state = ParserState::<u8>::new("\t a\n\t  b\n  c");
+        let state = state.stack_push_indent().unwrap();
+        assert_eq!(state.stack_spans()[0].as_str(), "\t ");
+
+        let state = state.match_string("a\n").unwrap();
+        // The same indentation is not deeper, nor shallower.
+        let state = state.stack_match_indent().unwrap_err();
+        let state = state.stack_dedent().unwrap_err();
+
+        let state = state.stack_push_indent().unwrap();
+        let state = state.match_string("b\n").unwrap();
+        // Spaces instead of a tab are neither deeper nor shallower.
+        let state = state.stack_push_indent().unwrap_err();
+        let state = state.stack_match_indent().unwrap_err();
+        let state = state.stack_dedent().unwrap_err();
+        assert_eq!(state.stack_spans().len(), 2);
+        assert_eq!(state.position().pos(), 9);
+
+        let state = state.skip(3).unwrap();
+        let state = state.stack_dedent().and_then(|state| state.stack_dedent());
+        let state = state.unwrap().stack_dedent().unwrap_err();
+        assert!(state.stack_spans().is_empty());
+    }
+
+    #[test]
+    fn failed_pop_no_label() {
+        let input = "(a)b";
+        let result = state::<u8, _>(input, |state| {
+            state
+                .stack_push(|state| state.match_string("("))
+                .and_then(|state| state.optional(|state| state.stack_pop()))
+                .and_then(|state| state.match_string("a)"))
+                .and_then(|state| state.rule(1, |state| state.match_string("c")))
+        });
+
+        assert!(result.unwrap_err().labels().is_empty());
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn attempt_tracking_max_depth() {
+        let parse = || {
+            state::<u8, _>("ab", |state| {
+                state.rule(0, |state| {
+                    state
+                        .match_string("a")
+                        .and_then(|state| state.rule(1, |state| state.match_string("c")))
+                })
+            })
+            .unwrap_err()
+        };
+
+        let error = with_attempt_tracking(AttemptTracking::MaxDepth(1), parse);
+        assert_eq!(error.location, InputLocation::Pos(1));
+        assert_eq!(
+            error.variant,
+            ErrorVariant::ParsingError {
+                positives: vec![],
+                negatives: vec![],
+            }
+        );
+
+        let error = with_attempt_tracking(AttemptTracking::MaxDepth(2), parse);
+        assert_eq!(
+            error.variant,
+            ErrorVariant::ParsingError {
+                positives: vec![1],
+                negatives: vec![],
+            }
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn attempt_tracking_farthest() {
+        let parse = || {
+            state::<u8, _>("ab", |state| {
+                state.rule(0, |state| {
+                    state.match_string("a").and_then(|state| {
+                        state.rule(10, |state| {
+                            state
+                                .rule(2, |state| state.match_string("c"))
+                                .or_else(|state| state.rule(3, |state| state.match_string("d")))
+                                .or_else(|state| state.rule(2, |state| state.match_string("e")))
+                        })
+                    })
+                })
+            })
+            .unwrap_err()
+        };
+
+        let error = parse();
+        assert_eq!(
+            error.variant,
+            ErrorVariant::ParsingError {
+                positives: vec![10],
+                negatives: vec![],
+            }
+        );
+        assert!(error.rule_stack().is_empty());
+
+        let error = with_attempt_tracking(AttemptTracking::Farthest, parse);
+        assert_eq!(error.location, InputLocation::Pos(1));
+        assert_eq!(
+            error.variant,
+            ErrorVariant::ParsingError {
+                positives: vec![10, 2, 3],
+                negatives: vec![],
+            }
+        );
+        assert_eq!(error.rule_stack(), [0, 10, 2]);
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn error_window() {
+        use crate::error::LineColLocation;
+
+        let parse = || {
+            state::<u8, _>("ab\nab", |state| {
+                state
+                    .rule(0, |state| state.match_string("ab\n"))
+                    .and_then(|state| state.rule(1, |state| state.match_string("c")))
+            })
+            .unwrap_err()
+        };
+
+        // The furthest attempt is before the window.
+        let error = with_error_window(4..5, parse);
+        assert_eq!(error.location, InputLocation::Pos(4));
+        assert_eq!(error.line_col, LineColLocation::Pos((2, 2)));
+        assert_eq!(
+            error.variant,
+            ErrorVariant::ParsingError {
+                positives: vec![],
+                negatives: vec![],
+            }
+        );
+        assert!(error.expected().is_empty());
+
+        let error = with_error_window(0..3, parse);
+        assert_eq!(error.location, InputLocation::Pos(3));
+        assert_eq!(
+            error.variant,
+            ErrorVariant::ParsingError {
+                positives: vec![1],
+                negatives: vec![],
+            }
+        );
+
+        assert_eq!(parse().location, InputLocation::Pos(3));
+    }
+
+    #[test]
+    fn recovery() {
+        // item = { "a" ~ ";" }, recovering at ";"
+        fn item(state: Box<ParserState<'_, u8>>) -> ParseResult<Box<ParserState<'_, u8>>> {
+            state.rule(1, |state| {
+                state.sequence(|state| {
+                    state
+                        .match_string("a")
+                        .and_then(|state| state.match_string(";"))
+                })
+            })
+        }
+        let recovery = Recovery::new(0).with_sync_rule(1).with_boundary(";");
+
+        let (pairs, errors) = state_recoverable("ab;a;ac;", &recovery, |state| {
+            state.repeat(|state| item(state))
+        });
+        assert_eq!(format!("{}", pairs), "[0(0, 3), 1(3, 5), 0(5, 8)]");
+        assert_eq!(errors.len(), 2);
+        // The rules failed at their start, since strings are not tracked.
+        assert_eq!(errors[0].location, InputLocation::Pos(0));
+        assert_eq!(errors[1].location, InputLocation::Pos(5));
+
+        let (pairs, errors) =
+            state_recoverable("ab;a;ac;", &recovery.clone().with_max_errors(1), |state| {
+                state.repeat(|state| item(state))
+            });
+        assert_eq!(format!("{}", pairs), "[0(0, 3), 1(3, 5)]");
+        assert_eq!(errors.len(), 1);
+
+        // Errors whose pairs are backtracked over are left out.
+        let (pairs, errors) = state_recoverable("ab;", &recovery, |state| {
+            state.sequence(|state| item(state).and_then(|state| state.match_string("!")))
+        });
+        assert_eq!(pairs.count(), 0);
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].location, InputLocation::Pos(3));
+    }
+
+    #[test]
+    fn tolerance() {
+        // field = { key ~ "=" ~ "data" }, key = { ("abc" ~ "!") | "abx" | ^"name" }, with key
+        // tolerant
+        fn field(state: Box<ParserState<'_, u8>>) -> ParseResult<Box<ParserState<'_, u8>>> {
+            state.rule(0, |state| {
+                state.sequence(|state| {
+                    state
+                        .rule(1, |state| {
+                            state
+                                .sequence(|state| {
+                                    state
+                                        .match_string("abc")
+                                        .and_then(|state| state.match_string("!"))
+                                })
+                                .or_else(|state| state.match_string("abx"))
+                                .or_else(|state| state.match_insensitive("name"))
+                        })
+                        .and_then(|state| state.match_string("="))
+                        .and_then(|state| state.match_string("data"))
+                })
+            })
+        }
+        let tolerance = Tolerance::new().with_rule(1);
+
+        let (pairs, repairs) = state_tolerant("name=data", &tolerance, field).unwrap();
+        assert_eq!(format!("{}", pairs), "[0(0, 9, [1(0, 4)])]");
+        assert!(repairs.is_empty());
+
+        let (pairs, repairs) = state_tolerant("NME=data", &tolerance, field).unwrap();
+        assert_eq!(format!("{}", pairs), "[0(0, 8, [1(0, 3)])]");
+        assert_eq!(
+            repairs,
+            vec![Repair {
+                literal: "name".to_owned(),
+                start: 0,
+                end: 3,
+                pos: 1,
+                kind: RepairKind::Missing('a'),
+            }]
+        );
+
+        let (pairs, repairs) = state_tolerant("naame=data", &tolerance, field).unwrap();
+        assert_eq!(format!("{}", pairs), "[0(0, 10, [1(0, 5)])]");
+        assert_eq!(
+            (repairs[0].pos, repairs[0].kind),
+            (1, RepairKind::Extra('a'))
+        );
+
+        // Literals outside of tolerant rules are matched strictly.
+        let error = state_tolerant("name=dta", &tolerance, field).unwrap_err();
+        assert_eq!(error, state("name=dta", field).unwrap_err());
+
+        // Repairs backtracked over are left out and do not count: "abc" is repaired in "abx"
+        // before "!" fails.
+        let tolerance = Tolerance::new().with_rule(0).with_max_repairs(1);
+        let (_, repairs) = state_tolerant("abx=dta", &tolerance, field).unwrap();
+        assert_eq!(repairs.len(), 1);
+        assert_eq!(format!("{}", repairs[0]), "missing 'a' in \"data\"");
+        assert!(state_tolerant("nme=dta", &tolerance, field).is_err());
+        // So are short literals.
+        assert!(state_tolerant("name data", &tolerance, field).is_err());
+    }
+
+    #[test]
+    fn expected_literals() {
+        use crate::error::Expected;
+
+        // list = { "(" ~ (number | list)* ~ ")" }, number = { ASCII_DIGIT }
+        fn list(state: Box<ParserState<'_, u8>>) -> ParseResult<Box<ParserState<'_, u8>>> {
+            state.rule(0, |state| {
+                state.sequence(|state| {
+                    state
+                        .match_string("(")
+                        .and_then(|state| {
+                            state.repeat(|state| {
+                                state
+                                    .rule(1, |state| state.match_char_by(|c| c.is_ascii_digit()))
+                                    .or_else(list)
+                            })
+                        })
+                        .and_then(|state| state.match_string(")"))
+                })
+            })
+        }
+
+        // The literals failing before the furthest rule attempt are reported along with it.
+        let error = state("(1x", list).unwrap_err();
+        assert_eq!(error.location, InputLocation::Pos(2));
+        assert_eq!(
+            error.expected(),
+            vec![
+                Expected::Literal("(".to_owned()),
+                Expected::Literal(")".to_owned()),
+                Expected::Rule(0),
+                Expected::Rule(1)
+            ]
+        );
+
+        // Literals failing behind the furthest rule attempt are not.
+        let error = state("(1x", |state| {
+            state.sequence(|state| {
+                state
+                    .match_string("z")
+                    .or_else(|state| state.match_string("("))
+                    .and_then(|state| {
+                        state.rule(1, |state| state.match_char_by(|c| c.is_ascii_digit()))
+                    })
+                    .and_then(|state| state.rule(2, |state| state.match_insensitive("Y")))
+            })
+        })
+        .unwrap_err();
+        assert_eq!(
+            error.expected(),
+            vec![Expected::Insensitive("Y".to_owned()), Expected::Rule(2)]
+        );
+
+        // Nor are literals in atomic rules or negative predicates.
+        let error = state::<u8, _>("x", |state| {
+            state
+                .atomic(Atomicity::Atomic, |state| state.match_string("a"))
+                .or_else(|state| state.lookahead(false, |state| state.match_string("x")))
+        })
+        .unwrap_err();
+        assert!(error.expected().is_empty());
+    }
+
+    // s = { a ~ "!" | a ~ "?" }, a = { "a" ~ b }, b = { "b" }, counting the matches of `b`
+    fn backtracking(options: &ParseOptions<u8>) -> (String, usize) {
+        fn a<'i>(
+            state: Box<ParserState<'i, u8>>,
+            calls: &Cell<usize>,
+        ) -> ParseResult<Box<ParserState<'i, u8>>> {
+            state.rule(0, |state| {
+                state.sequence(|state| {
+                    state.match_string("a").and_then(|state| {
+                        state.rule(1, |state| {
+                            calls.set(calls.get() + 1);
+                            state.match_string("b")
+                        })
+                    })
+                })
+            })
+        }
+
+        let calls = Cell::new(0);
+        let pairs = state("ab?", |state| {
+            state
+                .with_options(options)
+                .sequence(|state| a(state, &calls).and_then(|state| state.match_string("!")))
+                .or_else(|state| a(state, &calls).and_then(|state| state.match_string("?")))
+        })
+        .unwrap();
+
+        (format!("{}", pairs), calls.get())
+    }
+
+    #[test]
+    fn memoization() {
+        let pairs = "[0(0, 2, [1(1, 2)])]".to_owned();
+
+        assert_eq!(backtracking(&ParseOptions::new()), (pairs.clone(), 2));
+        assert_eq!(
+            backtracking(&ParseOptions::new().memoize()),
+            (pairs.clone(), 1)
+        );
+        assert_eq!(
+            backtracking(&ParseOptions::new().with_memoized_rule(1)),
+            (pairs.clone(), 1)
+        );
+        assert_eq!(
+            backtracking(&ParseOptions::new().with_memoized_rule(0)),
+            (pairs.clone(), 1)
+        );
+        assert_eq!(
+            backtracking(&ParseOptions::new().with_memoized_rule(2)),
+            (pairs.clone(), 2)
+        );
+        assert_eq!(
+            backtracking(&ParseOptions::new().memoize().with_memo_limit(0)),
+            (pairs, 2)
+        );
+    }
+
+    #[test]
+    fn memoized_failure() {
+        use crate::error::Expected;
+
+        let options = ParseOptions::new().memoize();
+        let error = state::<u8, _>("x", |state| {
+            state
+                .with_options(&options)
+                .rule(0, |state| state.match_string("a"))
+                .or_else(|state| state.rule(0, |state| state.match_string("b")))
+        })
+        .unwrap_err();
+
+        // The second attempt replays the failure of the first one.
+        assert_eq!(
+            error.expected(),
+            vec![Expected::Literal("a".to_owned()), Expected::Rule(0)]
+        );
+    }
+
+    #[test]
+    fn peeking() {
+        // Matches a string prefixed with its length, e.g. `3:abc`.
+        fn counted(state: Box<ParserState<'_, u8>>) -> ParseResult<Box<ParserState<'_, u8>>> {
+            let digits = state
+                .peek_slice(1)
+                .filter(|digit| digit.parse::<usize>().is_ok());
+            let n = match digits {
+                Some(digit) => digit.parse().unwrap(),
+                None => return Err(state),
+            };
+
+            state.sequence(|state| {
+                state
+                    .skip(1)
+                    .and_then(|state| state.match_string(":"))
+                    .and_then(|state| {
+                        if state.peek_slice(n).is_some() {
+                            state.skip(n)
+                        } else {
+                            Err(state)
+                        }
+                    })
+            })
+        }
+
+        let pairs = state("3:abcé", |state| state.rule(0, counted)).unwrap();
+        assert_eq!(pairs.as_str(), "3:abc");
+        assert!(state::<u8, _>("4:abc", |state| state.rule(0, counted)).is_err());
+
+        let state: Box<ParserState<'_, u8>> = ParserState::new("é");
+        assert_eq!(state.remaining_len(), 2);
+        assert_eq!(state.peek_char(), Some('é'));
+        assert_eq!(state.peek_slice(0), Some(""));
+        assert_eq!(state.peek_slice(1), Some("é"));
+        assert_eq!(state.peek_slice(2), None);
+    }
+
+    #[test]
+    fn error_bias() {
+        // The atomic rule `1` fails at `3`, after its inner rule `2` failed at `5`.
+        let parse = |bias| {
+            state::<u8, _>("ab;c d", |state| {
+                state
+                    .with_options(&ParseOptions::new().with_error_bias(bias))
+                    .sequence(|state| {
+                        state
+                            .rule(0, |state| state.match_string("ab"))
+                            .and_then(|state| state.match_string(";"))
+                            .and_then(|state| {
+                                state.rule(1, |state| {
+                                    state.atomic(Atomicity::Atomic, |state| {
+                                        state.match_string("c ").and_then(|state| {
+                                            state.rule(2, |state| state.match_string("x"))
+                                        })
+                                    })
+                                })
+                            })
+                    })
+            })
+            .unwrap_err()
+        };
+
+        let error = parse(ErrorBias::Attempt);
+        assert_eq!(error.location, InputLocation::Pos(3));
+        assert_eq!(
+            error.variant,
+            ErrorVariant::ParsingError {
+                positives: vec![1],
+                negatives: vec![],
+            }
+        );
+        assert_eq!(
+            error.failure_candidates(),
+            [
+                (ErrorBias::Attempt, 3),
+                (ErrorBias::Farthest, 5),
+                (ErrorBias::LongestMatch, 2)
+            ]
+        );
+
+        let error = parse(ErrorBias::Farthest);
+        assert_eq!(error.location, InputLocation::Pos(5));
+        assert_eq!(
+            error.variant,
+            ErrorVariant::ParsingError {
+                positives: vec![],
+                negatives: vec![],
+            }
+        );
+
+        let error = parse(ErrorBias::LongestMatch);
+        assert_eq!(error.location, InputLocation::Pos(2));
+        assert_eq!(
+            error.failure_candidates(),
+            parse(ErrorBias::Attempt).failure_candidates()
+        );
+    }
 }
@@ -170,19 +170,18 @@ impl<'i> Position<'i> {
         &self.input[self.find_line_start()..self.find_line_end()]
     }
 
+    pub(crate) fn input(&self) -> &'i str {
+        self.input
+    }
+
     pub(crate) fn find_line_start(&self) -> usize {
         if self.input.is_empty() {
             return 0;
         };
-        // Position's pos is always a UTF-8 border.
-        let start = self
-            .input
-            .char_indices()
-            .rev()
-            .skip_while(|&(i, _)| i >= self.pos)
-            .find(|&(_, c)| c == '\n');
-        match start {
-            Some((i, _)) => i + 1,
+        // Position's pos is always a UTF-8 border. Only the line itself is scanned, so that
+        // finding the lines of long inputs stays linear.
+        match self.input[..self.pos].rfind('\n') {
+            Some(i) => i + 1,
             None => 0,
         }
     }
@@ -194,13 +193,8 @@ impl<'i> Position<'i> {
             self.input.len()
         } else {
             // Position's pos is always a UTF-8 border.
-            let end = self
-                .input
-                .char_indices()
-                .skip_while(|&(i, _)| i < self.pos)
-                .find(|&(_, c)| c == '\n');
-            match end {
-                Some((i, _)) => i + 1,
+            match self.input[self.pos..].find('\n') {
+                Some(i) => self.pos + i + 1,
                 None => self.input.len(),
             }
         }
@@ -262,6 +256,14 @@ impl<'i> Position<'i> {
         true
     }
 
+    /// Returns the `Position` `n` bytes before this one, or `None` if it would be out of bounds or
+    /// not on a `char` boundary.
+    #[inline]
+    pub(crate) fn back(&self, n: usize) -> Option<Position<'i>> {
+        let pos = self.pos.checked_sub(n)?;
+        Position::new(self.input, pos)
+    }
+
     /// Skips until one of the given `strings` is found. If none of the `strings` can be found,
     /// this function will return `false` but its `pos` will *still* be updated.
     #[inline]
@@ -680,6 +682,17 @@ mod tests {
         let _ = pos1.cmp(&pos2);
     }
 
+    #[test]
+    fn partial_cmp_different_inputs() {
+        let input1 = "a";
+        let input2 = "b";
+        let pos1 = Position::from_start(input1);
+        let pos2 = Position::from_start(input2);
+
+        assert_eq!(pos1.partial_cmp(&pos2), None);
+        assert_eq!(pos1.lt(&pos2), pos2.lt(&pos1));
+    }
+
     #[test]
     #[cfg(feature = "std")]
     fn hash() {
@@ -16,7 +16,10 @@ use core::ops::BitOr;
 
 use alloc::boxed::Box;
 use alloc::collections::BTreeMap;
+use alloc::format;
+use alloc::vec::Vec;
 
+use crate::error::{Error, ErrorVariant};
 use crate::iterators::Pair;
 use crate::RuleType;
 
@@ -29,6 +32,9 @@ pub enum Assoc {
     Left,
     /// Right operator associativity. Evaluate expressions from right-to-left.
     Right,
+    /// No operator associativity. Expressions chaining operators of the same precedence, e.g.
+    /// `a == b == c`, are an error.
+    Non,
 }
 
 type Prec = u32;
@@ -45,6 +51,7 @@ enum Affix {
     Prefix,
     Postfix,
     Infix(Assoc),
+    Chain,
 }
 
 impl<R: RuleType> Op<R> {
@@ -74,6 +81,19 @@ impl<R: RuleType> Op<R> {
             next: None,
         }
     }
+
+    /// Defines `rule` as a chained infix operator, like the comparisons of Python, where
+    /// `a < b <= c` means `a < b and b <= c`. The operands chained by operators of the same
+    /// precedence are mapped at once with [`map_chain`].
+    ///
+    /// [`map_chain`]: struct.PrattParserMap.html#method.map_chain
+    pub fn chain(rule: R) -> Self {
+        Self {
+            rule,
+            affix: Affix::Chain,
+            next: None,
+        }
+    }
 }
 
 impl<R: RuleType> BitOr for Op<R> {
@@ -104,6 +124,7 @@ impl<R: RuleType> BitOr for Op<R> {
 /// * `pairs` is empty
 /// * The tokens in `pairs` does not alternate in the expected order.
 /// * No `map_*` function is specified for a certain kind of operator encountered in `pairs`.
+/// * Non-associative operators are chained, unless the expression is parsed with [`try_parse`].
 ///
 /// # Example
 ///
@@ -191,6 +212,7 @@ impl<R: RuleType> BitOr for Op<R> {
 /// [`map_postfix`]: struct.PrattParserMap.html#method.map_postfix
 /// [`map_infix`]: struct.PrattParserMap.html#method.map_infix
 /// [`parse`]: struct.PrattParserMap.html#method.parse
+/// [`try_parse`]: struct.PrattParserMap.html#method.try_parse
 /// [`op`]: struct.PrattParserMap.html#method.op
 pub struct PrattParser<R: RuleType> {
     prec: Prec,
@@ -226,7 +248,7 @@ impl<R: RuleType> PrattParser<R> {
             match affix {
                 Affix::Prefix => self.has_prefix = true,
                 Affix::Postfix => self.has_postfix = true,
-                Affix::Infix(_) => self.has_infix = true,
+                Affix::Infix(_) | Affix::Chain => self.has_infix = true,
             }
             self.ops.insert(rule, (affix, self.prec));
             iter = next.map(|op| *op);
@@ -248,7 +270,10 @@ impl<R: RuleType> PrattParser<R> {
             primary,
             prefix: None,
             postfix: None,
+            postfix_group: None,
             infix: None,
+            chain: None,
+            error: None,
             phantom: PhantomData,
         }
     }
@@ -256,7 +281,9 @@ impl<R: RuleType> PrattParser<R> {
 
 type PrefixFn<'i, R, T> = Box<dyn FnMut(Pair<'i, R>, T) -> T + 'i>;
 type PostfixFn<'i, R, T> = Box<dyn FnMut(T, Pair<'i, R>) -> T + 'i>;
+type PostfixGroupFn<'i, R, T> = Box<dyn FnMut(T, Pair<'i, R>, Vec<Pair<'i, R>>) -> T + 'i>;
 type InfixFn<'i, R, T> = Box<dyn FnMut(T, Pair<'i, R>, T) -> T + 'i>;
+type ChainFn<'i, R, T> = Box<dyn FnMut(T, Vec<(Pair<'i, R>, T)>) -> T + 'i>;
 
 /// Product of calling [`map_primary`] on [`PrattParser`], defines how expressions should
 /// be mapped.
@@ -272,7 +299,11 @@ where
     primary: F,
     prefix: Option<PrefixFn<'i, R, T>>,
     postfix: Option<PostfixFn<'i, R, T>>,
+    postfix_group: Option<PostfixGroupFn<'i, R, T>>,
     infix: Option<InfixFn<'i, R, T>>,
+    chain: Option<ChainFn<'i, R, T>>,
+    // The first error found by the current parse, after which it goes on to the end.
+    error: Option<Error<R>>,
     phantom: PhantomData<T>,
 }
 
@@ -299,6 +330,62 @@ where
         self
     }
 
+    /// Maps postfix operators along with their arguments with closure `postfix_group`, e.g. for
+    /// call or indexing expressions. The arguments of a postfix operator are the pairs following
+    /// it which are not operators, e.g. the `expr`s of a `call = { "(" ~ (expr ~ ",")* ~ ")" }`
+    /// whose pairs are flattened into the expression with `_{ call ~ (expr ~ ",")* }`.
+    ///
+    /// Once specified, it maps all postfix operators instead of [`map_postfix`], with no
+    /// arguments for those which take none.
+    ///
+    /// [`map_postfix`]: #method.map_postfix
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use pest::pratt_parser::{Op, PrattParser};
+    /// # #[allow(non_camel_case_types)]
+    /// # #[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+    /// # enum Rule { int, call, neg }
+    /// // -f(1)(2, 3), with the arguments of `call` following it
+    /// let input = "-f(1)(2,3)";
+    /// let pairs = pest::state(input, |state| {
+    ///     // generating Token pairs ...
+    /// #     state
+    /// #         .rule(Rule::neg, |s| s.match_string("-"))
+    /// #         .and_then(|s| s.rule(Rule::int, |s| s.match_string("f")))
+    /// #         .and_then(|s| s.rule(Rule::call, |s| s.match_string("(")))
+    /// #         .and_then(|s| s.rule(Rule::int, |s| s.match_string("1")))
+    /// #         .and_then(|s| s.rule(Rule::call, |s| s.match_string(")(")))
+    /// #         .and_then(|s| s.rule(Rule::int, |s| s.match_string("2")))
+    /// #         .and_then(|s| s.match_string(","))
+    /// #         .and_then(|s| s.rule(Rule::int, |s| s.match_string("3")))
+    /// #         .and_then(|s| s.match_string(")"))
+    /// }).unwrap();
+    ///
+    /// let pratt = PrattParser::new()
+    ///     .op(Op::prefix(Rule::neg))
+    ///     .op(Op::postfix(Rule::call));
+    ///
+    /// let expr = pratt
+    ///     .map_primary(|primary| primary.as_str().to_owned())
+    ///     .map_prefix(|_, rhs| format!("-{}", rhs))
+    ///     .map_postfix_group(|lhs, _, args| {
+    ///         let args: Vec<_> = args.iter().map(|arg| arg.as_str()).collect();
+    ///         format!("{}({})", lhs, args.join(", "))
+    ///     })
+    ///     .parse(pairs);
+    ///
+    /// assert_eq!(expr, "-f(1)(2, 3)");
+    /// ```
+    pub fn map_postfix_group<X>(mut self, postfix_group: X) -> Self
+    where
+        X: FnMut(T, Pair<'i, R>, Vec<Pair<'i, R>>) -> T + 'i,
+    {
+        self.postfix_group = Some(Box::new(postfix_group));
+        self
+    }
+
     /// Maps infix operators with a closure `infix`.
     pub fn map_infix<X>(mut self, infix: X) -> Self
     where
@@ -308,16 +395,125 @@ where
         self
     }
 
+    /// Maps the operands chained by [`Op::chain`] operators of the same precedence with closure
+    /// `chain`, which gets the first operand along with the following operators and operands.
+    ///
+    /// [`Op::chain`]: struct.Op.html#method.chain
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use pest::pratt_parser::{Op, PrattParser};
+    /// # #[allow(non_camel_case_types)]
+    /// # #[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+    /// # enum Rule { int, lt, le }
+    /// let input = "1<2<=2";
+    /// let pairs = pest::state(input, |state| {
+    ///     // generating Token pairs ...
+    /// #     state
+    /// #         .rule(Rule::int, |s| s.match_string("1"))
+    /// #         .and_then(|s| s.rule(Rule::lt, |s| s.match_string("<")))
+    /// #         .and_then(|s| s.rule(Rule::int, |s| s.match_string("2")))
+    /// #         .and_then(|s| s.rule(Rule::le, |s| s.match_string("<=")))
+    /// #         .and_then(|s| s.rule(Rule::int, |s| s.match_string("2")))
+    /// }).unwrap();
+    ///
+    /// let pratt = PrattParser::new().op(Op::chain(Rule::lt) | Op::chain(Rule::le));
+    ///
+    /// // 1 < 2 and 2 <= 2
+    /// let holds = pratt
+    ///     .map_primary(|primary| (primary.as_str().parse::<i32>().unwrap(), true))
+    ///     .map_chain(|(mut lhs, _), rest| {
+    ///         let mut holds = true;
+    ///         for (op, (rhs, _)) in rest {
+    ///             holds &= match op.as_rule() {
+    ///                 Rule::lt => lhs < rhs,
+    ///                 Rule::le => lhs <= rhs,
+    ///                 _ => unreachable!(),
+    ///             };
+    ///             lhs = rhs;
+    ///         }
+    ///         (lhs, holds)
+    ///     })
+    ///     .parse(pairs)
+    ///     .1;
+    ///
+    /// assert!(holds);
+    /// ```
+    pub fn map_chain<X>(mut self, chain: X) -> Self
+    where
+        X: FnMut(T, Vec<(Pair<'i, R>, T)>) -> T + 'i,
+    {
+        self.chain = Some(Box::new(chain));
+        self
+    }
+
     /// The last method to call on the provided pairs to execute the Pratt
     /// parser (previously defined using [`map_primary`], [`map_prefix`], [`map_postfix`],
-    /// and [`map_infix`] methods).
+    /// [`map_postfix_group`], [`map_infix`] and [`map_chain`] methods).
+    ///
+    /// # Panics
+    ///
+    /// Panics if non-associative operators are chained. See [`try_parse`].
     ///
     /// [`map_primary`]: struct.PrattParser.html#method.map_primary
     /// [`map_prefix`]: struct.PrattParserMap.html#method.map_prefix
     /// [`map_postfix`]: struct.PrattParserMap.html#method.map_postfix
+    /// [`map_postfix_group`]: struct.PrattParserMap.html#method.map_postfix_group
     /// [`map_infix`]: struct.PrattParserMap.html#method.map_infix
+    /// [`map_chain`]: struct.PrattParserMap.html#method.map_chain
+    /// [`try_parse`]: struct.PrattParserMap.html#method.try_parse
     pub fn parse<P: Iterator<Item = Pair<'i, R>>>(&mut self, pairs: P) -> T {
-        self.expr(&mut pairs.peekable(), 0)
+        match self.try_parse(pairs) {
+            Ok(expr) => expr,
+            Err(error) => panic!("{}", error),
+        }
+    }
+
+    /// Same as [`parse`], but returns an error pointing at the second operator if
+    /// [`Assoc::Non`] operators of the same precedence are chained, e.g. in `a == b == c`.
+    ///
+    /// [`parse`]: struct.PrattParserMap.html#method.parse
+    /// [`Assoc::Non`]: enum.Assoc.html#variant.Non
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use pest::pratt_parser::{Assoc, Op, PrattParser};
+    /// # use pest::error::InputLocation;
+    /// # #[allow(non_camel_case_types)]
+    /// # #[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+    /// # enum Rule { int, eq }
+    /// let input = "1==1==1";
+    /// let pairs = pest::state(input, |state| {
+    ///     // generating Token pairs ...
+    /// #     state
+    /// #         .rule(Rule::int, |s| s.match_string("1"))
+    /// #         .and_then(|s| s.rule(Rule::eq, |s| s.match_string("==")))
+    /// #         .and_then(|s| s.rule(Rule::int, |s| s.match_string("1")))
+    /// #         .and_then(|s| s.rule(Rule::eq, |s| s.match_string("==")))
+    /// #         .and_then(|s| s.rule(Rule::int, |s| s.match_string("1")))
+    /// }).unwrap();
+    ///
+    /// let pratt = PrattParser::new().op(Op::infix(Rule::eq, Assoc::Non));
+    ///
+    /// let error = pratt
+    ///     .map_primary(|primary| primary.as_str().parse::<i32>().unwrap())
+    ///     .map_infix(|lhs, _, rhs| (lhs == rhs) as i32)
+    ///     .try_parse(pairs)
+    ///     .unwrap_err();
+    ///
+    /// assert_eq!(error.location, InputLocation::Span((4, 6)));
+    /// ```
+    #[allow(clippy::perf)]
+    pub fn try_parse<P: Iterator<Item = Pair<'i, R>>>(&mut self, pairs: P) -> Result<T, Error<R>> {
+        self.error = None;
+        let expr = self.expr(&mut pairs.peekable(), 0);
+
+        match self.error.take() {
+            Some(error) => Err(error),
+            None => Ok(expr),
+        }
     }
 
     fn expr<P: Iterator<Item = Pair<'i, R>>>(&mut self, pairs: &mut Peekable<P>, rbp: Prec) -> T {
@@ -356,18 +552,65 @@ where
         match self.pratt.ops.get(&pair.as_rule()) {
             Some((Affix::Infix(assoc), prec)) => {
                 let rhs = match *assoc {
-                    Assoc::Left => self.expr(pairs, *prec),
+                    Assoc::Left | Assoc::Non => self.expr(pairs, *prec),
                     Assoc::Right => self.expr(pairs, *prec - 1),
                 };
+                if *assoc == Assoc::Non && self.error.is_none() && self.lbp(pairs) == *prec {
+                    // Operators of the same precedence are left which `rhs` did not bind.
+                    let next = pairs.peek().unwrap();
+                    self.error = Some(Error::new_from_span(
+                        ErrorVariant::CustomError {
+                            message: format!(
+                                "operator `{}` cannot be chained with `{}`",
+                                next.as_str(),
+                                pair.as_str()
+                            ),
+                        },
+                        next.as_span(),
+                    ));
+                }
                 match self.infix.as_mut() {
                     Some(infix) => infix(lhs, pair, rhs),
                     None => panic!("Could not map {}, no `.map_infix(...)` specified", pair),
                 }
             }
-            Some((Affix::Postfix, _)) => match self.postfix.as_mut() {
-                Some(postfix) => postfix(lhs, pair),
-                None => panic!("Could not map {}, no `.map_postfix(...)` specified", pair),
-            },
+            Some((Affix::Postfix, _)) => {
+                if let Some(postfix_group) = self.postfix_group.as_mut() {
+                    let ops = &self.pratt.ops;
+                    let mut args = Vec::new();
+                    while let Some(arg) = pairs.next_if(|arg| !ops.contains_key(&arg.as_rule())) {
+                        args.push(arg);
+                    }
+                    return postfix_group(lhs, pair, args);
+                }
+
+                match self.postfix.as_mut() {
+                    Some(postfix) => postfix(lhs, pair),
+                    None => panic!("Could not map {}, no `.map_postfix(...)` specified", pair),
+                }
+            }
+            Some((Affix::Chain, prec)) => {
+                let pratt = self.pratt;
+                let mut rest = Vec::new();
+                let mut op = pair;
+                loop {
+                    rest.push((op, self.expr(pairs, *prec)));
+                    op = match pairs.next_if(|next| {
+                        matches!(pratt.ops.get(&next.as_rule()), Some((Affix::Chain, other)) if other == prec)
+                    }) {
+                        Some(next) => next,
+                        None => break,
+                    };
+                }
+
+                match self.chain.as_mut() {
+                    Some(chain) => chain(lhs, rest),
+                    None => panic!(
+                        "Could not map {}, no `.map_chain(...)` specified",
+                        rest[0].0
+                    ),
+                }
+            }
             _ => panic!("Expected postfix or infix expression, found {}", pair),
         }
     }
@@ -385,3 +628,137 @@ where
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::iterators::Pairs;
+    use alloc::string::{String, ToString};
+
+    #[allow(non_camel_case_types)]
+    #[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+    enum Rule {
+        int,
+        add,
+        fac,
+        index,
+    }
+
+    fn display(pratt: &PrattParser<Rule>, input: &str) -> String {
+        // a[1]! + b[2][3], with the indices following `index`
+        let pairs = crate::state(input, |state| {
+            state
+                .rule(Rule::int, |s| s.match_string("a"))
+                .and_then(|s| s.rule(Rule::index, |s| s.match_string("[")))
+                .and_then(|s| s.rule(Rule::int, |s| s.match_string("1")))
+                .and_then(|s| s.match_string("]"))
+                .and_then(|s| s.rule(Rule::fac, |s| s.match_string("!")))
+                .and_then(|s| s.rule(Rule::add, |s| s.match_string("+")))
+                .and_then(|s| s.rule(Rule::int, |s| s.match_string("b")))
+                .and_then(|s| s.rule(Rule::index, |s| s.match_string("[")))
+                .and_then(|s| s.rule(Rule::int, |s| s.match_string("2")))
+                .and_then(|s| s.match_string("]"))
+                .and_then(|s| s.rule(Rule::index, |s| s.match_string("[")))
+                .and_then(|s| s.rule(Rule::int, |s| s.match_string("3")))
+                .and_then(|s| s.match_string("]"))
+        })
+        .unwrap();
+
+        pratt
+            .map_primary(|primary| primary.as_str().to_string())
+            .map_postfix_group(|lhs, op, args| match op.as_rule() {
+                Rule::fac => {
+                    assert!(args.is_empty());
+                    format!("({})!", lhs)
+                }
+                Rule::index => {
+                    assert_eq!(args.len(), 1);
+                    format!("{}[{}]", lhs, args[0].as_str())
+                }
+                _ => unreachable!(),
+            })
+            .map_infix(|lhs, _, rhs| format!("({} + {})", lhs, rhs))
+            .parse(pairs)
+    }
+
+    #[test]
+    fn postfix_group() {
+        let pratt = PrattParser::new()
+            .op(Op::infix(Rule::add, Assoc::Left))
+            .op(Op::postfix(Rule::fac))
+            .op(Op::postfix(Rule::index));
+
+        assert_eq!(display(&pratt, "a[1]!+b[2][3]"), "((a[1])! + b[2][3])");
+    }
+
+    // a + b < c == d, with `fac` and `index` standing in for `<` and `==`
+    fn comparison_pairs(input: &str) -> Pairs<'_, Rule> {
+        crate::state(input, |state| {
+            state
+                .rule(Rule::int, |s| s.match_string("a"))
+                .and_then(|s| s.rule(Rule::add, |s| s.match_string("+")))
+                .and_then(|s| s.rule(Rule::int, |s| s.match_string("b")))
+                .and_then(|s| s.rule(Rule::fac, |s| s.match_string("<")))
+                .and_then(|s| s.rule(Rule::int, |s| s.match_string("c")))
+                .and_then(|s| s.rule(Rule::index, |s| s.match_string("==")))
+                .and_then(|s| s.rule(Rule::int, |s| s.match_string("d")))
+        })
+        .unwrap()
+    }
+
+    #[allow(clippy::perf)]
+    fn comparison(input: &str, pratt: &PrattParser<Rule>) -> Result<String, Error<Rule>> {
+        pratt
+            .map_primary(|primary| primary.as_str().to_string())
+            .map_infix(|lhs, op, rhs| format!("({} {} {})", lhs, op.as_str(), rhs))
+            .map_chain(|first, rest| {
+                let mut chain = first;
+                for (op, operand) in rest {
+                    chain = format!("{} {} {}", chain, op.as_str(), operand);
+                }
+                format!("[{}]", chain)
+            })
+            .try_parse(comparison_pairs(input))
+    }
+
+    #[test]
+    fn chain() {
+        let pratt = PrattParser::new()
+            .op(Op::chain(Rule::fac) | Op::chain(Rule::index))
+            .op(Op::infix(Rule::add, Assoc::Left));
+
+        assert_eq!(
+            comparison("a+b<c==d", &pratt).unwrap(),
+            "[(a + b) < c == d]"
+        );
+    }
+
+    #[test]
+    fn non_associative() {
+        let pratt = PrattParser::new()
+            .op(Op::infix(Rule::fac, Assoc::Non) | Op::infix(Rule::index, Assoc::Non))
+            .op(Op::infix(Rule::add, Assoc::Non));
+
+        let error = comparison("a+b<c==d", &pratt).unwrap_err();
+        assert_eq!(error.location, crate::error::InputLocation::Span((5, 7)));
+        assert_eq!(
+            error.variant,
+            ErrorVariant::CustomError {
+                message: "operator `==` cannot be chained with `<`".to_string()
+            }
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "operator `==` cannot be chained with `<`")]
+    fn non_associative_parse() {
+        let pratt = PrattParser::new()
+            .op(Op::infix(Rule::fac, Assoc::Non) | Op::infix(Rule::index, Assoc::Non))
+            .op(Op::infix(Rule::add, Assoc::Left));
+
+        pratt
+            .map_primary(|_| ())
+            .map_infix(|_, _, _| ())
+            .parse(comparison_pairs("a+b<c==d"));
+    }
+}
@@ -0,0 +1,337 @@
+// pest. The Elegant Parser
+// Copyright (c) 2018 Dragoș Tiselice
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! A registry of parsers for multiple languages, selected by name, file extension, or MIME type.
+//!
+//! # Examples
+//!
+//! ```
+//! # use pest::dyn_parser::{DynError, DynPairs, DynParser};
+//! # use pest::registry::{Language, Registry};
+//! # struct JsonParser;
+//! # impl DynParser for JsonParser {
+//! #     fn rule_names(&self) -> Vec<&str> { vec!["json"] }
+//! #     fn parse_dyn<'a, 'i>(&'a self, _: &str, input: &'i str)
+//! #         -> Result<DynPairs<'i, 'a>, DynError<'a>> {
+//! #         pest::dyn_parser::into_dyn(pest::state(input, |s| s.rule("json", |s| s.match_string("{}"))), |r| r)
+//! #     }
+//! # }
+//! let mut registry = Registry::new();
+//! registry.register(
+//!     Language::new("json", JsonParser, "json")
+//!         .extension("json")
+//!         .mime_type("application/json"),
+//! );
+//!
+//! assert!(registry.parse_path("data/config.json", "{}").is_ok());
+//! assert!(registry.parse_path("data/config.toml", "").is_err());
+//! ```
+
+use alloc::borrow::ToOwned;
+use alloc::boxed::Box;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt;
+
+use crate::dyn_parser::{DynError, DynPairs, DynParser};
+
+/// A language of a [`Registry`], i.e. a parser with the rule to start parsing from, along with the
+/// file extensions and MIME types associated to it.
+///
+/// [`Registry`]: struct.Registry.html
+pub struct Language {
+    name: String,
+    parser: Box<dyn DynParser>,
+    start_rule: String,
+    extensions: Vec<String>,
+    mime_types: Vec<String>,
+}
+
+impl Language {
+    /// Creates a new `Language` named `name`, which parses inputs with `parser` starting from the
+    /// rule named `start_rule`.
+    pub fn new<P: DynParser + 'static>(name: &str, parser: P, start_rule: &str) -> Language {
+        Language {
+            name: name.to_owned(),
+            parser: Box::new(parser),
+            start_rule: start_rule.to_owned(),
+            extensions: Vec::new(),
+            mime_types: Vec::new(),
+        }
+    }
+
+    /// Associates the file extension `extension` (with or without a leading `.`) to the
+    /// `Language`. Extensions are matched case insensitively (ASCII only).
+    pub fn extension(mut self, extension: &str) -> Language {
+        let extension = extension.strip_prefix('.').unwrap_or(extension);
+        self.extensions.push(extension.to_ascii_lowercase());
+        self
+    }
+
+    /// Associates the MIME type `mime_type` to the `Language`. MIME types are matched case
+    /// insensitively (ASCII only) and without their parameters, e.g. `; charset=utf-8`.
+    pub fn mime_type(mut self, mime_type: &str) -> Language {
+        self.mime_types
+            .push(mime_essence(mime_type).to_ascii_lowercase());
+        self
+    }
+
+    /// Returns the name of the `Language`.
+    #[inline]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the parser of the `Language`.
+    #[inline]
+    pub fn parser(&self) -> &dyn DynParser {
+        &*self.parser
+    }
+
+    /// Returns the name of the rule the `Language` starts parsing from.
+    #[inline]
+    pub fn start_rule(&self) -> &str {
+        &self.start_rule
+    }
+
+    /// Returns the file extensions associated to the `Language`, without leading `.`s.
+    #[inline]
+    pub fn extensions(&self) -> &[String] {
+        &self.extensions
+    }
+
+    /// Returns the MIME types associated to the `Language`.
+    #[inline]
+    pub fn mime_types(&self) -> &[String] {
+        &self.mime_types
+    }
+
+    /// Parses `input` starting from the `Language`'s start rule.
+    #[allow(clippy::perf)]
+    pub fn parse<'a, 'i>(&'a self, input: &'i str) -> Result<DynPairs<'i, 'a>, DynError<'a>> {
+        self.parser.parse_dyn(&self.start_rule, input)
+    }
+}
+
+impl fmt::Debug for Language {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Language")
+            .field("name", &self.name)
+            .field("start_rule", &self.start_rule)
+            .field("extensions", &self.extensions)
+            .field("mime_types", &self.mime_types)
+            .finish()
+    }
+}
+
+/// A registry of [`Language`]s. When multiple languages match a name, file extension, or MIME
+/// type, the one registered first is used.
+///
+/// [`Language`]: struct.Language.html
+#[derive(Debug, Default)]
+pub struct Registry {
+    languages: Vec<Language>,
+}
+
+impl Registry {
+    /// Creates an empty `Registry`.
+    pub fn new() -> Registry {
+        Registry::default()
+    }
+
+    /// Registers `language` in the `Registry`.
+    pub fn register(&mut self, language: Language) -> &mut Registry {
+        self.languages.push(language);
+        self
+    }
+
+    /// Returns all the registered `Language`s, in the order they were registered.
+    #[inline]
+    pub fn languages(&self) -> &[Language] {
+        &self.languages
+    }
+
+    /// Returns the `Language` named `name`.
+    pub fn by_name(&self, name: &str) -> Option<&Language> {
+        self.languages.iter().find(|language| language.name == name)
+    }
+
+    /// Returns the `Language` associated to the file extension `extension` (with or without a
+    /// leading `.`).
+    pub fn by_extension(&self, extension: &str) -> Option<&Language> {
+        let extension = extension.strip_prefix('.').unwrap_or(extension);
+
+        self.languages.iter().find(|language| {
+            language
+                .extensions
+                .iter()
+                .any(|other| other.eq_ignore_ascii_case(extension))
+        })
+    }
+
+    /// Returns the `Language` associated to the MIME type `mime_type`.
+    pub fn by_mime_type(&self, mime_type: &str) -> Option<&Language> {
+        let mime_type = mime_essence(mime_type);
+
+        self.languages.iter().find(|language| {
+            language
+                .mime_types
+                .iter()
+                .any(|other| other.eq_ignore_ascii_case(mime_type))
+        })
+    }
+
+    /// Returns the `Language` associated to the extension of the file at `path`. Both `/` and `\`
+    /// are treated as path separators.
+    pub fn by_path(&self, path: &str) -> Option<&Language> {
+        let file_name = path
+            .rsplit(|c| c == '/' || c == '\\')
+            .next()
+            .unwrap_or(path);
+
+        match file_name.rfind('.') {
+            Some(index) if index > 0 => self.by_extension(&file_name[index + 1..]),
+            _ => None,
+        }
+    }
+
+    /// Parses `input`, the contents of the file at `path`, with the `Language` associated to its
+    /// extension. Parsing errors carry `path`, so that they can be displayed as-is.
+    #[allow(clippy::perf)]
+    pub fn parse_path<'a, 'i>(
+        &'a self,
+        path: &str,
+        input: &'i str,
+    ) -> Result<DynPairs<'i, 'a>, RegistryError<'a>> {
+        let language = self
+            .by_path(path)
+            .ok_or_else(|| RegistryError::UnknownLanguage(path.to_owned()))?;
+
+        language
+            .parse(input)
+            .map_err(|error| RegistryError::Parsing(error.with_path(path)))
+    }
+
+    /// Parses `input` with the `Language` named `name`.
+    #[allow(clippy::perf)]
+    pub fn parse<'a, 'i>(
+        &'a self,
+        name: &str,
+        input: &'i str,
+    ) -> Result<DynPairs<'i, 'a>, RegistryError<'a>> {
+        let language = self
+            .by_name(name)
+            .ok_or_else(|| RegistryError::UnknownLanguage(name.to_owned()))?;
+
+        language.parse(input).map_err(RegistryError::Parsing)
+    }
+}
+
+/// An error returned by a [`Registry`].
+///
+/// [`Registry`]: struct.Registry.html
+#[allow(clippy::large_enum_variant)]
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub enum RegistryError<'r> {
+    /// No language matches the given name or path
+    UnknownLanguage(String),
+    /// The input could not be parsed
+    Parsing(DynError<'r>),
+}
+
+impl<'r> fmt::Display for RegistryError<'r> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RegistryError::UnknownLanguage(name) => {
+                write!(f, "no language registered for `{}`", name)
+            }
+            RegistryError::Parsing(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<'r> std::error::Error for RegistryError<'r> {}
+
+fn mime_essence(mime_type: &str) -> &str {
+    mime_type.split(';').next().unwrap_or(mime_type).trim()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::macros::tests::*;
+    use super::*;
+    use alloc::format;
+
+    fn registry() -> Registry {
+        let mut registry = Registry::new();
+        registry
+            .register(
+                Language::new("abc", AbcParser, "a")
+                    .extension(".abc")
+                    .extension("ABCDE")
+                    .mime_type("text/x-abc"),
+            )
+            .register(Language::new("abc2", AbcParser, "a").extension("abc"));
+        registry
+    }
+
+    #[test]
+    fn lookup() {
+        let registry = registry();
+
+        assert_eq!(registry.by_name("abc2").unwrap().name(), "abc2");
+        assert_eq!(registry.by_extension("abc").unwrap().name(), "abc");
+        assert_eq!(registry.by_extension(".abcde").unwrap().name(), "abc");
+        assert_eq!(
+            registry
+                .by_mime_type("Text/X-ABC; charset=utf-8")
+                .unwrap()
+                .name(),
+            "abc"
+        );
+        assert_eq!(registry.by_path("a/b.c\\d.abc").unwrap().name(), "abc");
+        assert!(registry.by_path("a.abc/b").is_none());
+        assert!(registry.by_path(".abc").is_none());
+        assert!(registry.by_name("xyz").is_none());
+    }
+
+    #[test]
+    fn parse() {
+        let registry = registry();
+        let pairs = registry.parse("abc", "abcde").unwrap();
+
+        assert_eq!(
+            format!("{}", pairs),
+            r#"["a"(0, 3, ["b"(1, 2)]), "c"(4, 5)]"#
+        );
+    }
+
+    #[test]
+    fn parse_path_error() {
+        let registry = registry();
+
+        assert_eq!(
+            format!("{}", registry.parse_path("file.abc", "abcdx").unwrap_err()),
+            [
+                " --> file.abc:1:5",
+                "  |",
+                "1 | abcdx",
+                "  |     ^---",
+                "  |",
+                "  = expected \"c\"",
+            ]
+            .join("\n")
+        );
+        assert_eq!(
+            format!("{}", registry.parse_path("file.xyz", "").unwrap_err()),
+            "no language registered for `file.xyz`"
+        );
+    }
+}
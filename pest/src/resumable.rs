@@ -0,0 +1,241 @@
+// pest. The Elegant Parser
+// Copyright (c) 2018 Dragoș Tiselice
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+use alloc::borrow::ToOwned;
+use alloc::boxed::Box;
+use alloc::rc::Rc;
+use alloc::string::String;
+use core::fmt;
+
+use crate::error::Error;
+use crate::iterators::{pairs, Pairs};
+use crate::RuleType;
+
+type ParseFn<R> = fn(R, &str) -> Result<Pairs<'_, R>, Error<R>>;
+
+/// The outcome of [`Parser::parse_resumable`]: either the input parsed, or it ended where the parse
+/// could still have matched, so that more input might make it parse.
+///
+/// [`Parser::parse_resumable`]: trait.Parser.html#method.parse_resumable
+pub enum Resumable<'i, R> {
+    /// The input parsed
+    Complete(Pairs<'i, R>),
+    /// The input ended where the parse could still have matched, so it is to be resumed once more
+    /// input comes
+    NeedsMoreInput(Continuation<R>),
+}
+
+/// A parse which failed because its input ended, keeping the input received so far so that it can
+/// be resumed with more, e.g. as the data of a network connection arrives. It is created by
+/// [`Parser::parse_resumable`].
+///
+/// A parse fails because its input ended if a terminal failed to match while the rest of the
+/// input was a proper prefix of its match, e.g. `"ab"` for `"abc"`, or the end of the input for
+/// `ANY`. See [`Error::is_incomplete`]. Resuming parses the whole input again, so it suits inputs received in a few chunks, like the
+/// messages of a protocol. A parse which succeeds is complete, even if more input would have been
+/// matched, e.g. by a trailing repetition, so rules parsed this way should end with a delimiter or
+/// `EOI`.
+///
+/// [`Parser::parse_resumable`]: trait.Parser.html#method.parse_resumable
+/// [`Error::is_incomplete`]: error/struct.Error.html#method.is_incomplete
+///
+/// # Examples
+///
+/// ```
+/// # use pest::{Parser, Resumable, iterators::Pairs, error::Error};
+/// # #[allow(non_camel_case_types)]
+/// # #[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+/// # enum Rule { request, path }
+/// # struct HttpParser;
+/// # impl Parser<Rule> for HttpParser {
+/// #     fn parse(_: Rule, input: &str) -> Result<Pairs<'_, Rule>, Error<Rule>> {
+/// #         pest::state(input, |s| {
+/// #             s.rule(Rule::request, |s| {
+/// #                 s.sequence(|s| {
+/// #                     s.match_string("GET ")
+/// #                         .and_then(|s| {
+/// #                             s.rule(Rule::path, |s| s.repeat(|s| s.match_char_by(|c| c.is_ascii_lowercase())))
+/// #                         })
+/// #                         .and_then(|s| s.match_string("\r\n"))
+/// #                 })
+/// #             })
+/// #         })
+/// #     }
+/// # }
+/// // request = { "GET " ~ path ~ "\r\n" }, path = { ('a'..'z')* }
+/// let mut continuation = match HttpParser::parse_resumable(Rule::request, "GET in").unwrap() {
+///     Resumable::NeedsMoreInput(continuation) => continuation,
+///     Resumable::Complete(_) => unreachable!(),
+/// };
+///
+/// assert!(continuation.resume("dex").unwrap().is_none());
+/// let pairs = continuation.resume("\r\n").unwrap().unwrap();
+/// assert_eq!(pairs.as_str(), "GET index\r\n");
+///
+/// // Input which cannot parse fails right away.
+/// assert!(HttpParser::parse_resumable(Rule::request, "PUT").is_err());
+/// ```
+#[derive(Clone)]
+pub struct Continuation<R> {
+    rule: R,
+    input: String,
+    error: Box<Error<R>>,
+    parse: ParseFn<R>,
+}
+
+impl<R: RuleType> Continuation<R> {
+    /// Returns the input received so far.
+    pub fn input(&self) -> &str {
+        &self.input
+    }
+
+    /// Returns the error of the input received so far, which is the error of the parse if no more
+    /// input comes.
+    pub fn error(&self) -> &Error<R> {
+        &self.error
+    }
+
+    /// Ends the parse without more input, returning its error.
+    pub fn into_error(self) -> Error<R> {
+        *self.error
+    }
+
+    /// Appends `more` to the input and parses it again, returning its `Pairs` if it parses, or
+    /// `None` if it still fails because the input ended, in which case the parse can be resumed
+    /// again. If it fails otherwise, its error is returned and the `Continuation` is left as it
+    /// was.
+    #[allow(clippy::perf)]
+    pub fn resume(&mut self, more: &str) -> Result<Option<Pairs<'_, R>>, Error<R>> {
+        let len = self.input.len();
+        self.input.push_str(more);
+
+        // The tokens are taken out of the `Pairs`, which borrow the input, so that it can still be
+        // truncated.
+        let queue = match (self.parse)(self.rule, &self.input) {
+            Ok(pairs) => Rc::clone(pairs::queue(&pairs)),
+            Err(error) if error.is_incomplete() => {
+                *self.error = error;
+                return Ok(None);
+            }
+            Err(error) => {
+                self.input.truncate(len);
+                return Err(error);
+            }
+        };
+        let end = queue.len();
+
+        Ok(Some(pairs::new(queue, &self.input, 0, end)))
+    }
+}
+
+impl<'i, R: RuleType> fmt::Debug for Resumable<'i, R> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Resumable::Complete(ref pairs) => f.debug_tuple("Complete").field(pairs).finish(),
+            Resumable::NeedsMoreInput(ref continuation) => {
+                f.debug_tuple("NeedsMoreInput").field(continuation).finish()
+            }
+        }
+    }
+}
+
+impl<R: RuleType> fmt::Debug for Continuation<R> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Continuation")
+            .field("rule", &self.rule)
+            .field("input", &self.input)
+            .field("error", &self.error)
+            .finish()
+    }
+}
+
+#[allow(clippy::perf)]
+pub(crate) fn parse<R: RuleType>(
+    rule: R,
+    input: &str,
+    parse: ParseFn<R>,
+) -> Result<Resumable<'_, R>, Error<R>> {
+    match parse(rule, input) {
+        Ok(pairs) => Ok(Resumable::Complete(pairs)),
+        Err(error) if error.is_incomplete() => Ok(Resumable::NeedsMoreInput(Continuation {
+            rule,
+            input: input.to_owned(),
+            error: Box::new(error),
+            parse,
+        })),
+        Err(error) => Err(error),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::Parser;
+    use crate::parser_state::state;
+    use alloc::format;
+
+    // line = { ASCII_ALPHA_LOWER+ ~ ";" }
+    struct LineParser;
+
+    impl Parser<u8> for LineParser {
+        fn parse(rule: u8, input: &str) -> Result<Pairs<'_, u8>, Error<u8>> {
+            state(input, |state| {
+                state.rule(rule, |state| {
+                    state.sequence(|state| {
+                        state
+                            .match_char_by(|c| c.is_ascii_lowercase())
+                            .and_then(|state| {
+                                state
+                                    .repeat(|state| state.match_char_by(|c| c.is_ascii_lowercase()))
+                            })
+                            .and_then(|state| state.match_string(";"))
+                    })
+                })
+            })
+        }
+    }
+
+    #[test]
+    fn resume() {
+        let mut continuation = match LineParser::parse_resumable(0, "").unwrap() {
+            Resumable::NeedsMoreInput(continuation) => continuation,
+            Resumable::Complete(_) => panic!("the empty input parsed"),
+        };
+
+        assert!(continuation.resume("ab").unwrap().is_none());
+        assert_eq!(continuation.input(), "ab");
+        assert!(continuation.error().is_incomplete());
+
+        let pairs = continuation.resume("c;").unwrap().unwrap();
+        assert_eq!(format!("{}", pairs), "[0(0, 4)]");
+    }
+
+    #[test]
+    fn complete() {
+        match LineParser::parse_resumable(0, "ab;").unwrap() {
+            Resumable::Complete(pairs) => assert_eq!(format!("{}", pairs), "[0(0, 3)]"),
+            Resumable::NeedsMoreInput(_) => panic!("the input did not parse"),
+        }
+    }
+
+    #[test]
+    fn invalid() {
+        let error = LineParser::parse_resumable(0, "a1").unwrap_err();
+        assert!(!error.is_incomplete());
+
+        let mut continuation = match LineParser::parse_resumable(0, "ab").unwrap() {
+            Resumable::NeedsMoreInput(continuation) => continuation,
+            Resumable::Complete(_) => panic!("the input parsed"),
+        };
+        let error = continuation.resume("1;").unwrap_err();
+        assert!(!error.is_incomplete());
+        assert_eq!(continuation.input(), "ab");
+        assert!(continuation.into_error().is_incomplete());
+    }
+}
@@ -9,11 +9,15 @@
 
 use core::fmt;
 use core::hash::{Hash, Hasher};
+use core::iter::FusedIterator;
 use core::ops::{Bound, RangeBounds};
 use core::ptr;
 use core::str;
 
+use crate::error::Error;
+use crate::iterators::Pairs;
 use crate::position;
+use crate::{Parser, RuleType};
 
 /// A span over a `&str`. It is created from either [two `Position`s] or from a [`Pair`].
 ///
@@ -93,6 +97,25 @@ impl<'i> Span<'i> {
         })
     }
 
+    /// Returns the input string of the `Span`.
+    ///
+    /// This function returns the input string of the `Span` as a `&str`. This is the source string
+    /// from which the `Span` was created, not the substring covered by it.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use pest::Span;
+    /// let input = "Hello World!";
+    /// let world = Span::new(input, 6, input.len()).unwrap();
+    ///
+    /// assert_eq!(world.get_input(), input);
+    /// ```
+    #[inline]
+    pub fn get_input(&self) -> &'i str {
+        self.input
+    }
+
     /// Returns the `Span`'s start byte position as a `usize`.
     ///
     /// # Examples
@@ -189,6 +212,119 @@ impl<'i> Span<'i> {
         (pos1, pos2)
     }
 
+    /// Returns the smallest `Span` covering both `self` and `other`, along with everything
+    /// between them, e.g. the span of a node synthesized from its first and last children.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `other` is a span of another input.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use pest::Span;
+    /// let input = "a + b";
+    /// let lhs = Span::new(input, 0, 1).unwrap();
+    /// let rhs = Span::new(input, 4, 5).unwrap();
+    ///
+    /// assert_eq!(lhs.union(&rhs).as_str(), "a + b");
+    /// ```
+    pub fn union(&self, other: &Span<'i>) -> Span<'i> {
+        if !ptr::eq(self.input, other.input) {
+            panic!("union of spans from different inputs")
+        }
+
+        Span {
+            input: self.input,
+            start: self.start.min(other.start),
+            end: self.end.max(other.end),
+        }
+    }
+
+    /// Returns the `Span` covered by both `self` and `other`, or `None` if they do not overlap or
+    /// are of different inputs. Spans which only touch intersect in an empty `Span`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use pest::Span;
+    /// let input = "abcd";
+    /// let bc = Span::new(input, 1, 3).unwrap();
+    ///
+    /// assert_eq!(bc.intersect(&Span::new(input, 2, 4).unwrap()).unwrap().as_str(), "c");
+    /// assert_eq!(bc.intersect(&Span::new(input, 3, 4).unwrap()).unwrap().as_str(), "");
+    /// assert_eq!(bc.intersect(&Span::new(input, 0, 0).unwrap()), None);
+    /// ```
+    pub fn intersect(&self, other: &Span<'i>) -> Option<Span<'i>> {
+        let start = self.start.max(other.start);
+        let end = self.end.min(other.end);
+
+        if ptr::eq(self.input, other.input) && start <= end {
+            Some(Span {
+                input: self.input,
+                start,
+                end,
+            })
+        } else {
+            None
+        }
+    }
+
+    /// Returns `true` if `other` is a span of the same input covered by `self`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use pest::Span;
+    /// let input = "abcd";
+    /// let bc = Span::new(input, 1, 3).unwrap();
+    ///
+    /// assert!(bc.contains(&Span::new(input, 1, 2).unwrap()));
+    /// assert!(!bc.contains(&Span::new(input, 2, 4).unwrap()));
+    /// ```
+    #[inline]
+    pub fn contains(&self, other: &Span<'_>) -> bool {
+        ptr::eq(self.input, other.input) && self.start <= other.start && other.end <= self.end
+    }
+
+    /// Splits the `Span` in two at the byte offset `mid` relative to its start, like
+    /// [`str::split_at`]. Returns `None` if `mid` is past the end of the `Span` or is not a UTF-8
+    /// border.
+    ///
+    /// [`str::split_at`]: https://doc.rust-lang.org/std/primitive.str.html#method.split_at
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use pest::Span;
+    /// let input = "x: βc";
+    /// let span = Span::new(input, 3, input.len()).unwrap();
+    ///
+    /// let (beta, c) = span.split_at(2).unwrap();
+    /// assert_eq!((beta.as_str(), c.as_str()), ("β", "c"));
+    /// assert_eq!(c.start(), 5);
+    /// assert_eq!(span.split_at(1), None);
+    /// ```
+    pub fn split_at(&self, mid: usize) -> Option<(Span<'i>, Span<'i>)> {
+        if !self.as_str().is_char_boundary(mid) {
+            return None;
+        }
+
+        let mid = self.start + mid;
+        Some((
+            Span {
+                input: self.input,
+                start: self.start,
+                end: mid,
+            },
+            Span {
+                input: self.input,
+                start: mid,
+                end: self.end,
+            },
+        ))
+    }
+
     /// Captures a slice from the `&str` defined by the `Span`.
     ///
     /// # Examples
@@ -212,6 +348,53 @@ impl<'i> Span<'i> {
         &self.input[self.start..self.end]
     }
 
+    /// Iterates over the `char`s covered by the `Span`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use pest::Span;
+    /// let span = Span::new("aβc", 1, 4).unwrap();
+    ///
+    /// assert_eq!(span.chars().collect::<String>(), "βc");
+    /// ```
+    #[inline]
+    pub fn chars(&self) -> str::Chars<'i> {
+        self.as_str().chars()
+    }
+
+    /// Iterates over the `char`s covered by the `Span` along with their byte positions in the whole
+    /// input, which can be used to build sub-spans with [`Span::new`].
+    ///
+    /// [`Span::new`]: #method.new
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use pest::Span;
+    /// let span = Span::new("aβc", 1, 4).unwrap();
+    ///
+    /// assert_eq!(span.char_indices().collect::<Vec<_>>(), vec![(1, 'β'), (3, 'c')]);
+    /// ```
+    #[inline]
+    pub fn char_indices(&self) -> CharIndices<'i> {
+        CharIndices {
+            inner: self.as_str().char_indices(),
+            offset: self.start,
+        }
+    }
+
+    /// Parses the text covered by the `Span` starting from `rule`, with the resulting `Pairs` and
+    /// errors expressed relative to the `Span`'s full input. This is a shorthand for
+    /// [`Parser::parse_span`].
+    ///
+    /// [`Parser::parse_span`]: trait.Parser.html#method.parse_span
+    #[inline]
+    #[allow(clippy::perf)]
+    pub fn parse<P: Parser<R>, R: RuleType>(&self, rule: R) -> Result<Pairs<'i, R>, Error<R>> {
+        P::parse_span(rule, *self)
+    }
+
     /// Iterates over all lines (partially) covered by this span. Yielding a `&str` for each line.
     ///
     /// # Examples
@@ -309,7 +492,12 @@ impl<'i> Iterator for LinesSpan<'i> {
             return None;
         }
 
-        let line_start = pos.find_line_start();
+        // Only the first line can start before `pos`.
+        let line_start = if self.pos == self.span.start {
+            pos.find_line_start()
+        } else {
+            self.pos
+        };
         self.pos = pos.find_line_end();
 
         Span::new(self.span.input, line_start, self.pos)
@@ -332,10 +520,46 @@ impl<'i> Iterator for Lines<'i> {
     }
 }
 
+/// Iterator over the `char`s of a `Span` and their byte positions in the whole input, created by
+/// [`Span::char_indices()`].
+///
+/// [`Span::char_indices()`]: struct.Span.html#method.char_indices
+#[derive(Clone, Debug)]
+pub struct CharIndices<'i> {
+    inner: str::CharIndices<'i>,
+    offset: usize,
+}
+
+impl<'i> Iterator for CharIndices<'i> {
+    type Item = (usize, char);
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next().map(|(i, c)| (self.offset + i, c))
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<'i> DoubleEndedIterator for CharIndices<'i> {
+    #[inline]
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.inner.next_back().map(|(i, c)| (self.offset + i, c))
+    }
+}
+
+impl<'i> FusedIterator for CharIndices<'i> {}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::error::{InputLocation, LineColLocation};
+    use crate::macros::tests::{AbcParser, Rule};
     use alloc::borrow::ToOwned;
+    use alloc::format;
     use alloc::vec::Vec;
 
     #[test]
@@ -401,6 +625,44 @@ mod tests {
         assert_eq!(span.split(), (start, end));
     }
 
+    #[test]
+    fn arithmetic() {
+        let input = "abcdef";
+        let bc = Span::new(input, 1, 3).unwrap();
+        let e = Span::new(input, 4, 5).unwrap();
+
+        assert_eq!(bc.union(&e), Span::new(input, 1, 5).unwrap());
+        assert_eq!(e.union(&bc), bc.union(&e));
+        assert_eq!(bc.intersect(&e), None);
+        assert!(bc.union(&e).contains(&e));
+        assert!(bc.contains(&bc));
+
+        let (empty, rest) = bc.split_at(0).unwrap();
+        assert_eq!((empty.start(), empty.end()), (1, 1));
+        assert_eq!(rest, bc);
+        assert_eq!(bc.split_at(3), None);
+    }
+
+    #[test]
+    fn arithmetic_other_input() {
+        let input = "abc";
+        let other = input.to_owned();
+        let span = Span::new(input, 0, 3).unwrap();
+        let other_span = Span::new(&other, 0, 3).unwrap();
+
+        assert_eq!(span.intersect(&other_span), None);
+        assert!(!span.contains(&other_span));
+    }
+
+    #[test]
+    #[should_panic(expected = "union of spans from different inputs")]
+    fn union_other_input() {
+        let other = "abc".to_owned();
+        let _ = Span::new("abc", 0, 1)
+            .unwrap()
+            .union(&Span::new(&other, 1, 2).unwrap());
+    }
+
     #[test]
     fn lines_mid() {
         let input = "abc\ndef\nghi";
@@ -447,4 +709,60 @@ mod tests {
             lines
         );
     }
+
+    #[test]
+    fn lines_many() {
+        // Used to take quadratic time, since every line was searched from the end of the input.
+        let input = "ab\n".repeat(100_000);
+        let span = Span::new(&input, 1, input.len()).unwrap();
+
+        assert_eq!(span.lines().count(), 100_000);
+        assert_eq!(span.lines().next(), Some("ab\n"));
+        assert_eq!(span.lines().last(), Some("ab\n"));
+    }
+
+    #[test]
+    fn parse() {
+        let input = "xxabcdexx";
+        let span = Span::new(input, 2, 7).unwrap();
+        let pairs = span.parse::<AbcParser, _>(Rule::a).unwrap();
+
+        assert_eq!(format!("{}", pairs), "[a(2, 5, [b(3, 4)]), c(6, 7)]");
+        assert_eq!(pairs.as_str(), "abcde");
+
+        let pair = pairs.clone().next().unwrap();
+        assert_eq!(pair.as_span(), Span::new(input, 2, 5).unwrap());
+    }
+
+    #[test]
+    fn parse_error() {
+        let input = "x\nabcdxx";
+        let span = Span::new(input, 2, 7).unwrap();
+        let error = span.parse::<AbcParser, _>(Rule::a).unwrap_err();
+
+        assert_eq!(error.location, InputLocation::Pos(6));
+        assert_eq!(error.line_col, LineColLocation::Pos((2, 5)));
+        assert_eq!(error.line(), "abcdxx");
+    }
+
+    #[test]
+    fn char_indices() {
+        let input = "ab\u{e9}\u{1f600}c";
+        let span = Span::new(input, 1, input.len()).unwrap();
+
+        let indices: Vec<_> = span.char_indices().collect();
+        assert_eq!(
+            indices,
+            [(1, 'b'), (2, '\u{e9}'), (4, '\u{1f600}'), (8, 'c')]
+        );
+        assert_eq!(
+            span.char_indices().rev().collect::<Vec<_>>(),
+            indices.iter().rev().cloned().collect::<Vec<_>>()
+        );
+
+        let (start, c) = indices[2];
+        let sub = Span::new(input, start, start + c.len_utf8()).unwrap();
+        assert_eq!(sub.as_str(), "\u{1f600}");
+        assert_eq!(span.chars().count(), 4);
+    }
 }
@@ -0,0 +1,283 @@
+// pest. The Elegant Parser
+// Copyright (c) 2018 Dragoș Tiselice
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! A side table of values keyed by `Span`s.
+
+use alloc::collections::btree_map::{self, BTreeMap};
+use core::cmp::Reverse;
+use core::fmt;
+use core::ptr;
+
+use crate::span::Span;
+
+/// A side table of values keyed by the `Span`s of an input, e.g. the types or symbols resolved
+/// for the nodes of a syntax tree by later phases of a compiler.
+///
+/// Unlike a map keyed by `(start, end)`, a `SpanMap` knows its input, so that a span of another
+/// input is never mistaken for one of its own. Spans are ordered by their start and, for spans
+/// with the same start, from the outermost one in, which is the order of `Pairs::flatten`.
+///
+/// # Examples
+///
+/// ```
+/// # use pest::{Span, SpanMap};
+/// let input = "let x = 42";
+/// let mut types = SpanMap::new(input);
+///
+/// types.insert(Span::new(input, 4, 5).unwrap(), "int");
+/// types.insert(Span::new(input, 8, 10).unwrap(), "int");
+///
+/// let (span, ty) = types.innermost(9).unwrap();
+/// assert_eq!((span.as_str(), *ty), ("42", "int"));
+/// assert_eq!(types.innermost(6), None);
+/// ```
+#[derive(Clone, Eq, PartialEq)]
+pub struct SpanMap<'i, T> {
+    input: &'i str,
+    entries: BTreeMap<(usize, Reverse<usize>), T>,
+}
+
+impl<'i, T> SpanMap<'i, T> {
+    /// Creates an empty `SpanMap` of the spans of `input`.
+    pub fn new(input: &'i str) -> SpanMap<'i, T> {
+        SpanMap {
+            input,
+            entries: BTreeMap::new(),
+        }
+    }
+
+    /// Returns the input of the `SpanMap`.
+    #[inline]
+    pub fn input(&self) -> &'i str {
+        self.input
+    }
+
+    /// Returns the number of spans in the `SpanMap`.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns `true` if the `SpanMap` has no spans.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Inserts `value` for `span`, returning the previous value of `span`, if any.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `span` is a span of another input than the one of the `SpanMap`.
+    pub fn insert(&mut self, span: Span<'i>, value: T) -> Option<T> {
+        assert!(
+            self.owns(&span),
+            "the span is not of the input of the SpanMap"
+        );
+
+        self.entries.insert(key(&span), value)
+    }
+
+    /// Returns the value of `span`, or `None` if there is none, e.g. if `span` is of another
+    /// input.
+    pub fn get(&self, span: &Span<'_>) -> Option<&T> {
+        if self.owns(span) {
+            self.entries.get(&key(span))
+        } else {
+            None
+        }
+    }
+
+    /// Returns the value of `span` mutably, like [`get`].
+    ///
+    /// [`get`]: #method.get
+    pub fn get_mut(&mut self, span: &Span<'_>) -> Option<&mut T> {
+        if self.owns(span) {
+            self.entries.get_mut(&key(span))
+        } else {
+            None
+        }
+    }
+
+    /// Removes `span` from the `SpanMap`, returning its value, if any.
+    pub fn remove(&mut self, span: &Span<'_>) -> Option<T> {
+        if self.owns(span) {
+            self.entries.remove(&key(span))
+        } else {
+            None
+        }
+    }
+
+    /// Returns the spans containing the byte position `pos`, i.e. those with `start <= pos < end`,
+    /// from the innermost one out, along with their values.
+    ///
+    /// Looking them up skips the spans which start before `pos` but end before it, so it is
+    /// fastest for nested spans, like those of a syntax tree, with few siblings.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use pest::{Span, SpanMap};
+    /// let input = "f(x)";
+    /// let mut scopes = SpanMap::new(input);
+    ///
+    /// scopes.insert(Span::new(input, 0, 4).unwrap(), "call");
+    /// scopes.insert(Span::new(input, 0, 1).unwrap(), "callee");
+    /// scopes.insert(Span::new(input, 2, 3).unwrap(), "argument");
+    ///
+    /// let enclosing: Vec<_> = scopes.enclosing(2).map(|(_, scope)| *scope).collect();
+    /// assert_eq!(enclosing, ["argument", "call"]);
+    /// ```
+    pub fn enclosing(&self, pos: usize) -> impl Iterator<Item = (Span<'i>, &T)> {
+        let input = self.input;
+
+        self.entries
+            .range(..=(pos, Reverse(0)))
+            .rev()
+            .filter(move |(&(_, Reverse(end)), _)| pos < end)
+            .map(move |(&key, value)| (span(input, key), value))
+    }
+
+    /// Returns the innermost span containing the byte position `pos` along with its value, like
+    /// the first one of [`enclosing`].
+    ///
+    /// [`enclosing`]: #method.enclosing
+    pub fn innermost(&self, pos: usize) -> Option<(Span<'i>, &T)> {
+        self.enclosing(pos).next()
+    }
+
+    /// Returns the spans along with their values in order.
+    pub fn iter(&self) -> Iter<'_, 'i, T> {
+        Iter {
+            input: self.input,
+            entries: self.entries.iter(),
+        }
+    }
+
+    fn owns(&self, span: &Span<'_>) -> bool {
+        ptr::eq(self.input, span.get_input())
+    }
+}
+
+fn key(span: &Span<'_>) -> (usize, Reverse<usize>) {
+    (span.start(), Reverse(span.end()))
+}
+
+fn span(input: &str, (start, Reverse(end)): (usize, Reverse<usize>)) -> Span<'_> {
+    // Keys are only ever made from spans of `input`.
+    unsafe { Span::new_unchecked(input, start, end) }
+}
+
+impl<'i, T: fmt::Debug> fmt::Debug for SpanMap<'i, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+impl<'i, T> Extend<(Span<'i>, T)> for SpanMap<'i, T> {
+    fn extend<I: IntoIterator<Item = (Span<'i>, T)>>(&mut self, iter: I) {
+        for (span, value) in iter {
+            self.insert(span, value);
+        }
+    }
+}
+
+impl<'a, 'i, T> IntoIterator for &'a SpanMap<'i, T> {
+    type Item = (Span<'i>, &'a T);
+    type IntoIter = Iter<'a, 'i, T>;
+
+    fn into_iter(self) -> Iter<'a, 'i, T> {
+        self.iter()
+    }
+}
+
+/// An iterator over the spans of a [`SpanMap`] along with their values.
+///
+/// [`SpanMap`]: struct.SpanMap.html
+#[derive(Clone, Debug)]
+pub struct Iter<'a, 'i, T> {
+    input: &'i str,
+    entries: btree_map::Iter<'a, (usize, Reverse<usize>), T>,
+}
+
+impl<'a, 'i, T> Iterator for Iter<'a, 'i, T> {
+    type Item = (Span<'i>, &'a T);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (&key, value) = self.entries.next()?;
+        Some((span(self.input, key), value))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.entries.size_hint()
+    }
+}
+
+impl<'a, 'i, T> DoubleEndedIterator for Iter<'a, 'i, T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        let (&key, value) = self.entries.next_back()?;
+        Some((span(self.input, key), value))
+    }
+}
+
+impl<'a, 'i, T> ExactSizeIterator for Iter<'a, 'i, T> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::macros::tests::{AbcParser, Rule};
+    use crate::parser::Parser;
+    use alloc::string::String;
+    use alloc::vec::Vec;
+
+    #[test]
+    fn pairs() {
+        let input = "abcde";
+        let mut rules = SpanMap::new(input);
+        rules.extend(
+            AbcParser::parse(Rule::a, input)
+                .unwrap()
+                .flatten()
+                .map(|pair| (pair.as_span(), pair.as_rule())),
+        );
+
+        let flattened: Vec<_> = rules.iter().map(|(_, &rule)| rule).collect();
+        assert_eq!(flattened, [Rule::a, Rule::b, Rule::c]);
+
+        let enclosing: Vec<_> = rules.enclosing(1).map(|(_, &rule)| rule).collect();
+        assert_eq!(enclosing, [Rule::b, Rule::a]);
+        assert_eq!(rules.innermost(2).map(|(_, &rule)| rule), Some(Rule::a));
+        assert_eq!(rules.innermost(3), None);
+        assert_eq!(rules.innermost(5), None);
+    }
+
+    #[test]
+    fn input_identity() {
+        let input = "abcde";
+        let other = String::from(input);
+        let mut map = SpanMap::new(input);
+
+        map.insert(Span::new(input, 1, 2).unwrap(), 1);
+        assert_eq!(map.insert(Span::new(input, 1, 2).unwrap(), 2), Some(1));
+
+        assert_eq!(map.get(&Span::new(&other, 1, 2).unwrap()), None);
+        assert_eq!(map.remove(&Span::new(&other, 1, 2).unwrap()), None);
+        assert_eq!(map.remove(&Span::new(input, 1, 2).unwrap()), Some(2));
+        assert!(map.is_empty());
+    }
+
+    #[test]
+    #[should_panic(expected = "the span is not of the input of the SpanMap")]
+    fn insert_other_input() {
+        let other = String::from("abcde");
+        let mut map = SpanMap::new("abcde");
+
+        map.insert(Span::new(&other, 0, 1).unwrap(), ());
+    }
+}
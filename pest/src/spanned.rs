@@ -0,0 +1,219 @@
+// pest. The Elegant Parser
+// Copyright (c) 2018 Dragoș Tiselice
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+use alloc::string::{String, ToString};
+use core::fmt;
+use core::ops::{Deref, DerefMut};
+
+use crate::error::{Error, ErrorVariant};
+use crate::iterators::Pair;
+use crate::span::Span;
+use crate::RuleType;
+
+/// A value along with the `Span` of the input it was built from, e.g. a node of an AST built from
+/// `Pairs`, so that later passes can still point at its source.
+///
+/// `Spanned` dereferences to its value.
+///
+/// # Examples
+///
+/// ```
+/// # use pest::{Span, Spanned};
+/// let input = "x = 42";
+/// let number = Spanned::new(42, Span::new(input, 4, 6).unwrap());
+///
+/// assert_eq!(*number + 1, 43);
+/// assert_eq!(number.span.as_str(), "42");
+/// ```
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct Spanned<'i, T> {
+    /// The value
+    pub value: T,
+    /// The span of the input the value was built from
+    pub span: Span<'i>,
+}
+
+impl<'i, T> Spanned<'i, T> {
+    /// Pairs `value` with `span`.
+    pub fn new(value: T, span: Span<'i>) -> Spanned<'i, T> {
+        Spanned { value, span }
+    }
+
+    /// Pairs `value` with the span of `pair`.
+    pub fn of<R: RuleType>(pair: &Pair<'i, R>, value: T) -> Spanned<'i, T> {
+        Spanned::new(value, pair.as_span())
+    }
+
+    /// Returns the value, dropping the span.
+    pub fn into_inner(self) -> T {
+        self.value
+    }
+
+    /// Borrows the value, keeping the span.
+    pub fn as_ref(&self) -> Spanned<'i, &T> {
+        Spanned::new(&self.value, self.span)
+    }
+
+    /// Maps the value with `f`, keeping the span.
+    pub fn map<U, F>(self, f: F) -> Spanned<'i, U>
+    where
+        F: FnOnce(T) -> U,
+    {
+        Spanned::new(f(self.value), self.span)
+    }
+
+    /// Maps the value with the fallible `f`, keeping the span, which a failure of `f` points at.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use pest::{Span, Spanned, SpannedResult};
+    /// fn number<'i>(text: Spanned<'i, &str>) -> SpannedResult<'i, u8> {
+    ///     text.try_map(|text| text.parse::<u8>())
+    /// }
+    ///
+    /// let input = "x = 420";
+    /// let error = number(Spanned::new("420", Span::new(input, 4, 7).unwrap())).unwrap_err();
+    ///
+    /// assert_eq!(error.span.as_str(), "420");
+    /// assert_eq!(error.message, "number too large to fit in target type");
+    /// ```
+    pub fn try_map<U, E, F>(self, f: F) -> SpannedResult<'i, U>
+    where
+        E: fmt::Display,
+        F: FnOnce(T) -> Result<U, E>,
+    {
+        let span = self.span;
+        match f(self.value) {
+            Ok(value) => Ok(Spanned::new(value, span)),
+            Err(error) => Err(SpannedError::new(error.to_string(), span)),
+        }
+    }
+
+    /// Returns an error with `message` pointing at the span.
+    pub fn error(&self, message: &str) -> SpannedError<'i> {
+        SpannedError::new(message.to_string(), self.span)
+    }
+}
+
+impl<'i, T> Deref for Spanned<'i, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.value
+    }
+}
+
+impl<'i, T> DerefMut for Spanned<'i, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.value
+    }
+}
+
+/// An error of the semantic code working on parsed input, made of a message and of the `Span` of
+/// the input it is about.
+///
+/// It converts into a custom [`Error`] of any rule type, so that `?` propagates it from functions
+/// returning `Result<_, Error<R>>`.
+///
+/// [`Error`]: error/struct.Error.html
+///
+/// # Examples
+///
+/// ```
+/// # use pest::{Span, Spanned, SpannedError};
+/// # use pest::error::Error;
+/// fn lookup<'i>(name: Spanned<'i, &str>) -> Result<u32, SpannedError<'i>> {
+///     match *name {
+///         "x" => Ok(42),
+///         _ => Err(name.error("unknown variable")),
+///     }
+/// }
+///
+/// fn evaluate<'i>(name: Spanned<'i, &str>) -> Result<u32, Error<()>> {
+///     Ok(lookup(name)? + 1)
+/// }
+///
+/// let input = "y + 1";
+/// let error = evaluate(Spanned::new("y", Span::new(input, 0, 1).unwrap())).unwrap_err();
+///
+/// assert_eq!(error.variant.message(), "unknown variable");
+/// assert_eq!(error.line_col, pest::error::LineColLocation::Span((1, 1), (1, 2)));
+/// ```
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct SpannedError<'i> {
+    /// The message of the error
+    pub message: String,
+    /// The span of the input the error is about
+    pub span: Span<'i>,
+}
+
+impl<'i> SpannedError<'i> {
+    /// Creates an error with `message` pointing at `span`.
+    pub fn new(message: String, span: Span<'i>) -> SpannedError<'i> {
+        SpannedError { message, span }
+    }
+}
+
+impl<'i> fmt::Display for SpannedError<'i> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (line, col) = self.span.start_pos().line_col();
+        write!(f, "{}:{}: {}", line, col, self.message)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<'i> std::error::Error for SpannedError<'i> {}
+
+impl<'i, R: RuleType> From<SpannedError<'i>> for Error<R> {
+    fn from(error: SpannedError<'i>) -> Error<R> {
+        Error::new_from_span(
+            ErrorVariant::CustomError {
+                message: error.message,
+            },
+            error.span,
+        )
+    }
+}
+
+/// The result of semantic code working on parsed input, holding either a [`Spanned`] value or a
+/// [`SpannedError`].
+///
+/// [`Spanned`]: struct.Spanned.html
+/// [`SpannedError`]: struct.SpannedError.html
+pub type SpannedResult<'i, T> = Result<Spanned<'i, T>, SpannedError<'i>>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::macros::tests::{AbcParser, Rule};
+    use crate::Parser;
+    use alloc::format;
+
+    #[test]
+    fn spanned_pair() {
+        let pair = AbcParser::parse(Rule::a, "abcde").unwrap().next().unwrap();
+        let spanned = Spanned::of(&pair, pair.as_str().len());
+
+        assert_eq!(*spanned.as_ref().map(|len| len * 2), 6);
+        assert_eq!(spanned.span.as_str(), "abc");
+        assert_eq!(spanned.into_inner(), 3);
+    }
+
+    #[test]
+    fn error_conversion() {
+        let pair = AbcParser::parse(Rule::a, "abcde").unwrap().next().unwrap();
+        let error = Spanned::of(&pair, ()).error("unexpected a");
+        assert_eq!(format!("{}", error), "1:1: unexpected a");
+
+        let error: Error<Rule> = error.into();
+        assert_eq!(error.variant.message(), "unexpected a");
+        assert_eq!(error.location, crate::error::InputLocation::Span((0, 3)));
+    }
+}
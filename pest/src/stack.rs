@@ -7,8 +7,11 @@
 // option. All files in the project carrying such notice may not be copied,
 // modified, or distributed except according to those terms.
 
+use alloc::borrow::ToOwned;
+use alloc::string::String;
 use alloc::vec;
 use alloc::vec::Vec;
+use core::mem::size_of;
 use core::ops::{Index, Range};
 
 /// Implementation of a `Stack` which maintains an log of `StackOp`s in order to rewind the stack
@@ -18,6 +21,8 @@ pub struct Stack<T: Clone> {
     ops: Vec<StackOp<T>>,
     cache: Vec<T>,
     snapshots: Vec<usize>,
+    // The named checkpoints, along with the content of the stack when they were set.
+    checkpoints: Vec<(String, Vec<T>)>,
 }
 
 impl<T: Clone> Stack<T> {
@@ -27,6 +32,7 @@ impl<T: Clone> Stack<T> {
             ops: vec![],
             cache: vec![],
             snapshots: vec![],
+            checkpoints: vec![],
         }
     }
 
@@ -56,11 +62,80 @@ impl<T: Clone> Stack<T> {
         popped
     }
 
+    /// Returns the content of the `Stack`, from the bottom to the top.
+    pub fn as_slice(&self) -> &[T] {
+        &self.cache
+    }
+
+    /// Sets the checkpoint `name` to the current content of the `Stack`, replacing the previous
+    /// one of the same name. Like pushes and pops, it is undone by `restore()`.
+    pub fn checkpoint(&mut self, name: &str) {
+        let content = self.cache.clone();
+        let previous = match self.checkpoints.iter_mut().find(|(other, _)| other == name) {
+            Some((_, previous)) => Some(core::mem::replace(previous, content)),
+            None => {
+                self.checkpoints.push((name.to_owned(), content));
+                None
+            }
+        };
+        self.ops
+            .push(StackOp::Checkpoint(name.to_owned(), previous));
+    }
+
+    /// Pops and pushes elements until the `Stack` is back to its content at the checkpoint
+    /// `name`, returning `false` if there is no such checkpoint. The elements below the ones which
+    /// changed since are kept as they are.
+    pub fn restore_to(&mut self, name: &str) -> bool
+    where
+        T: PartialEq,
+    {
+        let content = match self.checkpoints.iter().find(|(other, _)| other == name) {
+            Some((_, content)) => content.clone(),
+            None => return false,
+        };
+        let kept = self
+            .cache
+            .iter()
+            .zip(&content)
+            .take_while(|(elem, other)| elem == other)
+            .count();
+
+        while self.cache.len() > kept {
+            self.pop();
+        }
+        for elem in &content[kept..] {
+            self.push(elem.clone());
+        }
+
+        true
+    }
+
+    /// Returns the number of heap bytes held by the `Stack`.
+    pub fn heap_size(&self) -> usize {
+        self.ops.capacity() * size_of::<StackOp<T>>()
+            + self.cache.capacity() * size_of::<T>()
+            + self.snapshots.capacity() * size_of::<usize>()
+            + self.checkpoints.capacity() * size_of::<(String, Vec<T>)>()
+            + self
+                .checkpoints
+                .iter()
+                .map(|(name, content)| name.capacity() + content.capacity() * size_of::<T>())
+                .sum::<usize>()
+    }
+
     /// Returns the size of the stack
     pub fn len(&self) -> usize {
         self.cache.len()
     }
 
+    /// Empties the `Stack`, along with its snapshots and checkpoints, keeping its buffers.
+    pub fn clear(&mut self) {
+        self.ops.clear();
+        self.cache.clear();
+        self.snapshots.clear();
+        self.checkpoints.clear();
+    }
+
     /// Takes a snapshot of the current `Stack`.
     pub fn snapshot(&mut self) {
         self.snapshots.push(self.ops.len());
@@ -82,6 +157,7 @@ impl<T: Clone> Stack<T> {
             None => {
                 self.cache.clear();
                 self.ops.clear();
+                self.checkpoints.clear();
             }
         }
     }
@@ -97,6 +173,18 @@ impl<T: Clone> Stack<T> {
                 StackOp::Pop(ref elem) => {
                     self.cache.push(elem.clone());
                 }
+                StackOp::Checkpoint(ref name, ref previous) => {
+                    let index = self.checkpoints.iter().position(|(other, _)| other == name);
+                    match (index, previous) {
+                        (Some(index), Some(previous)) => {
+                            self.checkpoints[index].1 = previous.clone();
+                        }
+                        (Some(index), None) => {
+                            self.checkpoints.remove(index);
+                        }
+                        (None, _) => unreachable!(),
+                    }
+                }
             }
         }
     }
@@ -114,6 +202,8 @@ impl<T: Clone> Index<Range<usize>> for Stack<T> {
 enum StackOp<T> {
     Push(T),
     Pop(T),
+    // A checkpoint was set, along with the content it had before, if it was set already.
+    Checkpoint(String, Option<Vec<T>>),
 }
 
 #[cfg(test)]
@@ -147,6 +237,36 @@ mod test {
         assert_eq!(stack[0..stack.len()], [0]);
     }
 
+    #[test]
+    fn checkpoints() {
+        let mut stack = Stack::new();
+
+        stack.push(0);
+        stack.push(1);
+        stack.checkpoint("a");
+        stack.pop();
+        stack.push(2);
+        stack.push(3);
+        assert!(stack.restore_to("a"));
+        assert_eq!(stack.as_slice(), [0, 1]);
+        assert!(!stack.restore_to("b"));
+
+        stack.snapshot();
+        stack.push(4);
+        stack.checkpoint("a");
+        stack.checkpoint("b");
+        stack.restore();
+        assert!(!stack.restore_to("b"));
+
+        stack.push(5);
+        assert!(stack.restore_to("a"));
+        assert_eq!(stack.as_slice(), [0, 1]);
+
+        stack.restore();
+        assert!(stack.is_empty());
+        assert!(!stack.restore_to("a"));
+    }
+
     #[test]
     fn stack_ops() {
         let mut stack = Stack::new();
@@ -0,0 +1,328 @@
+// pest. The Elegant Parser
+// Copyright (c) 2018 Dragoș Tiselice
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Function equivalents of [`parses_to!`] and [`fails_with!`], for expectations that are built at
+//! runtime, e.g. by generated conformance suites.
+//!
+//! The `check_*` functions return the mismatch as an `Err` instead of panicking, which makes them
+//! usable from other languages' test harnesses, while the `assert_*` functions panic.
+//!
+//! [`parses_to!`]: ../macro.parses_to.html
+//! [`fails_with!`]: ../macro.fails_with.html
+
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use crate::error::{Error, ErrorVariant, InputLocation};
+use crate::iterators::Pairs;
+use crate::token::Token;
+use crate::RuleType;
+
+/// An expected pair, along with its expected inner pairs.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct ExpectedPair<R> {
+    /// The rule of the pair
+    pub rule: R,
+    /// The byte position where the pair starts
+    pub start: usize,
+    /// The byte position where the pair ends
+    pub end: usize,
+    /// The inner pairs of the pair
+    pub children: Vec<ExpectedPair<R>>,
+}
+
+impl<R> ExpectedPair<R> {
+    /// Creates an `ExpectedPair` of `rule` between `start` and `end`, without inner pairs.
+    pub fn new(rule: R, start: usize, end: usize) -> ExpectedPair<R> {
+        ExpectedPair {
+            rule,
+            start,
+            end,
+            children: Vec::new(),
+        }
+    }
+
+    /// Sets the inner pairs of the `ExpectedPair`.
+    pub fn with_children(mut self, children: Vec<ExpectedPair<R>>) -> ExpectedPair<R> {
+        self.children = children;
+        self
+    }
+}
+
+/// Checks that parsing `input` from `rule` with `parse` produces exactly the `expected` pairs,
+/// optionally followed by an `EOI` pair, like [`parses_to!`] does.
+///
+/// [`parses_to!`]: ../macro.parses_to.html
+///
+/// # Examples
+///
+/// ```
+/// # use pest::Parser;
+/// # use pest::error::Error;
+/// # use pest::iterators::Pairs;
+/// # use pest::testing::{check_parses_to, ExpectedPair};
+/// # #[allow(non_camel_case_types)]
+/// # #[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+/// # enum Rule {
+/// #     a,
+/// #     b,
+/// # }
+/// # struct AbParser;
+/// # impl Parser<Rule> for AbParser {
+/// #     fn parse<'i>(_: Rule, input: &'i str) -> Result<Pairs<'i, Rule>, Error<Rule>> {
+/// #         pest::state(input, |state| {
+/// #             state.rule(Rule::a, |s| s.skip(1).and_then(|s| s.rule(Rule::b, |s| s.skip(1))))
+/// #         })
+/// #     }
+/// # }
+/// let expected = vec![ExpectedPair::new(Rule::a, 0, 2)
+///     .with_children(vec![ExpectedPair::new(Rule::b, 1, 2)])];
+///
+/// assert_eq!(check_parses_to(AbParser::parse, Rule::a, "ab", &expected), Ok(()));
+/// assert_eq!(
+///     check_parses_to(AbParser::parse, Rule::a, "ab", &expected[0].children),
+///     Err("expected Start { rule: b, pos: Position { pos: 1 } } but found \
+///          Start { rule: a, pos: Position { pos: 0 } }".to_owned())
+/// );
+/// ```
+pub fn check_parses_to<R, F>(
+    parse: F,
+    rule: R,
+    input: &str,
+    expected: &[ExpectedPair<R>],
+) -> Result<(), String>
+where
+    R: RuleType,
+    F: for<'i> FnOnce(R, &'i str) -> Result<Pairs<'i, R>, Error<R>>,
+{
+    let mut tokens = parse(rule, input)
+        .map_err(|error| format!("expected a successful parse but found\n{}", error))?
+        .tokens();
+
+    consumes_to(&mut tokens, expected)?;
+
+    let rest: Vec<_> = tokens.collect();
+
+    match rest.as_slice() {
+        [] => Ok(()),
+        [Token::Start { rule: first, .. }, Token::End { rule: second, .. }]
+            if format!("{:?}", first) == "EOI" && format!("{:?}", second) == "EOI" =>
+        {
+            Ok(())
+        }
+        _ => Err(format!("expected end of input, but found {:?}", rest)),
+    }
+}
+
+/// Asserts that parsing `input` from `rule` with `parse` produces exactly the `expected` pairs.
+/// See [`check_parses_to`].
+///
+/// [`check_parses_to`]: fn.check_parses_to.html
+///
+/// # Panics
+///
+/// Panics with the mismatch if the pairs differ from the `expected` ones.
+pub fn assert_parses_to<R, F>(parse: F, rule: R, input: &str, expected: &[ExpectedPair<R>])
+where
+    R: RuleType,
+    F: for<'i> FnOnce(R, &'i str) -> Result<Pairs<'i, R>, Error<R>>,
+{
+    if let Err(message) = check_parses_to(parse, rule, input, expected) {
+        panic!("{}", message);
+    }
+}
+
+/// Checks that parsing `input` from `rule` with `parse` fails at byte position `pos` with the given
+/// `positives` and `negatives`, like [`fails_with!`] does.
+///
+/// [`fails_with!`]: ../macro.fails_with.html
+pub fn check_fails_with<R, F>(
+    parse: F,
+    rule: R,
+    input: &str,
+    positives: &[R],
+    negatives: &[R],
+    pos: usize,
+) -> Result<(), String>
+where
+    R: RuleType,
+    F: for<'i> FnOnce(R, &'i str) -> Result<Pairs<'i, R>, Error<R>>,
+{
+    let error = match parse(rule, input) {
+        Ok(pairs) => return Err(format!("expected an error but found {}", pairs)),
+        Err(error) => error,
+    };
+
+    match error.variant {
+        ErrorVariant::ParsingError {
+            positives: ref found_positives,
+            negatives: ref found_negatives,
+        } => {
+            if found_positives.as_slice() != positives {
+                return Err(format!(
+                    "positives: expected {:?} but found {:?}",
+                    positives, found_positives
+                ));
+            }
+            if found_negatives.as_slice() != negatives {
+                return Err(format!(
+                    "negatives: expected {:?} but found {:?}",
+                    negatives, found_negatives
+                ));
+            }
+        }
+        ErrorVariant::CustomError { ref message } => {
+            return Err(format!("expected a parsing error but found {:?}", message))
+        }
+    }
+
+    match error.location {
+        InputLocation::Pos(found) if found == pos => Ok(()),
+        InputLocation::Pos(found) => Err(format!("pos: expected {} but found {}", pos, found)),
+        InputLocation::Span(span) => Err(format!("pos: expected {} but found {:?}", pos, span)),
+    }
+}
+
+/// Asserts that parsing `input` from `rule` with `parse` fails at byte position `pos` with the
+/// given `positives` and `negatives`. See [`check_fails_with`].
+///
+/// [`check_fails_with`]: fn.check_fails_with.html
+///
+/// # Panics
+///
+/// Panics with the mismatch if the parse does not fail as expected.
+pub fn assert_fails_with<R, F>(
+    parse: F,
+    rule: R,
+    input: &str,
+    positives: &[R],
+    negatives: &[R],
+    pos: usize,
+) where
+    R: RuleType,
+    F: for<'i> FnOnce(R, &'i str) -> Result<Pairs<'i, R>, Error<R>>,
+{
+    if let Err(message) = check_fails_with(parse, rule, input, positives, negatives, pos) {
+        panic!("{}", message);
+    }
+}
+
+fn consumes_to<'i, R: RuleType, I: Iterator<Item = Token<'i, R>>>(
+    tokens: &mut I,
+    expected: &[ExpectedPair<R>],
+) -> Result<(), String> {
+    for pair in expected {
+        consumes_token(tokens, "Start", pair.rule, pair.start)?;
+        consumes_to(tokens, &pair.children)?;
+        consumes_token(tokens, "End", pair.rule, pair.end)?;
+    }
+
+    Ok(())
+}
+
+fn consumes_token<'i, R: RuleType, I: Iterator<Item = Token<'i, R>>>(
+    tokens: &mut I,
+    kind: &str,
+    rule: R,
+    pos: usize,
+) -> Result<(), String> {
+    let expected = format!(
+        "expected {} {{ rule: {:?}, pos: Position {{ pos: {} }} }}",
+        kind, rule, pos
+    );
+
+    let (found_kind, found_rule, found_pos) = match tokens.next() {
+        Some(Token::Start { rule, pos }) => ("Start", rule, pos),
+        Some(Token::End { rule, pos }) => ("End", rule, pos),
+        None => return Err(format!("{} but found nothing", expected)),
+    };
+
+    if found_kind == kind && found_rule == rule && found_pos.pos() == pos {
+        Ok(())
+    } else {
+        Err(format!(
+            "{} but found {} {{ rule: {:?}, pos: Position {{ pos: {} }} }}",
+            expected,
+            found_kind,
+            found_rule,
+            found_pos.pos()
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::macros::tests::{AbcParser, Rule};
+    use crate::Parser;
+    use alloc::borrow::ToOwned;
+    use alloc::vec;
+
+    fn expected() -> Vec<ExpectedPair<Rule>> {
+        vec![
+            ExpectedPair::new(Rule::a, 0, 3).with_children(vec![ExpectedPair::new(Rule::b, 1, 2)]),
+            ExpectedPair::new(Rule::c, 4, 5),
+        ]
+    }
+
+    #[test]
+    fn parses_to() {
+        assert_parses_to(AbcParser::parse, Rule::a, "abcde", &expected());
+    }
+
+    #[test]
+    fn parses_to_mismatch() {
+        let mut expected = expected();
+        expected[0].children[0].end = 3;
+
+        assert_eq!(
+            check_parses_to(AbcParser::parse, Rule::a, "abcde", &expected),
+            Err(
+                "expected End { rule: b, pos: Position { pos: 3 } } but found \
+                 End { rule: b, pos: Position { pos: 2 } }"
+                    .to_owned()
+            )
+        );
+    }
+
+    #[test]
+    fn parses_to_missing() {
+        let mut expected = expected();
+        expected.pop();
+
+        assert_eq!(
+            check_parses_to(AbcParser::parse, Rule::a, "abcde", &expected),
+            Err(
+                "expected end of input, but found [Start { rule: c, pos: Position { pos: 4 } }, \
+                 End { rule: c, pos: Position { pos: 5 } }]"
+                    .to_owned()
+            )
+        );
+    }
+
+    #[test]
+    fn fails_with() {
+        assert_fails_with(AbcParser::parse, Rule::a, "abcdf", &[Rule::c], &[], 4);
+    }
+
+    #[test]
+    #[should_panic(expected = "positives: expected [b] but found [c]")]
+    fn fails_with_wrong_positives() {
+        assert_fails_with(AbcParser::parse, Rule::a, "abcdf", &[Rule::b], &[], 4);
+    }
+
+    #[test]
+    fn fails_with_wrong_pos() {
+        assert_eq!(
+            check_fails_with(AbcParser::parse, Rule::a, "abcdf", &[Rule::c], &[], 3),
+            Err("pos: expected 3 but found 4".to_owned())
+        );
+    }
+}
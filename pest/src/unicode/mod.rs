@@ -6,6 +6,7 @@
 #![allow(clippy::all)]
 
 use alloc::boxed::Box;
+use core::sync::atomic::{AtomicUsize, Ordering};
 
 macro_rules! char_property_functions {
     {$(
@@ -51,6 +52,25 @@ char_property_functions! {
     ];
 }
 
+// The number of code points found by the last `warm_up`, stored so that it is not optimized away.
+static WARM_UP: AtomicUsize = AtomicUsize::new(0);
+
+/// Looks up every part of the table of `property`, e.g. `LETTER`, so that the first parse matching
+/// it does not page it in. Generated parsers do this for the properties of their grammar in
+/// [`Parser::warm_up`].
+///
+/// [`Parser::warm_up`]: ../trait.Parser.html#method.warm_up
+pub fn warm_up(property: fn(char) -> bool) {
+    // The tables store code points by blocks of 64.
+    let found = (0..=0x10ffff)
+        .step_by(64)
+        .filter_map(char::from_u32)
+        .filter(|&c| property(c))
+        .count();
+
+    WARM_UP.store(found, Ordering::Relaxed);
+}
+
 pub fn by_name(name: &str) -> Option<Box<dyn Fn(char) -> bool>> {
     for property in binary::BY_NAME {
         if name == property.0.to_uppercase() {
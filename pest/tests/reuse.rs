@@ -0,0 +1,81 @@
+// pest. The Elegant Parser
+// Copyright (c) 2018 Dragoș Tiselice
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+
+use pest::iterators::Pairs;
+use pest::ParseReuse;
+
+// Counts the allocations made by the current thread, so that those of the test harness are left
+// out.
+struct Counting;
+
+thread_local! {
+    static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.with(|count| count.set(count.get() + 1));
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.with(|count| count.set(count.get() + 1));
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static COUNTING: Counting = Counting;
+
+fn allocations() -> usize {
+    ALLOCATIONS.with(|count| count.get())
+}
+
+fn parse<'i>(reuse: &mut ParseReuse<&'static str>, input: &'i str) -> Pairs<'i, &'static str> {
+    pest::state_reusing(input, reuse, |state| {
+        state.sequence(|state| {
+            state
+                .rule("key", |state| state.match_string("a"))
+                .and_then(|state| state.match_string("="))
+                .and_then(|state| {
+                    state.repeat(|state| {
+                        state
+                            .rule("digit", |state| state.match_range('0'..'9'))
+                            .or_else(|state| state.rule("letter", |state| state.match_string("x")))
+                    })
+                })
+        })
+    })
+    .unwrap()
+}
+
+#[test]
+fn repeated_parse_does_not_allocate() {
+    let mut reuse = ParseReuse::new();
+    let before = allocations();
+    let pairs = parse(&mut reuse, "a=12x3");
+    assert!(allocations() > before);
+    assert!(reuse.recycle(pairs));
+
+    let before = allocations();
+    for _ in 0..10 {
+        let pairs = parse(&mut reuse, "a=12x3");
+        assert_eq!(pairs.clone().count(), 5);
+        assert!(reuse.recycle(pairs));
+    }
+
+    assert_eq!(allocations() - before, 0);
+}
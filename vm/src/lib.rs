@@ -16,17 +16,20 @@
 )]
 #![warn(missing_docs, rust_2018_idioms, unused_qualifications)]
 
+use pest::dyn_parser::{self, DynError, DynPairs, DynParser};
 use pest::error::Error;
 use pest::iterators::Pairs;
 use pest::{unicode, Position};
-use pest::{Atomicity, MatchDir, ParseResult, ParserState};
+use pest::{Atomicity, MatchDir, ParseResult, ParseStats, ParserState};
 use pest_meta::ast::RuleType;
 use pest_meta::optimizer::{OptimizedExpr, OptimizedRule};
 
+use std::any::Any;
 use std::collections::HashMap;
 use std::panic::{RefUnwindSafe, UnwindSafe};
 
 mod macros;
+pub mod mutation;
 
 /// A callback function that is called when a rule is matched.
 /// The first argument is the name of the rule and the second is the span of the rule.
@@ -35,10 +38,50 @@ mod macros;
 type ListenerFn =
     Box<dyn Fn(String, &Position<'_>) -> bool + Sync + Send + RefUnwindSafe + UnwindSafe>;
 
+/// A callback function that is called when a rule finishes, registered with
+/// [`Vm::set_exit_listener`]. The arguments are the name of the rule, the position after the rule,
+/// which is where it started if it failed, and whether it matched.
+///
+/// [`Vm::set_exit_listener`]: struct.Vm.html#method.set_exit_listener
+type ExitListenerFn =
+    Box<dyn Fn(String, &Position<'_>, bool) + Sync + Send + RefUnwindSafe + UnwindSafe>;
+
+/// A custom built-in rule registered with [`Vm::add_builtin`].
+///
+/// [`Vm::add_builtin`]: struct.Vm.html#method.add_builtin
+type BuiltinFn = Box<
+    dyn for<'a, 'i> Fn(
+            &'a Vm,
+            Box<ParserState<'i, &'a str>>,
+        ) -> ParseResult<Box<ParserState<'i, &'a str>>>
+        + Sync
+        + Send
+        + RefUnwindSafe
+        + UnwindSafe,
+>;
+
+/// A semantic predicate registered with [`Vm::add_predicate`].
+///
+/// [`Vm::add_predicate`]: struct.Vm.html#method.add_predicate
+type PredicateFn = Box<dyn Fn(&str, &dyn Any) -> bool + Sync + Send + RefUnwindSafe + UnwindSafe>;
+
+/// A parser bound to an `@extern` rule with [`Vm::bind`], along with the name of its start rule.
+///
+/// [`Vm::bind`]: struct.Vm.html#method.bind
+type ExternParser = (
+    Box<dyn DynParser + Sync + Send + RefUnwindSafe + UnwindSafe>,
+    String,
+);
+
 /// A virtual machine-like construct that runs an AST on-the-fly
 pub struct Vm {
     rules: HashMap<String, OptimizedRule>,
+    builtins: HashMap<String, BuiltinFn>,
+    externs: HashMap<String, ExternParser>,
+    aliases: HashMap<String, String>,
+    predicates: HashMap<String, PredicateFn>,
     listener: Option<ListenerFn>,
+    exit_listener: Option<ExitListenerFn>,
 }
 
 impl Vm {
@@ -47,7 +90,12 @@ impl Vm {
         let rules = rules.into_iter().map(|r| (r.name.clone(), r)).collect();
         Vm {
             rules,
+            builtins: HashMap::new(),
+            externs: HashMap::new(),
+            aliases: HashMap::new(),
+            predicates: HashMap::new(),
             listener: None,
+            exit_listener: None,
         }
     }
 
@@ -58,10 +106,192 @@ impl Vm {
         let rules = rules.into_iter().map(|r| (r.name.clone(), r)).collect();
         Vm {
             rules,
+            builtins: HashMap::new(),
+            externs: HashMap::new(),
+            aliases: HashMap::new(),
+            predicates: HashMap::new(),
             listener: Some(listener),
+            exit_listener: None,
         }
     }
 
+    /// Registers a custom built-in rule named `name`, which can then be called from the grammar
+    /// like any other rule. Custom built-in rules take precedence over grammar rules of the same
+    /// name, but not over pest's own built-in rules.
+    ///
+    /// `builtin` is called with the `Vm`, so that it can run grammar rules itself through
+    /// [`Vm::parse_rule`]. Like with other built-in rules, no pair is produced unless `builtin`
+    /// creates one with [`ParserState::rule`]. Grammars calling `name` are validated with
+    /// [`pest_meta::parse_and_optimize_with_builtins`], which does not report it as undefined.
+    ///
+    /// [`pest_meta::parse_and_optimize_with_builtins`]: ../pest_meta/fn.parse_and_optimize_with_builtins.html
+    /// [`Vm::parse_rule`]: struct.Vm.html#method.parse_rule
+    /// [`ParserState::rule`]: ../pest/struct.ParserState.html#method.rule
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use pest_vm::Vm;
+    /// let grammar = "id = { \"#\" ~ HEX }";
+    /// let (_, rules) = pest_meta::parse_and_optimize_with_builtins(grammar, &["HEX"]).unwrap();
+    /// let mut vm = Vm::new(rules);
+    ///
+    /// vm.add_builtin("HEX", |_, state| {
+    ///     state.rule("HEX", |state| {
+    ///         state.repeat(|state| state.match_char_by(|c| c.is_ascii_hexdigit()))
+    ///     })
+    /// });
+    ///
+    /// let pairs = vm.parse("id", "#c0ffee").unwrap();
+    /// assert_eq!(pairs.flatten().map(|pair| pair.as_rule()).collect::<Vec<_>>(), ["id", "HEX"]);
+    /// ```
+    pub fn add_builtin<F>(&mut self, name: &str, builtin: F) -> &mut Vm
+    where
+        F: for<'a, 'i> Fn(
+                &'a Vm,
+                Box<ParserState<'i, &'a str>>,
+            ) -> ParseResult<Box<ParserState<'i, &'a str>>>
+            + Sync
+            + Send
+            + RefUnwindSafe
+            + UnwindSafe
+            + 'static,
+    {
+        self.builtins.insert(name.to_owned(), Box::new(builtin));
+        self
+    }
+
+    /// Binds the `@extern` rule `rule` to another `parser`, so that `rule` parses the input from
+    /// the rule named `start_rule` of `parser`. The pairs of `parser` are grafted into the pair of
+    /// `rule` with their positions in the outer input. The extern rules declared in a grammar can
+    /// be found with [`parser::consume_externs`].
+    ///
+    /// [`parser::consume_externs`]: ../pest_meta/parser/fn.consume_externs.html
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use pest_meta::{optimizer, parser};
+    /// # use pest_vm::Vm;
+    /// let vm = |grammar| {
+    ///     let pairs = parser::parse(parser::Rule::grammar_rules, grammar).unwrap();
+    ///     Vm::new(optimizer::optimize(parser::consume_rules(pairs).unwrap()))
+    /// };
+    ///
+    /// let mut template = vm("@extern { expr }\ntemplate = { \"{{\" ~ expr ~ \"}}\" }");
+    /// template.bind("expr", vm("number = { ASCII_DIGIT+ }"), "number");
+    ///
+    /// let pairs = template.parse("template", "{{42}}").unwrap();
+    /// assert_eq!(
+    ///     pairs.flatten().map(|pair| (pair.as_rule(), pair.as_str())).collect::<Vec<_>>(),
+    ///     [("template", "{{42}}"), ("expr", "42"), ("number", "42")]
+    /// );
+    /// ```
+    pub fn bind<P>(&mut self, rule: &str, parser: P, start_rule: &str) -> &mut Vm
+    where
+        P: DynParser + Sync + Send + RefUnwindSafe + UnwindSafe + 'static,
+    {
+        self.externs
+            .insert(rule.to_owned(), (Box::new(parser), start_rule.to_owned()));
+        self
+    }
+
+    /// Registers the semantic predicate named `name`, which the grammar calls with `#{name} e`:
+    /// after `e` matches, `predicate` is called with the input it matched and the context of the
+    /// parse, given to [`Vm::parse_with_context`], and `e` fails unless it returns `true`.
+    /// Parsing a grammar which calls an unregistered predicate panics.
+    ///
+    /// [`Vm::parse_with_context`]: struct.Vm.html#method.parse_with_context
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use pest_meta::{optimizer, parser};
+    /// # use pest_vm::Vm;
+    /// let grammar = "declaration = { #{is_type_name} name ~ \" \" ~ name }\nname = { ASCII_ALPHA+ }";
+    /// let pairs = parser::parse(parser::Rule::grammar_rules, grammar).unwrap();
+    /// let mut vm = Vm::new(optimizer::optimize(parser::consume_rules(pairs).unwrap()));
+    ///
+    /// vm.add_predicate("is_type_name", |text, context| {
+    ///     context.downcast_ref::<Vec<&str>>().unwrap().contains(&text)
+    /// });
+    ///
+    /// let type_names = vec!["size"];
+    /// assert!(vm.parse_with_context("declaration", "size n", &type_names).is_ok());
+    /// assert!(vm.parse_with_context("declaration", "count n", &type_names).is_err());
+    /// ```
+    pub fn add_predicate<F>(&mut self, name: &str, predicate: F) -> &mut Vm
+    where
+        F: Fn(&str, &dyn Any) -> bool + Sync + Send + RefUnwindSafe + UnwindSafe + 'static,
+    {
+        self.predicates.insert(name.to_owned(), Box::new(predicate));
+        self
+    }
+
+    /// Makes the pairs of the `rule` rule carry the name of `alias` instead of its own, like with
+    /// an `@alias` declaration. The aliases declared in a grammar can be registered from
+    /// [`parser::alias_names`].
+    ///
+    /// [`parser::alias_names`]: ../pest_meta/parser/fn.alias_names.html
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use pest_meta::{optimizer, parser};
+    /// # use pest_vm::Vm;
+    /// let grammar = "@alias(number) { int float }\nint = { ASCII_DIGIT+ }\nfloat = { int ~ \".\" ~ int }";
+    /// let pairs = parser::parse(parser::Rule::grammar_rules, grammar).unwrap();
+    /// let aliases = parser::alias_names(&parser::consume_aliases(pairs.clone()));
+    /// let ast = parser::consume_rules(pairs).unwrap();
+    /// let mut vm = Vm::new(optimizer::optimize(ast));
+    ///
+    /// for (rule, alias) in &aliases {
+    ///     vm.add_alias(rule, alias);
+    /// }
+    ///
+    /// let pairs = vm.parse("float", "1.5").unwrap();
+    /// assert_eq!(
+    ///     pairs.flatten().map(|pair| pair.as_rule()).collect::<Vec<_>>(),
+    ///     ["number", "number", "number"]
+    /// );
+    /// ```
+    pub fn add_alias(&mut self, rule: &str, alias: &str) -> &mut Vm {
+        self.aliases.insert(rule.to_owned(), alias.to_owned());
+        self
+    }
+
+    /// Sets a listener function that is called whenever a rule finishes, which, along with the
+    /// listener of [`Vm::new_with_listener`], traces the whole parse.
+    /// (used by the `pest_debugger` crate)
+    ///
+    /// [`Vm::new_with_listener`]: struct.Vm.html#method.new_with_listener
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use pest_meta::{optimizer, parser};
+    /// # use pest_vm::Vm;
+    /// # use std::sync::{Arc, Mutex};
+    /// let pairs = parser::parse(parser::Rule::grammar_rules, "ab = { \"a\" ~ \"b\" }").unwrap();
+    /// let mut vm = Vm::new(optimizer::optimize(parser::consume_rules(pairs).unwrap()));
+    ///
+    /// let exits = Arc::new(Mutex::new(vec![]));
+    /// let recorded = Arc::clone(&exits);
+    /// vm.set_exit_listener(move |rule, pos, matched| {
+    ///     recorded.lock().unwrap().push((rule, pos.pos(), matched));
+    /// });
+    ///
+    /// assert!(vm.parse("ab", "ac").is_err());
+    /// assert_eq!(*exits.lock().unwrap(), [("ab".to_owned(), 0, false)]);
+    /// ```
+    pub fn set_exit_listener<F>(&mut self, listener: F) -> &mut Vm
+    where
+        F: Fn(String, &Position<'_>, bool) + Sync + Send + RefUnwindSafe + UnwindSafe + 'static,
+    {
+        self.exit_listener = Some(Box::new(listener));
+        self
+    }
+
     /// Runs a parser rule on an input
     #[allow(clippy::perf)]
     pub fn parse<'a, 'i>(
@@ -72,8 +302,67 @@ impl Vm {
         pest::state(input, |state| self.parse_rule(rule, state))
     }
 
-    #[allow(clippy::suspicious)]
-    fn parse_rule<'a, 'i>(
+    /// Runs a parser rule on an input, handing `context` to the semantic predicates registered
+    /// with [`Vm::add_predicate`]
+    ///
+    /// [`Vm::add_predicate`]: struct.Vm.html#method.add_predicate
+    #[allow(clippy::perf)]
+    pub fn parse_with_context<'a, 'i>(
+        &'a self,
+        rule: &'a str,
+        input: &'i str,
+        context: &'i dyn Any,
+    ) -> Result<Pairs<'i, &'a str>, Error<&'a str>> {
+        pest::state(input, |state| {
+            self.parse_rule(rule, state.with_context(context))
+        })
+    }
+
+    /// Runs a parser rule on an input in the grammar's dialect `dialect`, which selects the
+    /// definitions of the rules annotated with `@dialect`
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use pest_meta::{optimizer, parser};
+    /// # use pest_vm::Vm;
+    /// let grammar = "int = @{ ASCII_DIGIT+ }\n@dialect(lenient) int = @{ (ASCII_DIGIT | \"_\")+ }";
+    /// let pairs = parser::parse(parser::Rule::grammar_rules, grammar).unwrap();
+    /// let vm = Vm::new(optimizer::optimize(parser::consume_rules(pairs).unwrap()));
+    ///
+    /// assert_eq!(vm.parse("int", "1_000").unwrap().as_str(), "1");
+    /// assert_eq!(vm.parse_with_dialect("int", "1_000", "lenient").unwrap().as_str(), "1_000");
+    /// ```
+    #[allow(clippy::perf)]
+    pub fn parse_with_dialect<'a, 'i>(
+        &'a self,
+        rule: &'a str,
+        input: &'i str,
+        dialect: &'i str,
+    ) -> Result<Pairs<'i, &'a str>, Error<&'a str>> {
+        pest::state(input, |state| {
+            self.parse_rule(rule, state.with_dialect(dialect))
+        })
+    }
+
+    /// Runs a parser rule on an input, also reporting the memory allocated by the parse
+    #[allow(clippy::perf)]
+    pub fn parse_with_stats<'a, 'i>(
+        &'a self,
+        rule: &'a str,
+        input: &'i str,
+    ) -> (Result<Pairs<'i, &'a str>, Error<&'a str>>, ParseStats) {
+        pest::state_with_stats(input, |state| self.parse_rule(rule, state))
+    }
+
+    /// Runs the rule named `rule` on a `ParserState`, which makes it possible to enter a grammar
+    /// rule from a custom built-in rule.
+    ///
+    /// # Panics
+    ///
+    /// Panics if no rule named `rule` exists.
+    #[allow(clippy::suspicious, clippy::perf)]
+    pub fn parse_rule<'a, 'i>(
         &'a self,
         rule: &'a str,
         state: Box<ParserState<'i, &'a str>>,
@@ -83,6 +372,26 @@ impl Vm {
                 return Err(ParserState::new(state.position().line_of()));
             }
         }
+
+        match self.exit_listener {
+            Some(ref listener) => {
+                let result = self.dispatch_rule(rule, state);
+                match result {
+                    Ok(ref state) => listener(rule.to_owned(), state.position(), true),
+                    Err(ref state) => listener(rule.to_owned(), state.position(), false),
+                }
+                result
+            }
+            None => self.dispatch_rule(rule, state),
+        }
+    }
+
+    #[allow(clippy::suspicious, clippy::perf)]
+    fn dispatch_rule<'a, 'i>(
+        &'a self,
+        rule: &'a str,
+        state: Box<ParserState<'i, &'a str>>,
+    ) -> ParseResult<Box<ParserState<'i, &'a str>>> {
         match rule {
             "ANY" => return state.skip(1),
             "EOI" => return state.rule("EOI", |state| state.end_of_input()),
@@ -125,10 +434,22 @@ impl Vm {
             _ => (),
         };
 
+        if let Some(builtin) = self.builtins.get(rule) {
+            return builtin(self, state);
+        }
+
+        if let Some((parser, start_rule)) = self.externs.get(rule) {
+            return state.rule(rule, |state| {
+                state.nested(|input| parser.parse_dyn(start_rule, input), Some)
+            });
+        }
+
         if let Some(rule) = self.rules.get(rule) {
+            let name = self.aliases.get(&rule.name).unwrap_or(&rule.name);
+
             if rule.name == "WHITESPACE" || rule.name == "COMMENT" {
                 match rule.ty {
-                    RuleType::Normal => state.rule(&rule.name, |state| {
+                    RuleType::Normal => state.rule(name, |state| {
                         state.atomic(Atomicity::Atomic, |state| {
                             self.parse_expr(&rule.expr, state)
                         })
@@ -136,40 +457,48 @@ impl Vm {
                     RuleType::Silent => state.atomic(Atomicity::Atomic, |state| {
                         self.parse_expr(&rule.expr, state)
                     }),
-                    RuleType::Atomic => state.rule(&rule.name, |state| {
+                    RuleType::Atomic => state.rule(name, |state| {
                         state.atomic(Atomicity::Atomic, |state| {
                             self.parse_expr(&rule.expr, state)
                         })
                     }),
                     RuleType::CompoundAtomic => state.atomic(Atomicity::CompoundAtomic, |state| {
-                        state.rule(&rule.name, |state| self.parse_expr(&rule.expr, state))
+                        state.rule(name, |state| self.parse_expr(&rule.expr, state))
                     }),
                     RuleType::NonAtomic => state.atomic(Atomicity::Atomic, |state| {
-                        state.rule(&rule.name, |state| self.parse_expr(&rule.expr, state))
+                        state.rule(name, |state| self.parse_expr(&rule.expr, state))
                     }),
                 }
             } else {
                 match rule.ty {
                     RuleType::Normal => {
-                        state.rule(&rule.name, move |state| self.parse_expr(&rule.expr, state))
+                        state.rule(name, move |state| self.parse_expr(&rule.expr, state))
                     }
                     RuleType::Silent => self.parse_expr(&rule.expr, state),
-                    RuleType::Atomic => state.rule(&rule.name, move |state| {
+                    RuleType::Atomic => state.rule(name, move |state| {
                         state.atomic(Atomicity::Atomic, move |state| {
                             self.parse_expr(&rule.expr, state)
                         })
                     }),
-                    RuleType::CompoundAtomic => {
-                        state.atomic(Atomicity::CompoundAtomic, move |state| {
-                            state.rule(&rule.name, |state| self.parse_expr(&rule.expr, state))
-                        })
-                    }
+                    RuleType::CompoundAtomic => state
+                        .atomic(Atomicity::CompoundAtomic, move |state| {
+                            state.rule(name, |state| self.parse_expr(&rule.expr, state))
+                        }),
                     RuleType::NonAtomic => state.atomic(Atomicity::NonAtomic, move |state| {
-                        state.rule(&rule.name, |state| self.parse_expr(&rule.expr, state))
+                        state.rule(name, |state| self.parse_expr(&rule.expr, state))
                     }),
                 }
             }
         } else {
+            // The indentation builtins are not keywords, so a grammar may define rules with their
+            // names, which take precedence.
+            match rule {
+                "PUSH_INDENT" => return state.stack_push_indent(),
+                "MATCH_INDENT" => return state.stack_match_indent(),
+                "DEDENT" => return state.stack_dedent(),
+                _ => (),
+            };
+
             if let Some(property) = unicode::by_name(rule) {
                 // std::boxed::Box<dyn std::ops::Fn(char) -> bool> is not FnOnce(char)->bool
                 return state.match_char_by(property);
@@ -203,6 +532,9 @@ impl Vm {
             OptimizedExpr::NegPred(ref expr) => {
                 state.lookahead(false, |state| self.parse_expr(expr, state))
             }
+            OptimizedExpr::PosLookbehind(ref expr, width) => state.lookbehind(width, |state| {
+                state.atomic(Atomicity::Atomic, |state| self.parse_expr(expr, state))
+            }),
             OptimizedExpr::Seq(ref lhs, ref rhs) => state.sequence(|state| {
                 self.parse_expr(lhs, state)
                     .and_then(|state| self.skip(state))
@@ -231,9 +563,28 @@ impl Vm {
                     .map(|state| state.as_str())
                     .collect::<Vec<&str>>(),
             ),
+            OptimizedExpr::Complement(ref ranges) => {
+                state.match_char_by(|c| !ranges.iter().any(|&(start, end)| start <= c && c <= end))
+            }
             OptimizedExpr::RestoreOnErr(ref expr) => {
                 state.restore_on_err(|state| self.parse_expr(expr, state))
             }
+            OptimizedExpr::Labeled(ref label, ref expr) => {
+                state.labeled(label, |state| self.parse_expr(expr, state))
+            }
+            OptimizedExpr::Predicate(ref name, ref expr) => {
+                let predicate = self
+                    .predicates
+                    .get(name)
+                    .unwrap_or_else(|| panic!("predicate `{}` is not registered", name));
+
+                state.predicate(|state| self.parse_expr(expr, state), predicate)
+            }
+            OptimizedExpr::Dialect(ref dialects, ref then, ref otherwise) => state.in_dialect(
+                &dialects.iter().map(String::as_str).collect::<Vec<_>>(),
+                |state| self.parse_expr(then, state),
+                |state| self.parse_expr(otherwise, state),
+            ),
         }
     }
 
@@ -284,3 +635,22 @@ impl Vm {
         }
     }
 }
+
+impl DynParser for Vm {
+    fn rule_names(&self) -> Vec<&str> {
+        let mut names: Vec<_> = self.rules.keys().map(String::as_str).collect();
+        names.sort_unstable();
+        names
+    }
+
+    fn parse_dyn<'a, 'i>(
+        &'a self,
+        rule_name: &str,
+        input: &'i str,
+    ) -> Result<DynPairs<'i, 'a>, DynError<'a>> {
+        match self.rules.get_key_value(rule_name) {
+            Some((rule, _)) => self.parse(rule, input),
+            None => Err(dyn_parser::unknown_rule(rule_name, input)),
+        }
+    }
+}
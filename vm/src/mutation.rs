@@ -0,0 +1,123 @@
+// pest. The Elegant Parser
+// Copyright (c) 2018 Dragoș Tiselice
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Mutation testing of grammars, which runs the test inputs of a grammar against its mutants and
+//! reports the mutants which no input tells apart from the original grammar.
+//!
+//! The original grammar is the oracle: an input catches a mutant if the mutant parses it into
+//! different pairs, or fails to parse it while the original grammar succeeds, or the other way
+//! around. Where a failed parse stops is not compared, so a surviving mutant always points at a
+//! change that tests only checking the pairs of successful parses would miss too.
+
+use pest::error::Error;
+use pest_meta::ast::Rule;
+use pest_meta::mutation::{self, Mutant};
+use pest_meta::{optimizer, parser};
+
+use crate::Vm;
+
+/// An input of a test of a grammar, to be parsed from the rule named `rule`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct TestCase {
+    /// The name of the rule to parse from
+    pub rule: String,
+    /// The input to parse
+    pub input: String,
+}
+
+impl TestCase {
+    /// Creates a `TestCase` parsing `input` from the rule named `rule`.
+    pub fn new(rule: &str, input: &str) -> TestCase {
+        TestCase {
+            rule: rule.to_owned(),
+            input: input.to_owned(),
+        }
+    }
+}
+
+/// The result of running test cases against the mutants of a grammar.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct MutationReport {
+    /// The number of mutants which were run
+    pub mutants: usize,
+    /// The mutants which no test case caught, in the order of their rules
+    pub survivors: Vec<Mutant>,
+}
+
+impl MutationReport {
+    /// Returns the number of mutants caught by at least one test case.
+    pub fn killed(&self) -> usize {
+        self.mutants - self.survivors.len()
+    }
+}
+
+/// Runs `cases` against every mutant of `rules`, as generated by [`mutation::mutants`].
+///
+/// [`mutation::mutants`]: ../../pest_meta/mutation/fn.mutants.html
+///
+/// # Panics
+///
+/// Panics if a test case parses from a rule which does not exist.
+///
+/// # Examples
+///
+/// ```
+/// # use pest_vm::mutation::{run_grammar, TestCase};
+/// let grammar = r#"number = { "-"? ~ ('0'..'9')+ }"#;
+///
+/// let cases = [TestCase::new("number", "42"), TestCase::new("number", "-7")];
+/// let report = run_grammar(grammar, &cases).unwrap();
+///
+/// assert_eq!(report.mutants, 2);
+/// assert_eq!(report.killed(), 1);
+/// // Nothing tests the characters around the digits, so their range can be widened.
+/// assert_eq!(
+///     report.survivors[0].to_string(),
+///     "rule number, mutation #2: widened a range to '/'..':'"
+/// );
+/// ```
+pub fn run(rules: &[Rule], cases: &[TestCase]) -> MutationReport {
+    let original = outcomes(rules.to_vec(), cases);
+    let mutants = mutation::mutants(rules);
+
+    MutationReport {
+        mutants: mutants.len(),
+        survivors: mutants
+            .into_iter()
+            .filter(|mutant| outcomes(mutant.rules.clone(), cases) == original)
+            .collect(),
+    }
+}
+
+/// Parses `grammar` and runs `cases` against the mutants of its rules like [`run`].
+///
+/// [`run`]: fn.run.html
+#[allow(clippy::perf)]
+pub fn run_grammar(
+    grammar: &str,
+    cases: &[TestCase],
+) -> Result<MutationReport, Vec<Error<parser::Rule>>> {
+    let pairs = parser::parse(parser::Rule::grammar_rules, grammar).map_err(|error| vec![error])?;
+
+    Ok(run(&parser::consume_rules(pairs)?, cases))
+}
+
+// Returns the pairs of every case, or `None` for those that fail to parse.
+fn outcomes(rules: Vec<Rule>, cases: &[TestCase]) -> Vec<Option<String>> {
+    let vm = Vm::new(optimizer::optimize(rules));
+
+    cases
+        .iter()
+        .map(|case| {
+            vm.parse(&case.rule, &case.input)
+                .ok()
+                .map(|pairs| pairs.to_string())
+        })
+        .collect()
+}
@@ -0,0 +1,147 @@
+// pest. The Elegant Parser
+// Copyright (c) 2018 Dragoș Tiselice
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+extern crate pest;
+extern crate pest_meta;
+extern crate pest_vm;
+
+use pest_meta::parser::Rule;
+use pest_meta::{optimizer, parser};
+use pest_vm::Vm;
+
+fn vm(grammar: &str) -> Vm {
+    let pairs = parser::parse(Rule::grammar_rules, grammar).unwrap();
+    let ast = parser::consume_rules(pairs).unwrap();
+    Vm::new(optimizer::optimize(ast))
+}
+
+fn uuid() -> Vm {
+    let mut vm = vm("ids = { UUID ~ (\",\" ~ UUID)* ~ EOI }");
+    vm.add_builtin("UUID", |_, state| {
+        state.rule("UUID", |state| {
+            state.sequence(|state| {
+                [8, 4, 4, 4, 12]
+                    .iter()
+                    .enumerate()
+                    .try_fold(state, |state, (i, &len)| {
+                        let state = if i > 0 {
+                            state.match_string("-")?
+                        } else {
+                            state
+                        };
+                        (0..len).try_fold(state, |state, _| {
+                            state.match_char_by(|c| c.is_ascii_hexdigit())
+                        })
+                    })
+            })
+        })
+    });
+    vm
+}
+
+#[test]
+fn builtin() {
+    let input = "123e4567-e89b-12d3-a456-426614174000,00000000-0000-0000-0000-000000000000";
+    let vm = uuid();
+    let pairs = vm.parse("ids", input).unwrap();
+
+    assert_eq!(
+        pairs
+            .flatten()
+            .map(|pair| (pair.as_rule(), pair.as_str().len()))
+            .collect::<Vec<_>>(),
+        [("ids", input.len()), ("UUID", 36), ("UUID", 36), ("EOI", 0)]
+    );
+}
+
+#[test]
+fn builtin_error() {
+    let vm = uuid();
+    let error = vm
+        .parse("ids", "123e4567-e89b-12d3-a456-42661417400")
+        .unwrap_err();
+
+    assert_eq!(
+        format!("{}", error),
+        [
+            " --> 1:1",
+            "  |",
+            "1 | 123e4567-e89b-12d3-a456-42661417400",
+            "  | ^---",
+            "  |",
+            "  = expected \"UUID\"",
+        ]
+        .join("\n")
+    );
+}
+
+#[test]
+fn builtin_calls_rule() {
+    let mut vm = vm("list = { \"[\" ~ ITEMS ~ \"]\" }\nitem = { ASCII_DIGIT+ }");
+    vm.add_builtin("ITEMS", |vm, state| {
+        state.sequence(|state| {
+            vm.parse_rule("item", state).and_then(|state| {
+                state.repeat(|state| {
+                    state.sequence(|state| {
+                        state
+                            .match_string(",")
+                            .and_then(|state| vm.parse_rule("item", state))
+                    })
+                })
+            })
+        })
+    });
+
+    let pairs = vm.parse("list", "[1,23]").unwrap();
+
+    assert_eq!(
+        pairs
+            .flatten()
+            .map(|pair| pair.as_str())
+            .collect::<Vec<_>>(),
+        ["[1,23]", "1", "23"]
+    );
+}
+
+#[test]
+fn builtin_overrides_rule() {
+    let mut vm = vm("a = { b }\nb = { \"b\" }");
+    vm.add_builtin("b", |_, state| state.match_string("c"));
+
+    assert!(vm.parse("a", "b").is_err());
+    assert!(vm.parse("a", "c").is_ok());
+}
+
+#[test]
+fn builtin_validation() {
+    let grammar = "ids = { UUID ~ (\",\" ~ UUID)* ~ EOI }";
+    assert!(pest_meta::parse_and_optimize(grammar).is_err());
+
+    let (defaults, rules) =
+        pest_meta::parse_and_optimize_with_builtins(grammar, &["UUID"]).unwrap();
+    assert_eq!(defaults, ["EOI"]);
+
+    let mut vm = Vm::new(rules);
+    vm.add_builtin("UUID", |_, state| state.match_string("uuid"));
+    assert!(vm.parse("ids", "uuid,uuid").is_ok());
+}
+
+#[test]
+fn defined_indent_builtin() {
+    let vm = vm("DEDENT = { \"x\" } a = { DEDENT ~ EOI }");
+    let pairs = vm.parse("a", "x").unwrap();
+
+    assert_eq!(
+        pairs
+            .flatten()
+            .map(|pair| (pair.as_rule(), pair.as_str()))
+            .collect::<Vec<_>>(),
+        [("a", "x"), ("DEDENT", "x"), ("EOI", "")]
+    );
+}
@@ -20,8 +20,13 @@ const GRAMMAR: &str = include_str!("grammar.pest");
 
 fn vm() -> Vm {
     let pairs = parser::parse(Rule::grammar_rules, GRAMMAR).unwrap();
+    let aliases = parser::alias_names(&parser::consume_aliases(pairs.clone()));
     let ast = parser::consume_rules(pairs).unwrap();
-    Vm::new(optimizer::optimize(ast))
+    let mut vm = Vm::new(optimizer::optimize(ast));
+    for (rule, alias) in &aliases {
+        vm.add_alias(rule, alias);
+    }
+    vm
 }
 
 #[test]
@@ -36,6 +41,30 @@ fn string() {
     };
 }
 
+#[test]
+fn escaped_string() {
+    parses_to! {
+        parser: vm(),
+        input: "ab",
+        rule: "escaped_string",
+        tokens: [
+            escaped_string(0, 2)
+        ]
+    };
+}
+
+#[test]
+fn raw_string() {
+    parses_to! {
+        parser: vm(),
+        input: "a\"\\",
+        rule: "raw_string",
+        tokens: [
+            raw_string(0, 3)
+        ]
+    };
+}
+
 #[test]
 fn insensitive() {
     parses_to! {
@@ -110,6 +139,120 @@ fn double_neg_pred() {
     };
 }
 
+#[test]
+fn lookbehind() {
+    parses_to! {
+        parser: vm(),
+        input: "abc1",
+        rule: "lookbehind",
+        tokens: [
+            lookbehind(0, 4, [
+                string(0, 3),
+                range(3, 4)
+            ])
+        ]
+    };
+}
+
+#[test]
+#[should_panic]
+fn lookbehind_space() {
+    parses_to! {
+        parser: vm(),
+        input: "abc 1",
+        rule: "lookbehind",
+        tokens: []
+    };
+}
+
+#[test]
+fn keyword_ident() {
+    parses_to! {
+        parser: vm(),
+        input: "iffy",
+        rule: "keyword_ident",
+        tokens: [
+            keyword_ident(0, 4)
+        ]
+    };
+}
+
+#[test]
+#[should_panic]
+fn keyword_ident_keyword() {
+    parses_to! {
+        parser: vm(),
+        input: "else",
+        rule: "keyword_ident",
+        tokens: []
+    };
+}
+
+#[test]
+fn keyword() {
+    parses_to! {
+        parser: vm(),
+        input: "if",
+        rule: "keyword",
+        tokens: [
+            kw_if(0, 2)
+        ]
+    };
+}
+
+#[test]
+fn quoted() {
+    parses_to! {
+        parser: vm(),
+        input: "\"ab\"",
+        rule: "quoted",
+        tokens: [
+            quoted(0, 4)
+        ]
+    };
+}
+
+#[test]
+fn quoted_list() {
+    parses_to! {
+        parser: vm(),
+        input: "'a', \"b\"",
+        rule: "quoted_list",
+        tokens: [
+            quoted_list(0, 8, [
+                quoted(0, 3),
+                quoted(5, 8)
+            ])
+        ]
+    };
+}
+
+#[test]
+fn labeled() {
+    parses_to! {
+        parser: vm(),
+        input: "(abc, abc)",
+        rule: "labeled",
+        tokens: [
+            labeled(0, 10, [
+                string(1, 4),
+                string(6, 9)
+            ])
+        ]
+    };
+}
+
+#[test]
+fn labeled_error() {
+    let vm = vm();
+    let error = vm.parse("labeled", "(abc, ab)").unwrap_err();
+    let labels = error.labels();
+
+    assert_eq!(labels.len(), 1);
+    assert_eq!(labels[0].location(), (1, 6));
+    assert_eq!(labels[0].message(), "while parsing argument list");
+}
+
 #[test]
 fn sequence() {
     parses_to! {
@@ -802,6 +945,27 @@ fn checkpoint_restore() {
     };
 }
 
+#[test]
+fn indented() {
+    parses_to! {
+        parser: vm(),
+        input: "a\n b\n  c\n d\ne\n",
+        rule: "indented",
+        tokens: [
+            indented(0, 14, [
+                indented_line(0, 12, [
+                    indented_block(2, 12, [
+                        indented_block(5, 9),
+                        indented_line(9, 12)
+                    ])
+                ]),
+                indented_line(12, 14),
+                EOI(14, 14)
+            ])
+        ]
+    };
+}
+
 #[test]
 fn ascii_digits() {
     parses_to! {
@@ -957,3 +1121,25 @@ fn shadowing() {
         ]
     }
 }
+
+#[test]
+fn error_eq() {
+    use pest::error::{Error, ErrorVariant, Expected};
+    use pest::Position;
+
+    let vm = vm();
+    let input = "abd";
+    let error = vm.parse("string", input).unwrap_err();
+
+    assert_eq!(error.expected()[0], Expected::Literal("abc".to_owned()));
+    assert_eq!(
+        error,
+        Error::new_from_pos(
+            ErrorVariant::ParsingError {
+                positives: vec!["string"],
+                negatives: vec![],
+            },
+            Position::new(input, 0).unwrap()
+        )
+    );
+}
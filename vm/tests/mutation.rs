@@ -0,0 +1,56 @@
+// pest. The Elegant Parser
+// Copyright (c) 2018 Dragoș Tiselice
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+use pest_meta::mutation::MutationKind;
+use pest_vm::mutation::{run_grammar, TestCase};
+
+const GRAMMAR: &str = r#"
+    value = { bool | ident }
+    bool = { "true" | "t" }
+    ident = { ('a'..'z')+ }
+"#;
+
+fn survivors(cases: &[TestCase]) -> Vec<(String, MutationKind)> {
+    run_grammar(GRAMMAR, cases)
+        .unwrap()
+        .survivors
+        .into_iter()
+        .map(|mutant| (mutant.rule, mutant.kind))
+        .collect()
+}
+
+#[test]
+fn superficial_tests() {
+    // Only `t` is tested, which both alternatives of `bool` parse the same way.
+    let cases = [TestCase::new("value", "t")];
+
+    assert_eq!(
+        survivors(&cases),
+        [
+            ("bool".to_owned(), MutationKind::SwapChoice),
+            (
+                "ident".to_owned(),
+                MutationKind::WidenRange {
+                    start: '`',
+                    end: '{'
+                }
+            ),
+        ]
+    );
+}
+
+#[test]
+fn thorough_tests() {
+    let cases = [TestCase::new("value", "true"), TestCase::new("value", "{")];
+
+    let report = run_grammar(GRAMMAR, &cases).unwrap();
+
+    assert_eq!(report.mutants, 3);
+    assert!(report.survivors.is_empty());
+}
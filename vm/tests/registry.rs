@@ -0,0 +1,81 @@
+// pest. The Elegant Parser
+// Copyright (c) 2018 Dragoș Tiselice
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+extern crate pest;
+extern crate pest_meta;
+extern crate pest_vm;
+
+use pest::registry::{Language, Registry};
+use pest::DynParser;
+use pest_meta::parser::Rule;
+use pest_meta::{optimizer, parser};
+use pest_vm::Vm;
+
+fn vm(grammar: &str) -> Vm {
+    let pairs = parser::parse(Rule::grammar_rules, grammar).unwrap();
+    let ast = parser::consume_rules(pairs).unwrap();
+    Vm::new(optimizer::optimize(ast))
+}
+
+fn registry() -> Registry {
+    let mut registry = Registry::new();
+    registry
+        .register(
+            Language::new("lists", vm(include_str!("lists.pest")), "lists")
+                .extension("lst")
+                .mime_type("text/x-lists"),
+        )
+        .register(
+            Language::new("csv", vm("row = { (ASCII_DIGIT+ ~ \",\"?)* }"), "row").extension("csv"),
+        );
+    registry
+}
+
+#[test]
+fn rule_names() {
+    let vm = vm("b = { a } a = { \"a\" }");
+
+    assert_eq!(vm.rule_names(), ["a", "b"]);
+}
+
+#[test]
+fn parse_dyn_unknown_rule() {
+    let vm = vm("a = { \"a\" }");
+
+    assert_eq!(
+        vm.parse_dyn("b", "a").unwrap_err().variant.message(),
+        "unknown rule `b`"
+    );
+}
+
+#[test]
+fn parse_path() {
+    let registry = registry();
+
+    let pairs = registry.parse_path("numbers.csv", "1,22,333").unwrap();
+    assert_eq!(pairs.clone().next().unwrap().as_rule(), "row");
+    assert_eq!(pairs.as_str(), "1,22,333");
+
+    let pairs = registry.parse_path("todo.LST", "- a\n- b").unwrap();
+    assert_eq!(
+        pairs
+            .flatten()
+            .filter(|pair| pair.as_rule() == "item")
+            .count(),
+        2
+    );
+}
+
+#[test]
+fn parse_path_error() {
+    let registry = registry();
+    let error = registry.parse_path("todo.lst", "- a\n  -").unwrap_err();
+
+    assert!(format!("{}", error).starts_with(" --> todo.lst:"));
+}